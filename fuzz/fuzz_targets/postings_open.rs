@@ -0,0 +1,74 @@
+//! Overwrites the postings, the positions or the term dictionary of a small
+//! segment with the fuzzer input, and checks that reading the postings
+//! and the positions of all of its terms returns an error rather than panicking.
+//!
+//! The first byte of the input selects the segment file.
+//!
+//! Run it with `cargo fuzz run postings_open`.
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+#[macro_use]
+extern crate tantivy;
+
+use tantivy::schema::{Field, SchemaBuilder, INT_INDEXED, TEXT};
+use tantivy::termdict::{TermDictionary, TermStreamer};
+use tantivy::{DocSet, Index, Postings, Result, SegmentComponent, SegmentReader};
+
+const COMPONENTS: [SegmentComponent; 3] = [
+    SegmentComponent::POSTINGS,
+    SegmentComponent::POSITIONS,
+    SegmentComponent::TERMS,
+];
+
+fn build_index() -> Index {
+    let mut schema_builder = SchemaBuilder::default();
+    let text_field = schema_builder.add_text_field("text", TEXT);
+    let int_field = schema_builder.add_u64_field("int", INT_INDEXED);
+    let index = Index::create_in_ram(schema_builder.build());
+    {
+        let mut index_writer = index.writer_with_num_threads(1, 3_000_000).unwrap();
+        for i in 0..200u64 {
+            index_writer.add_document(doc!(
+                text_field => format!("a b{} c{} a", i % 3, i % 150),
+                int_field => i % 7
+            ));
+        }
+        index_writer.commit().unwrap();
+    }
+    index
+}
+
+fn read_all_postings(segment_reader: &SegmentReader) -> Result<()> {
+    let schema = segment_reader.schema().clone();
+    for (field_id, field_entry) in schema.fields().iter().enumerate() {
+        let record_option = match field_entry.field_type().get_index_record_option() {
+            Some(record_option) => record_option,
+            None => continue,
+        };
+        let inverted_index = segment_reader.inverted_index(Field(field_id as u32))?;
+        let mut term_stream = inverted_index.terms().stream();
+        while term_stream.advance() {
+            let mut postings =
+                inverted_index.read_postings_from_terminfo(term_stream.value(), record_option)?;
+            while postings.advance() {
+                postings.positions();
+            }
+        }
+    }
+    Ok(())
+}
+
+fuzz_target!(|data: &[u8]| {
+    if data.is_empty() {
+        return;
+    }
+    let mut index = build_index();
+    let segment = index.searchable_segments().unwrap().pop().unwrap();
+    let component = COMPONENTS[data[0] as usize % COMPONENTS.len()];
+    let path = segment.relative_path(component);
+    index.directory_mut().atomic_write(&path, &data[1..]).unwrap();
+    if let Ok(segment_reader) = SegmentReader::open(&segment) {
+        let _ = read_all_postings(&segment_reader);
+    }
+});
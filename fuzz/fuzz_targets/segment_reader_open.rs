@@ -0,0 +1,54 @@
+//! Overwrites one of the files of a small segment with the fuzzer input,
+//! and checks that opening the segment returns an error rather than panicking.
+//!
+//! The first byte of the input selects the segment file.
+//!
+//! Run it with `cargo fuzz run segment_reader_open`.
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+#[macro_use]
+extern crate tantivy;
+
+use tantivy::schema::{Facet, SchemaBuilder, FAST, INT_INDEXED, STORED, TEXT};
+use tantivy::{Directory, Index, SegmentComponent, SegmentReader, Term};
+
+const COMPONENTS: [SegmentComponent; 7] = [
+    SegmentComponent::POSTINGS,
+    SegmentComponent::POSITIONS,
+    SegmentComponent::FASTFIELDS,
+    SegmentComponent::FIELDNORMS,
+    SegmentComponent::TERMS,
+    SegmentComponent::STORE,
+    SegmentComponent::DELETE,
+];
+
+fn build_index() -> Index {
+    let mut schema_builder = SchemaBuilder::default();
+    let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+    let int_field = schema_builder.add_u64_field("int", INT_INDEXED | FAST);
+    let facet_field = schema_builder.add_facet_field("facet");
+    let index = Index::create_in_ram(schema_builder.build());
+    {
+        let mut index_writer = index.writer_with_num_threads(1, 3_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a b c", int_field => 1u64));
+        index_writer.add_document(doc!(text_field => "b c d", int_field => 2u64));
+        index_writer.add_document(doc!(text_field => "c d e", facet_field => Facet::from("/a/b")));
+        index_writer.commit().unwrap();
+        index_writer.delete_term(Term::from_field_u64(int_field, 2u64));
+        index_writer.commit().unwrap();
+    }
+    index
+}
+
+fuzz_target!(|data: &[u8]| {
+    if data.is_empty() {
+        return;
+    }
+    let mut index = build_index();
+    let segment = index.searchable_segments().unwrap().pop().unwrap();
+    let component = COMPONENTS[data[0] as usize % COMPONENTS.len()];
+    let path = segment.relative_path(component);
+    index.directory_mut().atomic_write(&path, &data[1..]).unwrap();
+    let _ = SegmentReader::open(&segment);
+});
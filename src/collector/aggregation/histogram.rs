@@ -0,0 +1,260 @@
+use super::{Aggregation, AggregationResult};
+use collector::{Collector, MergeableCollector};
+use core::MemoryBudget;
+use fastfield::{FastFieldReader, FastValue};
+use schema::Field;
+use std::cmp;
+use std::collections::BTreeMap;
+use std::i64;
+use std::mem;
+use DocId;
+use ErrorKind;
+use Result;
+use Score;
+use SegmentLocalId;
+use SegmentReader;
+
+/// Returns the ordinal of the bucket of `val`,
+/// or `None` if it does not fit in an `i64`.
+pub(crate) fn checked_bucket_ord(val: f64, interval: f64) -> Option<i64> {
+    let bucket_ord = (val / interval).floor();
+    if bucket_ord >= i64::MIN as f64 && bucket_ord < i64::MAX as f64 {
+        Some(bucket_ord as i64)
+    } else {
+        None
+    }
+}
+
+/// Checks that the bucket ordinals of the values in `[min_value, max_value]`
+/// fit in an `i64`, and returns the number of buckets in between.
+pub(crate) fn num_buckets(interval: f64, min_value: f64, max_value: f64) -> Result<u64> {
+    match (
+        checked_bucket_ord(min_value, interval),
+        checked_bucket_ord(max_value, interval),
+    ) {
+        (Some(min_bucket_ord), Some(max_bucket_ord)) => {
+            Ok(max_bucket_ord.wrapping_sub(min_bucket_ord) as u64 + 1)
+        }
+        _ => bail!(ErrorKind::InvalidArgument(format!(
+            "The interval {} is too small for the values in [{}, {}].",
+            interval, min_value, max_value
+        ))),
+    }
+}
+
+/// Document counts of the buckets of a histogram.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    interval: f64,
+    // bucket ordinal -> count
+    counts: BTreeMap<i64, u64>,
+}
+
+impl Histogram {
+    fn with_interval(interval: f64) -> Histogram {
+        Histogram {
+            interval,
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the interval of the buckets.
+    pub fn interval(&self) -> f64 {
+        self.interval
+    }
+
+    /// Returns the buckets that contain at least one value,
+    /// as `(key, count)` pairs sorted by key.
+    ///
+    /// The key of a bucket is its lower bound. A bucket with the key `k`
+    /// counts the values in `[k, k + interval)`.
+    pub fn buckets(&self) -> Vec<(f64, u64)> {
+        self.counts
+            .iter()
+            .map(|(&bucket_ord, &count)| (bucket_ord as f64 * self.interval, count))
+            .collect()
+    }
+
+    fn bucket_ord(&self, val: f64) -> i64 {
+        (val / self.interval).floor() as i64
+    }
+
+    fn add(&mut self, val: f64) {
+        let bucket_ord = self.bucket_ord(val);
+        *self.counts.entry(bucket_ord).or_insert(0) += 1;
+    }
+
+    /// Merges the counts of another histogram with the same interval.
+    pub fn merge(&mut self, other: &Histogram) {
+        for (&bucket_ord, &count) in &other.counts {
+            *self.counts.entry(bucket_ord).or_insert(0) += count;
+        }
+    }
+}
+
+/// `HistogramCollector` counts the matching documents per bucket
+/// of a fixed interval over the values of a `u64` or `i64` fast field.
+///
+/// The values are bucketed as `f64`. The bucket of a value `val` is
+/// `floor(val / interval) * interval`, and only non-empty buckets are returned.
+///
+/// At most one bucket per document, or per interval between the min and the
+/// max value of a segment, is reserved against the memory budget of the search.
+///
+/// The search fails with an `InvalidArgument` error if the interval is so
+/// small that the bucket ordinals of the values do not fit in an `i64`.
+pub struct HistogramCollector<T: FastValue> {
+    field: Field,
+    histogram: Histogram,
+    ff_reader: Option<FastFieldReader<T>>,
+    memory_budget: MemoryBudget,
+}
+
+impl<T: FastValue> HistogramCollector<T> {
+    /// Creates a `HistogramCollector` over the fast field `field`,
+    /// with buckets of `interval`.
+    ///
+    /// # Panics
+    /// The method panics if `interval` is not strictly positive.
+    pub fn for_field(field: Field, interval: f64) -> HistogramCollector<T> {
+        if !(interval > 0f64) {
+            panic!("Interval must be strictly greater than 0.");
+        }
+        HistogramCollector {
+            field,
+            histogram: Histogram::with_interval(interval),
+            ff_reader: None,
+            memory_budget: MemoryBudget::unlimited(),
+        }
+    }
+
+    /// Returns the histogram of the documents collected.
+    pub fn histogram(&self) -> &Histogram {
+        &self.histogram
+    }
+}
+
+impl<T: FastValue> Collector for HistogramCollector<T> {
+    fn set_segment(&mut self, _: SegmentLocalId, reader: &SegmentReader) -> Result<()> {
+        let ff_reader: FastFieldReader<T> = reader.fast_field_reader(self.field)?;
+        let num_buckets = cmp::min(
+            reader.max_doc() as u64,
+            num_buckets(
+                self.histogram.interval,
+                ff_reader.min_value().to_f64(),
+                ff_reader.max_value().to_f64(),
+            )?,
+        );
+        self.memory_budget
+            .allocate(num_buckets as usize * mem::size_of::<(i64, u64)>())?;
+        self.ff_reader = Some(ff_reader);
+        Ok(())
+    }
+
+    fn collect(&mut self, doc: DocId, _: Score) {
+        let val = self.ff_reader
+            .as_ref()
+            .expect("collect() was called before set_segment. This should never happen.")
+            .get(doc);
+        self.histogram.add(val.to_f64());
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn set_memory_budget(&mut self, memory_budget: &MemoryBudget) -> Result<()> {
+        self.memory_budget = memory_budget.clone();
+        Ok(())
+    }
+}
+
+impl<T: FastValue + Send> MergeableCollector for HistogramCollector<T> {
+    fn split(&self) -> HistogramCollector<T> {
+        HistogramCollector::for_field(self.field, self.histogram.interval)
+    }
+
+    fn merge(&mut self, other: HistogramCollector<T>) {
+        self.histogram.merge(&other.histogram);
+    }
+}
+
+impl<T: FastValue + Send + 'static> Aggregation for HistogramCollector<T> {
+    fn split_aggregation(&self) -> Box<Aggregation> {
+        box self.split()
+    }
+
+    fn merge_result(&mut self, result: AggregationResult) {
+        if let AggregationResult::Histogram(ref histogram) = result {
+            self.histogram.merge(histogram);
+        }
+    }
+
+    fn result(&self) -> AggregationResult {
+        AggregationResult::Histogram(self.histogram.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::HistogramCollector;
+    use query::AllQuery;
+    use ErrorKind;
+    use schema::{SchemaBuilder, FAST};
+    use Index;
+
+    #[test]
+    fn test_histogram_collector() {
+        let mut schema_builder = SchemaBuilder::default();
+        let num_field = schema_builder.add_i64_field("num", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for &val in &[3i64, -2i64, 10i64, 5i64, 0i64, 14i64] {
+                index_writer.add_document(doc!(num_field => val));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut histogram_collector = HistogramCollector::<i64>::for_field(num_field, 5f64);
+        searcher
+            .search(&AllQuery, &mut histogram_collector)
+            .unwrap();
+        assert_eq!(
+            histogram_collector.histogram().buckets(),
+            vec![(-5f64, 1), (0f64, 2), (5f64, 1), (10f64, 2)]
+        );
+    }
+
+    #[test]
+    fn test_histogram_collector_tiny_interval() {
+        let mut schema_builder = SchemaBuilder::default();
+        let num_field = schema_builder.add_i64_field("num", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(num_field => -1_000i64));
+            index_writer.add_document(doc!(num_field => 1_000i64));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut histogram_collector = HistogramCollector::<i64>::for_field(num_field, 1e-300f64);
+        match *searcher
+            .search(&AllQuery, &mut histogram_collector)
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::InvalidArgument(_) => {}
+            _ => panic!("Expected an InvalidArgument error"),
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_histogram_collector_null_interval() {
+        HistogramCollector::<u64>::for_field(::schema::Field(0), 0f64);
+    }
+}
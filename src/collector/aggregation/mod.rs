@@ -0,0 +1,257 @@
+/*!
+Aggregations compute statistics over the fast fields
+of the documents matching a query.
+
+The `SignificantTermsCollector` also compares the facets of the
+matching documents with those of the whole index, to find
+the facets that are over-represented amongst the matches.
+
+Each aggregation is a collector of its own, so that a single aggregation
+can be passed directly to `Searcher::search`. Several aggregations
+can be computed in a single search pass by registering them, under a name,
+in an `AggregationCollector`.
+
+The `AggregationTreeCollector` goes further, and evaluates a tree of
+aggregations, where the buckets of the `histogram` and `terms` aggregations
+have sub-aggregations of their own, e.g. `terms` → `histogram` → `stats`.
+
+```rust
+#[macro_use]
+extern crate tantivy;
+use tantivy::schema::{SchemaBuilder, FAST, TEXT};
+use tantivy::{Index, Result};
+use tantivy::collector::aggregation::{AggregationCollector, AggregationResult,
+                                      HistogramCollector, StatsCollector};
+use tantivy::query::QueryParser;
+
+# fn main() { example().unwrap(); }
+fn example() -> Result<()> {
+    let mut schema_builder = SchemaBuilder::new();
+    let title = schema_builder.add_text_field("title", TEXT);
+    let year = schema_builder.add_u64_field("year", FAST);
+    let index = Index::create_in_ram(schema_builder.build());
+    {
+        let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+        index_writer.add_document(doc!(title => "The Diary of Muadib", year => 1965u64));
+        index_writer.add_document(doc!(title => "A Dairy Cow", year => 1999u64));
+        index_writer.add_document(doc!(title => "The Diary of Anne Frank", year => 1947u64));
+        index_writer.commit()?;
+    }
+    index.load_searchers()?;
+    let searcher = index.searcher();
+
+    let query_parser = QueryParser::for_index(&index, vec![title]);
+    let query = query_parser.parse_query("diary")?;
+    let mut aggregations = AggregationCollector::default();
+    aggregations.add_aggregation("year_stats", StatsCollector::<u64>::for_field(year));
+    aggregations.add_aggregation("decades", HistogramCollector::<u64>::for_field(year, 10f64));
+    searcher.search(&*query, &mut aggregations)?;
+
+    let results = aggregations.harvest();
+    if let AggregationResult::Stats(ref stats) = results["year_stats"] {
+        assert_eq!(stats.avg(), Some(1956f64));
+    }
+    if let AggregationResult::Histogram(ref histogram) = results["decades"] {
+        assert_eq!(histogram.buckets(), vec![(1940f64, 1), (1960f64, 1)]);
+    }
+    Ok(())
+}
+```
+*/
+
+use collector::{Collector, MergeableCollector};
+use core::MemoryBudget;
+use std::collections::BTreeMap;
+use DocId;
+use Result;
+use Score;
+use SegmentLocalId;
+use SegmentReader;
+
+mod stats;
+pub use self::stats::{Stats, StatsCollector};
+
+mod histogram;
+pub use self::histogram::{Histogram, HistogramCollector};
+
+mod significant_terms;
+pub use self::significant_terms::{SignificanceHeuristic, SignificantTerms,
+                                  SignificantTermsCollector};
+
+mod tree;
+pub use self::tree::{AggregationRequest, AggregationTreeCollector, AggregationTreeResult, Bucket,
+                     IntermediateAggregationResults};
+
+/// Result of an aggregation.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AggregationResult {
+    /// Result of a `StatsCollector`.
+    Stats(Stats),
+    /// Result of a `HistogramCollector`.
+    Histogram(Histogram),
+    /// Result of a `SignificantTermsCollector`.
+    SignificantTerms(SignificantTerms),
+    /// Intermediate results of an `AggregationTreeCollector`,
+    /// whose `harvest` returns the results of its aggregations.
+    Tree(IntermediateAggregationResults),
+}
+
+/// An aggregation is a collector whose result can be
+/// extracted as an `AggregationResult`.
+///
+/// Like `MergeableCollector`s, aggregations can be split and
+/// merged, which makes it possible to run an `AggregationCollector`
+/// via `Searcher::search_parallel`.
+pub trait Aggregation: Collector + Send {
+    /// Returns a new, empty aggregation, with the same configuration.
+    fn split_aggregation(&self) -> Box<Aggregation>;
+
+    /// Merges the result of an aggregation obtained
+    /// via `.split_aggregation()`.
+    fn merge_result(&mut self, result: AggregationResult);
+
+    /// Returns the result of the aggregation.
+    fn result(&self) -> AggregationResult;
+}
+
+/// Computes several named aggregations in a single search pass.
+#[derive(Default)]
+pub struct AggregationCollector {
+    aggregations: Vec<(String, Box<Aggregation>)>,
+}
+
+impl AggregationCollector {
+    /// Registers an aggregation under the name `name`.
+    ///
+    /// # Panics
+    /// The method panics if an aggregation was already registered
+    /// under the same name.
+    pub fn add_aggregation<A: Aggregation + 'static>(&mut self, name: &str, aggregation: A) {
+        assert!(
+            self.aggregations
+                .iter()
+                .all(|&(ref other_name, _)| other_name != name),
+            "An aggregation named {:?} was already added.",
+            name
+        );
+        self.aggregations.push((name.to_string(), box aggregation));
+    }
+
+    /// Returns the results of the aggregations, by name.
+    pub fn harvest(&self) -> BTreeMap<String, AggregationResult> {
+        self.aggregations
+            .iter()
+            .map(|&(ref name, ref aggregation)| (name.clone(), aggregation.result()))
+            .collect()
+    }
+}
+
+impl Collector for AggregationCollector {
+    fn set_segment(
+        &mut self,
+        segment_local_id: SegmentLocalId,
+        segment: &SegmentReader,
+    ) -> Result<()> {
+        for &mut (_, ref mut aggregation) in &mut self.aggregations {
+            aggregation.set_segment(segment_local_id, segment)?;
+        }
+        Ok(())
+    }
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        for &mut (_, ref mut aggregation) in &mut self.aggregations {
+            aggregation.collect(doc, score);
+        }
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.aggregations
+            .iter()
+            .any(|&(_, ref aggregation)| aggregation.requires_scoring())
+    }
+
+    fn set_memory_budget(&mut self, memory_budget: &MemoryBudget) -> Result<()> {
+        for &mut (_, ref mut aggregation) in &mut self.aggregations {
+            aggregation.set_memory_budget(memory_budget)?;
+        }
+        Ok(())
+    }
+}
+
+impl MergeableCollector for AggregationCollector {
+    fn split(&self) -> AggregationCollector {
+        AggregationCollector {
+            aggregations: self.aggregations
+                .iter()
+                .map(|&(ref name, ref aggregation)| (name.clone(), aggregation.split_aggregation()))
+                .collect(),
+        }
+    }
+
+    fn merge(&mut self, other: AggregationCollector) {
+        for (&mut (_, ref mut aggregation), (_, other_aggregation)) in
+            self.aggregations.iter_mut().zip(other.aggregations)
+        {
+            aggregation.merge_result(other_aggregation.result());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{AggregationCollector, AggregationResult, HistogramCollector, StatsCollector};
+    use query::AllQuery;
+    use schema::{SchemaBuilder, FAST};
+    use Index;
+
+    #[test]
+    fn test_aggregation_collector_parallel() {
+        let mut schema_builder = SchemaBuilder::default();
+        let num_field = schema_builder.add_u64_field("num", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0u64..30_000u64 {
+                index_writer.add_document(doc!(num_field => i % 100));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut aggregation_collector = AggregationCollector::default();
+        aggregation_collector.add_aggregation("stats", StatsCollector::<u64>::for_field(num_field));
+        aggregation_collector.add_aggregation(
+            "histogram",
+            HistogramCollector::<u64>::for_field(num_field, 50f64),
+        );
+        searcher
+            .search_parallel(&AllQuery, &mut aggregation_collector, 3)
+            .unwrap();
+        let results = aggregation_collector.harvest();
+        match results["stats"] {
+            AggregationResult::Stats(ref stats) => {
+                assert_eq!(stats.count(), 30_000);
+                assert_eq!(stats.min(), Some(0f64));
+                assert_eq!(stats.max(), Some(99f64));
+                assert_eq!(stats.avg(), Some(49.5f64));
+            }
+            _ => panic!("Expected stats"),
+        }
+        match results["histogram"] {
+            AggregationResult::Histogram(ref histogram) => {
+                assert_eq!(histogram.buckets(), vec![(0f64, 15_000), (50f64, 15_000)]);
+            }
+            _ => panic!("Expected a histogram"),
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_aggregation_collector_duplicate_name() {
+        let field = ::schema::Field(0);
+        let mut aggregation_collector = AggregationCollector::default();
+        aggregation_collector.add_aggregation("a", StatsCollector::<u64>::for_field(field));
+        aggregation_collector.add_aggregation("a", StatsCollector::<u64>::for_field(field));
+    }
+}
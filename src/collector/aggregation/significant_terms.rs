@@ -0,0 +1,445 @@
+use super::{Aggregation, AggregationResult};
+use collector::{Collector, MergeableCollector};
+use core::MemoryBudget;
+use fastfield::FacetReader;
+use schema::{Facet, Field};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::mem;
+use DocId;
+use Result;
+use Score;
+use SegmentLocalId;
+use SegmentReader;
+
+/// Heuristic used to score how significant a facet is for
+/// the set of documents matching a query.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignificanceHeuristic {
+    /// The JLH score: `(fg% - bg%) * (fg% / bg%)`, where `fg%` is the
+    /// ratio of matching documents that have the facet, and `bg%`
+    /// the ratio of the documents of the background set that have it.
+    Jlh,
+    /// Pearson's chi-square statistic of the contingency table
+    /// "has the facet" / "matches the query".
+    ChiSquare,
+}
+
+impl SignificanceHeuristic {
+    /// Returns the score of a facet. Facets that are not more
+    /// frequent amongst the matching documents than in the background
+    /// set get a score of `0`.
+    ///
+    /// The background set is expected to contain the matching documents.
+    pub fn score(
+        &self,
+        foreground_count: u64,
+        foreground_size: u64,
+        background_count: u64,
+        background_size: u64,
+    ) -> f64 {
+        if foreground_size == 0 || background_count == 0 {
+            return 0f64;
+        }
+        let foreground_ratio = foreground_count as f64 / foreground_size as f64;
+        let background_ratio = background_count as f64 / background_size as f64;
+        match *self {
+            SignificanceHeuristic::Jlh => {
+                if foreground_ratio <= background_ratio {
+                    return 0f64;
+                }
+                (foreground_ratio - background_ratio) * (foreground_ratio / background_ratio)
+            }
+            SignificanceHeuristic::ChiSquare => {
+                // n_{facet}{match}
+                let n11 = foreground_count as f64;
+                let n01 = (foreground_size - foreground_count) as f64;
+                let n10 = background_count.saturating_sub(foreground_count) as f64;
+                let n00 = background_size
+                    .saturating_sub(background_count)
+                    .saturating_sub(foreground_size - foreground_count)
+                    as f64;
+                if n10 + n00 > 0f64 && foreground_ratio <= n10 / (n10 + n00) {
+                    return 0f64;
+                }
+                let denominator = (n11 + n01) * (n11 + n10) * (n10 + n00) * (n01 + n00);
+                if denominator == 0f64 {
+                    return 0f64;
+                }
+                let delta = n11 * n00 - n10 * n01;
+                (n11 + n01 + n10 + n00) * delta * delta / denominator
+            }
+        }
+    }
+}
+
+/// Foreground and background document counts of the facets
+/// of a `SignificantTermsCollector`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SignificantTerms {
+    // facet -> (foreground count, background count)
+    counts: BTreeMap<Facet, (u64, u64)>,
+    foreground_size: u64,
+    background_size: u64,
+}
+
+impl SignificantTerms {
+    /// Returns the number of matching documents.
+    pub fn foreground_size(&self) -> u64 {
+        self.foreground_size
+    }
+
+    /// Returns the number of documents of the background set.
+    pub fn background_size(&self) -> u64 {
+        self.background_size
+    }
+
+    /// Returns the number of matching documents and the number
+    /// of documents of the background set that have the facet `facet`.
+    pub fn counts<T>(&self, facet: T) -> (u64, u64)
+    where
+        Facet: From<T>,
+    {
+        self.counts
+            .get(&Facet::from(facet))
+            .cloned()
+            .unwrap_or((0, 0))
+    }
+
+    /// Returns the `k` facets with the highest (non-zero) score,
+    /// sorted by decreasing score.
+    pub fn top_k(&self, k: usize, heuristic: SignificanceHeuristic) -> Vec<(&Facet, f64)> {
+        let mut scored_facets: Vec<(&Facet, f64)> = self.counts
+            .iter()
+            .map(|(facet, &(foreground_count, background_count))| {
+                let score = heuristic.score(
+                    foreground_count,
+                    self.foreground_size,
+                    background_count,
+                    self.background_size,
+                );
+                (facet, score)
+            })
+            .filter(|&(_, score)| score > 0f64)
+            .collect();
+        scored_facets.sort_by(|left, right| {
+            right
+                .1
+                .partial_cmp(&left.1)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| left.0.cmp(right.0))
+        });
+        scored_facets.truncate(k);
+        scored_facets
+    }
+
+    /// Merges the counts of another `SignificantTerms`.
+    pub fn merge(&mut self, other: &SignificantTerms) {
+        for (facet, &(foreground_count, background_count)) in &other.counts {
+            let counts = self.counts.entry(facet.clone()).or_insert((0, 0));
+            counts.0 += foreground_count;
+            counts.1 += background_count;
+        }
+        self.foreground_size += other.foreground_size;
+        self.background_size += other.background_size;
+    }
+}
+
+struct SegmentCounts {
+    facet_reader: FacetReader,
+    // facet_ord -> foreground count
+    foreground_counts: Vec<u64>,
+}
+
+/// `SignificantTermsCollector` finds the facets that are over-represented
+/// amongst the documents matching a query, compared to all of the
+/// documents of the index.
+///
+/// For each segment visited, the background set consists of all of
+/// the non-deleted documents of the segment. Computing the background
+/// counts therefore requires to read the facets of all of these documents.
+/// Only the facets directly attached to the documents are counted,
+/// not their ancestors.
+///
+/// One foreground and one background counter per facet of a segment
+/// are reserved against the memory budget of the search.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::collector::aggregation::{SignificanceHeuristic, SignificantTermsCollector};
+/// use tantivy::query::QueryParser;
+/// use tantivy::schema::{Facet, SchemaBuilder, TEXT};
+/// use tantivy::{Index, Result};
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let title = schema_builder.add_text_field("title", TEXT);
+///     let topic = schema_builder.add_facet_field("topic");
+///     let index = Index::create_in_ram(schema_builder.build());
+///     {
+///         let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+///         for _ in 0..10 {
+///             index_writer.add_document(doc!(
+///                 title => "The Diary of a Young Girl",
+///                 topic => Facet::from("/history")
+///             ));
+///             index_writer.add_document(doc!(
+///                 title => "A Dairy Cow",
+///                 topic => Facet::from("/farming")
+///             ));
+///         }
+///         index_writer.add_document(doc!(
+///             title => "The Diary of a Farmer",
+///             topic => Facet::from("/farming")
+///         ));
+///         index_writer.commit()?;
+///     }
+///     index.load_searchers()?;
+///     let searcher = index.searcher();
+///
+///     let query_parser = QueryParser::for_index(&index, vec![title]);
+///     let query = query_parser.parse_query("diary")?;
+///     let mut significant_terms_collector = SignificantTermsCollector::for_field(topic);
+///     searcher.search(&*query, &mut significant_terms_collector)?;
+///
+///     let significant_terms = significant_terms_collector.significant_terms();
+///     assert_eq!(significant_terms.foreground_size(), 11);
+///     assert_eq!(significant_terms.background_size(), 21);
+///     let top_facets = significant_terms.top_k(10, SignificanceHeuristic::Jlh);
+///     assert_eq!(top_facets.len(), 1);
+///     assert_eq!(top_facets[0].0, &Facet::from("/history"));
+///     Ok(())
+/// }
+/// ```
+pub struct SignificantTermsCollector {
+    field: Field,
+    significant_terms: SignificantTerms,
+    segment_counts: Option<SegmentCounts>,
+    facet_ords: Vec<u64>,
+    memory_budget: MemoryBudget,
+}
+
+impl SignificantTermsCollector {
+    /// Creates a `SignificantTermsCollector` over the facet field `field`.
+    pub fn for_field(field: Field) -> SignificantTermsCollector {
+        SignificantTermsCollector {
+            field,
+            significant_terms: SignificantTerms::default(),
+            segment_counts: None,
+            facet_ords: Vec::new(),
+            memory_budget: MemoryBudget::unlimited(),
+        }
+    }
+
+    /// Returns the foreground and background counts
+    /// of the facets of the documents collected.
+    pub fn significant_terms(&self) -> SignificantTerms {
+        let mut significant_terms = self.significant_terms.clone();
+        if let Some(ref segment_counts) = self.segment_counts {
+            add_foreground_counts(&mut significant_terms, segment_counts);
+        }
+        significant_terms
+    }
+
+    fn finalize_segment(&mut self) {
+        if let Some(segment_counts) = self.segment_counts.take() {
+            add_foreground_counts(&mut self.significant_terms, &segment_counts);
+        }
+    }
+}
+
+fn add_foreground_counts(significant_terms: &mut SignificantTerms, segment_counts: &SegmentCounts) {
+    let mut facet = Facet::root();
+    for (facet_ord, &count) in segment_counts.foreground_counts.iter().enumerate() {
+        if count > 0 {
+            segment_counts
+                .facet_reader
+                .facet_from_ord(facet_ord as u64, &mut facet);
+            let counts = significant_terms
+                .counts
+                .entry(facet.clone())
+                .or_insert((0, 0));
+            counts.0 += count;
+        }
+    }
+}
+
+fn read_facet_ords(facet_reader: &mut FacetReader, doc: DocId, facet_ords: &mut Vec<u64>) {
+    facet_reader.facet_ords(doc, facet_ords);
+    facet_ords.sort();
+    facet_ords.dedup();
+}
+
+impl Collector for SignificantTermsCollector {
+    fn set_segment(&mut self, _: SegmentLocalId, reader: &SegmentReader) -> Result<()> {
+        self.finalize_segment();
+        let mut facet_reader = reader.facet_reader(self.field)?;
+        let num_facets = facet_reader.num_facets();
+        self.memory_budget
+            .allocate(num_facets * 2 * mem::size_of::<u64>())?;
+        let mut background_counts = vec![0u64; num_facets];
+        for doc in 0..reader.max_doc() {
+            if reader.is_deleted(doc) {
+                continue;
+            }
+            read_facet_ords(&mut facet_reader, doc, &mut self.facet_ords);
+            for &facet_ord in &self.facet_ords {
+                background_counts[facet_ord as usize] += 1;
+            }
+        }
+        let mut facet = Facet::root();
+        for (facet_ord, &count) in background_counts.iter().enumerate() {
+            if count > 0 {
+                facet_reader.facet_from_ord(facet_ord as u64, &mut facet);
+                let counts = self.significant_terms
+                    .counts
+                    .entry(facet.clone())
+                    .or_insert((0, 0));
+                counts.1 += count;
+            }
+        }
+        self.significant_terms.background_size += u64::from(reader.num_docs());
+        self.segment_counts = Some(SegmentCounts {
+            facet_reader,
+            foreground_counts: vec![0u64; num_facets],
+        });
+        Ok(())
+    }
+
+    fn collect(&mut self, doc: DocId, _: Score) {
+        let segment_counts = self.segment_counts
+            .as_mut()
+            .expect("collect() was called before set_segment. This should never happen.");
+        read_facet_ords(&mut segment_counts.facet_reader, doc, &mut self.facet_ords);
+        for &facet_ord in &self.facet_ords {
+            segment_counts.foreground_counts[facet_ord as usize] += 1;
+        }
+        self.significant_terms.foreground_size += 1;
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn set_memory_budget(&mut self, memory_budget: &MemoryBudget) -> Result<()> {
+        self.memory_budget = memory_budget.clone();
+        Ok(())
+    }
+}
+
+impl MergeableCollector for SignificantTermsCollector {
+    fn split(&self) -> SignificantTermsCollector {
+        SignificantTermsCollector::for_field(self.field)
+    }
+
+    fn merge(&mut self, other: SignificantTermsCollector) {
+        self.significant_terms.merge(&other.significant_terms());
+    }
+}
+
+impl Aggregation for SignificantTermsCollector {
+    fn split_aggregation(&self) -> Box<Aggregation> {
+        box self.split()
+    }
+
+    fn merge_result(&mut self, result: AggregationResult) {
+        if let AggregationResult::SignificantTerms(ref significant_terms) = result {
+            self.significant_terms.merge(significant_terms);
+        }
+    }
+
+    fn result(&self) -> AggregationResult {
+        AggregationResult::SignificantTerms(self.significant_terms())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{SignificanceHeuristic, SignificantTermsCollector};
+    use query::{Query, TermQuery};
+    use schema::{Facet, IndexRecordOption, SchemaBuilder, Term, STRING};
+    use Index;
+
+    #[test]
+    fn test_significance_heuristics() {
+        for &heuristic in &[SignificanceHeuristic::Jlh, SignificanceHeuristic::ChiSquare] {
+            // as frequent in the foreground as in the background.
+            assert_eq!(heuristic.score(5, 10, 50, 100), 0f64);
+            // less frequent in the foreground.
+            assert_eq!(heuristic.score(1, 10, 50, 100), 0f64);
+            assert_eq!(heuristic.score(0, 0, 50, 100), 0f64);
+            assert!(heuristic.score(9, 10, 10, 100) > heuristic.score(6, 10, 10, 100));
+        }
+        assert_eq!(SignificanceHeuristic::Jlh.score(5, 10, 10, 100), 2f64);
+    }
+
+    #[test]
+    fn test_significant_terms_collector() {
+        let mut schema_builder = SchemaBuilder::default();
+        let lang_field = schema_builder.add_text_field("lang", STRING);
+        let tag_field = schema_builder.add_facet_field("tag");
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0..100 {
+                let lang = if i % 10 == 0 { "rust" } else { "java" };
+                let mut doc = doc!(lang_field => lang, tag_field => Facet::from("/common"));
+                if i % 20 == 0 {
+                    doc.add_facet(tag_field, Facet::from("/rare"));
+                }
+                if i % 2 == 0 {
+                    doc.add_facet(tag_field, Facet::from("/even"));
+                }
+                index_writer.add_document(doc);
+                if i == 50 {
+                    index_writer.commit().unwrap();
+                }
+            }
+            index_writer.add_document(doc!(
+                lang_field => "java",
+                tag_field => Facet::from("/rare")
+            ));
+            index_writer.commit().unwrap();
+            index_writer.delete_term(Term::from_field_text(lang_field, "java"));
+            index_writer.add_document(doc!(
+                lang_field => "java",
+                tag_field => Facet::from("/odd")
+            ));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query = TermQuery::new(
+            Term::from_field_text(lang_field, "rust"),
+            IndexRecordOption::Basic,
+        );
+        let expected_significant_terms = {
+            let mut collector = SignificantTermsCollector::for_field(tag_field);
+            query.search(&*searcher, &mut collector).unwrap();
+            collector.significant_terms()
+        };
+        assert_eq!(expected_significant_terms.foreground_size(), 10);
+        assert_eq!(expected_significant_terms.background_size(), 11);
+        assert_eq!(expected_significant_terms.counts("/rare"), (5, 5));
+        assert_eq!(expected_significant_terms.counts("/even"), (10, 10));
+        assert_eq!(expected_significant_terms.counts("/common"), (10, 10));
+        assert_eq!(expected_significant_terms.counts("/odd"), (0, 1));
+        let top_facets: Vec<(String, f64)> = expected_significant_terms
+            .top_k(2, SignificanceHeuristic::Jlh)
+            .into_iter()
+            .map(|(facet, score)| (facet.to_string(), score))
+            .collect();
+        assert_eq!(top_facets.len(), 2);
+        assert_eq!(top_facets[0].0, "/common");
+        assert_eq!(top_facets[1].0, "/even");
+
+        let mut collector = SignificantTermsCollector::for_field(tag_field);
+        searcher
+            .search_parallel(&query, &mut collector, 2)
+            .unwrap();
+        assert_eq!(collector.significant_terms(), expected_significant_terms);
+    }
+}
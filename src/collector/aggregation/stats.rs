@@ -0,0 +1,241 @@
+use super::{Aggregation, AggregationResult};
+use collector::{Collector, MergeableCollector};
+use fastfield::{FastFieldReader, FastValue};
+use schema::Field;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::f64;
+use DocId;
+use Result;
+use Score;
+use SegmentLocalId;
+use SegmentReader;
+
+/// Statistics over the values of a fast field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stats {
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for Stats {
+    fn default() -> Stats {
+        Stats {
+            count: 0,
+            sum: 0f64,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl Stats {
+    /// Returns the number of values.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the sum of the values.
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// Returns the smallest value, or `None` if there are no values.
+    pub fn min(&self) -> Option<f64> {
+        if self.count > 0 {
+            Some(self.min)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the largest value, or `None` if there are no values.
+    pub fn max(&self) -> Option<f64> {
+        if self.count > 0 {
+            Some(self.max)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the average of the values, or `None` if there are no values.
+    pub fn avg(&self) -> Option<f64> {
+        if self.count > 0 {
+            Some(self.sum / self.count as f64)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn add(&mut self, val: f64) {
+        self.count += 1;
+        self.sum += val;
+        self.min = self.min.min(val);
+        self.max = self.max.max(val);
+    }
+
+    /// Merges the statistics of another set of values.
+    pub fn merge(&mut self, other: &Stats) {
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+}
+
+// The min and max of empty stats are infinite, which is not representable
+// in some formats, e.g. JSON. They are serialized as options instead.
+#[derive(Serialize, Deserialize)]
+struct SerializableStats {
+    count: u64,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl Serialize for Stats {
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        SerializableStats {
+            count: self.count,
+            sum: self.sum,
+            min: self.min(),
+            max: self.max(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Stats {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Stats, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let stats = SerializableStats::deserialize(deserializer)?;
+        Ok(Stats {
+            count: stats.count,
+            sum: stats.sum,
+            min: stats.min.unwrap_or(f64::INFINITY),
+            max: stats.max.unwrap_or(f64::NEG_INFINITY),
+        })
+    }
+}
+
+/// `StatsCollector` computes the count, min, max, sum
+/// and average of a `u64` or `i64` fast field over the matching documents.
+///
+/// The values are aggregated as `f64`.
+pub struct StatsCollector<T: FastValue> {
+    field: Field,
+    stats: Stats,
+    ff_reader: Option<FastFieldReader<T>>,
+}
+
+impl<T: FastValue> StatsCollector<T> {
+    /// Creates a `StatsCollector` over the fast field `field`.
+    pub fn for_field(field: Field) -> StatsCollector<T> {
+        StatsCollector {
+            field,
+            stats: Stats::default(),
+            ff_reader: None,
+        }
+    }
+
+    /// Returns the statistics of the documents collected.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+}
+
+impl<T: FastValue> Collector for StatsCollector<T> {
+    fn set_segment(&mut self, _: SegmentLocalId, reader: &SegmentReader) -> Result<()> {
+        self.ff_reader = Some(reader.fast_field_reader(self.field)?);
+        Ok(())
+    }
+
+    fn collect(&mut self, doc: DocId, _: Score) {
+        let val = self.ff_reader
+            .as_ref()
+            .expect("collect() was called before set_segment. This should never happen.")
+            .get(doc);
+        self.stats.add(val.to_f64());
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+}
+
+impl<T: FastValue + Send> MergeableCollector for StatsCollector<T> {
+    fn split(&self) -> StatsCollector<T> {
+        StatsCollector::for_field(self.field)
+    }
+
+    fn merge(&mut self, other: StatsCollector<T>) {
+        self.stats.merge(&other.stats);
+    }
+}
+
+impl<T: FastValue + Send + 'static> Aggregation for StatsCollector<T> {
+    fn split_aggregation(&self) -> Box<Aggregation> {
+        box self.split()
+    }
+
+    fn merge_result(&mut self, result: AggregationResult) {
+        if let AggregationResult::Stats(ref stats) = result {
+            self.stats.merge(stats);
+        }
+    }
+
+    fn result(&self) -> AggregationResult {
+        AggregationResult::Stats(self.stats.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Stats, StatsCollector};
+    use query::AllQuery;
+    use schema::{SchemaBuilder, FAST, TEXT};
+    use Index;
+
+    #[test]
+    fn test_stats_collector() {
+        let mut schema_builder = SchemaBuilder::default();
+        let num_field = schema_builder.add_i64_field("num", FAST);
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for &val in &[3i64, -2i64, 10i64, 5i64] {
+                index_writer.add_document(doc!(num_field => val));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut stats_collector = StatsCollector::<i64>::for_field(num_field);
+        searcher.search(&AllQuery, &mut stats_collector).unwrap();
+        let stats = stats_collector.stats();
+        assert_eq!(stats.count(), 4);
+        assert_eq!(stats.sum(), 16f64);
+        assert_eq!(stats.min(), Some(-2f64));
+        assert_eq!(stats.max(), Some(10f64));
+        assert_eq!(stats.avg(), Some(4f64));
+
+        let mut stats_collector = StatsCollector::<i64>::for_field(text_field);
+        assert!(searcher.search(&AllQuery, &mut stats_collector).is_err());
+    }
+
+    #[test]
+    fn test_stats_empty() {
+        let stats = Stats::default();
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.min(), None);
+        assert_eq!(stats.max(), None);
+        assert_eq!(stats.avg(), None);
+    }
+}
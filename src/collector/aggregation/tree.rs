@@ -0,0 +1,1138 @@
+use super::histogram::{checked_bucket_ord, num_buckets};
+use super::{Aggregation, AggregationResult, Stats};
+use collector::{Collector, MergeableCollector};
+use core::MemoryBudget;
+use fastfield::{self, FastFieldNotAvailableError, FastFieldReader};
+use schema::{Field, FieldType};
+use serde::de;
+use serde::{Deserialize, Deserializer};
+use std::cmp::{self, Ordering};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::mem;
+use DocId;
+use ErrorKind;
+use Result;
+use Score;
+use SegmentLocalId;
+use SegmentReader;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum AggregationKind {
+    #[serde(rename = "stats")]
+    Stats,
+    #[serde(rename = "cardinality")]
+    Cardinality,
+    #[serde(rename = "histogram")]
+    Histogram { interval: f64 },
+    #[serde(rename = "terms")]
+    Terms {
+        size: usize,
+        shard_size: Option<usize>,
+    },
+}
+
+/// Request for an aggregation of an `AggregationTreeCollector`.
+///
+/// Metric aggregations (`stats`, `cardinality`) compute a value over
+/// the documents. Bucket aggregations (`histogram`, `terms`) group the
+/// documents into buckets, and compute their sub-aggregations over
+/// the documents of each bucket, e.g. `terms` → `histogram` → `stats`.
+///
+/// All of the aggregations apply to single-valued `u64` or `i64` fast fields.
+///
+/// A deserialized request is checked like the requests built with its methods:
+/// the deserialization fails if the interval of a `histogram` aggregation is not
+/// strictly positive, if a metric aggregation has sub-aggregations, or if two
+/// sub-aggregations have the same name.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AggregationRequest {
+    field: Field,
+    kind: AggregationKind,
+    sub_aggregations: Vec<(String, AggregationRequest)>,
+}
+
+#[derive(Deserialize)]
+struct SerializableAggregationRequest {
+    field: Field,
+    kind: AggregationKind,
+    sub_aggregations: Vec<(String, AggregationRequest)>,
+}
+
+impl<'de> Deserialize<'de> for AggregationRequest {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<AggregationRequest, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let request = SerializableAggregationRequest::deserialize(deserializer)?;
+        if let AggregationKind::Histogram { interval } = request.kind {
+            if !(interval > 0f64) {
+                return Err(de::Error::custom(NON_POSITIVE_INTERVAL));
+            }
+        }
+        let mut aggregation_request = AggregationRequest::new(request.field, request.kind);
+        for (name, sub_request) in request.sub_aggregations {
+            if let Err(msg) = aggregation_request.check_sub_aggregation(&name) {
+                return Err(de::Error::custom(msg));
+            }
+            aggregation_request.sub_aggregations.push((name, sub_request));
+        }
+        Ok(aggregation_request)
+    }
+}
+
+const NON_POSITIVE_INTERVAL: &str = "Interval must be strictly greater than 0.";
+
+impl AggregationRequest {
+    fn new(field: Field, kind: AggregationKind) -> AggregationRequest {
+        AggregationRequest {
+            field,
+            kind,
+            sub_aggregations: Vec::new(),
+        }
+    }
+
+    /// Computes the count, min, max, sum and average of the values of `field`.
+    pub fn stats(field: Field) -> AggregationRequest {
+        AggregationRequest::new(field, AggregationKind::Stats)
+    }
+
+    /// Counts the distinct values of `field`.
+    ///
+    /// The count is exact: the distinct values are kept in memory.
+    pub fn cardinality(field: Field) -> AggregationRequest {
+        AggregationRequest::new(field, AggregationKind::Cardinality)
+    }
+
+    /// Groups the documents in buckets of a fixed `interval`
+    /// over the values of `field`. (See `HistogramCollector`)
+    ///
+    /// For instance, with a field holding timestamps, an interval
+    /// of `86_400` seconds makes a daily date histogram.
+    ///
+    /// # Panics
+    /// The method panics if `interval` is not strictly positive.
+    pub fn histogram(field: Field, interval: f64) -> AggregationRequest {
+        if !(interval > 0f64) {
+            panic!("{}", NON_POSITIVE_INTERVAL);
+        }
+        AggregationRequest::new(field, AggregationKind::Histogram { interval })
+    }
+
+    /// Groups the documents by value of `field`, and keeps
+    /// the `size` buckets with the most documents.
+    ///
+    /// The buckets are only truncated once the results of all
+    /// of the segments are merged, so that the counts are exact.
+    pub fn terms(field: Field, size: usize) -> AggregationRequest {
+        AggregationRequest::new(
+            field,
+            AggregationKind::Terms {
+                size,
+                shard_size: None,
+            },
+        )
+    }
+
+    /// Sets the number of buckets of a `terms` aggregation kept in
+    /// its intermediate results. (See `IntermediateAggregationResults`)
+    ///
+    /// By default, all of the buckets are kept, so that the merged counts are exact.
+    /// With a shard size, the intermediate results are smaller, but the
+    /// buckets truncated from some of them are missing documents once merged.
+    /// The number of missing documents is bounded by
+    /// `Bucket::doc_count_error_upper_bound`.
+    ///
+    /// # Panics
+    /// The method panics if the aggregation is not a `terms` aggregation.
+    pub fn shard_size(mut self, shard_size: usize) -> AggregationRequest {
+        match self.kind {
+            AggregationKind::Terms {
+                shard_size: ref mut shard_size_opt,
+                ..
+            } => {
+                *shard_size_opt = Some(shard_size);
+            }
+            _ => panic!("Only the terms aggregations have a shard size."),
+        }
+        self
+    }
+
+    /// Adds an aggregation, computed over the documents
+    /// of each of the buckets, under the name `name`.
+    ///
+    /// # Panics
+    /// The method panics if the aggregation is not a bucket aggregation,
+    /// or if a sub-aggregation was already added under the same name.
+    pub fn sub_aggregation(
+        mut self,
+        name: &str,
+        request: AggregationRequest,
+    ) -> AggregationRequest {
+        if let Err(msg) = self.check_sub_aggregation(name) {
+            panic!("{}", msg);
+        }
+        self.sub_aggregations.push((name.to_string(), request));
+        self
+    }
+
+    /// Checks that a sub-aggregation can be added under the name `name`.
+    fn check_sub_aggregation(&self, name: &str) -> ::std::result::Result<(), String> {
+        if !self.is_bucket_aggregation() {
+            return Err("Only the bucket aggregations have sub-aggregations.".to_string());
+        }
+        if self.sub_aggregations
+            .iter()
+            .any(|&(ref other_name, _)| other_name == name)
+        {
+            return Err(format!("A sub-aggregation named {:?} was already added.", name));
+        }
+        Ok(())
+    }
+
+    fn is_bucket_aggregation(&self) -> bool {
+        match self.kind {
+            AggregationKind::Histogram { .. } | AggregationKind::Terms { .. } => true,
+            AggregationKind::Stats | AggregationKind::Cardinality => false,
+        }
+    }
+
+    fn new_intermediate_result(&self) -> IntermediateResult {
+        match self.kind {
+            AggregationKind::Stats => IntermediateResult::Stats(Stats::default()),
+            AggregationKind::Cardinality => IntermediateResult::Cardinality(HashSet::new()),
+            AggregationKind::Histogram { .. } | AggregationKind::Terms { .. } => {
+                IntermediateResult::Buckets {
+                    buckets: HashMap::new(),
+                    doc_count_error: 0,
+                }
+            }
+        }
+    }
+
+    fn new_intermediate_bucket(&self, key: f64) -> IntermediateBucket {
+        IntermediateBucket {
+            key,
+            doc_count: 0,
+            doc_count_error: 0,
+            sub_results: self.sub_aggregations
+                .iter()
+                .map(|&(_, ref sub_request)| sub_request.new_intermediate_result())
+                .collect(),
+        }
+    }
+}
+
+/// Result of an aggregation of an `AggregationTreeCollector`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AggregationTreeResult {
+    /// Result of a `stats` aggregation.
+    Stats(Stats),
+    /// Result of a `cardinality` aggregation.
+    Cardinality(u64),
+    /// Result of a `histogram` aggregation, sorted by key,
+    /// or of a `terms` aggregation, sorted by decreasing document count.
+    Buckets(Vec<Bucket>),
+}
+
+/// Bucket of a bucket aggregation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bucket {
+    key: f64,
+    doc_count: u64,
+    doc_count_error_upper_bound: u64,
+    sub_aggregations: BTreeMap<String, AggregationTreeResult>,
+}
+
+impl Bucket {
+    /// Returns the key of the bucket: its lower bound for a histogram,
+    /// and its value for terms.
+    pub fn key(&self) -> f64 {
+        self.key
+    }
+
+    /// Returns the number of documents in the bucket.
+    pub fn doc_count(&self) -> u64 {
+        self.doc_count
+    }
+
+    /// Returns the maximum number of documents missing from `doc_count`.
+    ///
+    /// It is always 0, unless the bucket was truncated from some of the
+    /// merged intermediate results of a `terms` aggregation with a shard size.
+    /// (See `AggregationRequest::shard_size`)
+    pub fn doc_count_error_upper_bound(&self) -> u64 {
+        self.doc_count_error_upper_bound
+    }
+
+    /// Returns the results of the sub-aggregations, by name.
+    pub fn sub_aggregations(&self) -> &BTreeMap<String, AggregationTreeResult> {
+        &self.sub_aggregations
+    }
+}
+
+/// Mergeable result of an aggregation, before the buckets of
+/// the `terms` aggregations are truncated.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+enum IntermediateResult {
+    #[serde(rename = "stats")]
+    Stats(Stats),
+    #[serde(rename = "cardinality")]
+    Cardinality(HashSet<u64>),
+    // The buckets are keyed by the ordinal of the bucket for a histogram
+    // (as a `u64`), and by the `u64` representation of the value for terms.
+    #[serde(rename = "buckets")]
+    Buckets {
+        buckets: HashMap<u64, IntermediateBucket>,
+        // Maximum number of documents of the buckets truncated
+        // from the results. (See `AggregationRequest::shard_size`)
+        doc_count_error: u64,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct IntermediateBucket {
+    key: f64,
+    doc_count: u64,
+    // Maximum number of documents missing from `doc_count`,
+    // as the bucket was truncated from some of the merged results.
+    doc_count_error: u64,
+    sub_results: Vec<IntermediateResult>,
+}
+
+impl IntermediateResult {
+    fn collect(
+        &mut self,
+        request: &AggregationRequest,
+        segment_aggregation: &SegmentAggregation,
+        doc: DocId,
+    ) {
+        let val_u64 = segment_aggregation.reader.get(doc);
+        let val = (segment_aggregation.to_f64)(val_u64);
+        match *self {
+            IntermediateResult::Stats(ref mut stats) => {
+                stats.add(val);
+            }
+            IntermediateResult::Cardinality(ref mut vals) => {
+                vals.insert(val_u64);
+            }
+            IntermediateResult::Buckets { ref mut buckets, .. } => {
+                let (bucket_ord, key) = match request.kind {
+                    AggregationKind::Histogram { interval } => {
+                        let bucket_ord = checked_bucket_ord(val, interval).expect(
+                            "The bucket ordinals are checked in set_segment. \
+                             This should never happen.",
+                        );
+                        (bucket_ord as u64, bucket_ord as f64 * interval)
+                    }
+                    _ => (val_u64, val),
+                };
+                let bucket = buckets
+                    .entry(bucket_ord)
+                    .or_insert_with(|| request.new_intermediate_bucket(key));
+                bucket.doc_count += 1;
+                for ((sub_result, &(_, ref sub_request)), sub_segment_aggregation) in bucket
+                    .sub_results
+                    .iter_mut()
+                    .zip(&request.sub_aggregations)
+                    .zip(&segment_aggregation.sub_aggregations)
+                {
+                    sub_result.collect(sub_request, sub_segment_aggregation, doc);
+                }
+            }
+        }
+    }
+
+    fn merge(&mut self, other: IntermediateResult) {
+        match *self {
+            IntermediateResult::Stats(ref mut stats) => {
+                if let IntermediateResult::Stats(other_stats) = other {
+                    stats.merge(&other_stats);
+                    return;
+                }
+            }
+            IntermediateResult::Cardinality(ref mut vals) => {
+                if let IntermediateResult::Cardinality(other_vals) = other {
+                    vals.extend(other_vals);
+                    return;
+                }
+            }
+            IntermediateResult::Buckets {
+                ref mut buckets,
+                ref mut doc_count_error,
+            } => {
+                if let IntermediateResult::Buckets {
+                    buckets: other_buckets,
+                    doc_count_error: other_doc_count_error,
+                } = other
+                {
+                    // a bucket missing from one of the results may have been
+                    // truncated from it, with up to its `doc_count_error` documents.
+                    for (bucket_ord, bucket) in buckets.iter_mut() {
+                        if !other_buckets.contains_key(bucket_ord) {
+                            bucket.doc_count_error += other_doc_count_error;
+                        }
+                    }
+                    for (bucket_ord, mut other_bucket) in other_buckets {
+                        if let Some(bucket) = buckets.get_mut(&bucket_ord) {
+                            bucket.doc_count += other_bucket.doc_count;
+                            bucket.doc_count_error += other_bucket.doc_count_error;
+                            for (sub_result, other_sub_result) in
+                                bucket.sub_results.iter_mut().zip(other_bucket.sub_results)
+                            {
+                                sub_result.merge(other_sub_result);
+                            }
+                            continue;
+                        }
+                        other_bucket.doc_count_error += *doc_count_error;
+                        buckets.insert(bucket_ord, other_bucket);
+                    }
+                    *doc_count_error += other_doc_count_error;
+                    return;
+                }
+            }
+        }
+        panic!("Merging the results of different aggregations. This should never happen.");
+    }
+
+    /// Truncates the buckets of the `terms` aggregations to their shard size.
+    fn truncate(&mut self, request: &AggregationRequest) {
+        if let IntermediateResult::Buckets {
+            ref mut buckets,
+            ref mut doc_count_error,
+        } = *self
+        {
+            if let AggregationKind::Terms {
+                shard_size: Some(shard_size),
+                ..
+            } = request.kind
+            {
+                if buckets.len() > shard_size {
+                    let mut bucket_ords: Vec<u64> = buckets.keys().cloned().collect();
+                    bucket_ords.sort_by(|left, right| terms_order(&buckets[left], &buckets[right]));
+                    for bucket_ord in &bucket_ords[shard_size..] {
+                        let bucket = buckets.remove(bucket_ord).unwrap();
+                        *doc_count_error =
+                            (*doc_count_error).max(bucket.doc_count + bucket.doc_count_error);
+                    }
+                }
+            }
+            for bucket in buckets.values_mut() {
+                for (sub_result, &(_, ref sub_request)) in
+                    bucket.sub_results.iter_mut().zip(&request.sub_aggregations)
+                {
+                    sub_result.truncate(sub_request);
+                }
+            }
+        }
+    }
+
+    /// Returns true iff the result has the structure of the results of `request`.
+    fn matches(&self, request: &AggregationRequest) -> bool {
+        match (self, &request.kind) {
+            (&IntermediateResult::Stats(_), &AggregationKind::Stats)
+            | (&IntermediateResult::Cardinality(_), &AggregationKind::Cardinality) => true,
+            (&IntermediateResult::Buckets { ref buckets, .. }, _)
+                if request.is_bucket_aggregation() =>
+            {
+                buckets.values().all(|bucket| {
+                    bucket.sub_results.len() == request.sub_aggregations.len()
+                        && bucket
+                            .sub_results
+                            .iter()
+                            .zip(&request.sub_aggregations)
+                            .all(|(sub_result, &(_, ref sub_request))| {
+                                sub_result.matches(sub_request)
+                            })
+                })
+            }
+            _ => false,
+        }
+    }
+
+    fn result(&self, request: &AggregationRequest) -> AggregationTreeResult {
+        match *self {
+            IntermediateResult::Stats(ref stats) => AggregationTreeResult::Stats(stats.clone()),
+            IntermediateResult::Cardinality(ref vals) => {
+                AggregationTreeResult::Cardinality(vals.len() as u64)
+            }
+            IntermediateResult::Buckets { ref buckets, .. } => {
+                let mut buckets: Vec<&IntermediateBucket> = buckets.values().collect();
+                match request.kind {
+                    AggregationKind::Terms { size, .. } => {
+                        buckets.sort_by(|left, right| terms_order(left, right));
+                        buckets.truncate(size);
+                    }
+                    _ => {
+                        buckets.sort_by(|left, right| compare_keys(left, right));
+                    }
+                }
+                AggregationTreeResult::Buckets(
+                    buckets
+                        .into_iter()
+                        .map(|bucket| Bucket {
+                            key: bucket.key,
+                            doc_count: bucket.doc_count,
+                            doc_count_error_upper_bound: bucket.doc_count_error,
+                            sub_aggregations: request
+                                .sub_aggregations
+                                .iter()
+                                .zip(&bucket.sub_results)
+                                .map(|(&(ref name, ref sub_request), sub_result)| {
+                                    (name.clone(), sub_result.result(sub_request))
+                                })
+                                .collect(),
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+}
+
+fn compare_keys(left: &IntermediateBucket, right: &IntermediateBucket) -> Ordering {
+    left.key
+        .partial_cmp(&right.key)
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Order of the buckets of a `terms` aggregation:
+/// by decreasing document count, then by key.
+fn terms_order(left: &IntermediateBucket, right: &IntermediateBucket) -> Ordering {
+    right
+        .doc_count
+        .cmp(&left.doc_count)
+        .then_with(|| compare_keys(left, right))
+}
+
+fn harvest_results(
+    requests: &[(String, AggregationRequest)],
+    results: &[IntermediateResult],
+) -> BTreeMap<String, AggregationTreeResult> {
+    requests
+        .iter()
+        .zip(results)
+        .map(|(&(ref name, ref request), result)| (name.clone(), result.result(request)))
+        .collect()
+}
+
+/// Intermediate results of an `AggregationTreeCollector`.
+/// (See `AggregationTreeCollector::intermediate_results`)
+///
+/// They are serializable, so that each of the shards of a distributed index
+/// can compute the intermediate results of the aggregations over its documents,
+/// and a coordinator can merge them all, before harvesting the final results.
+///
+/// The merge is associative and commutative, so the intermediate results
+/// can be merged in any order. The stats and cardinalities are exact, and so
+/// are the buckets, unless the `terms` aggregations have a shard size.
+/// Then, each of the intermediate results only keeps the `shard_size` buckets
+/// with the most documents, and records the maximum number of documents of
+/// the buckets it truncated: once merged, a bucket truncated from some of
+/// the intermediate results is missing at most the sum of their maximums.
+/// (See `Bucket::doc_count_error_upper_bound`)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct IntermediateAggregationResults {
+    requests: Vec<(String, AggregationRequest)>,
+    results: Vec<IntermediateResult>,
+}
+
+impl IntermediateAggregationResults {
+    fn is_valid(&self) -> bool {
+        self.requests.len() == self.results.len()
+            && self.requests
+                .iter()
+                .zip(&self.results)
+                .all(|(&(_, ref request), result)| result.matches(request))
+    }
+
+    /// Merges the intermediate results of the same aggregations,
+    /// computed over other documents.
+    ///
+    /// # Errors
+    /// Returns an `InvalidArgument` error if the intermediate results
+    /// are not the results of the same aggregations.
+    pub fn merge(&mut self, other: IntermediateAggregationResults) -> Result<()> {
+        if self.requests != other.requests || !self.is_valid() || !other.is_valid() {
+            let msg = "The intermediate results of different aggregations cannot be merged.";
+            bail!(ErrorKind::InvalidArgument(msg.to_string()));
+        }
+        for (result, other_result) in self.results.iter_mut().zip(other.results) {
+            result.merge(other_result);
+        }
+        Ok(())
+    }
+
+    /// Returns the results of the aggregations, by name.
+    pub fn harvest(&self) -> BTreeMap<String, AggregationTreeResult> {
+        harvest_results(&self.requests, &self.results)
+    }
+}
+
+/// Fast field readers of an aggregation tree, for the current segment.
+struct SegmentAggregation {
+    reader: FastFieldReader<u64>,
+    // converts the `u64` representation of the values.
+    to_f64: fn(u64) -> f64,
+    // maximum number of buckets, or of distinct values,
+    // of the aggregation over the documents of the segment.
+    num_keys: u64,
+    sub_aggregations: Vec<SegmentAggregation>,
+}
+
+impl SegmentAggregation {
+    fn open(request: &AggregationRequest, reader: &SegmentReader) -> Result<SegmentAggregation> {
+        let field_entry = reader.schema().get_field_entry(request.field);
+        let fast_field_reader = reader
+            .u64_fast_field_reader(request.field)
+            .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))?;
+        let to_f64: fn(u64) -> f64 = match *field_entry.field_type() {
+            FieldType::I64(_) => fastfield::u64_to_f64::<i64>,
+            _ => fastfield::u64_to_f64::<u64>,
+        };
+        let (min_value, max_value) = (fast_field_reader.min_value(), fast_field_reader.max_value());
+        let num_keys = match request.kind {
+            AggregationKind::Stats => 1,
+            AggregationKind::Cardinality | AggregationKind::Terms { .. } => {
+                (max_value - min_value).saturating_add(1)
+            }
+            AggregationKind::Histogram { interval } => {
+                num_buckets(interval, to_f64(min_value), to_f64(max_value))?
+            }
+        };
+        let sub_aggregations = request
+            .sub_aggregations
+            .iter()
+            .map(|&(_, ref sub_request)| SegmentAggregation::open(sub_request, reader))
+            .collect::<Result<_>>()?;
+        Ok(SegmentAggregation {
+            reader: fast_field_reader,
+            to_f64,
+            num_keys,
+            sub_aggregations,
+        })
+    }
+
+    /// Returns the maximum number of bytes added to the results of the
+    /// aggregation by the documents of the segment, given the number of
+    /// buckets of its parent aggregation: each of the documents adds
+    /// at most one bucket, or one value, to each of the aggregations.
+    fn num_bytes(
+        &self,
+        request: &AggregationRequest,
+        num_parent_buckets: u64,
+        max_doc: u64,
+    ) -> u64 {
+        let num_entries = cmp::min(max_doc, num_parent_buckets.saturating_mul(self.num_keys));
+        let entry_size = match request.kind {
+            AggregationKind::Stats => mem::size_of::<Stats>(),
+            AggregationKind::Cardinality => mem::size_of::<u64>(),
+            AggregationKind::Histogram { .. } | AggregationKind::Terms { .. } => {
+                mem::size_of::<(u64, IntermediateBucket)>()
+            }
+        };
+        self.sub_aggregations
+            .iter()
+            .zip(&request.sub_aggregations)
+            .map(|(sub_aggregation, &(_, ref sub_request))| {
+                sub_aggregation.num_bytes(sub_request, num_entries, max_doc)
+            })
+            .fold(num_entries * entry_size as u64, u64::saturating_add)
+    }
+}
+
+/// `AggregationTreeCollector` computes a tree of aggregations
+/// in a single search pass.
+///
+/// The aggregations are evaluated segment by segment, into intermediate
+/// results that are merged across segments, and across the threads of
+/// `Searcher::search_parallel`. It is also an `Aggregation`, so
+/// it can be registered in an `AggregationCollector`, along with
+/// the other aggregations: its result is then its intermediate results.
+///
+/// The `stats` aggregations compute `Stats`, and the buckets of the
+/// `histogram` aggregations are those of a `Histogram` with the same interval.
+///
+/// At most one bucket, or one value, per document, or per key between the
+/// min and the max value of a segment, is reserved for each of the aggregations
+/// against the memory budget of the search. The search fails with an
+/// `InvalidArgument` error if the interval of a `histogram` aggregation is so
+/// small that the bucket ordinals of the values do not fit in an `i64`.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::schema::{SchemaBuilder, FAST};
+/// use tantivy::collector::aggregation::{AggregationRequest, AggregationTreeCollector,
+///                                       AggregationTreeResult};
+/// use tantivy::query::AllQuery;
+/// use tantivy::Index;
+///
+/// # fn main() {
+/// let mut schema_builder = SchemaBuilder::default();
+/// let category = schema_builder.add_u64_field("category", FAST);
+/// let price = schema_builder.add_u64_field("price", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// {
+///     let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+///     index_writer.add_document(doc!(category => 1u64, price => 10u64));
+///     index_writer.add_document(doc!(category => 2u64, price => 20u64));
+///     index_writer.add_document(doc!(category => 1u64, price => 30u64));
+///     index_writer.commit().unwrap();
+/// }
+/// index.load_searchers().unwrap();
+/// let searcher = index.searcher();
+/// let mut aggregations = AggregationTreeCollector::default();
+/// aggregations.add_aggregation(
+///     "categories",
+///     AggregationRequest::terms(category, 10)
+///         .sub_aggregation("prices", AggregationRequest::stats(price)),
+/// );
+/// searcher.search(&AllQuery, &mut aggregations).unwrap();
+/// let results = aggregations.harvest();
+/// if let AggregationTreeResult::Buckets(ref buckets) = results["categories"] {
+///     assert_eq!(buckets[0].key(), 1f64);
+///     assert_eq!(buckets[0].doc_count(), 2);
+///     if let AggregationTreeResult::Stats(ref stats) = buckets[0].sub_aggregations()["prices"] {
+///         assert_eq!(stats.avg(), Some(20f64));
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Default)]
+pub struct AggregationTreeCollector {
+    requests: Vec<(String, AggregationRequest)>,
+    results: Vec<IntermediateResult>,
+    segment_aggregations: Vec<SegmentAggregation>,
+    memory_budget: MemoryBudget,
+}
+
+impl AggregationTreeCollector {
+    /// Registers an aggregation under the name `name`.
+    ///
+    /// # Panics
+    /// The method panics if an aggregation was already registered
+    /// under the same name.
+    pub fn add_aggregation(&mut self, name: &str, request: AggregationRequest) {
+        assert!(
+            self.requests
+                .iter()
+                .all(|&(ref other_name, _)| other_name != name),
+            "An aggregation named {:?} was already added.",
+            name
+        );
+        self.results.push(request.new_intermediate_result());
+        self.requests.push((name.to_string(), request));
+    }
+
+    /// Returns the results of the aggregations, by name.
+    pub fn harvest(&self) -> BTreeMap<String, AggregationTreeResult> {
+        harvest_results(&self.requests, &self.results)
+    }
+
+    /// Returns the intermediate results of the aggregations,
+    /// which can be serialized and merged with the intermediate
+    /// results of other collectors, e.g. over the other shards of an index.
+    ///
+    /// The buckets of the `terms` aggregations are truncated
+    /// to their shard size, if any. (See `AggregationRequest::shard_size`)
+    pub fn intermediate_results(&self) -> IntermediateAggregationResults {
+        let mut results = self.results.clone();
+        for (result, &(_, ref request)) in results.iter_mut().zip(&self.requests) {
+            result.truncate(request);
+        }
+        IntermediateAggregationResults {
+            requests: self.requests.clone(),
+            results,
+        }
+    }
+}
+
+impl Collector for AggregationTreeCollector {
+    fn set_segment(&mut self, _: SegmentLocalId, reader: &SegmentReader) -> Result<()> {
+        self.segment_aggregations = self.requests
+            .iter()
+            .map(|&(_, ref request)| SegmentAggregation::open(request, reader))
+            .collect::<Result<_>>()?;
+        let num_bytes = self.segment_aggregations
+            .iter()
+            .zip(&self.requests)
+            .map(|(segment_aggregation, &(_, ref request))| {
+                segment_aggregation.num_bytes(request, 1, reader.max_doc() as u64)
+            })
+            .fold(0, u64::saturating_add);
+        self.memory_budget
+            .allocate(cmp::min(num_bytes, usize::max_value() as u64) as usize)?;
+        Ok(())
+    }
+
+    fn collect(&mut self, doc: DocId, _: Score) {
+        for ((result, &(_, ref request)), segment_aggregation) in self.results
+            .iter_mut()
+            .zip(&self.requests)
+            .zip(&self.segment_aggregations)
+        {
+            result.collect(request, segment_aggregation, doc);
+        }
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn set_memory_budget(&mut self, memory_budget: &MemoryBudget) -> Result<()> {
+        self.memory_budget = memory_budget.clone();
+        Ok(())
+    }
+}
+
+impl MergeableCollector for AggregationTreeCollector {
+    fn split(&self) -> AggregationTreeCollector {
+        let mut aggregation_tree_collector = AggregationTreeCollector::default();
+        for &(ref name, ref request) in &self.requests {
+            aggregation_tree_collector.add_aggregation(name, request.clone());
+        }
+        aggregation_tree_collector
+    }
+
+    fn merge(&mut self, other: AggregationTreeCollector) {
+        for (result, other_result) in self.results.iter_mut().zip(other.results) {
+            result.merge(other_result);
+        }
+    }
+}
+
+impl Aggregation for AggregationTreeCollector {
+    fn split_aggregation(&self) -> Box<Aggregation> {
+        box self.split()
+    }
+
+    fn merge_result(&mut self, result: AggregationResult) {
+        if let AggregationResult::Tree(intermediate_results) = result {
+            if intermediate_results.requests == self.requests {
+                for (result, other_result) in self.results
+                    .iter_mut()
+                    .zip(intermediate_results.results)
+                {
+                    result.merge(other_result);
+                }
+            }
+        }
+    }
+
+    /// Returns the intermediate results of the aggregations,
+    /// before the buckets of the `terms` aggregations are truncated.
+    fn result(&self) -> AggregationResult {
+        AggregationResult::Tree(IntermediateAggregationResults {
+            requests: self.requests.clone(),
+            results: self.results.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{AggregationRequest, AggregationTreeCollector, AggregationTreeResult, Bucket,
+                IntermediateAggregationResults, IntermediateBucket};
+    use collector::aggregation::{AggregationCollector, AggregationResult};
+    use collector::MergeableCollector;
+    use core::MemoryBudget;
+    use query::AllQuery;
+    use schema::{Field, SchemaBuilder, FAST, TEXT};
+    use serde_json;
+    use std::mem;
+    use ErrorKind;
+    use Index;
+
+    fn buckets(result: &AggregationTreeResult) -> &[Bucket] {
+        match *result {
+            AggregationTreeResult::Buckets(ref buckets) => buckets,
+            _ => panic!("Expected buckets"),
+        }
+    }
+
+    #[test]
+    fn test_aggregation_tree_collector() {
+        let mut schema_builder = SchemaBuilder::default();
+        let category_field = schema_builder.add_u64_field("category", FAST);
+        let timestamp_field = schema_builder.add_i64_field("timestamp", FAST);
+        let price_field = schema_builder.add_u64_field("price", FAST);
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0u64..3_000u64 {
+                index_writer.add_document(doc!(
+                    category_field => i % 3 + (i % 2),
+                    timestamp_field => (i % 10) as i64 * 50 - 100,
+                    price_field => i % 7
+                ));
+                if i % 1_000 == 999 {
+                    index_writer.commit().unwrap();
+                }
+            }
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut collector = AggregationTreeCollector::default();
+        collector.add_aggregation(
+            "categories",
+            AggregationRequest::terms(category_field, 2).sub_aggregation(
+                "dates",
+                AggregationRequest::histogram(timestamp_field, 200f64)
+                    .sub_aggregation("prices", AggregationRequest::stats(price_field)),
+            ),
+        );
+        collector.add_aggregation(
+            "num_prices",
+            AggregationRequest::cardinality(price_field),
+        );
+        searcher.search(&AllQuery, &mut collector).unwrap();
+        let results = collector.harvest();
+        assert_eq!(results["num_prices"], AggregationTreeResult::Cardinality(7));
+
+        // categories: 0 (i % 6 == 0), 1 (i % 6 in {3, 4}), 2 (i % 6 in {1, 2}), 3 (i % 6 == 5)
+        let categories = buckets(&results["categories"]);
+        assert_eq!(
+            categories
+                .iter()
+                .map(|bucket| (bucket.key(), bucket.doc_count()))
+                .collect::<Vec<_>>(),
+            vec![(1f64, 1_000), (2f64, 1_000)]
+        );
+        let dates = buckets(&categories[0].sub_aggregations()["dates"]);
+        assert_eq!(
+            dates
+                .iter()
+                .map(|bucket| (bucket.key(), bucket.doc_count()))
+                .collect::<Vec<_>>(),
+            vec![(-200f64, 200), (0f64, 400), (200f64, 400)]
+        );
+        match dates[0].sub_aggregations()["prices"] {
+            AggregationTreeResult::Stats(ref stats) => {
+                assert_eq!(stats.count(), 200);
+                assert_eq!(stats.min(), Some(0f64));
+                assert_eq!(stats.max(), Some(6f64));
+            }
+            _ => panic!("Expected stats"),
+        }
+
+        let mut parallel_collector = collector.split();
+        searcher
+            .search_parallel(&AllQuery, &mut parallel_collector, 3)
+            .unwrap();
+        assert_eq!(parallel_collector.harvest(), results);
+
+        let mut invalid_collector = AggregationTreeCollector::default();
+        invalid_collector.add_aggregation("text", AggregationRequest::stats(text_field));
+        assert!(searcher.search(&AllQuery, &mut invalid_collector).is_err());
+    }
+
+    #[test]
+    fn test_aggregation_tree_in_aggregation_collector() {
+        let mut schema_builder = SchemaBuilder::default();
+        let category_field = schema_builder.add_u64_field("category", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0u64..30u64 {
+                index_writer.add_document(doc!(category_field => i % 3));
+                if i % 10 == 9 {
+                    index_writer.commit().unwrap();
+                }
+            }
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut tree_collector = AggregationTreeCollector::default();
+        tree_collector.add_aggregation("categories", AggregationRequest::terms(category_field, 2));
+        let mut aggregations = AggregationCollector::default();
+        aggregations.add_aggregation("tree", tree_collector);
+        let mut parallel_aggregations = aggregations.split();
+        searcher
+            .search_parallel(&AllQuery, &mut parallel_aggregations, 3)
+            .unwrap();
+        match parallel_aggregations.harvest()["tree"] {
+            AggregationResult::Tree(ref intermediate_results) => {
+                assert_eq!(
+                    buckets(&intermediate_results.harvest()["categories"])
+                        .iter()
+                        .map(|bucket| (bucket.key(), bucket.doc_count()))
+                        .collect::<Vec<_>>(),
+                    vec![(0f64, 10), (1f64, 10)]
+                );
+            }
+            _ => panic!("Expected the results of an aggregation tree"),
+        }
+    }
+
+    #[test]
+    fn test_aggregation_tree_memory_budget() {
+        let mut schema_builder = SchemaBuilder::default();
+        let category_field = schema_builder.add_u64_field("category", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0u64..10u64 {
+                index_writer.add_document(doc!(category_field => i));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let bucket_size = mem::size_of::<(u64, IntermediateBucket)>();
+
+        // One bucket per document, and one cardinality value per document and bucket.
+        let memory_budget = MemoryBudget::unlimited();
+        let mut collector = AggregationTreeCollector::default();
+        collector.add_aggregation(
+            "categories",
+            AggregationRequest::terms(category_field, 10)
+                .sub_aggregation("num_categories", AggregationRequest::cardinality(category_field)),
+        );
+        searcher
+            .with_memory_budget(memory_budget.clone())
+            .search(&AllQuery, &mut collector)
+            .unwrap();
+        assert_eq!(
+            memory_budget.allocated_bytes(),
+            10 * bucket_size + 10 * mem::size_of::<u64>()
+        );
+
+        let mut collector = AggregationTreeCollector::default();
+        collector.add_aggregation("categories", AggregationRequest::terms(category_field, 10));
+        assert!(
+            searcher
+                .with_memory_budget(MemoryBudget::with_limit(9 * bucket_size))
+                .search(&AllQuery, &mut collector)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_aggregation_tree_tiny_interval() {
+        let mut schema_builder = SchemaBuilder::default();
+        let num_field = schema_builder.add_i64_field("num", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(num_field => -1_000i64));
+            index_writer.add_document(doc!(num_field => 1_000i64));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut collector = AggregationTreeCollector::default();
+        collector.add_aggregation("nums", AggregationRequest::histogram(num_field, 1e-300f64));
+        match *searcher
+            .search(&AllQuery, &mut collector)
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::InvalidArgument(_) => {}
+            _ => panic!("Expected an InvalidArgument error"),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_invalid_aggregation_request() {
+        let field = Field(0);
+        let histogram_json = serde_json::to_string(&AggregationRequest::histogram(field, 10f64))
+            .unwrap()
+            .replace("10.0", "-1.0");
+        assert!(serde_json::from_str::<AggregationRequest>(&histogram_json).is_err());
+
+        let terms_json = serde_json::to_string(
+            &AggregationRequest::terms(field, 10)
+                .sub_aggregation("a", AggregationRequest::stats(field))
+                .sub_aggregation("b", AggregationRequest::stats(field)),
+        ).unwrap();
+        assert!(serde_json::from_str::<AggregationRequest>(&terms_json).is_ok());
+        let duplicate_json = terms_json.replace("\"b\"", "\"a\"");
+        assert!(serde_json::from_str::<AggregationRequest>(&duplicate_json).is_err());
+        let stats_json = terms_json.replace(
+            r#"{"terms":{"size":10,"shard_size":null}}"#,
+            r#""stats""#,
+        );
+        assert_ne!(stats_json, terms_json);
+        assert!(serde_json::from_str::<AggregationRequest>(&stats_json).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_metric_aggregation_has_no_sub_aggregation() {
+        let field = ::schema::Field(0);
+        AggregationRequest::stats(field).sub_aggregation("a", AggregationRequest::stats(field));
+    }
+
+    fn shard_intermediate_results(categories: &[u64]) -> IntermediateAggregationResults {
+        let mut schema_builder = SchemaBuilder::default();
+        let category_field = schema_builder.add_u64_field("category", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for &category in categories {
+                index_writer.add_document(doc!(category_field => category));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut collector = AggregationTreeCollector::default();
+        collector.add_aggregation(
+            "categories",
+            AggregationRequest::terms(category_field, 2).shard_size(2),
+        );
+        collector.add_aggregation("stats", AggregationRequest::stats(category_field));
+        collector.add_aggregation(
+            "num_categories",
+            AggregationRequest::cardinality(category_field),
+        );
+        searcher.search(&AllQuery, &mut collector).unwrap();
+        let json = serde_json::to_string(&collector.intermediate_results()).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_intermediate_aggregation_results() {
+        let mut results = shard_intermediate_results(&[1, 1, 1, 1, 1, 2, 2, 2, 2, 3]);
+        // the buckets 1 and 3 are truncated from the shards, with 1 document each.
+        let other_results = shard_intermediate_results(&[2, 2, 2, 2, 2, 3, 3, 3, 3, 1]);
+        results.merge(other_results).unwrap();
+        let merged_results = results.harvest();
+        assert_eq!(
+            buckets(&merged_results["categories"])
+                .iter()
+                .map(|bucket| {
+                    (
+                        bucket.key(),
+                        bucket.doc_count(),
+                        bucket.doc_count_error_upper_bound(),
+                    )
+                })
+                .collect::<Vec<_>>(),
+            vec![(2f64, 9, 0), (1f64, 5, 1)]
+        );
+        match merged_results["stats"] {
+            AggregationTreeResult::Stats(ref stats) => {
+                assert_eq!(stats.count(), 20);
+                assert_eq!(stats.sum(), 39f64);
+                assert_eq!(stats.min(), Some(1f64));
+                assert_eq!(stats.max(), Some(3f64));
+            }
+            _ => panic!("Expected stats"),
+        }
+        assert_eq!(
+            merged_results["num_categories"],
+            AggregationTreeResult::Cardinality(3)
+        );
+
+        let mut other_collector = AggregationTreeCollector::default();
+        other_collector.add_aggregation("categories", AggregationRequest::stats(Field(0)));
+        assert!(
+            results
+                .merge(other_collector.intermediate_results())
+                .is_err()
+        );
+    }
+}
@@ -4,6 +4,7 @@ use SegmentLocalId;
 use SegmentReader;
 use DocId;
 use Score;
+use core::MemoryBudget;
 
 /// Collector that does nothing.
 /// This is used in the chain Collector and will hopefully
@@ -59,6 +60,11 @@ impl<Left: Collector, Right: Collector> Collector for ChainedCollector<Left, Rig
     fn requires_scoring(&self) -> bool {
         self.left.requires_scoring() || self.right.requires_scoring()
     }
+
+    fn set_memory_budget(&mut self, memory_budget: &MemoryBudget) -> Result<()> {
+        self.left.set_memory_budget(memory_budget)?;
+        self.right.set_memory_budget(memory_budget)
+    }
 }
 
 /// Creates a `ChainedCollector`
@@ -1,4 +1,4 @@
-use super::Collector;
+use super::{Collector, MergeableCollector};
 use DocId;
 use Score;
 use Result;
@@ -7,6 +7,9 @@ use SegmentLocalId;
 
 /// `CountCollector` collector only counts how many
 /// documents match the query.
+///
+/// If only the count is needed, prefer `Query::count`,
+/// which does not necessarily decode the postings of the query.
 #[derive(Default)]
 pub struct CountCollector {
     count: usize,
@@ -34,6 +37,16 @@ impl Collector for CountCollector {
     }
 }
 
+impl MergeableCollector for CountCollector {
+    fn split(&self) -> CountCollector {
+        CountCollector::default()
+    }
+
+    fn merge(&mut self, other: CountCollector) {
+        self.count += other.count;
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -43,10 +56,10 @@ mod tests {
     fn test_count_collector() {
         let mut count_collector = CountCollector::default();
         assert_eq!(count_collector.count(), 0);
-        count_collector.collect(0u32, 1f32);
+        count_collector.collect(0u32, 1.0);
         assert_eq!(count_collector.count(), 1);
         assert_eq!(count_collector.count(), 1);
-        count_collector.collect(1u32, 1f32);
+        count_collector.collect(1u32, 1.0);
         assert_eq!(count_collector.count(), 2);
         assert!(!count_collector.requires_scoring());
     }
@@ -0,0 +1,326 @@
+use collector::Collector;
+use core::Searcher;
+use docset::{DocSet, SkipResult};
+use query::{Query, Scorer, Weight};
+use DocId;
+use Result;
+use SegmentLocalId;
+
+/// Drill sideways makes it possible to compute the facet counts
+/// of a drill down query, in which each dimension ignores its own filter.
+///
+/// A typical faceted search UI lets a user select a value in several
+/// dimensions. If the user selected `brand:acme` and `color:red`,
+///
+/// - the hits are the documents matching the base query and both filters,
+/// - the counts of the `brand` dimension are computed over the documents
+/// matching the base query and `color:red`, so that the other brands
+/// remain available,
+/// - the counts of the `color` dimension are computed over the documents
+/// matching the base query and `brand:acme`.
+///
+/// All of these are computed in a single pass over the documents
+/// matching the base query:
+///
+/// - documents matching all of the filters are pushed to the hits collector
+/// and to the collector of every dimension.
+/// - documents matching all of the filters but one are pushed to the
+/// collector of the dimension whose filter they do not match.
+///
+/// The dimension collectors are typically `FacetCollector`s,
+/// and the documents are scored by the base query only.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::schema::{Facet, IndexRecordOption, SchemaBuilder};
+/// use tantivy::{Index, Result, Term};
+/// use tantivy::collector::{CountCollector, DrillSideways, FacetCollector};
+/// use tantivy::query::{AllQuery, TermQuery};
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let brand = schema_builder.add_facet_field("brand");
+///     let color = schema_builder.add_facet_field("color");
+///     let index = Index::create_in_ram(schema_builder.build());
+///     {
+///         let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+///         for &(brand_val, color_val) in &[("acme", "red"), ("acme", "blue"), ("ajax", "red")] {
+///             index_writer.add_document(doc!(
+///                 brand => Facet::from(&format!("/{}", brand_val)[..]),
+///                 color => Facet::from(&format!("/{}", color_val)[..])
+///             ));
+///         }
+///         index_writer.commit()?;
+///     }
+///     index.load_searchers()?;
+///     let searcher = index.searcher();
+///
+///     let facet_term = |field, path: &str| {
+///         let term = Term::from_facet(field, &Facet::from(path));
+///         TermQuery::new(term, IndexRecordOption::Basic)
+///     };
+///     let acme = facet_term(brand, "/acme");
+///     let red = facet_term(color, "/red");
+///     let mut brand_counts = FacetCollector::for_field(brand);
+///     brand_counts.add_facet("/");
+///     let mut color_counts = FacetCollector::for_field(color);
+///     color_counts.add_facet("/");
+///     let mut hits = CountCollector::default();
+///     {
+///         let mut drill_sideways = DrillSideways::new(&AllQuery);
+///         drill_sideways.add_dimension(Some(&acme), &mut brand_counts);
+///         drill_sideways.add_dimension(Some(&red), &mut color_counts);
+///         drill_sideways.search(&*searcher, &mut hits)?;
+///     }
+///     assert_eq!(hits.count(), 1);
+///
+///     let brand_counts = brand_counts.harvest();
+///     let brands: Vec<(String, u64)> = brand_counts
+///         .get("/")
+///         .map(|(facet, count)| (facet.to_string(), count))
+///         .collect();
+///     assert_eq!(brands, vec![("/acme".to_string(), 1), ("/ajax".to_string(), 1)]);
+///
+///     let color_counts = color_counts.harvest();
+///     let colors: Vec<(String, u64)> = color_counts
+///         .get("/")
+///         .map(|(facet, count)| (facet.to_string(), count))
+///         .collect();
+///     assert_eq!(colors, vec![("/blue".to_string(), 1), ("/red".to_string(), 1)]);
+///     Ok(())
+/// }
+/// ```
+pub struct DrillSideways<'a> {
+    base_query: &'a Query,
+    dimensions: Vec<(Option<&'a Query>, &'a mut Collector)>,
+}
+
+impl<'a> DrillSideways<'a> {
+    /// Creates a `DrillSideways` over the documents matching `base_query`.
+    pub fn new(base_query: &'a Query) -> DrillSideways<'a> {
+        DrillSideways {
+            base_query,
+            dimensions: Vec::new(),
+        }
+    }
+
+    /// Adds a dimension, with its filter and the collector
+    /// in charge of computing its counts.
+    ///
+    /// `filter` is `None` if no value was selected for this dimension.
+    pub fn add_dimension(&mut self, filter: Option<&'a Query>, collector: &'a mut Collector) {
+        self.dimensions.push((filter, collector));
+    }
+
+    /// Runs the search, pushing the documents matching the base query
+    /// and all of the filters to `collector`, and computing the counts
+    /// of the dimensions.
+    pub fn search(&mut self, searcher: &Searcher, collector: &mut Collector) -> Result<()> {
+        let scoring_enabled = collector.requires_scoring()
+            || self.dimensions
+                .iter()
+                .any(|&(_, ref dimension_collector)| dimension_collector.requires_scoring());
+        let base_weight = self.base_query.weight(searcher, scoring_enabled)?;
+        let mut filter_weights: Vec<Option<Box<Weight>>> = Vec::new();
+        for &(filter_opt, _) in &self.dimensions {
+            filter_weights.push(match filter_opt {
+                Some(filter) => Some(filter.weight(searcher, false)?),
+                None => None,
+            });
+        }
+        collector.set_memory_budget(searcher.memory_budget())?;
+        for &mut (_, ref mut dimension_collector) in &mut self.dimensions {
+            dimension_collector.set_memory_budget(searcher.memory_budget())?;
+        }
+        for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+            let segment_ord = segment_ord as SegmentLocalId;
+            collector.set_segment(segment_ord, segment_reader)?;
+            for &mut (_, ref mut dimension_collector) in &mut self.dimensions {
+                dimension_collector.set_segment(segment_ord, segment_reader)?;
+            }
+            let mut filters: Vec<Option<FilterCursor>> = Vec::with_capacity(filter_weights.len());
+            for filter_weight_opt in &filter_weights {
+                filters.push(match *filter_weight_opt {
+                    Some(ref filter_weight) => {
+                        Some(FilterCursor::new(filter_weight.scorer(segment_reader)?))
+                    }
+                    None => None,
+                });
+            }
+            let mut base_scorer = base_weight.scorer(segment_reader)?;
+            while base_scorer.advance() {
+                let doc = base_scorer.doc();
+                let mut num_failed = 0;
+                let mut failed_dimension = 0;
+                for (dimension, filter_opt) in filters.iter_mut().enumerate() {
+                    if let Some(ref mut filter) = *filter_opt {
+                        if !filter.contains(doc) {
+                            num_failed += 1;
+                            failed_dimension = dimension;
+                            if num_failed > 1 {
+                                break;
+                            }
+                        }
+                    }
+                }
+                match num_failed {
+                    0 => {
+                        let score = base_scorer.score();
+                        collector.collect(doc, score);
+                        for &mut (_, ref mut dimension_collector) in &mut self.dimensions {
+                            dimension_collector.collect(doc, score);
+                        }
+                    }
+                    1 => {
+                        let score = base_scorer.score();
+                        self.dimensions[failed_dimension].1.collect(doc, score);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks whether an increasing sequence of documents
+/// belong to a filter scorer.
+struct FilterCursor {
+    scorer: Box<Scorer>,
+    started: bool,
+    exhausted: bool,
+}
+
+impl FilterCursor {
+    fn new(scorer: Box<Scorer>) -> FilterCursor {
+        FilterCursor {
+            scorer,
+            started: false,
+            exhausted: false,
+        }
+    }
+
+    fn contains(&mut self, doc: DocId) -> bool {
+        if self.exhausted {
+            return false;
+        }
+        if !self.started {
+            self.started = true;
+            if !self.scorer.advance() {
+                self.exhausted = true;
+                return false;
+            }
+        }
+        if self.scorer.doc() >= doc {
+            return self.scorer.doc() == doc;
+        }
+        match self.scorer.skip_next(doc) {
+            SkipResult::Reached => true,
+            SkipResult::OverStep => false,
+            SkipResult::End => {
+                self.exhausted = true;
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::DrillSideways;
+    use collector::{FacetCollector, TopCollector};
+    use query::{Query, TermQuery};
+    use schema::{Facet, IndexRecordOption, SchemaBuilder, Term, TEXT};
+    use Index;
+
+    #[test]
+    fn test_drill_sideways() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let brand = schema_builder.add_facet_field("brand");
+        let color = schema_builder.add_facet_field("color");
+        let size = schema_builder.add_facet_field("size");
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            let docs = [
+                ("shoe", "/acme", "/red", "/s"),
+                ("shoe", "/acme", "/blue", "/m"),
+                ("shoe", "/ajax", "/red", "/m"),
+                ("shoe", "/ajax", "/blue", "/s"),
+                ("shoe", "/acme", "/red", "/m"),
+                ("hat", "/acme", "/red", "/s"),
+            ];
+            for &(text_val, brand_val, color_val, size_val) in &docs {
+                index_writer.add_document(doc!(
+                    text => text_val,
+                    brand => Facet::from(brand_val),
+                    color => Facet::from(color_val),
+                    size => Facet::from(size_val)
+                ));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let facet_query = |field, path: &str| -> Box<Query> {
+            box TermQuery::new(
+                Term::from_facet(field, &Facet::from(path)),
+                IndexRecordOption::Basic,
+            )
+        };
+        let base_query = TermQuery::new_text(text, "shoe");
+        let acme = facet_query(brand, "/acme");
+        let red = facet_query(color, "/red");
+        let facet_collector = |field| {
+            let mut facet_collector = FacetCollector::for_field(field);
+            facet_collector.add_facet("/");
+            facet_collector
+        };
+        let mut brand_counts = facet_collector(brand);
+        let mut color_counts = facet_collector(color);
+        let mut size_counts = facet_collector(size);
+        let mut top_collector = TopCollector::with_limit(10);
+        {
+            let mut drill_sideways = DrillSideways::new(&base_query);
+            drill_sideways.add_dimension(Some(&*acme), &mut brand_counts);
+            drill_sideways.add_dimension(Some(&*red), &mut color_counts);
+            drill_sideways.add_dimension(None, &mut size_counts);
+            drill_sideways
+                .search(&*searcher, &mut top_collector)
+                .unwrap();
+        }
+        let mut docs: Vec<u32> = top_collector
+            .docs()
+            .into_iter()
+            .map(|doc_address| doc_address.doc())
+            .collect();
+        docs.sort();
+        assert_eq!(docs, vec![0, 4]);
+        let counts = |facet_collector: FacetCollector| -> Vec<(String, u64)> {
+            facet_collector
+                .harvest()
+                .get("/")
+                .map(|(facet, count)| (facet.to_string(), count))
+                .collect()
+        };
+        // shoes that are red
+        assert_eq!(
+            counts(brand_counts),
+            vec![("/acme".to_string(), 2), ("/ajax".to_string(), 1)]
+        );
+        // shoes by acme
+        assert_eq!(
+            counts(color_counts),
+            vec![("/blue".to_string(), 1), ("/red".to_string(), 2)]
+        );
+        // red shoes by acme
+        assert_eq!(
+            counts(size_counts),
+            vec![("/m".to_string(), 1), ("/s".to_string(), 1)]
+        );
+    }
+}
@@ -15,6 +15,7 @@ use termdict::TermMerger;
 use docset::SkipResult;
 use std::{usize, u64};
 use std::iter::Peekable;
+use core::MemoryBudget;
 
 use DocId;
 use Result;
@@ -209,6 +210,8 @@ pub struct FacetCollector {
     current_collapse_facet_ords: Vec<u64>,
 
     facets: BTreeSet<Facet>,
+
+    memory_budget: MemoryBudget,
 }
 
 fn skip<'a, I: Iterator<Item = &'a Facet>>(
@@ -251,6 +254,8 @@ impl FacetCollector {
             current_segment_collapse_mapping: Vec::new(),
             current_collapse_facet_ords: Vec::new(),
             current_segment_counts: Vec::new(),
+
+            memory_budget: MemoryBudget::unlimited(),
         }
     }
 
@@ -393,9 +398,14 @@ impl Collector for FacetCollector {
     fn set_segment(&mut self, _: SegmentLocalId, reader: &SegmentReader) -> Result<()> {
         self.finalize_segment();
         let facet_reader = reader.facet_reader(self.field)?;
+        let num_facets = facet_reader.facet_dict().num_terms();
+        self.memory_budget
+            .allocate(num_facets * mem::size_of::<usize>())?;
         self.set_collapse_mapping(&facet_reader);
-        self.current_segment_counts
-            .resize(self.current_collapse_facet_ords.len(), 0);
+        let num_collapsed_facets = self.current_collapse_facet_ords.len();
+        self.memory_budget
+            .allocate(num_collapsed_facets * 2 * mem::size_of::<u64>())?;
+        self.current_segment_counts.resize(num_collapsed_facets, 0);
         self.ff_reader = Some(UnsafeCell::new(facet_reader));
         Ok(())
     }
@@ -424,6 +434,11 @@ impl Collector for FacetCollector {
     fn requires_scoring(&self) -> bool {
         false
     }
+
+    fn set_memory_budget(&mut self, memory_budget: &MemoryBudget) -> Result<()> {
+        self.memory_budget = memory_budget.clone();
+        Ok(())
+    }
 }
 
 /// Intermediary result of the `FacetCollector` that stores
@@ -0,0 +1,203 @@
+use super::{Collector, MergeableCollector};
+use core::MemoryBudget;
+use fastfield::{FastFieldReader, FastValue};
+use schema::Field;
+use DocId;
+use Result;
+use Score;
+use SegmentLocalId;
+use SegmentReader;
+
+/// The `FilterCollector` wraps a collector, and only forwards to it
+/// the documents whose fast field value passes a given predicate.
+///
+/// It makes it possible to post-filter the documents matching a query
+/// on a `u64` or `i64` fast field, without modifying the query.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::schema::{SchemaBuilder, FAST, TEXT};
+/// use tantivy::{Index, Result};
+/// use tantivy::collector::{CountCollector, FilterCollector};
+/// use tantivy::query::QueryParser;
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let title = schema_builder.add_text_field("title", TEXT);
+///     let price = schema_builder.add_u64_field("price", FAST);
+///     let index = Index::create_in_ram(schema_builder.build());
+///     {
+///         let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+///         index_writer.add_document(doc!(title => "The Diary of Muadib", price => 120u64));
+///         index_writer.add_document(doc!(title => "A Dairy Cow", price => 30u64));
+///         index_writer.add_document(doc!(title => "The Diary of a Young Girl", price => 15u64));
+///         index_writer.commit()?;
+///     }
+///     index.load_searchers()?;
+///     let searcher = index.searcher();
+///
+///     let query_parser = QueryParser::for_index(&index, vec![title]);
+///     let query = query_parser.parse_query("diary")?;
+///     let mut count_collector = CountCollector::default();
+///     {
+///         let mut filter_collector =
+///             FilterCollector::new(&mut count_collector, price, |price: u64| price < 100);
+///         searcher.search(&*query, &mut filter_collector)?;
+///     }
+///     assert_eq!(count_collector.count(), 1);
+///     Ok(())
+/// }
+/// ```
+pub struct FilterCollector<C, T, F>
+where
+    C: Collector,
+    T: FastValue,
+    F: Fn(T) -> bool,
+{
+    collector: C,
+    field: Field,
+    predicate: F,
+    ff_reader: Option<FastFieldReader<T>>,
+}
+
+impl<C, T, F> FilterCollector<C, T, F>
+where
+    C: Collector,
+    T: FastValue,
+    F: Fn(T) -> bool,
+{
+    /// Creates a `FilterCollector` forwarding to `collector` the documents
+    /// for which `predicate` returns true, given the value of the fast field `field`.
+    pub fn new(collector: C, field: Field, predicate: F) -> FilterCollector<C, T, F> {
+        FilterCollector {
+            collector,
+            field,
+            predicate,
+            ff_reader: None,
+        }
+    }
+
+    /// Accessor to the wrapped collector.
+    pub fn collector(&self) -> &C {
+        &self.collector
+    }
+
+    /// Returns the wrapped collector.
+    pub fn into_inner(self) -> C {
+        self.collector
+    }
+}
+
+impl<C, T, F> Collector for FilterCollector<C, T, F>
+where
+    C: Collector,
+    T: FastValue,
+    F: Fn(T) -> bool,
+{
+    fn set_segment(&mut self, segment_id: SegmentLocalId, reader: &SegmentReader) -> Result<()> {
+        self.ff_reader = Some(reader.fast_field_reader(self.field)?);
+        self.collector.set_segment(segment_id, reader)
+    }
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        let val = self.ff_reader
+            .as_ref()
+            .expect("collect() was called before set_segment. This should never happen.")
+            .get(doc);
+        if (self.predicate)(val) {
+            self.collector.collect(doc, score);
+        }
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.collector.requires_scoring()
+    }
+
+    fn set_memory_budget(&mut self, memory_budget: &MemoryBudget) -> Result<()> {
+        self.collector.set_memory_budget(memory_budget)
+    }
+
+    fn min_competitive_score(&self) -> Option<Score> {
+        self.collector.min_competitive_score()
+    }
+}
+
+impl<C, T, F> MergeableCollector for FilterCollector<C, T, F>
+where
+    C: MergeableCollector,
+    T: FastValue + Send,
+    F: Fn(T) -> bool + Clone + Send,
+{
+    fn split(&self) -> FilterCollector<C, T, F> {
+        FilterCollector::new(self.collector.split(), self.field, self.predicate.clone())
+    }
+
+    fn merge(&mut self, other: FilterCollector<C, T, F>) {
+        self.collector.merge(other.collector);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::FilterCollector;
+    use collector::{CountCollector, TopCollector};
+    use query::AllQuery;
+    use schema::{SchemaBuilder, FAST};
+    use Index;
+
+    #[test]
+    fn test_filter_collector() {
+        let mut schema_builder = SchemaBuilder::new();
+        let price = schema_builder.add_i64_field("price", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in -50i64..150i64 {
+                index_writer.add_document(doc!(price => i));
+                if i % 60 == 0 {
+                    index_writer.commit().unwrap();
+                }
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+
+        let mut count_collector = CountCollector::default();
+        {
+            let mut filter_collector =
+                FilterCollector::new(&mut count_collector, price, |price: i64| price < 100);
+            searcher.search(&AllQuery, &mut filter_collector).unwrap();
+        }
+        assert_eq!(count_collector.count(), 150);
+
+        let mut filter_collector = FilterCollector::new(
+            CountCollector::default(),
+            price,
+            |price: i64| price >= 0 && price < 10,
+        );
+        searcher
+            .search_parallel(&AllQuery, &mut filter_collector, 3)
+            .unwrap();
+        assert_eq!(filter_collector.collector().count(), 10);
+        assert_eq!(filter_collector.into_inner().count(), 10);
+
+        let mut top_collector = TopCollector::with_limit(100);
+        {
+            let mut filter_collector =
+                FilterCollector::new(&mut top_collector, price, |price: i64| price % 2 == 0);
+            searcher.search(&AllQuery, &mut filter_collector).unwrap();
+        }
+        assert_eq!(top_collector.docs().len(), 100);
+        for doc_address in top_collector.docs() {
+            let price_reader = searcher
+                .segment_reader(doc_address.segment_ord())
+                .fast_field_reader::<i64>(price)
+                .unwrap();
+            assert_eq!(price_reader.get(doc_address.doc()) % 2, 0);
+        }
+    }
+}
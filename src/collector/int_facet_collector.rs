@@ -1,76 +1,117 @@
-use std::cmp::Eq;
 use std::collections::HashMap;
 use std::hash::Hash;
 
-use collector::Collector;
-use fastfield::FastFieldReader;
+use collector::{Collector, MergeableCollector};
+use fastfield::{FastFieldReader, FastValue};
 use schema::Field;
 
 use DocId;
 use Result;
 use Score;
-use SegmentReader;
 use SegmentLocalId;
+use SegmentReader;
 
-
-/// Facet collector  for i64/u64 fast field
+/// Facet collector for `u64` or `i64` fast fields.
+///
+/// It counts the matching documents for each of the values of the field.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::schema::{SchemaBuilder, FAST};
+/// use tantivy::collector::IntFacetCollector;
+/// use tantivy::query::AllQuery;
+/// use tantivy::Index;
+///
+/// # fn main() {
+/// let mut schema_builder = SchemaBuilder::default();
+/// let rating = schema_builder.add_i64_field("rating", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// {
+///     let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+///     index_writer.add_document(doc!(rating => -1i64));
+///     index_writer.add_document(doc!(rating => 2i64));
+///     index_writer.add_document(doc!(rating => -1i64));
+///     index_writer.commit().unwrap();
+/// }
+/// index.load_searchers().unwrap();
+/// let searcher = index.searcher();
+/// let mut facet_collector = IntFacetCollector::<i64>::new(rating);
+/// searcher.search(&AllQuery, &mut facet_collector).unwrap();
+/// assert_eq!(facet_collector.counts()[&-1], 2);
+/// assert_eq!(facet_collector.counts()[&2], 1);
+/// # }
+/// ```
 pub struct IntFacetCollector<T>
 where
-    T: FastFieldReader,
-    T::ValueType: Eq + Hash,
+    T: FastValue + Eq + Hash,
 {
-    counters: HashMap<T::ValueType, u64>,
+    counters: HashMap<T, u64>,
     field: Field,
-    ff_reader: Option<T>,
+    ff_reader: Option<FastFieldReader<T>>,
 }
 
-
 impl<T> IntFacetCollector<T>
 where
-    T: FastFieldReader,
-    T::ValueType: Eq + Hash,
+    T: FastValue + Eq + Hash,
 {
     /// Creates a new facet collector for aggregating a given field.
     pub fn new(field: Field) -> IntFacetCollector<T> {
         IntFacetCollector {
             counters: HashMap::new(),
-            field: field,
+            field,
             ff_reader: None,
         }
     }
-}
 
+    /// Returns the number of matching documents for each value of the field.
+    pub fn counts(&self) -> &HashMap<T, u64> {
+        &self.counters
+    }
+}
 
 impl<T> Collector for IntFacetCollector<T>
 where
-    T: FastFieldReader,
-    T::ValueType: Eq + Hash,
+    T: FastValue + Eq + Hash,
 {
     fn set_segment(&mut self, _: SegmentLocalId, reader: &SegmentReader) -> Result<()> {
-        self.ff_reader = Some(reader.get_fast_field_reader(self.field)?);
+        self.ff_reader = Some(reader.fast_field_reader(self.field)?);
         Ok(())
     }
 
     fn collect(&mut self, doc: DocId, _: Score) {
         let val = self.ff_reader
             .as_ref()
-            .expect(
-                "collect() was called before set_segment. \
-                This should never happen.",
-            )
+            .expect("collect() was called before set_segment. This should never happen.")
             .get(doc);
         *(self.counters.entry(val).or_insert(0)) += 1;
     }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
 }
 
+impl<T> MergeableCollector for IntFacetCollector<T>
+where
+    T: FastValue + Eq + Hash + Send,
+{
+    fn split(&self) -> IntFacetCollector<T> {
+        IntFacetCollector::new(self.field)
+    }
 
+    fn merge(&mut self, other: IntFacetCollector<T>) {
+        for (val, count) in other.counters {
+            *(self.counters.entry(val).or_insert(0)) += count;
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
 
     use collector::{chain, IntFacetCollector};
-    use query::QueryParser;
-    use fastfield::{I64FastFieldReader, U64FastFieldReader};
+    use query::{Query, QueryParser};
     use schema::{self, FAST, STRING};
     use Index;
 
@@ -78,7 +119,6 @@ mod tests {
     // create 10 documents, set num field value to 0 or 1 for even/odd ones
     // make sure we have facet counters correctly filled
     fn test_facet_collector_results() {
-
         let mut schema_builder = schema::SchemaBuilder::new();
         let num_field_i64 = schema_builder.add_i64_field("num_i64", FAST);
         let num_field_u64 = schema_builder.add_u64_field("num_u64", FAST);
@@ -103,21 +143,57 @@ mod tests {
 
         index.load_searchers().unwrap();
         let searcher = index.searcher();
-        let mut ffvf_i64: IntFacetCollector<I64FastFieldReader> = IntFacetCollector::new(num_field_i64);
-        let mut ffvf_u64: IntFacetCollector<U64FastFieldReader> = IntFacetCollector::new(num_field_u64);
+        let mut ffvf_i64: IntFacetCollector<i64> = IntFacetCollector::new(num_field_i64);
+        let mut ffvf_u64: IntFacetCollector<u64> = IntFacetCollector::new(num_field_u64);
 
         {
             // perform the query
             let mut facet_collectors = chain().push(&mut ffvf_i64).push(&mut ffvf_u64);
-            let mut query_parser = QueryParser::for_index(index, vec![text_field]);
+            let query_parser = QueryParser::for_index(&index, vec![text_field]);
             let query = query_parser.parse_query("text:text").unwrap();
-            query.search(&searcher, &mut facet_collectors).unwrap();
+            query.search(&*searcher, &mut facet_collectors).unwrap();
         }
 
-        assert_eq!(ffvf_u64.counters[&0], 5);
-        assert_eq!(ffvf_u64.counters[&1], 5);
-        assert_eq!(ffvf_i64.counters[&0], 4);
-        assert_eq!(ffvf_i64.counters[&1], 3);
+        assert_eq!(ffvf_u64.counts()[&0], 5);
+        assert_eq!(ffvf_u64.counts()[&1], 5);
+        assert_eq!(ffvf_i64.counts()[&0], 4);
+        assert_eq!(ffvf_i64.counts()[&1], 3);
+    }
 
+    #[test]
+    // negative values are mapped back from the u64 fast field values,
+    // and the counters of the parallel collectors are merged.
+    fn test_facet_collector_negative_values_parallel() {
+        let mut schema_builder = schema::SchemaBuilder::new();
+        let num_field_i64 = schema_builder.add_i64_field("num_i64", FAST);
+        let text_field = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0i64..10i64 {
+                index_writer.add_document(doc!(
+                    num_field_i64 => i % 3i64 - 1i64,
+                    text_field => "text"
+                ));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query = QueryParser::for_index(&index, vec![text_field])
+            .parse_query("text:text")
+            .unwrap();
+
+        let mut ffvf_i64: IntFacetCollector<i64> = IntFacetCollector::new(num_field_i64);
+        query.search(&*searcher, &mut ffvf_i64).unwrap();
+        assert_eq!(ffvf_i64.counts()[&-1], 4);
+        assert_eq!(ffvf_i64.counts()[&0], 3);
+        assert_eq!(ffvf_i64.counts()[&1], 3);
+
+        let mut parallel_collector: IntFacetCollector<i64> = IntFacetCollector::new(num_field_i64);
+        searcher
+            .search_parallel(&*query, &mut parallel_collector, 2)
+            .unwrap();
+        assert_eq!(parallel_collector.counts(), ffvf_i64.counts());
     }
 }
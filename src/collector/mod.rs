@@ -7,6 +7,7 @@ use SegmentLocalId;
 use DocId;
 use Score;
 use Result;
+use core::MemoryBudget;
 
 mod count_collector;
 pub use self::count_collector::CountCollector;
@@ -15,14 +16,28 @@ mod multi_collector;
 pub use self::multi_collector::MultiCollector;
 
 mod top_collector;
-pub use self::top_collector::TopCollector;
+pub use self::top_collector::{TopCollector, TotalHits};
+
+mod top_field_collector;
+pub use self::top_field_collector::{SortOrder, TopFieldCollector};
 
 mod facet_collector;
 pub use self::facet_collector::FacetCollector;
 
+mod int_facet_collector;
+pub use self::int_facet_collector::IntFacetCollector;
+
+mod drill_sideways;
+pub use self::drill_sideways::DrillSideways;
+
 mod chained_collector;
 pub use self::chained_collector::chain;
 
+mod filter_collector;
+pub use self::filter_collector::FilterCollector;
+
+pub mod aggregation;
+
 /// Collectors are in charge of collecting and retaining relevant
 /// information from the document found and scored by the query.
 ///
@@ -65,6 +80,43 @@ pub trait Collector {
 
     /// Returns true iff the collector requires to compute scores for documents.
     fn requires_scoring(&self) -> bool;
+
+    /// Informs the collector of the memory budget of the search.
+    ///
+    /// It is called once, before the first call to `.set_segment(...)`.
+    /// Collectors whose memory grows with the size of the segments
+    /// are expected to reserve it against the budget in `.set_segment(...)`.
+    fn set_memory_budget(&mut self, _memory_budget: &MemoryBudget) -> Result<()> {
+        Ok(())
+    }
+
+    /// Returns a score such that the documents scoring strictly lower
+    /// than it would be ignored by the collector, if any.
+    ///
+    /// Scorers may use it as a hint to skip the documents that cannot
+    /// make it to the collector. It is only ever expected to increase.
+    fn min_competitive_score(&self) -> Option<Score> {
+        None
+    }
+}
+
+/// Collectors that can be split into several independent collectors
+/// and merged back together.
+///
+/// This makes it possible for the searcher to process different
+/// segments, or different ranges of documents within a segment,
+/// in parallel. (See `Searcher::search_parallel`)
+///
+/// Each split collector receives its own calls to `.set_segment(...)` and `.collect(...)`.
+/// The split collectors are eventually merged back in the original collector,
+/// following the order of the document ranges they processed.
+pub trait MergeableCollector: Collector + Send + Sized {
+    /// Returns a new, empty collector, with the same configuration.
+    fn split(&self) -> Self;
+
+    /// Merges the documents collected by a collector obtained
+    /// via `.split()`.
+    fn merge(&mut self, other: Self);
 }
 
 impl<'a, C: Collector> Collector for &'a mut C {
@@ -83,6 +135,14 @@ impl<'a, C: Collector> Collector for &'a mut C {
     fn requires_scoring(&self) -> bool {
         C::requires_scoring(self)
     }
+
+    fn set_memory_budget(&mut self, memory_budget: &MemoryBudget) -> Result<()> {
+        C::set_memory_budget(self, memory_budget)
+    }
+
+    fn min_competitive_score(&self) -> Option<Score> {
+        C::min_competitive_score(self)
+    }
 }
 
 #[cfg(test)]
@@ -185,7 +245,7 @@ pub mod tests {
             let mut count_collector = CountCollector::default();
             let docs: Vec<u32> = (0..1_000_000).collect();
             for doc in docs {
-                count_collector.collect(doc, 1f32);
+                count_collector.collect(doc, 1.0);
             }
             count_collector.count()
         });
@@ -4,6 +4,7 @@ use Score;
 use Result;
 use SegmentReader;
 use SegmentLocalId;
+use core::MemoryBudget;
 
 /// Multicollector makes it possible to collect on more than one collector.
 /// It should only be used for use cases where the Collector types is unknown
@@ -42,6 +43,13 @@ impl<'a> Collector for MultiCollector<'a> {
             .iter()
             .any(|collector| collector.requires_scoring())
     }
+
+    fn set_memory_budget(&mut self, memory_budget: &MemoryBudget) -> Result<()> {
+        for collector in &mut self.collectors {
+            collector.set_memory_budget(memory_budget)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -1,10 +1,16 @@
-use super::Collector;
+use super::{Collector, MergeableCollector};
 use SegmentReader;
 use SegmentLocalId;
 use DocAddress;
 use Result;
 use std::collections::BinaryHeap;
-use std::cmp::Ordering;
+use std::cmp::{self, Ordering};
+use std::mem;
+use core::MemoryBudget;
+use core::Searcher;
+use docset::{DocSet, SkipResult};
+use query::{BooleanQuery, Occur, Weight};
+use itertools::Itertools;
 use DocId;
 use Score;
 
@@ -24,10 +30,13 @@ impl PartialOrd for GlobalScoredDoc {
 impl Ord for GlobalScoredDoc {
     #[inline]
     fn cmp(&self, other: &GlobalScoredDoc) -> Ordering {
+        // Ties are broken by the doc address, so that the order
+        // of the results does not depend on the order of insertion.
         other
             .score
             .partial_cmp(&self.score)
-            .unwrap_or_else(|| other.doc_address.cmp(&self.doc_address))
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| self.doc_address.cmp(&other.doc_address))
     }
 }
 
@@ -39,15 +48,62 @@ impl PartialEq for GlobalScoredDoc {
 
 impl Eq for GlobalScoredDoc {}
 
+/// Number of documents matching a query, as counted by a `TopCollector`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TotalHits {
+    /// Exact number of matching documents.
+    Exact(usize),
+    /// Lower bound of the number of matching documents.
+    ///
+    /// Some of the documents may have been skipped by the scorer,
+    /// as they could not make it to the top K.
+    AtLeast(usize),
+}
+
+impl TotalHits {
+    /// Returns the number of hits counted, which is a lower bound
+    /// of the number of matching documents for `TotalHits::AtLeast`.
+    pub fn count(&self) -> usize {
+        match *self {
+            TotalHits::Exact(count) | TotalHits::AtLeast(count) => count,
+        }
+    }
+
+    /// Returns true iff the count is only a lower bound.
+    pub fn is_lower_bound(&self) -> bool {
+        match *self {
+            TotalHits::Exact(_) => false,
+            TotalHits::AtLeast(_) => true,
+        }
+    }
+}
+
 /// The Top Collector keeps track of the K documents
 /// with the best scores.
 ///
 /// The implementation is based on a `BinaryHeap`.
 /// The theorical complexity is `O(n log K)`.
+///
+/// The heap grows with the number of documents collected, up to K.
+/// Its memory is reserved against the memory budget of the search,
+/// assuming that all of the documents of each segment may be collected.
+///
+/// The collector also counts the documents it receives.
+/// By default, as soon as K documents have been collected, it lets the scorer
+/// skip the documents that cannot make it to the top K
+/// (See `Collector::min_competitive_score`), so that the count is only
+/// a lower bound of the number of hits.
+/// `.track_total_hits_up_to(n)` makes it count the hits exactly,
+/// up to `n` of them, before allowing such skipping.
 pub struct TopCollector {
     limit: usize,
+    track_total_hits_up_to: usize,
+    num_hits: usize,
     heap: BinaryHeap<GlobalScoredDoc>,
     segment_id: u32,
+    memory_budget: MemoryBudget,
+    // number of scored docs reserved against the memory budget.
+    reserved_capacity: usize,
 }
 
 impl TopCollector {
@@ -61,8 +117,37 @@ impl TopCollector {
         }
         TopCollector {
             limit: limit,
-            heap: BinaryHeap::with_capacity(limit),
+            track_total_hits_up_to: 0,
+            num_hits: 0,
+            heap: BinaryHeap::new(),
             segment_id: 0,
+            memory_budget: MemoryBudget::unlimited(),
+            reserved_capacity: 0,
+        }
+    }
+
+    /// Counts the hits exactly, up to `threshold` of them.
+    ///
+    /// Beyond `threshold` hits, the scorer is allowed to skip the
+    /// documents that cannot make it to the top K, and `.total_hits()`
+    /// only returns a lower bound of the number of hits.
+    /// This avoids exhaustively evaluating queries with many hits, when
+    /// knowing that there are more than `threshold` of them is enough.
+    ///
+    /// `usize::MAX` makes the count always exact.
+    pub fn track_total_hits_up_to(&mut self, threshold: usize) {
+        self.track_total_hits_up_to = threshold;
+    }
+
+    /// Returns the number of documents matching the query.
+    ///
+    /// The count is exact up to the threshold set via
+    /// `.track_total_hits_up_to(...)`, and may be a lower bound beyond it.
+    pub fn total_hits(&self) -> TotalHits {
+        if self.skips_non_competitive() {
+            TotalHits::AtLeast(self.num_hits)
+        } else {
+            TotalHits::Exact(self.num_hits)
         }
     }
 
@@ -90,45 +175,184 @@ impl TopCollector {
             .collect()
     }
 
+    /// Returns K best ScoredDocument sorted in decreasing order, with the
+    /// contribution of each of the clauses of `query` to their score.
+    ///
+    /// `query` is expected to be the query the documents were collected with.
+    /// The `i`-th element of the vector associated to a document is the
+    /// score of the `i`-th clause of `query` (See `BooleanQuery::clauses`)
+    /// for this document, or `0` if the document does not match
+    /// this clause. `MustNot` and `Filter` clauses never contribute to the score.
+    ///
+    /// This is a lot cheaper than explaining each of the documents,
+    /// as each clause is only scored once per segment and per document.
+    /// Note that, as the score of a boolean query may include a coordination factor,
+    /// the contributions of the clauses do not necessarily sum up to the score.
+    pub fn clause_score_docs(
+        &self,
+        searcher: &Searcher,
+        query: &BooleanQuery,
+    ) -> Result<Vec<(Score, DocAddress, Vec<Score>)>> {
+        let clause_weights: Vec<Option<Box<Weight>>> = query
+            .clauses()
+            .iter()
+            .map(|&(occur, ref subquery)| match occur {
+                Occur::Must | Occur::Should => subquery.weight(searcher, true).map(Some),
+                Occur::MustNot | Occur::Filter => Ok(None),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let score_docs = self.score_docs();
+        let mut clause_scores = vec![vec![]; score_docs.len()];
+        let mut hit_ords: Vec<usize> = (0..score_docs.len()).collect();
+        hit_ords.sort_by_key(|&hit_ord| score_docs[hit_ord].1);
+        for (segment_ord, segment_hit_ords) in &hit_ords
+            .into_iter()
+            .group_by(|&hit_ord| score_docs[hit_ord].1.segment_ord())
+        {
+            let segment_hit_ords: Vec<usize> = segment_hit_ords.collect();
+            let docs: Vec<DocId> = segment_hit_ords
+                .iter()
+                .map(|&hit_ord| score_docs[hit_ord].1.doc())
+                .collect();
+            let segment_reader = searcher.segment_reader(segment_ord);
+            let segment_scores = score_clauses(segment_reader, &clause_weights, &docs)?;
+            for (hit_ord, doc_scores) in segment_hit_ords.into_iter().zip(segment_scores) {
+                clause_scores[hit_ord] = doc_scores;
+            }
+        }
+        Ok(score_docs
+            .into_iter()
+            .zip(clause_scores)
+            .map(|((score, doc_address), clause_scores)| (score, doc_address, clause_scores))
+            .collect())
+    }
+
     /// Return true iff at least K documents have gone through
     /// the collector.
     #[inline]
     pub fn at_capacity(&self) -> bool {
         self.heap.len() >= self.limit
     }
+
+    // True iff the scorer may have been allowed to skip documents.
+    fn skips_non_competitive(&self) -> bool {
+        self.at_capacity() && self.num_hits >= self.track_total_hits_up_to
+    }
+}
+
+/// Returns, for each of the sorted `docs`, the score of each of the clauses.
+///
+/// Clauses without any weight do not contribute to the score.
+fn score_clauses(
+    segment_reader: &SegmentReader,
+    clause_weights: &[Option<Box<Weight>>],
+    docs: &[DocId],
+) -> Result<Vec<Vec<Score>>> {
+    let mut clause_scores = vec![vec![0.0; clause_weights.len()]; docs.len()];
+    for (clause_ord, clause_weight_opt) in clause_weights.iter().enumerate() {
+        let mut scorer = match *clause_weight_opt {
+            Some(ref clause_weight) => clause_weight.scorer(segment_reader)?,
+            None => continue,
+        };
+        // `None` until the scorer is advanced for the first time.
+        let mut current_doc: Option<DocId> = None;
+        for (doc_ord, &doc) in docs.iter().enumerate() {
+            let reached = match current_doc {
+                Some(current_doc) if current_doc >= doc => current_doc == doc,
+                _ => match scorer.skip_next(doc) {
+                    SkipResult::Reached => {
+                        current_doc = Some(doc);
+                        true
+                    }
+                    SkipResult::OverStep => {
+                        current_doc = Some(scorer.doc());
+                        false
+                    }
+                    SkipResult::End => break,
+                },
+            };
+            if reached {
+                clause_scores[doc_ord][clause_ord] = scorer.score();
+            }
+        }
+    }
+    Ok(clause_scores)
 }
 
 impl Collector for TopCollector {
-    fn set_segment(&mut self, segment_id: SegmentLocalId, _: &SegmentReader) -> Result<()> {
+    fn set_segment(&mut self, segment_id: SegmentLocalId, reader: &SegmentReader) -> Result<()> {
         self.segment_id = segment_id;
+        let capacity = cmp::min(self.limit, self.heap.len() + reader.max_doc() as usize);
+        if capacity > self.reserved_capacity {
+            let num_bytes = (capacity - self.reserved_capacity) * mem::size_of::<GlobalScoredDoc>();
+            self.memory_budget.allocate(num_bytes)?;
+            self.reserved_capacity = capacity;
+        }
         Ok(())
     }
 
     fn collect(&mut self, doc: DocId, score: Score) {
+        let doc_address = DocAddress(self.segment_id, doc);
+        self.num_hits += 1;
+        self.insert(score, doc_address);
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn set_memory_budget(&mut self, memory_budget: &MemoryBudget) -> Result<()> {
+        self.memory_budget = memory_budget.clone();
+        Ok(())
+    }
+
+    fn min_competitive_score(&self) -> Option<Score> {
+        if self.skips_non_competitive() {
+            self.heap.peek().map(|scored_doc| scored_doc.score)
+        } else {
+            None
+        }
+    }
+}
+
+impl MergeableCollector for TopCollector {
+    fn split(&self) -> TopCollector {
+        let mut top_collector = TopCollector::with_limit(self.limit);
+        top_collector.track_total_hits_up_to(self.track_total_hits_up_to);
+        top_collector
+    }
+
+    fn merge(&mut self, other: TopCollector) {
+        self.num_hits += other.num_hits;
+        for scored_doc in other.heap.into_vec() {
+            self.insert(scored_doc.score, scored_doc.doc_address);
+        }
+    }
+}
+
+impl TopCollector {
+    fn insert(&mut self, score: Score, doc_address: DocAddress) {
+        let wrapped_doc = GlobalScoredDoc {
+            score: score,
+            doc_address: doc_address,
+        };
         if self.at_capacity() {
             // It's ok to unwrap as long as a limit of 0 is forbidden.
             let limit_doc: GlobalScoredDoc = *self.heap
                 .peek()
                 .expect("Top collector with size 0 is forbidden");
-            if limit_doc.score < score {
+            // Ties are broken by doc address, so that the result does not
+            // depend on the order in which the documents are inserted.
+            if wrapped_doc < limit_doc {
                 let mut mut_head = self.heap
                     .peek_mut()
                     .expect("Top collector with size 0 is forbidden");
-                mut_head.score = score;
-                mut_head.doc_address = DocAddress(self.segment_id, doc);
+                *mut_head = wrapped_doc;
             }
         } else {
-            let wrapped_doc = GlobalScoredDoc {
-                score: score,
-                doc_address: DocAddress(self.segment_id, doc),
-            };
             self.heap.push(wrapped_doc);
         }
     }
-
-    fn requires_scoring(&self) -> bool {
-        true
-    }
 }
 
 #[cfg(test)]
@@ -138,6 +362,73 @@ mod tests {
     use DocId;
     use Score;
     use collector::Collector;
+    use query::{Query, TermQuery};
+    use schema::{SchemaBuilder, TEXT};
+    use Index;
+
+    #[test]
+    fn test_top_collector_clause_score_docs() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a b"));
+            index_writer.add_document(doc!(text_field => "a c"));
+            index_writer.commit().unwrap();
+            index_writer.add_document(doc!(text_field => "b c"));
+            index_writer.add_document(doc!(text_field => "a b c"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query = BooleanQuery::builder()
+            .should(TermQuery::new_text(text_field, "a"))
+            .should(TermQuery::new_text(text_field, "b"))
+            .must_not(TermQuery::new_text(text_field, "c"))
+            .filter(TermQuery::new_text(text_field, "a"))
+            .build();
+        let mut top_collector = TopCollector::with_limit(10);
+        searcher.search(&query, &mut top_collector).unwrap();
+        let clause_score_docs = top_collector.clause_score_docs(&*searcher, &query).unwrap();
+        assert_eq!(clause_score_docs.len(), 1);
+        let (score, doc_address, ref clause_scores) = clause_score_docs[0];
+        assert_eq!(doc_address, DocAddress(0, 0));
+        let a_score = TermQuery::new_text(text_field, "a")
+            .explain(&*searcher, &doc_address)
+            .unwrap()
+            .value();
+        let b_score = TermQuery::new_text(text_field, "b")
+            .explain(&*searcher, &doc_address)
+            .unwrap()
+            .value();
+        assert_eq!(clause_scores, &vec![a_score, b_score, 0.0, 0.0]);
+        assert!((a_score + b_score - score).abs() < 0.0001);
+
+        let query = BooleanQuery::builder()
+            .should(TermQuery::new_text(text_field, "a"))
+            .should(TermQuery::new_text(text_field, "c"))
+            .build();
+        let mut top_collector = TopCollector::with_limit(10);
+        searcher.search(&query, &mut top_collector).unwrap();
+        let clause_score_docs = top_collector.clause_score_docs(&*searcher, &query).unwrap();
+        let score_docs = top_collector.score_docs();
+        assert_eq!(clause_score_docs.len(), 4);
+        for (&(score, doc_address, ref clause_scores), &(expected_score, expected_doc_address)) in
+            clause_score_docs.iter().zip(score_docs.iter())
+        {
+            assert_eq!(score, expected_score);
+            assert_eq!(doc_address, expected_doc_address);
+            // "a b", "a c", "b c" and "a b c"
+            let expected_matching_clauses = match doc_address {
+                DocAddress(0, 0) => vec![true, false],
+                DocAddress(0, 1) | DocAddress(1, 1) => vec![true, true],
+                _ => vec![false, true],
+            };
+            let matching_clauses: Vec<bool> = clause_scores.iter().map(|&s| s > 0.0).collect();
+            assert_eq!(matching_clauses, expected_matching_clauses);
+        }
+    }
 
     #[test]
     fn test_top_collector_not_at_capacity() {
@@ -181,6 +472,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_top_collector_ties() {
+        let mut top_collector = TopCollector::with_limit(2);
+        top_collector.collect(1, 0.5);
+        top_collector.collect(3, 0.5);
+        top_collector.collect(5, 0.5);
+        let docs: Vec<DocId> = top_collector
+            .docs()
+            .into_iter()
+            .map(|doc_address| doc_address.doc())
+            .collect();
+        assert_eq!(docs, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_top_collector_memory_budget() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for _ in 0..10 {
+                index_writer.add_document(doc!(text_field => "a"));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query = TermQuery::new_text(text_field, "a");
+        let scored_doc_size = mem::size_of::<GlobalScoredDoc>();
+
+        // Only the number of documents of the segment is reserved.
+        let memory_budget = MemoryBudget::unlimited();
+        let mut top_collector = TopCollector::with_limit(1_000_000);
+        searcher
+            .with_memory_budget(memory_budget.clone())
+            .search(&query, &mut top_collector)
+            .unwrap();
+        assert_eq!(top_collector.docs().len(), 10);
+        assert_eq!(memory_budget.allocated_bytes(), 10 * scored_doc_size);
+
+        let mut top_collector = TopCollector::with_limit(3);
+        assert!(
+            searcher
+                .with_memory_budget(MemoryBudget::with_limit(3 * scored_doc_size))
+                .search(&query, &mut top_collector)
+                .is_ok()
+        );
+        let mut top_collector = TopCollector::with_limit(4);
+        assert!(
+            searcher
+                .with_memory_budget(MemoryBudget::with_limit(3 * scored_doc_size))
+                .search(&query, &mut top_collector)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_top_collector_total_hits() {
+        let mut top_collector = TopCollector::with_limit(2);
+        top_collector.track_total_hits_up_to(3);
+        top_collector.collect(1, 0.8);
+        top_collector.collect(3, 0.2);
+        assert!(top_collector.at_capacity());
+        assert_eq!(top_collector.min_competitive_score(), None);
+        assert_eq!(top_collector.total_hits(), TotalHits::Exact(2));
+        top_collector.collect(5, 0.3);
+        assert_eq!(top_collector.min_competitive_score(), Some(0.3));
+        top_collector.collect(7, 0.9);
+        assert_eq!(top_collector.total_hits(), TotalHits::AtLeast(4));
+        assert_eq!(top_collector.total_hits().count(), 4);
+
+        let mut top_collector = TopCollector::with_limit(2);
+        top_collector.track_total_hits_up_to(usize::max_value());
+        for doc in 0..10 {
+            top_collector.collect(doc, doc as Score);
+        }
+        assert_eq!(top_collector.min_competitive_score(), None);
+        assert_eq!(top_collector.total_hits(), TotalHits::Exact(10));
+    }
+
+    #[test]
+    fn test_top_collector_total_hits_search() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0..1_000 {
+                if i % 3 == 0 {
+                    index_writer.add_document(doc!(text_field => "a b"));
+                } else {
+                    index_writer.add_document(doc!(text_field => "a"));
+                }
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query = BooleanQuery::builder()
+            .should(TermQuery::new_text(text_field, "a"))
+            .should(TermQuery::new_text(text_field, "b"))
+            .build();
+        let mut top_collector = TopCollector::with_limit(10);
+        top_collector.track_total_hits_up_to(usize::max_value());
+        searcher.search(&query, &mut top_collector).unwrap();
+        assert_eq!(top_collector.total_hits(), TotalHits::Exact(1_000));
+
+        let mut limited_top_collector = TopCollector::with_limit(10);
+        limited_top_collector.track_total_hits_up_to(100);
+        searcher.search(&query, &mut limited_top_collector).unwrap();
+        let total_hits = limited_top_collector.total_hits();
+        assert!(total_hits.is_lower_bound());
+        assert!(total_hits.count() >= 100);
+        assert!(total_hits.count() <= 1_000);
+        assert_eq!(limited_top_collector.score_docs(), top_collector.score_docs());
+    }
+
     #[test]
     #[should_panic]
     fn test_top_0() {
@@ -0,0 +1,448 @@
+use super::{Collector, MergeableCollector};
+use core::MemoryBudget;
+use fastfield::{FastFieldReader, FastValue};
+use schema::Field;
+use std::cmp::{self, Ordering};
+use std::collections::BinaryHeap;
+use std::mem;
+use DocAddress;
+use DocId;
+use Result;
+use Score;
+use SegmentLocalId;
+use SegmentReader;
+
+/// Order in which the `TopFieldCollector` sorts the documents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Smallest values first.
+    Ascending,
+    /// Largest values first.
+    Descending,
+}
+
+// Rust heap is a max-heap: the head of the heap is the worst document kept.
+#[derive(Clone, Copy)]
+struct GlobalFieldDoc {
+    // u64 representation of the fast field value,
+    // arranged so that larger keys rank first.
+    key: u64,
+    score: Score,
+    doc_address: DocAddress,
+}
+
+impl PartialOrd for GlobalFieldDoc {
+    fn partial_cmp(&self, other: &GlobalFieldDoc) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for GlobalFieldDoc {
+    #[inline]
+    fn cmp(&self, other: &GlobalFieldDoc) -> Ordering {
+        // Ties on the value are broken by the score, and then
+        // by the doc address, so that the order of the results
+        // does not depend on the order of insertion.
+        other
+            .key
+            .cmp(&self.key)
+            .then_with(|| {
+                other
+                    .score
+                    .partial_cmp(&self.score)
+                    .unwrap_or(Ordering::Equal)
+            })
+            .then_with(|| self.doc_address.cmp(&other.doc_address))
+    }
+}
+
+impl PartialEq for GlobalFieldDoc {
+    fn eq(&self, other: &GlobalFieldDoc) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for GlobalFieldDoc {}
+
+/// The Top Field Collector keeps track of the K documents
+/// with the best values for a given `u64` or `i64` fast field.
+///
+/// Documents with the same value are sorted by decreasing score.
+///
+/// Like the `TopCollector`, the implementation is based on a `BinaryHeap`,
+/// and the memory of the heap is reserved against the memory budget
+/// of the search.
+///
+/// Deep pagination does not require to increase the limit: the next page
+/// of results is obtained by calling `.search_after(...)` with the last
+/// result of the previous page.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::schema::{SchemaBuilder, FAST, TEXT};
+/// use tantivy::{Index, Result};
+/// use tantivy::collector::{SortOrder, TopFieldCollector};
+/// use tantivy::query::QueryParser;
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let title = schema_builder.add_text_field("title", TEXT);
+///     let year = schema_builder.add_u64_field("year", FAST);
+///     let index = Index::create_in_ram(schema_builder.build());
+///     {
+///         let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+///         index_writer.add_document(doc!(title => "The Diary of Muadib", year => 1965u64));
+///         index_writer.add_document(doc!(title => "A Dairy Cow", year => 1999u64));
+///         index_writer.add_document(doc!(title => "The Diary of a Young Girl", year => 1947u64));
+///         index_writer.commit()?;
+///     }
+///     index.load_searchers()?;
+///     let searcher = index.searcher();
+///
+///     let query_parser = QueryParser::for_index(&index, vec![title]);
+///     let query = query_parser.parse_query("diary")?;
+///     let mut top_collector = TopFieldCollector::with_limit(year, 2, SortOrder::Descending);
+///     searcher.search(&*query, &mut top_collector)?;
+///
+///     let years: Vec<u64> = top_collector
+///         .field_docs()
+///         .into_iter()
+///         .map(|(year, _score, _doc_address)| year)
+///         .collect();
+///     assert_eq!(years, vec![1965, 1947]);
+///     Ok(())
+/// }
+/// ```
+pub struct TopFieldCollector<T: FastValue> {
+    field: Field,
+    order: SortOrder,
+    limit: usize,
+    heap: BinaryHeap<GlobalFieldDoc>,
+    segment_id: u32,
+    ff_reader: Option<FastFieldReader<T>>,
+    memory_budget: MemoryBudget,
+    // number of field docs reserved against the memory budget.
+    reserved_capacity: usize,
+    // documents ranking before this cursor, or equal to it, are skipped.
+    after: Option<GlobalFieldDoc>,
+    // true iff no document of the current segment can enter the heap.
+    skip_segment: bool,
+}
+
+impl<T: FastValue> TopFieldCollector<T> {
+    /// Creates a top field collector, sorting the documents by
+    /// the value of the fast field `field`, and keeping
+    /// a number of documents equal to "limit".
+    ///
+    /// # Panics
+    /// The method panics if limit is 0
+    pub fn with_limit(field: Field, limit: usize, order: SortOrder) -> TopFieldCollector<T> {
+        if limit < 1 {
+            panic!("Limit must be strictly greater than 0.");
+        }
+        TopFieldCollector {
+            field,
+            order,
+            limit,
+            heap: BinaryHeap::new(),
+            segment_id: 0,
+            ff_reader: None,
+            memory_budget: MemoryBudget::unlimited(),
+            reserved_capacity: 0,
+            after: None,
+            skip_segment: false,
+        }
+    }
+
+    /// Only collects the documents ranking after the given cursor,
+    /// typically the last `(value, score, doc_address)` returned
+    /// by `.field_docs()` for the previous page of results.
+    ///
+    /// The doc address is specific to a searcher: the pages need to be
+    /// collected with the same searcher. (See `Searcher::resolve_doc_address`)
+    pub fn search_after(&mut self, value: T, score: Score, doc_address: DocAddress) {
+        self.after = Some(GlobalFieldDoc {
+            key: self.key_to_u64(value.to_u64()),
+            score,
+            doc_address,
+        });
+    }
+
+    /// Returns K best documents sorted by their value.
+    ///
+    /// Calling this method triggers the sort.
+    /// The result of the sort is not cached.
+    pub fn docs(&self) -> Vec<DocAddress> {
+        self.field_docs()
+            .into_iter()
+            .map(|(_, _, doc_address)| doc_address)
+            .collect()
+    }
+
+    /// Returns K best documents sorted by their value, together
+    /// with their value and their score.
+    ///
+    /// Calling this method triggers the sort.
+    /// The result of the sort is not cached.
+    pub fn field_docs(&self) -> Vec<(T, Score, DocAddress)> {
+        let mut field_docs: Vec<GlobalFieldDoc> = self.heap.iter().cloned().collect();
+        field_docs.sort();
+        field_docs
+            .into_iter()
+            .map(|field_doc| {
+                (
+                    T::from_u64(self.key_to_u64(field_doc.key)),
+                    field_doc.score,
+                    field_doc.doc_address,
+                )
+            })
+            .collect()
+    }
+
+    /// Return true iff at least K documents have gone through
+    /// the collector.
+    #[inline]
+    pub fn at_capacity(&self) -> bool {
+        self.heap.len() >= self.limit
+    }
+
+    // The key to u64 mapping is its own inverse.
+    fn key_to_u64(&self, val: u64) -> u64 {
+        match self.order {
+            SortOrder::Descending => val,
+            SortOrder::Ascending => !val,
+        }
+    }
+
+    /// Returns true iff the heap is full, and the bounds of the values
+    /// of the segment show that none of its documents can rank
+    /// before the worst document of the heap.
+    fn can_skip_segment(&self, reader: &SegmentReader) -> bool {
+        if !self.at_capacity() {
+            return false;
+        }
+        let (min_value, max_value) = match reader.u64_fast_field_bounds(self.field) {
+            Some(bounds) => bounds,
+            None => return false,
+        };
+        let best_key = cmp::max(self.key_to_u64(min_value), self.key_to_u64(max_value));
+        match self.heap.peek() {
+            // documents with the same key may still rank first thanks to their score.
+            Some(head) => best_key < head.key,
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, field_doc: GlobalFieldDoc) {
+        if self.at_capacity() {
+            let mut head = self.heap
+                .peek_mut()
+                .expect("Top field collector with size 0 is forbidden");
+            if field_doc < *head {
+                *head = field_doc;
+            }
+        } else {
+            self.heap.push(field_doc);
+        }
+    }
+}
+
+impl<T: FastValue> Collector for TopFieldCollector<T> {
+    fn set_segment(&mut self, segment_id: SegmentLocalId, reader: &SegmentReader) -> Result<()> {
+        self.segment_id = segment_id;
+        self.ff_reader = Some(reader.fast_field_reader(self.field)?);
+        self.skip_segment = self.can_skip_segment(reader);
+        let capacity = cmp::min(self.limit, self.heap.len() + reader.max_doc() as usize);
+        if capacity > self.reserved_capacity {
+            let num_bytes = (capacity - self.reserved_capacity) * mem::size_of::<GlobalFieldDoc>();
+            self.memory_budget.allocate(num_bytes)?;
+            self.reserved_capacity = capacity;
+        }
+        Ok(())
+    }
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        if self.skip_segment {
+            return;
+        }
+        let val = self.ff_reader
+            .as_ref()
+            .expect("collect() was called before set_segment. This should never happen.")
+            .get(doc);
+        let field_doc = GlobalFieldDoc {
+            key: self.key_to_u64(val.to_u64()),
+            score,
+            doc_address: DocAddress(self.segment_id, doc),
+        };
+        if let Some(ref after) = self.after {
+            if field_doc <= *after {
+                return;
+            }
+        }
+        self.insert(field_doc);
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn set_memory_budget(&mut self, memory_budget: &MemoryBudget) -> Result<()> {
+        self.memory_budget = memory_budget.clone();
+        Ok(())
+    }
+}
+
+impl<T: FastValue + Send> MergeableCollector for TopFieldCollector<T> {
+    fn split(&self) -> TopFieldCollector<T> {
+        let mut collector = TopFieldCollector::with_limit(self.field, self.limit, self.order);
+        collector.after = self.after;
+        collector
+    }
+
+    fn merge(&mut self, other: TopFieldCollector<T>) {
+        for field_doc in other.heap.into_vec() {
+            self.insert(field_doc);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{SortOrder, TopFieldCollector};
+    use query::{AllQuery, TermQuery};
+    use schema::{SchemaBuilder, FAST, TEXT};
+    use DocAddress;
+    use DocId;
+    use Index;
+    use Score;
+
+    #[test]
+    fn test_top_field_collector() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let num_field = schema_builder.add_i64_field("num", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a b c d", num_field => 3i64));
+            index_writer.add_document(doc!(text_field => "a a a", num_field => -2i64));
+            index_writer.add_document(doc!(text_field => "a a", num_field => 3i64));
+            index_writer.add_document(doc!(text_field => "b", num_field => 10i64));
+            index_writer.add_document(doc!(text_field => "a", num_field => 0i64));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query = TermQuery::new_text(text_field, "a");
+        let top_docs = |limit: usize, order: SortOrder| {
+            let mut collector = TopFieldCollector::<i64>::with_limit(num_field, limit, order);
+            searcher.search(&query, &mut collector).unwrap();
+            collector
+                .field_docs()
+                .into_iter()
+                .map(|(val, _, doc_address)| (val, doc_address.doc()))
+                .collect::<Vec<(i64, DocId)>>()
+        };
+        // ties on `3` are broken by the score.
+        assert_eq!(
+            top_docs(10, SortOrder::Descending),
+            vec![(3, 2), (3, 0), (0, 4), (-2, 1)]
+        );
+        assert_eq!(top_docs(2, SortOrder::Descending), vec![(3, 2), (3, 0)]);
+        assert_eq!(top_docs(2, SortOrder::Ascending), vec![(-2, 1), (0, 4)]);
+    }
+
+    #[test]
+    fn test_top_field_collector_search_after() {
+        let mut schema_builder = SchemaBuilder::default();
+        let num_field = schema_builder.add_u64_field("num", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0u64..50u64 {
+                index_writer.add_document(doc!(num_field => i % 7));
+                if i == 30 {
+                    index_writer.commit().unwrap();
+                }
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        for &order in &[SortOrder::Ascending, SortOrder::Descending] {
+            let mut collector = TopFieldCollector::<u64>::with_limit(num_field, 50, order);
+            searcher.search(&AllQuery, &mut collector).unwrap();
+            let expected_field_docs = collector.field_docs();
+            assert_eq!(expected_field_docs.len(), 50);
+
+            let mut field_docs: Vec<(u64, Score, DocAddress)> = vec![];
+            loop {
+                let mut collector = TopFieldCollector::<u64>::with_limit(num_field, 8, order);
+                if let Some(&(val, score, doc_address)) = field_docs.last() {
+                    collector.search_after(val, score, doc_address);
+                }
+                searcher
+                    .search_parallel(&AllQuery, &mut collector, 2)
+                    .unwrap();
+                let page = collector.field_docs();
+                if page.is_empty() {
+                    break;
+                }
+                field_docs.extend(page);
+            }
+            assert_eq!(field_docs, expected_field_docs);
+        }
+    }
+
+    #[test]
+    fn test_top_field_collector_parallel() {
+        let mut schema_builder = SchemaBuilder::default();
+        let num_field = schema_builder.add_u64_field("num", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0u64..30_000u64 {
+                index_writer.add_document(doc!(num_field => (i * 7_919) % 30_000));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut collector = TopFieldCollector::with_limit(num_field, 3, SortOrder::Ascending);
+        searcher
+            .search_parallel(&AllQuery, &mut collector, 4)
+            .unwrap();
+        let vals: Vec<u64> = collector
+            .field_docs()
+            .into_iter()
+            .map(|(val, _, _)| val)
+            .collect();
+        assert_eq!(vals, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_top_field_collector_not_a_fast_field() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut collector: TopFieldCollector<u64> =
+            TopFieldCollector::with_limit(text_field, 1, SortOrder::Ascending);
+        assert!(searcher.search(&AllQuery, &mut collector).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_top_field_0() {
+        TopFieldCollector::<u64>::with_limit(::schema::Field(0), 0, SortOrder::Ascending);
+    }
+}
@@ -0,0 +1,89 @@
+//! Standard base64 encoding, with padding. (RFC 4648)
+//!
+//! It is used to represent the values of bytes fields in JSON.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const PADDING: u8 = b'=';
+
+fn decode_char(c: u8) -> Option<u32> {
+    match c {
+        b'A'..=b'Z' => Some(u32::from(c - b'A')),
+        b'a'..=b'z' => Some(u32::from(c - b'a') + 26),
+        b'0'..=b'9' => Some(u32::from(c - b'0') + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+/// Encodes `bytes` in base64.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let mut group = 0u32;
+        for (i, &byte) in chunk.iter().enumerate() {
+            group |= u32::from(byte) << (16 - 8 * i);
+        }
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let sextet = (group >> (18 - 6 * i)) & 63;
+                encoded.push(ALPHABET[sextet as usize] as char);
+            } else {
+                encoded.push(PADDING as char);
+            }
+        }
+    }
+    encoded
+}
+
+/// Decodes a base64 string.
+///
+/// Returns `None` if `encoded` is not valid base64.
+pub fn decode(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.as_bytes();
+    if encoded.len() % 4 != 0 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(encoded.len() / 4 * 3);
+    let num_chunks = encoded.len() / 4;
+    for (chunk_id, chunk) in encoded.chunks(4).enumerate() {
+        let num_padding = chunk.iter().rev().take_while(|&&c| c == PADDING).count();
+        if num_padding > 2 || (num_padding > 0 && chunk_id + 1 != num_chunks) {
+            return None;
+        }
+        let mut group = 0u32;
+        for (i, &c) in chunk[..4 - num_padding].iter().enumerate() {
+            group |= decode_char(c)? << (18 - 6 * i);
+        }
+        for i in 0..3 - num_padding {
+            bytes.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{decode, encode};
+
+    #[test]
+    fn test_base64() {
+        let cases: &[(&[u8], &str)] = &[
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"\xff\x00\xfe", "/wD+"),
+        ];
+        for &(bytes, encoded) in cases {
+            assert_eq!(encode(bytes), encoded);
+            assert_eq!(decode(encoded).unwrap(), bytes);
+        }
+        assert_eq!(decode("Zm9"), None);
+        assert_eq!(decode("Zg==Zm8="), None);
+        assert_eq!(decode("Z==="), None);
+        assert_eq!(decode("Zm9*"), None);
+    }
+}
@@ -116,8 +116,14 @@ impl CompositeFile {
     /// `ReadOnlySource`.
     pub fn open(data: &ReadOnlySource) -> io::Result<CompositeFile> {
         let end = data.len();
+        if end < 4 {
+            return Err(corrupted_composite_file("missing footer length"));
+        }
         let footer_len_data = data.slice_from(end - 4);
         let footer_len = u32::deserialize(&mut footer_len_data.as_slice())? as usize;
+        if footer_len > end - 4 {
+            return Err(corrupted_composite_file("footer longer than the file"));
+        }
 
         let footer_start = end - 4 - footer_len;
         let footer_data = data.slice(footer_start, footer_start + footer_len);
@@ -131,12 +137,17 @@ impl CompositeFile {
 
         let mut offset = 0;
         for _ in 0..num_fields {
-            offset += VInt::deserialize(&mut footer_buffer)?.0 as usize;
+            offset = (VInt::deserialize(&mut footer_buffer)?.0 as usize)
+                .checked_add(offset)
+                .ok_or_else(|| corrupted_composite_file("invalid field offsets"))?;
             let file_addr = FileAddr::deserialize(&mut footer_buffer)?;
             offsets.push(offset);
             file_addrs.push(file_addr);
         }
         offsets.push(footer_start);
+        if offsets.windows(2).any(|window| window[0] > window[1]) {
+            return Err(corrupted_composite_file("invalid field offsets"));
+        }
         for i in 0..num_fields {
             let file_addr = file_addrs[i];
             let start_offset = offsets[i];
@@ -159,6 +170,38 @@ impl CompositeFile {
         }
     }
 
+    /// Copies all of the fields of the composite file
+    /// to a `CompositeWrite`.
+    pub(crate) fn copy_to<W: Write>(
+        &self,
+        composite_write: &mut CompositeWrite<W>,
+    ) -> io::Result<()> {
+        let mut file_addrs: Vec<(usize, FileAddr)> = self.offsets_index
+            .iter()
+            .map(|(file_addr, &(start_offset, _))| (start_offset, *file_addr))
+            .collect();
+        file_addrs.sort();
+        for (_, file_addr) in file_addrs {
+            let (start_offset, end_offset) = self.offsets_index[&file_addr];
+            let data = self.data.slice(start_offset, end_offset);
+            composite_write
+                .for_field_with_idx(file_addr.field, file_addr.idx)
+                .write_all(data.as_slice())?;
+        }
+        Ok(())
+    }
+
+    /// Returns the fields stored in the composite file, sorted.
+    pub fn fields(&self) -> Vec<Field> {
+        let mut fields: Vec<Field> = self.offsets_index
+            .keys()
+            .map(|file_addr| file_addr.field)
+            .collect();
+        fields.sort();
+        fields.dedup();
+        fields
+    }
+
     /// Returns the `ReadOnlySource` associated
     /// to a given `Field` and stored in a `CompositeFile`.
     pub fn open_read(&self, field: Field) -> Option<ReadOnlySource> {
@@ -177,12 +220,19 @@ impl CompositeFile {
     }
 }
 
+fn corrupted_composite_file(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Corrupted composite file: {}", msg),
+    )
+}
+
 #[cfg(test)]
 mod test {
 
     use std::io::Write;
     use super::{CompositeFile, CompositeWrite};
-    use directory::{Directory, RAMDirectory};
+    use directory::{Directory, RAMDirectory, ReadOnlySource};
     use schema::Field;
     use common::VInt;
     use common::BinarySerializable;
@@ -226,6 +276,20 @@ mod test {
                 assert_eq!(payload_4, 2u64);
             }
         }
+        {
+            let r = directory.open_read(path).unwrap();
+            let data = r.as_slice();
+            for len in 0..4 {
+                let truncated = ReadOnlySource::from(data[..len].to_vec());
+                assert!(CompositeFile::open(&truncated).is_err());
+            }
+            let mut invalid_footer_len = data.to_vec();
+            let footer_len_start = invalid_footer_len.len() - 4;
+            for b in &mut invalid_footer_len[footer_len_start..] {
+                *b = 255u8;
+            }
+            assert!(CompositeFile::open(&ReadOnlySource::from(invalid_footer_len)).is_err());
+        }
     }
 
 }
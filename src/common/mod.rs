@@ -5,6 +5,8 @@ mod counting_writer;
 mod composite_file;
 pub mod bitpacker;
 mod bitset;
+mod span;
+pub(crate) mod base64;
 
 pub(crate) use self::composite_file::{CompositeFile, CompositeWrite};
 pub use self::serialize::{BinarySerializable, FixedSize};
@@ -15,6 +17,7 @@ pub use self::vint::VInt;
 pub use self::counting_writer::CountingWriter;
 pub use self::bitset::BitSet;
 pub(crate) use self::bitset::TinySet;
+pub(crate) use self::span::Span;
 pub use byteorder::LittleEndian as Endianness;
 
 use std::io;
@@ -0,0 +1,145 @@
+//! Instrumentation of the long running operations of tantivy.
+//!
+//! If the `instrumentation` feature is enabled, spans are logged
+//! at the `debug` level under the `tantivy::span` target, with their
+//! details and their duration in microseconds, e.g.
+//!
+//! `merge segments=[..] num_docs=10000 elapsed_us=35162`
+//!
+//! Without the feature, spans are no-ops, and their details are
+//! never computed.
+
+#[cfg(feature = "instrumentation")]
+mod enabled {
+
+    #[cfg(test)]
+    use std::cell::RefCell;
+    use std::fmt;
+    use time::PreciseTime;
+
+    #[cfg(test)]
+    thread_local! {
+        // spans ended by the current thread, checked by the tests.
+        static ENDED_SPANS: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    }
+
+    #[cfg(test)]
+    fn record_ended_span(line: &str) {
+        ENDED_SPANS.with(|ended_spans| ended_spans.borrow_mut().push(line.to_string()));
+    }
+
+    #[cfg(not(test))]
+    #[inline(always)]
+    fn record_ended_span(_line: &str) {}
+
+    /// Returns the spans ended by the current thread since the last call.
+    #[cfg(test)]
+    pub(crate) fn take_ended_spans() -> Vec<String> {
+        ENDED_SPANS.with(|ended_spans| ended_spans.borrow_mut().drain(..).collect())
+    }
+
+    /// Times an operation, from its creation to its drop.
+    pub(crate) struct Span {
+        name: &'static str,
+        details: String,
+        start: PreciseTime,
+    }
+
+    impl Span {
+        /// Starts a span. `details` is called right away,
+        /// and should list the inputs of the operation.
+        pub fn enter<F: FnOnce() -> String>(name: &'static str, details: F) -> Span {
+            let details = details();
+            debug!(target: "tantivy::span", "enter {} {}", name, details);
+            Span {
+                name,
+                details,
+                start: PreciseTime::now(),
+            }
+        }
+
+        /// Records an output of the operation.
+        pub fn record<V: fmt::Display>(&mut self, key: &'static str, val: V) {
+            self.details.push_str(&format!(" {}={}", key, val));
+        }
+    }
+
+    impl Drop for Span {
+        fn drop(&mut self) {
+            let elapsed_us = self.start
+                .to(PreciseTime::now())
+                .num_microseconds()
+                .unwrap_or(i64::max_value());
+            let line = format!("{} {} elapsed_us={}", self.name, self.details, elapsed_us);
+            debug!(target: "tantivy::span", "{}", line);
+            record_ended_span(&line);
+        }
+    }
+}
+
+#[cfg(not(feature = "instrumentation"))]
+mod disabled {
+
+    use std::fmt;
+
+    pub(crate) struct Span;
+
+    impl Span {
+        #[inline(always)]
+        pub fn enter<F: FnOnce() -> String>(_name: &'static str, _details: F) -> Span {
+            Span
+        }
+
+        #[inline(always)]
+        pub fn record<V: fmt::Display>(&mut self, _key: &'static str, _val: V) {}
+    }
+}
+
+#[cfg(feature = "instrumentation")]
+pub(crate) use self::enabled::Span;
+#[cfg(not(feature = "instrumentation"))]
+pub(crate) use self::disabled::Span;
+
+#[cfg(all(test, feature = "instrumentation"))]
+mod tests {
+
+    use super::enabled::take_ended_spans;
+    use collector::CountCollector;
+    use query::{Query, TermQuery};
+    use schema::{SchemaBuilder, TEXT};
+    use Index;
+
+    #[test]
+    fn test_search_spans() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a b"));
+            index_writer.add_document(doc!(text_field => "a"));
+            index_writer.add_document(doc!(text_field => "b"));
+            index_writer.add_document(doc!(text_field => "a c"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let segment_id = searcher.segment_readers()[0].segment_id();
+        take_ended_spans();
+
+        let mut count_collector = CountCollector::default();
+        TermQuery::new_text(text_field, "a")
+            .search(&*searcher, &mut count_collector)
+            .unwrap();
+        assert_eq!(count_collector.count(), 3);
+        let ended_spans = take_ended_spans();
+        assert_eq!(ended_spans.len(), 2);
+        assert!(ended_spans[0].starts_with("weight query="));
+        // the collect span counts the documents pushed to the collector.
+        let expected_prefix = format!(
+            "collect_segment segment={} num_docs=3 elapsed_us=",
+            segment_id.short_uuid_string()
+        );
+        assert!(ended_spans[1].starts_with(&expected_prefix));
+    }
+}
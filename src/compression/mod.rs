@@ -1,17 +1,82 @@
 #![allow(dead_code)]
 
-
-mod stream;
-
-pub use self::stream::CompressedIntStream;
+use std::io;
 
 pub const COMPRESSION_BLOCK_SIZE: usize = 128;
 
+/// Maximum size in bytes of the variable bytes encoding of the
+/// `2 * COMPRESSION_BLOCK_SIZE - 1` integers at most that do not fill
+/// a postings block, as a `u32` is encoded over 5 bytes at most.
+const VINT_BLOCK_MAX_SIZE: usize = 2 * COMPRESSION_BLOCK_SIZE * 5;
+
 /// Returns the size in bytes of a compressed block, given `num_bits`.
 pub fn compressed_block_size(num_bits: u8) -> usize {
     1 + (num_bits as usize) * 16
 }
 
+/// Checks that `compressed_data` starts with a complete compressed block,
+/// and returns its number of bits per value.
+///
+/// Decoding a truncated block would read out of its bounds.
+fn check_compressed_block(compressed_data: &[u8]) -> io::Result<u8> {
+    let num_bits = match compressed_data.first() {
+        Some(&num_bits) => num_bits,
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Empty compressed block",
+            ))
+        }
+    };
+    if num_bits > 32 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid number of bits {} in compressed block", num_bits),
+        ));
+    }
+    if compressed_data.len() < compressed_block_size(num_bits) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Truncated compressed block",
+        ));
+    }
+    Ok(num_bits)
+}
+
+/// Returns the size in bytes of the blocks compressing `num_vals` integers
+/// at the start of `compressed_data`, without decoding them.
+///
+/// Returns an error if `compressed_data` does not start with complete blocks.
+pub fn compressed_blocks_size(compressed_data: &[u8], num_vals: usize) -> io::Result<usize> {
+    let mut size = 0;
+    let mut start = 0;
+    while start < num_vals {
+        let num_bits = check_compressed_block(&compressed_data[size..])?;
+        size += compressed_block_size(num_bits);
+        start += COMPRESSION_BLOCK_SIZE;
+    }
+    Ok(size)
+}
+
+/// Checks that `compressed_data` starts with `num_vals` variable bytes
+/// encoded `u32`, and returns their size in bytes.
+pub fn check_vints(compressed_data: &[u8], num_vals: usize) -> io::Result<usize> {
+    let mut size = 0;
+    for _ in 0..num_vals {
+        // a `u32` is encoded over 5 bytes at most,
+        // the last one being flagged by its highest bit.
+        let vint_len = compressed_data[size..]
+            .iter()
+            .take(5)
+            .position(|&b| b & 128u8 != 0u8)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "Truncated or invalid vint")
+            })?;
+        size += vint_len + 1;
+    }
+    Ok(size)
+}
+
 #[cfg(not(feature = "simdcompression"))]
 mod pack {
     mod compression_pack_nosimd;
@@ -89,11 +154,11 @@ pub trait VIntDecoder {
 
 impl VIntEncoder for BlockEncoder {
     fn compress_vint_sorted(&mut self, input: &[u32], offset: u32) -> &[u8] {
-        vint::compress_sorted(input, &mut self.output, offset)
+        vint::compress_sorted(input, &mut self.vint_output, offset)
     }
 
     fn compress_vint_unsorted(&mut self, input: &[u32]) -> &[u8] {
-        vint::compress_unsorted(input, &mut self.output)
+        vint::compress_unsorted(input, &mut self.vint_output)
     }
 }
 
@@ -114,6 +179,89 @@ impl VIntDecoder for BlockDecoder {
     }
 }
 
+impl BlockEncoder {
+    /// Compresses an array of sorted `u32` integers, as consecutive
+    /// blocks of `COMPRESSION_BLOCK_SIZE` integers, and appends them to `output`.
+    ///
+    /// An incomplete last block is padded by repeating its last value.
+    pub fn compress_blocks_sorted(&mut self, vals: &[u32], mut offset: u32, output: &mut Vec<u8>) {
+        let mut padded_block = [0u32; COMPRESSION_BLOCK_SIZE];
+        for block in vals.chunks(COMPRESSION_BLOCK_SIZE) {
+            let last_val = block[block.len() - 1];
+            padded_block[..block.len()].copy_from_slice(block);
+            for val in &mut padded_block[block.len()..] {
+                *val = last_val;
+            }
+            output.extend_from_slice(self.compress_block_sorted(&padded_block, offset));
+            offset = last_val;
+        }
+    }
+
+    /// Compresses an array of `u32` integers, as consecutive
+    /// blocks of `COMPRESSION_BLOCK_SIZE` integers, and appends them to `output`.
+    ///
+    /// An incomplete last block is padded by repeating its first value.
+    pub fn compress_blocks_unsorted(&mut self, vals: &[u32], output: &mut Vec<u8>) {
+        let mut padded_block = [0u32; COMPRESSION_BLOCK_SIZE];
+        for block in vals.chunks(COMPRESSION_BLOCK_SIZE) {
+            padded_block[..block.len()].copy_from_slice(block);
+            for val in &mut padded_block[block.len()..] {
+                *val = block[0];
+            }
+            output.extend_from_slice(self.compress_block_unsorted(&padded_block));
+        }
+    }
+}
+
+impl BlockDecoder {
+    /// Uncompresses `num_vals` sorted integers, compressed with
+    /// `BlockEncoder::compress_blocks_sorted`, and returns the amount
+    /// of bytes that were read.
+    ///
+    /// `num_vals` is at most `2 * COMPRESSION_BLOCK_SIZE`.
+    pub fn uncompress_blocks_sorted(
+        &mut self,
+        compressed_data: &[u8],
+        mut offset: u32,
+        num_vals: usize,
+    ) -> io::Result<usize> {
+        let mut consumed_size = 0;
+        let mut start = 0;
+        while start < num_vals {
+            consumed_size += self.uncompress_block_sorted_at(
+                &compressed_data[consumed_size..],
+                offset,
+                start,
+            )?;
+            offset = self.output[start + COMPRESSION_BLOCK_SIZE - 1];
+            start += COMPRESSION_BLOCK_SIZE;
+        }
+        self.output_len = num_vals;
+        Ok(consumed_size)
+    }
+
+    /// Uncompresses `num_vals` integers, compressed with
+    /// `BlockEncoder::compress_blocks_unsorted`, and returns the amount
+    /// of bytes that were read.
+    ///
+    /// `num_vals` is at most `2 * COMPRESSION_BLOCK_SIZE`.
+    pub fn uncompress_blocks_unsorted(
+        &mut self,
+        compressed_data: &[u8],
+        num_vals: usize,
+    ) -> io::Result<usize> {
+        let mut consumed_size = 0;
+        let mut start = 0;
+        while start < num_vals {
+            consumed_size +=
+                self.uncompress_block_unsorted_at(&compressed_data[consumed_size..], start)?;
+            start += COMPRESSION_BLOCK_SIZE;
+        }
+        self.output_len = num_vals;
+        Ok(consumed_size)
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
 
@@ -128,7 +276,7 @@ pub mod tests {
         let compressed_data = encoder.compress_block_sorted(&vals, 0);
         let mut decoder = BlockDecoder::new();
         {
-            let consumed_num_bytes = decoder.uncompress_block_sorted(compressed_data, 0);
+            let consumed_num_bytes = decoder.uncompress_block_sorted(compressed_data, 0).unwrap();
             assert_eq!(consumed_num_bytes, compressed_data.len());
         }
         for i in 0..128 {
@@ -143,7 +291,7 @@ pub mod tests {
         let compressed_data = encoder.compress_block_sorted(&vals, 10);
         let mut decoder = BlockDecoder::new();
         {
-            let consumed_num_bytes = decoder.uncompress_block_sorted(compressed_data, 10);
+            let consumed_num_bytes = decoder.uncompress_block_sorted(compressed_data, 10).unwrap();
             assert_eq!(consumed_num_bytes, compressed_data.len());
         }
         for i in 0..128 {
@@ -162,7 +310,7 @@ pub mod tests {
         compressed.push(173u8);
         let mut decoder = BlockDecoder::new();
         {
-            let consumed_num_bytes = decoder.uncompress_block_sorted(&compressed, 10);
+            let consumed_num_bytes = decoder.uncompress_block_sorted(&compressed, 10).unwrap();
             assert_eq!(consumed_num_bytes, compressed.len() - 1);
             assert_eq!(compressed[consumed_num_bytes], 173u8);
         }
@@ -171,6 +319,22 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_uncompress_truncated_block() {
+        let vals: Vec<u32> = (0u32..128u32).map(|i| i * 7).collect();
+        let mut encoder = BlockEncoder::new();
+        let compressed_data = encoder.compress_block_sorted(&vals, 0).to_vec();
+        let mut decoder = BlockDecoder::new();
+        assert!(decoder.uncompress_block_sorted(&[], 0).is_err());
+        assert!(decoder.uncompress_block_unsorted(&[]).is_err());
+        let truncated_data = &compressed_data[..compressed_data.len() - 1];
+        assert!(decoder.uncompress_block_sorted(truncated_data, 0).is_err());
+        assert!(decoder.uncompress_block_unsorted(truncated_data).is_err());
+        let mut invalid_data = vec![0u8; compressed_block_size(32) + 1];
+        invalid_data[0] = 33;
+        assert!(decoder.uncompress_block_unsorted(&invalid_data).is_err());
+    }
+
     #[test]
     fn test_encode_unsorted_block_with_junk() {
         let mut compressed: Vec<u8> = Vec::new();
@@ -182,7 +346,7 @@ pub mod tests {
         compressed.push(173u8);
         let mut decoder = BlockDecoder::new();
         {
-            let consumed_num_bytes = decoder.uncompress_block_unsorted(&compressed);
+            let consumed_num_bytes = decoder.uncompress_block_unsorted(&compressed).unwrap();
             assert_eq!(consumed_num_bytes + 1, compressed.len());
             assert_eq!(compressed[consumed_num_bytes], 173u8);
         }
@@ -191,6 +355,36 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_encode_blocks() {
+        for &num_vals in &[64usize, 128, 256] {
+            let sorted_vals: Vec<u32> = (0..num_vals as u32).map(|i| 11 + i * 7).collect();
+            let unsorted_vals: Vec<u32> = (0..num_vals as u32).map(|i| 1 + i % 13).collect();
+            let mut encoder = BlockEncoder::new();
+            let mut compressed = Vec::new();
+            encoder.compress_blocks_sorted(&sorted_vals, 10, &mut compressed);
+            let sorted_len = compressed.len();
+            encoder.compress_blocks_unsorted(&unsorted_vals, &mut compressed);
+            compressed.push(173u8);
+            assert_eq!(
+                compressed_blocks_size(&compressed, num_vals).unwrap(),
+                sorted_len
+            );
+            assert!(compressed_blocks_size(&compressed[..sorted_len - 1], num_vals).is_err());
+            let mut decoder = BlockDecoder::new();
+            let consumed_num_bytes = decoder
+                .uncompress_blocks_sorted(&compressed, 10, num_vals)
+                .unwrap();
+            assert_eq!(consumed_num_bytes, sorted_len);
+            assert_eq!(decoder.output_array(), &sorted_vals[..]);
+            let consumed_num_bytes = decoder
+                .uncompress_blocks_unsorted(&compressed[sorted_len..], num_vals)
+                .unwrap();
+            assert_eq!(compressed[sorted_len + consumed_num_bytes], 173u8);
+            assert_eq!(decoder.output_array(), &unsorted_vals[..]);
+        }
+    }
+
     #[test]
     fn test_encode_vint() {
         {
@@ -225,7 +419,7 @@ pub mod tests {
         let compressed = encoder.compress_block_sorted(&data, 0u32);
         let mut decoder = BlockDecoder::new();
         b.iter(|| {
-            decoder.uncompress_block_sorted(compressed, 0u32);
+            decoder.uncompress_block_sorted(compressed, 0u32).unwrap();
         });
     }
 
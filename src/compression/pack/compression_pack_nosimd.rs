@@ -2,8 +2,9 @@ use common::bitpacker::compute_num_bits;
 use common::bitpacker::{BitPacker, BitUnpacker};
 use common::CountingWriter;
 use std::cmp;
-use std::io::Write;
-use super::super::{compressed_block_size, COMPRESSION_BLOCK_SIZE};
+use std::io::{self, Write};
+use super::super::{check_compressed_block, compressed_block_size, COMPRESSION_BLOCK_SIZE,
+                   VINT_BLOCK_MAX_SIZE};
 
 const COMPRESSED_BLOCK_MAX_SIZE: usize = COMPRESSION_BLOCK_SIZE * 4 + 1;
 
@@ -37,6 +38,9 @@ pub fn compress_sorted(vals: &mut [u32], output: &mut [u8], offset: u32) -> usiz
 pub struct BlockEncoder {
     pub output: [u8; COMPRESSED_BLOCK_MAX_SIZE],
     pub output_len: usize,
+    // the vint encoded integers that do not fill a block
+    // may not fit in `output`.
+    pub vint_output: [u8; VINT_BLOCK_MAX_SIZE],
     input_buffer: [u32; COMPRESSION_BLOCK_SIZE],
 }
 
@@ -45,6 +49,7 @@ impl BlockEncoder {
         BlockEncoder {
             output: [0u8; COMPRESSED_BLOCK_MAX_SIZE],
             output_len: 0,
+            vint_output: [0u8; VINT_BLOCK_MAX_SIZE],
             input_buffer: [0u32; COMPRESSION_BLOCK_SIZE],
         }
     }
@@ -108,32 +113,55 @@ impl BlockDecoder {
     pub fn uncompress_block_sorted<'a>(
         &mut self,
         compressed_data: &'a [u8],
-        mut offset: u32,
-    ) -> usize {
-        let consumed_size = {
-            let num_bits = compressed_data[0];
-            let bit_unpacker = BitUnpacker::new(&compressed_data[1..], num_bits as usize);
-            for i in 0..COMPRESSION_BLOCK_SIZE {
-                let delta = bit_unpacker.get(i);
-                let val = offset + delta as u32;
-                self.output[i] = val;
-                offset = val;
-            }
-            compute_block_size(num_bits)
-        };
+        offset: u32,
+    ) -> io::Result<usize> {
+        let consumed_size = self.uncompress_block_sorted_at(compressed_data, offset, 0)?;
         self.output_len = COMPRESSION_BLOCK_SIZE;
-        consumed_size
+        Ok(consumed_size)
     }
 
-    pub fn uncompress_block_unsorted<'a>(&mut self, compressed_data: &'a [u8]) -> usize {
-        let num_bits = compressed_data[0];
+    /// Uncompresses a block into `output[start..start + COMPRESSION_BLOCK_SIZE]`,
+    /// leaving `output_len` untouched.
+    pub(crate) fn uncompress_block_sorted_at(
+        &mut self,
+        compressed_data: &[u8],
+        mut offset: u32,
+        start: usize,
+    ) -> io::Result<usize> {
+        let num_bits = check_compressed_block(compressed_data)?;
         let bit_unpacker = BitUnpacker::new(&compressed_data[1..], num_bits as usize);
         for i in 0..COMPRESSION_BLOCK_SIZE {
-            self.output[i] = bit_unpacker.get(i) as u32;
+            let delta = bit_unpacker.get(i);
+            // corrupted deltas may overflow.
+            let val = offset.wrapping_add(delta as u32);
+            self.output[start + i] = val;
+            offset = val;
         }
-        let consumed_size = 1 + (num_bits as usize * COMPRESSION_BLOCK_SIZE + 7) / 8;
+        Ok(compute_block_size(num_bits))
+    }
+
+    pub fn uncompress_block_unsorted<'a>(
+        &mut self,
+        compressed_data: &'a [u8],
+    ) -> io::Result<usize> {
+        let consumed_size = self.uncompress_block_unsorted_at(compressed_data, 0)?;
         self.output_len = COMPRESSION_BLOCK_SIZE;
-        consumed_size
+        Ok(consumed_size)
+    }
+
+    /// Uncompresses a block into `output[start..start + COMPRESSION_BLOCK_SIZE]`,
+    /// leaving `output_len` untouched.
+    pub(crate) fn uncompress_block_unsorted_at(
+        &mut self,
+        compressed_data: &[u8],
+        start: usize,
+    ) -> io::Result<usize> {
+        let num_bits = check_compressed_block(compressed_data)?;
+        let bit_unpacker = BitUnpacker::new(&compressed_data[1..], num_bits as usize);
+        for i in 0..COMPRESSION_BLOCK_SIZE {
+            self.output[start + i] = bit_unpacker.get(i) as u32;
+        }
+        Ok(1 + (num_bits as usize * COMPRESSION_BLOCK_SIZE + 7) / 8)
     }
 
     #[inline]
@@ -1,4 +1,5 @@
-use compression::COMPRESSION_BLOCK_SIZE;
+use compression::{check_compressed_block, COMPRESSION_BLOCK_SIZE, VINT_BLOCK_MAX_SIZE};
+use std::io;
 
 const COMPRESSED_BLOCK_MAX_SIZE: usize = COMPRESSION_BLOCK_SIZE * 4 + 1;
 
@@ -39,6 +40,9 @@ fn uncompress_unsorted(compressed_data: &[u8], output: &mut [u32]) -> usize {
 pub struct BlockEncoder {
     pub output: [u8; COMPRESSED_BLOCK_MAX_SIZE],
     pub output_len: usize,
+    // the vint encoded integers that do not fill a block
+    // may not fit in `output`.
+    pub vint_output: [u8; VINT_BLOCK_MAX_SIZE],
 }
 
 impl BlockEncoder {
@@ -46,6 +50,7 @@ impl BlockEncoder {
         BlockEncoder {
             output: [0u8; COMPRESSED_BLOCK_MAX_SIZE],
             output_len: 0,
+            vint_output: [0u8; VINT_BLOCK_MAX_SIZE],
         }
     }
 
@@ -77,16 +82,46 @@ impl BlockDecoder {
         }
     }
 
-    pub fn uncompress_block_sorted(&mut self, compressed_data: &[u8], offset: u32) -> usize {
-        let consumed_size = uncompress_sorted(compressed_data, &mut self.output, offset);
+    pub fn uncompress_block_sorted(
+        &mut self,
+        compressed_data: &[u8],
+        offset: u32,
+    ) -> io::Result<usize> {
+        let consumed_size = self.uncompress_block_sorted_at(compressed_data, offset, 0)?;
         self.output_len = COMPRESSION_BLOCK_SIZE;
-        consumed_size
+        Ok(consumed_size)
     }
 
-    pub fn uncompress_block_unsorted<'a>(&mut self, compressed_data: &'a [u8]) -> usize {
-        let consumed_size = uncompress_unsorted(compressed_data, &mut self.output);
+    /// Uncompresses a block into `output[start..start + COMPRESSION_BLOCK_SIZE]`,
+    /// leaving `output_len` untouched.
+    pub(crate) fn uncompress_block_sorted_at(
+        &mut self,
+        compressed_data: &[u8],
+        offset: u32,
+        start: usize,
+    ) -> io::Result<usize> {
+        check_compressed_block(compressed_data)?;
+        Ok(uncompress_sorted(compressed_data, &mut self.output[start..], offset))
+    }
+
+    pub fn uncompress_block_unsorted<'a>(
+        &mut self,
+        compressed_data: &'a [u8],
+    ) -> io::Result<usize> {
+        let consumed_size = self.uncompress_block_unsorted_at(compressed_data, 0)?;
         self.output_len = COMPRESSION_BLOCK_SIZE;
-        consumed_size
+        Ok(consumed_size)
+    }
+
+    /// Uncompresses a block into `output[start..start + COMPRESSION_BLOCK_SIZE]`,
+    /// leaving `output_len` untouched.
+    pub(crate) fn uncompress_block_unsorted_at(
+        &mut self,
+        compressed_data: &[u8],
+        start: usize,
+    ) -> io::Result<usize> {
+        check_compressed_block(compressed_data)?;
+        Ok(uncompress_unsorted(compressed_data, &mut self.output[start..]))
     }
 
     #[inline]
@@ -59,7 +59,7 @@ pub(crate) fn uncompress_sorted<'a>(
         loop {
             let cur_byte = compressed_data[read_byte];
             read_byte += 1;
-            result += ((cur_byte % 128u8) as u32) << shift;
+            result = result.wrapping_add(((cur_byte % 128u8) as u32) << shift);
             if cur_byte & 128u8 != 0u8 {
                 break;
             }
@@ -80,7 +80,7 @@ pub(crate) fn uncompress_unsorted<'a>(compressed_data: &'a [u8], output: &mut [u
         loop {
             let cur_byte = compressed_data[read_byte];
             read_byte += 1;
-            result += ((cur_byte % 128u8) as u32) << shift;
+            result = result.wrapping_add(((cur_byte % 128u8) as u32) << shift);
             if cur_byte & 128u8 != 0u8 {
                 break;
             }
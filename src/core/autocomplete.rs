@@ -0,0 +1,465 @@
+use Result;
+use core::{Index, InvertedIndexReader, Searcher, SegmentReader};
+use error::ErrorKind;
+use postings::TermInfo;
+use query::{BooleanQuery, BoostQuery, Occur, Query, TermQuery};
+use schema::{Field, IndexRecordOption, Schema, SchemaBuilder, Term, TextFieldIndexing,
+             TextOptions, Value};
+use schema::Document;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use tokenizer::{EdgeNgramTokenizer, LowerCaser, RawTokenizer, Tokenizer};
+use DocSet;
+use Score;
+use MAX_PREFIX_TERMS;
+
+/// Name of the tokenizer of the completion fields. (See `Autocomplete`)
+pub const COMPLETION_TOKENIZER: &str = "autocomplete_completion";
+
+/// Name of the tokenizer of the edge n-gram fields. (See `Autocomplete`)
+pub const EDGE_NGRAM_TOKENIZER: &str = "autocomplete_edge_ngram";
+
+/// Maximum number of characters of the prefixes indexed in the edge n-gram fields.
+const MAX_PREFIX_LEN: usize = 20;
+
+/// Minimum number of characters of a prefix for the fuzzy completions
+/// to be looked for.
+const MIN_FUZZY_PREFIX_LEN: usize = 3;
+
+/// Factor applied to the score of the fuzzy completions.
+const FUZZY_COMPLETION_PENALTY: Score = 0.5;
+
+#[derive(Clone, Copy, Debug)]
+struct AutocompleteField {
+    source: Field,
+    completion: Field,
+    edge_ngram: Field,
+    weight: Score,
+}
+
+/// A completion of a prefix. (See `Autocomplete::complete`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Completion {
+    /// The completed value, lowercased.
+    pub text: String,
+    /// Sum, over the source fields, of the number of documents
+    /// having this value, multiplied by the weight of the field.
+    /// The deleted documents are not counted.
+    pub score: Score,
+    /// True iff the value does not start with the prefix, but with
+    /// a prefix at one edit of it.
+    pub fuzzy: bool,
+}
+
+/// `Autocomplete` completes the values of a set of weighted text fields,
+/// e.g. the titles and the authors of books.
+///
+/// For each of its source fields, two fields are added to the schema:
+/// - `<field>_completion` indexes the whole lowercased values. Its term
+///   dictionary is used as the dictionary of completions.
+/// - `<field>_prefix` indexes their prefixes of up to 20 characters,
+///   to search the documents whose value starts with a prefix.
+///
+/// Documents have to go through `.prepare_document(...)` before being
+/// added, so that the values of the source fields are copied to these fields.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::schema::{SchemaBuilder, TEXT};
+/// use tantivy::{Autocomplete, Index};
+///
+/// # fn main() {
+/// let mut schema_builder = SchemaBuilder::default();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let author = schema_builder.add_text_field("author", TEXT);
+/// let mut autocomplete = Autocomplete::default();
+/// autocomplete.add_field(&mut schema_builder, title, 2.0);
+/// autocomplete.add_field(&mut schema_builder, author, 1.0);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// autocomplete.register_tokenizers(&index);
+/// {
+///     let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+///     let doc = doc!(title => "Harry Potter", author => "J. K. Rowling");
+///     index_writer.add_document(autocomplete.prepare_document(doc));
+///     index_writer.commit().unwrap();
+/// }
+/// index.load_searchers().unwrap();
+/// let searcher = index.searcher();
+/// let completions = autocomplete.complete(&*searcher, "Harr", 5).unwrap();
+/// assert_eq!(completions[0].text, "harry potter");
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Autocomplete {
+    fields: Vec<AutocompleteField>,
+}
+
+fn completion_field_name(field_name: &str) -> String {
+    format!("{}_completion", field_name)
+}
+
+fn edge_ngram_field_name(field_name: &str) -> String {
+    format!("{}_prefix", field_name)
+}
+
+fn indexed_text_options(tokenizer_name: &str) -> TextOptions {
+    let text_field_indexing = TextFieldIndexing::default()
+        .set_tokenizer(tokenizer_name)
+        .set_index_option(IndexRecordOption::Basic);
+    TextOptions::default().set_indexing_options(text_field_indexing)
+}
+
+/// Returns the number of documents of the segment having the term,
+/// not counting the deleted documents.
+fn live_doc_freq(
+    segment_reader: &SegmentReader,
+    inverted_index: &InvertedIndexReader,
+    term_info: &TermInfo,
+) -> Result<u32> {
+    if !segment_reader.has_deletes() {
+        return Ok(term_info.doc_freq);
+    }
+    let mut postings =
+        inverted_index.read_postings_from_terminfo(term_info, IndexRecordOption::Basic)?;
+    let mut doc_freq = 0u32;
+    while postings.advance() {
+        doc_freq += 1;
+    }
+    Ok(doc_freq)
+}
+
+/// Returns the smallest edit distance between `prefix` and a prefix of `text`,
+/// or `None` if it is greater than `max_distance`.
+fn prefix_edit_distance(prefix: &[char], text: &str, max_distance: usize) -> Option<usize> {
+    // distances between the prefixes of `prefix` and the current prefix of `text`.
+    let mut distances: Vec<usize> = (0..prefix.len() + 1).collect();
+    let mut min_distance = distances[prefix.len()];
+    for c in text.chars() {
+        let mut previous_diagonal = distances[0];
+        distances[0] += 1;
+        for i in 1..prefix.len() + 1 {
+            let substitution_cost = if prefix[i - 1] == c { 0 } else { 1 };
+            let distance = (previous_diagonal + substitution_cost)
+                .min(distances[i] + 1)
+                .min(distances[i - 1] + 1);
+            previous_diagonal = distances[i];
+            distances[i] = distance;
+        }
+        min_distance = min_distance.min(distances[prefix.len()]);
+        if distances.iter().all(|&distance| distance > max_distance) {
+            break;
+        }
+    }
+    if min_distance <= max_distance {
+        Some(min_distance)
+    } else {
+        None
+    }
+}
+
+impl Autocomplete {
+    /// Adds a source field, of the given weight, and adds its completion
+    /// and edge n-gram fields to the schema.
+    pub fn add_field(&mut self, schema_builder: &mut SchemaBuilder, source: Field, weight: Score) {
+        let field_name = schema_builder.get_field_name(source).to_string();
+        let completion = schema_builder.add_text_field(
+            &completion_field_name(&field_name),
+            indexed_text_options(COMPLETION_TOKENIZER),
+        );
+        let edge_ngram = schema_builder.add_text_field(
+            &edge_ngram_field_name(&field_name),
+            indexed_text_options(EDGE_NGRAM_TOKENIZER),
+        );
+        self.fields.push(AutocompleteField {
+            source,
+            completion,
+            edge_ngram,
+            weight,
+        });
+    }
+
+    /// Creates the `Autocomplete` of an existing index, given its
+    /// source fields and their weights.
+    ///
+    /// Fails if the completion and edge n-gram fields of a source field
+    /// are not part of the schema.
+    pub fn for_schema(schema: &Schema, source_fields: &[(Field, Score)]) -> Result<Autocomplete> {
+        let mut fields = Vec::with_capacity(source_fields.len());
+        for &(source, weight) in source_fields {
+            let field_name = schema.get_field_name(source);
+            let get_field = |name: String| {
+                schema
+                    .get_field(&name)
+                    .ok_or_else(|| ErrorKind::SchemaError(name.clone()))
+            };
+            fields.push(AutocompleteField {
+                source,
+                completion: get_field(completion_field_name(field_name))?,
+                edge_ngram: get_field(edge_ngram_field_name(field_name))?,
+                weight,
+            });
+        }
+        Ok(Autocomplete { fields })
+    }
+
+    /// Registers the tokenizers of the completion and edge n-gram fields.
+    pub fn register_tokenizers(&self, index: &Index) {
+        let tokenizers = index.tokenizers();
+        tokenizers.register(COMPLETION_TOKENIZER, RawTokenizer.filter(LowerCaser));
+        tokenizers.register(
+            EDGE_NGRAM_TOKENIZER,
+            EdgeNgramTokenizer::new(1, MAX_PREFIX_LEN).filter(LowerCaser),
+        );
+    }
+
+    /// Copies the text values of the source fields of the document
+    /// to their completion and edge n-gram fields.
+    pub fn prepare_document(&self, mut doc: Document) -> Document {
+        for field in &self.fields {
+            let texts: Vec<String> = doc.get_all(field.source)
+                .into_iter()
+                .filter_map(|value| match *value {
+                    Value::Str(ref text) => Some(text.clone()),
+                    _ => None,
+                })
+                .collect();
+            for text in &texts {
+                doc.add_text(field.completion, text);
+                doc.add_text(field.edge_ngram, text);
+            }
+        }
+        doc
+    }
+
+    /// Returns the query matching the documents whose value starts with `prefix`
+    /// in one of the source fields, scored according to the weights of the fields.
+    pub fn prefix_query(&self, prefix: &str) -> Box<Query> {
+        let prefix: String = prefix.to_lowercase().chars().take(MAX_PREFIX_LEN).collect();
+        let subqueries: Vec<(Occur, Box<Query>)> = self.fields
+            .iter()
+            .map(|field| {
+                let term = Term::from_field_text(field.edge_ngram, &prefix);
+                let term_query: Box<Query> = box TermQuery::new(term, IndexRecordOption::Basic);
+                let boost_query: Box<Query> = box BoostQuery::new(term_query, field.weight);
+                (Occur::Should, boost_query)
+            })
+            .collect();
+        box BooleanQuery::from(subqueries)
+    }
+
+    /// Returns at most `limit` completions of `prefix`, by decreasing score.
+    ///
+    /// The values starting with `prefix` come first. If there are fewer than
+    /// `limit` of them, they are followed by the values starting with a prefix
+    /// at one edit of `prefix` (with the same first character), whose score
+    /// is halved. The prefix is matched case insensitively.
+    ///
+    /// In order to bound the cost of a completion, only the first
+    /// `MAX_PREFIX_TERMS` values starting with the prefix, in lexicographic
+    /// order, are scored in each of the segments and fields: with a very
+    /// short prefix, a value with a high score may be missing.
+    pub fn complete(
+        &self,
+        searcher: &Searcher,
+        prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<Completion>> {
+        let prefix = prefix.to_lowercase();
+        let mut completions = self.completions(searcher, &prefix, |text| {
+            if text.starts_with(&prefix) {
+                Some(1.0)
+            } else {
+                None
+            }
+        })?;
+        let prefix_chars: Vec<char> = prefix.chars().collect();
+        if completions.len() < limit && prefix_chars.len() >= MIN_FUZZY_PREFIX_LEN {
+            let first_char: String = prefix_chars[..1].iter().collect();
+            let fuzzy_completions = self.completions(searcher, &first_char, |text| {
+                if text.starts_with(&prefix) {
+                    return None;
+                }
+                prefix_edit_distance(&prefix_chars, text, 1).map(|_| FUZZY_COMPLETION_PENALTY)
+            })?;
+            completions.extend(fuzzy_completions.into_iter().map(|mut completion| {
+                completion.fuzzy = true;
+                completion
+            }));
+        }
+        completions.truncate(limit);
+        Ok(completions)
+    }
+
+    /// Returns the values of the completion fields starting with `prefix`
+    /// that are accepted by `score_factor`, sorted by decreasing score.
+    fn completions<F>(
+        &self,
+        searcher: &Searcher,
+        prefix: &str,
+        score_factor: F,
+    ) -> Result<Vec<Completion>>
+    where
+        F: Fn(&str) -> Option<Score>,
+    {
+        let mut scores: HashMap<String, Score> = HashMap::new();
+        for field in &self.fields {
+            for segment_reader in searcher.segment_readers() {
+                let inverted_index = segment_reader.inverted_index(field.completion)?;
+                for (term_bytes, term_info) in
+                    inverted_index.prefix_terms(prefix.as_bytes(), MAX_PREFIX_TERMS)
+                {
+                    let text = match String::from_utf8(term_bytes) {
+                        Ok(text) => text,
+                        Err(_) => continue,
+                    };
+                    if let Some(factor) = score_factor(&text) {
+                        let doc_freq = live_doc_freq(segment_reader, &inverted_index, &term_info)?;
+                        if doc_freq == 0 {
+                            continue;
+                        }
+                        let score = field.weight * factor * doc_freq as Score;
+                        *scores.entry(text).or_insert(0.0) += score;
+                    }
+                }
+            }
+        }
+        let mut completions: Vec<Completion> = scores
+            .into_iter()
+            .map(|(text, score)| Completion {
+                text,
+                score,
+                fuzzy: false,
+            })
+            .collect();
+        completions.sort_by(|left, right| {
+            right
+                .score
+                .partial_cmp(&left.score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| left.text.cmp(&right.text))
+        });
+        Ok(completions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{prefix_edit_distance, Autocomplete};
+    use collector::TopCollector;
+    use schema::{SchemaBuilder, Term, STORED, TEXT};
+    use Index;
+    use Score;
+
+    #[test]
+    fn test_prefix_edit_distance() {
+        let prefix: Vec<char> = "harr".chars().collect();
+        assert_eq!(prefix_edit_distance(&prefix, "harry potter", 1), Some(0));
+        assert_eq!(prefix_edit_distance(&prefix, "hary potter", 1), Some(1));
+        assert_eq!(prefix_edit_distance(&prefix, "hurry up", 1), Some(1));
+        assert_eq!(prefix_edit_distance(&prefix, "hobbit", 1), None);
+        assert_eq!(prefix_edit_distance(&prefix, "ha", 1), None);
+    }
+
+    #[test]
+    fn test_autocomplete() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT | STORED);
+        let author = schema_builder.add_text_field("author", TEXT);
+        let mut autocomplete = Autocomplete::default();
+        autocomplete.add_field(&mut schema_builder, title, 2.0);
+        autocomplete.add_field(&mut schema_builder, author, 1.0);
+        let index = Index::create_in_ram(schema_builder.build());
+        autocomplete.register_tokenizers(&index);
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for doc in vec![
+                doc!(title => "Harry Potter", author => "J. K. Rowling"),
+                doc!(title => "Harry Potter", author => "Someone Else"),
+                doc!(title => "Hard Times", author => "Charles Dickens"),
+                doc!(title => "The Hobbit", author => "Harriet Tolkien"),
+                doc!(title => "Hurry Up", author => "Anonymous"),
+            ] {
+                index_writer.add_document(autocomplete.prepare_document(doc));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+
+        let completions = autocomplete.complete(&*searcher, "HAR", 10).unwrap();
+        let texts: Vec<(&str, Score, bool)> = completions
+            .iter()
+            .map(|completion| (completion.text.as_str(), completion.score, completion.fuzzy))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("harry potter", 4.0, false),
+                ("hard times", 2.0, false),
+                ("harriet tolkien", 1.0, false),
+                ("hurry up", 1.0, true),
+            ]
+        );
+        let completions = autocomplete.complete(&*searcher, "harr", 2).unwrap();
+        assert_eq!(completions.len(), 2);
+        assert_eq!(completions[0].text, "harry potter");
+        assert_eq!(completions[1].text, "harriet tolkien");
+        let completions = autocomplete.complete(&*searcher, "harr", 10).unwrap();
+        assert_eq!(completions.len(), 4);
+        assert_eq!(completions[2].text, "hard times");
+        assert!(completions[2].fuzzy);
+        assert_eq!(completions[3].text, "hurry up");
+        assert_eq!(completions[3].score, 1.0);
+        assert!(autocomplete.complete(&*searcher, "xyz", 10).unwrap().is_empty());
+
+        let mut top_collector = TopCollector::with_limit(10);
+        searcher
+            .search(&*autocomplete.prefix_query("Harr"), &mut top_collector)
+            .unwrap();
+        assert_eq!(top_collector.docs().len(), 3);
+
+        let autocomplete = Autocomplete::for_schema(&index.schema(), &[(title, 2.0)]).unwrap();
+        let completions = autocomplete.complete(&*searcher, "the", 10).unwrap();
+        assert_eq!(completions[0].text, "the hobbit");
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let schema = schema_builder.build();
+        assert!(Autocomplete::for_schema(&schema, &[(title, 1.0)]).is_err());
+    }
+
+    #[test]
+    fn test_autocomplete_deleted_docs() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let mut autocomplete = Autocomplete::default();
+        autocomplete.add_field(&mut schema_builder, title, 1.0);
+        let index = Index::create_in_ram(schema_builder.build());
+        autocomplete.register_tokenizers(&index);
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for doc in vec![
+                doc!(title => "Harry Potter"),
+                doc!(title => "Harry Potter"),
+                doc!(title => "Hard Times"),
+                doc!(title => "Harriet"),
+            ] {
+                index_writer.add_document(autocomplete.prepare_document(doc));
+            }
+            index_writer.commit().unwrap();
+            index_writer.delete_term(Term::from_field_text(title, "potter"));
+            index_writer.delete_term(Term::from_field_text(title, "harriet"));
+            index_writer.add_document(autocomplete.prepare_document(doc!(title => "Harry Potter")));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let completions = autocomplete.complete(&*searcher, "har", 10).unwrap();
+        let texts: Vec<(&str, Score)> = completions
+            .iter()
+            .map(|completion| (completion.text.as_str(), completion.score))
+            .collect();
+        assert_eq!(texts, vec![("hard times", 1.0), ("harry potter", 1.0)]);
+    }
+}
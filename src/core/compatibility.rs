@@ -0,0 +1,171 @@
+use core::{Index, IndexMeta, SegmentId};
+use postings::PostingsBlockSize;
+use std::fmt;
+
+/// Optional feature of the segment format: some fields record
+/// the offsets of their tokens in the positions file.
+pub const OFFSETS_FEATURE: &str = "offsets";
+
+/// Optional feature of the segment format: the postings blocks
+/// do not have the default size of 128 documents.
+/// (See `IndexSettings::postings_block_size`)
+pub const POSTINGS_BLOCK_SIZE_FEATURE: &str = "postings_block_size";
+
+/// Optional feature of the segment format: the segment has a `.docmap`
+/// file associating the doc ids of the segments it was merged from.
+/// (See `IndexWriter::set_doc_id_mapping`)
+pub const DOC_ID_MAPPING_FEATURE: &str = "doc_id_mapping";
+
+/// Optional feature of the segment format: the fast field file of the
+/// segment was rewritten. (See `Index::backfill_fast_field`)
+pub const FAST_FIELDS_GENERATION_FEATURE: &str = "fast_fields_generation";
+
+/// The optional features supported by this version of tantivy.
+const SUPPORTED_FEATURES: [&str; 4] = [
+    OFFSETS_FEATURE,
+    POSTINGS_BLOCK_SIZE_FEATURE,
+    DOC_ID_MAPPING_FEATURE,
+    FAST_FIELDS_GENERATION_FEATURE,
+];
+
+/// Returns the optional features used by the segments written
+/// by the index, given its schema and its settings.
+pub(crate) fn segment_features(index: &Index) -> Vec<String> {
+    let mut features = vec![];
+    let has_offsets = index.schema().fields().iter().any(|field_entry| {
+        field_entry
+            .field_type()
+            .get_index_record_option()
+            .map(|record_option| record_option.has_offsets())
+            .unwrap_or(false)
+    });
+    if has_offsets {
+        features.push(OFFSETS_FEATURE.to_string());
+    }
+    if index.settings().postings_block_size != PostingsBlockSize::default() {
+        features.push(POSTINGS_BLOCK_SIZE_FEATURE.to_string());
+    }
+    features
+}
+
+/// Lists the optional features used by the segments of an index
+/// that are not supported by this version of tantivy.
+///
+/// The features are recorded in the `SegmentMeta` of the segments
+/// using them, so that opening an index written by a more recent
+/// version fails upfront rather than in the middle of a query.
+/// (See `Index::compatibility_report`)
+#[derive(Clone, Debug, Default)]
+pub struct CompatibilityReport {
+    unsupported_features: Vec<(SegmentId, Vec<String>)>,
+}
+
+impl CompatibilityReport {
+    /// Checks the features of the segments of the given metas.
+    pub fn for_metas(metas: &IndexMeta) -> CompatibilityReport {
+        let unsupported_features = metas
+            .segments
+            .iter()
+            .filter_map(|segment_meta| {
+                let unsupported_features: Vec<String> = segment_meta
+                    .features()
+                    .iter()
+                    .filter(|feature| !SUPPORTED_FEATURES.contains(&feature.as_str()))
+                    .cloned()
+                    .collect();
+                if unsupported_features.is_empty() {
+                    None
+                } else {
+                    Some((segment_meta.id(), unsupported_features))
+                }
+            })
+            .collect();
+        CompatibilityReport {
+            unsupported_features,
+        }
+    }
+
+    /// Returns true iff all of the features used by the segments are supported.
+    pub fn is_compatible(&self) -> bool {
+        self.unsupported_features.is_empty()
+    }
+
+    /// Returns the segments using unsupported features,
+    /// together with these features.
+    pub fn unsupported_features(&self) -> &[(SegmentId, Vec<String>)] {
+        &self.unsupported_features
+    }
+}
+
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_compatible() {
+            return write!(f, "All of the features of the segments are supported.");
+        }
+        write!(f, "Unsupported features:")?;
+        for &(ref segment_id, ref features) in &self.unsupported_features {
+            write!(
+                f,
+                " segment {} uses {};",
+                segment_id.short_uuid_string(),
+                features.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use core::META_FILEPATH;
+    use directory::{Directory, MmapDirectory};
+    use error::{Error, ErrorKind};
+    use schema::{IndexRecordOption, SchemaBuilder, TextFieldIndexing, TextOptions};
+    use serde_json::{self, Value};
+    use tempdir::TempDir;
+    use super::OFFSETS_FEATURE;
+    use Index;
+
+    #[test]
+    fn test_compatibility_report() {
+        let temp_dir = TempDir::new("compatibility").unwrap();
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field_indexing = TextFieldIndexing::default()
+            .set_tokenizer("default")
+            .set_index_option(IndexRecordOption::WithFreqsPositionsAndOffsets);
+        let text_options = TextOptions::default().set_indexing_options(text_field_indexing);
+        let text_field = schema_builder.add_text_field("text", text_options);
+        {
+            let index = Index::create(temp_dir.path(), schema_builder.build()).unwrap();
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "hello"));
+            index_writer.commit().unwrap();
+            let metas = index.load_metas().unwrap();
+            assert_eq!(metas.segments[0].features(), &[OFFSETS_FEATURE.to_string()]);
+        }
+        let report = Index::compatibility_report(temp_dir.path()).unwrap();
+        assert!(report.is_compatible());
+        assert!(Index::open(temp_dir.path()).is_ok());
+
+        // a segment written by a version of tantivy with more features.
+        let mut directory = MmapDirectory::open(temp_dir.path()).unwrap();
+        let meta_data = directory.atomic_read(&META_FILEPATH).unwrap();
+        let mut metas: Value = serde_json::from_slice(&meta_data).unwrap();
+        metas["segments"][0]["features"] =
+            Value::Array(vec![Value::from("offsets"), Value::from("bitmap_postings")]);
+        let meta_data = serde_json::to_vec(&metas).unwrap();
+        directory.atomic_write(&META_FILEPATH, &meta_data).unwrap();
+
+        let report = Index::compatibility_report(temp_dir.path()).unwrap();
+        assert!(!report.is_compatible());
+        assert_eq!(report.unsupported_features().len(), 1);
+        assert_eq!(report.unsupported_features()[0].1, vec!["bitmap_postings".to_string()]);
+        match Index::open(temp_dir.path()) {
+            Err(Error(ErrorKind::IncompatibleIndex(report), _)) => {
+                assert!(report.contains("bitmap_postings"));
+            }
+            _ => panic!("Expected IncompatibleIndex error"),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use DocId;
+use Result;
+use byteorder::{ByteOrder, LittleEndian};
+use common::BinarySerializable;
+use core::SegmentId;
+use directory::ReadOnlySource;
+use std::collections::HashMap;
+use std::io;
+use std::io::Write;
+
+/// Doc id of the documents that were deleted at the time of the merge.
+const DELETED_DOC: DocId = DocId::max_value();
+
+/// Associates the doc ids of the segments that were merged
+/// into a segment to the doc ids of the merged segment.
+///
+/// The mapping is only recorded by merges if it was
+/// enabled via `IndexWriter::set_doc_id_mapping(true)`.
+/// It is stored in the `.docmap` file of the merged segment,
+/// and only covers the last merge: the segments that got merged
+/// into the segments that got merged are not part of it.
+///
+/// # File format
+///
+/// - the number of merged segments, as a `u32`
+/// - for each merged segment, its `SegmentId` and its `max_doc` as a `u32`
+/// - for each merged segment, `max_doc` doc ids (`u32`, little endian).
+///   Deleted documents are associated to `u32::max_value()`.
+pub struct DocIdMapping {
+    data: ReadOnlySource,
+    // segment id -> (offset of its doc ids within `data`, max_doc)
+    segments: HashMap<SegmentId, (usize, DocId)>,
+}
+
+impl DocIdMapping {
+    /// Opens a `DocIdMapping` from its serialized form.
+    pub(crate) fn open(data: ReadOnlySource) -> Result<DocIdMapping> {
+        let mut segments = HashMap::new();
+        let offset = {
+            let mut cursor = data.as_slice();
+            let num_segments = u32::deserialize(&mut cursor)?;
+            let mut offset = 4 + num_segments as usize * (16 + 4);
+            for _ in 0..num_segments {
+                let segment_id = SegmentId::deserialize(&mut cursor)?;
+                let max_doc = u32::deserialize(&mut cursor)?;
+                segments.insert(segment_id, (offset, max_doc));
+                offset += max_doc as usize * 4;
+            }
+            offset
+        };
+        if offset != data.as_slice().len() {
+            let msg = "Truncated doc id mapping";
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg).into());
+        }
+        Ok(DocIdMapping { data, segments })
+    }
+
+    /// Returns the ids of the segments that were merged.
+    pub fn segment_ids(&self) -> Vec<SegmentId> {
+        self.segments.keys().cloned().collect()
+    }
+
+    /// Returns the doc id, within the merged segment,
+    /// of the document `doc` of the segment `segment_id`.
+    ///
+    /// Returns `None` if the segment is not part of the merge,
+    /// or if the document was already deleted at the time of the merge.
+    pub fn get(&self, segment_id: SegmentId, doc: DocId) -> Option<DocId> {
+        let &(offset, max_doc) = self.segments.get(&segment_id)?;
+        if doc >= max_doc {
+            return None;
+        }
+        let doc_offset = offset + doc as usize * 4;
+        let new_doc = LittleEndian::read_u32(&self.data.as_slice()[doc_offset..doc_offset + 4]);
+        if new_doc == DELETED_DOC {
+            None
+        } else {
+            Some(new_doc)
+        }
+    }
+}
+
+/// Serializes a `DocIdMapping`.
+///
+/// `segments` contains, for each merged segment, the new doc id
+/// of each of its documents, or `None` if the document is deleted.
+pub(crate) fn write_doc_id_mapping<W: Write>(
+    segments: &[(SegmentId, Vec<Option<DocId>>)],
+    writer: &mut W,
+) -> io::Result<()> {
+    (segments.len() as u32).serialize(writer)?;
+    for &(ref segment_id, ref doc_ids) in segments {
+        segment_id.serialize(writer)?;
+        (doc_ids.len() as u32).serialize(writer)?;
+    }
+    for &(_, ref doc_ids) in segments {
+        for doc_id in doc_ids {
+            let mut buffer = [0u8; 4];
+            LittleEndian::write_u32(&mut buffer, doc_id.unwrap_or(DELETED_DOC));
+            writer.write_all(&buffer)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{write_doc_id_mapping, DocIdMapping};
+    use core::SegmentId;
+    use directory::ReadOnlySource;
+
+    #[test]
+    fn test_doc_id_mapping() {
+        let first_segment = SegmentId::generate_random();
+        let second_segment = SegmentId::generate_random();
+        let mut buffer: Vec<u8> = vec![];
+        write_doc_id_mapping(
+            &[
+                (first_segment, vec![Some(0), None, Some(1)]),
+                (second_segment, vec![None, Some(2)]),
+            ],
+            &mut buffer,
+        ).unwrap();
+        let doc_id_mapping = DocIdMapping::open(ReadOnlySource::from(buffer)).unwrap();
+        assert_eq!(doc_id_mapping.segment_ids().len(), 2);
+        assert_eq!(doc_id_mapping.get(first_segment, 0), Some(0));
+        assert_eq!(doc_id_mapping.get(first_segment, 1), None);
+        assert_eq!(doc_id_mapping.get(first_segment, 2), Some(1));
+        assert_eq!(doc_id_mapping.get(first_segment, 3), None);
+        assert_eq!(doc_id_mapping.get(second_segment, 0), None);
+        assert_eq!(doc_id_mapping.get(second_segment, 1), Some(2));
+        assert_eq!(doc_id_mapping.get(SegmentId::generate_random(), 0), None);
+    }
+
+    #[test]
+    fn test_doc_id_mapping_truncated() {
+        let mut buffer: Vec<u8> = vec![];
+        write_doc_id_mapping(
+            &[(SegmentId::generate_random(), vec![Some(0), Some(1)])],
+            &mut buffer,
+        ).unwrap();
+        buffer.pop();
+        assert!(DocIdMapping::open(ReadOnlySource::from(buffer)).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use Result;
+use DocId;
+use SegmentLocalId;
+use core::Searcher;
+use fastfield::{FastFieldReader, FastValue};
+use query::{Scorer, Weight};
+use schema::Field;
+
+/// Maximum number of values in a `FieldValuesBatch`.
+const BATCH_LEN: usize = 1_024;
+
+/// Values of a fast field, for some of the documents
+/// matching a query within a given segment.
+///
+/// See `Searcher::fetch_field_values`.
+pub struct FieldValuesBatch<Item: FastValue> {
+    segment_ord: SegmentLocalId,
+    docs: Vec<DocId>,
+    values: Vec<Item>,
+}
+
+impl<Item: FastValue> FieldValuesBatch<Item> {
+    /// Returns the ordinal of the segment, within the searcher,
+    /// the documents belong to.
+    pub fn segment_ord(&self) -> SegmentLocalId {
+        self.segment_ord
+    }
+
+    /// Returns the matching documents, in increasing order.
+    pub fn docs(&self) -> &[DocId] {
+        &self.docs
+    }
+
+    /// Returns the values of the fast field for the matching documents.
+    ///
+    /// `values()[i]` is the value associated to `docs()[i]`.
+    pub fn values(&self) -> &[Item] {
+        &self.values
+    }
+}
+
+/// Iterator over the values of a fast field for the documents matching a query.
+///
+/// The values are yielded by batches, segment after segment.
+/// A batch never contains the values of several segments.
+///
+/// See `Searcher::fetch_field_values`.
+pub struct FieldValues<'a, Item: FastValue> {
+    searcher: &'a Searcher,
+    weight: Box<Weight>,
+    field: Field,
+    next_segment_ord: usize,
+    segment: Option<(SegmentLocalId, Box<Scorer>, FastFieldReader<Item>)>,
+    doc_buffer: Vec<DocId>,
+}
+
+impl<'a, Item: FastValue> FieldValues<'a, Item> {
+    pub(crate) fn new(
+        searcher: &'a Searcher,
+        weight: Box<Weight>,
+        field: Field,
+    ) -> FieldValues<'a, Item> {
+        FieldValues {
+            searcher,
+            weight,
+            field,
+            next_segment_ord: 0,
+            segment: None,
+            doc_buffer: vec![0; BATCH_LEN],
+        }
+    }
+
+    fn open_next_segment(&mut self) -> Result<bool> {
+        let segment_readers = self.searcher.segment_readers();
+        if self.next_segment_ord == segment_readers.len() {
+            return Ok(false);
+        }
+        let segment_reader = &segment_readers[self.next_segment_ord];
+        let fast_field_reader = segment_reader.fast_field_reader(self.field)?;
+        let scorer = self.weight.scorer(segment_reader)?;
+        self.segment = Some((
+            self.next_segment_ord as SegmentLocalId,
+            scorer,
+            fast_field_reader,
+        ));
+        self.next_segment_ord += 1;
+        Ok(true)
+    }
+}
+
+impl<'a, Item: FastValue> Iterator for FieldValues<'a, Item> {
+    type Item = Result<FieldValuesBatch<Item>>;
+
+    fn next(&mut self) -> Option<Result<FieldValuesBatch<Item>>> {
+        loop {
+            let mut batch = None;
+            let mut is_exhausted = true;
+            if let Some((segment_ord, ref mut scorer, ref fast_field_reader)) = self.segment {
+                let num_docs = scorer.fill_buffer(&mut self.doc_buffer[..]);
+                // A short batch means that the scorer is exhausted,
+                // and it must not be advanced anymore.
+                is_exhausted = num_docs < self.doc_buffer.len();
+                if num_docs > 0 {
+                    let docs = self.doc_buffer[..num_docs].to_vec();
+                    let values = docs.iter()
+                        .map(|&doc| fast_field_reader.get(doc))
+                        .collect();
+                    batch = Some(FieldValuesBatch {
+                        segment_ord,
+                        docs,
+                        values,
+                    });
+                }
+            }
+            if is_exhausted {
+                self.segment = None;
+            }
+            if let Some(batch) = batch {
+                return Some(Ok(batch));
+            }
+            match self.open_next_segment() {
+                Ok(true) => {}
+                Ok(false) => {
+                    return None;
+                }
+                Err(e) => {
+                    // The remaining segments are skipped.
+                    self.next_segment_ord = self.searcher.segment_readers().len();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
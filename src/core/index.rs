@@ -9,24 +9,28 @@ use core::SegmentId;
 use directory::{Directory, MmapDirectory, RAMDirectory};
 use indexer::index_writer::open_index_writer;
 use core::searcher::Searcher;
-use std::convert::From;
 use num_cpus;
 use super::segment::Segment;
 use core::SegmentReader;
+use core::{IndexReader, ReloadPolicy};
 use super::pool::Pool;
 use core::SegmentMeta;
 use super::pool::LeasedItem;
-use std::path::Path;
-use core::IndexMeta;
+use std::path::{Path, PathBuf};
+use core::{CompatibilityReport, IndexChanges, IndexMeta, IndexSettings};
 use indexer::DirectoryLock;
+use indexer::{backfill_fast_field, FastFieldValueProvider};
+use schema::Field;
 use IndexWriter;
 use directory::ManagedDirectory;
 use core::META_FILEPATH;
 use super::segment::create_segment;
 use indexer::segment_updater::save_new_metas;
 use tokenizer::TokenizerManager;
+use core::index_snapshot::{required_segment_files, IndexSnapshot};
+use core::IndexMetrics;
 
-const NUM_SEARCHERS: usize = 12;
+pub(crate) const NUM_SEARCHERS: usize = 12;
 
 fn load_metas(directory: &Directory) -> Result<IndexMeta> {
     let meta_data = directory.atomic_read(&META_FILEPATH)?;
@@ -38,8 +42,10 @@ fn load_metas(directory: &Directory) -> Result<IndexMeta> {
 pub struct Index {
     directory: ManagedDirectory,
     schema: Schema,
+    settings: IndexSettings,
     searcher_pool: Arc<Pool<Searcher>>,
     tokenizers: TokenizerManager,
+    metrics: Arc<IndexMetrics>,
 }
 
 impl Index {
@@ -67,11 +73,24 @@ impl Index {
         Index::from_directory(directory, schema)
     }
 
+    /// Accessor to the settings of the index.
+    pub fn settings(&self) -> &IndexSettings {
+        &self.settings
+    }
+
     /// Accessor for the tokenizer manager.
     pub fn tokenizers(&self) -> &TokenizerManager {
         &self.tokenizers
     }
 
+    /// Accessor to the metrics of the index.
+    ///
+    /// The metrics are shared by all of the clones of the index,
+    /// and are disabled by default. (See `IndexMetrics::enable()`)
+    pub fn metrics(&self) -> &Arc<IndexMetrics> {
+        &self.metrics
+    }
+
     /// Creates a new index in a temp directory.
     ///
     /// The index will use the `MMapDirectory` in a newly created directory.
@@ -87,26 +106,65 @@ impl Index {
     }
 
     /// Creates a new index given a directory and an `IndexMeta`.
+    ///
+    /// Fails if the segments use features that are not supported.
     fn create_from_metas(directory: ManagedDirectory, metas: &IndexMeta) -> Result<Index> {
+        let compatibility_report = CompatibilityReport::for_metas(metas);
+        if !compatibility_report.is_compatible() {
+            bail!(ErrorKind::IncompatibleIndex(compatibility_report.to_string()));
+        }
         let schema = metas.schema.clone();
         let index = Index {
             directory,
             schema,
+            settings: metas.settings.clone(),
             searcher_pool: Arc::new(Pool::new()),
             tokenizers: TokenizerManager::default(),
+            metrics: Arc::new(IndexMetrics::default()),
         };
         index.load_searchers()?;
         Ok(index)
     }
 
     /// Create a new index from a directory.
-    pub fn from_directory(mut directory: ManagedDirectory, schema: Schema) -> Result<Index> {
-        save_new_metas(schema.clone(), 0, directory.borrow_mut())?;
-        let metas = IndexMeta::with_schema(schema);
+    pub fn from_directory(directory: ManagedDirectory, schema: Schema) -> Result<Index> {
+        Index::from_directory_with_settings(directory, schema, IndexSettings::default())
+    }
+
+    /// Create a new index from a directory, with the given settings.
+    ///
+    /// The settings are saved in the `meta.json` file, and cannot
+    /// be changed afterwards.
+    pub fn from_directory_with_settings(
+        mut directory: ManagedDirectory,
+        schema: Schema,
+        settings: IndexSettings,
+    ) -> Result<Index> {
+        if let Some(ref source_options) = settings.source {
+            if source_options.resolve_field(&schema).is_none() {
+                bail!(ErrorKind::SchemaError(format!(
+                    "The source field '{}' should be a stored text field",
+                    source_options.field_name()
+                )));
+            }
+        }
+        save_new_metas(schema.clone(), settings.clone(), 0, directory.borrow_mut())?;
+        let metas = IndexMeta::with_schema(schema, settings);
         Index::create_from_metas(directory, &metas)
     }
 
+    /// Checks whether the features used by the segments of the index
+    /// in the given path are supported, without opening the index.
+    pub fn compatibility_report<P: AsRef<Path>>(directory_path: P) -> Result<CompatibilityReport> {
+        let mmap_directory = MmapDirectory::open(directory_path)?;
+        let metas = load_metas(&mmap_directory)?;
+        Ok(CompatibilityReport::for_metas(&metas))
+    }
+
     /// Opens a new directory from an index path.
+    ///
+    /// Fails with `ErrorKind::IncompatibleIndex` if the segments use
+    /// features that are not supported by this version of tantivy.
     pub fn open<P: AsRef<Path>>(directory_path: P) -> Result<Index> {
         let mmap_directory = MmapDirectory::open(directory_path)?;
         let directory = ManagedDirectory::new(mmap_directory)?;
@@ -114,11 +172,55 @@ impl Index {
         Index::create_from_metas(directory, &metas)
     }
 
+    /// Opens an index copied from an `IndexSnapshot`.
+    /// (See `IndexSnapshot::copy_to`)
+    ///
+    /// Returns an error if one of the files required by the
+    /// segments of the copy is missing.
+    ///
+    /// The files of the copy are registered as managed by tantivy,
+    /// so that they get garbage collected once they are merged away.
+    pub fn restore_from<P: AsRef<Path>>(directory_path: P) -> Result<Index> {
+        let mmap_directory = MmapDirectory::open(directory_path)?;
+        let mut directory = ManagedDirectory::new(mmap_directory)?;
+        let metas = load_metas(&directory)?;
+        let files: Vec<PathBuf> = metas
+            .segments
+            .iter()
+            .flat_map(required_segment_files)
+            .collect();
+        if let Some(missing_file) = files.iter().find(|path| !directory.exists(path)) {
+            bail!(ErrorKind::PathDoesNotExist(missing_file.clone()));
+        }
+        directory.register_files_as_managed(&files)?;
+        Index::create_from_metas(directory, &metas)
+    }
+
+    /// Takes a snapshot of the last commit of the index.
+    ///
+    /// The files of the snapshot are protected from garbage collection
+    /// until the snapshot is dropped, so that they can be copied
+    /// while indexing and merging continue.
+    pub fn snapshot(&self) -> Result<IndexSnapshot> {
+        IndexSnapshot::take(&self.directory)
+    }
+
     /// Reads the index meta file from the directory.
     pub fn load_metas(&self) -> Result<IndexMeta> {
         load_metas(self.directory())
     }
 
+    /// Returns the adds and the deletes committed after the commit
+    /// of opstamp `opstamp`, e.g. the commit of a previous generation of searchers.
+    ///
+    /// Returns `None` if `opstamp` is not the opstamp of one of the
+    /// last commits, whose changes are kept in the `meta.json` file.
+    /// (See `IndexMeta::commits`)
+    pub fn changes_since(&self, opstamp: u64) -> Result<Option<IndexChanges>> {
+        let metas = self.load_metas()?;
+        Ok(IndexChanges::since(&metas, opstamp))
+    }
+
     /// Open a new index writer. Attempts to acquire a lockfile.
     ///
     /// The lockfile should be deleted on drop, but it is possible
@@ -161,6 +263,36 @@ impl Index {
         self.schema.clone()
     }
 
+    pub(crate) fn set_schema(&mut self, schema: Schema) {
+        self.schema = schema;
+    }
+
+    /// Makes the `u64` or `i64` field `field` a single-valued fast field
+    /// in the existing segments, without reindexing their documents.
+    ///
+    /// A new version of the fast field file of each segment is written,
+    /// with the values given by `value_provider`, e.g. `StoredFieldValues`
+    /// to read them from the doc store.
+    /// The segment metas and the schema are then swapped atomically
+    /// in the `meta.json` file.
+    ///
+    /// The searchers need to be reloaded to access the fast field
+    /// (See `.load_searchers()`), and the other clones of the `Index`
+    /// still have the previous schema: they need to be reopened.
+    ///
+    /// # Errors
+    /// If an `IndexWriter` is opened on the index, returns `Error::FileAlreadyExists`.
+    /// If `field` is not a `u64` or an `i64` field, or is already a fast field,
+    /// returns `Error::InvalidArgument`.
+    pub fn backfill_fast_field<P: FastFieldValueProvider>(
+        &mut self,
+        field: Field,
+        mut value_provider: P,
+    ) -> Result<()> {
+        let _directory_lock = DirectoryLock::lock(self.directory().box_clone())?;
+        backfill_fast_field(self, field, &mut value_provider)
+    }
+
     /// Returns the list of segments that are searchable
     pub fn searchable_segments(&self) -> Result<Vec<Segment>> {
         Ok(self.searchable_segment_metas()?
@@ -212,17 +344,38 @@ impl Index {
     /// published or after a merge.
     pub fn load_searchers(&self) -> Result<()> {
         let searchable_segments = self.searchable_segments()?;
-        let segment_readers: Vec<SegmentReader> = searchable_segments
-            .iter()
-            .map(SegmentReader::open)
-            .collect::<Result<_>>()?;
+        let segment_readers: Vec<SegmentReader> =
+            SegmentReader::open_all(&searchable_segments, num_cpus::get())?;
         let searchers = (0..NUM_SEARCHERS)
-            .map(|_| Searcher::from(segment_readers.clone()))
+            .map(|_| {
+                Searcher::new(
+                    self.schema(),
+                    segment_readers.clone(),
+                    self.tokenizers.clone(),
+                    Arc::clone(&self.metrics),
+                    self.settings.source.clone(),
+                )
+            })
             .collect();
         self.searcher_pool.publish_new_generation(searchers);
         Ok(())
     }
 
+    /// Creates an `IndexReader`, reloading its searchers after every commit.
+    ///
+    /// This is equivalent to `.reader_with_policy(ReloadPolicy::OnCommit)`.
+    pub fn reader(&self) -> Result<IndexReader> {
+        self.reader_with_policy(ReloadPolicy::OnCommit)
+    }
+
+    /// Creates an `IndexReader` with the given `ReloadPolicy`.
+    ///
+    /// The reader has its own pool of searchers, independent
+    /// from the one of `.searcher()`.
+    pub fn reader_with_policy(&self, reload_policy: ReloadPolicy) -> Result<IndexReader> {
+        IndexReader::open(self.clone(), reload_policy)
+    }
+
     /// Returns a searcher
     ///
     /// This method should be called every single time a search
@@ -249,8 +402,10 @@ impl Clone for Index {
         Index {
             directory: self.directory.clone(),
             schema: self.schema.clone(),
+            settings: self.settings.clone(),
             searcher_pool: Arc::clone(&self.searcher_pool),
             tokenizers: self.tokenizers.clone(),
+            metrics: Arc::clone(&self.metrics),
         }
     }
 }
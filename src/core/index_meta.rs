@@ -1,8 +1,140 @@
-use schema::Schema;
+use schema::{Schema, SourceOptions};
 use core::SegmentMeta;
+use store::Compressor;
+use postings::PostingsBlockSize;
 use std::fmt;
 use serde_json;
 
+/// Settings of the `Index`, defined at its creation.
+///
+/// They are serialized in the `meta.json` file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexSettings {
+    /// Codec compressing the blocks of the doc store.
+    #[serde(default)]
+    pub store_compressor: Compressor,
+    /// Number of documents of the blocks of the posting lists.
+    #[serde(default)]
+    pub postings_block_size: PostingsBlockSize,
+    /// If set, the JSON representation of the documents is automatically
+    /// written in a stored field. (See `Searcher::source`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceOptions>,
+}
+
+/// Maximum number of commits whose changes are kept in the `meta.json` file.
+pub(crate) const MAX_COMMIT_HISTORY: usize = 100;
+
+/// Operations included in a commit.
+///
+/// Every operation of an `IndexWriter` is identified by its opstamp.
+/// Note that some opstamps are neither adds nor deletes,
+/// e.g. the ones consumed by the merges.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CommitChanges {
+    /// Opstamp of the previous commit.
+    pub previous_opstamp: u64,
+    /// Opstamp of the commit.
+    pub opstamp: u64,
+    /// Opstamps of the added documents, as `[start, end)` ranges.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub add_opstamps: Vec<(u64, u64)>,
+    /// Opstamps of the delete operations, including the deletes
+    /// of the documents replaced because of their unique key.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delete_opstamps: Vec<u64>,
+}
+
+impl CommitChanges {
+    pub(crate) fn new(previous_opstamp: u64) -> CommitChanges {
+        CommitChanges {
+            previous_opstamp,
+            ..CommitChanges::default()
+        }
+    }
+
+    pub(crate) fn record_add(&mut self, opstamp: u64) {
+        if let Some(&mut (_, ref mut end)) = self.add_opstamps.last_mut() {
+            if *end == opstamp {
+                *end += 1;
+                return;
+            }
+        }
+        self.add_opstamps.push((opstamp, opstamp + 1));
+    }
+
+    pub(crate) fn record_delete(&mut self, opstamp: u64) {
+        self.delete_opstamps.push(opstamp);
+    }
+
+    /// Returns the number of documents added in the commit.
+    pub fn num_added_docs(&self) -> u64 {
+        self.add_opstamps
+            .iter()
+            .map(|&(start, end)| end - start)
+            .sum()
+    }
+
+    /// Returns true iff the document added with the opstamp `opstamp`
+    /// is part of the commit.
+    pub fn contains_add(&self, opstamp: u64) -> bool {
+        self.add_opstamps
+            .iter()
+            .any(|&(start, end)| start <= opstamp && opstamp < end)
+    }
+
+    /// Returns true iff the delete operation with the opstamp `opstamp`
+    /// is part of the commit.
+    pub fn contains_delete(&self, opstamp: u64) -> bool {
+        self.delete_opstamps.binary_search(&opstamp).is_ok()
+    }
+}
+
+/// Summary of the changes committed to an `Index` since a given commit.
+/// (See `Index::changes_since`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexChanges {
+    commits: Vec<CommitChanges>,
+}
+
+impl IndexChanges {
+    pub(crate) fn since(index_meta: &IndexMeta, opstamp: u64) -> Option<IndexChanges> {
+        if opstamp == index_meta.opstamp {
+            return Some(IndexChanges { commits: vec![] });
+        }
+        index_meta
+            .commits
+            .iter()
+            .position(|commit| commit.previous_opstamp == opstamp)
+            .map(|start| IndexChanges {
+                commits: index_meta.commits[start..].to_vec(),
+            })
+    }
+
+    /// Returns the changes of each commit, from the oldest to the newest.
+    pub fn commits(&self) -> &[CommitChanges] {
+        &self.commits
+    }
+
+    /// Returns true iff nothing was committed since the opstamp.
+    pub fn is_empty(&self) -> bool {
+        self.commits.is_empty()
+    }
+
+    /// Returns the number of added documents.
+    pub fn num_added_docs(&self) -> u64 {
+        self.commits.iter().map(CommitChanges::num_added_docs).sum()
+    }
+
+    /// Returns the opstamps of the delete operations, in increasing order.
+    pub fn delete_opstamps(&self) -> Vec<u64> {
+        self.commits
+            .iter()
+            .flat_map(|commit| commit.delete_opstamps.iter().cloned())
+            .collect()
+    }
+}
+
 /// Meta information about the `Index`.
 ///
 /// This object is serialized on disk in the `meta.json` file.
@@ -10,23 +142,34 @@ use serde_json;
 /// * the searchable segments,
 /// * the index `docstamp`
 /// * the schema
+/// * the settings
+/// * the changes of the last commits
 ///
 #[derive(Clone, Serialize, Deserialize)]
 pub struct IndexMeta {
     pub segments: Vec<SegmentMeta>,
     pub schema: Schema,
+    #[serde(default)]
+    pub settings: IndexSettings,
     pub opstamp: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payload: Option<String>,
+    /// Changes of the last commits, from the oldest to the newest.
+    ///
+    /// At most the last `100` commits are kept.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commits: Vec<CommitChanges>,
 }
 
 impl IndexMeta {
-    pub fn with_schema(schema: Schema) -> IndexMeta {
+    pub fn with_schema(schema: Schema, settings: IndexSettings) -> IndexMeta {
         IndexMeta {
             segments: vec![],
             schema,
+            settings,
             opstamp: 0u64,
             payload: None,
+            commits: vec![],
         }
     }
 }
@@ -41,8 +184,10 @@ impl fmt::Debug for IndexMeta {
 mod tests {
 
     use serde_json;
-    use super::IndexMeta;
+    use super::{CommitChanges, IndexMeta, IndexSettings};
     use schema::{SchemaBuilder, TEXT};
+    use store::Compressor;
+    use postings::PostingsBlockSize;
 
     #[test]
     fn test_serialize_metas() {
@@ -54,10 +199,40 @@ mod tests {
         let index_metas = IndexMeta {
             segments: Vec::new(),
             schema: schema,
+            settings: IndexSettings::default(),
             opstamp: 0u64,
             payload: None,
+            commits: vec![],
         };
         let json = serde_json::ser::to_string(&index_metas).expect("serialization failed");
-        assert_eq!(json, r#"{"segments":[],"schema":[{"name":"text","type":"text","options":{"indexing":{"record":"position","tokenizer":"default"},"stored":false}}],"opstamp":0}"#);
+        assert_eq!(json, r#"{"segments":[],"schema":[{"name":"text","type":"text","options":{"indexing":{"record":"position","tokenizer":"default"},"stored":false}}],"settings":{"store_compressor":"lz4","postings_block_size":"128"},"opstamp":0}"#);
+    }
+
+    #[test]
+    fn test_deserialize_metas_without_settings() {
+        let json = r#"{"segments":[],"schema":[],"opstamp":3}"#;
+        let index_metas: IndexMeta = serde_json::from_str(json).unwrap();
+        assert_eq!(index_metas.settings.store_compressor, Compressor::Lz4);
+        assert_eq!(
+            index_metas.settings.postings_block_size,
+            PostingsBlockSize::Docs128
+        );
+        assert_eq!(index_metas.opstamp, 3);
+        assert!(index_metas.commits.is_empty());
+    }
+
+    #[test]
+    fn test_commit_changes() {
+        let mut commit = CommitChanges::new(3);
+        commit.record_add(4);
+        commit.record_add(5);
+        commit.record_delete(6);
+        commit.record_add(7);
+        assert_eq!(commit.add_opstamps, vec![(4, 6), (7, 8)]);
+        assert_eq!(commit.num_added_docs(), 3);
+        assert!(commit.contains_add(5));
+        assert!(!commit.contains_add(6));
+        assert!(commit.contains_delete(6));
+        assert!(!commit.contains_delete(7));
     }
 }
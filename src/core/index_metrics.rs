@@ -0,0 +1,254 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+fn duration_to_micros(duration: Duration) -> usize {
+    (duration.as_secs() as usize) * 1_000_000 + (duration.subsec_nanos() / 1_000) as usize
+}
+
+/// Counters of the activity of an `Index`.
+///
+/// The counters are shared by all of the clones of the `Index`, and
+/// are fed by its writer, by its merges and by its searchers.
+/// (See `Index::metrics()`)
+///
+/// Metrics are disabled by default: nothing is recorded until
+/// `.enable()` is called.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::schema::{SchemaBuilder, TEXT};
+/// use tantivy::{Index, Result};
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let title = schema_builder.add_text_field("title", TEXT);
+///     let index = Index::create_in_ram(schema_builder.build());
+///     index.metrics().enable();
+///     let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+///     index_writer.add_document(doc!(title => "The Diary of Muadib"));
+///     index_writer.commit()?;
+///
+///     let snapshot = index.metrics().snapshot();
+///     assert_eq!(snapshot.num_docs_indexed, 1);
+///     assert_eq!(snapshot.num_commits, 1);
+///     for (name, value) in snapshot.counters() {
+///         println!("{} {}", name, value);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Default)]
+pub struct IndexMetrics {
+    enabled: AtomicBool,
+    enabled_since: Mutex<Option<Instant>>,
+    num_docs_indexed: AtomicUsize,
+    num_segments_flushed: AtomicUsize,
+    num_commits: AtomicUsize,
+    num_merges: AtomicUsize,
+    num_docs_merged: AtomicUsize,
+    merge_time_us: AtomicUsize,
+    num_searches: AtomicUsize,
+    search_time_us: AtomicUsize,
+}
+
+/// Values of the counters of an `IndexMetrics` at a given time.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct MetricsSnapshot {
+    /// Number of documents that were indexed.
+    pub num_docs_indexed: u64,
+    /// Number of segments that were flushed by the indexing threads.
+    pub num_segments_flushed: u64,
+    /// Number of commits.
+    pub num_commits: u64,
+    /// Number of merges that completed.
+    pub num_merges: u64,
+    /// Number of documents written by the merges.
+    pub num_docs_merged: u64,
+    /// Time spent merging, in microseconds.
+    pub merge_time_us: u64,
+    /// Number of searches.
+    pub num_searches: u64,
+    /// Time spent searching, in microseconds.
+    pub search_time_us: u64,
+    /// Time elapsed since the metrics were enabled, in seconds.
+    pub elapsed_secs: f64,
+}
+
+impl MetricsSnapshot {
+    /// Returns the average number of documents indexed per second
+    /// since the metrics were enabled.
+    pub fn docs_indexed_per_sec(&self) -> f64 {
+        self.per_sec(self.num_docs_indexed)
+    }
+
+    /// Returns the average number of searches per second
+    /// since the metrics were enabled.
+    pub fn searches_per_sec(&self) -> f64 {
+        self.per_sec(self.num_searches)
+    }
+
+    fn per_sec(&self, count: u64) -> f64 {
+        if self.elapsed_secs > 0f64 {
+            count as f64 / self.elapsed_secs
+        } else {
+            0f64
+        }
+    }
+
+    /// Returns the counters, named following the conventions of
+    /// Prometheus, so that they can be exported as is.
+    pub fn counters(&self) -> Vec<(&'static str, u64)> {
+        vec![
+            ("tantivy_docs_indexed_total", self.num_docs_indexed),
+            ("tantivy_segments_flushed_total", self.num_segments_flushed),
+            ("tantivy_commits_total", self.num_commits),
+            ("tantivy_merges_total", self.num_merges),
+            ("tantivy_docs_merged_total", self.num_docs_merged),
+            ("tantivy_merge_time_microseconds_total", self.merge_time_us),
+            ("tantivy_searches_total", self.num_searches),
+            ("tantivy_search_time_microseconds_total", self.search_time_us),
+        ]
+    }
+}
+
+impl IndexMetrics {
+    /// Starts recording the metrics.
+    pub fn enable(&self) {
+        let mut enabled_since = self.enabled_since
+            .lock()
+            .expect("Metrics lock poisoned");
+        if enabled_since.is_none() {
+            *enabled_since = Some(Instant::now());
+        }
+        self.enabled.store(true, Ordering::SeqCst);
+    }
+
+    /// Stops recording the metrics. The counters are kept.
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::SeqCst);
+    }
+
+    /// Returns true iff the metrics are being recorded.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Returns the current values of the counters.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let load = |counter: &AtomicUsize| counter.load(Ordering::SeqCst) as u64;
+        let elapsed_secs = self.enabled_since
+            .lock()
+            .expect("Metrics lock poisoned")
+            .map(|enabled_since| {
+                let elapsed = enabled_since.elapsed();
+                elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9
+            })
+            .unwrap_or(0f64);
+        MetricsSnapshot {
+            num_docs_indexed: load(&self.num_docs_indexed),
+            num_segments_flushed: load(&self.num_segments_flushed),
+            num_commits: load(&self.num_commits),
+            num_merges: load(&self.num_merges),
+            num_docs_merged: load(&self.num_docs_merged),
+            merge_time_us: load(&self.merge_time_us),
+            num_searches: load(&self.num_searches),
+            search_time_us: load(&self.search_time_us),
+            elapsed_secs,
+        }
+    }
+
+    pub(crate) fn record_segment_flush(&self, num_docs: u32) {
+        if self.is_enabled() {
+            self.num_segments_flushed.fetch_add(1, Ordering::Relaxed);
+            self.num_docs_indexed
+                .fetch_add(num_docs as usize, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_commit(&self) {
+        if self.is_enabled() {
+            self.num_commits.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_merge(&self, num_docs: u32, duration: Duration) {
+        if self.is_enabled() {
+            self.num_merges.fetch_add(1, Ordering::Relaxed);
+            self.num_docs_merged
+                .fetch_add(num_docs as usize, Ordering::Relaxed);
+            self.merge_time_us
+                .fetch_add(duration_to_micros(duration), Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn record_search(&self, duration: Duration) {
+        if self.is_enabled() {
+            self.num_searches.fetch_add(1, Ordering::Relaxed);
+            self.search_time_us
+                .fetch_add(duration_to_micros(duration), Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::IndexMetrics;
+    use collector::CountCollector;
+    use futures::Future;
+    use query::AllQuery;
+    use schema::{SchemaBuilder, TEXT};
+    use std::time::Duration;
+    use Index;
+
+    #[test]
+    fn test_index_metrics_disabled() {
+        let index_metrics = IndexMetrics::default();
+        index_metrics.record_commit();
+        index_metrics.record_search(Duration::from_millis(3));
+        assert_eq!(index_metrics.snapshot().num_commits, 0);
+        index_metrics.enable();
+        index_metrics.record_search(Duration::from_millis(3));
+        let snapshot = index_metrics.snapshot();
+        assert_eq!(snapshot.num_searches, 1);
+        assert_eq!(snapshot.search_time_us, 3_000);
+    }
+
+    #[test]
+    fn test_index_metrics() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        index.metrics().enable();
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for _ in 0..2 {
+                index_writer.add_document(doc!(text_field => "a"));
+                index_writer.add_document(doc!(text_field => "b"));
+                index_writer.commit().unwrap();
+            }
+            let segment_ids = index.searchable_segment_ids().unwrap();
+            index_writer.merge(&segment_ids).wait().unwrap();
+            index_writer.wait_merging_threads().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut count_collector = CountCollector::default();
+        searcher.search(&AllQuery, &mut count_collector).unwrap();
+        searcher
+            .search_parallel(&AllQuery, &mut count_collector, 2)
+            .unwrap();
+        let snapshot = index.metrics().snapshot();
+        assert_eq!(snapshot.num_docs_indexed, 4);
+        assert_eq!(snapshot.num_segments_flushed, 2);
+        assert_eq!(snapshot.num_commits, 2);
+        assert_eq!(snapshot.num_merges, 1);
+        assert_eq!(snapshot.num_docs_merged, 4);
+        assert_eq!(snapshot.num_searches, 2);
+        assert!(snapshot.elapsed_secs > 0f64);
+        assert_eq!(snapshot.counters()[0], ("tantivy_docs_indexed_total", 4));
+    }
+}
@@ -0,0 +1,355 @@
+use Result;
+use core::Index;
+use core::SegmentId;
+use core::SegmentReader;
+use core::searcher::Searcher;
+use core::Warmer;
+use super::index::NUM_SEARCHERS;
+use super::pool::{LeasedItem, Pool};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::thread;
+use std::time::Duration;
+use num_cpus;
+
+/// Interval, in milliseconds, at which `ReloadPolicy::OnCommit`
+/// readers check the meta file.
+const META_POLLING_INTERVAL_MS: u64 = 500;
+
+/// Defines when an `IndexReader` reloads its searchers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReloadPolicy {
+    /// The searchers are only reloaded by calls to `IndexReader::reload()`.
+    Manual,
+    /// The searchers are reloaded as soon as the set of searchable segments
+    /// changes, i.e. after a commit or after a merge.
+    ///
+    /// A background thread polls the meta file of the index, so that the
+    /// commits of writers living in other processes are picked up too.
+    /// It may take up to half a second for a commit to become visible.
+    OnCommit,
+}
+
+struct InnerIndexReader {
+    index: Index,
+    searcher_pool: Pool<Searcher>,
+    // segment readers of the searchers currently published.
+    segment_readers: Mutex<Vec<SegmentReader>>,
+    warmers: RwLock<Vec<Arc<Warmer>>>,
+}
+
+impl InnerIndexReader {
+    /// Reloads the searchers, if the metas of the searchable segments
+    /// differ from the ones of the current searchers, or if `force` is true.
+    ///
+    /// Comparing the metas rather than the segment ids makes sure that
+    /// the commits only deleting documents are picked up too.
+    fn reload(&self, force: bool) -> Result<()> {
+        let mut current_segment_readers = self.segment_readers.lock()?;
+        let searchable_segments = self.index.searchable_segments()?;
+        let is_up_to_date = current_segment_readers.len() == searchable_segments.len()
+            && current_segment_readers
+                .iter()
+                .zip(searchable_segments.iter())
+                .all(|(segment_reader, segment)| segment_reader.segment_meta() == segment.meta());
+        if !force && is_up_to_date {
+            return Ok(());
+        }
+        let current_segment_ids: Vec<SegmentId> = current_segment_readers
+            .iter()
+            .map(|segment_reader| segment_reader.segment_id())
+            .collect();
+        let searchable_segment_ids: Vec<SegmentId> = searchable_segments
+            .iter()
+            .map(|segment| segment.id())
+            .collect();
+        let segment_readers: Vec<SegmentReader> =
+            SegmentReader::open_all(&searchable_segments, num_cpus::get())?;
+        let warmers = self.warmers.read()?;
+        for warmer in warmers.iter() {
+            for segment_reader in &segment_readers {
+                warmer.warm(segment_reader)?;
+            }
+        }
+        let searchers = (0..NUM_SEARCHERS)
+            .map(|_| {
+                Searcher::new(
+                    self.index.schema(),
+                    segment_readers.clone(),
+                    self.index.tokenizers().clone(),
+                    Arc::clone(self.index.metrics()),
+                    self.index.settings().source.clone(),
+                )
+            })
+            .collect();
+        self.searcher_pool.publish_new_generation(searchers);
+        *current_segment_readers = segment_readers;
+        for segment_id in current_segment_ids {
+            if !searchable_segment_ids.contains(&segment_id) {
+                for warmer in warmers.iter() {
+                    warmer.evict(segment_id);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `IndexReader` is the entry point to search an index.
+///
+/// It holds a pool of `Searcher`s, which are reloaded following
+/// its `ReloadPolicy`. (See `Index::reader_with_policy`)
+///
+/// Unlike `Index::searcher()`, it does not require to call
+/// `Index::load_searchers()` after every commit.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::schema::{SchemaBuilder, TEXT};
+/// use tantivy::{Index, ReloadPolicy, Result};
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let title = schema_builder.add_text_field("title", TEXT);
+///     let index = Index::create_in_ram(schema_builder.build());
+///     let reader = index.reader_with_policy(ReloadPolicy::Manual)?;
+///     assert_eq!(reader.searcher().num_docs(), 0);
+///
+///     let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+///     index_writer.add_document(doc!(title => "The Diary of Muadib"));
+///     index_writer.commit()?;
+///
+///     reader.reload()?;
+///     assert_eq!(reader.searcher().num_docs(), 1);
+///     Ok(())
+/// }
+/// ```
+pub struct IndexReader {
+    inner: Arc<InnerIndexReader>,
+    reload_policy: ReloadPolicy,
+}
+
+impl IndexReader {
+    pub(crate) fn open(index: Index, reload_policy: ReloadPolicy) -> Result<IndexReader> {
+        let inner = Arc::new(InnerIndexReader {
+            index,
+            searcher_pool: Pool::new(),
+            segment_readers: Mutex::new(Vec::new()),
+            warmers: RwLock::new(Vec::new()),
+        });
+        inner.reload(true)?;
+        if reload_policy == ReloadPolicy::OnCommit {
+            let weak_inner: Weak<InnerIndexReader> = Arc::downgrade(&inner);
+            thread::Builder::new()
+                .name("index-reader-watcher".to_string())
+                .spawn(move || watch_metas(&weak_inner))?;
+        }
+        Ok(IndexReader {
+            inner,
+            reload_policy,
+        })
+    }
+
+    /// Returns the reload policy of the reader.
+    pub fn reload_policy(&self) -> ReloadPolicy {
+        self.reload_policy
+    }
+
+    /// Reloads the searchers, so that they reflect the last commit.
+    ///
+    /// The searchers that are currently leased are not affected.
+    pub fn reload(&self) -> Result<()> {
+        self.inner.reload(false)
+    }
+
+    /// Registers a `Warmer`.
+    ///
+    /// The warmer is immediately called with the segment readers of the
+    /// current searchers, and then with the segment readers of every
+    /// subsequent reload.
+    pub fn add_warmer(&self, warmer: Arc<Warmer>) -> Result<()> {
+        let segment_readers = self.inner.segment_readers.lock()?;
+        for segment_reader in segment_readers.iter() {
+            warmer.warm(segment_reader)?;
+        }
+        self.inner.warmers.write()?.push(warmer);
+        Ok(())
+    }
+
+    /// Returns a searcher
+    ///
+    /// This method should be called every single time a search
+    /// query is performed.
+    /// The searchers are taken from a pool of `NUM_SEARCHERS` searchers.
+    /// If no searcher is available this may block.
+    ///
+    /// The same searcher must be used for a given query, as it ensures
+    /// the use of a consistent segment set.
+    pub fn searcher(&self) -> LeasedItem<Searcher> {
+        self.inner.searcher_pool.acquire()
+    }
+}
+
+/// Reloads the searchers of the reader whenever the meta file changes,
+/// until the reader is dropped.
+fn watch_metas(weak_inner: &Weak<InnerIndexReader>) {
+    loop {
+        thread::sleep(Duration::from_millis(META_POLLING_INTERVAL_MS));
+        let inner = match weak_inner.upgrade() {
+            Some(inner) => inner,
+            None => return,
+        };
+        if let Err(e) = inner.reload(false) {
+            error!("Failed to reload the searchers {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::ReloadPolicy;
+    use core::{SegmentId, SegmentReader, Warmer};
+    use futures::Future;
+    use query::{Query, TermQuery};
+    use schema::{IndexRecordOption, SchemaBuilder, TEXT};
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+    use Index;
+    use Result;
+    use Term;
+
+    #[derive(Default)]
+    struct SegmentIdsWarmer {
+        warmed: Mutex<HashSet<SegmentId>>,
+        evicted: Mutex<HashSet<SegmentId>>,
+    }
+
+    impl Warmer for SegmentIdsWarmer {
+        fn warm(&self, segment_reader: &SegmentReader) -> Result<()> {
+            self.warmed
+                .lock()
+                .unwrap()
+                .insert(segment_reader.segment_id());
+            Ok(())
+        }
+
+        fn evict(&self, segment_id: SegmentId) {
+            self.evicted.lock().unwrap().insert(segment_id);
+        }
+    }
+
+    #[test]
+    fn test_index_reader_manual() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let reader = index.reader_with_policy(ReloadPolicy::Manual).unwrap();
+        assert_eq!(reader.reload_policy(), ReloadPolicy::Manual);
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a"));
+        index_writer.commit().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 0);
+        reader.reload().unwrap();
+        let searcher = reader.searcher();
+        assert_eq!(searcher.num_docs(), 1);
+        index_writer.add_document(doc!(text_field => "b"));
+        index_writer.commit().unwrap();
+        reader.reload().unwrap();
+        // leased searchers keep their segments
+        assert_eq!(searcher.num_docs(), 1);
+        assert_eq!(reader.searcher().num_docs(), 2);
+    }
+
+    #[test]
+    fn test_index_reader_reloads_deletes() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a"));
+        index_writer.add_document(doc!(text_field => "b"));
+        index_writer.commit().unwrap();
+        let reader = index.reader_with_policy(ReloadPolicy::Manual).unwrap();
+        assert_eq!(reader.searcher().num_docs(), 2);
+        let segment_ids = index.searchable_segment_ids().unwrap();
+
+        // the commit only deletes a document, the segments are the same.
+        index_writer.delete_term(Term::from_field_text(text_field, "a"));
+        index_writer.commit().unwrap();
+        assert_eq!(index.searchable_segment_ids().unwrap(), segment_ids);
+        reader.reload().unwrap();
+        let searcher = reader.searcher();
+        assert_eq!(searcher.num_docs(), 1);
+        let term_query = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::Basic,
+        );
+        assert_eq!(term_query.count(&*searcher).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_index_reader_on_commit() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let reader = index.reader().unwrap();
+        assert_eq!(reader.reload_policy(), ReloadPolicy::OnCommit);
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a"));
+        index_writer.commit().unwrap();
+        for _ in 0..100 {
+            if reader.searcher().num_docs() == 1 {
+                return;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("The reader did not pick up the commit");
+    }
+
+    #[test]
+    fn test_index_reader_warmer() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a"));
+        index_writer.commit().unwrap();
+        let reader = index.reader_with_policy(ReloadPolicy::Manual).unwrap();
+        let warmer = Arc::new(SegmentIdsWarmer::default());
+        reader.add_warmer(warmer.clone()).unwrap();
+        let first_segment_ids: HashSet<SegmentId> =
+            index.searchable_segment_ids().unwrap().into_iter().collect();
+        assert_eq!(*warmer.warmed.lock().unwrap(), first_segment_ids);
+
+        index_writer.add_document(doc!(text_field => "b"));
+        index_writer.commit().unwrap();
+        reader.reload().unwrap();
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        assert_eq!(segment_ids.len(), 2);
+        assert_eq!(
+            *warmer.warmed.lock().unwrap(),
+            segment_ids.iter().cloned().collect::<HashSet<SegmentId>>()
+        );
+        assert!(warmer.evicted.lock().unwrap().is_empty());
+
+        let merged_segment_meta = index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+        reader.reload().unwrap();
+        assert!(
+            warmer
+                .warmed
+                .lock()
+                .unwrap()
+                .contains(&merged_segment_meta.id())
+        );
+        assert_eq!(
+            *warmer.evicted.lock().unwrap(),
+            segment_ids.into_iter().collect::<HashSet<SegmentId>>()
+        );
+        assert_eq!(reader.searcher().num_docs(), 2);
+    }
+}
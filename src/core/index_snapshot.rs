@@ -0,0 +1,190 @@
+use Result;
+use core::{IndexMeta, SegmentComponent, SegmentMeta, META_FILEPATH};
+use directory::{Directory, FileProtection, ManagedDirectory};
+use error::{ErrorKind, ResultExt};
+use serde_json;
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Returns the files of a segment that are required to open it.
+///
+/// The doc id mapping of a merged segment is optional.
+pub(crate) fn required_segment_files(segment_meta: &SegmentMeta) -> Vec<PathBuf> {
+    SegmentComponent::iterator()
+        .filter(|component| match **component {
+            SegmentComponent::DELETE => segment_meta.has_deletes(),
+            SegmentComponent::DOCMAP => false,
+            _ => true,
+        })
+        .map(|component| segment_meta.relative_path(*component))
+        .collect()
+}
+
+/// An `IndexSnapshot` is a consistent view of the files of an index,
+/// as of a given commit. (See `Index::snapshot`)
+///
+/// As long as the snapshot is alive, the files of the snapshot
+/// are protected from garbage collection, even if their segments
+/// are merged away by an `IndexWriter`.
+///
+/// The snapshot can be copied to another location while indexing
+/// continues, and the copy can then be opened using `Index::restore_from`.
+pub struct IndexSnapshot {
+    directory: ManagedDirectory,
+    meta_data: Vec<u8>,
+    metas: IndexMeta,
+    files: Vec<PathBuf>,
+    _file_protections: Vec<FileProtection>,
+}
+
+impl IndexSnapshot {
+    pub(crate) fn take(directory: &ManagedDirectory) -> Result<IndexSnapshot> {
+        loop {
+            let meta_data = directory.atomic_read(&META_FILEPATH)?;
+            let metas: IndexMeta = serde_json::from_slice(&meta_data)
+                .chain_err(|| ErrorKind::CorruptedFile(META_FILEPATH.clone()))?;
+            let candidate_files: Vec<PathBuf> = metas
+                .segments
+                .iter()
+                .flat_map(|segment_meta| segment_meta.list_files())
+                .collect();
+            let file_protections: Vec<FileProtection> = candidate_files
+                .iter()
+                .map(|path| directory.protect_file_from_delete(path))
+                .collect();
+            // The files of the segments that are still part of the last commit
+            // cannot have been garbage collected. If a commit happened in the
+            // meantime, some of the files may be gone, and we need to try again.
+            if directory.atomic_read(&META_FILEPATH)? != meta_data {
+                continue;
+            }
+            let mut files: Vec<PathBuf> = candidate_files
+                .into_iter()
+                .filter(|path| directory.exists(path))
+                .collect();
+            files.sort();
+            return Ok(IndexSnapshot {
+                directory: directory.clone(),
+                meta_data,
+                metas,
+                files,
+                _file_protections: file_protections,
+            });
+        }
+    }
+
+    /// Returns the index meta of the snapshot.
+    pub fn metas(&self) -> &IndexMeta {
+        &self.metas
+    }
+
+    /// Returns the content of the `meta.json` file of the snapshot.
+    ///
+    /// It may differ from the current `meta.json` file of the index.
+    pub fn meta_data(&self) -> &[u8] {
+        &self.meta_data
+    }
+
+    /// Returns the list of the segment files of the snapshot,
+    /// relative to the index directory.
+    ///
+    /// The `meta.json` file is not part of the list, as it may have
+    /// been overwritten by a later commit. Its content as of the snapshot
+    /// is available via `.meta_data()`.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Copies the snapshot to the given directory.
+    ///
+    /// The segment files are copied first, and the `meta.json` file last,
+    /// so that an interrupted copy does not look like a valid index.
+    pub fn copy_to(&self, target: &mut Directory) -> Result<()> {
+        for path in &self.files {
+            let source = self.directory.open_read(path)?;
+            let mut write = target.open_write(path)?;
+            write.write_all(source.as_slice())?;
+            write.flush()?;
+        }
+        target.atomic_write(&META_FILEPATH, &self.meta_data)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for IndexSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IndexSnapshot({:?})", self.files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use directory::{Directory, MmapDirectory};
+    use futures::Future;
+    use schema::{SchemaBuilder, TEXT};
+    use std::path::PathBuf;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_index_snapshot() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a"));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(text_field => "b"));
+        index_writer.commit().unwrap();
+
+        let snapshot = index.snapshot().unwrap();
+        assert_eq!(snapshot.metas().segments.len(), 2);
+        assert!(!snapshot.files().is_empty());
+
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.add_document(doc!(text_field => "c"));
+        index_writer.commit().unwrap();
+        index_writer.garbage_collect_files().unwrap();
+        assert_eq!(index.searchable_segment_ids().unwrap().len(), 2);
+        for path in snapshot.files() {
+            assert!(index.directory().exists(path));
+        }
+
+        let tempdir = TempDir::new("snapshot").unwrap();
+        {
+            let mut target = MmapDirectory::open(tempdir.path()).unwrap();
+            snapshot.copy_to(&mut target).unwrap();
+        }
+        let restored_index = Index::restore_from(tempdir.path()).unwrap();
+        assert_eq!(restored_index.searcher().num_docs(), 2);
+
+        let snapshot_files: Vec<PathBuf> = snapshot.files().to_vec();
+        drop(snapshot);
+        index_writer.garbage_collect_files().unwrap();
+        for path in &snapshot_files {
+            assert!(!index.directory().exists(path));
+        }
+    }
+
+    #[test]
+    fn test_restore_from_missing_file() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a"));
+        index_writer.commit().unwrap();
+
+        let snapshot = index.snapshot().unwrap();
+        let tempdir = TempDir::new("snapshot").unwrap();
+        {
+            let mut target = MmapDirectory::open(tempdir.path()).unwrap();
+            snapshot.copy_to(&mut target).unwrap();
+            target.delete(&snapshot.files()[0]).unwrap();
+        }
+        assert!(Index::restore_from(tempdir.path()).is_err());
+    }
+}
@@ -1,12 +1,19 @@
 use directory::{ReadOnlySource, SourceRead};
-use termdict::{TermDictionary, TermDictionaryImpl};
+use termdict::{TermDictionary, TermDictionaryImpl, TermStreamer, TermStreamerBuilder};
 use postings::{BlockSegmentPostings, SegmentPostings};
 use postings::TermInfo;
-use schema::IndexRecordOption;
+use schema::{FieldType, IndexRecordOption};
 use schema::Term;
 use fastfield::DeleteBitSet;
-use compression::CompressedIntStream;
+use postings::PositionReader;
 use postings::FreqReadingOption;
+use postings::PostingsBlockSize;
+use compression::{BlockEncoder, VIntEncoder};
+use common::HasLen;
+use std::io;
+
+/// Maximum number of terms returned by `InvertedIndexReader::prefix_terms`.
+pub const MAX_PREFIX_TERMS: usize = 10_000;
 
 /// The inverted index reader is in charge of accessing
 /// the inverted index associated to a specific field.
@@ -27,6 +34,7 @@ pub struct InvertedIndexReader {
     positions_source: ReadOnlySource,
     delete_bitset: DeleteBitSet,
     record_option: IndexRecordOption,
+    postings_block_size: PostingsBlockSize,
 }
 
 impl InvertedIndexReader {
@@ -36,26 +44,94 @@ impl InvertedIndexReader {
         positions_source: ReadOnlySource,
         delete_bitset: DeleteBitSet,
         record_option: IndexRecordOption,
-    ) -> InvertedIndexReader {
-        InvertedIndexReader {
-            termdict: TermDictionaryImpl::from_source(termdict_source),
+        postings_block_size: PostingsBlockSize,
+    ) -> io::Result<InvertedIndexReader> {
+        Ok(InvertedIndexReader {
+            termdict: TermDictionaryImpl::open(termdict_source)?,
             postings_source,
             positions_source,
             delete_bitset,
             record_option,
+            postings_block_size,
+        })
+    }
+
+    /// Creates an `InvertedIndexReader` without any term, for a field
+    /// that is not indexed in the segment.
+    pub(crate) fn empty(
+        field_type: FieldType,
+        record_option: IndexRecordOption,
+    ) -> InvertedIndexReader {
+        InvertedIndexReader {
+            termdict: TermDictionaryImpl::empty(field_type),
+            postings_source: ReadOnlySource::empty(),
+            positions_source: ReadOnlySource::empty(),
+            delete_bitset: DeleteBitSet::empty(),
+            record_option,
+            postings_block_size: PostingsBlockSize::default(),
         }
     }
 
+    /// Returns the number of documents of the blocks of the posting lists
+    /// of the field, as recorded when the segment was written.
+    pub fn postings_block_size(&self) -> PostingsBlockSize {
+        self.postings_block_size
+    }
+
     /// Returns the term info associated with the term.
     pub fn get_term_info(&self, term: &Term) -> Option<TermInfo> {
         self.termdict.get(term.value_bytes())
     }
 
+    /// Returns the postings data of the given term.
+    ///
+    /// If the posting list of the term is inlined in its `TermInfo`,
+    /// the postings data is encoded on the fly, and the postings
+    /// file is not accessed.
+    fn postings_data(&self, term_info: &TermInfo) -> io::Result<SourceRead> {
+        if let Some(doc) = term_info.inlined_doc {
+            let mut block_encoder = BlockEncoder::new();
+            let postings_data = block_encoder.compress_vint_sorted(&[doc], 0u32).to_vec();
+            Ok(SourceRead::from(ReadOnlySource::from(postings_data)))
+        } else {
+            let offset = term_info.postings_offset as usize;
+            let postings_source = slice_from(&self.postings_source, offset)?;
+            Ok(SourceRead::from(postings_source))
+        }
+    }
+
     /// Return the term dictionary datastructure.
     pub fn terms(&self) -> &TermDictionaryImpl {
         &self.termdict
     }
 
+    /// Returns the terms starting with the given prefix, in sorted order,
+    /// together with their `TermInfo`.
+    ///
+    /// The prefix is expressed in the same format as `Term::value_bytes()`.
+    /// At most `limit` terms are returned. In order to avoid scanning
+    /// the entire term dictionary by accident, `limit` is capped
+    /// to `MAX_PREFIX_TERMS`.
+    pub fn prefix_terms(&self, prefix: &[u8], limit: usize) -> Vec<(Vec<u8>, TermInfo)> {
+        let limit = limit.min(MAX_PREFIX_TERMS);
+        let mut terms = Vec::new();
+        if limit == 0 {
+            return terms;
+        }
+        let mut term_stream = self.termdict.range().ge(prefix).into_stream();
+        while term_stream.advance() {
+            let key = term_stream.key();
+            if !key.starts_with(prefix) {
+                break;
+            }
+            terms.push((key.to_owned(), term_stream.value().clone()));
+            if terms.len() == limit {
+                break;
+            }
+        }
+        terms
+    }
+
     /// Resets the block segment to another position of the postings
     /// file.
     ///
@@ -70,12 +146,13 @@ impl InvertedIndexReader {
         &self,
         term_info: &TermInfo,
         block_postings: &mut BlockSegmentPostings,
-    ) {
-        let offset = term_info.postings_offset as usize;
-        let end_source = self.postings_source.len();
-        let postings_slice = self.postings_source.slice(offset, end_source);
-        let postings_reader = SourceRead::from(postings_slice);
-        block_postings.reset(term_info.doc_freq as usize, postings_reader);
+    ) -> io::Result<()> {
+        let postings_reader = self.postings_data(term_info)?;
+        block_postings.reset(
+            term_info.doc_freq as usize,
+            postings_reader,
+            self.postings_block_size,
+        )
     }
 
     /// Returns a block postings given a `term_info`.
@@ -86,18 +163,18 @@ impl InvertedIndexReader {
         &self,
         term_info: &TermInfo,
         requested_option: IndexRecordOption,
-    ) -> BlockSegmentPostings {
-        let offset = term_info.postings_offset as usize;
-        let postings_data = self.postings_source.slice_from(offset);
+    ) -> io::Result<BlockSegmentPostings> {
+        let postings_data = self.postings_data(term_info)?;
         let freq_reading_option = match (self.record_option, requested_option) {
-            (IndexRecordOption::Basic, _) => FreqReadingOption::NoFreq,
+            (record_option, _) if !record_option.has_freq() => FreqReadingOption::NoFreq,
             (_, IndexRecordOption::Basic) => FreqReadingOption::SkipFreq,
             (_, _) => FreqReadingOption::ReadFreq,
         };
         BlockSegmentPostings::from_data(
             term_info.doc_freq as usize,
-            SourceRead::from(postings_data),
+            postings_data,
             freq_reading_option,
+            self.postings_block_size,
         )
     }
 
@@ -109,21 +186,27 @@ impl InvertedIndexReader {
         &self,
         term_info: &TermInfo,
         option: IndexRecordOption,
-    ) -> SegmentPostings {
-        let block_postings = self.read_block_postings_from_terminfo(term_info, option);
+    ) -> io::Result<SegmentPostings> {
+        let block_postings = self.read_block_postings_from_terminfo(term_info, option)?;
         let delete_bitset = self.delete_bitset.clone();
-        let position_stream = {
+        let position_reader = {
             if option.has_positions() {
-                let position_offset = term_info.positions_offset;
-                let positions_source = self.positions_source.slice_from(position_offset as usize);
-                let mut stream = CompressedIntStream::wrap(positions_source);
-                stream.skip(term_info.positions_inner_offset as usize);
-                Some(stream)
+                let position_offset = term_info.positions_offset as usize;
+                let positions_source = slice_from(&self.positions_source, position_offset)?;
+                let mut position_reader = PositionReader::wrap(positions_source);
+                position_reader.skip(term_info.positions_inner_offset as usize)?;
+                Some(position_reader)
             } else {
                 None
             }
         };
-        SegmentPostings::from_block_postings(block_postings, delete_bitset, position_stream)
+        let has_offsets = self.record_option.has_offsets();
+        Ok(SegmentPostings::from_block_postings(
+            block_postings,
+            delete_bitset,
+            position_reader,
+            has_offsets,
+        ))
     }
 
     /// Returns the segment postings associated with the term, and with the given option,
@@ -136,9 +219,17 @@ impl InvertedIndexReader {
     /// For instance, requesting `IndexRecordOption::Freq` for a
     /// `TextIndexingOptions` that does not index position will return a `SegmentPostings`
     /// with `DocId`s and frequencies.
-    pub fn read_postings(&self, term: &Term, option: IndexRecordOption) -> Option<SegmentPostings> {
-        let term_info = get!(self.get_term_info(term));
-        Some(self.read_postings_from_terminfo(&term_info, option))
+    ///
+    /// An error is returned if the postings of the term are corrupted.
+    pub fn read_postings(
+        &self,
+        term: &Term,
+        option: IndexRecordOption,
+    ) -> io::Result<Option<SegmentPostings>> {
+        match self.get_term_info(term) {
+            Some(term_info) => self.read_postings_from_terminfo(&term_info, option).map(Some),
+            None => Ok(None),
+        }
     }
 
     /// Returns the number of documents containing the term.
@@ -148,3 +239,56 @@ impl InvertedIndexReader {
             .unwrap_or(0u32)
     }
 }
+
+/// Returns the data of `source` starting at `offset`, or an error
+/// if the offset read from the term dictionary is out of bounds.
+fn slice_from(source: &ReadOnlySource, offset: usize) -> io::Result<ReadOnlySource> {
+    if offset > source.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Offset {} is out of bounds ({} bytes).", offset, source.len()),
+        ));
+    }
+    Ok(source.slice_from(offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use schema::{SchemaBuilder, STRING};
+    use Index;
+
+    #[test]
+    fn test_prefix_terms() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for text in &["apple", "apricot", "apricot", "banana", "ap", "a"] {
+                index_writer.add_document(doc!(text_field => *text));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let inverted_index = searcher.segment_reader(0).inverted_index(text_field).unwrap();
+        let terms: Vec<(Vec<u8>, u32)> = inverted_index
+            .prefix_terms(b"ap", 10)
+            .into_iter()
+            .map(|(term, term_info)| (term, term_info.doc_freq))
+            .collect();
+        assert_eq!(
+            terms,
+            vec![
+                (b"ap".to_vec(), 1),
+                (b"apple".to_vec(), 1),
+                (b"apricot".to_vec(), 2),
+            ]
+        );
+        assert_eq!(inverted_index.prefix_terms(b"ap", 2).len(), 2);
+        assert_eq!(inverted_index.prefix_terms(b"ap", 0).len(), 0);
+        assert_eq!(inverted_index.prefix_terms(b"c", 10).len(), 0);
+        assert_eq!(inverted_index.prefix_terms(b"", 10).len(), 5);
+        assert_eq!(inverted_index.prefix_terms(b"", usize::max_value()).len(), 5);
+    }
+}
@@ -0,0 +1,119 @@
+use ErrorKind;
+use Result;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct InnerMemoryBudget {
+    limit: Option<usize>,
+    allocated: AtomicUsize,
+}
+
+/// Bounds the memory a search is allowed to allocate.
+///
+/// The weights that build large structures (e.g. the `BitSet` of a
+/// `RangeQuery`) and the collectors keeping one entry per document or per
+/// term reserve their memory against the budget of the searcher before
+/// allocating it. (See `Searcher::with_memory_budget`)
+///
+/// If a reservation would exceed the limit, the search fails with a
+/// `ErrorKind::BudgetExceeded` error instead of exhausting the memory of
+/// the process.
+///
+/// Reservations are cumulative: the memory is never given back to the
+/// budget during the search, even if it is released or reused.
+/// The budget is shared by its clones, so that a single budget should
+/// not be reused across concurrent searches.
+#[derive(Clone)]
+pub struct MemoryBudget(Arc<InnerMemoryBudget>);
+
+impl MemoryBudget {
+    /// Creates a budget of `num_bytes` bytes.
+    pub fn with_limit(num_bytes: usize) -> MemoryBudget {
+        MemoryBudget::new(Some(num_bytes))
+    }
+
+    /// Creates a budget without any limit.
+    ///
+    /// The memory reserved is still tracked.
+    pub fn unlimited() -> MemoryBudget {
+        MemoryBudget::new(None)
+    }
+
+    fn new(limit: Option<usize>) -> MemoryBudget {
+        MemoryBudget(Arc::new(InnerMemoryBudget {
+            limit,
+            allocated: AtomicUsize::new(0),
+        }))
+    }
+
+    /// Returns the limit of the budget, in bytes, if any.
+    pub fn limit(&self) -> Option<usize> {
+        self.0.limit
+    }
+
+    /// Returns the number of bytes reserved so far.
+    pub fn allocated_bytes(&self) -> usize {
+        self.0.allocated.load(Ordering::SeqCst)
+    }
+
+    /// Reserves `num_bytes` bytes.
+    ///
+    /// Returns a `BudgetExceeded` error, and leaves the budget
+    /// untouched, if the reservation would exceed the limit.
+    pub fn allocate(&self, num_bytes: usize) -> Result<()> {
+        let previously_allocated = self.0.allocated.fetch_add(num_bytes, Ordering::SeqCst);
+        if let Some(limit) = self.0.limit {
+            if previously_allocated.saturating_add(num_bytes) > limit {
+                self.0.allocated.fetch_sub(num_bytes, Ordering::SeqCst);
+                bail!(ErrorKind::BudgetExceeded(limit));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for MemoryBudget {
+    fn default() -> MemoryBudget {
+        MemoryBudget::unlimited()
+    }
+}
+
+impl fmt::Debug for MemoryBudget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "MemoryBudget(allocated={}, limit={:?})",
+            self.allocated_bytes(),
+            self.limit()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::MemoryBudget;
+    use ErrorKind;
+
+    #[test]
+    fn test_memory_budget() {
+        let memory_budget = MemoryBudget::with_limit(100);
+        assert!(memory_budget.allocate(60).is_ok());
+        match *memory_budget.clone().allocate(41).unwrap_err().kind() {
+            ErrorKind::BudgetExceeded(100) => {}
+            _ => panic!("Expected a BudgetExceeded error"),
+        }
+        assert_eq!(memory_budget.allocated_bytes(), 60);
+        assert!(memory_budget.allocate(40).is_ok());
+        assert_eq!(memory_budget.allocated_bytes(), 100);
+    }
+
+    #[test]
+    fn test_memory_budget_unlimited() {
+        let memory_budget = MemoryBudget::unlimited();
+        assert!(memory_budget.allocate(1_000_000).is_ok());
+        assert_eq!(memory_budget.allocated_bytes(), 1_000_000);
+        assert_eq!(memory_budget.limit(), None);
+    }
+}
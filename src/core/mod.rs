@@ -1,5 +1,6 @@
 pub mod searcher;
 pub mod index;
+mod index_reader;
 mod segment_reader;
 mod segment_id;
 mod segment_component;
@@ -8,17 +9,43 @@ mod index_meta;
 mod pool;
 mod segment_meta;
 mod inverted_index_reader;
+mod doc_id_mapping;
+mod memory_budget;
+mod index_metrics;
+mod field_values;
+mod search_stream;
+mod warmer;
+mod index_snapshot;
+mod rollover_index;
+mod compatibility;
+mod autocomplete;
 
-pub use self::inverted_index_reader::InvertedIndexReader;
+pub use self::inverted_index_reader::{InvertedIndexReader, MAX_PREFIX_TERMS};
 pub use self::searcher::Searcher;
 pub use self::segment_component::SegmentComponent;
 pub use self::segment_id::SegmentId;
-pub use self::segment_reader::SegmentReader;
+pub use self::segment_reader::{SegmentFieldInfo, SegmentReader};
 pub use self::segment::Segment;
 pub use self::segment::SerializableSegment;
 pub use self::index::Index;
+pub use self::index_reader::{IndexReader, ReloadPolicy};
 pub use self::segment_meta::SegmentMeta;
-pub use self::index_meta::IndexMeta;
+pub use self::index_meta::{CommitChanges, IndexChanges, IndexMeta, IndexSettings};
+pub(crate) use self::index_meta::MAX_COMMIT_HISTORY;
+pub use self::doc_id_mapping::DocIdMapping;
+pub use self::memory_budget::MemoryBudget;
+pub use self::index_metrics::{IndexMetrics, MetricsSnapshot};
+pub use self::field_values::{FieldValues, FieldValuesBatch};
+pub use self::search_stream::SearchStream;
+pub use self::autocomplete::{Autocomplete, Completion};
+pub use self::warmer::Warmer;
+pub use self::index_snapshot::IndexSnapshot;
+pub use self::rollover_index::{RolloverIndex, RolloverPolicy};
+pub use self::compatibility::{CompatibilityReport, DOC_ID_MAPPING_FEATURE,
+                              FAST_FIELDS_GENERATION_FEATURE, OFFSETS_FEATURE,
+                              POSTINGS_BLOCK_SIZE_FEATURE};
+pub(crate) use self::compatibility::segment_features;
+pub(crate) use self::doc_id_mapping::write_doc_id_mapping;
 
 use std::path::PathBuf;
 
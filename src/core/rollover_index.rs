@@ -0,0 +1,420 @@
+use Result;
+use core::{Index, Searcher, SegmentReader};
+use error::ErrorKind;
+use num_cpus;
+use schema::{Document, Schema};
+use serde_json;
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use IndexWriter;
+
+/// Defines when a `RolloverIndex` starts writing into a new index,
+/// and when its old indexes are deleted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RolloverPolicy {
+    /// Rolls over once the active index is older than `max_age`.
+    pub max_age: Option<Duration>,
+    /// Rolls over once `max_docs` documents were added to the active index.
+    pub max_docs: Option<u64>,
+    /// The indexes whose documents are all older than `retention`
+    /// are deleted by `RolloverIndex::delete_expired()`.
+    pub retention: Option<Duration>,
+}
+
+/// One of the indexes of a `RolloverIndex`.
+///
+/// Its directory is named after its generation and its creation time,
+/// in milliseconds since the epoch.
+struct Partition {
+    generation: u64,
+    created_at: SystemTime,
+    path: PathBuf,
+    index: Index,
+}
+
+impl Partition {
+    fn dir_name(generation: u64, created_at_millis: u64) -> String {
+        format!("{:08}-{}", generation, created_at_millis)
+    }
+
+    fn parse_dir_name(dir_name: &str) -> Option<(u64, u64)> {
+        let mut parts = dir_name.splitn(2, '-');
+        match (parts.next(), parts.next()) {
+            (Some(generation), Some(created_at_millis)) => {
+                match (generation.parse::<u64>(), created_at_millis.parse::<u64>()) {
+                    (Ok(generation), Ok(created_at_millis)) => {
+                        Some((generation, created_at_millis))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A family of indexes sharing the same schema, partitioned by time
+/// or by size, typically for time series or logs.
+///
+/// Documents are written into the last index, the active one.
+/// Whenever the `RolloverPolicy` triggers, the active index is
+/// committed and a new one is created. All of the indexes are searched
+/// at once by the `Searcher` returned by `.searcher()`, and the indexes
+/// older than the retention of the policy are deleted by
+/// `.delete_expired()`.
+///
+/// Each index lives in its own sub-directory of the directory
+/// of the `RolloverIndex`.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// extern crate tempdir;
+/// use tantivy::schema::{SchemaBuilder, TEXT};
+/// use tantivy::{Result, RolloverIndex, RolloverPolicy};
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let message = schema_builder.add_text_field("message", TEXT);
+///     let tempdir = tempdir::TempDir::new("logs")?;
+///     let policy = RolloverPolicy {
+///         max_docs: Some(1_000),
+///         ..RolloverPolicy::default()
+///     };
+///     let schema = schema_builder.build();
+///     let mut rollover_index =
+///         RolloverIndex::open_or_create(tempdir.path(), schema, policy, 1, 3_000_000)?;
+///     for i in 0..2_500 {
+///         rollover_index.add_document(doc!(message => format!("event {}", i)))?;
+///     }
+///     rollover_index.commit()?;
+///     assert_eq!(rollover_index.indexes().len(), 3);
+///     assert_eq!(rollover_index.searcher()?.num_docs(), 2_500);
+///     Ok(())
+/// }
+/// ```
+pub struct RolloverIndex {
+    directory_path: PathBuf,
+    schema: Schema,
+    policy: RolloverPolicy,
+    num_threads: usize,
+    heap_size_in_bytes: usize,
+    // sorted by generation, the last one is the active one.
+    partitions: Vec<Partition>,
+    writer: IndexWriter,
+    // documents added to the active index, committed or not.
+    num_added_docs: u64,
+}
+
+impl RolloverIndex {
+    /// Opens the `RolloverIndex` living in the given directory,
+    /// or creates it if it does not contain any index yet.
+    ///
+    /// The writers of the indexes use `num_threads` threads,
+    /// and a heap of `heap_size_in_bytes`.
+    /// (See `Index::writer_with_num_threads`)
+    ///
+    /// # Errors
+    /// Returns a `SchemaError` if the existing indexes were
+    /// created with a different schema.
+    pub fn open_or_create<P: AsRef<Path>>(
+        directory_path: P,
+        schema: Schema,
+        policy: RolloverPolicy,
+        num_threads: usize,
+        heap_size_in_bytes: usize,
+    ) -> Result<RolloverIndex> {
+        let directory_path = directory_path.as_ref().to_path_buf();
+        fs::create_dir_all(&directory_path)?;
+        let mut partitions = vec![];
+        for entry in fs::read_dir(&directory_path)? {
+            let path = entry?.path();
+            let parsed_dir_name = path.file_name()
+                .and_then(|dir_name| dir_name.to_str())
+                .and_then(Partition::parse_dir_name);
+            if let Some((generation, created_at_millis)) = parsed_dir_name {
+                let index = Index::open(&path)?;
+                if serde_json::to_string(&index.schema())? != serde_json::to_string(&schema)? {
+                    bail!(ErrorKind::SchemaError(format!(
+                        "The schema of the index {:?} differs from the given schema",
+                        path
+                    )));
+                }
+                partitions.push(Partition {
+                    generation,
+                    created_at: UNIX_EPOCH + Duration::from_millis(created_at_millis),
+                    path,
+                    index,
+                });
+            }
+        }
+        partitions.sort_by_key(|partition| partition.generation);
+        if partitions.is_empty() {
+            partitions.push(create_partition(&directory_path, &schema, 0)?);
+        }
+        let (writer, num_added_docs) = {
+            let active_index = &partitions[partitions.len() - 1].index;
+            let num_docs: u64 = active_index
+                .searchable_segment_metas()?
+                .iter()
+                .map(|segment_meta| u64::from(segment_meta.max_doc()))
+                .sum();
+            let writer = active_index.writer_with_num_threads(num_threads, heap_size_in_bytes)?;
+            (writer, num_docs)
+        };
+        Ok(RolloverIndex {
+            directory_path,
+            schema,
+            policy,
+            num_threads,
+            heap_size_in_bytes,
+            partitions,
+            writer,
+            num_added_docs,
+        })
+    }
+
+    /// Returns the schema shared by the indexes.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Returns the indexes, from the oldest to the active one.
+    pub fn indexes(&self) -> Vec<&Index> {
+        self.partitions
+            .iter()
+            .map(|partition| &partition.index)
+            .collect()
+    }
+
+    /// Returns the index the documents are currently written into.
+    pub fn active_index(&self) -> &Index {
+        &self.active_partition().index
+    }
+
+    fn active_partition(&self) -> &Partition {
+        &self.partitions[self.partitions.len() - 1]
+    }
+
+    fn should_roll_over(&self) -> bool {
+        if self.num_added_docs == 0 {
+            return false;
+        }
+        if let Some(max_docs) = self.policy.max_docs {
+            if self.num_added_docs >= max_docs {
+                return true;
+            }
+        }
+        if let Some(max_age) = self.policy.max_age {
+            let created_at = self.active_partition().created_at;
+            if let Ok(age) = SystemTime::now().duration_since(created_at) {
+                if age >= max_age {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Adds a document to the active index, after rolling over
+    /// to a new index if the policy requires it.
+    ///
+    /// As for `IndexWriter::add_document`, the document only
+    /// becomes searchable after a commit.
+    pub fn add_document(&mut self, document: Document) -> Result<u64> {
+        if self.should_roll_over() {
+            self.roll_over()?;
+        }
+        self.num_added_docs += 1;
+        Ok(self.writer.add_document(document))
+    }
+
+    /// Commits the documents added to the active index.
+    pub fn commit(&mut self) -> Result<u64> {
+        self.writer.commit()
+    }
+
+    /// Commits the active index, and starts writing into a new index.
+    pub fn roll_over(&mut self) -> Result<()> {
+        self.writer.commit()?;
+        let generation = self.active_partition().generation + 1;
+        let partition = create_partition(&self.directory_path, &self.schema, generation)?;
+        let writer = partition
+            .index
+            .writer_with_num_threads(self.num_threads, self.heap_size_in_bytes)?;
+        let previous_writer = mem::replace(&mut self.writer, writer);
+        previous_writer.wait_merging_threads()?;
+        self.partitions.push(partition);
+        self.num_added_docs = 0;
+        Ok(())
+    }
+
+    /// Deletes the indexes whose documents are all older than the
+    /// retention of the policy, i.e. the indexes followed by an index
+    /// created more than `retention` ago.
+    ///
+    /// The active index is never deleted.
+    /// Returns the number of deleted indexes.
+    pub fn delete_expired(&mut self) -> Result<usize> {
+        let retention = match self.policy.retention {
+            Some(retention) => retention,
+            None => return Ok(0),
+        };
+        let now = SystemTime::now();
+        let num_expired = self.partitions
+            .windows(2)
+            .take_while(|partitions| {
+                now.duration_since(partitions[1].created_at)
+                    .map(|age| age >= retention)
+                    .unwrap_or(false)
+            })
+            .count();
+        for partition in self.partitions.drain(..num_expired) {
+            let Partition { path, index, .. } = partition;
+            drop(index);
+            fs::remove_dir_all(&path)?;
+        }
+        Ok(num_expired)
+    }
+
+    /// Returns a `Searcher` over the committed documents of all of the indexes.
+    ///
+    /// The segment ordinals of its `DocAddress`es span the segments
+    /// of all of the indexes, from the oldest to the active one.
+    pub fn searcher(&self) -> Result<Searcher> {
+        let mut segments = vec![];
+        for partition in &self.partitions {
+            segments.extend(partition.index.searchable_segments()?);
+        }
+        let segment_readers: Vec<SegmentReader> =
+            SegmentReader::open_all(&segments, num_cpus::get())?;
+        let active_index = self.active_index();
+        Ok(Searcher::new(
+            self.schema.clone(),
+            segment_readers,
+            active_index.tokenizers().clone(),
+            Arc::clone(active_index.metrics()),
+            active_index.settings().source.clone(),
+        ))
+    }
+}
+
+fn create_partition(directory_path: &Path, schema: &Schema, generation: u64) -> Result<Partition> {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_else(|_| Duration::from_secs(0));
+    let created_at_millis =
+        since_epoch.as_secs() * 1_000 + u64::from(since_epoch.subsec_nanos()) / 1_000_000;
+    let path = directory_path.join(Partition::dir_name(generation, created_at_millis));
+    fs::create_dir(&path)?;
+    let index = Index::create(&path, schema.clone())?;
+    Ok(Partition {
+        generation,
+        created_at: UNIX_EPOCH + Duration::from_millis(created_at_millis),
+        path,
+        index,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{RolloverIndex, RolloverPolicy};
+    use schema::{SchemaBuilder, TEXT};
+    use std::time::Duration;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_rollover_index() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let tempdir = TempDir::new("rollover").unwrap();
+        let policy = RolloverPolicy {
+            max_docs: Some(3),
+            ..RolloverPolicy::default()
+        };
+        {
+            let mut rollover_index = RolloverIndex::open_or_create(
+                tempdir.path(),
+                schema.clone(),
+                policy,
+                1,
+                40_000_000,
+            ).unwrap();
+            for _ in 0..7 {
+                rollover_index.add_document(doc!(text_field => "a")).unwrap();
+            }
+            rollover_index.commit().unwrap();
+            assert_eq!(rollover_index.indexes().len(), 3);
+            rollover_index.active_index().load_searchers().unwrap();
+            assert_eq!(rollover_index.active_index().searcher().num_docs(), 1);
+            let searcher = rollover_index.searcher().unwrap();
+            assert_eq!(searcher.num_docs(), 7);
+            assert_eq!(searcher.segment_readers().len(), 3);
+        }
+        let policy = RolloverPolicy {
+            retention: Some(Duration::from_secs(0)),
+            ..RolloverPolicy::default()
+        };
+        let mut rollover_index =
+            RolloverIndex::open_or_create(tempdir.path(), schema, policy, 1, 40_000_000).unwrap();
+        assert_eq!(rollover_index.indexes().len(), 3);
+        assert_eq!(rollover_index.searcher().unwrap().num_docs(), 7);
+        rollover_index.roll_over().unwrap();
+        rollover_index.add_document(doc!(text_field => "b")).unwrap();
+        rollover_index.commit().unwrap();
+        assert_eq!(rollover_index.delete_expired().unwrap(), 3);
+        assert_eq!(rollover_index.indexes().len(), 1);
+        assert_eq!(rollover_index.searcher().unwrap().num_docs(), 1);
+    }
+
+    #[test]
+    fn test_rollover_index_max_age() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let tempdir = TempDir::new("rollover").unwrap();
+        let policy = RolloverPolicy {
+            max_age: Some(Duration::from_secs(0)),
+            ..RolloverPolicy::default()
+        };
+        let schema = schema_builder.build();
+        let mut rollover_index =
+            RolloverIndex::open_or_create(tempdir.path(), schema, policy, 1, 40_000_000).unwrap();
+        for _ in 0..3 {
+            rollover_index.add_document(doc!(text_field => "a")).unwrap();
+        }
+        rollover_index.commit().unwrap();
+        assert_eq!(rollover_index.indexes().len(), 3);
+        assert_eq!(rollover_index.searcher().unwrap().num_docs(), 3);
+    }
+
+    #[test]
+    fn test_rollover_index_schema_mismatch() {
+        let tempdir = TempDir::new("rollover").unwrap();
+        let mut schema_builder = SchemaBuilder::default();
+        schema_builder.add_text_field("text", TEXT);
+        RolloverIndex::open_or_create(
+            tempdir.path(),
+            schema_builder.build(),
+            RolloverPolicy::default(),
+            1,
+            40_000_000,
+        ).unwrap();
+        let mut schema_builder = SchemaBuilder::default();
+        schema_builder.add_text_field("title", TEXT);
+        assert!(
+            RolloverIndex::open_or_create(
+                tempdir.path(),
+                schema_builder.build(),
+                RolloverPolicy::default(),
+                1,
+                40_000_000,
+            ).is_err()
+        );
+    }
+}
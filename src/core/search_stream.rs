@@ -0,0 +1,85 @@
+use Result;
+use DocAddress;
+use Score;
+use SegmentLocalId;
+use core::Searcher;
+use docset::DocSet;
+use query::{Scorer, Weight};
+use schema::Document;
+
+/// Iterator over the `(score, doc_address)` of the documents matching a query.
+///
+/// The documents are yielded segment after segment, in the order of
+/// their doc ids within a segment. Each segment is scored lazily, as the
+/// iterator is consumed, so that the hits are never buffered.
+///
+/// See `Searcher::search_streaming`.
+pub struct SearchStream<'a> {
+    searcher: &'a Searcher,
+    weight: Box<Weight>,
+    next_segment_ord: usize,
+    segment: Option<(SegmentLocalId, Box<Scorer>)>,
+}
+
+impl<'a> SearchStream<'a> {
+    pub(crate) fn new(searcher: &'a Searcher, weight: Box<Weight>) -> SearchStream<'a> {
+        SearchStream {
+            searcher,
+            weight,
+            next_segment_ord: 0,
+            segment: None,
+        }
+    }
+
+    /// Returns an iterator yielding, together with each hit,
+    /// its stored document.
+    ///
+    /// A document is only read from the store when the
+    /// iterator reaches it.
+    pub fn with_docs(self) -> impl Iterator<Item = Result<(Score, DocAddress, Document)>> + 'a {
+        let searcher = self.searcher;
+        self.map(move |hit_res| {
+            let (score, doc_address) = hit_res?;
+            let doc = searcher.doc(&doc_address)?;
+            Ok((score, doc_address, doc))
+        })
+    }
+
+    fn open_next_segment(&mut self) -> Result<bool> {
+        let segment_readers = self.searcher.segment_readers();
+        if self.next_segment_ord == segment_readers.len() {
+            return Ok(false);
+        }
+        let scorer = self.weight.scorer(&segment_readers[self.next_segment_ord])?;
+        self.segment = Some((self.next_segment_ord as SegmentLocalId, scorer));
+        self.next_segment_ord += 1;
+        Ok(true)
+    }
+}
+
+impl<'a> Iterator for SearchStream<'a> {
+    type Item = Result<(Score, DocAddress)>;
+
+    fn next(&mut self) -> Option<Result<(Score, DocAddress)>> {
+        loop {
+            if let Some((segment_ord, ref mut scorer)) = self.segment {
+                if scorer.advance() {
+                    let doc_address = DocAddress(segment_ord, scorer.doc());
+                    return Some(Ok((scorer.score(), doc_address)));
+                }
+            }
+            self.segment = None;
+            match self.open_next_segment() {
+                Ok(true) => {}
+                Ok(false) => {
+                    return None;
+                }
+                Err(e) => {
+                    // The remaining segments are skipped.
+                    self.next_segment_ord = self.searcher.segment_readers().len();
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
@@ -1,16 +1,104 @@
 use Result;
+use error::ErrorKind;
+use serde_json::{self, Value as JsonValue};
 use core::SegmentReader;
-use schema::Document;
-use collector::Collector;
-use common::TimerTree;
-use query::Query;
+use schema::{Document, Schema, SchemaBuilder};
+use collector::{Collector, MergeableCollector};
+use common::{Span, TimerTree};
+use query::{Query, Weight};
+use docset::{DocSet, SkipResult};
 use DocId;
 use DocAddress;
-use schema::{Field, Term};
+use SegmentLocalId;
+use crossbeam;
+use std::cmp;
+use std::sync::Mutex;
+use schema::{Field, IndexRecordOption, SourceOptions, Term, Value};
 use termdict::{TermDictionary, TermMerger};
 use std::sync::Arc;
 use std::fmt;
 use core::InvertedIndexReader;
+use core::SegmentId;
+use core::{IndexMetrics, MemoryBudget};
+use core::{FieldValues, SearchStream};
+use fastfield::{DeleteBitSet, FastValue};
+use std::collections::HashMap;
+use std::time::Instant;
+use tokenizer::TokenizerManager;
+
+/// Segments are not split into slices smaller than this number of documents.
+const MIN_DOCS_PER_SLICE: DocId = 10_000;
+
+/// Range of documents `[start_doc, end_doc)` of a given segment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct LeafSlice {
+    segment_ord: SegmentLocalId,
+    start_doc: DocId,
+    end_doc: DocId,
+}
+
+/// Splits the segments into doc ranges that can be collected independently.
+///
+/// Each segment is split into at most `num_slices_per_segment` slices of
+/// the same size, and each slice contains at least `MIN_DOCS_PER_SLICE`
+/// documents, unless the segment itself is smaller.
+fn leaf_slices(segment_readers: &[SegmentReader], num_slices_per_segment: usize) -> Vec<LeafSlice> {
+    let mut slices = vec![];
+    for (segment_ord, segment_reader) in segment_readers.iter().enumerate() {
+        let max_doc = segment_reader.max_doc();
+        let num_slices = cmp::max(
+            1,
+            cmp::min(num_slices_per_segment as DocId, max_doc / MIN_DOCS_PER_SLICE),
+        );
+        let slice_len = (max_doc + num_slices - 1) / num_slices;
+        let mut start_doc = 0;
+        while start_doc < max_doc {
+            let end_doc = cmp::min(start_doc + slice_len, max_doc);
+            slices.push(LeafSlice {
+                segment_ord: segment_ord as SegmentLocalId,
+                start_doc,
+                end_doc,
+            });
+            start_doc = end_doc;
+        }
+    }
+    slices
+}
+
+/// Pushes the documents of `slice` matching `weight` to the collector.
+fn collect_slice<C: Collector>(
+    weight: &Weight,
+    segment_reader: &SegmentReader,
+    slice: &LeafSlice,
+    collector: &mut C,
+) -> Result<()> {
+    let mut span = Span::enter("collect_segment", || {
+        format!(
+            "segment={} start_doc={} end_doc={}",
+            segment_reader.segment_id().short_uuid_string(),
+            slice.start_doc,
+            slice.end_doc
+        )
+    });
+    collector.set_segment(slice.segment_ord, segment_reader)?;
+    let mut scorer = weight.scorer(segment_reader)?;
+    if !scorer.advance() {
+        return Ok(());
+    }
+    if scorer.doc() < slice.start_doc && scorer.skip_next(slice.start_doc) == SkipResult::End {
+        return Ok(());
+    }
+    let mut num_docs = 0u64;
+    while scorer.doc() < slice.end_doc {
+        collector.collect(scorer.doc(), scorer.score());
+        num_docs += 1;
+        if !scorer.advance() {
+            break;
+        }
+    }
+    span.record("num_docs", num_docs);
+    Ok(())
+}
 
 /// Holds a list of `SegmentReader`s ready for search.
 ///
@@ -18,10 +106,45 @@ use core::InvertedIndexReader;
 /// the destruction of the `Searcher`.
 ///
 pub struct Searcher {
+    schema: Schema,
     segment_readers: Vec<SegmentReader>,
+    tokenizers: TokenizerManager,
+    memory_budget: MemoryBudget,
+    metrics: Arc<IndexMetrics>,
+    source_options: Option<SourceOptions>,
 }
 
 impl Searcher {
+    pub(crate) fn new(
+        schema: Schema,
+        segment_readers: Vec<SegmentReader>,
+        tokenizers: TokenizerManager,
+        metrics: Arc<IndexMetrics>,
+        source_options: Option<SourceOptions>,
+    ) -> Searcher {
+        Searcher {
+            schema,
+            segment_readers,
+            tokenizers,
+            memory_budget: MemoryBudget::unlimited(),
+            metrics,
+            source_options,
+        }
+    }
+
+    /// Returns the schema of the index.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Returns the tokenizers of the index.
+    ///
+    /// Queries analyzing some user text rely on them,
+    /// so that the text is tokenized like the indexed documents.
+    pub fn tokenizers(&self) -> &TokenizerManager {
+        &self.tokenizers
+    }
+
     /// Fetches a document from tantivy's store given a `DocAddress`.
     ///
     /// The searcher uses the segment ordinal to route the
@@ -32,6 +155,38 @@ impl Searcher {
         segment_reader.doc(doc_id)
     }
 
+    /// Fetches the values of the given stored fields of a document.
+    ///
+    /// The other stored fields are skipped without being decoded.
+    /// (See `SegmentReader::doc_fields`)
+    pub fn doc_fields(&self, doc_address: &DocAddress, fields: &[Field]) -> Result<Document> {
+        let DocAddress(segment_local_id, doc_id) = *doc_address;
+        let segment_reader = &self.segment_readers[segment_local_id as usize];
+        segment_reader.doc_fields(doc_id, fields)
+    }
+
+    /// Fetches the original JSON document given a `DocAddress`.
+    ///
+    /// The index needs to be configured to keep the source of
+    /// the documents (See `IndexSettings::source`).
+    /// `Value::Null` is returned if the document has no source.
+    pub fn source(&self, doc_address: &DocAddress) -> Result<JsonValue> {
+        let source_field_opt = self.source_options
+            .as_ref()
+            .and_then(|source_options| source_options.resolve_field(&self.schema));
+        let source_field = match source_field_opt {
+            Some(source_field) => source_field,
+            None => bail!(ErrorKind::SchemaError(
+                "The index does not keep the source of the documents".to_string()
+            )),
+        };
+        let doc = self.doc(doc_address)?;
+        match doc.get_first(source_field) {
+            Some(&Value::Str(ref source)) => Ok(serde_json::from_str(source)?),
+            _ => Ok(JsonValue::Null),
+        }
+    }
+
     /// Returns the overall number of documents in the index.
     pub fn num_docs(&self) -> DocId {
         self.segment_readers
@@ -42,11 +197,58 @@ impl Searcher {
 
     /// Return the overall number of documents containing
     /// the given term.
-    pub fn doc_freq(&self, term: &Term) -> u32 {
-        self.segment_readers
-            .iter()
-            .map(|segment_reader| segment_reader.inverted_index(term.field()).doc_freq(term))
-            .sum::<u32>()
+    pub fn doc_freq(&self, term: &Term) -> Result<u32> {
+        let mut doc_freq = 0u32;
+        for segment_reader in &self.segment_readers {
+            doc_freq += segment_reader.inverted_index(term.field())?.doc_freq(term);
+        }
+        Ok(doc_freq)
+    }
+
+    /// Returns the address of the live document containing the given term,
+    /// if any.
+    ///
+    /// This is meant for primary-key lookups: the term is expected to
+    /// appear in at most one live document, typically in a `STRING` or
+    /// `INT_INDEXED` id field. If several live documents contain
+    /// the term, one of them is returned.
+    ///
+    /// The segments are probed from the smallest to the largest, as
+    /// recently updated documents tend to live in the newest, smaller
+    /// segments. For fields that do not record term frequencies, the
+    /// posting list of a term appearing in a single document of a segment
+    /// is inlined in its `TermInfo`, so that the lookup does not read
+    /// the postings file.
+    pub fn get_by_term(&self, term: &Term) -> Result<Option<DocAddress>> {
+        let mut segment_ords: Vec<usize> = (0..self.segment_readers.len()).collect();
+        segment_ords.sort_by_key(|&segment_ord| self.segment_readers[segment_ord].max_doc());
+        for segment_ord in segment_ords {
+            let segment_reader = &self.segment_readers[segment_ord];
+            let inverted_index = segment_reader.inverted_index(term.field())?;
+            let term_info = match inverted_index.get_term_info(term) {
+                Some(term_info) => term_info,
+                None => continue,
+            };
+            let doc_opt = if let Some(doc) = term_info.inlined_doc {
+                if segment_reader.is_deleted(doc) {
+                    None
+                } else {
+                    Some(doc)
+                }
+            } else {
+                let mut postings = inverted_index
+                    .read_postings_from_terminfo(&term_info, IndexRecordOption::Basic)?;
+                if postings.advance() {
+                    Some(postings.doc())
+                } else {
+                    None
+                }
+            };
+            if let Some(doc) = doc_opt {
+                return Ok(Some(DocAddress(segment_ord as SegmentLocalId, doc)));
+            }
+        }
+        Ok(None)
     }
 
     /// Return the list of segment readers
@@ -59,18 +261,236 @@ impl Searcher {
         &self.segment_readers[segment_ord as usize]
     }
 
+    /// Returns the memory budget of the searches run by this searcher.
+    ///
+    /// Unless it was set via `.with_memory_budget(...)`, the budget is unlimited.
+    pub fn memory_budget(&self) -> &MemoryBudget {
+        &self.memory_budget
+    }
+
+    /// Returns a searcher over the same segments, whose searches
+    /// are bounded by `memory_budget`.
+    ///
+    /// Searches exceeding the budget fail with a `BudgetExceeded` error.
+    /// As reservations are cumulative, a new budget should be used
+    /// for each search.
+    pub fn with_memory_budget(&self, memory_budget: MemoryBudget) -> Searcher {
+        Searcher {
+            schema: self.schema.clone(),
+            segment_readers: self.segment_readers.clone(),
+            tokenizers: self.tokenizers.clone(),
+            memory_budget,
+            metrics: Arc::clone(&self.metrics),
+            source_options: self.source_options.clone(),
+        }
+    }
+
+    /// Returns a searcher over the same segments, in which the documents
+    /// of the given masks are hidden, as if they were deleted.
+    /// (See `SegmentReader::with_mask`)
+    ///
+    /// The masks are keyed by segment id. The segments
+    /// without a mask are searched as is.
+    pub fn with_masks(&self, masks: &HashMap<SegmentId, DeleteBitSet>) -> Searcher {
+        let segment_readers = self.segment_readers
+            .iter()
+            .map(|segment_reader| match masks.get(&segment_reader.segment_id()) {
+                Some(mask) => segment_reader.with_mask(mask),
+                None => segment_reader.clone(),
+            })
+            .collect();
+        Searcher {
+            schema: self.schema.clone(),
+            segment_readers,
+            tokenizers: self.tokenizers.clone(),
+            memory_budget: self.memory_budget.clone(),
+            metrics: Arc::clone(&self.metrics),
+            source_options: self.source_options.clone(),
+        }
+    }
+
+    /// Returns the metrics of the index this searcher was created from.
+    pub(crate) fn metrics(&self) -> &IndexMetrics {
+        &self.metrics
+    }
+
+    /// Returns the address, within this searcher, of the document `doc`
+    /// of the segment `segment_id`.
+    ///
+    /// The segment may be either one of the segments of this searcher,
+    /// or a segment that was merged into one of them, provided the merge
+    /// recorded its doc id mapping. (See `IndexWriter::set_doc_id_mapping`)
+    ///
+    /// Returns `None` if the document cannot be found, or if it is deleted.
+    pub fn resolve_doc(&self, segment_id: SegmentId, doc: DocId) -> Option<DocAddress> {
+        for (segment_ord, segment_reader) in self.segment_readers.iter().enumerate() {
+            let doc_opt = if segment_reader.segment_id() == segment_id {
+                if doc < segment_reader.max_doc() {
+                    Some(doc)
+                } else {
+                    None
+                }
+            } else {
+                segment_reader
+                    .doc_id_mapping()
+                    .and_then(|doc_id_mapping| doc_id_mapping.get(segment_id, doc))
+            };
+            if let Some(doc) = doc_opt {
+                if segment_reader.is_deleted(doc) {
+                    return None;
+                }
+                return Some(DocAddress(segment_ord as SegmentLocalId, doc));
+            }
+        }
+        None
+    }
+
+    /// Translates a `DocAddress` obtained from a previous searcher
+    /// into the address of the same document within this searcher.
+    ///
+    /// Segment ordinals are local to a searcher, and merges change the
+    /// doc ids of the documents, so that the `DocAddress` of a document
+    /// may differ from a searcher to another.
+    ///
+    /// The address can be resolved if its segment still exists, or if it
+    /// was merged by a merge that recorded its doc id mapping. Only one
+    /// merge can be resolved: if the segment resulting from the merge was
+    /// merged again before being part of a searcher, the address is lost.
+    ///
+    /// Returns `None` if the document cannot be found, or if it is deleted.
+    pub fn resolve_doc_address(
+        &self,
+        previous_searcher: &Searcher,
+        doc_address: &DocAddress,
+    ) -> Option<DocAddress> {
+        let DocAddress(segment_ord, doc) = *doc_address;
+        let segment_reader = previous_searcher
+            .segment_readers
+            .get(segment_ord as usize)?;
+        self.resolve_doc(segment_reader.segment_id(), doc)
+    }
+
     /// Runs a query on the segment readers wrapped by the searcher
     pub fn search<C: Collector>(&self, query: &Query, collector: &mut C) -> Result<TimerTree> {
         query.search(self, collector)
     }
 
+    /// Returns an iterator over the `(score, doc_address)` of the documents
+    /// matching `query`, without going through a collector.
+    ///
+    /// The segments are visited one after the other, and the documents of
+    /// a segment are yielded in the order of their doc ids. Hits are computed
+    /// as the iterator is consumed, which makes it possible to export all
+    /// of the documents matching a query without buffering them.
+    /// (See `SearchStream::with_docs` to also fetch the stored documents)
+    pub fn search_streaming(&self, query: &Query) -> Result<SearchStream> {
+        let weight = query.weight(self, true)?;
+        Ok(SearchStream::new(self, weight))
+    }
+
+    /// Returns the values of the fast field `field` for the documents matching `query`.
+    ///
+    /// The values are yielded lazily, by batches, segment after segment,
+    /// so that custom aggregates can be computed without implementing a `Collector`.
+    ///
+    /// The field needs to be a single valued fast field.
+    /// Scoring is disabled.
+    pub fn fetch_field_values<Item: FastValue>(
+        &self,
+        query: &Query,
+        field: Field,
+    ) -> Result<FieldValues<Item>> {
+        let weight = query.weight(self, false)?;
+        Ok(FieldValues::new(self, weight, field))
+    }
+
+    /// Runs a query on the segment readers wrapped by the searcher,
+    /// using up to `num_threads` threads.
+    ///
+    /// Large segments are split into ranges of documents, so that
+    /// even an index made of a single large segment benefits from
+    /// the concurrency.
+    ///
+    /// Each range of documents is collected by its own collector,
+    /// obtained by calling `.split()` over `collector`.
+    /// Once all of the ranges have been processed, the resulting collectors
+    /// are merged back into `collector`, following the order of the documents.
+    pub fn search_parallel<C: MergeableCollector>(
+        &self,
+        query: &Query,
+        collector: &mut C,
+        num_threads: usize,
+    ) -> Result<()> {
+        let start = Instant::now();
+        let num_threads = cmp::max(num_threads, 1);
+        let scoring_enabled = collector.requires_scoring();
+        let slices = leaf_slices(&self.segment_readers, num_threads);
+        let num_slices = slices.len();
+        let tasks: Mutex<Vec<(usize, LeafSlice, C)>> = Mutex::new(
+            slices
+                .into_iter()
+                .enumerate()
+                .map(|(slice_ord, slice)| {
+                    let mut child = collector.split();
+                    child.set_memory_budget(&self.memory_budget)?;
+                    Ok((slice_ord, slice, child))
+                })
+                .collect::<Result<_>>()?,
+        );
+        let children: Mutex<Vec<(usize, C)>> = Mutex::new(Vec::with_capacity(num_slices));
+        let num_workers = cmp::min(num_threads, num_slices);
+        let worker_results: Vec<Result<()>> = crossbeam::scope(|scope| {
+            let handles: Vec<_> = (0..num_workers)
+                .map(|_| {
+                    // `SegmentReader`s are not `Sync`: each worker
+                    // works on its own copy.
+                    let searcher = self.with_memory_budget(self.memory_budget.clone());
+                    let tasks = &tasks;
+                    let children = &children;
+                    scope.spawn(move || -> Result<()> {
+                        let weight = {
+                            let _span = Span::enter("weight", || format!("query={:?}", query));
+                            query.weight(&searcher, scoring_enabled)?
+                        };
+                        loop {
+                            let task = tasks.lock().expect("Task queue lock poisoned").pop();
+                            let (slice_ord, slice, mut child) = match task {
+                                Some(task) => task,
+                                None => return Ok(()),
+                            };
+                            let segment_reader = searcher.segment_reader(slice.segment_ord);
+                            collect_slice(&*weight, segment_reader, &slice, &mut child)?;
+                            children
+                                .lock()
+                                .expect("Collector list lock poisoned")
+                                .push((slice_ord, child));
+                        }
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join()).collect()
+        });
+        for worker_result in worker_results {
+            worker_result?;
+        }
+        let mut children = children
+            .into_inner()
+            .expect("Collector list lock poisoned");
+        children.sort_by_key(|&(slice_ord, _)| slice_ord);
+        for (_, child) in children {
+            collector.merge(child);
+        }
+        self.metrics.record_search(start.elapsed());
+        Ok(())
+    }
+
     /// Return the field searcher associated to a `Field`.
-    pub fn field(&self, field: Field) -> FieldSearcher {
+    pub fn field(&self, field: Field) -> Result<FieldSearcher> {
         let inv_index_readers = self.segment_readers
             .iter()
             .map(|segment_reader| segment_reader.inverted_index(field))
-            .collect::<Vec<_>>();
-        FieldSearcher::new(inv_index_readers)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(FieldSearcher::new(inv_index_readers))
     }
 }
 
@@ -96,7 +516,17 @@ impl FieldSearcher {
 
 impl From<Vec<SegmentReader>> for Searcher {
     fn from(segment_readers: Vec<SegmentReader>) -> Searcher {
-        Searcher { segment_readers }
+        let schema = segment_readers
+            .first()
+            .map(|segment_reader| segment_reader.schema().clone())
+            .unwrap_or_else(|| SchemaBuilder::default().build());
+        Searcher::new(
+            schema,
+            segment_readers,
+            TokenizerManager::default(),
+            Arc::new(IndexMetrics::default()),
+            None,
+        )
     }
 }
 
@@ -109,3 +539,424 @@ impl fmt::Debug for Searcher {
         write!(f, "Searcher({:?})", segment_ids)
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use collector::{CountCollector, TopCollector};
+    use query::TermQuery;
+    use futures::Future;
+    use schema::{IndexRecordOption, SchemaBuilder, SourceOptions, Term, FAST, INT_INDEXED,
+                 INT_STORED, STORED, STRING, TEXT};
+    use core::FieldValuesBatch;
+    use Score;
+    use directory::{ManagedDirectory, RAMDirectory};
+    use Index;
+    use IndexSettings;
+
+    #[test]
+    fn test_fetch_field_values() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let price_field = schema_builder.add_i64_field("price", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0..3_000i64 {
+                let text = if i % 3 == 0 { "a b" } else { "b" };
+                index_writer.add_document(doc!(text_field => text, price_field => i - 100));
+                if i == 1_999 {
+                    index_writer.commit().unwrap();
+                }
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let query = TermQuery::new_text(text_field, "a");
+        let batches: Vec<FieldValuesBatch<i64>> = searcher
+            .fetch_field_values(&query, price_field)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert!(batches.iter().all(|batch| batch.values().len() <= 1_024));
+        assert!(batches.iter().all(|batch| batch.docs().len() == batch.values().len()));
+        let mut values: Vec<i64> = batches
+            .iter()
+            .flat_map(|batch| batch.values().iter().cloned())
+            .collect();
+        values.sort();
+        let expected_values: Vec<i64> = (0..3_000i64)
+            .filter(|i| i % 3 == 0)
+            .map(|i| i - 100)
+            .collect();
+        assert_eq!(values, expected_values);
+
+        // the scorers of positions-enabled postings must not be
+        // advanced once exhausted.
+        let query_with_positions = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::WithFreqsAndPositions,
+        );
+        let num_values: usize = searcher
+            .fetch_field_values::<i64>(&query_with_positions, price_field)
+            .unwrap()
+            .map(|batch| batch.unwrap().values().len())
+            .sum();
+        assert_eq!(num_values, expected_values.len());
+
+        let text_as_fast_field = searcher.fetch_field_values::<u64>(&query, text_field).unwrap();
+        assert_eq!(text_as_fast_field.filter(|batch| batch.is_err()).count(), 1);
+    }
+
+    #[test]
+    fn test_search_streaming() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0..30 {
+                let text = if i % 3 == 0 { "a b" } else { "b" };
+                index_writer.add_document(doc!(text_field => text));
+                if i == 19 {
+                    index_writer.commit().unwrap();
+                }
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let query = TermQuery::new_text(text_field, "a");
+        let hits: Vec<(Score, DocAddress)> = searcher
+            .search_streaming(&query)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(hits.len(), 10);
+        let mut top_collector = TopCollector::with_limit(10);
+        searcher.search(&query, &mut top_collector).unwrap();
+        let mut expected_hits = top_collector.score_docs();
+        expected_hits.sort_by_key(|&(_, doc_address)| doc_address);
+        assert_eq!(hits, expected_hits);
+        for segment_hits in hits.windows(2) {
+            assert!(segment_hits[0].1 < segment_hits[1].1);
+        }
+
+        let mut num_docs = 0;
+        for hit in searcher.search_streaming(&query).unwrap().with_docs() {
+            let (_, _, doc) = hit.unwrap();
+            assert_eq!(doc.get_first(text_field).unwrap().text(), "a b");
+            num_docs += 1;
+        }
+        assert_eq!(num_docs, 10);
+    }
+
+    #[test]
+    fn test_searcher_source() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title_field = schema_builder.add_text_field("title", TEXT);
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let count_field = schema_builder.add_u64_field("count", INT_INDEXED);
+        schema_builder.add_text_field("source", STORED);
+        let schema = schema_builder.build();
+        let settings = IndexSettings {
+            source: Some(SourceOptions::new("source").exclude("body")),
+            ..IndexSettings::default()
+        };
+        let directory = ManagedDirectory::new(RAMDirectory::create()).unwrap();
+        let index = Index::from_directory_with_settings(directory, schema, settings).unwrap();
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(
+                title_field => "The Old Man and the Sea",
+                body_field => "He was an old man who fished alone",
+                count_field => 3u64
+            ));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let source = searcher.source(&DocAddress(0, 0)).unwrap();
+        let expected_source: JsonValue =
+            serde_json::from_str(r#"{"count": [3], "title": ["The Old Man and the Sea"]}"#)
+                .unwrap();
+        assert_eq!(source, expected_source);
+        let doc = searcher.doc(&DocAddress(0, 0)).unwrap();
+        assert_eq!(doc.field_values().len(), 1);
+    }
+
+    #[test]
+    fn test_searcher_source_requires_stored_text_field() {
+        let mut schema_builder = SchemaBuilder::default();
+        schema_builder.add_text_field("source", TEXT);
+        let settings = IndexSettings {
+            source: Some(SourceOptions::new("source")),
+            ..IndexSettings::default()
+        };
+        let directory = ManagedDirectory::new(RAMDirectory::create()).unwrap();
+        assert!(
+            Index::from_directory_with_settings(directory, schema_builder.build(), settings)
+                .is_err()
+        );
+        let index = Index::create_in_ram(SchemaBuilder::default().build());
+        assert!(index.searcher().source(&DocAddress(0, 0)).is_err());
+    }
+
+    #[test]
+    fn test_search_parallel() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0..25_000 {
+                let text = match i % 3 {
+                    0 => "a",
+                    1 => "a b",
+                    _ => "b c a b",
+                };
+                index_writer.add_document(doc!(text_field => text));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let slices = leaf_slices(searcher.segment_readers(), 4);
+        assert_eq!(
+            slices,
+            vec![
+                LeafSlice {
+                    segment_ord: 0,
+                    start_doc: 0,
+                    end_doc: 12_500,
+                },
+                LeafSlice {
+                    segment_ord: 0,
+                    start_doc: 12_500,
+                    end_doc: 25_000,
+                },
+            ]
+        );
+        let query = TermQuery::new(
+            Term::from_field_text(text_field, "b"),
+            IndexRecordOption::WithFreqs,
+        );
+        {
+            let mut count_collector = CountCollector::default();
+            searcher
+                .search_parallel(&query, &mut count_collector, 4)
+                .unwrap();
+            assert_eq!(count_collector.count(), 16_666);
+        }
+        {
+            let mut top_collector = TopCollector::with_limit(20);
+            searcher.search(&query, &mut top_collector).unwrap();
+            let mut parallel_top_collector = TopCollector::with_limit(20);
+            searcher
+                .search_parallel(&query, &mut parallel_top_collector, 4)
+                .unwrap();
+            assert_eq!(parallel_top_collector.docs(), top_collector.docs());
+        }
+    }
+
+    fn aux_test_resolve_doc_address(doc_id_mapping_enabled: bool) {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED | INT_STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.set_doc_id_mapping(doc_id_mapping_enabled);
+        for id in 0u64..6u64 {
+            index_writer.add_document(doc!(id_field => id));
+            if id % 3 == 2 {
+                index_writer.commit().unwrap();
+            }
+        }
+        index.load_searchers().unwrap();
+        let previous_searcher = index.searcher();
+        assert_eq!(previous_searcher.segment_readers().len(), 2);
+
+        index_writer.delete_term(Term::from_field_u64(id_field, 1u64));
+        index_writer.commit().unwrap();
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+
+        let get_id = |searcher: &Searcher, doc_address: &DocAddress| {
+            searcher
+                .doc(doc_address)
+                .unwrap()
+                .get_first(id_field)
+                .unwrap()
+                .u64_value()
+        };
+        for segment_ord in 0..2u32 {
+            for doc in 0..3u32 {
+                let doc_address = DocAddress(segment_ord, doc);
+                let id = get_id(&*previous_searcher, &doc_address);
+                let resolved_doc_address =
+                    searcher.resolve_doc_address(&*previous_searcher, &doc_address);
+                if doc_id_mapping_enabled && id != 1u64 {
+                    assert_eq!(get_id(&*searcher, &resolved_doc_address.unwrap()), id);
+                } else {
+                    assert!(resolved_doc_address.is_none());
+                }
+            }
+        }
+        // doc addresses of the current searcher resolve to themselves.
+        assert_eq!(
+            searcher.resolve_doc_address(&*searcher, &DocAddress(0, 2)),
+            Some(DocAddress(0, 2))
+        );
+        assert!(
+            searcher
+                .resolve_doc_address(&*searcher, &DocAddress(0, 5))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_resolve_doc_address() {
+        aux_test_resolve_doc_address(true);
+        aux_test_resolve_doc_address(false);
+    }
+
+    #[test]
+    fn test_get_by_term() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_text_field("id", STRING | STORED);
+        let num_field = schema_builder.add_u64_field("num", INT_INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        for i in 0u64..100u64 {
+            index_writer.add_document(doc!(id_field => format!("id{}", i), num_field => i % 10));
+        }
+        index_writer.commit().unwrap();
+        // upsert of the document `id3`.
+        index_writer.delete_term(Term::from_field_text(id_field, "id3"));
+        index_writer.add_document(doc!(id_field => "id3", num_field => 100u64));
+        index_writer.delete_term(Term::from_field_text(id_field, "id4"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+
+        let get_id = |term: &Term| {
+            searcher.get_by_term(term).unwrap().map(|doc_address| {
+                let doc = searcher.doc(&doc_address).unwrap();
+                doc.get_first(id_field).unwrap().text().to_string()
+            })
+        };
+        assert_eq!(
+            get_id(&Term::from_field_text(id_field, "id17")),
+            Some("id17".to_string())
+        );
+        let doc_address = searcher
+            .get_by_term(&Term::from_field_text(id_field, "id3"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(searcher.segment_reader(doc_address.segment_ord()).max_doc(), 1);
+        assert_eq!(get_id(&Term::from_field_text(id_field, "id4")), None);
+        assert_eq!(get_id(&Term::from_field_text(id_field, "id100")), None);
+        // terms appearing in several documents.
+        assert!(get_id(&Term::from_field_u64(num_field, 7u64)).is_some());
+        assert_eq!(
+            get_id(&Term::from_field_u64(num_field, 100u64)),
+            Some("id3".to_string())
+        );
+    }
+
+    #[test]
+    fn test_searcher_with_masks() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        for i in 0..20 {
+            let text = if i % 2 == 0 { "a b" } else { "b" };
+            index_writer.add_document(doc!(text_field => text));
+        }
+        index_writer.commit().unwrap();
+        index_writer.delete_term(Term::from_field_text(text_field, "a"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let segment_reader = &searcher.segment_readers()[0];
+        assert_eq!(segment_reader.num_docs(), 10);
+
+        // hides the documents 1 and 3 in addition to the deleted documents.
+        let exported = segment_reader.delete_bitset().to_bytes(segment_reader.max_doc());
+        assert_eq!(exported.len(), 3);
+        assert_eq!(exported[0], 0b0101_0101);
+        let mut mask = vec![0u8; exported.len()];
+        mask[0] = 0b0000_1010;
+        let mut masks = HashMap::new();
+        masks.insert(
+            segment_reader.segment_id(),
+            DeleteBitSet::from_bytes(mask, segment_reader.max_doc()),
+        );
+        let masked_searcher = searcher.with_masks(&masks);
+        assert!(masked_searcher.segment_readers()[0].is_masked());
+        assert_eq!(masked_searcher.num_docs(), 8);
+        let count = |searcher: &Searcher| {
+            let mut count_collector = CountCollector::default();
+            searcher
+                .search(&TermQuery::new_text(text_field, "b"), &mut count_collector)
+                .unwrap();
+            count_collector.count()
+        };
+        assert_eq!(count(&masked_searcher), 8);
+        // the mask is not written to the index.
+        assert_eq!(count(&searcher), 10);
+        index.load_searchers().unwrap();
+        assert_eq!(count(&*index.searcher()), 10);
+    }
+
+    #[test]
+    fn test_searcher_with_masks_range_query() {
+        use query::RangeQuery;
+
+        let mut schema_builder = SchemaBuilder::default();
+        let num_field = schema_builder.add_u64_field("num", INT_INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        for i in 0..10u64 {
+            index_writer.add_document(doc!(num_field => i));
+        }
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let segment_reader = &searcher.segment_readers()[0];
+
+        // hides the documents 2 and 3.
+        let mut mask = vec![0u8; 2];
+        mask[0] = 0b0000_1100;
+        let mut masks = HashMap::new();
+        masks.insert(
+            segment_reader.segment_id(),
+            DeleteBitSet::from_bytes(mask, segment_reader.max_doc()),
+        );
+        let masked_searcher = searcher.with_masks(&masks);
+        let range_docs = |searcher: &Searcher| {
+            let range_query = RangeQuery::new_u64(num_field, 1..5);
+            let mut top_collector = TopCollector::with_limit(10);
+            searcher.search(&range_query, &mut top_collector).unwrap();
+            let mut docs: Vec<DocId> = top_collector
+                .docs()
+                .into_iter()
+                .map(|doc_address| doc_address.doc())
+                .collect();
+            docs.sort();
+            docs
+        };
+        assert_eq!(range_docs(&masked_searcher), vec![1, 4]);
+        assert_eq!(range_docs(&searcher), vec![1, 2, 3, 4]);
+    }
+}
@@ -1,7 +1,9 @@
 /// Enum describing each component of a tantivy segment.
 /// Each component is stored in its own file,
 /// using the pattern `segment_uuid`.`component_extension`,
-/// except the delete component that takes an `segment_uuid`.`delete_opstamp`.`component_extension`
+/// except the delete component that takes an `segment_uuid`.`delete_opstamp`.`component_extension`,
+/// and the fast fields component that takes an `segment_uuid`.`generation`.`component_extension`
+/// once a fast field has been added to the segment.
 #[derive(Copy, Clone)]
 pub enum SegmentComponent {
     /// Postings (or inverted list). Sorted lists of document ids, associated to terms
@@ -22,12 +24,17 @@ pub enum SegmentComponent {
     STORE,
     /// Bitset describing which document of the segment is deleted.
     DELETE,
+    /// Mapping from the doc ids of the segments that were merged into
+    /// this segment to its doc ids. (See `DocIdMapping`)
+    ///
+    /// It is only written by merges, if enabled.
+    DOCMAP,
 }
 
 impl SegmentComponent {
     /// Iterates through the components.
     pub fn iterator() -> impl Iterator<Item = &'static SegmentComponent> {
-        static SEGMENT_COMPONENTS: [SegmentComponent; 7] = [
+        static SEGMENT_COMPONENTS: [SegmentComponent; 8] = [
             SegmentComponent::POSTINGS,
             SegmentComponent::POSITIONS,
             SegmentComponent::FASTFIELDS,
@@ -35,6 +42,7 @@ impl SegmentComponent {
             SegmentComponent::TERMS,
             SegmentComponent::STORE,
             SegmentComponent::DELETE,
+            SegmentComponent::DOCMAP,
         ];
         SEGMENT_COMPONENTS.into_iter()
     }
@@ -1,6 +1,9 @@
 use uuid::Uuid;
 use std::fmt;
 use std::cmp::{Ord, Ordering};
+use std::io;
+use std::io::{Read, Write};
+use common::BinarySerializable;
 
 #[cfg(test)]
 use std::sync::atomic;
@@ -78,3 +81,17 @@ impl Ord for SegmentId {
         self.0.as_bytes().cmp(other.0.as_bytes())
     }
 }
+
+impl BinarySerializable for SegmentId {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.0.as_bytes())
+    }
+
+    fn deserialize<R: Read>(reader: &mut R) -> io::Result<SegmentId> {
+        let mut bytes = [0u8; 16];
+        reader.read_exact(&mut bytes)?;
+        let uuid = Uuid::from_bytes(&bytes)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid segment id"))?;
+        Ok(SegmentId(uuid))
+    }
+}
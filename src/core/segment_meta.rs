@@ -1,23 +1,40 @@
 use core::SegmentId;
+use core::compatibility::FAST_FIELDS_GENERATION_FEATURE;
 use super::SegmentComponent;
+use schema::Field;
 use std::path::PathBuf;
 use std::collections::HashSet;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 struct DeleteMeta {
     num_deleted_docs: u32,
     opstamp: u64,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+struct FastFieldBounds {
+    field: Field,
+    min_value: u64,
+    max_value: u64,
+}
+
 /// `SegmentMeta` contains simple meta information about a segment.
 ///
 /// For instance the number of docs it contains,
 /// how many are deleted, etc.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SegmentMeta {
     segment_id: SegmentId,
     max_doc: u32,
     deletes: Option<DeleteMeta>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fast_field_bounds: Vec<FastFieldBounds>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    single_term_fields: Vec<Field>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fast_fields_generation: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    features: Vec<String>,
 }
 
 impl SegmentMeta {
@@ -28,6 +45,10 @@ impl SegmentMeta {
             segment_id,
             max_doc: 0,
             deletes: None,
+            fast_field_bounds: Vec::new(),
+            single_term_fields: Vec::new(),
+            fast_fields_generation: None,
+            features: Vec::new(),
         }
     }
 
@@ -67,13 +88,39 @@ impl SegmentMeta {
             SegmentComponent::POSTINGS => ".idx".to_string(),
             SegmentComponent::TERMS => ".term".to_string(),
             SegmentComponent::STORE => ".store".to_string(),
-            SegmentComponent::FASTFIELDS => ".fast".to_string(),
+            SegmentComponent::FASTFIELDS => match self.fast_fields_generation {
+                Some(generation) => format!(".{}.fast", generation),
+                None => ".fast".to_string(),
+            },
             SegmentComponent::FIELDNORMS => ".fieldnorm".to_string(),
             SegmentComponent::DELETE => format!(".{}.del", self.delete_opstamp().unwrap_or(0)),
+            SegmentComponent::DOCMAP => ".docmap".to_string(),
         });
         PathBuf::from(path)
     }
 
+    /// Points the segment to a new version of its fast field file,
+    /// which has been rewritten to add a fast field.
+    /// (See `Index::backfill_fast_field`)
+    pub(crate) fn next_fast_fields_generation(&mut self) {
+        let generation = self.fast_fields_generation.map(|generation| generation + 1);
+        self.fast_fields_generation = Some(generation.unwrap_or(1));
+        self.add_feature(FAST_FIELDS_GENERATION_FEATURE);
+    }
+
+    /// Returns the optional features of the segment format
+    /// used by the segment. (See `CompatibilityReport`)
+    pub fn features(&self) -> &[String] {
+        &self.features
+    }
+
+    #[doc(hidden)]
+    pub fn add_feature(&mut self, feature: &str) {
+        if !self.features.iter().any(|existing| existing == feature) {
+            self.features.push(feature.to_string());
+        }
+    }
+
     /// Return the highest doc id + 1
     ///
     /// If there are no deletes, then num_docs = max_docs
@@ -100,6 +147,87 @@ impl SegmentMeta {
         self.deletes.is_some()
     }
 
+    /// Returns the smallest and the largest values of a single-valued
+    /// `u64` or `i64` fast field in the segment, as their `u64` representation,
+    /// if they were recorded when the segment was written.
+    ///
+    /// The values of the deleted documents are included.
+    pub fn fast_field_bounds(&self, field: Field) -> Option<(u64, u64)> {
+        self.fast_field_bounds
+            .iter()
+            .find(|bounds| bounds.field == field)
+            .map(|bounds| (bounds.min_value, bounds.max_value))
+    }
+
+    #[doc(hidden)]
+    pub fn set_fast_field_bounds(&mut self, field: Field, min_value: u64, max_value: u64) {
+        self.fast_field_bounds.retain(|bounds| bounds.field != field);
+        self.fast_field_bounds.push(FastFieldBounds {
+            field,
+            min_value,
+            max_value,
+        });
+    }
+
+    /// Records the bounds of the fast fields of a segment merging the
+    /// given segments, for the fields whose bounds they all recorded.
+    pub(crate) fn merge_fast_field_bounds<'a, I>(&mut self, segment_metas: I)
+    where
+        I: IntoIterator<Item = &'a SegmentMeta>,
+    {
+        let mut segment_metas = segment_metas.into_iter();
+        let mut fast_field_bounds = match segment_metas.next() {
+            Some(segment_meta) => segment_meta.fast_field_bounds.clone(),
+            None => Vec::new(),
+        };
+        for segment_meta in segment_metas {
+            fast_field_bounds = fast_field_bounds
+                .into_iter()
+                .filter_map(|bounds| {
+                    segment_meta
+                        .fast_field_bounds(bounds.field)
+                        .map(|(min_value, max_value)| FastFieldBounds {
+                            field: bounds.field,
+                            min_value: bounds.min_value.min(min_value),
+                            max_value: bounds.max_value.max(max_value),
+                        })
+                })
+                .collect();
+        }
+        self.fast_field_bounds = fast_field_bounds;
+    }
+
+    /// Returns true iff the field is an indexed integer field,
+    /// for which every document of the segment holds one value at most.
+    ///
+    /// Segments written by older versions of tantivy do not
+    /// record this information, and return false.
+    pub fn has_single_term_per_doc(&self, field: Field) -> bool {
+        self.single_term_fields.contains(&field)
+    }
+
+    #[doc(hidden)]
+    pub fn set_single_term_fields(&mut self, single_term_fields: Vec<Field>) {
+        self.single_term_fields = single_term_fields;
+    }
+
+    /// Records the fields holding one term per document at most in a segment
+    /// merging the given segments, that is the fields they all recorded.
+    pub(crate) fn merge_single_term_fields<'a, I>(&mut self, segment_metas: I)
+    where
+        I: IntoIterator<Item = &'a SegmentMeta>,
+    {
+        let mut segment_metas = segment_metas.into_iter();
+        let mut single_term_fields = match segment_metas.next() {
+            Some(segment_meta) => segment_meta.single_term_fields.clone(),
+            None => Vec::new(),
+        };
+        for segment_meta in segment_metas {
+            single_term_fields.retain(|&field| segment_meta.has_single_term_per_doc(field));
+        }
+        self.single_term_fields = single_term_fields;
+    }
+
     #[doc(hidden)]
     pub fn set_max_doc(&mut self, max_doc: u32) {
         self.max_doc = max_doc;
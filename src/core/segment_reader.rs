@@ -2,11 +2,14 @@ use Result;
 use core::Segment;
 use core::SegmentId;
 use core::SegmentComponent;
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
+use std::cmp;
+use crossbeam;
 use common::HasLen;
 use core::SegmentMeta;
+use core::DocIdMapping;
 use fastfield::{self, FastFieldNotAvailableError};
-use fastfield::DeleteBitSet;
+use fastfield::{DeleteBitSet, DocIdsAlive};
 use store::StoreReader;
 use directory::ReadOnlySource;
 use schema::Document;
@@ -15,17 +18,23 @@ use std::sync::Arc;
 use std::collections::HashMap;
 use common::CompositeFile;
 use std::fmt;
+use std::io;
 use core::InvertedIndexReader;
 use schema::Field;
 use schema::FieldType;
+use schema::FieldEntry;
 use error::ErrorKind;
 use termdict::TermDictionaryImpl;
 use fastfield::FacetReader;
+use fastfield::VectorFastFieldReader;
+use fastfield::BytesFastFieldReader;
 use fastfield::FastFieldReader;
 use schema::Schema;
-use termdict::TermDictionary;
+use termdict::{TermDictionary, TermStreamer};
 use fastfield::{FastValue, MultiValueIntFastFieldReader};
 use schema::Cardinality;
+use common::BinarySerializable;
+use postings::PostingsBlockSize;
 
 /// Entry point to access all of the datastructures of the `Segment`
 ///
@@ -43,8 +52,10 @@ use schema::Cardinality;
 #[derive(Clone)]
 pub struct SegmentReader {
     inv_idx_reader_cache: Arc<RwLock<HashMap<Field, Arc<InvertedIndexReader>>>>,
+    sorted_fields_cache: Arc<RwLock<HashMap<Field, bool>>>,
 
     segment_id: SegmentId,
+    doc_id_mapping: Option<Arc<DocIdMapping>>,
     segment_meta: SegmentMeta,
 
     termdict_composite: CompositeFile,
@@ -55,10 +66,16 @@ pub struct SegmentReader {
 
     store_reader: StoreReader,
     delete_bitset: DeleteBitSet,
+    masked: bool,
     schema: Schema,
 }
 
 impl SegmentReader {
+    /// Returns the schema of the segment.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
     /// Returns the highest document id ever attributed in
     /// this segment + 1.
     /// Today, `tantivy` does not handle deletes, so it happens
@@ -73,7 +90,11 @@ impl SegmentReader {
     /// Today, `tantivy` does not handle deletes so max doc and
     /// num_docs are the same.
     pub fn num_docs(&self) -> DocId {
-        self.segment_meta.num_docs()
+        if self.masked {
+            self.max_doc() - self.num_deleted_docs()
+        } else {
+            self.segment_meta.num_docs()
+        }
     }
 
     /// Return the number of documents that have been
@@ -82,16 +103,18 @@ impl SegmentReader {
         self.delete_bitset.len() as DocId
     }
 
+    /// Returns true iff some of the documents of the segment are deleted.
+    pub fn has_deletes(&self) -> bool {
+        self.num_deleted_docs() > 0
+    }
+
     /// Accessor to a segment's fast field reader given a field.
     ///
     /// Returns the u64 fast value reader if the field
     /// is a u64 field indexed as "fast".
     ///
     /// Return a FastFieldNotAvailableError if the field is not
-    /// declared as a fast field in the schema.
-    ///
-    /// # Panics
-    /// May panic if the index is corrupted.
+    /// declared as a fast field in the schema, or if its data is corrupted.
     pub fn fast_field_reader<Item: FastValue>(
         &self,
         field: Field,
@@ -101,12 +124,153 @@ impl SegmentReader {
             self.fast_fields_composite
                 .open_read(field)
                 .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))
-                .map(FastFieldReader::open)
+                .and_then(|data| open_fast_field(data, field_entry))
         } else {
             Err(FastFieldNotAvailableError::new(field_entry))
         }
     }
 
+    /// Accessor to a segment's fast field reader given a field, as
+    /// `fast_field_reader`, except that if the segment is missing the
+    /// fast field, the reader returns the default value of `Item` (`0`)
+    /// for all of the documents. (See `is_missing_fast_field`)
+    pub fn fast_field_reader_or_default<Item: FastValue>(
+        &self,
+        field: Field,
+    ) -> fastfield::Result<FastFieldReader<Item>> {
+        let field_entry = self.schema.get_field_entry(field);
+        let cardinality = Item::fast_field_cardinality(field_entry.field_type());
+        if self.is_missing_fast_field(field) && cardinality == Some(Cardinality::SingleValue) {
+            return Ok(FastFieldReader::constant(Item::default()));
+        }
+        self.fast_field_reader(field)
+    }
+
+    /// Returns the reader of a single-valued `u64` or `i64` fast field,
+    /// yielding the `u64` representation of its values.
+    ///
+    /// As for terms, `i64` values are mapped to `u64` in an order-preserving way.
+    pub(crate) fn u64_fast_field_reader(&self, field: Field) -> Option<FastFieldReader<u64>> {
+        match *self.schema.get_field_entry(field).field_type() {
+            FieldType::U64(ref int_options) | FieldType::I64(ref int_options)
+                if int_options.get_fastfield_cardinality() == Some(Cardinality::SingleValue) =>
+            {
+                self.fast_fields_composite
+                    .open_read(field)
+                    .and_then(|data| FastFieldReader::open(data).ok())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the smallest and the largest values of a single-valued
+    /// `u64` or `i64` fast field in the segment, including the values
+    /// of the deleted documents.
+    ///
+    /// Queries and collectors may use them to skip the segments
+    /// where no document can match or rank high enough.
+    ///
+    /// Returns `None` if the field is not a single-valued fast field of type `Item`.
+    pub fn fast_field_bounds<Item: FastValue>(&self, field: Field) -> Option<(Item, Item)> {
+        let field_entry = self.schema.get_field_entry(field);
+        if Item::fast_field_cardinality(field_entry.field_type()) != Some(Cardinality::SingleValue)
+        {
+            return None;
+        }
+        self.u64_fast_field_bounds(field)
+            .map(|(min_value, max_value)| (Item::from_u64(min_value), Item::from_u64(max_value)))
+    }
+
+    /// Returns the bounds of a single-valued `u64` or `i64` fast field,
+    /// as their `u64` representation. (See `.fast_field_bounds(...)`)
+    ///
+    /// They are read from the segment meta, or from the header
+    /// of the fast field if the segment meta did not record them.
+    pub(crate) fn u64_fast_field_bounds(&self, field: Field) -> Option<(u64, u64)> {
+        self.segment_meta.fast_field_bounds(field).or_else(|| {
+            self.u64_fast_field_reader(field).map(|fast_field_reader| {
+                (fast_field_reader.min_value(), fast_field_reader.max_value())
+            })
+        })
+    }
+
+    /// Returns the bounds of all of the single-valued `u64` and `i64`
+    /// fast fields, as read from their headers.
+    pub(crate) fn read_fast_field_bounds(&self) -> Vec<(Field, u64, u64)> {
+        (0..self.schema.fields().len())
+            .map(|field_id| Field(field_id as u32))
+            .filter_map(|field| {
+                self.u64_fast_field_reader(field).map(|fast_field_reader| {
+                    (field, fast_field_reader.min_value(), fast_field_reader.max_value())
+                })
+            })
+            .collect()
+    }
+
+    /// Returns true iff all of the documents of the segment hold exactly one
+    /// value for the indexed, single-valued integer fast field `field`, and
+    /// are sorted by increasing values of the field.
+    ///
+    /// `RangeQuery` relies on it to binary search the fast field
+    /// rather than walking the term dictionary.
+    ///
+    /// The check scans the values of the field and its term dictionary.
+    /// Its result is cached.
+    pub fn is_sorted_by(&self, field: Field) -> bool {
+        if let Some(&sorted) = self.sorted_fields_cache
+            .read()
+            .expect("Lock poisoned. This should never happen")
+            .get(&field)
+        {
+            return sorted;
+        }
+        let sorted = self.compute_is_sorted_by(field);
+        self.sorted_fields_cache
+            .write()
+            .expect("Sorted fields cache lock poisoned. This should never happen.")
+            .insert(field, sorted);
+        sorted
+    }
+
+    fn compute_is_sorted_by(&self, field: Field) -> bool {
+        if !self.schema.get_field_entry(field).is_indexed() {
+            return false;
+        }
+        let fast_field_reader = match self.u64_fast_field_reader(field) {
+            Some(fast_field_reader) => fast_field_reader,
+            None => return false,
+        };
+        let max_doc = self.max_doc();
+        let mut vals = vec![0u64; 1_024];
+        let mut previous_val = 0u64;
+        let mut start = 0u32;
+        while start < max_doc {
+            let len = cmp::min(vals.len() as u32, max_doc - start);
+            let vals = &mut vals[..len as usize];
+            fast_field_reader.get_range(start, vals);
+            for &val in vals.iter() {
+                if val < previous_val {
+                    return false;
+                }
+                previous_val = val;
+            }
+            start += len;
+        }
+        // Documents without a value have a default value in the fast field.
+        // They are detected by counting the documents holding a term,
+        // as a single-valued field holds one term per document at most.
+        let inverted_index = match self.inverted_index(field) {
+            Ok(inverted_index) => inverted_index,
+            Err(_) => return false,
+        };
+        let mut term_stream = inverted_index.terms().stream();
+        let mut num_docs_with_value = 0u64;
+        while term_stream.advance() {
+            num_docs_with_value += u64::from(term_stream.value().doc_freq);
+        }
+        num_docs_with_value == u64::from(max_doc)
+    }
+
     /// Accessor to the `MultiValueIntFastFieldReader` associated to a given `Field`.
     /// May panick if the field is not a multivalued fastfield of the type `Item`.
     pub fn multi_fast_field_reader<Item: FastValue>(&self, field: Field) -> fastfield::Result<MultiValueIntFastFieldReader<Item>> {
@@ -115,17 +279,57 @@ impl SegmentReader {
             let idx_reader = self.fast_fields_composite
                 .open_read_with_idx(field, 0)
                 .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))
-                .map(FastFieldReader::open)?;
+                .and_then(|data| open_fast_field(data, field_entry))?;
             let vals_reader = self.fast_fields_composite
                 .open_read_with_idx(field, 1)
                 .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))
-                .map(FastFieldReader::open)?;
+                .and_then(|data| open_fast_field(data, field_entry))?;
             Ok(MultiValueIntFastFieldReader::open(idx_reader, vals_reader))
         } else {
             Err(FastFieldNotAvailableError::new(field_entry))
         }
     }
 
+    /// Accessor to the `VectorFastFieldReader` associated to a given `Field`.
+    ///
+    /// Return a FastFieldNotAvailableError if the field is not
+    /// declared as a vector field in the schema.
+    pub fn vector_reader(&self, field: Field) -> fastfield::Result<VectorFastFieldReader> {
+        let field_entry = self.schema.get_field_entry(field);
+        if let FieldType::Vector(ref vector_options) = *field_entry.field_type() {
+            self.fast_fields_composite
+                .open_read(field)
+                .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))
+                .map(|data| VectorFastFieldReader::open(data, vector_options.dims()))
+        } else {
+            Err(FastFieldNotAvailableError::new(field_entry))
+        }
+    }
+
+    /// Accessor to the `BytesFastFieldReader` associated to a given `Field`.
+    ///
+    /// Return a FastFieldNotAvailableError if the field is not
+    /// declared as a bytes fast field in the schema.
+    pub fn bytes_fast_field_reader(
+        &self,
+        field: Field,
+    ) -> fastfield::Result<BytesFastFieldReader> {
+        let field_entry = self.schema.get_field_entry(field);
+        match *field_entry.field_type() {
+            FieldType::Bytes(ref bytes_options) if bytes_options.is_fast() => {
+                let idx_reader = self.fast_fields_composite
+                    .open_read_with_idx(field, 0)
+                    .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))
+                    .and_then(|data| open_fast_field(data, field_entry))?;
+                let values = self.fast_fields_composite
+                    .open_read_with_idx(field, 1)
+                    .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))?;
+                Ok(BytesFastFieldReader::open(idx_reader, values))
+            }
+            _ => Err(FastFieldNotAvailableError::new(field_entry)),
+        }
+    }
+
     /// Accessor to the `FacetReader` associated to a given `Field`.
     pub fn facet_reader(&self, field: Field) -> Result<FacetReader> {
         let field_entry = self.schema.get_field_entry(field);
@@ -145,7 +349,7 @@ impl SegmentReader {
                 field_entry.name()
             ))
         })?;
-        let termdict = TermDictionaryImpl::from_source(termdict_source);
+        let termdict = TermDictionaryImpl::open(termdict_source)?;
         let facet_reader = FacetReader::new(term_ords_reader, termdict);
         Ok(facet_reader)
     }
@@ -161,7 +365,7 @@ impl SegmentReader {
     pub fn get_fieldnorms_reader(&self, field: Field) -> Option<FastFieldReader<u64>> {
         self.fieldnorms_composite
             .open_read(field)
-            .map(FastFieldReader::open)
+            .and_then(|data| FastFieldReader::open(data).ok())
     }
 
     /// Accessor to the segment's `StoreReader`.
@@ -170,12 +374,16 @@ impl SegmentReader {
     }
 
     /// Open a new segment for reading.
+    ///
+    /// Returns an error, rather than panicking, if the footers of
+    /// the segment files or the headers of the term dictionaries,
+    /// fast fields and field norms are corrupted.
     pub fn open(segment: &Segment) -> Result<SegmentReader> {
         let termdict_source = segment.open_read(SegmentComponent::TERMS)?;
         let termdict_composite = CompositeFile::open(&termdict_source)?;
 
         let store_source = segment.open_read(SegmentComponent::STORE)?;
-        let store_reader = StoreReader::from_source(store_source);
+        let store_reader = StoreReader::from_source(store_source)?;
 
         let postings_source = segment.open_read(SegmentComponent::POSTINGS)?;
         let postings_composite = CompositeFile::open(&postings_source)?;
@@ -196,58 +404,159 @@ impl SegmentReader {
 
         let delete_bitset = if segment.meta().has_deletes() {
             let delete_data = segment.open_read(SegmentComponent::DELETE)?;
-            DeleteBitSet::open(delete_data)
+            DeleteBitSet::open(delete_data, segment.meta().max_doc())?
         } else {
             DeleteBitSet::empty()
         };
 
+        let doc_id_mapping = {
+            if let Ok(source) = segment.open_read(SegmentComponent::DOCMAP) {
+                Some(Arc::new(DocIdMapping::open(source)?))
+            } else {
+                None
+            }
+        };
+
         let schema = segment.schema();
+        check_fields(
+            &schema,
+            segment.meta().max_doc(),
+            &termdict_composite,
+            &fast_fields_composite,
+            &fieldnorms_composite,
+        )?;
         Ok(SegmentReader {
             inv_idx_reader_cache: Arc::new(RwLock::new(HashMap::new())),
+            sorted_fields_cache: Arc::new(RwLock::new(HashMap::new())),
             segment_meta: segment.meta().clone(),
             termdict_composite,
             postings_composite,
             fast_fields_composite,
             fieldnorms_composite,
             segment_id: segment.id(),
+            doc_id_mapping,
             store_reader,
             delete_bitset,
+            masked: false,
             positions_composite,
             schema,
         })
     }
 
+    /// Opens the readers of several segments, using up to `num_threads` threads.
+    ///
+    /// The readers are returned in the same order as `segments`.
+    /// Opening a reader only reads the footers of the segment files,
+    /// and checks the headers of the per-field structures:
+    /// the structures themselves (inverted index, fast fields, field norms)
+    /// are only loaded on first access.
+    pub fn open_all(segments: &[Segment], num_threads: usize) -> Result<Vec<SegmentReader>> {
+        let num_workers = cmp::min(cmp::max(num_threads, 1), segments.len());
+        if num_workers <= 1 {
+            return segments.iter().map(SegmentReader::open).collect();
+        }
+        let tasks: Mutex<Vec<(usize, Segment)>> =
+            Mutex::new(segments.iter().cloned().enumerate().collect());
+        let segment_readers: Mutex<Vec<(usize, SegmentReader)>> =
+            Mutex::new(Vec::with_capacity(segments.len()));
+        let worker_results: Vec<Result<()>> = crossbeam::scope(|scope| {
+            let handles: Vec<_> = (0..num_workers)
+                .map(|_| {
+                    let tasks = &tasks;
+                    let segment_readers = &segment_readers;
+                    scope.spawn(move || -> Result<()> {
+                        loop {
+                            let task = tasks.lock().expect("Task queue lock poisoned").pop();
+                            let (segment_ord, segment) = match task {
+                                Some(task) => task,
+                                None => return Ok(()),
+                            };
+                            let segment_reader = SegmentReader::open(&segment)?;
+                            segment_readers
+                                .lock()
+                                .expect("Segment reader list lock poisoned")
+                                .push((segment_ord, segment_reader));
+                        }
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join()).collect()
+        });
+        for worker_result in worker_results {
+            worker_result?;
+        }
+        let mut segment_readers = segment_readers
+            .into_inner()
+            .expect("Segment reader list lock poisoned");
+        segment_readers.sort_by_key(|&(segment_ord, _)| segment_ord);
+        Ok(segment_readers
+            .into_iter()
+            .map(|(_, segment_reader)| segment_reader)
+            .collect())
+    }
+
     /// Returns a field reader associated to the field given in argument.
     ///
     /// The field reader is in charge of iterating through the
     /// term dictionary associated to a specific field,
     /// and opening the posting list associated to any term.
-    pub fn inverted_index(&self, field: Field) -> Arc<InvertedIndexReader> {
+    ///
+    /// If the field is indexed but has no term in the segment, e.g. because
+    /// the segment was written before the field was added to the schema,
+    /// the inverted index is empty.
+    ///
+    /// Returns an error if the field is not indexed, or if its
+    /// term dictionary is corrupted.
+    pub fn inverted_index(&self, field: Field) -> Result<Arc<InvertedIndexReader>> {
         if let Some(inv_idx_reader) = self.inv_idx_reader_cache
             .read()
             .expect("Lock poisoned. This should never happen")
             .get(&field)
         {
-            return Arc::clone(inv_idx_reader);
+            return Ok(Arc::clone(inv_idx_reader));
         }
 
-        let record_option = self.schema
-            .get_field_entry(field)
-            .field_type()
-            .get_index_record_option()
-            .expect("Field does not seem indexed.");
+        let field_entry = self.schema.get_field_entry(field);
+        let field_type = field_entry.field_type();
+        let record_option = match field_type.get_index_record_option() {
+            Some(record_option) => record_option,
+            None => bail!(ErrorKind::InvalidArgument(format!(
+                "Field {:?} is not indexed.",
+                field_entry.name()
+            ))),
+        };
 
-        let termdict_source: ReadOnlySource = self.termdict_composite
-            .open_read(field)
-            .expect("Failed to open field term dictionary in composite file. Is the field indexed");
+        let termdict_source: ReadOnlySource = match self.termdict_composite.open_read(field) {
+            Some(termdict_source) => termdict_source,
+            None => {
+                // the field has no term in the segment.
+                let inv_idx_reader = InvertedIndexReader::empty(field_type.clone(), record_option);
+                return Ok(Arc::new(inv_idx_reader));
+            }
+        };
 
         let postings_source = self.postings_composite
             .open_read(field)
-            .expect("Index corrupted. Failed to open field postings in composite file.");
+            .ok_or_else(|| missing_composite_field("postings", field_entry.name()))?;
+
+        // the segments written before the postings block size was configurable
+        // do not record it.
+        let postings_block_size = match self.postings_composite.open_read_with_idx(field, 1) {
+            Some(header) => {
+                let num_docs = u32::deserialize(&mut header.as_slice())?;
+                PostingsBlockSize::from_num_docs(num_docs as usize).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Index corrupted. Invalid postings block size {}.", num_docs),
+                    )
+                })?
+            }
+            None => PostingsBlockSize::default(),
+        };
 
         let positions_source = self.positions_composite
             .open_read(field)
-            .expect("Index corrupted. Failed to open field positions in composite file.");
+            .ok_or_else(|| missing_composite_field("positions", field_entry.name()))?;
 
         let inv_idx_reader = Arc::new(InvertedIndexReader::new(
             termdict_source,
@@ -255,7 +564,8 @@ impl SegmentReader {
             positions_source,
             self.delete_bitset.clone(),
             record_option,
-        ));
+            postings_block_size,
+        )?);
 
         // by releasing the lock in between, we may end up opening the inverting index
         // twice, but this is fine.
@@ -264,7 +574,7 @@ impl SegmentReader {
             .expect("Field reader cache lock poisoned. This should never happen.")
             .insert(field, Arc::clone(&inv_idx_reader));
 
-        inv_idx_reader
+        Ok(inv_idx_reader)
     }
 
     /// Returns the document (or to be accurate, its stored field)
@@ -275,11 +585,34 @@ impl SegmentReader {
         self.store_reader.get(doc_id)
     }
 
+    /// Returns the values of the given stored fields of a document.
+    ///
+    /// The values of the other stored fields are not decoded,
+    /// which makes it cheaper than `.doc(doc_id)` when the document
+    /// has large stored fields that are not needed.
+    pub fn doc_fields(&self, doc_id: DocId, fields: &[Field]) -> Result<Document> {
+        self.store_reader.get_fields(doc_id, fields)
+    }
+
     /// Returns the segment id
     pub fn segment_id(&self) -> SegmentId {
         self.segment_id
     }
 
+    /// Returns the meta of the segment, as of the opening of the reader.
+    pub(crate) fn segment_meta(&self) -> &SegmentMeta {
+        &self.segment_meta
+    }
+
+    /// Returns the mapping from the doc ids of the segments that
+    /// were merged into this segment to the doc ids of this segment.
+    ///
+    /// Returns `None` if the segment was not created by a merge,
+    /// or if the merge did not record its doc id mapping.
+    pub fn doc_id_mapping(&self) -> Option<&DocIdMapping> {
+        self.doc_id_mapping.as_ref().map(|doc_id_mapping| &**doc_id_mapping)
+    }
+
     /// Returns the bitset representing
     /// the documents that have been deleted.
     pub fn delete_bitset(&self) -> &DeleteBitSet {
@@ -291,6 +624,210 @@ impl SegmentReader {
     pub fn is_deleted(&self, doc: DocId) -> bool {
         self.delete_bitset.is_deleted(doc)
     }
+
+    /// Returns an iterator over the `DocId`s of the documents
+    /// of the segment that are not deleted.
+    pub fn doc_ids_alive(&self) -> DocIdsAlive {
+        self.delete_bitset.doc_ids_alive(self.max_doc())
+    }
+
+    /// Returns a reader over the same segment, in which the documents
+    /// of `mask` are considered as deleted, on top of the actual deletes.
+    ///
+    /// The mask is not written to the index. It makes it possible to hide
+    /// documents computed outside of tantivy (e.g. by a privacy filter)
+    /// from the searches, using a bitset imported via `DeleteBitSet::from_bytes`.
+    /// (See also `Searcher::with_masks`)
+    pub fn with_mask(&self, mask: &DeleteBitSet) -> SegmentReader {
+        let mut segment_reader = self.clone();
+        segment_reader.delete_bitset = self.delete_bitset.union(mask, self.max_doc());
+        // the inverted index readers filter out the deleted documents.
+        segment_reader.inv_idx_reader_cache = Arc::new(RwLock::new(HashMap::new()));
+        segment_reader.masked = true;
+        segment_reader
+    }
+
+    /// Returns true iff the reader was created by `.with_mask(...)`.
+    pub fn is_masked(&self) -> bool {
+        self.masked
+    }
+
+    /// Returns true iff the segment has an inverted index for the field.
+    ///
+    /// It may not be the case, even though the field is indexed according
+    /// to the schema, if the segment was written before the field was
+    /// added to the schema, or if no document of the segment has a term
+    /// for it. Queries may then skip the segment.
+    pub fn has_inverted_index(&self, field: Field) -> bool {
+        self.termdict_composite.open_read(field).is_some()
+    }
+
+    /// Returns true iff the field is a fast field according to the schema,
+    /// but has no fast field in the segment, e.g. because the segment was
+    /// written before the field was added to the schema.
+    ///
+    /// The range queries over the fast field match no document of the segment,
+    /// while the queries scoring the documents with it use its default value,
+    /// i.e. `0` or an empty vector.
+    pub fn is_missing_fast_field(&self, field: Field) -> bool {
+        self.schema.get_field_entry(field).is_fast()
+            && self.fast_fields_composite.open_read(field).is_none()
+    }
+
+    /// Lists the fields having data in the segment, with the data
+    /// structures recorded for each of them, sorted by field.
+    ///
+    /// Unlike the schema, this reflects what was actually written
+    /// in the segment.
+    pub fn fields(&self) -> Vec<SegmentFieldInfo> {
+        let mut fields: Vec<Field> = self.termdict_composite
+            .fields()
+            .into_iter()
+            .chain(self.fast_fields_composite.fields())
+            .chain(self.fieldnorms_composite.fields())
+            .collect();
+        fields.sort();
+        fields.dedup();
+        fields
+            .into_iter()
+            .map(|field| {
+                let num_terms = self.termdict_composite
+                    .open_read(field)
+                    .and_then(|termdict_source| TermDictionaryImpl::open(termdict_source).ok())
+                    .map(|termdict| termdict.num_terms())
+                    .unwrap_or(0);
+                let has_positions = self.positions_composite
+                    .open_read(field)
+                    .map(|positions_source| positions_source.len() > 0)
+                    .unwrap_or(false);
+                SegmentFieldInfo {
+                    field,
+                    num_terms,
+                    has_positions,
+                    has_fast_field: self.fast_fields_composite.open_read(field).is_some(),
+                    has_fieldnorms: self.fieldnorms_composite.open_read(field).is_some(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Data structures recorded for a field in a segment.
+/// (See `SegmentReader::fields`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SegmentFieldInfo {
+    /// The field.
+    pub field: Field,
+    /// Number of terms of the field in the segment, i.e. of posting lists.
+    ///
+    /// It is 0 if the field is not indexed in the segment.
+    pub num_terms: usize,
+    /// True iff the positions of the terms are recorded.
+    pub has_positions: bool,
+    /// True iff the field has a fast field in the segment.
+    pub has_fast_field: bool,
+    /// True iff the field has field norms in the segment.
+    pub has_fieldnorms: bool,
+}
+
+/// Checks the headers of the term dictionaries, fast fields and field norms
+/// of a segment, so that their readers can later be opened without panicking.
+fn check_fields(
+    schema: &Schema,
+    max_doc: DocId,
+    termdict_composite: &CompositeFile,
+    fast_fields_composite: &CompositeFile,
+    fieldnorms_composite: &CompositeFile,
+) -> Result<()> {
+    let max_doc = u64::from(max_doc);
+    for (field_id, field_entry) in schema.fields().iter().enumerate() {
+        let field = Field(field_id as u32);
+        if let Some(termdict_source) = termdict_composite.open_read(field) {
+            TermDictionaryImpl::open(termdict_source)?;
+        }
+        if let Some(fieldnorms_source) = fieldnorms_composite.open_read(field) {
+            FastFieldReader::<u64>::check(&fieldnorms_source, max_doc)?;
+        }
+        let cardinality = match *field_entry.field_type() {
+            FieldType::U64(ref int_options) | FieldType::I64(ref int_options) => {
+                int_options.get_fastfield_cardinality()
+            }
+            FieldType::HierarchicalFacet => Some(Cardinality::MultiValues),
+            FieldType::Bytes(ref bytes_options) if bytes_options.is_fast() => {
+                let idx_max_value = check_fast_field_idx(fast_fields_composite, field, max_doc)?;
+                if let Some(values) = fast_fields_composite.open_read_with_idx(field, 1) {
+                    if idx_max_value > values.len() as u64 {
+                        return Err(corrupted_fast_fields(field_entry.name()).into());
+                    }
+                }
+                None
+            }
+            _ => None,
+        };
+        match cardinality {
+            Some(Cardinality::SingleValue) => {
+                if let Some(data) = fast_fields_composite.open_read(field) {
+                    FastFieldReader::<u64>::check(&data, max_doc)?;
+                }
+            }
+            Some(Cardinality::MultiValues) => {
+                let num_vals = check_fast_field_idx(fast_fields_composite, field, max_doc)?;
+                if let Some(data) = fast_fields_composite.open_read_with_idx(field, 1) {
+                    FastFieldReader::<u64>::check(&data, num_vals)?;
+                }
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+/// Checks the index of a multivalued fast field, and returns the
+/// upper bound it gives to the number of values of the field.
+fn check_fast_field_idx(
+    fast_fields_composite: &CompositeFile,
+    field: Field,
+    max_doc: u64,
+) -> Result<u64> {
+    match fast_fields_composite.open_read_with_idx(field, 0) {
+        Some(idx_data) => {
+            FastFieldReader::<u64>::check(&idx_data, max_doc + 1)?;
+            Ok(FastFieldReader::<u64>::open(idx_data)?.max_value())
+        }
+        None => Ok(0),
+    }
+}
+
+/// Opens a fast field reader, reporting a corrupted fast field
+/// as not available.
+///
+/// The headers of the fast fields are checked when the segment reader
+/// is opened, so that this only happens if the files are modified afterwards.
+fn open_fast_field<Item: FastValue>(
+    data: ReadOnlySource,
+    field_entry: &FieldEntry,
+) -> fastfield::Result<FastFieldReader<Item>> {
+    FastFieldReader::open(data).map_err(|_| FastFieldNotAvailableError::new(field_entry))
+}
+
+fn corrupted_fast_fields(field_name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "Corrupted fast fields: the index of \"{}\" points past its values",
+            field_name
+        ),
+    )
+}
+
+fn missing_composite_field(file_name: &str, field_name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "Index corrupted: the {} of \"{}\" are missing from the composite file",
+            file_name, field_name
+        ),
+    )
 }
 
 impl fmt::Debug for SegmentReader {
@@ -298,3 +835,455 @@ impl fmt::Debug for SegmentReader {
         write!(f, "SegmentReader({:?})", self.segment_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::{SegmentFieldInfo, SegmentReader};
+    use common::i64_to_u64;
+    use core::{Segment, SegmentComponent, SegmentId, META_FILEPATH};
+    use directory::{Directory, MmapDirectory};
+    use docset::DocSet;
+    use futures::Future;
+    use postings::Postings;
+    use collector::TopCollector;
+    use query::{AllQuery, FunctionScoreQuery, Query, RangeQuery, RecencyBoostQuery, Similarity,
+                TermQuery, VectorSimilarityQuery};
+    use schema::{BytesOptions, Facet, Field, IndexRecordOption, SchemaBuilder, VectorOptions, FAST,
+                 INT_INDEXED, STORED, STRING, TEXT};
+    use std::fs::{self, File};
+    use std::io::Read;
+    use std::path::Path;
+    use std::time::Duration;
+    use serde_json::{self, Value};
+    use tempdir::TempDir;
+    use termdict::{TermDictionary, TermStreamer};
+    use Index;
+    use Result;
+    use Term;
+
+    #[test]
+    fn test_segment_fields() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let count_field = schema_builder.add_u64_field("count", FAST);
+        let stored_field = schema_builder.add_text_field("stored", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(
+                text_field => "hello world",
+                id_field => "id1",
+                count_field => 3u64,
+                stored_field => "stored"
+            ));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        assert_eq!(
+            segment_reader.fields(),
+            vec![
+                SegmentFieldInfo {
+                    field: text_field,
+                    num_terms: 2,
+                    has_positions: true,
+                    has_fast_field: false,
+                    has_fieldnorms: true,
+                },
+                SegmentFieldInfo {
+                    field: id_field,
+                    num_terms: 1,
+                    has_positions: false,
+                    has_fast_field: false,
+                    has_fieldnorms: true,
+                },
+                SegmentFieldInfo {
+                    field: count_field,
+                    num_terms: 0,
+                    has_positions: false,
+                    has_fast_field: true,
+                    has_fieldnorms: false,
+                },
+            ]
+        );
+        assert!(segment_reader.has_inverted_index(text_field));
+        assert!(!segment_reader.has_inverted_index(count_field));
+        assert!(!segment_reader.has_inverted_index(stored_field));
+    }
+
+    #[test]
+    fn test_query_fields_missing_from_segment() {
+        let temp_dir = TempDir::new("missing_fields").unwrap();
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        {
+            let index = Index::create(temp_dir.path(), schema_builder.build()).unwrap();
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "hello"));
+            index_writer.commit().unwrap();
+        }
+        // fields are added to the schema after the segment was written.
+        let mut schema_builder = SchemaBuilder::default();
+        schema_builder.add_text_field("text", TEXT);
+        let tag_field = schema_builder.add_text_field("tag", STRING);
+        let views_field = schema_builder.add_u64_field("views", INT_INDEXED | FAST);
+        let date_field = schema_builder.add_i64_field("date", FAST);
+        let vector_field = schema_builder.add_vector_field("vector", VectorOptions::with_dims(2));
+        let schema = schema_builder.build();
+        let mut directory = MmapDirectory::open(temp_dir.path()).unwrap();
+        let meta_data = directory.atomic_read(&META_FILEPATH).unwrap();
+        let mut metas: Value = serde_json::from_slice(&meta_data).unwrap();
+        metas["schema"] = serde_json::to_value(&schema).unwrap();
+        let meta_data = serde_json::to_vec(&metas).unwrap();
+        directory.atomic_write(&META_FILEPATH, &meta_data).unwrap();
+
+        let index = Index::open(temp_dir.path()).unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        assert!(!segment_reader.has_inverted_index(tag_field));
+        assert!(segment_reader.is_missing_fast_field(views_field));
+        assert!(!segment_reader.is_missing_fast_field(text_field));
+        assert_eq!(segment_reader.inverted_index(tag_field).unwrap().terms().num_terms(), 0);
+
+        let term = Term::from_field_text(tag_field, "tag");
+        let term_query = TermQuery::new(term, IndexRecordOption::Basic);
+        assert_eq!(term_query.count(&*searcher).unwrap(), 0);
+        let range_query = RangeQuery::new_u64(views_field, 0..10);
+        assert_eq!(range_query.count(&*searcher).unwrap(), 0);
+        assert_eq!(AllQuery.count(&*searcher).unwrap(), 1);
+
+        // the scoring queries keep matching, with the default value of the fast field.
+        let top_scores = |query: &Query| {
+            let mut top_collector = TopCollector::with_limit(10);
+            query.search(&*searcher, &mut top_collector).unwrap();
+            top_collector
+                .score_docs()
+                .into_iter()
+                .map(|(score, _)| score)
+                .collect::<Vec<_>>()
+        };
+        let function_query =
+            FunctionScoreQuery::new(box AllQuery, views_field, |score, views: u64| {
+                score + 2.0 + views as f32
+            });
+        assert_eq!(function_query.count(&*searcher).unwrap(), 1);
+        assert_eq!(top_scores(&function_query), vec![3.0]);
+        let recency_query =
+            RecencyBoostQuery::new(box AllQuery, date_field, Duration::from_secs(3_600));
+        assert_eq!(recency_query.count(&*searcher).unwrap(), 1);
+        assert_eq!(top_scores(&recency_query).len(), 1);
+        let vector_query = VectorSimilarityQuery::new(
+            vector_field,
+            vec![1f32, 0f32],
+            Similarity::DotProduct,
+            box AllQuery,
+        );
+        assert_eq!(vector_query.count(&*searcher).unwrap(), 1);
+        assert_eq!(top_scores(&vector_query), vec![0.0]);
+    }
+
+    #[test]
+    fn test_open_all() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        for i in 0..5 {
+            for _ in 0..i + 1 {
+                index_writer.add_document(doc!(text_field => "a"));
+            }
+            index_writer.commit().unwrap();
+        }
+        let segments = index.searchable_segments().unwrap();
+        assert_eq!(segments.len(), 5);
+        let segment_ids: Vec<SegmentId> = segments.iter().map(|segment| segment.id()).collect();
+        for num_threads in 0..7 {
+            let segment_readers = SegmentReader::open_all(&segments, num_threads).unwrap();
+            assert_eq!(
+                segment_readers
+                    .iter()
+                    .map(|segment_reader| segment_reader.segment_id())
+                    .collect::<Vec<SegmentId>>(),
+                segment_ids
+            );
+            for (segment, segment_reader) in segments.iter().zip(segment_readers.iter()) {
+                assert_eq!(segment_reader.max_doc(), segment.meta().max_doc());
+            }
+        }
+        assert!(SegmentReader::open_all(&[], 4).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fast_field_bounds() {
+        let mut schema_builder = SchemaBuilder::default();
+        let num_field = schema_builder.add_i64_field("num", INT_INDEXED | FAST);
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        for &(min_value, max_value) in &[(-5i64, 3i64), (10i64, 20i64)] {
+            for num in min_value..max_value + 1 {
+                index_writer.add_document(doc!(num_field => num, text_field => "a"));
+            }
+            index_writer.commit().unwrap();
+        }
+        for segment_meta in index.searchable_segment_metas().unwrap() {
+            assert!(segment_meta.fast_field_bounds(num_field).is_some());
+            assert!(segment_meta.fast_field_bounds(text_field).is_none());
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut bounds: Vec<(i64, i64)> = searcher
+            .segment_readers()
+            .iter()
+            .map(|segment_reader| segment_reader.fast_field_bounds(num_field).unwrap())
+            .collect();
+        bounds.sort();
+        assert_eq!(bounds, vec![(-5, 3), (10, 20)]);
+        let segment_reader = searcher.segment_reader(0);
+        assert!(segment_reader.fast_field_bounds::<u64>(num_field).is_none());
+        assert!(segment_reader.fast_field_bounds::<i64>(text_field).is_none());
+        let range_query = RangeQuery::new_i64(num_field, 2i64..12i64);
+        assert_eq!(range_query.count(&*searcher).unwrap(), 4);
+
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+        let segment_metas = index.searchable_segment_metas().unwrap();
+        assert_eq!(segment_metas.len(), 1);
+        assert_eq!(
+            segment_metas[0].fast_field_bounds(num_field),
+            Some((i64_to_u64(-5), i64_to_u64(20)))
+        );
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(
+            searcher.segment_reader(0).fast_field_bounds(num_field),
+            Some((-5i64, 20i64))
+        );
+        assert_eq!(range_query.count(&*searcher).unwrap(), 4);
+    }
+
+    /// Returns the corrupted versions of `data` fed to `SegmentReader::open`.
+    fn corruptions(data: &[u8]) -> Vec<Vec<u8>> {
+        let len = data.len();
+        let mut corrupted_data: Vec<Vec<u8>> = [0, 1, 3, len / 2, len - 1]
+            .iter()
+            .map(|&truncated_len| data[..truncated_len].to_vec())
+            .collect();
+        let mut invalid_footer = data.to_vec();
+        for byte in &mut invalid_footer[len - 4..] {
+            *byte = 0xFF;
+        }
+        corrupted_data.push(invalid_footer);
+        let flipped_offsets = (len - 16..len).chain(vec![0, len / 2]);
+        for offset in flipped_offsets {
+            let mut flipped = data.to_vec();
+            flipped[offset] ^= 0xFF;
+            corrupted_data.push(flipped);
+        }
+        corrupted_data
+    }
+
+    /// Reads the postings and the positions of all of the terms of the segment.
+    fn read_all_postings(segment_reader: &SegmentReader) -> Result<()> {
+        let schema = segment_reader.schema().clone();
+        for (field_id, field_entry) in schema.fields().iter().enumerate() {
+            let record_option = match field_entry.field_type().get_index_record_option() {
+                Some(record_option) => record_option,
+                None => continue,
+            };
+            let inverted_index = segment_reader.inverted_index(Field(field_id as u32))?;
+            let mut term_stream = inverted_index.terms().stream();
+            while term_stream.advance() {
+                let mut postings = inverted_index
+                    .read_postings_from_terminfo(term_stream.value(), record_option)?;
+                while postings.advance() {
+                    postings.positions();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_corrupted_segment() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let int_field = schema_builder.add_u64_field("int", INT_INDEXED | FAST);
+        let bytes_field =
+            schema_builder.add_bytes_field("bytes", BytesOptions::default().set_fast());
+        let facet_field = schema_builder.add_facet_field("facet");
+        let string_field = schema_builder.add_text_field("string", STRING);
+        let mut index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0..300u64 {
+                index_writer.add_document(doc!(
+                    text_field => format!("a b{} c{}", i, i % 7),
+                    int_field => i,
+                    bytes_field => vec![i as u8; (i % 5) as usize],
+                    facet_field => Facet::from(&format!("/f/{}", i % 3)),
+                    string_field => format!("s{}", i)
+                ));
+            }
+            index_writer.commit().unwrap();
+            index_writer.delete_term(Term::from_field_u64(int_field, 3u64));
+            index_writer.commit().unwrap();
+        }
+        let segment = index.searchable_segments().unwrap().pop().unwrap();
+        assert!(segment.meta().has_deletes());
+        let components = [
+            SegmentComponent::POSTINGS,
+            SegmentComponent::POSITIONS,
+            SegmentComponent::FASTFIELDS,
+            SegmentComponent::FIELDNORMS,
+            SegmentComponent::TERMS,
+            SegmentComponent::STORE,
+            SegmentComponent::DELETE,
+        ];
+        for &component in &components {
+            let path = segment.relative_path(component);
+            let data = index.directory().atomic_read(&path).unwrap();
+            assert!(data.len() >= 16);
+            let reads_postings = match component {
+                SegmentComponent::POSTINGS | SegmentComponent::POSITIONS => true,
+                _ => false,
+            };
+            for corrupted_data in corruptions(&data) {
+                index
+                    .directory_mut()
+                    .atomic_write(&path, &corrupted_data)
+                    .unwrap();
+                // Opening the segment and reading its postings
+                // may succeed or fail, but must not panic.
+                if let Ok(segment_reader) = SegmentReader::open(&segment) {
+                    if reads_postings {
+                        let _ = read_all_postings(&segment_reader);
+                    }
+                }
+            }
+            index.directory_mut().atomic_write(&path, &data).unwrap();
+        }
+        let segment_reader = SegmentReader::open(&segment).unwrap();
+        assert_eq!(segment_reader.num_docs(), 299);
+        read_all_postings(&segment_reader).unwrap();
+    }
+
+    /// Returns the inputs of the `cargo fuzz` corpus of `fuzz_target`.
+    fn fuzz_corpus(fuzz_target: &str) -> Vec<Vec<u8>> {
+        let corpus_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fuzz")
+            .join("corpus")
+            .join(fuzz_target);
+        let mut corpus = vec![];
+        for entry in fs::read_dir(&corpus_path).unwrap() {
+            let mut data = vec![];
+            File::open(entry.unwrap().path())
+                .unwrap()
+                .read_to_end(&mut data)
+                .unwrap();
+            corpus.push(data);
+        }
+        assert!(!corpus.is_empty());
+        corpus
+    }
+
+    /// Overwrites a segment file with each of the inputs of the fuzz corpus,
+    /// the way the fuzz target does: the first byte of the input
+    /// selects the file among `components`.
+    fn replay_fuzz_corpus<F>(
+        fuzz_target: &str,
+        index: &mut Index,
+        components: &[SegmentComponent],
+        check: F,
+    ) where
+        F: Fn(&Segment),
+    {
+        let segment = index.searchable_segments().unwrap().pop().unwrap();
+        for data in fuzz_corpus(fuzz_target) {
+            if data.is_empty() {
+                continue;
+            }
+            let component = components[data[0] as usize % components.len()];
+            let path = segment.relative_path(component);
+            let original_data = index.directory().atomic_read(&path).unwrap();
+            index
+                .directory_mut()
+                .atomic_write(&path, &data[1..])
+                .unwrap();
+            check(&segment);
+            index
+                .directory_mut()
+                .atomic_write(&path, &original_data)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_segment_reader_open_fuzz_corpus() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let int_field = schema_builder.add_u64_field("int", INT_INDEXED | FAST);
+        let facet_field = schema_builder.add_facet_field("facet");
+        let mut index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a b c", int_field => 1u64));
+            index_writer.add_document(doc!(text_field => "b c d", int_field => 2u64));
+            index_writer.add_document(doc!(
+                text_field => "c d e",
+                facet_field => Facet::from("/a/b")
+            ));
+            index_writer.commit().unwrap();
+            index_writer.delete_term(Term::from_field_u64(int_field, 2u64));
+            index_writer.commit().unwrap();
+        }
+        let components = [
+            SegmentComponent::POSTINGS,
+            SegmentComponent::POSITIONS,
+            SegmentComponent::FASTFIELDS,
+            SegmentComponent::FIELDNORMS,
+            SegmentComponent::TERMS,
+            SegmentComponent::STORE,
+            SegmentComponent::DELETE,
+        ];
+        // Opening the segment may succeed or fail, but must not panic.
+        replay_fuzz_corpus("segment_reader_open", &mut index, &components, |segment| {
+            let _ = SegmentReader::open(segment);
+        });
+    }
+
+    #[test]
+    fn test_postings_open_fuzz_corpus() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let int_field = schema_builder.add_u64_field("int", INT_INDEXED);
+        let mut index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0..200u64 {
+                index_writer.add_document(doc!(
+                    text_field => format!("a b{} c{} a", i % 3, i % 150),
+                    int_field => i % 7
+                ));
+            }
+            index_writer.commit().unwrap();
+        }
+        let components = [
+            SegmentComponent::POSTINGS,
+            SegmentComponent::POSITIONS,
+            SegmentComponent::TERMS,
+        ];
+        // Opening the segment and reading its postings
+        // may succeed or fail, but must not panic.
+        replay_fuzz_corpus("postings_open", &mut index, &components, |segment| {
+            if let Ok(segment_reader) = SegmentReader::open(segment) {
+                let _ = read_all_postings(&segment_reader);
+            }
+        });
+    }
+}
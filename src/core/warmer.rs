@@ -0,0 +1,31 @@
+use Result;
+use core::SegmentId;
+use core::SegmentReader;
+
+/// A `Warmer` is notified of the segments entering and leaving
+/// the searchers of an `IndexReader`. (See `IndexReader::add_warmer`)
+///
+/// It makes it possible to keep per-segment caches, keyed by `SegmentId`,
+/// consistent with the segments being searched: e.g. to load a fast field
+/// in memory before it is used, or to build a filter ahead of the first
+/// search.
+///
+/// A new `SegmentReader` is opened for every segment of every new
+/// generation of searchers, including the segments that already belonged
+/// to the previous generation. Warmers keyed by segment id may therefore
+/// be called several times for the same segment.
+pub trait Warmer: Send + Sync {
+    /// Called with each new `SegmentReader`, before the searchers
+    /// relying on it are published.
+    ///
+    /// If warming fails, the new searchers are not published, and the
+    /// error is returned by the reload of the `IndexReader`.
+    fn warm(&self, segment_reader: &SegmentReader) -> Result<()>;
+
+    /// Called once a segment is not part of the published searchers anymore,
+    /// typically after it was merged into another segment.
+    ///
+    /// Searchers that were leased before the reload may still be
+    /// searching the evicted segment.
+    fn evict(&self, _segment_id: SegmentId) {}
+}
@@ -224,6 +224,25 @@ impl ManagedDirectory {
         }
         Ok(())
     }
+
+    /// Registers files that already exist in the directory as managed,
+    /// so that they get garbage collected once they are not used anymore.
+    ///
+    /// This is typically required after copying the files
+    /// of an index into a new directory.
+    pub fn register_files_as_managed(&mut self, filepaths: &[PathBuf]) -> io::Result<()> {
+        let mut meta_wlock = self.meta_informations
+            .write()
+            .expect("Managed file lock poisoned");
+        let mut has_changed = false;
+        for filepath in filepaths {
+            has_changed |= meta_wlock.managed_paths.insert(filepath.clone());
+        }
+        if has_changed {
+            save_managed_paths(self.directory.as_mut(), &meta_wlock)?;
+        }
+        Ok(())
+    }
 }
 
 impl Directory for ManagedDirectory {
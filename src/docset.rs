@@ -85,8 +85,22 @@ pub trait DocSet {
 
     /// Returns a best-effort hint of the
     /// length of the docset.
+    ///
+    /// The hint does not take in account deleted documents.
     fn size_hint(&self) -> u32;
 
+    /// Returns a best-effort estimate of the number of
+    /// non-deleted documents in the docset.
+    ///
+    /// Contrary to `.size_hint()`, the estimate factors in the ratio
+    /// of deleted documents of the segment, and should be preferred for
+    /// cost-based decisions (e.g. ordering the docsets of an intersection).
+    ///
+    /// By default, returns `.size_hint()`.
+    fn estimate_live(&self) -> u32 {
+        self.size_hint()
+    }
+
     /// Appends all docs to a `bitset`.
     fn append_to_bitset(&mut self, bitset: &mut BitSet) {
         while self.advance() {
@@ -128,6 +142,11 @@ impl<TDocSet: DocSet + ?Sized> DocSet for Box<TDocSet> {
         unboxed.size_hint()
     }
 
+    fn estimate_live(&self) -> u32 {
+        let unboxed: &TDocSet = self.borrow();
+        unboxed.estimate_live()
+    }
+
     fn count(&mut self) -> u32 {
         let unboxed: &mut TDocSet = self.borrow_mut();
         unboxed.count()
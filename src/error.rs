@@ -58,6 +58,17 @@ error_chain!(
             description("fast field not available")
             display("fast field not available: '{:?}'", err)
         }
+        /// The search exceeded its memory budget. (See `MemoryBudget`)
+        BudgetExceeded(limit: usize) {
+            description("memory budget exceeded")
+            display("memory budget of {} bytes exceeded", limit)
+        }
+        /// The index uses features that are not supported by this version of tantivy.
+        /// (See `CompatibilityReport`)
+        IncompatibleIndex(report: String) {
+            description("the index uses unsupported features")
+            display("the index uses unsupported features: '{}'", report)
+        }
     }
 );
 
@@ -0,0 +1,82 @@
+mod writer;
+mod reader;
+
+pub use self::writer::BytesFastFieldWriter;
+pub use self::reader::BytesFastFieldReader;
+
+#[cfg(test)]
+mod tests {
+
+    use futures::Future;
+    use query::{Query, TermQuery};
+    use schema::{BytesOptions, IndexRecordOption, SchemaBuilder, Term, INT_INDEXED};
+    use Index;
+
+    #[test]
+    fn test_bytes_fast_field() {
+        let mut schema_builder = SchemaBuilder::default();
+        let field = schema_builder.add_bytes_field("bytes", BytesOptions::default().set_fast());
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 3_000_000).unwrap();
+        index_writer.add_document(doc!(field => vec![4u8, 2u8]));
+        index_writer.add_document(doc!());
+        index_writer.add_document(doc!(field => b"tantivy".to_vec()));
+        index_writer.add_document(doc!(field => Vec::<u8>::new()));
+        index_writer.commit().unwrap();
+
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let bytes_reader = searcher
+            .segment_reader(0)
+            .bytes_fast_field_reader(field)
+            .unwrap();
+        assert_eq!(bytes_reader.get_val(0), &[4u8, 2u8]);
+        assert!(bytes_reader.get_val(1).is_empty());
+        assert_eq!(bytes_reader.get_val(2), &b"tantivy"[..]);
+        assert!(bytes_reader.get_val(3).is_empty());
+    }
+
+    #[test]
+    fn test_bytes_field_indexed_stored_merge() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED);
+        let bytes_options = BytesOptions::default()
+            .set_indexed()
+            .set_fast()
+            .set_stored();
+        let field = schema_builder.add_bytes_field("bytes", bytes_options);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 3_000_000).unwrap();
+        for i in 0u64..3u64 {
+            index_writer.add_document(doc!(id_field => i, field => vec![i as u8; i as usize]));
+            index_writer.commit().unwrap();
+        }
+        index_writer.delete_term(Term::from_field_u64(id_field, 1u64));
+        index_writer.commit().unwrap();
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let bytes_reader = searcher
+            .segment_reader(0)
+            .bytes_fast_field_reader(field)
+            .unwrap();
+        assert!(bytes_reader.get_val(0).is_empty());
+        assert_eq!(bytes_reader.get_val(1), &[2u8, 2u8]);
+
+        let term_query = |bytes: &[u8]| {
+            TermQuery::new(
+                Term::from_field_bytes(field, bytes),
+                IndexRecordOption::Basic,
+            )
+        };
+        assert_eq!(term_query(&[2u8, 2u8]).count(&*searcher).unwrap(), 1);
+        assert_eq!(term_query(&[2u8]).count(&*searcher).unwrap(), 0);
+        assert_eq!(term_query(&[1u8]).count(&*searcher).unwrap(), 0);
+        let doc = searcher.doc(&::DocAddress(0, 1)).unwrap();
+        assert_eq!(doc.get_first(field).unwrap().bytes_value(), &[2u8, 2u8]);
+    }
+}
@@ -0,0 +1,32 @@
+use directory::ReadOnlySource;
+use fastfield::FastFieldReader;
+use DocId;
+
+/// Reader for a bytes fast field.
+///
+/// The reader is implemented as a `u64` fast field, `idx_reader`,
+/// associating to each document the offset of its value within
+/// the concatenated values of all of the documents.
+///
+/// Documents that did not have a value have an empty value.
+#[derive(Clone)]
+pub struct BytesFastFieldReader {
+    idx_reader: FastFieldReader<u64>,
+    values: ReadOnlySource,
+}
+
+impl BytesFastFieldReader {
+    pub(crate) fn open(
+        idx_reader: FastFieldReader<u64>,
+        values: ReadOnlySource,
+    ) -> BytesFastFieldReader {
+        BytesFastFieldReader { idx_reader, values }
+    }
+
+    /// Returns the bytes associated to the given `doc`.
+    pub fn get_val(&self, doc: DocId) -> &[u8] {
+        let start = self.idx_reader.get(doc) as usize;
+        let stop = self.idx_reader.get(doc + 1) as usize;
+        &self.values.as_slice()[start..stop]
+    }
+}
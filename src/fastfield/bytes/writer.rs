@@ -0,0 +1,71 @@
+use fastfield::FastFieldSerializer;
+use schema::{Document, Field, Value};
+use std::io::{self, Write};
+
+/// Writer for bytes fast fields.
+///
+/// The values are kept in memory, and serialized as two parts
+/// when the segment is flushed:
+/// - a `u64` fast field associating to each document the offset
+/// of its value, (with an extra offset at the end)
+/// - the concatenated values themselves.
+///
+/// Documents without a value for the field get an empty value.
+pub struct BytesFastFieldWriter {
+    field: Field,
+    vals: Vec<u8>,
+    doc_index: Vec<u64>,
+}
+
+impl BytesFastFieldWriter {
+    /// Creates a new `BytesFastFieldWriter`.
+    pub fn new(field: Field) -> BytesFastFieldWriter {
+        BytesFastFieldWriter {
+            field,
+            vals: Vec::new(),
+            doc_index: Vec::new(),
+        }
+    }
+
+    /// Returns the field that this writer is targetting.
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Records the value of a new document.
+    ///
+    /// Only the first value of the document is taken in account.
+    pub fn add_document(&mut self, doc: &Document) {
+        self.doc_index.push(self.vals.len() as u64);
+        match doc.get_first(self.field) {
+            Some(&Value::Bytes(ref bytes)) => {
+                self.vals.extend_from_slice(bytes);
+            }
+            Some(value) => {
+                error!(
+                    "Ignoring value {:?} for the bytes field {:?}.",
+                    value, self.field
+                );
+            }
+            None => {}
+        }
+    }
+
+    /// Push the values to the `FastFieldSerializer`.
+    pub fn serialize(&self, serializer: &mut FastFieldSerializer) -> io::Result<()> {
+        {
+            // writing the offset index
+            let mut doc_index_serializer =
+                serializer.new_u64_fast_field_with_idx(self.field, 0, self.vals.len() as u64, 0)?;
+            for &offset in &self.doc_index {
+                doc_index_serializer.add_val(offset)?;
+            }
+            doc_index_serializer.add_val(self.vals.len() as u64)?;
+            doc_index_serializer.close_field()?;
+        }
+        // writing the values themselves.
+        serializer
+            .new_bytes_fast_field_with_idx(self.field, 1)
+            .write_all(&self.vals[..])
+    }
+}
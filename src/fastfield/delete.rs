@@ -36,11 +36,27 @@ pub fn write_delete_bitset(delete_bitset: &BitSet, writer: &mut WritePtr) -> io:
 pub struct DeleteBitSet {
     data: ReadOnlySource,
     len: usize,
+    max_doc: DocId,
 }
 
 impl DeleteBitSet {
-    /// Opens a delete bitset given its data source.
-    pub fn open(data: ReadOnlySource) -> DeleteBitSet {
+    /// Opens a delete bitset given its data source,
+    /// and the `max_doc` of its segment.
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if the data is too short
+    /// to hold a bit for each of the `max_doc` documents.
+    pub fn open(data: ReadOnlySource, max_doc: DocId) -> io::Result<DeleteBitSet> {
+        if data.len() < (max_doc as usize + 7) / 8 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Truncated delete bitset",
+            ));
+        }
+        Ok(DeleteBitSet::new(data, max_doc))
+    }
+
+    fn new(data: ReadOnlySource, max_doc: DocId) -> DeleteBitSet {
         let num_deleted: usize = data.as_slice()
             .iter()
             .map(|b| b.count_ones() as usize)
@@ -48,6 +64,7 @@ impl DeleteBitSet {
         DeleteBitSet {
             data,
             len: num_deleted,
+            max_doc,
         }
     }
 
@@ -56,7 +73,47 @@ impl DeleteBitSet {
         DeleteBitSet {
             data: ReadOnlySource::empty(),
             len: 0,
+            max_doc: 0,
+        }
+    }
+
+    /// Creates a delete bitset from its serialized representation,
+    /// as returned by `.to_bytes(max_doc)`.
+    ///
+    /// `bytes` is truncated, or padded with zeros, to the
+    /// `(max_doc + 7) / 8` bytes required by the segment.
+    pub fn from_bytes(mut bytes: Vec<u8>, max_doc: DocId) -> DeleteBitSet {
+        bytes.resize((max_doc as usize + 7) / 8, 0u8);
+        if max_doc % 8 > 0 {
+            // the bits after `max_doc` are not documents.
+            if let Some(last_byte) = bytes.last_mut() {
+                *last_byte &= (1u8 << (max_doc % 8)) - 1;
+            }
+        }
+        DeleteBitSet::new(ReadOnlySource::from(bytes), max_doc)
+    }
+
+    /// Serializes the bitset of a segment with `max_doc` documents
+    /// as a compact bitmap, in the format of the delete files:
+    /// bit `doc % 8` of byte `doc / 8` is set iff `doc` is deleted.
+    pub fn to_bytes(&self, max_doc: DocId) -> Vec<u8> {
+        let num_bytes = (max_doc as usize + 7) / 8;
+        if self.len == 0 {
+            return vec![0u8; num_bytes];
         }
+        let mut bytes = self.data.as_slice().to_vec();
+        bytes.resize(num_bytes, 0u8);
+        bytes
+    }
+
+    /// Returns a delete bitset in which the documents deleted in
+    /// either `self` or `other` are deleted.
+    pub fn union(&self, other: &DeleteBitSet, max_doc: DocId) -> DeleteBitSet {
+        let mut bytes = self.to_bytes(max_doc);
+        for (byte, other_byte) in bytes.iter_mut().zip(other.to_bytes(max_doc)) {
+            *byte |= other_byte;
+        }
+        DeleteBitSet::from_bytes(bytes, max_doc)
     }
 
     /// Returns true iff the segment has some deleted documents.
@@ -65,15 +122,44 @@ impl DeleteBitSet {
     }
 
     /// Returns true iff the document is deleted.
+    ///
+    /// Documents beyond the end of the bitset, which only
+    /// corrupted postings may yield, are not deleted.
     #[inline]
     pub fn is_deleted(&self, doc: DocId) -> bool {
         if self.len == 0 {
             false
         } else {
             let byte_offset = doc / 8u32;
-            let b: u8 = (*self.data)[byte_offset as usize];
             let shift = (doc & 7u32) as u8;
-            b & (1u8 << shift) != 0
+            self.data
+                .as_slice()
+                .get(byte_offset as usize)
+                .map(|&b| b & (1u8 << shift) != 0)
+                .unwrap_or(false)
+        }
+    }
+
+    /// Estimates how many documents out of `num_docs` documents
+    /// of the segment are not deleted, assuming that
+    /// deletes are evenly spread over the segment.
+    pub fn estimate_alive(&self, num_docs: u32) -> u32 {
+        if self.len == 0 {
+            return num_docs;
+        }
+        let alive_ratio = 1f64 - (self.len as f64) / f64::from(self.max_doc);
+        (f64::from(num_docs) * alive_ratio).round() as u32
+    }
+
+    /// Returns an iterator over the `DocId`s in `[0, max_doc)`
+    /// that are not deleted.
+    pub fn doc_ids_alive(&self, max_doc: DocId) -> DocIdsAlive {
+        DocIdsAlive {
+            delete_bitset: self.clone(),
+            max_doc,
+            next_byte_ord: 0,
+            base: 0,
+            alive_mask: 0u8,
         }
     }
 }
@@ -84,6 +170,57 @@ impl HasLen for DeleteBitSet {
     }
 }
 
+/// Iterator over the non-deleted `DocId`s of a segment,
+/// in increasing order.
+///
+/// The delete bitset is scanned one byte at a time,
+/// so that runs of deleted documents are skipped
+/// without testing each of them.
+#[derive(Clone)]
+pub struct DocIdsAlive {
+    delete_bitset: DeleteBitSet,
+    max_doc: DocId,
+    // ordinal of the next byte of the bitset to be loaded.
+    next_byte_ord: usize,
+    // doc id associated to the lowest bit of `alive_mask`.
+    base: DocId,
+    // bits of the alive documents of the current byte
+    // that have not been emitted yet.
+    alive_mask: u8,
+}
+
+impl Iterator for DocIdsAlive {
+    type Item = DocId;
+
+    fn next(&mut self) -> Option<DocId> {
+        loop {
+            if self.alive_mask != 0 {
+                let shift = self.alive_mask.trailing_zeros();
+                // clears the lowest bit.
+                self.alive_mask &= self.alive_mask - 1;
+                return Some(self.base + shift);
+            }
+            let base = (self.next_byte_ord as DocId) * 8u32;
+            if base >= self.max_doc {
+                return None;
+            }
+            let deleted_byte: u8 = if self.delete_bitset.has_deletes() {
+                (*self.delete_bitset.data)[self.next_byte_ord]
+            } else {
+                0u8
+            };
+            let mut alive_mask = !deleted_byte;
+            let num_remaining_docs = self.max_doc - base;
+            if num_remaining_docs < 8 {
+                alive_mask &= (1u8 << num_remaining_docs) - 1;
+            }
+            self.next_byte_ord += 1;
+            self.base = base;
+            self.alive_mask = alive_mask;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
@@ -100,15 +237,48 @@ mod tests {
         }
         {
             let source = directory.open_read(&test_path).unwrap();
-            let delete_bitset = DeleteBitSet::open(source);
             let n = bitset.capacity();
+            let delete_bitset = DeleteBitSet::open(source, n as DocId).unwrap();
             for doc in 0..n {
                 assert_eq!(bitset.contains(doc), delete_bitset.is_deleted(doc as DocId));
             }
             assert_eq!(delete_bitset.len(), bitset.len());
+            let expected_alive: Vec<DocId> = (0..n as DocId)
+                .filter(|doc| !bitset.contains(*doc as usize))
+                .collect();
+            let alive: Vec<DocId> = delete_bitset.doc_ids_alive(n as DocId).collect();
+            assert_eq!(alive, expected_alive);
+
+            let bytes = delete_bitset.to_bytes(n as DocId);
+            assert_eq!(bytes.len(), (n + 7) / 8);
+            let imported_delete_bitset = DeleteBitSet::from_bytes(bytes, n as DocId);
+            for doc in 0..n {
+                assert_eq!(bitset.contains(doc), imported_delete_bitset.is_deleted(doc as DocId));
+            }
+            assert_eq!(imported_delete_bitset.len(), bitset.len());
         }
     }
 
+    #[test]
+    fn test_delete_bitset_union() {
+        let mut left = vec![0u8; 2];
+        left[0] = 0b0000_0010;
+        let mut right = vec![0u8; 2];
+        right[1] = 0b0000_0001;
+        // the bits after max_doc are ignored.
+        right[1] |= 0b1000_0000;
+        let max_doc = 10;
+        let union = DeleteBitSet::from_bytes(left, max_doc)
+            .union(&DeleteBitSet::from_bytes(right, max_doc), max_doc);
+        assert_eq!(union.len(), 2);
+        assert!(union.is_deleted(1));
+        assert!(union.is_deleted(8));
+        assert_eq!(DeleteBitSet::empty().to_bytes(max_doc), vec![0u8; 2]);
+        let union_with_empty = DeleteBitSet::empty().union(&union, max_doc);
+        let alive: Vec<DocId> = union_with_empty.doc_ids_alive(max_doc).collect();
+        assert_eq!(alive, vec![0, 2, 3, 4, 5, 6, 7, 9]);
+    }
+
     #[test]
     fn test_delete_bitset() {
         {
@@ -127,4 +297,11 @@ mod tests {
             test_delete_bitset_helper(&bitset);
         }
     }
+
+    #[test]
+    fn test_delete_bitset_truncated() {
+        assert!(DeleteBitSet::open(ReadOnlySource::from(vec![0u8]), 8).is_ok());
+        assert!(DeleteBitSet::open(ReadOnlySource::from(vec![0u8]), 9).is_err());
+        assert!(DeleteBitSet::open(ReadOnlySource::empty(), 1).is_err());
+    }
 }
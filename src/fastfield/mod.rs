@@ -27,7 +27,7 @@ use common;
 use schema::Cardinality;
 use schema::FieldType;
 use schema::Value;
-pub use self::delete::DeleteBitSet;
+pub use self::delete::{DeleteBitSet, DocIdsAlive};
 pub use self::delete::write_delete_bitset;
 pub use self::error::{FastFieldNotAvailableError, Result};
 pub use self::facet_reader::FacetReader;
@@ -35,6 +35,8 @@ pub use self::multivalued::MultiValueIntFastFieldReader;
 pub use self::reader::FastFieldReader;
 pub use self::serializer::FastFieldSerializer;
 pub use self::writer::{FastFieldsWriter, IntFastFieldWriter};
+pub use self::vector::{VectorFastFieldReader, VectorFastFieldWriter};
+pub use self::bytes::{BytesFastFieldReader, BytesFastFieldWriter};
 
 mod reader;
 mod writer;
@@ -43,15 +45,27 @@ mod error;
 mod delete;
 mod facet_reader;
 mod multivalued;
+mod vector;
+mod bytes;
 
 /// Trait for types that are allowed for fast fields: (u64 or i64).
+///
+/// Internally all fast field values are encoded as u64. The mapping
+/// between a value and its `u64` representation is defined by this trait
+/// only, so that fast field readers (`FastFieldReader<Item>`), collectors and
+/// queries can be generic over the type of the values, without converting
+/// the `u64` representation themselves.
+///
+/// The mapping preserves the order: `a < b` iff `a.to_u64() < b.to_u64()`.
+/// This makes it possible to compare, sort or bound the values
+/// via their `u64` representation.
 pub trait FastValue: Default + Clone + Copy {
     /// Converts a value from u64
     ///
-    /// Internally all fast field values are encoded as u64.
+    /// This is the inverse of `.to_u64()`.
     fn from_u64(val: u64) -> Self;
 
-    /// Converts a value to u64.
+    /// Converts a value to u64, preserving the order.
     ///
     /// Internally all fast field values are encoded as u64.
     fn to_u64(&self) -> u64;
@@ -65,6 +79,11 @@ pub trait FastValue: Default + Clone + Copy {
     /// Cast value to `u64`.
     /// The value is just reinterpreted in memory.
     fn as_u64(&self) -> u64;
+
+    /// Converts the value to a `f64`.
+    ///
+    /// Large values may lose precision.
+    fn to_f64(&self) -> f64;
 }
 
 
@@ -81,6 +100,10 @@ impl FastValue for u64 {
         *self
     }
 
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+
     fn fast_field_cardinality(field_type: &FieldType) -> Option<Cardinality> {
         match *field_type {
             FieldType::U64(ref integer_options) =>
@@ -113,16 +136,25 @@ impl FastValue for i64 {
     fn as_u64(&self) -> u64 {
         *self as u64
     }
+
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
 }
 
 fn value_to_u64(value: &Value) -> u64 {
     match *value {
-        Value::U64(ref val) => *val,
-        Value::I64(ref val) => common::i64_to_u64(*val),
+        Value::U64(ref val) => val.to_u64(),
+        Value::I64(ref val) => val.to_u64(),
         _ => panic!("Expected a u64/i64 field, got {:?} ", value),
     }
 }
 
+/// Converts the `u64` representation of a value of type `Item` to a `f64`.
+pub(crate) fn u64_to_f64<Item: FastValue>(val: u64) -> f64 {
+    Item::from_u64(val).to_f64()
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -138,6 +170,7 @@ mod tests {
     use schema::FAST;
     use schema::Field;
     use std::collections::HashMap;
+    use std::fmt;
     use std::path::Path;
     use super::*;
     use test;
@@ -154,6 +187,48 @@ mod tests {
         };
     }
 
+    fn assert_preserves_order<Item: FastValue + PartialOrd + fmt::Debug>(vals: &[Item]) {
+        for &left in vals {
+            assert_eq!(Item::from_u64(left.to_u64()).to_u64(), left.to_u64());
+            for &right in vals {
+                assert_eq!(
+                    left.partial_cmp(&right),
+                    left.to_u64().partial_cmp(&right.to_u64()),
+                    "{:?} {:?}",
+                    left,
+                    right
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fast_value_mapping_preserves_order() {
+        assert_preserves_order(&[
+            0u64,
+            1u64,
+            2u64,
+            1_000u64,
+            u64::max_value() - 1,
+            u64::max_value(),
+        ]);
+        assert_preserves_order(&[
+            i64::min_value(),
+            i64::min_value() + 1,
+            -1_000i64,
+            -1i64,
+            0i64,
+            1i64,
+            1_000i64,
+            i64::max_value(),
+        ]);
+        for &val in &[i64::min_value(), -3i64, 0i64, 7i64, i64::max_value()] {
+            assert_eq!(i64::from_u64(val.to_u64()), val);
+            assert_eq!(u64_to_f64::<i64>(val.to_u64()), val as f64);
+        }
+        assert_eq!(u64_to_f64::<u64>(7u64), 7f64);
+    }
+
     #[test]
     pub fn test_fastfield() {
         let test_fastfield = FastFieldReader::<u64>::from(vec![100, 200, 300]);
@@ -185,7 +260,7 @@ mod tests {
         {
             let composite_file = CompositeFile::open(&source).unwrap();
             let field_source = composite_file.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(field_source);
+            let fast_field_reader = FastFieldReader::<u64>::open(field_source).unwrap();
             assert_eq!(fast_field_reader.get(0), 13u64);
             assert_eq!(fast_field_reader.get(1), 14u64);
             assert_eq!(fast_field_reader.get(2), 2u64);
@@ -221,7 +296,7 @@ mod tests {
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(data);
+            let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
             assert_eq!(fast_field_reader.get(0), 4u64);
             assert_eq!(fast_field_reader.get(1), 14_082_001u64);
             assert_eq!(fast_field_reader.get(2), 3_052u64);
@@ -258,7 +333,7 @@ mod tests {
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(data);
+            let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
             for doc in 0..10_000 {
                 assert_eq!(fast_field_reader.get(doc), 100_000u64);
             }
@@ -291,7 +366,7 @@ mod tests {
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(data);
+            let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
             assert_eq!(fast_field_reader.get(0), 0u64);
             for doc in 1..10_001 {
                 assert_eq!(
@@ -331,7 +406,7 @@ mod tests {
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(i64_field).unwrap();
-            let fast_field_reader = FastFieldReader::<i64>::open(data);
+            let fast_field_reader = FastFieldReader::<i64>::open(data).unwrap();
 
             assert_eq!(fast_field_reader.min_value(), -100i64);
             assert_eq!(fast_field_reader.max_value(), 9_999i64);
@@ -370,7 +445,7 @@ mod tests {
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(i64_field).unwrap();
-            let fast_field_reader = FastFieldReader::<i64>::open(data);
+            let fast_field_reader = FastFieldReader::<i64>::open(data).unwrap();
             assert_eq!(fast_field_reader.get(0u32), 0i64);
         }
     }
@@ -405,7 +480,7 @@ mod tests {
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(data);
+            let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
 
             let mut a = 0u64;
             for _ in 0..n {
@@ -462,7 +537,7 @@ mod tests {
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(data);
+            let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
 
             b.iter(|| {
                 let n = test::black_box(7000u32);
@@ -496,7 +571,7 @@ mod tests {
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(data);
+            let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
 
             b.iter(|| {
                 let n = test::black_box(1000u32);
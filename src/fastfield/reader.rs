@@ -11,6 +11,7 @@ use schema::FAST;
 use schema::SchemaBuilder;
 use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::io;
 use std::mem;
 use std::path::Path;
 use super::FastValue;
@@ -30,28 +31,62 @@ pub struct FastFieldReader<Item: FastValue> {
 impl<Item: FastValue> FastFieldReader<Item> {
 
     /// Opens a fast field given a source.
-    pub fn open(data: ReadOnlySource) -> Self {
+    ///
+    /// Returns an error if the header of the fast field is corrupted.
+    pub fn open(data: ReadOnlySource) -> io::Result<Self> {
         let min_value: u64;
         let amplitude: u64;
         {
             let mut cursor = data.as_slice();
-            min_value =
-                u64::deserialize(&mut cursor).expect("Failed to read the min_value of fast field.");
-            amplitude =
-                u64::deserialize(&mut cursor).expect("Failed to read the amplitude of fast field.");
+            min_value = u64::deserialize(&mut cursor)?;
+            amplitude = u64::deserialize(&mut cursor)?;
         }
-        let max_value = min_value + amplitude;
+        let max_value = min_value
+            .checked_add(amplitude)
+            .ok_or_else(|| corrupted_fast_field("the amplitude overflows"))?;
         let num_bits = compute_num_bits(amplitude);
         let owning_ref = OwningRef::new(data).map(|data| &data[16..]);
         let bit_unpacker = BitUnpacker::new(owning_ref, num_bits);
-        FastFieldReader {
+        Ok(FastFieldReader {
             min_value_u64: min_value,
             max_value_u64: max_value,
             bit_unpacker,
             _phantom: PhantomData
+        })
+    }
+
+    /// Creates a fast field reader returning `value` for all of the documents.
+    pub(crate) fn constant(value: Item) -> FastFieldReader<Item> {
+        let owning_ref = OwningRef::new(ReadOnlySource::empty()).map(|data| &data[..]);
+        FastFieldReader {
+            min_value_u64: value.to_u64(),
+            max_value_u64: value.to_u64(),
+            bit_unpacker: BitUnpacker::new(owning_ref, 0),
+            _phantom: PhantomData
         }
     }
 
+    /// Checks that the fast field `data` is not truncated,
+    /// given the number of values it is supposed to hold.
+    pub(crate) fn check(data: &ReadOnlySource, num_vals: u64) -> io::Result<()> {
+        let data = data.as_slice();
+        if data.len() < 16 {
+            return Err(corrupted_fast_field("the header is truncated"));
+        }
+        let amplitude = {
+            let mut cursor = &data[8..];
+            u64::deserialize(&mut cursor)?
+        };
+        let num_bits = u64::from(compute_num_bits(amplitude));
+        // the bitpacked values are followed by 7 bytes of padding.
+        let expected_len = num_vals
+            .checked_mul(num_bits)
+            .map(|num_bits_total| 16 + (num_bits_total + 7) / 8 + 7);
+        match expected_len {
+            Some(expected_len) if (data.len() as u64) >= expected_len => Ok(()),
+            _ => Err(corrupted_fast_field("the values are truncated")),
+        }
+    }
 
     /// Return the value associated to the given document.
     ///
@@ -100,6 +135,13 @@ impl<Item: FastValue> FastFieldReader<Item> {
     }
 }
 
+fn corrupted_fast_field(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Corrupted fast field: {}", msg),
+    )
+}
+
 impl<Item: FastValue> From<Vec<Item>> for FastFieldReader<Item> {
     fn from(vals: Vec<Item>) -> FastFieldReader<Item> {
         let mut schema_builder = SchemaBuilder::default();
@@ -134,7 +176,7 @@ impl<Item: FastValue> From<Vec<Item>> for FastFieldReader<Item> {
         let field_source = composite_file
             .open_read(field)
             .expect("File component not found");
-        FastFieldReader::open(field_source)
+        FastFieldReader::open(field_source).expect("Failed to open the fast field")
     }
 }
 
@@ -4,7 +4,7 @@ use schema::Field;
 use common::bitpacker::BitPacker;
 use common::compute_num_bits;
 use common::CountingWriter;
-use common::CompositeWrite;
+use common::{CompositeFile, CompositeWrite};
 use std::io::{self, Write};
 
 /// `FastFieldSerializer` is in charge of serializing
@@ -63,6 +63,30 @@ impl FastFieldSerializer {
         FastSingleFieldSerializer::open(field_write, min_value, max_value)
     }
 
+    /// Start serializing a new vector fast field.
+    ///
+    /// The components of the vectors are simply written
+    /// one after the other.
+    pub fn new_vector_fast_field(&mut self, field: Field) -> &mut CountingWriter<WritePtr> {
+        self.composite_write.for_field(field)
+    }
+
+    /// Start serializing the values of a bytes fast field.
+    ///
+    /// The values are simply written one after the other.
+    pub fn new_bytes_fast_field_with_idx(
+        &mut self,
+        field: Field,
+        idx: usize,
+    ) -> &mut CountingWriter<WritePtr> {
+        self.composite_write.for_field_with_idx(field, idx)
+    }
+
+    /// Copies the fast fields of an existing fast field file.
+    pub(crate) fn copy_fields(&mut self, composite_file: &CompositeFile) -> io::Result<()> {
+        composite_file.copy_to(&mut self.composite_write)
+    }
+
     /// Closes the serializer
     ///
     /// After this call the data must be persistently save on disk.
@@ -0,0 +1,71 @@
+mod writer;
+mod reader;
+
+pub use self::writer::VectorFastFieldWriter;
+pub use self::reader::VectorFastFieldReader;
+
+#[cfg(test)]
+mod tests {
+
+    use futures::Future;
+    use schema::{SchemaBuilder, Term, VectorOptions, INT_INDEXED};
+    use Index;
+
+    #[test]
+    fn test_vector_fast_field() {
+        let mut schema_builder = SchemaBuilder::default();
+        let field = schema_builder.add_vector_field("embedding", VectorOptions::with_dims(3));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 3_000_000).unwrap();
+        index_writer.add_document(doc!(field => vec![1f32, 2f32, 3f32]));
+        index_writer.add_document(doc!());
+        // wrong number of dimensions
+        index_writer.add_document(doc!(field => vec![1f32, 2f32]));
+        index_writer.add_document(doc!(field => vec![-0.5f32, 0f32, 4.25f32]));
+        index_writer.commit().unwrap();
+
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let vector_reader = searcher.segment_reader(0).vector_reader(field).unwrap();
+        assert_eq!(vector_reader.dims(), 3);
+        assert_eq!(vector_reader.num_docs(), 4);
+        let mut vals = Vec::new();
+        vector_reader.get_vals(0, &mut vals);
+        assert_eq!(&vals, &[1f32, 2f32, 3f32]);
+        vector_reader.get_vals(1, &mut vals);
+        assert_eq!(&vals, &[0f32, 0f32, 0f32]);
+        vector_reader.get_vals(2, &mut vals);
+        assert_eq!(&vals, &[0f32, 0f32, 0f32]);
+        vector_reader.get_vals(3, &mut vals);
+        assert_eq!(&vals, &[-0.5f32, 0f32, 4.25f32]);
+    }
+
+    #[test]
+    fn test_vector_fast_field_merge() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED);
+        let field = schema_builder.add_vector_field("embedding", VectorOptions::with_dims(2));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 3_000_000).unwrap();
+        for i in 0u64..3u64 {
+            index_writer.add_document(doc!(id_field => i, field => vec![i as f32, 1f32]));
+            index_writer.commit().unwrap();
+        }
+        index_writer.delete_term(Term::from_field_u64(id_field, 1u64));
+        index_writer.commit().unwrap();
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let vector_reader = searcher.segment_reader(0).vector_reader(field).unwrap();
+        assert_eq!(vector_reader.num_docs(), 2);
+        let mut vals = Vec::new();
+        vector_reader.get_vals(0, &mut vals);
+        assert_eq!(&vals, &[0f32, 1f32]);
+        vector_reader.get_vals(1, &mut vals);
+        assert_eq!(&vals, &[2f32, 1f32]);
+    }
+}
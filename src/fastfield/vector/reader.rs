@@ -0,0 +1,50 @@
+use byteorder::{ByteOrder, LittleEndian};
+use directory::ReadOnlySource;
+use DocId;
+
+const F32_NUM_BYTES: usize = 4;
+
+/// Reader for a vector field.
+///
+/// Gives random access to the vector of each document of a segment.
+/// Documents that did not have a vector have a vector of zeros.
+pub struct VectorFastFieldReader {
+    data: ReadOnlySource,
+    dims: usize,
+}
+
+impl VectorFastFieldReader {
+    /// Opens a vector field reader over the data of the field
+    /// within the fast field file.
+    pub fn open(data: ReadOnlySource, dims: usize) -> VectorFastFieldReader {
+        VectorFastFieldReader { data, dims }
+    }
+
+    /// Returns the number of dimensions of the vectors.
+    pub fn dims(&self) -> usize {
+        self.dims
+    }
+
+    /// Returns the number of documents of the segment.
+    pub fn num_docs(&self) -> DocId {
+        (self.data.len() / (self.dims * F32_NUM_BYTES)) as DocId
+    }
+
+    /// Returns the vector of the document `doc`, as little-endian bytes.
+    pub(crate) fn get_bytes(&self, doc: DocId) -> &[u8] {
+        let num_bytes = self.dims * F32_NUM_BYTES;
+        let start = doc as usize * num_bytes;
+        &self.data.as_slice()[start..start + num_bytes]
+    }
+
+    /// Pushes the components of the vector of the document `doc`
+    /// to `vals`, after clearing it.
+    pub fn get_vals(&self, doc: DocId, vals: &mut Vec<f32>) {
+        vals.clear();
+        vals.extend(
+            self.get_bytes(doc)
+                .chunks(F32_NUM_BYTES)
+                .map(LittleEndian::read_f32),
+        );
+    }
+}
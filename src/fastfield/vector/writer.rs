@@ -0,0 +1,64 @@
+use common::BinarySerializable;
+use fastfield::FastFieldSerializer;
+use schema::{Document, Field, Value};
+use std::io;
+
+/// Writer for vector fields.
+///
+/// The vectors are kept in memory, and serialized
+/// as a flat array of `dims * num_docs` little-endian `f32`
+/// when the segment is flushed.
+///
+/// Documents without a vector for the field get a vector of zeros.
+pub struct VectorFastFieldWriter {
+    field: Field,
+    dims: usize,
+    vals: Vec<f32>,
+}
+
+impl VectorFastFieldWriter {
+    /// Creates a new `VectorFastFieldWriter`.
+    pub fn new(field: Field, dims: usize) -> VectorFastFieldWriter {
+        VectorFastFieldWriter {
+            field,
+            dims,
+            vals: Vec::new(),
+        }
+    }
+
+    /// Returns the field that this writer is targetting.
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Records the vector of a new document.
+    ///
+    /// Only the first vector of the document is taken in account.
+    /// Vectors of the wrong number of dimensions are ignored.
+    pub fn add_document(&mut self, doc: &Document) {
+        match doc.get_first(self.field) {
+            Some(&Value::Vector(ref vector)) if vector.dims() == self.dims => {
+                self.vals.extend_from_slice(vector.as_slice());
+                return;
+            }
+            Some(value) => {
+                error!(
+                    "Ignoring value {:?} for the vector field {:?} of {} dimensions.",
+                    value, self.field, self.dims
+                );
+            }
+            None => {}
+        }
+        let num_vals = self.vals.len() + self.dims;
+        self.vals.resize(num_vals, 0f32);
+    }
+
+    /// Push the vectors to the `FastFieldSerializer`.
+    pub fn serialize(&self, serializer: &mut FastFieldSerializer) -> io::Result<()> {
+        let write = serializer.new_vector_fast_field(self.field);
+        for &val in &self.vals {
+            val.to_bits().serialize(write)?;
+        }
+        Ok(())
+    }
+}
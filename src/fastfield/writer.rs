@@ -1,19 +1,22 @@
 use schema::{Cardinality, Document, Field, Schema};
-use fastfield::FastFieldSerializer;
+use fastfield::{FastFieldSerializer, FastValue};
 use std::io;
 use DocId;
 use schema::FieldType;
-use common;
 use common::VInt;
 use std::collections::HashMap;
 use postings::UnorderedTermId;
 use super::multivalued::MultiValueIntFastFieldWriter;
+use super::vector::VectorFastFieldWriter;
+use super::bytes::BytesFastFieldWriter;
 use common::BinarySerializable;
 
 /// The fastfieldswriter regroup all of the fast field writers.
 pub struct FastFieldsWriter {
     single_value_writers: Vec<IntFastFieldWriter>,
     multi_values_writers: Vec<MultiValueIntFastFieldWriter>,
+    vector_writers: Vec<VectorFastFieldWriter>,
+    bytes_writers: Vec<BytesFastFieldWriter>,
 }
 
 impl FastFieldsWriter {
@@ -21,11 +24,13 @@ impl FastFieldsWriter {
     pub fn from_schema(schema: &Schema) -> FastFieldsWriter {
         let mut single_value_writers = Vec::new();
         let mut multi_values_writers = Vec::new();
+        let mut vector_writers = Vec::new();
+        let mut bytes_writers = Vec::new();
 
         for (field_id, field_entry) in schema.fields().iter().enumerate() {
             let field = Field(field_id as u32);
             let default_value = if let FieldType::I64(_) = *field_entry.field_type() {
-                common::i64_to_u64(0i64)
+                0i64.to_u64()
             } else {
                 0u64
             };
@@ -48,12 +53,23 @@ impl FastFieldsWriter {
                     let fast_field_writer = MultiValueIntFastFieldWriter::new(field, true);
                     multi_values_writers.push(fast_field_writer);
                 }
+                FieldType::Vector(ref vector_options) => {
+                    let vector_writer = VectorFastFieldWriter::new(field, vector_options.dims());
+                    vector_writers.push(vector_writer);
+                }
+                FieldType::Bytes(ref bytes_options) => {
+                    if bytes_options.is_fast() {
+                        bytes_writers.push(BytesFastFieldWriter::new(field));
+                    }
+                }
                 _ => {}
             }
         }
         FastFieldsWriter {
             single_value_writers,
-            multi_values_writers
+            multi_values_writers,
+            vector_writers,
+            bytes_writers,
         }
     }
 
@@ -63,6 +79,8 @@ impl FastFieldsWriter {
         FastFieldsWriter {
             single_value_writers: fields.into_iter().map(IntFastFieldWriter::new).collect(),
             multi_values_writers: vec![],
+            vector_writers: vec![],
+            bytes_writers: vec![],
         }
     }
 
@@ -98,6 +116,12 @@ impl FastFieldsWriter {
             field_writer.next_doc();
             field_writer.add_document(doc);
         }
+        for vector_writer in &mut self.vector_writers {
+            vector_writer.add_document(doc);
+        }
+        for bytes_writer in &mut self.bytes_writers {
+            bytes_writer.add_document(doc);
+        }
     }
 
     /// Serializes all of the `FastFieldWriter`s by pushing them in
@@ -114,9 +138,26 @@ impl FastFieldsWriter {
             let field = field_writer.field();
             field_writer.serialize(serializer, mapping.get(&field))?;
         }
+        for vector_writer in &self.vector_writers {
+            vector_writer.serialize(serializer)?;
+        }
+        for bytes_writer in &self.bytes_writers {
+            bytes_writer.serialize(serializer)?;
+        }
         Ok(())
     }
 
+    /// Returns the values recorded for the `u64` fast field `field`,
+    /// in the order of the doc ids.
+    ///
+    /// Returns None if there is no single value writer for the field.
+    pub(crate) fn get_field_vals(&self, field: Field) -> Option<Vec<u64>> {
+        self.single_value_writers
+            .iter()
+            .find(|field_writer| field_writer.field() == field)
+            .map(|field_writer| field_writer.vals())
+    }
+
     /// Ensures all of the fast field writers have
     /// reached `doc`. (included)
     ///
@@ -142,7 +183,7 @@ impl FastFieldsWriter {
 ///
 /// Both u64, and i64 use the same writer.
 /// i64 are just remapped to the `0..2^64 - 1`
-/// using `common::i64_to_u64`.
+/// using `FastValue::to_u64`.
 pub struct IntFastFieldWriter {
     field: Field,
     vals: Vec<u8>,
@@ -211,11 +252,20 @@ impl IntFastFieldWriter {
         self.val_count += 1;
     }
 
+    fn vals(&self) -> Vec<u64> {
+        let mut vals = Vec::with_capacity(self.val_count);
+        let mut cursor = self.vals.as_slice();
+        while let Ok(VInt(val)) = VInt::deserialize(&mut cursor) {
+            vals.push(val);
+        }
+        vals
+    }
+
     /// Extract the value associated to the fast field for
     /// this document.
     ///
     /// i64 are remapped to u64 using the logic
-    /// in `common::i64_to_u64`.
+    /// in `FastValue::to_u64`.
     ///
     /// If the value is missing, then the default value is used
     /// instead.
@@ -0,0 +1,259 @@
+use Result;
+use DocId;
+use common::CompositeFile;
+use core::{Index, SegmentComponent, SegmentMeta, SegmentReader};
+use error::ErrorKind;
+use fastfield::{FastFieldSerializer, FastValue};
+use indexer::segment_updater::save_metas;
+use schema::{Field, FieldType, Value};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Provides the values of a field that becomes a fast field
+/// in the existing segments. (See `Index::backfill_fast_field`)
+///
+/// It is implemented by the closures taking the `SegmentReader`
+/// and the `DocId` of the document, and by `StoredFieldValues`.
+pub trait FastFieldValueProvider {
+    /// Returns the value of the document `doc` of the segment.
+    ///
+    /// The value must be a `Value::U64` for a `u64` field,
+    /// and a `Value::I64` for an `i64` field.
+    /// The documents without value get `0`, as when they are indexed.
+    fn value(&mut self, segment_reader: &SegmentReader, doc: DocId) -> Result<Option<Value>>;
+}
+
+impl<F> FastFieldValueProvider for F
+where
+    F: FnMut(&SegmentReader, DocId) -> Result<Option<Value>>,
+{
+    fn value(&mut self, segment_reader: &SegmentReader, doc: DocId) -> Result<Option<Value>> {
+        self(segment_reader, doc)
+    }
+}
+
+/// Reads the values of a fast field from the doc store:
+/// the value of a document is the first value of a stored field.
+pub struct StoredFieldValues {
+    field: Field,
+}
+
+impl StoredFieldValues {
+    /// Creates a `StoredFieldValues` reading the values of the stored field `field`.
+    pub fn new(field: Field) -> StoredFieldValues {
+        StoredFieldValues { field }
+    }
+}
+
+impl FastFieldValueProvider for StoredFieldValues {
+    fn value(&mut self, segment_reader: &SegmentReader, doc: DocId) -> Result<Option<Value>> {
+        let doc_fields = segment_reader.doc_fields(doc, &[self.field])?;
+        Ok(doc_fields.get_first(self.field).cloned())
+    }
+}
+
+fn fast_value(value_opt: Option<Value>, is_i64: bool, doc: DocId) -> Result<u64> {
+    match (value_opt, is_i64) {
+        (None, false) => Ok(0u64),
+        (None, true) => Ok(0i64.to_u64()),
+        (Some(Value::U64(val)), false) => Ok(val),
+        (Some(Value::I64(val)), true) => Ok(val.to_u64()),
+        (Some(value), _) => bail!(ErrorKind::InvalidArgument(format!(
+            "The value {:?} of the document {} does not match the type of the field",
+            value, doc
+        ))),
+    }
+}
+
+/// Writes the new version of the fast field file of a segment,
+/// with the values of `field` given by `value_provider`.
+///
+/// Returns the meta of the segment pointing to the new file.
+fn backfill_segment<P: FastFieldValueProvider>(
+    index: &Index,
+    segment_meta: SegmentMeta,
+    field: Field,
+    is_i64: bool,
+    value_provider: &mut P,
+) -> Result<SegmentMeta> {
+    let segment = index.segment(segment_meta);
+    let segment_reader = SegmentReader::open(&segment)?;
+    let mut vals = Vec::with_capacity(segment_reader.max_doc() as usize);
+    for doc in 0..segment_reader.max_doc() {
+        let value_opt = value_provider.value(&segment_reader, doc)?;
+        vals.push(fast_value(value_opt, is_i64, doc)?);
+    }
+    let min_value = vals.iter().cloned().min().unwrap_or(0u64);
+    let max_value = vals.iter().cloned().max().unwrap_or(0u64);
+
+    let mut backfilled_segment_meta = segment.meta().clone();
+    backfilled_segment_meta.next_fast_fields_generation();
+    if !vals.is_empty() {
+        backfilled_segment_meta.set_fast_field_bounds(field, min_value, max_value);
+    }
+    let mut backfilled_segment = index.segment(backfilled_segment_meta.clone());
+    let fast_fields_data = segment.open_read(SegmentComponent::FASTFIELDS)?;
+    let fast_fields_composite = CompositeFile::open(&fast_fields_data)?;
+    let write = backfilled_segment.open_write(SegmentComponent::FASTFIELDS)?;
+    let mut fast_field_serializer = FastFieldSerializer::from_write(write)?;
+    fast_field_serializer.copy_fields(&fast_fields_composite)?;
+    {
+        let mut field_serializer =
+            fast_field_serializer.new_u64_fast_field(field, min_value, max_value)?;
+        for &val in &vals {
+            field_serializer.add_val(val)?;
+        }
+        field_serializer.close_field()?;
+    }
+    fast_field_serializer.close()?;
+    Ok(backfilled_segment_meta)
+}
+
+/// Makes `field` a fast field in all of the segments of the index,
+/// and saves the updated schema and segment metas.
+///
+/// The caller is in charge of holding the directory lock.
+pub(crate) fn backfill_fast_field<P: FastFieldValueProvider>(
+    index: &mut Index,
+    field: Field,
+    value_provider: &mut P,
+) -> Result<()> {
+    let schema = index.schema();
+    let field_entry = schema.get_field_entry(field);
+    if field_entry.is_int_fast() {
+        bail!(ErrorKind::InvalidArgument(format!(
+            "The field '{}' is already a fast field",
+            field_entry.name()
+        )));
+    }
+    let backfilled_schema = schema.with_fast_field(field).ok_or_else(|| {
+        ErrorKind::InvalidArgument(format!(
+            "The field '{}' is neither a u64 nor an i64 field",
+            field_entry.name()
+        ))
+    })?;
+    let is_i64 = match *field_entry.field_type() {
+        FieldType::I64(_) => true,
+        _ => false,
+    };
+    let metas = index.load_metas()?;
+    let mut segment_metas = Vec::with_capacity(metas.segments.len());
+    for segment_meta in metas.segments {
+        segment_metas.push(backfill_segment(
+            index,
+            segment_meta,
+            field,
+            is_i64,
+            value_provider,
+        )?);
+    }
+    let living_files: HashSet<PathBuf> = segment_metas
+        .iter()
+        .flat_map(|segment_meta| segment_meta.list_files())
+        .collect();
+    save_metas(
+        segment_metas,
+        backfilled_schema.clone(),
+        metas.settings,
+        metas.opstamp,
+        metas.payload,
+        metas.commits,
+        index.directory_mut(),
+    )?;
+    index.set_schema(backfilled_schema);
+    index.directory_mut().garbage_collect(|| living_files);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::StoredFieldValues;
+    use core::{SegmentComponent, SegmentReader};
+    use error::{Error, ErrorKind};
+    use futures::Future;
+    use schema::{SchemaBuilder, Value, INT_STORED, TEXT};
+    use DocId;
+    use Index;
+    use Result;
+    use Term;
+
+    #[test]
+    fn test_backfill_fast_field() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let num_field = schema_builder.add_u64_field("num", INT_STORED);
+        let rank_field = schema_builder.add_i64_field("rank", INT_STORED);
+        let mut index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a", num_field => 4u64));
+            index_writer.add_document(doc!(text_field => "b", num_field => 2u64));
+            index_writer.commit().unwrap();
+            index_writer.add_document(doc!(text_field => "c", num_field => 7u64));
+            index_writer.add_document(doc!(text_field => "d"));
+            index_writer.delete_term(Term::from_field_text(text_field, "b"));
+            index_writer.commit().unwrap();
+        }
+        index
+            .backfill_fast_field(num_field, StoredFieldValues::new(num_field))
+            .unwrap();
+        let negate = |segment_reader: &SegmentReader, doc: DocId| -> Result<Option<Value>> {
+            let doc_fields = segment_reader.doc_fields(doc, &[num_field])?;
+            Ok(doc_fields
+                .get_first(num_field)
+                .map(|value| Value::I64(-(value.u64_value() as i64))))
+        };
+        index.backfill_fast_field(rank_field, negate).unwrap();
+        let metas = index.load_metas().unwrap();
+        assert!(metas.schema.get_field_entry(num_field).is_int_fast());
+        for segment_meta in &metas.segments {
+            let path = segment_meta.relative_path(SegmentComponent::FASTFIELDS);
+            assert!(path.to_str().unwrap().ends_with(".2.fast"));
+        }
+        index.load_searchers().unwrap();
+        {
+            let searcher = index.searcher();
+            let mut num_vals = vec![];
+            let mut rank_vals = vec![];
+            for segment_reader in searcher.segment_readers() {
+                let num_reader = segment_reader.fast_field_reader::<u64>(num_field).unwrap();
+                let rank_reader = segment_reader.fast_field_reader::<i64>(rank_field).unwrap();
+                for doc in 0..segment_reader.max_doc() {
+                    num_vals.push(num_reader.get(doc));
+                    rank_vals.push(rank_reader.get(doc));
+                }
+            }
+            num_vals.sort();
+            rank_vals.sort();
+            assert_eq!(num_vals, vec![0, 2, 4, 7]);
+            assert_eq!(rank_vals, vec![-7, -4, -2, 0]);
+        }
+        match index.backfill_fast_field(num_field, StoredFieldValues::new(num_field)) {
+            Err(Error(ErrorKind::InvalidArgument(_), _)) => {}
+            _ => panic!("Expected InvalidArgument error"),
+        }
+
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        match index.backfill_fast_field(text_field, StoredFieldValues::new(text_field)) {
+            Err(Error(ErrorKind::FileAlreadyExists(_), _)) => {}
+            _ => panic!("Expected FileAlreadyExists error"),
+        }
+        index_writer.add_document(doc!(text_field => "e", num_field => 9u64, rank_field => -9i64));
+        index_writer.commit().unwrap();
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let segment_reader = searcher.segment_reader(0);
+        let num_reader = segment_reader.fast_field_reader::<u64>(num_field).unwrap();
+        let mut num_vals: Vec<u64> = (0..segment_reader.max_doc())
+            .map(|doc| num_reader.get(doc))
+            .collect();
+        num_vals.sort();
+        assert_eq!(num_vals, vec![0, 4, 7, 9]);
+        assert_eq!(segment_reader.fast_field_bounds::<u64>(num_field), Some((0, 9)));
+    }
+}
@@ -1,11 +1,14 @@
 use bit_set::BitSet;
 use chan;
+use core::CommitChanges;
 use core::Index;
 use core::Segment;
 use core::SegmentComponent;
 use core::SegmentId;
 use core::SegmentMeta;
 use core::SegmentReader;
+use core::segment_features;
+use common::Span;
 use indexer::stamper::Stamper;
 use datastruct::stacker::Heap;
 use directory::FileProtection;
@@ -17,6 +20,7 @@ use datastruct::stacker::hashmap::split_memory;
 use futures::Future;
 use indexer::doc_opstamp_mapping::DocToOpstampMapping;
 use indexer::MergePolicy;
+use indexer::MergeListener;
 use indexer::operation::DeleteOperation;
 use indexer::SegmentEntry;
 use indexer::SegmentWriter;
@@ -24,6 +28,7 @@ use docset::DocSet;
 use schema::IndexRecordOption;
 use schema::Document;
 use schema::Term;
+use schema::{Field, Value};
 use std::mem;
 use std::mem::swap;
 use std::thread::JoinHandle;
@@ -79,12 +84,31 @@ pub struct IndexWriter {
 
     stamper: Stamper,
     committed_opstamp: u64,
+    uncommitted_changes: CommitChanges,
+
+    unique_fields: Vec<Field>,
 }
 
 // IndexWriter cannot be sent to another thread.
 impl !Send for IndexWriter {}
 impl !Sync for IndexWriter {}
 
+/// Returns the terms of the unique key fields of a document.
+fn unique_key_terms(unique_fields: &[Field], document: &Document) -> Vec<Term> {
+    let mut terms = Vec::new();
+    for &field in unique_fields {
+        for value in document.get_all(field) {
+            match *value {
+                Value::Str(ref text) => terms.push(Term::from_field_text(field, text)),
+                Value::U64(val) => terms.push(Term::from_field_u64(field, val)),
+                Value::I64(val) => terms.push(Term::from_field_i64(field, val)),
+                _ => {}
+            }
+        }
+    }
+    terms
+}
+
 /// Open a new index writer. Attempts to acquire a lockfile.
 ///
 /// The lockfile should be deleted on drop, but it is possible
@@ -123,6 +147,15 @@ pub fn open_index_writer(
     let segment_updater =
         SegmentUpdater::new(index.clone(), stamper.clone(), &delete_queue.cursor())?;
 
+    let schema = index.schema();
+    let unique_fields: Vec<Field> = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|&(_, field_entry)| field_entry.is_unique())
+        .map(|(field_id, _)| Field(field_id as u32))
+        .collect();
+
     let mut index_writer = IndexWriter {
         _directory_lock: Some(directory_lock),
 
@@ -140,11 +173,14 @@ pub fn open_index_writer(
         delete_queue,
 
         committed_opstamp: current_opstamp,
+        uncommitted_changes: CommitChanges::new(current_opstamp),
         stamper,
 
         generation: 0,
 
         worker_id: 0,
+
+        unique_fields,
     };
     index_writer.start_workers()?;
     Ok(index_writer)
@@ -171,9 +207,9 @@ pub fn compute_deleted_bitset(
                 // Limit doc helps identify the first document
                 // that may be affected by the delete operation.
                 let limit_doc = doc_opstamps.compute_doc_limit(delete_op.opstamp);
-                let inverted_index = segment_reader.inverted_index(delete_op.term.field());
+                let inverted_index = segment_reader.inverted_index(delete_op.term.field())?;
                 if let Some(mut docset) =
-                    inverted_index.read_postings(&delete_op.term, IndexRecordOption::Basic)
+                    inverted_index.read_postings(&delete_op.term, IndexRecordOption::Basic)?
                 {
                     while docset.advance() {
                         let deleted_doc = docset.doc();
@@ -257,6 +293,9 @@ fn index_documents(
     heap.clear();
     let schema = segment.schema();
     let segment_id = segment.id();
+    let mut span = Span::enter("flush_segment", || {
+        format!("segment={}", segment_id.short_uuid_string())
+    });
     let mut segment_writer =
         SegmentWriter::for_segment(heap, table_size, segment.clone(), &schema)?;
     for doc in document_iterator {
@@ -295,15 +334,25 @@ fn index_documents(
     // the worker thread.
     assert!(num_docs > 0);
 
+    span.record("num_docs", num_docs);
+    segment.index().metrics().record_segment_flush(num_docs);
+    let single_term_fields = segment_writer.single_term_fields().to_vec();
     let doc_opstamps: Vec<u64> = segment_writer.finalize()?;
 
     let mut segment_meta = SegmentMeta::new(segment_id);
     segment_meta.set_max_doc(num_docs);
+    segment_meta.set_single_term_fields(single_term_fields);
+    for feature in segment_features(segment.index()) {
+        segment_meta.add_feature(&feature);
+    }
 
     let last_docstamp: u64 = *(doc_opstamps.last().unwrap());
 
     let doc_to_opstamps = DocToOpstampMapping::from(doc_opstamps);
     let segment_reader = SegmentReader::open(segment)?;
+    for (field, min_value, max_value) in segment_reader.read_fast_field_bounds() {
+        segment_meta.set_fast_field_bounds(field, min_value, max_value);
+    }
     let mut deleted_bitset = BitSet::with_capacity(num_docs as usize);
     let may_have_deletes = compute_deleted_bitset(
         &mut deleted_bitset,
@@ -434,6 +483,39 @@ impl IndexWriter {
         self.segment_updater.set_merge_policy(merge_policy);
     }
 
+    /// Sets the merge listener, notified before and after each merge.
+    ///
+    /// The listener may veto or delay the merges suggested
+    /// by the merge policy. (See `MergeListener`)
+    pub fn set_merge_listener(&self, merge_listener: Box<MergeListener>) {
+        self.segment_updater.set_merge_listener(merge_listener);
+    }
+
+    /// Sets whether merges should record the mapping from the doc ids
+    /// of the segments they merge to the doc ids of the resulting segment.
+    ///
+    /// This mapping makes it possible to resolve the `DocAddress`es obtained
+    /// from a searcher prior to a merge. (See `Searcher::resolve_doc_address`)
+    ///
+    /// It is disabled by default, and costs 4 bytes per merged document.
+    pub fn set_doc_id_mapping(&self, enabled: bool) {
+        self.segment_updater.set_doc_id_mapping(enabled);
+    }
+
+    /// Sets whether merges should check the segments they merge,
+    /// and leave out the segments found corrupted instead of failing.
+    ///
+    /// Corrupted segments are quarantined: they are kept as is in the index,
+    /// and are not considered for merges anymore by this `IndexWriter`.
+    /// The corruption, as well as the range of documents left out
+    /// of the merge, are logged.
+    ///
+    /// It is disabled by default, as checking a segment requires
+    /// reading it entirely.
+    pub fn set_quarantine_corrupt_segments(&self, enabled: bool) {
+        self.segment_updater.set_quarantine_corrupt_segments(enabled);
+    }
+
     fn start_workers(&mut self) -> Result<()> {
         for _ in 0..self.num_threads {
             self.add_indexing_worker()?;
@@ -573,7 +655,9 @@ impl IndexWriter {
         }
 
         let commit_opstamp = self.stamper.stamp();
-        let prepared_commit = PreparedCommit::new(self, commit_opstamp);
+        let mut changes = self.uncommitted_changes.clone();
+        changes.opstamp = commit_opstamp;
+        let prepared_commit = PreparedCommit::new(self, changes);
         info!("Prepared commit {}", commit_opstamp);
         Ok(prepared_commit)
     }
@@ -600,6 +684,18 @@ impl IndexWriter {
         &self.segment_updater
     }
 
+    /// Returns the adds and the deletes that happened since the last commit.
+    ///
+    /// Once committed, they are available in the `meta.json` file.
+    /// (See `Index::changes_since`)
+    pub fn uncommitted_changes(&self) -> &CommitChanges {
+        &self.uncommitted_changes
+    }
+
+    pub(crate) fn reset_uncommitted_changes(&mut self, commit_opstamp: u64) {
+        self.uncommitted_changes = CommitChanges::new(commit_opstamp);
+    }
+
     /// Delete all documents containing a given term.
     ///
     /// Delete operation only affects documents that
@@ -612,6 +708,7 @@ impl IndexWriter {
         let opstamp = self.stamper.stamp();
         let delete_operation = DeleteOperation { opstamp, term };
         self.delete_queue.push(delete_operation);
+        self.uncommitted_changes.record_delete(opstamp);
         opstamp
     }
 
@@ -636,10 +733,21 @@ impl IndexWriter {
     ///
     /// Currently it represents the number of documents that
     /// have been added since the creation of the index.
+    ///
+    /// If the schema has unique key fields, the documents with the same
+    /// key as the added document are deleted first, as if `delete_term`
+    /// had been called. Like the deletes of `delete_term`, this applies
+    /// to the documents of the previous commits, as well as to the
+    /// documents that were added previously in the same commit.
+    /// (See `TextOptions::set_unique`)
     pub fn add_document(&mut self, document: Document) -> u64 {
+        for term in unique_key_terms(&self.unique_fields, &document) {
+            self.delete_term(term);
+        }
         let opstamp = self.stamper.stamp();
         let add_operation = AddOperation { opstamp, document };
         self.document_sender.send(add_operation);
+        self.uncommitted_changes.record_add(opstamp);
         opstamp
     }
 }
@@ -673,7 +781,7 @@ mod tests {
         assert_eq!(
             format!("{:?}", index_writer.get_merge_policy()),
             "LogMergePolicy { min_merge_size: 8, min_layer_size: 10000, \
-             level_log_size: 0.75 }"
+             max_docs_before_merge: 4294967295, level_log_size: 0.75 }"
         );
         let merge_policy = box NoMergePolicy::default();
         index_writer.set_merge_policy(merge_policy);
@@ -704,7 +812,7 @@ mod tests {
         let num_docs_containing = |s: &str| {
             let searcher = index.searcher();
             let term = Term::from_field_text(text_field, s);
-            searcher.doc_freq(&term)
+            searcher.doc_freq(&term).unwrap()
         };
 
         {
@@ -738,7 +846,7 @@ mod tests {
         let num_docs_containing = |s: &str| {
             let searcher = index.searcher();
             let term_a = Term::from_field_text(text_field, s);
-            searcher.doc_freq(&term_a)
+            searcher.doc_freq(&term_a).unwrap()
         };
         {
             // writing the segment
@@ -835,10 +943,111 @@ mod tests {
         let num_docs_containing = |s: &str| {
             let searcher = index.searcher();
             let term_a = Term::from_field_text(text_field, s);
-            searcher.doc_freq(&term_a)
+            searcher.doc_freq(&term_a).unwrap()
         };
         assert_eq!(num_docs_containing("a"), 0);
         assert_eq!(num_docs_containing("b"), 100);
     }
 
+    #[test]
+    fn test_unique_key() {
+        use collector::CountCollector;
+        use query::TermQuery;
+        use schema::IndexRecordOption;
+
+        let mut schema_builder = schema::SchemaBuilder::default();
+        let id_field = schema_builder.add_text_field("id", schema::STRING.set_unique());
+        let num_field = schema_builder.add_u64_field("num", schema::INT_INDEXED.set_unique());
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(id_field => "a", text_field => "first"));
+        index_writer.add_document(doc!(id_field => "b", text_field => "first"));
+        index_writer.add_document(doc!(num_field => 1u64, text_field => "first"));
+        index_writer.commit().unwrap();
+        // upserts within the same commit, and across commits.
+        index_writer.add_document(doc!(id_field => "c", text_field => "first"));
+        index_writer.add_document(doc!(id_field => "c", text_field => "second"));
+        index_writer.add_document(doc!(id_field => "a", text_field => "second"));
+        index_writer.add_document(doc!(num_field => 1u64, text_field => "second"));
+        index_writer.add_document(doc!(text_field => "second"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.num_docs(), 5);
+        let num_docs_containing = |term: Term| {
+            let term_query = TermQuery::new(term, IndexRecordOption::Basic);
+            let mut count_collector = CountCollector::default();
+            searcher.search(&term_query, &mut count_collector).unwrap();
+            count_collector.count()
+        };
+        assert_eq!(num_docs_containing(Term::from_field_text(id_field, "a")), 1);
+        assert_eq!(num_docs_containing(Term::from_field_text(id_field, "b")), 1);
+        assert_eq!(num_docs_containing(Term::from_field_text(id_field, "c")), 1);
+        assert_eq!(num_docs_containing(Term::from_field_u64(num_field, 1u64)), 1);
+        assert_eq!(num_docs_containing(Term::from_field_text(text_field, "first")), 1);
+        assert_eq!(num_docs_containing(Term::from_field_text(text_field, "second")), 4);
+    }
+
+    #[test]
+    fn test_unique_key_not_raw_indexed() {
+        {
+            let mut schema_builder = schema::SchemaBuilder::default();
+            schema_builder.add_text_field("id", schema::TEXT.set_unique());
+            assert!(schema_builder.try_build().is_err());
+        }
+        {
+            let mut schema_builder = schema::SchemaBuilder::default();
+            schema_builder.add_text_field("id", schema::STORED.set_unique());
+            assert!(schema_builder.try_build().is_err());
+        }
+        {
+            let mut schema_builder = schema::SchemaBuilder::default();
+            schema_builder.add_u64_field("num", schema::INT_STORED.set_unique());
+            assert!(schema_builder.try_build().is_err());
+        }
+        {
+            let mut schema_builder = schema::SchemaBuilder::default();
+            schema_builder.add_text_field("id", schema::STRING.set_unique());
+            schema_builder.add_u64_field("num", schema::INT_INDEXED.set_unique());
+            assert!(schema_builder.try_build().is_ok());
+        }
+    }
+    #[test]
+    fn test_changes_since() {
+        let mut schema_builder = schema::SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a"));
+        index_writer.add_document(doc!(text_field => "b"));
+        assert_eq!(index_writer.commit().unwrap(), 2u64);
+        index_writer.delete_term(Term::from_field_text(text_field, "a"));
+        index_writer.add_document(doc!(text_field => "c"));
+        {
+            let prepared_commit = index_writer.prepare_commit().unwrap();
+            let changes = prepared_commit.changes().clone();
+            assert_eq!(changes.previous_opstamp, 2u64);
+            assert_eq!(changes.opstamp, 5u64);
+            assert_eq!(changes.add_opstamps, vec![(4, 5)]);
+            assert_eq!(changes.delete_opstamps, vec![3]);
+            prepared_commit.commit().unwrap();
+        }
+        assert_eq!(index_writer.uncommitted_changes().num_added_docs(), 0);
+        index_writer.add_document(doc!(text_field => "d"));
+        assert_eq!(index_writer.uncommitted_changes().num_added_docs(), 1);
+        index_writer.rollback().unwrap();
+        assert_eq!(index_writer.uncommitted_changes().num_added_docs(), 0);
+
+        let changes = index.changes_since(0).unwrap().unwrap();
+        assert_eq!(changes.commits().len(), 2);
+        assert_eq!(changes.num_added_docs(), 3);
+        assert_eq!(changes.delete_opstamps(), vec![3]);
+        assert!(changes.commits()[0].contains_add(1));
+        let changes = index.changes_since(2).unwrap().unwrap();
+        assert_eq!(changes.commits().len(), 1);
+        assert_eq!(changes.num_added_docs(), 1);
+        assert!(index.changes_since(5).unwrap().unwrap().is_empty());
+        assert!(index.changes_since(1).unwrap().is_none());
+    }
 }
@@ -2,10 +2,12 @@ use super::merge_policy::{MergeCandidate, MergePolicy};
 use core::SegmentMeta;
 use std::cmp;
 use std::f64;
+use std::u32;
 
 const DEFAULT_LEVEL_LOG_SIZE: f64 = 0.75;
 const DEFAULT_MIN_LAYER_SIZE: u32 = 10_000;
 const DEFAULT_MIN_MERGE_SIZE: usize = 8;
+const DEFAULT_MAX_DOCS_BEFORE_MERGE: u32 = u32::MAX;
 
 /// `LogMergePolicy` tries tries to merge segments that have a similar number of
 /// documents.
@@ -13,6 +15,7 @@ const DEFAULT_MIN_MERGE_SIZE: usize = 8;
 pub struct LogMergePolicy {
     min_merge_size: usize,
     min_layer_size: u32,
+    max_docs_before_merge: u32,
     level_log_size: f64,
 }
 
@@ -32,6 +35,15 @@ impl LogMergePolicy {
         self.min_layer_size = min_layer_size;
     }
 
+    /// Set the maximum number of documents a segment may have to
+    /// be considered for a merge.
+    ///
+    /// Segments that are larger than this are left untouched, which
+    /// bounds the write amplification of merges.
+    pub fn set_max_docs_before_merge(&mut self, max_docs_before_merge: u32) {
+        self.max_docs_before_merge = max_docs_before_merge;
+    }
+
     /// Set the ratio between two consecutive levels.
     ///
     /// Segment are group in levels according to their sizes.
@@ -46,16 +58,17 @@ impl LogMergePolicy {
 
 impl MergePolicy for LogMergePolicy {
     fn compute_merge_candidates(&self, segments: &[SegmentMeta]) -> Vec<MergeCandidate> {
-        if segments.is_empty() {
-            return Vec::new();
-        }
-
         let mut size_sorted_tuples = segments
             .iter()
             .map(|x| x.num_docs())
             .enumerate()
+            .filter(|&(_, num_docs)| num_docs <= self.max_docs_before_merge)
             .collect::<Vec<(usize, u32)>>();
 
+        if size_sorted_tuples.is_empty() {
+            return Vec::new();
+        }
+
         size_sorted_tuples.sort_by(|x, y| y.1.cmp(&(x.1)));
 
         let size_sorted_log_tuples: Vec<_> = size_sorted_tuples
@@ -91,6 +104,7 @@ impl Default for LogMergePolicy {
         LogMergePolicy {
             min_merge_size: DEFAULT_MIN_MERGE_SIZE,
             min_layer_size: DEFAULT_MIN_LAYER_SIZE,
+            max_docs_before_merge: DEFAULT_MAX_DOCS_BEFORE_MERGE,
             level_log_size: DEFAULT_LEVEL_LOG_SIZE,
         }
     }
@@ -181,4 +195,24 @@ mod tests {
         let result_list = test_merge_policy().compute_merge_candidates(&test_input);
         assert_eq!(result_list.len(), 1);
     }
+
+    #[test]
+    fn test_log_merge_policy_max_docs_before_merge() {
+        // segments larger than max_docs_before_merge are excluded
+        let mut merge_policy = test_merge_policy();
+        merge_policy.set_max_docs_before_merge(100);
+        let test_input = vec![
+            seg_meta(10),
+            seg_meta(10),
+            seg_meta(1000),
+            seg_meta(1000),
+            seg_meta(1000),
+        ];
+        let result_list = merge_policy.compute_merge_candidates(&test_input);
+        assert!(result_list.is_empty());
+        let test_input = vec![seg_meta(10), seg_meta(10), seg_meta(10), seg_meta(1000)];
+        let result_list = merge_policy.compute_merge_candidates(&test_input);
+        assert_eq!(result_list.len(), 1);
+        assert_eq!(result_list[0].0.len(), 3);
+    }
 }
@@ -0,0 +1,44 @@
+use core::SegmentId;
+use core::SegmentMeta;
+use std::marker;
+use std::time::Duration;
+
+/// Decision of a `MergeListener` about a merge suggested by the merge policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeDecision {
+    /// The merge starts right away.
+    Proceed,
+    /// The merge does not happen.
+    ///
+    /// The merge policy may suggest it again,
+    /// the next time the list of segments changes.
+    Veto,
+    /// The merge starts after the given duration.
+    ///
+    /// In the meantime, its segments are searchable but
+    /// are not considered for other merges.
+    Delay(Duration),
+}
+
+/// The `MergeListener` is notified of the merges of an `IndexWriter`,
+/// and may veto or delay them. (See `IndexWriter::set_merge_listener`)
+///
+/// This makes it possible, for instance, to avoid large merges
+/// during peak traffic.
+///
+/// Its methods are called on the segment updater thread, and will block
+/// other segment updates, so all implementations should return rapidly.
+pub trait MergeListener: marker::Send + marker::Sync {
+    /// Called for each of the merges suggested by the merge policy,
+    /// with the metas of the segments to merge.
+    ///
+    /// The merges requested explicitly via `IndexWriter::merge`
+    /// are not subject to this decision.
+    fn before_merge(&self, _segment_metas: &[SegmentMeta]) -> MergeDecision {
+        MergeDecision::Proceed
+    }
+
+    /// Called once the segments `merged_segment_ids` have been replaced
+    /// by the result of their merge.
+    fn after_merge(&self, _merged_segment_ids: &[SegmentId], _segment_meta: &SegmentMeta) {}
+}
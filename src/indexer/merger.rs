@@ -9,7 +9,7 @@ use itertools::Itertools;
 use postings::Postings;
 use docset::DocSet;
 use fastfield::DeleteBitSet;
-use schema::{Field, Schema};
+use schema::{Field, FieldType, IndexRecordOption, Schema};
 use termdict::TermMerger;
 use fastfield::FastFieldSerializer;
 use fastfield::FastFieldReader;
@@ -17,6 +17,11 @@ use store::StoreWriter;
 use std::cmp::{max, min};
 use termdict::TermDictionary;
 use termdict::TermStreamer;
+use core::write_doc_id_mapping;
+use core::SegmentComponent;
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
 
 pub struct IndexMerger {
     schema: Schema,
@@ -38,8 +43,8 @@ fn compute_min_max_val(
     } else {
         // some deleted documents,
         // we need to recompute the max / min
-        (0..max_doc)
-            .filter(|doc_id| !delete_bitset.is_deleted(*doc_id))
+        delete_bitset
+            .doc_ids_alive(max_doc)
             .map(|doc_id| u64_reader.get(doc_id))
             .minmax()
             .into_option()
@@ -82,6 +87,162 @@ impl DeltaComputer {
         }
         &self.buffer[..positions.len()]
     }
+
+    /// Interleaves the position deltas with the encoded offsets.
+    ///
+    /// (See `FieldSerializer::write_doc`)
+    fn compute_delta_with_offsets(&mut self, positions: &[u32], offsets: &[(u32, u32)]) -> &[u32] {
+        self.buffer.clear();
+        let mut last_pos = 0u32;
+        let mut last_offset_from = 0u32;
+        for (&cur_pos, &(offset_from, offset_to)) in positions.iter().zip(offsets.iter()) {
+            self.buffer.push(cur_pos - last_pos);
+            self.buffer.push(offset_from.wrapping_sub(last_offset_from));
+            self.buffer.push(offset_to.wrapping_sub(offset_from));
+            last_pos = cur_pos;
+            last_offset_from = offset_from;
+        }
+        &self.buffer[..]
+    }
+}
+
+/// Runs a check over a segment, reporting errors and panics
+/// as an `ErrorKind::CorruptedFile` for the given path.
+///
+/// `check` may set its argument to describe what it is currently
+/// reading. This description is logged together with the error.
+fn check_segment<T, F>(segment: &Segment, path: PathBuf, check: F) -> Result<T>
+where
+    F: FnOnce(&mut String) -> ::std::result::Result<T, String>,
+{
+    let mut location = String::new();
+    let detail = match panic::catch_unwind(AssertUnwindSafe(|| check(&mut location))) {
+        Ok(Ok(val)) => return Ok(val),
+        Ok(Err(detail)) => detail,
+        Err(_) => "decoding panicked".to_string(),
+    };
+    error!(
+        "Segment {:?} is corrupted ({:?}, {}): {}",
+        segment.id(),
+        path,
+        location,
+        detail
+    );
+    bail!(ErrorKind::CorruptedFile(path))
+}
+
+/// Reads through the postings, the fieldnorms, the fast fields and
+/// the doc store of a segment, in order to detect corrupted data
+/// before merging it.
+///
+/// Decoding corrupted data may panic. Panics are caught, and reported
+/// as an `ErrorKind::CorruptedFile` error. The field and term, or the document
+/// being read when the corruption was detected are logged.
+pub fn validate_segment(segment: &Segment) -> Result<()> {
+    let segment_reader = check_segment(
+        segment,
+        PathBuf::from(segment.id().uuid_string()),
+        |_| SegmentReader::open(segment).map_err(|e| e.to_string()),
+    )?;
+    let schema = segment_reader.schema().clone();
+    let max_doc = segment_reader.max_doc();
+    let indexed_fields: Vec<(Field, IndexRecordOption)> = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .flat_map(|(field_id, field_entry)| {
+            field_entry
+                .field_type()
+                .get_index_record_option()
+                .map(|record_option| (Field(field_id as u32), record_option))
+        })
+        .collect();
+
+    check_segment(
+        segment,
+        segment.relative_path(SegmentComponent::POSTINGS),
+        |location| {
+            for &(field, record_option) in &indexed_fields {
+                let inverted_index = segment_reader
+                    .inverted_index(field)
+                    .map_err(|e| e.to_string())?;
+                let mut term_stream = inverted_index.terms().stream();
+                while term_stream.advance() {
+                    *location = format!(
+                        "field {:?}, term {:?}",
+                        schema.get_field_name(field),
+                        term_stream.key()
+                    );
+                    let mut postings = inverted_index
+                        .read_postings_from_terminfo(term_stream.value(), record_option)
+                        .map_err(|e| e.to_string())?;
+                    let mut previous_doc: Option<DocId> = None;
+                    while postings.advance() {
+                        let doc = postings.doc();
+                        if doc >= max_doc || previous_doc.map(|prev| doc <= prev).unwrap_or(false) {
+                            return Err(format!("invalid doc id {}", doc));
+                        }
+                        previous_doc = Some(doc);
+                        if record_option.has_positions()
+                            && postings.positions().len() != postings.term_freq() as usize
+                        {
+                            return Err(format!("invalid positions for doc {}", doc));
+                        }
+                    }
+                }
+            }
+            Ok(())
+        },
+    )?;
+
+    check_segment(
+        segment,
+        segment.relative_path(SegmentComponent::FIELDNORMS),
+        |location| {
+            for &(field, _) in &indexed_fields {
+                *location = format!("field {:?}", schema.get_field_name(field));
+                let fieldnorms_reader = extract_fieldnorm_reader(&segment_reader, field)
+                    .ok_or_else(|| "missing fieldnorms".to_string())?;
+                for doc in 0..max_doc {
+                    fieldnorms_reader.get(doc);
+                }
+            }
+            Ok(())
+        },
+    )?;
+
+    check_segment(
+        segment,
+        segment.relative_path(SegmentComponent::FASTFIELDS),
+        |location| {
+            for (field_id, field_entry) in schema.fields().iter().enumerate() {
+                if !field_entry.is_int_fast() {
+                    continue;
+                }
+                let field = Field(field_id as u32);
+                *location = format!("field {:?}", field_entry.name());
+                if let Some(fast_field_reader) = extract_fast_field_reader(&segment_reader, field) {
+                    for doc in 0..max_doc {
+                        fast_field_reader.get(doc);
+                    }
+                }
+            }
+            Ok(())
+        },
+    )?;
+
+    check_segment(
+        segment,
+        segment.relative_path(SegmentComponent::STORE),
+        |location| {
+            let store_reader = segment_reader.get_store_reader();
+            for doc in 0..max_doc {
+                *location = format!("doc {}", doc);
+                store_reader.get(doc).map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        },
+    )
 }
 
 impl IndexMerger {
@@ -132,6 +293,69 @@ impl IndexMerger {
         )
     }
 
+    fn write_vector_fields(&self, fast_field_serializer: &mut FastFieldSerializer) -> Result<()> {
+        for (field_id, field_entry) in self.schema.fields().iter().enumerate() {
+            if let FieldType::Vector(_) = *field_entry.field_type() {
+                let field = Field(field_id as u32);
+                let mut vector_readers = vec![];
+                for reader in &self.readers {
+                    vector_readers.push((reader, reader.vector_reader(field)?));
+                }
+                let write = fast_field_serializer.new_vector_fast_field(field);
+                for (reader, vector_reader) in vector_readers {
+                    for doc_id in reader.doc_ids_alive() {
+                        write.write_all(vector_reader.get_bytes(doc_id))?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_bytes_fast_fields(
+        &self,
+        fast_field_serializer: &mut FastFieldSerializer,
+    ) -> Result<()> {
+        for (field_id, field_entry) in self.schema.fields().iter().enumerate() {
+            match *field_entry.field_type() {
+                FieldType::Bytes(ref bytes_options) if bytes_options.is_fast() => {}
+                _ => continue,
+            }
+            let field = Field(field_id as u32);
+            let mut bytes_readers = vec![];
+            let mut total_num_bytes = 0u64;
+            for reader in &self.readers {
+                let bytes_reader = reader.bytes_fast_field_reader(field)?;
+                for doc_id in reader.doc_ids_alive() {
+                    total_num_bytes += bytes_reader.get_val(doc_id).len() as u64;
+                }
+                bytes_readers.push((reader, bytes_reader));
+            }
+            {
+                // writing the offset index
+                let mut doc_index_serializer = fast_field_serializer
+                    .new_u64_fast_field_with_idx(field, 0, total_num_bytes, 0)?;
+                let mut offset = 0u64;
+                for &(reader, ref bytes_reader) in &bytes_readers {
+                    for doc_id in reader.doc_ids_alive() {
+                        doc_index_serializer.add_val(offset)?;
+                        offset += bytes_reader.get_val(doc_id).len() as u64;
+                    }
+                }
+                doc_index_serializer.add_val(offset)?;
+                doc_index_serializer.close_field()?;
+            }
+            // writing the values themselves.
+            let write = fast_field_serializer.new_bytes_fast_field_with_idx(field, 1);
+            for (reader, bytes_reader) in bytes_readers {
+                for doc_id in reader.doc_ids_alive() {
+                    write.write_all(bytes_reader.get_val(doc_id))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     // used both to merge field norms and regular u64 fast fields.
     fn generic_write_fast_field(
         &self,
@@ -182,11 +406,9 @@ impl IndexMerger {
             let mut fast_single_field_serializer =
                 fast_field_serializer.new_u64_fast_field(field, min_val, max_val)?;
             for (max_doc, u64_reader, delete_bitset) in u64_readers {
-                for doc_id in 0..max_doc {
-                    if !delete_bitset.is_deleted(doc_id) {
-                        let val = u64_reader.get(doc_id);
-                        fast_single_field_serializer.add_val(val)?;
-                    }
+                for doc_id in delete_bitset.doc_ids_alive(max_doc) {
+                    let val = u64_reader.get(doc_id);
+                    fast_single_field_serializer.add_val(val)?;
                 }
             }
 
@@ -209,7 +431,7 @@ impl IndexMerger {
             let field_readers = self.readers
                 .iter()
                 .map(|reader| reader.inverted_index(indexed_field))
-                .collect::<Vec<_>>();
+                .collect::<Result<Vec<_>>>()?;
 
             let field_term_streams = field_readers
                 .iter()
@@ -247,7 +469,21 @@ impl IndexMerger {
             //                                seg0.max_doc + seg1.max_doc + seg2.max_doc]
             // ...
 
-            let mut field_serializer = serializer.new_field(indexed_field)?;
+            // fieldnorms of the merged segment, used to compute the
+            // impacts of the postings blocks.
+            let mut fieldnorms = Vec::with_capacity(max_doc as usize);
+            for reader in &self.readers {
+                let fieldnorms_reader = reader.get_fieldnorms_reader(indexed_field);
+                for doc_id in reader.doc_ids_alive() {
+                    let fieldnorm = fieldnorms_reader
+                        .as_ref()
+                        .map(|fieldnorms_reader| fieldnorms_reader.get(doc_id))
+                        .unwrap_or(1u64);
+                    fieldnorms.push(fieldnorm);
+                }
+            }
+
+            let mut field_serializer = serializer.new_field(indexed_field, &fieldnorms)?;
 
             let field_entry = self.schema.get_field_entry(indexed_field);
 
@@ -262,23 +498,16 @@ impl IndexMerger {
                 let term_bytes: &[u8] = merged_terms.key();
 
                 // Let's compute the list of non-empty posting lists
-                let segment_postings: Vec<_> = merged_terms
-                    .current_kvs()
-                    .iter()
-                    .flat_map(|heap_item| {
-                        let segment_ord = heap_item.segment_ord;
-                        let term_info = heap_item.streamer.value();
-                        let segment_reader = &self.readers[heap_item.segment_ord];
-                        let inverted_index = segment_reader.inverted_index(indexed_field);
-                        let mut segment_postings = inverted_index
-                            .read_postings_from_terminfo(term_info, segment_postings_option);
-                        if segment_postings.advance() {
-                            Some((segment_ord, segment_postings))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
+                let mut segment_postings = Vec::new();
+                for heap_item in merged_terms.current_kvs() {
+                    let segment_ord = heap_item.segment_ord;
+                    let term_info = heap_item.streamer.value();
+                    let mut postings = field_readers[segment_ord]
+                        .read_postings_from_terminfo(term_info, segment_postings_option)?;
+                    if postings.advance() {
+                        segment_postings.push((segment_ord, postings));
+                    }
+                }
 
                 // At this point, `segment_postings` contains the posting list
                 // of all of the segments containing the given term.
@@ -306,7 +535,12 @@ impl IndexMerger {
                                 // there is at least one document.
                                 let positions: &[u32] = segment_postings.positions();
                                 let term_freq = segment_postings.term_freq();
-                                let delta_positions = delta_computer.compute_delta(positions);
+                                let delta_positions = if segment_postings_option.has_offsets() {
+                                    let offsets = segment_postings.offsets();
+                                    delta_computer.compute_delta_with_offsets(positions, offsets)
+                                } else {
+                                    delta_computer.compute_delta(positions)
+                                };
                                 field_serializer.write_doc(
                                     remapped_doc_id,
                                     term_freq,
@@ -333,11 +567,9 @@ impl IndexMerger {
         for reader in &self.readers {
             let store_reader = reader.get_store_reader();
             if reader.num_deleted_docs() > 0 {
-                for doc_id in 0..reader.max_doc() {
-                    if !reader.is_deleted(doc_id) {
-                        let doc = store_reader.get(doc_id)?;
-                        store_writer.store(&doc)?;
-                    }
+                for doc_id in reader.doc_ids_alive() {
+                    let doc = store_reader.get(doc_id)?;
+                    store_writer.store(&doc)?;
                 }
             } else {
                 store_writer.stack(store_reader)?;
@@ -345,6 +577,32 @@ impl IndexMerger {
         }
         Ok(())
     }
+
+    /// Writes the mapping from the doc ids of the merged segments
+    /// to the doc ids of the resulting segment.
+    ///
+    /// See [`DocIdMapping`](../core/struct.DocIdMapping.html).
+    pub fn write_doc_id_mapping<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let mut new_doc: DocId = 0;
+        let segments: Vec<_> = self.readers
+            .iter()
+            .map(|reader| {
+                let new_doc_ids = (0..reader.max_doc())
+                    .map(|doc| {
+                        if reader.is_deleted(doc) {
+                            None
+                        } else {
+                            new_doc += 1;
+                            Some(new_doc - 1)
+                        }
+                    })
+                    .collect();
+                (reader.segment_id(), new_doc_ids)
+            })
+            .collect();
+        write_doc_id_mapping(&segments, writer)?;
+        Ok(())
+    }
 }
 
 impl SerializableSegment for IndexMerger {
@@ -352,6 +610,8 @@ impl SerializableSegment for IndexMerger {
         self.write_postings(serializer.get_postings_serializer())?;
         self.write_fieldnorms(serializer.get_fieldnorms_serializer())?;
         self.write_fast_fields(serializer.get_fast_field_serializer())?;
+        self.write_vector_fields(serializer.get_fast_field_serializer())?;
+        self.write_bytes_fast_fields(serializer.get_fast_field_serializer())?;
         self.write_storable_fields(serializer.get_store_writer())?;
         serializer.close()?;
         Ok(self.max_doc)
@@ -2,6 +2,7 @@ pub mod index_writer;
 pub mod segment_serializer;
 pub mod merger;
 mod merge_policy;
+mod merge_listener;
 mod log_merge_policy;
 mod segment_register;
 mod segment_writer;
@@ -14,6 +15,7 @@ mod doc_opstamp_mapping;
 pub mod operation;
 mod stamper;
 mod prepared_commit;
+mod fast_field_backfill;
 
 pub use self::prepared_commit::PreparedCommit;
 pub use self::segment_entry::{SegmentEntry, SegmentState};
@@ -22,8 +24,11 @@ pub use self::segment_writer::SegmentWriter;
 pub use self::index_writer::IndexWriter;
 pub use self::log_merge_policy::LogMergePolicy;
 pub use self::merge_policy::{MergeCandidate, MergePolicy, NoMergePolicy};
+pub use self::merge_listener::{MergeDecision, MergeListener};
 pub use self::segment_manager::SegmentManager;
 pub(crate) use self::directory_lock::DirectoryLock;
+pub use self::fast_field_backfill::{FastFieldValueProvider, StoredFieldValues};
+pub(crate) use self::fast_field_backfill::backfill_fast_field;
 
 /// Alias for the default merge policy, which is the `LogMergePolicy`.
 pub type DefaultMergePolicy = LogMergePolicy;
@@ -1,19 +1,23 @@
 use Result;
 use super::IndexWriter;
+use common::Span;
+use core::CommitChanges;
 
 /// A prepared commit
 pub struct PreparedCommit<'a> {
     index_writer: &'a mut IndexWriter,
     payload: Option<String>,
     opstamp: u64,
+    changes: CommitChanges,
 }
 
 impl<'a> PreparedCommit<'a> {
-    pub(crate) fn new(index_writer: &'a mut IndexWriter, opstamp: u64) -> PreparedCommit {
+    pub(crate) fn new(index_writer: &'a mut IndexWriter, changes: CommitChanges) -> PreparedCommit {
         PreparedCommit {
             index_writer: index_writer,
             payload: None,
-            opstamp: opstamp,
+            opstamp: changes.opstamp,
+            changes: changes,
         }
     }
 
@@ -21,6 +25,11 @@ impl<'a> PreparedCommit<'a> {
         self.opstamp
     }
 
+    /// Returns the adds and the deletes included in the commit.
+    pub fn changes(&self) -> &CommitChanges {
+        &self.changes
+    }
+
     pub fn set_payload(&mut self, payload: &str) {
         self.payload = Some(payload.to_string())
     }
@@ -31,9 +40,11 @@ impl<'a> PreparedCommit<'a> {
 
     pub fn commit(self) -> Result<u64> {
         info!("committing {}", self.opstamp);
+        let _span = Span::enter("commit", || format!("opstamp={}", self.opstamp));
         self.index_writer
             .segment_updater()
-            .commit(self.opstamp, self.payload)?;
+            .commit(self.opstamp, self.payload, self.changes)?;
+        self.index_writer.reset_uncommitted_changes(self.opstamp);
         Ok(self.opstamp)
     }
 }
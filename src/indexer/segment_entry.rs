@@ -8,6 +8,7 @@ use std::fmt;
 pub enum SegmentState {
     Ready,
     InMerge,
+    Quarantined,
 }
 
 impl SegmentState {
@@ -15,6 +16,7 @@ impl SegmentState {
         match *self {
             SegmentState::InMerge => 'M',
             SegmentState::Ready => 'R',
+            SegmentState::Quarantined => 'Q',
         }
     }
 }
@@ -25,7 +27,7 @@ impl SegmentState {
 /// In addition to segment `meta`,
 /// it contains a few transient states
 /// - `state` expresses whether the segment is already in the
-/// middle of a merge
+/// middle of a merge, or was found corrupted by a merge
 /// - `delete_bitset` is a bitset describing
 /// documents that were deleted during the commit
 /// itself.
@@ -104,7 +106,17 @@ impl SegmentEntry {
     /// the segment back to a idle state, so that it
     /// may be elligible for future merges.
     pub fn cancel_merge(&mut self) {
-        self.state = SegmentState::Ready;
+        if self.state == SegmentState::InMerge {
+            self.state = SegmentState::Ready;
+        }
+    }
+
+    /// Mark the `SegmentEntry` as quarantined.
+    ///
+    /// A quarantined segment was found corrupted during a merge.
+    /// It is kept as is, and is not elligible for merges anymore.
+    pub fn quarantine(&mut self) {
+        self.state = SegmentState::Quarantined;
     }
 
     /// Returns true iff a segment should
@@ -177,6 +177,16 @@ impl SegmentManager {
         registers_lock.writing.remove(&after_merge_segment_id);
     }
 
+    /// Marks a segment as quarantined. (See `SegmentEntry::quarantine`)
+    pub fn quarantine_segment(&self, segment_id: &SegmentId) {
+        let mut registers_lock = self.write();
+        if !registers_lock.uncommitted.quarantine(segment_id)
+            && !registers_lock.committed.quarantine(segment_id)
+        {
+            warn!("couldn't find segment in SegmentManager");
+        }
+    }
+
     pub fn write_segment(&self, segment_id: SegmentId) {
         let mut registers_lock = self.write();
         registers_lock.writing.insert(segment_id);
@@ -215,7 +225,11 @@ impl SegmentManager {
             }
         };
         for segment_id in before_merge_segment_ids {
-            target_register.remove_segment(segment_id);
+            // segments quarantined during the merge were not merged,
+            // and are kept as is.
+            if !target_register.is_quarantined(segment_id) {
+                target_register.remove_segment(segment_id);
+            }
         }
         target_register.add_segment_entry(after_merge_segment_entry);
     }
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use core::SegmentMeta;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
-use indexer::segment_entry::SegmentEntry;
+use indexer::segment_entry::{SegmentEntry, SegmentState};
 use indexer::delete_queue::DeleteCursor;
 
 /// The segment register keeps track
@@ -93,6 +93,22 @@ impl SegmentRegister {
             .cancel_merge();
     }
 
+    pub fn quarantine(&mut self, segment_id: &SegmentId) -> bool {
+        if let Some(segment_entry) = self.segment_states.get_mut(segment_id) {
+            segment_entry.quarantine();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn is_quarantined(&self, segment_id: &SegmentId) -> bool {
+        self.segment_states
+            .get(segment_id)
+            .map(|segment_entry| segment_entry.state() == SegmentState::Quarantined)
+            .unwrap_or(false)
+    }
+
     pub fn start_merge(&mut self, segment_id: &SegmentId) {
         self.segment_states
             .get_mut(segment_id)
@@ -18,6 +18,7 @@ pub struct SegmentSerializer {
 impl SegmentSerializer {
     /// Creates a new `SegmentSerializer`.
     pub fn for_segment(segment: &mut Segment) -> Result<SegmentSerializer> {
+        let store_compressor = segment.index().settings().store_compressor;
         let store_write = segment.open_write(SegmentComponent::STORE)?;
 
         let fast_field_write = segment.open_write(SegmentComponent::FASTFIELDS)?;
@@ -29,7 +30,7 @@ impl SegmentSerializer {
         let postings_serializer = InvertedIndexSerializer::open(segment)?;
         Ok(SegmentSerializer {
             postings_serializer,
-            store_writer: StoreWriter::new(store_write),
+            store_writer: StoreWriter::new(store_write, store_compressor),
             fast_field_serializer,
             fieldnorms_serializer,
         })
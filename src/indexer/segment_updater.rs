@@ -1,10 +1,13 @@
 use core::Index;
-use core::IndexMeta;
+use core::{CommitChanges, IndexMeta, IndexSettings, MAX_COMMIT_HISTORY};
+use core::{segment_features, DOC_ID_MAPPING_FEATURE};
 use core::META_FILEPATH;
 use core::Segment;
 use core::SegmentId;
 use core::SegmentMeta;
+use core::SegmentComponent;
 use core::SerializableSegment;
+use common::Span;
 use directory::Directory;
 use indexer::stamper::Stamper;
 use error::{Error, ErrorKind, Result};
@@ -13,10 +16,10 @@ use futures::Future;
 use futures::Canceled;
 use futures::oneshot;
 use directory::FileProtection;
-use indexer::{DefaultMergePolicy, MergePolicy};
+use indexer::{DefaultMergePolicy, MergeDecision, MergeListener, MergePolicy};
 use indexer::index_writer::advance_deletes;
 use indexer::MergeCandidate;
-use indexer::merger::IndexMerger;
+use indexer::merger::{validate_segment, IndexMerger};
 use indexer::SegmentEntry;
 use indexer::SegmentSerializer;
 use futures_cpupool::CpuFuture;
@@ -34,6 +37,7 @@ use std::sync::atomic::Ordering;
 use std::sync::RwLock;
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use super::segment_manager::{get_mergeable_segments, SegmentManager};
 
 /// Save the index meta file.
@@ -45,8 +49,13 @@ use super::segment_manager::{get_mergeable_segments, SegmentManager};
 /// and flushed.
 ///
 /// This method is not part of tantivy's public API
-pub fn save_new_metas(schema: Schema, opstamp: u64, directory: &mut Directory) -> Result<()> {
-    save_metas(vec![], schema, opstamp, None, directory)
+pub fn save_new_metas(
+    schema: Schema,
+    settings: IndexSettings,
+    opstamp: u64,
+    directory: &mut Directory,
+) -> Result<()> {
+    save_metas(vec![], schema, settings, opstamp, None, vec![], directory)
 }
 
 /// Save the index meta file.
@@ -61,15 +70,19 @@ pub fn save_new_metas(schema: Schema, opstamp: u64, directory: &mut Directory) -
 pub fn save_metas(
     segment_metas: Vec<SegmentMeta>,
     schema: Schema,
+    settings: IndexSettings,
     opstamp: u64,
     payload: Option<String>,
+    commits: Vec<CommitChanges>,
     directory: &mut Directory,
 ) -> Result<()> {
     let metas = IndexMeta {
         segments: segment_metas,
         schema,
+        settings,
         opstamp,
         payload,
+        commits,
     };
     let mut buffer = serde_json::to_vec_pretty(&metas)?;
     write!(&mut buffer, "\n")?;
@@ -94,6 +107,8 @@ fn perform_merge(
 ) -> Result<SegmentEntry> {
     // first we need to apply deletes to our segment.
     info!("Start merge: {:?}", segment_ids);
+    let start = Instant::now();
+    let mut span = Span::enter("merge", || format!("segments={:?}", segment_ids));
 
     let index = &segment_updater.0.index;
     let schema = index.schema();
@@ -101,10 +116,24 @@ fn perform_merge(
 
     let mut file_protections: Vec<FileProtection> = vec![];
 
+    let quarantine_corrupt_segments = segment_updater
+        .0
+        .quarantine_corrupt_segments
+        .load(Ordering::SeqCst);
+
     for segment_id in segment_ids {
         if let Some(mut segment_entry) = segment_updater.0.segment_manager.segment_entry(segment_id)
         {
             let segment = index.segment(segment_entry.meta().clone());
+            if quarantine_corrupt_segments && validate_segment(&segment).is_err() {
+                error!(
+                    "Quarantining segment {:?}: its documents 0..{} are left out of the merge.",
+                    segment_id,
+                    segment.meta().max_doc()
+                );
+                segment_updater.0.segment_manager.quarantine_segment(segment_id);
+                continue;
+            }
             if let Some(file_protection) =
                 advance_deletes(segment, &mut segment_entry, target_opstamp)?
             {
@@ -121,6 +150,11 @@ fn perform_merge(
         }
     }
 
+    if segment_entries.is_empty() {
+        let msg = format!("All of the segments {:?} are corrupted.", segment_ids);
+        bail!(ErrorKind::InvalidArgument(msg));
+    }
+
     let delete_cursor = segment_entries[0].delete_cursor().clone();
 
     let segments: Vec<Segment> = segment_entries
@@ -140,8 +174,24 @@ fn perform_merge(
     let num_docs = merger
         .write(segment_serializer)
         .expect("Serializing merged index failed");
+    let doc_id_mapping_enabled = segment_updater.0.doc_id_mapping_enabled.load(Ordering::SeqCst);
+    if doc_id_mapping_enabled {
+        let mut doc_id_mapping_write = merged_segment.open_write(SegmentComponent::DOCMAP)?;
+        merger.write_doc_id_mapping(&mut doc_id_mapping_write)?;
+        doc_id_mapping_write.flush()?;
+    }
+    span.record("num_docs", num_docs);
+    index.metrics().record_merge(num_docs, start.elapsed());
     let mut segment_meta = SegmentMeta::new(merged_segment.id());
     segment_meta.set_max_doc(num_docs);
+    for feature in segment_features(index) {
+        segment_meta.add_feature(&feature);
+    }
+    if doc_id_mapping_enabled {
+        segment_meta.add_feature(DOC_ID_MAPPING_FEATURE);
+    }
+    segment_meta.merge_fast_field_bounds(segments.iter().map(|segment| segment.meta()));
+    segment_meta.merge_single_term_fields(segments.iter().map(|segment| segment.meta()));
 
     let after_merge_segment_entry = SegmentEntry::new(segment_meta.clone(), delete_cursor, None);
     Ok(after_merge_segment_entry)
@@ -152,10 +202,13 @@ struct InnerSegmentUpdater {
     index: Index,
     segment_manager: SegmentManager,
     merge_policy: RwLock<Box<MergePolicy>>,
+    merge_listener: RwLock<Option<Arc<MergeListener>>>,
     merging_thread_id: AtomicUsize,
     merging_threads: RwLock<HashMap<usize, JoinHandle<Result<()>>>>,
     generation: AtomicUsize,
     killed: AtomicBool,
+    doc_id_mapping_enabled: AtomicBool,
+    quarantine_corrupt_segments: AtomicBool,
     stamper: Stamper,
 }
 
@@ -172,10 +225,13 @@ impl SegmentUpdater {
             index,
             segment_manager,
             merge_policy: RwLock::new(box DefaultMergePolicy::default()),
+            merge_listener: RwLock::new(None),
             merging_thread_id: AtomicUsize::default(),
             merging_threads: RwLock::new(HashMap::new()),
             generation: AtomicUsize::default(),
             killed: AtomicBool::new(false),
+            doc_id_mapping_enabled: AtomicBool::new(false),
+            quarantine_corrupt_segments: AtomicBool::new(false),
             stamper,
         })))
     }
@@ -195,6 +251,26 @@ impl SegmentUpdater {
         *self.0.merge_policy.write().unwrap() = merge_policy;
     }
 
+    pub fn set_merge_listener(&self, merge_listener: Box<MergeListener>) {
+        *self.0.merge_listener.write().unwrap() = Some(Arc::from(merge_listener));
+    }
+
+    fn get_merge_listener(&self) -> Option<Arc<MergeListener>> {
+        self.0.merge_listener.read().unwrap().clone()
+    }
+
+    pub fn set_doc_id_mapping(&self, enabled: bool) {
+        self.0
+            .doc_id_mapping_enabled
+            .store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn set_quarantine_corrupt_segments(&self, enabled: bool) {
+        self.0
+            .quarantine_corrupt_segments
+            .store(enabled, Ordering::SeqCst);
+    }
+
     fn get_merging_thread_id(&self) -> usize {
         self.0.merging_thread_id.fetch_add(1, Ordering::SeqCst)
     }
@@ -241,15 +317,22 @@ impl SegmentUpdater {
         Ok(segment_entries)
     }
 
-    pub fn save_metas(&self, opstamp: u64, commit_message: Option<String>) {
+    pub fn save_metas(
+        &self,
+        opstamp: u64,
+        commit_message: Option<String>,
+        commits: Vec<CommitChanges>,
+    ) {
         if self.is_alive() {
             let index = &self.0.index;
             let directory = index.directory();
             save_metas(
                 self.0.segment_manager.committed_segment_metas(),
                 index.schema(),
+                index.settings().clone(),
                 opstamp,
                 commit_message,
+                commits,
                 directory.box_clone().borrow_mut(),
             ).expect("Could not save metas.");
         }
@@ -269,14 +352,26 @@ impl SegmentUpdater {
             .garbage_collect(|| self.0.segment_manager.list_files());
     }
 
-    pub fn commit(&self, opstamp: u64, payload: Option<String>) -> Result<()> {
+    pub fn commit(
+        &self,
+        opstamp: u64,
+        payload: Option<String>,
+        changes: CommitChanges,
+    ) -> Result<()> {
         self.run_async(move |segment_updater| {
             if segment_updater.is_alive() {
                 let segment_entries = segment_updater
                     .purge_deletes(opstamp)
                     .expect("Failed purge deletes");
                 segment_updater.0.segment_manager.commit(segment_entries);
-                segment_updater.save_metas(opstamp, payload);
+                let mut commits = segment_updater.0.index.load_metas().unwrap().commits;
+                commits.push(changes);
+                if commits.len() > MAX_COMMIT_HISTORY {
+                    let num_expired = commits.len() - MAX_COMMIT_HISTORY;
+                    commits.drain(..num_expired);
+                }
+                segment_updater.save_metas(opstamp, payload, commits);
+                segment_updater.0.index.metrics().record_commit();
                 segment_updater.garbage_collect_files_exec();
                 segment_updater.consider_merge_options();
             }
@@ -286,6 +381,15 @@ impl SegmentUpdater {
     pub fn start_merge(
         &self,
         segment_ids: &[SegmentId],
+    ) -> impl Future<Item = SegmentMeta, Error = Canceled> {
+        self.start_merge_after(segment_ids, None)
+    }
+
+    /// Starts a merge, whose thread waits for `delay_opt` before merging.
+    fn start_merge_after(
+        &self,
+        segment_ids: &[SegmentId],
+        delay_opt: Option<Duration>,
     ) -> impl Future<Item = SegmentMeta, Error = Canceled> {
         self.0.segment_manager.start_merge(segment_ids);
         let segment_updater_clone = self.clone();
@@ -301,6 +405,9 @@ impl SegmentUpdater {
 
         let target_opstamp = self.0.stamper.stamp();
         let merging_join_handle = thread::spawn(move || {
+            if let Some(delay) = delay_opt {
+                thread::sleep(delay);
+            }
             // first we need to apply deletes to our segment.
             let merged_segment = segment_updater_clone.new_segment();
             let merged_segment_id = merged_segment.id();
@@ -360,8 +467,35 @@ impl SegmentUpdater {
         let mut merge_candidates = merge_policy.compute_merge_candidates(&uncommitted_segments);
         let committed_merge_candidates = merge_policy.compute_merge_candidates(&committed_segments);
         merge_candidates.extend_from_slice(&committed_merge_candidates[..]);
-        for MergeCandidate(segment_metas) in merge_candidates {
-            self.start_merge(&segment_metas);
+        let merge_listener_opt = self.get_merge_listener();
+        for MergeCandidate(segment_ids) in merge_candidates {
+            let decision = match merge_listener_opt {
+                Some(ref merge_listener) => {
+                    let segment_metas: Vec<SegmentMeta> = segment_ids
+                        .iter()
+                        .filter_map(|segment_id| {
+                            committed_segments
+                                .iter()
+                                .chain(uncommitted_segments.iter())
+                                .find(|segment_meta| segment_meta.id() == *segment_id)
+                                .cloned()
+                        })
+                        .collect();
+                    merge_listener.before_merge(&segment_metas)
+                }
+                None => MergeDecision::Proceed,
+            };
+            match decision {
+                MergeDecision::Proceed => {
+                    self.start_merge(&segment_ids);
+                }
+                MergeDecision::Veto => {
+                    info!("Merge of {:?} was vetoed", segment_ids);
+                }
+                MergeDecision::Delay(delay) => {
+                    self.start_merge_after(&segment_ids, Some(delay));
+                }
+            }
         }
     }
 
@@ -420,14 +554,22 @@ impl SegmentUpdater {
                     }
                 }
             }
+            let merged_segment_meta = after_merge_segment_entry.meta().clone();
             segment_updater
                 .0
                 .segment_manager
                 .end_merge(&before_merge_segment_ids, after_merge_segment_entry);
+            if let Some(merge_listener) = segment_updater.get_merge_listener() {
+                merge_listener.after_merge(&before_merge_segment_ids, &merged_segment_meta);
+            }
             segment_updater.consider_merge_options();
             info!("save metas");
             let previous_metas = segment_updater.0.index.load_metas().unwrap();
-            segment_updater.save_metas(previous_metas.opstamp, previous_metas.payload);
+            segment_updater.save_metas(
+                previous_metas.opstamp,
+                previous_metas.payload,
+                previous_metas.commits,
+            );
             segment_updater.garbage_collect_files_exec();
         }).wait()
     }
@@ -482,7 +624,38 @@ mod tests {
 
     use Index;
     use schema::*;
+    use core::SegmentComponent;
+    use core::SegmentId;
+    use directory::Directory;
+    use futures::Future;
+    use indexer::NoMergePolicy;
     use indexer::merge_policy::tests::MergeWheneverPossible;
+    use indexer::{MergeDecision, MergeListener};
+    use core::SegmentMeta;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    struct TestMergeListener {
+        decision: MergeDecision,
+        num_candidates: Arc<AtomicUsize>,
+        merges: Arc<Mutex<Vec<(usize, u32)>>>,
+    }
+
+    impl MergeListener for TestMergeListener {
+        fn before_merge(&self, segment_metas: &[SegmentMeta]) -> MergeDecision {
+            assert!(segment_metas.iter().all(|segment_meta| segment_meta.max_doc() == 1));
+            self.num_candidates.fetch_add(1, Ordering::SeqCst);
+            self.decision
+        }
+
+        fn after_merge(&self, merged_segment_ids: &[SegmentId], segment_meta: &SegmentMeta) {
+            self.merges
+                .lock()
+                .unwrap()
+                .push((merged_segment_ids.len(), segment_meta.max_doc()));
+        }
+    }
 
     #[test]
     fn test_delete_during_merge() {
@@ -537,4 +710,81 @@ mod tests {
         assert_eq!(index.searcher().segment_readers().len(), 1);
         assert_eq!(index.searcher().num_docs(), 302);
     }
+
+    #[test]
+    fn test_merge_listener() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let num_candidates = Arc::new(AtomicUsize::default());
+        let merges = Arc::new(Mutex::new(vec![]));
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.set_merge_policy(box MergeWheneverPossible);
+            index_writer.set_merge_listener(box TestMergeListener {
+                decision: MergeDecision::Veto,
+                num_candidates: num_candidates.clone(),
+                merges: merges.clone(),
+            });
+            for text in &["a", "b", "c"] {
+                index_writer.add_document(doc!(text_field => *text));
+                assert!(index_writer.commit().is_ok());
+            }
+            index_writer.wait_merging_threads().unwrap();
+        }
+        assert!(num_candidates.load(Ordering::SeqCst) > 0);
+        assert!(merges.lock().unwrap().is_empty());
+        assert_eq!(index.searchable_segment_ids().unwrap().len(), 3);
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.set_merge_policy(box MergeWheneverPossible);
+            index_writer.set_merge_listener(box TestMergeListener {
+                decision: MergeDecision::Delay(Duration::from_millis(10)),
+                num_candidates: num_candidates.clone(),
+                merges: merges.clone(),
+            });
+            index_writer.add_document(doc!(text_field => "d"));
+            assert!(index_writer.commit().is_ok());
+            index_writer.wait_merging_threads().unwrap();
+        }
+        assert_eq!(*merges.lock().unwrap(), vec![(4, 4)]);
+        assert_eq!(index.searchable_segment_ids().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_merge_quarantines_corrupt_segment() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let mut index = Index::create_in_ram(schema_builder.build());
+
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.set_merge_policy(box NoMergePolicy::default());
+        index_writer.set_quarantine_corrupt_segments(true);
+        for text in &["a", "b", "c"] {
+            index_writer.add_document(doc!(text_field => *text));
+            assert!(index_writer.commit().is_ok());
+        }
+
+        let segment_metas = index.searchable_segment_metas().unwrap();
+        let segment_ids: Vec<SegmentId> = segment_metas.iter().map(|meta| meta.id()).collect();
+        let corrupt_segment_id = segment_ids[0];
+        let postings_path = segment_metas[0].relative_path(SegmentComponent::POSTINGS);
+        index
+            .directory_mut()
+            .atomic_write(&postings_path, &[])
+            .unwrap();
+
+        let merged_segment_meta = index_writer
+            .merge(&segment_ids)
+            .wait()
+            .expect("Merging failed");
+        index_writer.wait_merging_threads().unwrap();
+        assert_eq!(merged_segment_meta.max_doc(), 2);
+
+        let mut expected_segment_ids = vec![corrupt_segment_id, merged_segment_meta.id()];
+        expected_segment_ids.sort();
+        let mut searchable_segment_ids = index.searchable_segment_ids().unwrap();
+        searchable_segment_ids.sort();
+        assert_eq!(searchable_segment_ids, expected_segment_ids);
+    }
 }
@@ -17,8 +17,9 @@ use super::operation::AddOperation;
 use postings::MultiFieldPostingsWriter;
 use tokenizer::BoxedTokenizer;
 use tokenizer::FacetTokenizer;
-use tokenizer::{TokenStream, Tokenizer};
+use tokenizer::{PreTokenizedStream, TokenStream, TokenStreamChain, Tokenizer};
 use schema::Value;
+use schema::SourceOptions;
 
 /// A `SegmentWriter` is in charge of creating segment index from a
 /// documents.
@@ -34,6 +35,8 @@ pub struct SegmentWriter<'a> {
     fieldnorms_writer: FastFieldsWriter,
     doc_opstamps: Vec<u64>,
     tokenizers: Vec<Option<Box<BoxedTokenizer>>>,
+    source: Option<(Field, SourceOptions)>,
+    single_term_fields: Vec<Field>,
 }
 
 fn create_fieldnorms_writer(schema: &Schema) -> FastFieldsWriter {
@@ -47,6 +50,22 @@ fn create_fieldnorms_writer(schema: &Schema) -> FastFieldsWriter {
     FastFieldsWriter::new(u64_fields)
 }
 
+/// Returns the indexed `u64` and `i64` fields of the schema.
+fn indexed_int_fields(schema: &Schema) -> Vec<Field> {
+    schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|&(_, field_entry)| match *field_entry.field_type() {
+            FieldType::U64(ref int_options) | FieldType::I64(ref int_options) => {
+                int_options.is_indexed()
+            }
+            _ => false,
+        })
+        .map(|(field_id, _)| Field(field_id as u32))
+        .collect()
+}
+
 impl<'a> SegmentWriter<'a> {
     /// Creates a new `SegmentWriter`
     ///
@@ -79,6 +98,10 @@ impl<'a> SegmentWriter<'a> {
                 _ => None,
             })
             .collect();
+        let source = segment.index().settings().source.clone().and_then(|source_options| {
+            let source_field = source_options.resolve_field(schema)?;
+            Some((source_field, source_options))
+        });
         Ok(SegmentWriter {
             heap,
             max_doc: 0,
@@ -88,6 +111,8 @@ impl<'a> SegmentWriter<'a> {
             fast_field_writers: FastFieldsWriter::from_schema(schema),
             doc_opstamps: Vec::with_capacity(1_000),
             tokenizers,
+            source,
+            single_term_fields: indexed_int_fields(schema),
         })
     }
 
@@ -105,6 +130,12 @@ impl<'a> SegmentWriter<'a> {
         Ok(self.doc_opstamps)
     }
 
+    /// Returns the indexed integer fields for which no document
+    /// of the segment holds more than one value.
+    pub fn single_term_fields(&self) -> &[Field] {
+        &self.single_term_fields
+    }
+
     /// Returns true iff the segment writer's buffer has reached capacity.
     ///
     /// The limit is defined as `the user defined heap size - an arbitrary margin of 10MB`
@@ -131,6 +162,16 @@ impl<'a> SegmentWriter<'a> {
         let mut doc = add_operation.document;
         self.doc_opstamps.push(add_operation.opstamp);
 
+        if let Some((source_field, ref source_options)) = self.source {
+            doc.filter_fields(|field| field != source_field);
+            let mut source_doc = doc.clone();
+            source_doc.filter_fields(|field| {
+                source_options.is_included(schema.get_field_name(field))
+            });
+            let source_json = source_doc.to_named_json(schema);
+            doc.add_text(source_field, &source_json);
+        }
+
         self.fast_field_writers.add_document(&doc);
 
         for (field, field_values) in doc.get_sorted_field_values() {
@@ -172,17 +213,29 @@ impl<'a> SegmentWriter<'a> {
                     let num_tokens = if let Some(ref mut tokenizer) =
                         self.tokenizers[field.0 as usize]
                     {
-                        let texts: Vec<&str> = field_values
-                            .iter()
-                            .flat_map(|field_value| match *field_value.value() {
-                                Value::Str(ref text) => Some(text.as_str()),
-                                _ => None,
-                            })
-                            .collect();
-                        if texts.is_empty() {
+                        // The pre-tokenized values bypass the tokenizer of the field.
+                        let mut offsets = vec![];
+                        let mut token_streams = vec![];
+                        let mut total_offset = 0;
+                        for field_value in &field_values {
+                            match *field_value.value() {
+                                Value::Str(ref text) => {
+                                    offsets.push(total_offset);
+                                    total_offset += text.len();
+                                    token_streams.push(tokenizer.token_stream(text));
+                                }
+                                Value::PreTokStr(ref pre_tokenized) => {
+                                    offsets.push(total_offset);
+                                    total_offset += pre_tokenized.text.len();
+                                    token_streams.push(box PreTokenizedStream::from(pre_tokenized));
+                                }
+                                _ => {}
+                            }
+                        }
+                        if token_streams.is_empty() {
                             0
                         } else {
-                            let mut token_stream = tokenizer.token_stream_texts(&texts[..]);
+                            let mut token_stream = TokenStreamChain::new(offsets, token_streams);
                             self.multifield_postings
                                 .index_text(doc_id, field, &mut token_stream)
                         }
@@ -197,6 +250,10 @@ impl<'a> SegmentWriter<'a> {
                 }
                 FieldType::U64(ref int_option) => {
                     if int_option.is_indexed() {
+                        if field_values.len() > 1 {
+                            self.single_term_fields
+                                .retain(|&single_term_field| single_term_field != field);
+                        }
                         for field_value in field_values {
                             let term = Term::from_field_u64(
                                 field_value.field(),
@@ -208,6 +265,10 @@ impl<'a> SegmentWriter<'a> {
                 }
                 FieldType::I64(ref int_option) => {
                     if int_option.is_indexed() {
+                        if field_values.len() > 1 {
+                            self.single_term_fields
+                                .retain(|&single_term_field| single_term_field != field);
+                        }
                         for field_value in field_values {
                             let term = Term::from_field_i64(
                                 field_value.field(),
@@ -217,6 +278,17 @@ impl<'a> SegmentWriter<'a> {
                         }
                     }
                 }
+                FieldType::Vector(_) => {}
+                FieldType::Bytes(ref bytes_options) => {
+                    if bytes_options.is_indexed() {
+                        for field_value in field_values {
+                            if let Value::Bytes(ref bytes) = *field_value.value() {
+                                let term = Term::from_field_bytes(field_value.field(), bytes);
+                                self.multifield_postings.subscribe(doc_id, &term);
+                            }
+                        }
+                    }
+                }
             }
         }
         self.fieldnorms_writer.fill_val_up_to(doc_id);
@@ -255,7 +327,8 @@ fn write(
     fieldnorms_writer: &FastFieldsWriter,
     mut serializer: SegmentSerializer,
 ) -> Result<()> {
-    let term_ord_map = multifield_postings.serialize(serializer.get_postings_serializer())?;
+    let term_ord_map =
+        multifield_postings.serialize(serializer.get_postings_serializer(), fieldnorms_writer)?;
     fast_field_writers.serialize(serializer.get_fast_field_serializer(), &term_ord_map)?;
     fieldnorms_writer.serialize(serializer.get_fieldnorms_serializer(), &HashMap::new())?;
     serializer.close()?;
@@ -143,6 +143,7 @@ extern crate lz4;
 extern crate num_cpus;
 extern crate owning_ref;
 extern crate regex;
+extern crate regex_syntax;
 extern crate rust_stemmers;
 extern crate serde;
 extern crate serde_json;
@@ -209,10 +210,21 @@ mod docset;
 pub use self::docset::{DocSet, SkipResult};
 
 pub use directory::Directory;
-pub use core::{Index, Searcher, Segment, SegmentId, SegmentMeta};
+pub use core::{Index, IndexMetrics, IndexReader, MemoryBudget, MetricsSnapshot, ReloadPolicy,
+               Searcher, Segment, SegmentId, SegmentMeta};
+pub use core::IndexSettings;
+pub use core::CompatibilityReport;
+pub use core::{CommitChanges, IndexChanges};
+pub use core::{FieldValues, FieldValuesBatch};
+pub use core::SearchStream;
+pub use core::{Autocomplete, Completion};
+pub use core::Warmer;
+pub use core::IndexSnapshot;
+pub use core::{RolloverIndex, RolloverPolicy};
 pub use indexer::IndexWriter;
+pub use indexer::{FastFieldValueProvider, StoredFieldValues};
 pub use schema::{Document, Term};
-pub use core::{InvertedIndexReader, SegmentReader};
+pub use core::{InvertedIndexReader, SegmentFieldInfo, SegmentReader, MAX_PREFIX_TERMS};
 pub use self::common::TimerTree;
 
 pub use postings::Postings;
@@ -236,6 +248,7 @@ pub mod merge_policy {
     pub use indexer::LogMergePolicy;
     pub use indexer::NoMergePolicy;
     pub use indexer::DefaultMergePolicy;
+    pub use indexer::{MergeDecision, MergeListener};
 }
 
 /// A `u32` identifying a document within a segment.
@@ -248,8 +261,21 @@ pub type DocId = u32;
 /// This is modelled internally as a `f32`. The
 /// larger the number, the more relevant the document
 /// to the search
+#[cfg(not(feature = "f64score"))]
 pub type Score = f32;
 
+/// A f64 that represents the relevance of the document to the query
+///
+/// Enabling the `f64score` feature makes tantivy compute scores
+/// as `f64`. Long disjunctions accumulate less rounding errors, which makes
+/// the ordering of documents with close scores more reproducible, at the
+/// cost of a slightly slower scoring.
+///
+/// The larger the number, the more relevant the document
+/// to the search
+#[cfg(feature = "f64score")]
+pub type Score = f64;
+
 /// A `SegmentLocalId` identifies a segment.
 /// It only makes sense for a given searcher.
 pub type SegmentLocalId = u32;
@@ -381,13 +407,13 @@ mod tests {
             index.load_searchers().unwrap();
             let searcher = index.searcher();
             let term_a = Term::from_field_text(text_field, "a");
-            assert_eq!(searcher.doc_freq(&term_a), 3);
+            assert_eq!(searcher.doc_freq(&term_a).unwrap(), 3);
             let term_b = Term::from_field_text(text_field, "b");
-            assert_eq!(searcher.doc_freq(&term_b), 1);
+            assert_eq!(searcher.doc_freq(&term_b).unwrap(), 1);
             let term_c = Term::from_field_text(text_field, "c");
-            assert_eq!(searcher.doc_freq(&term_c), 2);
+            assert_eq!(searcher.doc_freq(&term_c).unwrap(), 2);
             let term_d = Term::from_field_text(text_field, "d");
-            assert_eq!(searcher.doc_freq(&term_d), 0);
+            assert_eq!(searcher.doc_freq(&term_d).unwrap(), 0);
         }
     }
 
@@ -478,15 +504,17 @@ mod tests {
             index.load_searchers().unwrap();
             let searcher = index.searcher();
             let reader = searcher.segment_reader(0);
-            let inverted_index = reader.inverted_index(text_field);
+            let inverted_index = reader.inverted_index(text_field).unwrap();
             assert!(
                 inverted_index
                     .read_postings(&term_abcd, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .is_none()
             );
             {
                 let mut postings = inverted_index
                     .read_postings(&term_a, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(postings.advance());
                 assert_eq!(postings.doc(), 5);
@@ -495,6 +523,7 @@ mod tests {
             {
                 let mut postings = inverted_index
                     .read_postings(&term_b, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(postings.advance());
                 assert_eq!(postings.doc(), 3);
@@ -521,16 +550,18 @@ mod tests {
             index.load_searchers().unwrap();
             let searcher = index.searcher();
             let reader = searcher.segment_reader(0);
-            let inverted_index = reader.inverted_index(term_abcd.field());
+            let inverted_index = reader.inverted_index(term_abcd.field()).unwrap();
 
             assert!(
                 inverted_index
                     .read_postings(&term_abcd, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .is_none()
             );
             {
                 let mut postings = inverted_index
                     .read_postings(&term_a, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(postings.advance());
                 assert_eq!(postings.doc(), 5);
@@ -539,6 +570,7 @@ mod tests {
             {
                 let mut postings = inverted_index
                     .read_postings(&term_b, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(postings.advance());
                 assert_eq!(postings.doc(), 3);
@@ -565,21 +597,24 @@ mod tests {
             index.load_searchers().unwrap();
             let searcher = index.searcher();
             let reader = searcher.segment_reader(0);
-            let inverted_index = reader.inverted_index(term_abcd.field());
+            let inverted_index = reader.inverted_index(term_abcd.field()).unwrap();
             assert!(
                 inverted_index
                     .read_postings(&term_abcd, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .is_none()
             );
             {
                 let mut postings = inverted_index
                     .read_postings(&term_a, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(!postings.advance());
             }
             {
                 let mut postings = inverted_index
                     .read_postings(&term_b, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(postings.advance());
                 assert_eq!(postings.doc(), 3);
@@ -590,6 +625,7 @@ mod tests {
             {
                 let mut postings = inverted_index
                     .read_postings(&term_c, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(postings.advance());
                 assert_eq!(postings.doc(), 4);
@@ -614,7 +650,9 @@ mod tests {
         let mut postings = searcher
             .segment_reader(0)
             .inverted_index(term.field())
+            .unwrap()
             .read_postings(&term, IndexRecordOption::Basic)
+            .unwrap()
             .unwrap();
         assert!(postings.advance());
         assert_eq!(postings.doc(), 0);
@@ -638,7 +676,9 @@ mod tests {
         let mut postings = searcher
             .segment_reader(0)
             .inverted_index(term.field())
+            .unwrap()
             .read_postings(&term, IndexRecordOption::Basic)
+            .unwrap()
             .unwrap();
         assert!(postings.advance());
         assert_eq!(postings.doc(), 0);
@@ -700,16 +740,18 @@ mod tests {
             index.load_searchers().unwrap();
             let searcher = index.searcher();
             let reader = searcher.segment_reader(0);
-            let inverted_index = reader.inverted_index(text_field);
+            let inverted_index = reader.inverted_index(text_field).unwrap();
             let term_abcd = Term::from_field_text(text_field, "abcd");
             assert!(
                 inverted_index
                     .read_postings(&term_abcd, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .is_none()
             );
             let term_af = Term::from_field_text(text_field, "af");
             let mut postings = inverted_index
                 .read_postings(&term_af, IndexRecordOption::WithFreqsAndPositions)
+                .unwrap()
                 .unwrap();
             assert!(postings.advance());
             assert_eq!(postings.doc(), 0);
@@ -718,6 +760,68 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_pre_tokenized_text() {
+        use tokenizer::{PreTokenizedString, Token};
+        use DocAddress;
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let pre_tokenized = PreTokenizedString {
+            text: String::from("New York City"),
+            tokens: vec![
+                Token {
+                    offset_from: 0,
+                    offset_to: 8,
+                    position: 0,
+                    text: String::from("new york"),
+                },
+                Token {
+                    offset_from: 9,
+                    offset_to: 13,
+                    position: 1,
+                    text: String::from("city"),
+                },
+            ],
+        };
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            let mut doc = Document::default();
+            doc.add_pre_tokenized_text(text_field, pre_tokenized.clone());
+            index_writer.add_document(doc);
+            index_writer.add_document(doc!(text_field=>"New York City"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let inverted_index = searcher.segment_reader(0).inverted_index(text_field).unwrap();
+        let read_postings = |text: &str| {
+            let term = Term::from_field_text(text_field, text);
+            inverted_index
+                .read_postings(&term, IndexRecordOption::WithFreqsAndPositions)
+                .unwrap()
+        };
+        let mut postings = read_postings("new york").unwrap();
+        assert!(postings.advance());
+        assert_eq!(postings.doc(), 0);
+        assert!(!postings.advance());
+        let mut postings = read_postings("city").unwrap();
+        assert!(postings.advance());
+        assert_eq!(postings.doc(), 0);
+        assert_eq!(&[1], postings.positions());
+        assert!(postings.advance());
+        assert_eq!(postings.doc(), 1);
+        assert_eq!(&[2], postings.positions());
+        let mut postings = read_postings("new").unwrap();
+        assert!(postings.advance());
+        assert_eq!(postings.doc(), 1);
+        assert!(!postings.advance());
+
+        let doc = searcher.doc(&DocAddress(0, 0)).unwrap();
+        assert_eq!(doc.get_first(text_field), Some(&Value::PreTokStr(pre_tokenized)));
+        assert_eq!(doc.get_first(text_field).unwrap().text(), "New York City");
+    }
+
     #[test]
     fn test_searcher_1() {
         let mut schema_builder = SchemaBuilder::default();
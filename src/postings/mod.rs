@@ -13,8 +13,12 @@ mod serializer;
 mod postings_writer;
 mod term_info;
 mod segment_postings;
+mod position_reader;
+mod postings_block_size;
+mod postings_cursor;
 
-use self::recorder::{NothingRecorder, Recorder, TFAndPositionRecorder, TermFrequencyRecorder};
+use self::recorder::{NothingRecorder, PositionRecorder, Recorder, TFAndPositionRecorder,
+                     TFPositionAndOffsetRecorder, TermFrequencyRecorder};
 pub use self::serializer::{FieldSerializer, InvertedIndexSerializer};
 pub(crate) use self::postings_writer::MultiFieldPostingsWriter;
 
@@ -22,12 +26,16 @@ pub use self::term_info::TermInfo;
 pub use self::postings::Postings;
 
 pub use self::segment_postings::{BlockSegmentPostings, SegmentPostings};
+pub(crate) use self::position_reader::PositionReader;
+pub use self::postings_block_size::PostingsBlockSize;
+pub use self::postings_cursor::{PositionInfo, PostingsCursor};
 
 pub use common::HasLen;
 
 pub(crate) type UnorderedTermId = u64;
 
 #[allow(enum_variant_names)]
+#[derive(PartialEq, Eq)]
 pub(crate) enum FreqReadingOption {
     NoFreq,
     SkipFreq,
@@ -57,6 +65,91 @@ pub mod tests {
     use indexer::operation::AddOperation;
     use tests;
     use rand::{Rng, SeedableRng, XorShiftRng};
+    use futures::Future;
+    use directory::{ManagedDirectory, RAMDirectory};
+    use IndexSettings;
+
+    #[test]
+    pub fn test_estimate_live() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0..16 {
+                let text = if i % 2 == 0 { "a even" } else { "a odd" };
+                index_writer.add_document(doc!(text_field => text));
+            }
+            index_writer.commit().unwrap();
+            index_writer.delete_term(Term::from_field_text(text_field, "odd"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        let postings = segment_reader
+            .inverted_index(text_field)
+            .unwrap()
+            .read_postings(&Term::from_field_text(text_field, "a"), IndexRecordOption::Basic)
+            .unwrap()
+            .unwrap();
+        assert_eq!(postings.size_hint(), 16);
+        assert_eq!(postings.estimate_live(), 8);
+    }
+
+    #[test]
+    pub fn test_postings_block_size() {
+        for &postings_block_size in &[
+            PostingsBlockSize::Docs64,
+            PostingsBlockSize::Docs128,
+            PostingsBlockSize::Docs256,
+        ] {
+            let mut schema_builder = SchemaBuilder::default();
+            let text_field = schema_builder.add_text_field("text", TEXT);
+            let directory = ManagedDirectory::new(RAMDirectory::create()).unwrap();
+            let settings = IndexSettings {
+                postings_block_size,
+                ..IndexSettings::default()
+            };
+            let index =
+                Index::from_directory_with_settings(directory, schema_builder.build(), settings)
+                    .unwrap();
+            {
+                let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+                for i in 0..1_000 {
+                    let text = if i % 3 == 0 { "a a b" } else { "b" };
+                    index_writer.add_document(doc!(text_field => text));
+                }
+                index_writer.commit().unwrap();
+            }
+            index.load_searchers().unwrap();
+            let searcher = index.searcher();
+            let inverted_index = searcher.segment_reader(0).inverted_index(text_field).unwrap();
+            assert_eq!(inverted_index.postings_block_size(), postings_block_size);
+            let term_a = Term::from_field_text(text_field, "a");
+            let mut postings = inverted_index
+                .read_postings(&term_a, IndexRecordOption::WithFreqsAndPositions)
+                .unwrap()
+                .unwrap();
+            for doc in (0..1_000).filter(|doc| doc % 3 == 0) {
+                assert!(postings.advance());
+                assert_eq!(postings.doc(), doc);
+                assert_eq!(postings.term_freq(), 2);
+                assert_eq!(postings.positions(), &[0, 1]);
+            }
+            assert!(!postings.advance());
+            let term_b = Term::from_field_text(text_field, "b");
+            let mut postings = inverted_index
+                .read_postings(&term_b, IndexRecordOption::WithFreqsAndPositions)
+                .unwrap()
+                .unwrap();
+            assert_eq!(postings.skip_next(700), SkipResult::Reached);
+            assert_eq!(postings.positions(), &[0]);
+            assert_eq!(postings.skip_next(999), SkipResult::Reached);
+            assert_eq!(postings.positions(), &[2]);
+            assert!(!postings.advance());
+        }
+    }
 
     #[test]
     pub fn test_position_write() {
@@ -67,7 +160,7 @@ pub mod tests {
         let mut segment = index.new_segment();
         let mut posting_serializer = InvertedIndexSerializer::open(&mut segment).unwrap();
         {
-            let mut field_serializer = posting_serializer.new_field(text_field).unwrap();
+            let mut field_serializer = posting_serializer.new_field(text_field, &[]).unwrap();
             field_serializer.new_term("abc".as_bytes()).unwrap();
             for doc_id in 0u32..120u32 {
                 let delta_positions = vec![1, 2, 3, 2];
@@ -102,10 +195,11 @@ pub mod tests {
             Term::from_field_text(title, "abc"),
             IndexRecordOption::WithFreqsAndPositions,
         );
-        let weight = query.specialized_weight(&*searcher, true);
+        let weight = query.specialized_weight(&*searcher, true).unwrap();
         {
             let mut scorer = weight
                 .specialized_scorer(searcher.segment_reader(0u32))
+                .unwrap()
                 .unwrap();
             scorer.advance();
             assert_eq!(&[0, 1, 2], scorer.postings().positions());
@@ -115,6 +209,7 @@ pub mod tests {
         {
             let mut scorer = weight
                 .specialized_scorer(searcher.segment_reader(0u32))
+                .unwrap()
                 .unwrap();
             scorer.advance();
             scorer.advance();
@@ -123,6 +218,7 @@ pub mod tests {
         {
             let mut scorer = weight
                 .specialized_scorer(searcher.segment_reader(0u32))
+                .unwrap()
                 .unwrap();
             assert_eq!(scorer.skip_next(1), SkipResult::Reached);
             assert_eq!(scorer.doc(), 1);
@@ -131,6 +227,7 @@ pub mod tests {
         {
             let mut scorer = weight
                 .specialized_scorer(searcher.segment_reader(0u32))
+                .unwrap()
                 .unwrap();
             assert_eq!(scorer.skip_next(1002), SkipResult::Reached);
             assert_eq!(scorer.doc(), 1002);
@@ -139,6 +236,7 @@ pub mod tests {
         {
             let mut scorer = weight
                 .specialized_scorer(searcher.segment_reader(0u32))
+                .unwrap()
                 .unwrap();
             assert_eq!(scorer.skip_next(100), SkipResult::Reached);
             assert_eq!(scorer.skip_next(1002), SkipResult::Reached);
@@ -147,6 +245,265 @@ pub mod tests {
         }
     }
 
+    #[test]
+    pub fn test_offsets() {
+        use schema::{TextFieldIndexing, TextOptions};
+        let mut schema_builder = SchemaBuilder::new();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_index_option(IndexRecordOption::WithFreqsPositionsAndOffsets),
+        );
+        let title = schema_builder.add_text_field("title", text_options);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 30_000_000).unwrap();
+            index_writer.add_document(doc!(title => r#"abc abc"#));
+            for _ in 0..300 {
+                index_writer.add_document(doc!(title => r#"abc de abc"#));
+            }
+            index_writer.commit().unwrap();
+            index_writer.add_document(doc!(title => r#"de  abc"#));
+            index_writer.commit().unwrap();
+        }
+        let check_offsets = |index: &Index| {
+            index.load_searchers().unwrap();
+            let searcher = index.searcher();
+            let term = Term::from_field_text(title, "abc");
+            let mut docs = vec![];
+            for segment_reader in searcher.segment_readers() {
+                let mut postings = segment_reader
+                    .inverted_index(title)
+                    .unwrap()
+                    .read_postings(&term, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
+                    .unwrap();
+                while postings.advance() {
+                    if postings.doc() % 100 == 0 || postings.doc() == 301 {
+                        docs.push((
+                            postings.positions().to_vec(),
+                            postings.offsets().to_vec(),
+                        ));
+                    }
+                }
+            }
+            docs.sort();
+            docs
+        };
+        let expected = vec![
+            (vec![0, 1], vec![(0, 3), (4, 7)]),
+            (vec![0, 2], vec![(0, 3), (7, 10)]),
+            (vec![0, 2], vec![(0, 3), (7, 10)]),
+            (vec![0, 2], vec![(0, 3), (7, 10)]),
+            (vec![1], vec![(4, 7)]),
+        ];
+        assert_eq!(check_offsets(&index), expected);
+        {
+            let segment_ids = index.searchable_segment_ids().unwrap();
+            let mut index_writer = index.writer_with_num_threads(1, 30_000_000).unwrap();
+            index_writer.merge(&segment_ids).wait().unwrap();
+            index_writer.wait_merging_threads().unwrap();
+        }
+        assert_eq!(check_offsets(&index), expected);
+    }
+
+    #[test]
+    pub fn test_postings_matches() {
+        use schema::{TextFieldIndexing, TextOptions};
+        let mut schema_builder = SchemaBuilder::new();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_index_option(IndexRecordOption::WithFreqsPositionsAndOffsets),
+        );
+        let title = schema_builder.add_text_field("title", text_options);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 30_000_000).unwrap();
+            index_writer.add_document(doc!(title => "abc de abc", body => "de abc"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let inverted_index = searcher.segment_reader(0).inverted_index(title).unwrap();
+        let term = Term::from_field_text(title, "abc");
+        let mut postings = inverted_index
+            .read_postings(&term, IndexRecordOption::WithFreqsAndPositions)
+            .unwrap()
+            .unwrap();
+        assert!(postings.advance());
+        assert_eq!(postings.matches().len(), 2);
+        assert_eq!(
+            postings.matches().collect::<Vec<PositionInfo>>(),
+            vec![
+                PositionInfo {
+                    pos: 0,
+                    offset: Some((0, 3)),
+                },
+                PositionInfo {
+                    pos: 2,
+                    offset: Some((7, 10)),
+                },
+            ]
+        );
+        let inverted_index = searcher.segment_reader(0).inverted_index(body).unwrap();
+        let term = Term::from_field_text(body, "abc");
+        let mut postings = inverted_index
+            .read_postings(&term, IndexRecordOption::WithFreqsAndPositions)
+            .unwrap()
+            .unwrap();
+        assert!(postings.advance());
+        assert_eq!(
+            postings.matches().collect::<Vec<PositionInfo>>(),
+            vec![PositionInfo {
+                pos: 1,
+                offset: None,
+            }]
+        );
+    }
+
+    #[test]
+    pub fn test_positions_only() {
+        use collector::CountCollector;
+        use query::PhraseQuery;
+        use schema::{TextFieldIndexing, TextOptions};
+        let mut schema_builder = SchemaBuilder::new();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default().set_index_option(IndexRecordOption::WithPositions),
+        );
+        let title = schema_builder.add_text_field("title", text_options);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 30_000_000).unwrap();
+            index_writer.add_document(doc!(title => "abc abc"));
+            for _ in 0..300 {
+                index_writer.add_document(doc!(title => "de abc abc"));
+            }
+            index_writer.commit().unwrap();
+            index_writer.add_document(doc!(title => "de abc"));
+            index_writer.commit().unwrap();
+        }
+        let check_positions = |index: &Index| {
+            index.load_searchers().unwrap();
+            let searcher = index.searcher();
+            let term = Term::from_field_text(title, "abc");
+            let mut docs = vec![];
+            for segment_reader in searcher.segment_readers() {
+                let mut postings = segment_reader
+                    .inverted_index(title)
+                    .unwrap()
+                    .read_postings(&term, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
+                    .unwrap();
+                for target in (0..segment_reader.max_doc()).filter(|doc| doc % 100 == 0) {
+                    assert_eq!(postings.skip_next(target), SkipResult::Reached);
+                    docs.push((postings.term_freq(), postings.positions().to_vec()));
+                }
+            }
+            docs.sort();
+            let mut phrase_count = CountCollector::default();
+            let phrase_query = PhraseQuery::from(vec![
+                Term::from_field_text(title, "de"),
+                Term::from_field_text(title, "abc"),
+            ]);
+            searcher.search(&phrase_query, &mut phrase_count).unwrap();
+            (docs, phrase_count.count())
+        };
+        let expected = (
+            vec![
+                (1, vec![0]),
+                (1, vec![1]),
+                (1, vec![1]),
+                (1, vec![1]),
+                (1, vec![1]),
+            ],
+            301,
+        );
+        assert_eq!(check_positions(&index), expected);
+        {
+            let segment_ids = index.searchable_segment_ids().unwrap();
+            let mut index_writer = index.writer_with_num_threads(1, 30_000_000).unwrap();
+            index_writer.merge(&segment_ids).wait().unwrap();
+            index_writer.wait_merging_threads().unwrap();
+        }
+        assert_eq!(check_positions(&index), expected);
+    }
+
+    #[test]
+    pub fn test_pulsing() {
+        use collector::CountCollector;
+        let mut schema_builder = SchemaBuilder::new();
+        let id = schema_builder.add_text_field("id", STRING);
+        let num = schema_builder.add_u64_field("num", INT_INDEXED);
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 30_000_000).unwrap();
+            for i in 0u64..300u64 {
+                index_writer.add_document(doc!(
+                    id => format!("id{}", i),
+                    num => i / 2,
+                    text => format!("t{}", i)
+                ));
+                if i == 200 {
+                    index_writer.commit().unwrap();
+                }
+            }
+            index_writer.commit().unwrap();
+        }
+        let check_postings = |index: &Index| {
+            index.load_searchers().unwrap();
+            let searcher = index.searcher();
+            let mut docs = vec![];
+            for segment_reader in searcher.segment_readers() {
+                let id_index = segment_reader.inverted_index(id).unwrap();
+                let num_index = segment_reader.inverted_index(num).unwrap();
+                let text_index = segment_reader.inverted_index(text).unwrap();
+                for i in 0u64..300u64 {
+                    let id_term = Term::from_field_text(id, &format!("id{}", i));
+                    if let Some(term_info) = id_index.get_term_info(&id_term) {
+                        let inlined_doc = term_info.inlined_doc.unwrap();
+                        let mut postings = id_index
+                            .read_postings(&id_term, IndexRecordOption::Basic)
+                            .unwrap()
+                            .unwrap();
+                        assert!(postings.advance());
+                        assert_eq!(postings.doc(), inlined_doc);
+                        assert!(!postings.advance());
+                        docs.push(i);
+                    }
+                    let text_term = Term::from_field_text(text, &format!("t{}", i));
+                    if let Some(term_info) = text_index.get_term_info(&text_term) {
+                        assert_eq!(term_info.inlined_doc, None);
+                    }
+                }
+                for i in 0u64..150u64 {
+                    let num_term = Term::from_field_u64(num, i);
+                    if let Some(term_info) = num_index.get_term_info(&num_term) {
+                        assert_eq!(term_info.doc_freq == 1, term_info.inlined_doc.is_some());
+                    }
+                }
+            }
+            docs.sort();
+            let mut count_collector = CountCollector::default();
+            let term_query = TermQuery::new(
+                Term::from_field_u64(num, 100u64),
+                IndexRecordOption::Basic,
+            );
+            searcher.search(&term_query, &mut count_collector).unwrap();
+            (docs, count_collector.count())
+        };
+        let expected = ((0u64..300u64).collect::<Vec<u64>>(), 2);
+        assert_eq!(check_postings(&index), expected);
+        {
+            let segment_ids = index.searchable_segment_ids().unwrap();
+            let mut index_writer = index.writer_with_num_threads(1, 30_000_000).unwrap();
+            index_writer.merge(&segment_ids).wait().unwrap();
+            index_writer.wait_merging_threads().unwrap();
+        }
+        assert_eq!(check_postings(&index), expected);
+    }
+
     #[test]
     pub fn test_position_and_fieldnorm1() {
         let mut schema_builder = SchemaBuilder::default();
@@ -207,7 +564,9 @@ pub mod tests {
                 assert!(
                     segment_reader
                         .inverted_index(term_a.field())
+                        .unwrap()
                         .read_postings(&term_a, IndexRecordOption::WithFreqsAndPositions)
+                        .unwrap()
                         .is_none()
                 );
             }
@@ -215,7 +574,9 @@ pub mod tests {
                 let term_a = Term::from_field_text(text_field, "a");
                 let mut postings_a = segment_reader
                     .inverted_index(term_a.field())
+                    .unwrap()
                     .read_postings(&term_a, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert_eq!(postings_a.len(), 1000);
                 assert!(postings_a.advance());
@@ -238,7 +599,9 @@ pub mod tests {
                 let term_e = Term::from_field_text(text_field, "e");
                 let mut postings_e = segment_reader
                     .inverted_index(term_e.field())
+                    .unwrap()
                     .read_postings(&term_e, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert_eq!(postings_e.len(), 1000 - 2);
                 for i in 2u32..1000u32 {
@@ -282,10 +645,10 @@ pub mod tests {
             IndexRecordOption::Basic,
         );
         let searcher = index.searcher();
-        let mut term_weight = term_query.specialized_weight(&*searcher, true);
+        let mut term_weight = term_query.specialized_weight(&*searcher, true).unwrap();
         term_weight.index_record_option = IndexRecordOption::WithFreqsAndPositions;
         let segment_reader = &searcher.segment_readers()[0];
-        let mut term_scorer = term_weight.specialized_scorer(segment_reader).unwrap();
+        let mut term_scorer = term_weight.specialized_scorer(segment_reader).unwrap().unwrap();
         assert!(term_scorer.advance());
         assert_eq!(term_scorer.doc(), 1u32);
         assert_eq!(term_scorer.postings().positions(), &[1u32, 4]);
@@ -328,7 +691,9 @@ pub mod tests {
             for j in i + 1..num_docs {
                 let mut segment_postings = segment_reader
                     .inverted_index(term_2.field())
+                    .unwrap()
                     .read_postings(&term_2, IndexRecordOption::Basic)
+                    .unwrap()
                     .unwrap();
 
                 assert_eq!(segment_postings.skip_next(i), SkipResult::Reached);
@@ -342,7 +707,9 @@ pub mod tests {
         {
             let mut segment_postings = segment_reader
                 .inverted_index(term_2.field())
+                .unwrap()
                 .read_postings(&term_2, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             // check that `skip_next` advances the iterator
@@ -363,7 +730,9 @@ pub mod tests {
         {
             let mut segment_postings = segment_reader
                 .inverted_index(term_0.field())
+                .unwrap()
                 .read_postings(&term_0, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             for i in 0..num_docs / 2 {
@@ -373,7 +742,9 @@ pub mod tests {
 
             let mut segment_postings = segment_reader
                 .inverted_index(term_0.field())
+                .unwrap()
                 .read_postings(&term_0, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             for i in 0..num_docs / 2 - 1 {
@@ -398,7 +769,9 @@ pub mod tests {
         for i in 0..num_docs {
             let mut segment_postings = segment_reader
                 .inverted_index(term_2.field())
+                .unwrap()
                 .read_postings(&term_2, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             if i % 2 == 0 {
@@ -414,7 +787,9 @@ pub mod tests {
         {
             let mut segment_postings = segment_reader
                 .inverted_index(term_2.field())
+                .unwrap()
                 .read_postings(&term_2, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             let mut last = 2; // start from 5 to avoid seeking to 3 twice
@@ -450,14 +825,18 @@ pub mod tests {
         {
             let mut segment_postings = segment_reader
                 .inverted_index(term_2.field())
+                .unwrap()
                 .read_postings(&term_2, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             assert_eq!(segment_postings.skip_next(0), SkipResult::End);
 
             let mut segment_postings = segment_reader
                 .inverted_index(term_2.field())
+                .unwrap()
                 .read_postings(&term_2, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             assert_eq!(segment_postings.skip_next(num_docs), SkipResult::End);
@@ -524,7 +903,9 @@ pub mod tests {
         b.iter(|| {
             let mut segment_postings = segment_reader
                 .inverted_index(TERM_A.field())
+                .unwrap()
                 .read_postings(&*TERM_A, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             while segment_postings.advance() {}
         });
@@ -537,19 +918,27 @@ pub mod tests {
         b.iter(|| {
             let segment_postings_a = segment_reader
                 .inverted_index(TERM_A.field())
+                .unwrap()
                 .read_postings(&*TERM_A, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             let segment_postings_b = segment_reader
                 .inverted_index(TERM_B.field())
+                .unwrap()
                 .read_postings(&*TERM_B, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             let segment_postings_c = segment_reader
                 .inverted_index(TERM_C.field())
+                .unwrap()
                 .read_postings(&*TERM_C, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             let segment_postings_d = segment_reader
                 .inverted_index(TERM_D.field())
+                .unwrap()
                 .read_postings(&*TERM_D, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             let mut intersection = Intersection::from(vec![
                 segment_postings_a,
@@ -568,7 +957,9 @@ pub mod tests {
 
         let mut segment_postings = segment_reader
             .inverted_index(TERM_A.field())
+            .unwrap()
             .read_postings(&*TERM_A, IndexRecordOption::Basic)
+            .unwrap()
             .unwrap();
 
         let mut existing_docs = Vec::new();
@@ -585,7 +976,9 @@ pub mod tests {
         b.iter(|| {
             let mut segment_postings = segment_reader
                 .inverted_index(TERM_A.field())
+                .unwrap()
                 .read_postings(&*TERM_A, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             for doc in &existing_docs {
                 if segment_postings.skip_next(*doc) == SkipResult::End {
@@ -623,7 +1016,9 @@ pub mod tests {
             let n: u32 = test::black_box(17);
             let mut segment_postings = segment_reader
                 .inverted_index(TERM_A.field())
+                .unwrap()
                 .read_postings(&*TERM_A, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             let mut s = 0u32;
             while segment_postings.advance() {
@@ -0,0 +1,157 @@
+use compression::BlockDecoder;
+use compression::COMPRESSION_BLOCK_SIZE;
+use compression::compressed_blocks_size;
+use directory::{ReadOnlySource, SourceRead};
+use std::cmp;
+use std::io;
+
+/// Reads the positions of a term.
+///
+/// Positions are delta-encoded, and the deltas are stored
+/// in blocks of `COMPRESSION_BLOCK_SIZE` bitpacked integers.
+/// Each block starts with a one-byte header giving the number
+/// of bits used to encode its deltas.
+///
+/// The length of a block is therefore known without decoding it.
+/// `.skip(...)` only reads the block headers, and blocks are
+/// only decoded when some of their positions are actually read.
+///
+/// Both return an error if the positions are corrupted.
+pub struct PositionReader {
+    // starts at the beginning of the current block.
+    buffer: SourceRead,
+    block_decoder: BlockDecoder,
+    // offset of the next position within the current block.
+    inner_offset: usize,
+    // true iff the current block has been decoded
+    // in `block_decoder`.
+    block_loaded: bool,
+}
+
+impl PositionReader {
+    /// Opens a position reader, positioned at the beginning of `source`.
+    pub(crate) fn wrap(source: ReadOnlySource) -> PositionReader {
+        PositionReader {
+            buffer: SourceRead::from(source),
+            block_decoder: BlockDecoder::new(),
+            inner_offset: 0,
+            block_loaded: false,
+        }
+    }
+
+    /// Returns an upper bound of the number of position deltas
+    /// that remain to be read, as a block takes one byte at least.
+    pub fn max_num_remaining(&self) -> usize {
+        (self.buffer.as_ref().len() * COMPRESSION_BLOCK_SIZE).saturating_sub(self.inner_offset)
+    }
+
+    /// Fills a buffer with the next `output.len()` position deltas,
+    /// and advance the reader by that many els.
+    pub fn read(&mut self, output: &mut [u32]) -> io::Result<()> {
+        let mut start = 0;
+        while start < output.len() {
+            if !self.block_loaded {
+                self.block_decoder
+                    .uncompress_block_unsorted(self.buffer.as_ref())?;
+                self.block_loaded = true;
+            }
+            let available = COMPRESSION_BLOCK_SIZE - self.inner_offset;
+            let len = cmp::min(available, output.len() - start);
+            output[start..][..len].clone_from_slice(
+                &self.block_decoder.output_array()[self.inner_offset..][..len],
+            );
+            start += len;
+            self.skip(len)?;
+        }
+        Ok(())
+    }
+
+    /// Skip the next `skip_len` position deltas.
+    ///
+    /// Skipped blocks are never decoded.
+    pub fn skip(&mut self, skip_len: usize) -> io::Result<()> {
+        let target_offset = self.inner_offset + skip_len;
+        let num_skipped_vals = target_offset - target_offset % COMPRESSION_BLOCK_SIZE;
+        let num_skipped_bytes = compressed_blocks_size(self.buffer.as_ref(), num_skipped_vals)?;
+        self.buffer.advance(num_skipped_bytes);
+        if num_skipped_vals > 0 {
+            self.block_loaded = false;
+        }
+        self.inner_offset = target_offset % COMPRESSION_BLOCK_SIZE;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+
+    use super::PositionReader;
+    use compression::compressed_block_size;
+    use compression::COMPRESSION_BLOCK_SIZE;
+    use compression::BlockEncoder;
+    use directory::ReadOnlySource;
+
+    fn create_positions_buffer() -> ReadOnlySource {
+        let mut buffer: Vec<u8> = vec![];
+        let mut encoder = BlockEncoder::new();
+        let vals: Vec<u32> = (0u32..1_025u32).collect();
+        for chunk in vals.chunks(COMPRESSION_BLOCK_SIZE) {
+            let compressed_block = encoder.compress_block_unsorted(chunk);
+            let num_bits = compressed_block[0];
+            assert_eq!(compressed_block_size(num_bits), compressed_block.len());
+            buffer.extend_from_slice(compressed_block);
+        }
+        if cfg!(simd) {
+            buffer.extend_from_slice(&[0u8; 7]);
+        }
+        ReadOnlySource::from(buffer)
+    }
+
+    #[test]
+    fn test_position_reader() {
+        let buffer = create_positions_buffer();
+        let mut reader = PositionReader::wrap(buffer);
+        let mut block: [u32; COMPRESSION_BLOCK_SIZE] = [0u32; COMPRESSION_BLOCK_SIZE];
+
+        reader.read(&mut block[0..2]).unwrap();
+        assert_eq!(block[0], 0);
+        assert_eq!(block[1], 1);
+        reader.skip(5).unwrap();
+        reader.read(&mut block[0..3]).unwrap();
+        assert_eq!(block[0], 7);
+        assert_eq!(block[1], 8);
+        assert_eq!(block[2], 9);
+        reader.skip(500).unwrap();
+        reader.read(&mut block[0..3]).unwrap();
+        assert_eq!(block[0], 510);
+        assert_eq!(block[1], 511);
+        assert_eq!(block[2], 512);
+        reader.skip(511).unwrap();
+        reader.read(&mut block[..1]).unwrap();
+        assert_eq!(block[0], 1024);
+    }
+
+    #[test]
+    fn test_position_reader_across_blocks() {
+        let buffer = create_positions_buffer();
+        let mut reader = PositionReader::wrap(buffer);
+        let mut vals = vec![0u32; 300];
+        reader.skip(COMPRESSION_BLOCK_SIZE).unwrap();
+        reader.read(&mut vals[..]).unwrap();
+        let expected: Vec<u32> = (128u32..428u32).collect();
+        assert_eq!(vals, expected);
+    }
+
+    #[test]
+    fn test_position_reader_corrupted() {
+        let buffer = create_positions_buffer();
+        let truncated = buffer.slice(0, buffer.len() / 2);
+        let mut reader = PositionReader::wrap(truncated);
+        let mut vals = vec![0u32; 1_024];
+        assert!(reader.read(&mut vals[..]).is_err());
+        let mut reader = PositionReader::wrap(buffer.slice(0, buffer.len() / 2));
+        assert!(reader.skip(1_000).is_err());
+        let mut reader = PositionReader::wrap(ReadOnlySource::empty());
+        assert!(reader.read(&mut vals[..1]).is_err());
+    }
+}
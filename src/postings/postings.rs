@@ -1,11 +1,12 @@
 use docset::DocSet;
+use postings::PostingsCursor;
 
 /// Postings (also called inverted list)
 ///
 /// For a given term, it is the list of doc ids of the doc
 /// containing the term. Optionally, for each document,
 /// it may also give access to the term frequency
-/// as well as the list of term positions.
+/// as well as the list of term positions and offsets.
 ///
 /// Its main implementation is `SegmentPostings`,
 /// but other implementations mocking `SegmentPostings` exist,
@@ -16,4 +17,22 @@ pub trait Postings: DocSet {
     /// Returns the list of positions of the term, expressed as a list of
     /// token ordinals.
     fn positions(&self) -> &[u32];
+    /// Returns the list of the byte offsets `(offset_from, offset_to)` of
+    /// the occurrences of the term, in the same order as `.positions()`.
+    ///
+    /// The list is empty if the field does not record offsets
+    /// (See `IndexRecordOption::WithFreqsPositionsAndOffsets`).
+    fn offsets(&self) -> &[(u32, u32)];
+
+    /// Returns an iterator over the occurrences of the term
+    /// within the current document, yielding their position
+    /// together with their offsets.
+    ///
+    /// It is a convenience over `.positions()` and `.offsets()`:
+    /// all of the positions of the term within the document are decoded
+    /// when it is created, not as the iterator advances. The phrase and
+    /// near scorers work on the `.positions()` slices directly.
+    fn matches(&self) -> PostingsCursor {
+        PostingsCursor::new(self.positions(), self.offsets())
+    }
 }
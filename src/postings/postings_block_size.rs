@@ -0,0 +1,57 @@
+use compression::COMPRESSION_BLOCK_SIZE;
+
+/// Number of documents of the blocks of the posting lists.
+///
+/// It is defined at the creation of the index, in its `IndexSettings`.
+/// Each block is preceded by a header holding its last doc and its
+/// maximum impact, so that smaller blocks make it possible to skip
+/// more documents without decoding them, at the expense of a larger
+/// postings file.
+///
+/// The block size is recorded along with the postings of each field,
+/// so that the segments written before a change of the setting
+/// remain readable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PostingsBlockSize {
+    /// Blocks of 64 documents.
+    ///
+    /// The bitpacking works on 128 integers at a time, so these blocks
+    /// are padded, and take as much space as blocks of 128 documents.
+    #[serde(rename = "64")]
+    Docs64,
+    /// Blocks of 128 documents.
+    ///
+    /// This is the default.
+    #[serde(rename = "128")]
+    Docs128,
+    /// Blocks of 256 documents.
+    #[serde(rename = "256")]
+    Docs256,
+}
+
+impl Default for PostingsBlockSize {
+    fn default() -> PostingsBlockSize {
+        PostingsBlockSize::Docs128
+    }
+}
+
+impl PostingsBlockSize {
+    /// Returns the number of documents of a block.
+    pub fn num_docs(&self) -> usize {
+        match *self {
+            PostingsBlockSize::Docs64 => COMPRESSION_BLOCK_SIZE / 2,
+            PostingsBlockSize::Docs128 => COMPRESSION_BLOCK_SIZE,
+            PostingsBlockSize::Docs256 => COMPRESSION_BLOCK_SIZE * 2,
+        }
+    }
+
+    pub(crate) fn from_num_docs(num_docs: usize) -> Option<PostingsBlockSize> {
+        [
+            PostingsBlockSize::Docs64,
+            PostingsBlockSize::Docs128,
+            PostingsBlockSize::Docs256,
+        ].iter()
+            .cloned()
+            .find(|block_size| block_size.num_docs() == num_docs)
+    }
+}
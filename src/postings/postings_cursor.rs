@@ -0,0 +1,52 @@
+/// Occurrence of a term within a document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionInfo {
+    /// Position of the occurrence, expressed as a token ordinal.
+    pub pos: u32,
+    /// Byte offsets `(offset_from, offset_to)` of the occurrence,
+    /// if the field records offsets.
+    /// (See `IndexRecordOption::WithFreqsPositionsAndOffsets`)
+    pub offset: Option<(u32, u32)>,
+}
+
+/// Iterator over the occurrences of a term within the current
+/// document of a `Postings`, in increasing position order.
+///
+/// It is returned by `Postings::matches()`, so that the positions
+/// and the offsets of the occurrences are consumed together.
+/// It iterates over the `.positions()` and `.offsets()` slices
+/// of the postings, which are already decoded.
+pub struct PostingsCursor<'a> {
+    positions: &'a [u32],
+    // empty if the field does not record offsets.
+    offsets: &'a [(u32, u32)],
+    cursor: usize,
+}
+
+impl<'a> PostingsCursor<'a> {
+    pub(crate) fn new(positions: &'a [u32], offsets: &'a [(u32, u32)]) -> PostingsCursor<'a> {
+        PostingsCursor {
+            positions,
+            offsets,
+            cursor: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for PostingsCursor<'a> {
+    type Item = PositionInfo;
+
+    fn next(&mut self) -> Option<PositionInfo> {
+        let pos = *self.positions.get(self.cursor)?;
+        let offset = self.offsets.get(self.cursor).cloned();
+        self.cursor += 1;
+        Some(PositionInfo { pos, offset })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.positions.len() - self.cursor;
+        (len, Some(len))
+    }
+}
+
+impl<'a> ExactSizeIterator for PostingsCursor<'a> {}
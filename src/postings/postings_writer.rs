@@ -9,13 +9,15 @@ use schema::{Field, Schema};
 use std::marker::PhantomData;
 use std::ops::DerefMut;
 use datastruct::stacker::{Heap, TermHashMap};
-use postings::{NothingRecorder, TFAndPositionRecorder, TermFrequencyRecorder};
+use postings::{NothingRecorder, PositionRecorder, TFAndPositionRecorder,
+               TFPositionAndOffsetRecorder, TermFrequencyRecorder};
 use schema::FieldEntry;
 use schema::FieldType;
 use tokenizer::Token;
 use tokenizer::TokenStream;
 use schema::IndexRecordOption;
 use postings::UnorderedTermId;
+use fastfield::FastFieldsWriter;
 
 fn posting_from_field_entry<'a>(
     field_entry: &FieldEntry,
@@ -34,11 +36,19 @@ fn posting_from_field_entry<'a>(
                 IndexRecordOption::WithFreqsAndPositions => {
                     SpecializedPostingsWriter::<TFAndPositionRecorder>::new_boxed(heap)
                 }
+                IndexRecordOption::WithFreqsPositionsAndOffsets => {
+                    SpecializedPostingsWriter::<TFPositionAndOffsetRecorder>::new_boxed(heap)
+                }
+                IndexRecordOption::WithPositions => {
+                    SpecializedPostingsWriter::<PositionRecorder>::new_boxed(heap)
+                }
             })
             .unwrap_or_else(|| SpecializedPostingsWriter::<NothingRecorder>::new_boxed(heap)),
-        FieldType::U64(_) | FieldType::I64(_) | FieldType::HierarchicalFacet => {
-            SpecializedPostingsWriter::<NothingRecorder>::new_boxed(heap)
-        }
+        FieldType::U64(_)
+        | FieldType::I64(_)
+        | FieldType::HierarchicalFacet
+        | FieldType::Vector(_)
+        | FieldType::Bytes(_) => SpecializedPostingsWriter::<NothingRecorder>::new_boxed(heap),
     }
 }
 
@@ -73,16 +83,19 @@ impl<'a> MultiFieldPostingsWriter<'a> {
 
     pub fn subscribe(&mut self, doc: DocId, term: &Term) -> UnorderedTermId {
         let postings_writer = self.per_field_postings_writers[term.field().0 as usize].deref_mut();
-        postings_writer.subscribe(&mut self.term_index, doc, 0u32, term, self.heap)
+        postings_writer.subscribe(&mut self.term_index, doc, 0u32, (0u32, 0u32), term, self.heap)
     }
 
     /// Serialize the inverted index.
     /// It pushes all term, one field at a time, towards the
     /// postings serializer.
+    ///
+    /// The fieldnorms are required to compute the impacts of the postings blocks.
     #[allow(needless_range_loop)]
     pub fn serialize(
         &self,
         serializer: &mut InvertedIndexSerializer,
+        fieldnorms_writer: &FastFieldsWriter,
     ) -> Result<HashMap<Field, HashMap<UnorderedTermId, usize>>> {
         let mut term_offsets: Vec<(&[u8], u32, UnorderedTermId)> = self.term_index.iter().collect();
         term_offsets.sort_by_key(|&(k, _, _)| k);
@@ -123,7 +136,10 @@ impl<'a> MultiFieldPostingsWriter<'a> {
             unordered_term_mappings.insert(field, mapping);
 
             let postings_writer = &self.per_field_postings_writers[field.0 as usize];
-            let mut field_serializer = serializer.new_field(field)?;
+            let fieldnorms = fieldnorms_writer
+                .get_field_vals(field)
+                .unwrap_or_default();
+            let mut field_serializer = serializer.new_field(field, &fieldnorms)?;
             postings_writer.serialize(
                 &term_offsets[start..stop],
                 &mut field_serializer,
@@ -149,6 +165,7 @@ pub trait PostingsWriter {
     ///
     /// * doc  - the document id
     /// * pos  - the term position (expressed in tokens)
+    /// * offsets - the byte offsets `(offset_from, offset_to)` of the term in the text
     /// * term - the term
     /// * heap - heap used to store the postings informations as well as the terms
     /// in the hashmap.
@@ -157,6 +174,7 @@ pub trait PostingsWriter {
         term_index: &mut TermHashMap,
         doc: DocId,
         pos: u32,
+        offsets: (u32, u32),
         term: &Term,
         heap: &Heap,
     ) -> UnorderedTermId;
@@ -183,7 +201,8 @@ pub trait PostingsWriter {
         term.set_field(field);
         let mut sink = |token: &Token| {
             term.set_text(token.text.as_str());
-            self.subscribe(term_index, doc_id, token.position as u32, &term, heap);
+            let offsets = (token.offset_from as u32, token.offset_to as u32);
+            self.subscribe(term_index, doc_id, token.position as u32, offsets, &term, heap);
         };
         token_stream.process(&mut sink)
     }
@@ -217,6 +236,7 @@ impl<'a, Rec: Recorder + 'static> PostingsWriter for SpecializedPostingsWriter<'
         term_index: &mut TermHashMap,
         doc: DocId,
         position: u32,
+        offsets: (u32, u32),
         term: &Term,
         heap: &Heap,
     ) -> UnorderedTermId {
@@ -229,7 +249,7 @@ impl<'a, Rec: Recorder + 'static> PostingsWriter for SpecializedPostingsWriter<'
             }
             recorder.new_doc(doc, heap);
         }
-        recorder.record_position(position, heap);
+        recorder.record_position(position, offsets, heap);
         term_ord
     }
 
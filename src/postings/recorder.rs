@@ -1,5 +1,5 @@
 use DocId;
-use std::{self, io};
+use std::{self, cmp, io};
 use postings::FieldSerializer;
 use datastruct::stacker::{ExpUnrolledLinkedList, Heap, HeapAllocable};
 
@@ -15,15 +15,17 @@ const POSITION_END: u32 = std::u32::MAX;
 ///   * the document id
 ///   * the term frequency
 ///   * the term positions
+///   * the term offsets
 pub trait Recorder: HeapAllocable {
     /// Returns the current document
     fn current_doc(&self) -> u32;
     /// Starts recording information about a new document
     /// This method shall only be called if the term is within the document.
     fn new_doc(&mut self, doc: DocId, heap: &Heap);
-    /// Record the position of a term. For each document,
+    /// Record the position of a term, as well as its byte offsets
+    /// `(offset_from, offset_to)` in the original text. For each document,
     /// this method will be called `term_freq` times.
-    fn record_position(&mut self, position: u32, heap: &Heap);
+    fn record_position(&mut self, position: u32, offsets: (u32, u32), heap: &Heap);
     /// Close the document. It will help record the term frequency.
     fn close_doc(&mut self, heap: &Heap);
     /// Pushes the postings information to the serializer.
@@ -60,7 +62,7 @@ impl Recorder for NothingRecorder {
         self.stack.push(doc, heap);
     }
 
-    fn record_position(&mut self, _position: u32, _heap: &Heap) {}
+    fn record_position(&mut self, _position: u32, _offsets: (u32, u32), _heap: &Heap) {}
 
     fn close_doc(&mut self, _heap: &Heap) {}
 
@@ -104,7 +106,7 @@ impl Recorder for TermFrequencyRecorder {
         self.stack.push(doc, heap);
     }
 
-    fn record_position(&mut self, _position: u32, _heap: &Heap) {
+    fn record_position(&mut self, _position: u32, _offsets: (u32, u32), _heap: &Heap) {
         self.current_tf += 1;
     }
 
@@ -161,7 +163,7 @@ impl Recorder for TFAndPositionRecorder {
         self.stack.push(doc, heap);
     }
 
-    fn record_position(&mut self, position: u32, heap: &Heap) {
+    fn record_position(&mut self, position: u32, _offsets: (u32, u32), heap: &Heap) {
         self.stack.push(position, heap);
     }
 
@@ -178,18 +180,168 @@ impl Recorder for TFAndPositionRecorder {
         let mut doc_positions = Vec::with_capacity(100);
         let mut positions_iter = self.stack.iter(self_addr, heap);
         while let Some(doc) = positions_iter.next() {
-            let mut prev_position = 0;
             doc_positions.clear();
             for position in &mut positions_iter {
                 if position == POSITION_END {
                     break;
                 } else {
-                    doc_positions.push(position - prev_position);
-                    prev_position = position;
+                    doc_positions.push(position);
                 }
             }
+            // Token filters emitting several tokens at the
+            // same position (e.g. synonyms) may not emit the
+            // positions of a given term in order.
+            doc_positions.sort();
+            let mut prev_position = 0;
+            for position in &mut doc_positions {
+                let delta = *position - prev_position;
+                prev_position = *position;
+                *position = delta;
+            }
             serializer.write_doc(doc, doc_positions.len() as u32, &doc_positions)?;
         }
         Ok(())
     }
 }
+
+/// Recorder encoding document ids, and the position of the
+/// first occurrence of the term in each document.
+///
+/// The term frequency is not recorded. (See `IndexRecordOption::WithPositions`)
+pub struct PositionRecorder {
+    stack: ExpUnrolledLinkedList,
+    current_doc: DocId,
+    first_position: u32,
+}
+
+impl HeapAllocable for PositionRecorder {
+    fn with_addr(addr: u32) -> PositionRecorder {
+        PositionRecorder {
+            stack: ExpUnrolledLinkedList::with_addr(addr),
+            current_doc: u32::max_value(),
+            first_position: u32::max_value(),
+        }
+    }
+}
+
+impl Recorder for PositionRecorder {
+    fn current_doc(&self) -> DocId {
+        self.current_doc
+    }
+
+    fn new_doc(&mut self, doc: DocId, heap: &Heap) {
+        self.current_doc = doc;
+        self.stack.push(doc, heap);
+    }
+
+    fn record_position(&mut self, position: u32, _offsets: (u32, u32), _heap: &Heap) {
+        // Token filters emitting several tokens at the
+        // same position (e.g. synonyms) may not emit the
+        // positions of a given term in order.
+        self.first_position = cmp::min(self.first_position, position);
+    }
+
+    fn close_doc(&mut self, heap: &Heap) {
+        debug_assert!(self.first_position != u32::max_value());
+        self.stack.push(self.first_position, heap);
+        self.first_position = u32::max_value();
+    }
+
+    fn serialize(
+        &self,
+        self_addr: u32,
+        serializer: &mut FieldSerializer,
+        heap: &Heap,
+    ) -> io::Result<()> {
+        // the last document has not been closed...
+        // its position is self.first_position.
+        let mut doc_iter = self.stack
+            .iter(self_addr, heap)
+            .chain(Some(self.first_position).into_iter());
+
+        while let Some(doc) = doc_iter.next() {
+            let position = doc_iter
+                .next()
+                .expect("The IndexWriter recorded a doc without a position.");
+            serializer.write_doc(doc, 1u32, &[position])?;
+        }
+        Ok(())
+    }
+}
+
+/// Recorder encoding term frequencies, positions, as well as offsets.
+pub struct TFPositionAndOffsetRecorder {
+    stack: ExpUnrolledLinkedList,
+    current_doc: DocId,
+}
+
+impl HeapAllocable for TFPositionAndOffsetRecorder {
+    fn with_addr(addr: u32) -> TFPositionAndOffsetRecorder {
+        TFPositionAndOffsetRecorder {
+            stack: ExpUnrolledLinkedList::with_addr(addr),
+            current_doc: u32::max_value(),
+        }
+    }
+}
+
+impl Recorder for TFPositionAndOffsetRecorder {
+    fn current_doc(&self) -> DocId {
+        self.current_doc
+    }
+
+    fn new_doc(&mut self, doc: DocId, heap: &Heap) {
+        self.current_doc = doc;
+        self.stack.push(doc, heap);
+    }
+
+    fn record_position(&mut self, position: u32, offsets: (u32, u32), heap: &Heap) {
+        self.stack.push(position, heap);
+        self.stack.push(offsets.0, heap);
+        self.stack.push(offsets.1, heap);
+    }
+
+    fn close_doc(&mut self, heap: &Heap) {
+        self.stack.push(POSITION_END, heap);
+    }
+
+    fn serialize(
+        &self,
+        self_addr: u32,
+        serializer: &mut FieldSerializer,
+        heap: &Heap,
+    ) -> io::Result<()> {
+        let mut doc_occurrences: Vec<(u32, u32, u32)> = Vec::with_capacity(100);
+        let mut encoded_occurrences: Vec<u32> = Vec::with_capacity(300);
+        let mut stack_iter = self.stack.iter(self_addr, heap);
+        while let Some(doc) = stack_iter.next() {
+            doc_occurrences.clear();
+            while let Some(position) = stack_iter.next() {
+                if position == POSITION_END {
+                    break;
+                }
+                let offset_from = stack_iter
+                    .next()
+                    .expect("The IndexWriter recorded a position without offsets.");
+                let offset_to = stack_iter
+                    .next()
+                    .expect("The IndexWriter recorded a position without offsets.");
+                doc_occurrences.push((position, offset_from, offset_to));
+            }
+            doc_occurrences.sort();
+            encoded_occurrences.clear();
+            let mut prev_position = 0u32;
+            let mut prev_offset_from = 0u32;
+            for &(position, offset_from, offset_to) in &doc_occurrences {
+                // Offsets are not necessarily increasing with the position,
+                // hence the wrapping arithmetic.
+                encoded_occurrences.push(position - prev_position);
+                encoded_occurrences.push(offset_from.wrapping_sub(prev_offset_from));
+                encoded_occurrences.push(offset_to.wrapping_sub(offset_from));
+                prev_position = position;
+                prev_offset_from = offset_from;
+            }
+            serializer.write_doc(doc, doc_occurrences.len() as u32, &encoded_occurrences)?;
+        }
+        Ok(())
+    }
+}
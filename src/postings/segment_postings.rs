@@ -1,4 +1,4 @@
-use compression::{BlockDecoder, CompressedIntStream, VIntDecoder, COMPRESSION_BLOCK_SIZE};
+use compression::{BlockDecoder, VIntDecoder, COMPRESSION_BLOCK_SIZE};
 use DocId;
 
 use common::BitSet;
@@ -7,14 +7,28 @@ use postings::Postings;
 use docset::{DocSet, SkipResult};
 use std::cmp;
 use fst::Streamer;
-use compression::compressed_block_size;
+use compression::{check_vints, compressed_blocks_size};
 use fastfield::DeleteBitSet;
 use std::cell::UnsafeCell;
 use directory::{ReadOnlySource, SourceRead};
 use postings::FreqReadingOption;
 use postings::serializer::PostingsSerializer;
+use postings::PositionReader;
+use postings::PostingsBlockSize;
+use byteorder::{ByteOrder, LittleEndian};
+use std::f32;
+use std::io;
 
 const EMPTY_POSITIONS: [u32; 0] = [0u32; 0];
+const EMPTY_OFFSETS: [(u32, u32); 0] = [(0u32, 0u32); 0];
+
+// Each bitpacked block is preceded by its last doc and its max impact.
+// (See `PostingsSerializer`)
+const BLOCK_HEADER_LEN: usize = 8;
+
+// Cursor of a `SegmentPostings` that is not on a document.
+// It is past the end of the largest blocks, yet within the bounds of the decoders output.
+const UNPOSITIONED_CURSOR: usize = 2 * COMPRESSION_BLOCK_SIZE;
 
 struct PositionComputer {
     // store the amount of position int
@@ -24,40 +38,107 @@ struct PositionComputer {
     // the positions vec.
     position_to_skip: Option<usize>,
     positions: Vec<u32>,
-    positions_stream: CompressedIntStream,
+    // empty unless the field records offsets.
+    offsets: Vec<(u32, u32)>,
+    // buffer used to read the interleaved positions and offsets.
+    encoded_occurrences: Vec<u32>,
+    has_offsets: bool,
+    position_reader: PositionReader,
 }
 
 impl PositionComputer {
-    pub fn new(positions_stream: CompressedIntStream) -> PositionComputer {
+    pub fn new(position_reader: PositionReader, has_offsets: bool) -> PositionComputer {
         PositionComputer {
             position_to_skip: None,
             positions: vec![],
-            positions_stream,
+            offsets: vec![],
+            encoded_occurrences: vec![],
+            has_offsets,
+            position_reader,
+        }
+    }
+
+    /// Number of values in the positions file for each occurrence of the term.
+    ///
+    /// (See `FieldSerializer::write_doc`)
+    fn num_vals_per_occurrence(&self) -> usize {
+        if self.has_offsets {
+            3
+        } else {
+            1
         }
     }
 
     pub fn add_skip(&mut self, num_skip: usize) {
+        let num_vals_skipped = num_skip * self.num_vals_per_occurrence();
         self.position_to_skip = Some(
             self.position_to_skip
-                .map(|prev_skip| prev_skip + num_skip)
+                .map(|prev_skip| prev_skip + num_vals_skipped)
                 .unwrap_or(0),
         );
     }
 
-    pub fn positions(&mut self, term_freq: usize) -> &[u32] {
+    /// Reads the positions of the current document, if they
+    /// have not been read yet.
+    ///
+    /// Corrupted positions are reported, and read as no position at all.
+    fn load(&mut self, term_freq: usize) {
         if let Some(num_skip) = self.position_to_skip {
-            self.positions.resize(term_freq, 0u32);
-            self.positions_stream.skip(num_skip);
-            self.positions_stream.read(&mut self.positions[..term_freq]);
+            self.position_to_skip = None;
+            if let Err(err) = self.read_positions(num_skip, term_freq) {
+                warn!("Failed to read the positions: {:?}", err);
+                self.positions.clear();
+                self.offsets.clear();
+            }
+        }
+    }
 
+    fn read_positions(&mut self, num_skip: usize, term_freq: usize) -> io::Result<()> {
+        self.position_reader.skip(num_skip)?;
+        let num_vals = term_freq * self.num_vals_per_occurrence();
+        // the term frequency is checked before allocating the buffers.
+        if num_vals > self.position_reader.max_num_remaining() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The term frequency exceeds the number of positions",
+            ));
+        }
+        self.positions.resize(term_freq, 0u32);
+        if self.has_offsets {
+            self.encoded_occurrences.resize(num_vals, 0u32);
+            self.position_reader
+                .read(&mut self.encoded_occurrences[..num_vals])?;
+            self.offsets.resize(term_freq, (0u32, 0u32));
+            let mut position = 0u32;
+            let mut offset_from = 0u32;
+            for (i, occurrence) in self.encoded_occurrences.chunks(3).enumerate() {
+                position = position.wrapping_add(occurrence[0]);
+                offset_from = offset_from.wrapping_add(occurrence[1]);
+                self.positions[i] = position;
+                self.offsets[i] = (offset_from, offset_from.wrapping_add(occurrence[2]));
+            }
+        } else {
+            self.position_reader.read(&mut self.positions[..term_freq])?;
             let mut cum = 0u32;
             for i in 0..term_freq as usize {
-                cum += self.positions[i];
+                cum = cum.wrapping_add(self.positions[i]);
                 self.positions[i] = cum;
             }
-            self.position_to_skip = None;
         }
-        &self.positions[..term_freq]
+        Ok(())
+    }
+
+    pub fn positions(&mut self, term_freq: usize) -> &[u32] {
+        self.load(term_freq);
+        &self.positions[..cmp::min(term_freq, self.positions.len())]
+    }
+
+    pub fn offsets(&mut self, term_freq: usize) -> &[(u32, u32)] {
+        if !self.has_offsets {
+            return &EMPTY_OFFSETS[..];
+        }
+        self.load(term_freq);
+        &self.offsets[..cmp::min(term_freq, self.offsets.len())]
     }
 }
 
@@ -85,9 +166,10 @@ impl SegmentPostings {
     pub fn create_from_docs(docs: &[u32]) -> SegmentPostings {
         let mut buffer = Vec::new();
         {
-            let mut postings_serializer = PostingsSerializer::new(&mut buffer, false);
+            let mut postings_serializer =
+                PostingsSerializer::new(&mut buffer, false, PostingsBlockSize::default());
             for &doc in docs {
-                postings_serializer.write_doc(doc, 1u32).unwrap();
+                postings_serializer.write_doc(doc, 1u32, 1u64).unwrap();
             }
             postings_serializer.close_term().unwrap();
         }
@@ -96,8 +178,14 @@ impl SegmentPostings {
             docs.len(),
             SourceRead::from(data),
             FreqReadingOption::NoFreq,
-        );
-        SegmentPostings::from_block_postings(block_segment_postings, DeleteBitSet::empty(), None)
+            PostingsBlockSize::default(),
+        ).expect("The postings were just serialized");
+        SegmentPostings::from_block_postings(
+            block_segment_postings,
+            DeleteBitSet::empty(),
+            None,
+            false,
+        )
     }
 
     /// Reads a Segment postings from an &[u8]
@@ -106,16 +194,19 @@ impl SegmentPostings {
     /// * `data` - data array. The complete data is not necessarily used.
     /// * `freq_handler` - the freq handler is in charge of decoding
     ///   frequencies and/or positions
+    /// * `has_offsets` - true iff the offsets are interleaved with the positions
     pub fn from_block_postings(
         segment_block_postings: BlockSegmentPostings,
         delete_bitset: DeleteBitSet,
-        positions_stream_opt: Option<CompressedIntStream>,
+        position_reader_opt: Option<PositionReader>,
+        has_offsets: bool,
     ) -> SegmentPostings {
-        let position_computer =
-            positions_stream_opt.map(|stream| UnsafeCell::new(PositionComputer::new(stream)));
+        let position_computer = position_reader_opt.map(|position_reader| {
+            UnsafeCell::new(PositionComputer::new(position_reader, has_offsets))
+        });
         SegmentPostings {
             block_cursor: segment_block_postings,
-            cur: COMPRESSION_BLOCK_SIZE, // cursor within the block
+            cur: UNPOSITIONED_CURSOR, // cursor within the block
             delete_bitset,
             position_computer,
         }
@@ -127,11 +218,31 @@ impl SegmentPostings {
         SegmentPostings {
             block_cursor: empty_block_cursor,
             delete_bitset: DeleteBitSet::empty(),
-            cur: COMPRESSION_BLOCK_SIZE,
+            cur: UNPOSITIONED_CURSOR,
             position_computer: None,
         }
     }
 
+    /// Returns an upper bound of `term_freq / fieldnorm` over the documents
+    /// of the current block.
+    ///
+    /// See `BlockSegmentPostings::block_max_impact()`.
+    pub fn block_max_impact(&self) -> f32 {
+        self.block_cursor.block_max_impact()
+    }
+
+    /// Returns the last doc of the current block.
+    pub fn block_last_doc(&self) -> DocId {
+        let block_docs = self.block_cursor.docs();
+        block_docs[block_docs.len() - 1]
+    }
+
+    /// Returns an upper bound of `term_freq / fieldnorm` over the documents
+    /// of the current block and of the blocks that follow.
+    pub fn max_impact(&self) -> f32 {
+        self.block_cursor.max_impact()
+    }
+
     fn position_add_skip<F: FnOnce() -> usize>(&self, num_skips_fn: F) {
         if let Some(position_computer) = self.position_computer.as_ref() {
             let num_skips = num_skips_fn();
@@ -153,7 +264,7 @@ impl DocSet for SegmentPostings {
             if self.cur >= self.block_cursor.block_len() {
                 self.cur = 0;
                 if !self.block_cursor.advance() {
-                    self.cur = COMPRESSION_BLOCK_SIZE;
+                    self.cur = UNPOSITIONED_CURSOR;
                     return false;
                 }
             }
@@ -186,11 +297,16 @@ impl DocSet for SegmentPostings {
                 // add the term freq.
                 //let num_skips: u32 = ;
                 self.position_add_skip(|| {
-                    let freqs_skipped = &self.block_cursor.freqs()[self.cur..];
-                    let sum_freq: u32 = freqs_skipped.iter().cloned().sum();
-                    sum_freq as usize
+                    let block_len = self.block_cursor.block_len();
+                    self.block_cursor.sum_freqs(self.cur, block_len) as usize
                 });
 
+                // the blocks that end before the target are skipped without
+                // decoding their doc ids.
+                let sum_freqs = self.position_computer.is_some();
+                let freqs_skipped = self.block_cursor.skip_blocks_before(target, sum_freqs);
+                self.position_add_skip(|| freqs_skipped as usize);
+
                 if !self.block_cursor.advance() {
                     return SkipResult::End;
                 }
@@ -245,11 +361,7 @@ impl DocSet for SegmentPostings {
             // `doc` is now >= `target`
             let doc = block_docs[start];
 
-            self.position_add_skip(|| {
-                let freqs_skipped = &self.block_cursor.freqs()[self.cur..start];
-                let sum_freqs: u32 = freqs_skipped.iter().sum();
-                sum_freqs as usize
-            });
+            self.position_add_skip(|| self.block_cursor.sum_freqs(self.cur, start) as usize);
 
             self.cur = start;
 
@@ -272,6 +384,10 @@ impl DocSet for SegmentPostings {
         self.len() as u32
     }
 
+    fn estimate_live(&self) -> u32 {
+        self.delete_bitset.estimate_alive(self.size_hint())
+    }
+
     /// Return the current document's `DocId`.
     #[inline]
     fn doc(&self) -> DocId {
@@ -287,12 +403,16 @@ impl DocSet for SegmentPostings {
         // finish the current block
         if self.advance() {
             for &doc in &self.block_cursor.docs()[self.cur..] {
-                bitset.insert(doc);
+                if !self.delete_bitset.is_deleted(doc) {
+                    bitset.insert(doc);
+                }
             }
             // ... iterate through the remaining blocks.
             while self.block_cursor.advance() {
                 for &doc in self.block_cursor.docs() {
-                    bitset.insert(doc);
+                    if !self.delete_bitset.is_deleted(doc) {
+                        bitset.insert(doc);
+                    }
                 }
             }
         }
@@ -319,6 +439,16 @@ impl Postings for SegmentPostings {
             })
             .unwrap_or(&EMPTY_POSITIONS[..])
     }
+
+    fn offsets(&self) -> &[(u32, u32)] {
+        let term_freq = self.term_freq();
+        self.position_computer
+            .as_ref()
+            .map(|position_computer| unsafe {
+                (&mut *position_computer.get()).offsets(term_freq as usize)
+            })
+            .unwrap_or(&EMPTY_OFFSETS[..])
+    }
 }
 
 /// `BlockSegmentPostings` is a cursor iterating over blocks
@@ -335,20 +465,63 @@ pub struct BlockSegmentPostings {
 
     doc_freq: usize,
     doc_offset: DocId,
+    block_max_impact: f32,
+    block_size: usize,
     num_bitpacked_blocks: usize,
     num_vint_docs: usize,
     remaining_data: SourceRead,
 }
 
+/// Checks that `data` holds the postings of `doc_freq` documents,
+/// so that their blocks can then be decoded without reading out of bounds.
+///
+/// Only the block headers are read: the blocks are not decoded.
+fn check_postings(
+    data: &[u8],
+    doc_freq: usize,
+    block_size: usize,
+    has_freqs: bool,
+) -> io::Result<()> {
+    let num_bitpacked_blocks = doc_freq / block_size;
+    let num_vint_docs = doc_freq - block_size * num_bitpacked_blocks;
+    let mut data = data;
+    for _ in 0..num_bitpacked_blocks {
+        if data.len() < BLOCK_HEADER_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Truncated postings block header",
+            ));
+        }
+        data = &data[BLOCK_HEADER_LEN..];
+        data = &data[compressed_blocks_size(data, block_size)?..];
+        if has_freqs {
+            data = &data[compressed_blocks_size(data, block_size)?..];
+        }
+    }
+    data = &data[check_vints(data, num_vint_docs)?..];
+    if has_freqs {
+        check_vints(data, num_vint_docs)?;
+    }
+    Ok(())
+}
+
 impl BlockSegmentPostings {
+    /// Opens the postings of `doc_freq` documents.
+    ///
+    /// Returns an error if `data` does not hold their blocks.
     pub(crate) fn from_data(
         doc_freq: usize,
         data: SourceRead,
         freq_reading_option: FreqReadingOption,
-    ) -> BlockSegmentPostings {
-        let num_bitpacked_blocks: usize = (doc_freq as usize) / COMPRESSION_BLOCK_SIZE;
-        let num_vint_docs = (doc_freq as usize) - COMPRESSION_BLOCK_SIZE * num_bitpacked_blocks;
-        BlockSegmentPostings {
+        postings_block_size: PostingsBlockSize,
+    ) -> io::Result<BlockSegmentPostings> {
+        let block_size = postings_block_size.num_docs();
+        let has_freqs = freq_reading_option != FreqReadingOption::NoFreq;
+        check_postings(data.as_ref(), doc_freq, block_size, has_freqs)?;
+        let num_bitpacked_blocks: usize = doc_freq / block_size;
+        let num_vint_docs = doc_freq - block_size * num_bitpacked_blocks;
+        Ok(BlockSegmentPostings {
+            block_size,
             num_bitpacked_blocks,
             num_vint_docs,
             doc_decoder: BlockDecoder::new(),
@@ -356,8 +529,9 @@ impl BlockSegmentPostings {
             freq_reading_option,
             remaining_data: data,
             doc_offset: 0,
+            block_max_impact: 0f32,
             doc_freq,
-        }
+        })
     }
 
     // Resets the block segment postings on another position
@@ -370,14 +544,25 @@ impl BlockSegmentPostings {
     // # Warning
     //
     // This does not reset the positions list.
-    pub(crate) fn reset(&mut self, doc_freq: usize, postings_data: SourceRead) {
-        let num_binpacked_blocks: usize = doc_freq / COMPRESSION_BLOCK_SIZE;
-        let num_vint_docs = doc_freq & (COMPRESSION_BLOCK_SIZE - 1);
+    pub(crate) fn reset(
+        &mut self,
+        doc_freq: usize,
+        postings_data: SourceRead,
+        postings_block_size: PostingsBlockSize,
+    ) -> io::Result<()> {
+        let block_size = postings_block_size.num_docs();
+        let has_freqs = self.freq_reading_option != FreqReadingOption::NoFreq;
+        check_postings(postings_data.as_ref(), doc_freq, block_size, has_freqs)?;
+        let num_binpacked_blocks: usize = doc_freq / block_size;
+        let num_vint_docs = doc_freq - block_size * num_binpacked_blocks;
+        self.block_size = block_size;
         self.num_bitpacked_blocks = num_binpacked_blocks;
         self.num_vint_docs = num_vint_docs;
         self.remaining_data = postings_data;
         self.doc_offset = 0;
+        self.block_max_impact = 0f32;
         self.doc_freq = doc_freq;
+        Ok(())
     }
 
     /// Returns the document frequency associated to this block postings.
@@ -415,40 +600,186 @@ impl BlockSegmentPostings {
         self.freq_decoder.output(idx)
     }
 
+    /// Returns the sum of the term frequencies of the
+    /// documents `start..end` of the current block.
+    ///
+    /// Term frequencies that were not read are considered to be 1.
+    fn sum_freqs(&self, start: usize, end: usize) -> u32 {
+        match self.freq_reading_option {
+            FreqReadingOption::ReadFreq => self.freqs()[start..end].iter().sum(),
+            FreqReadingOption::NoFreq | FreqReadingOption::SkipFreq => (end - start) as u32,
+        }
+    }
+
     /// Returns the length of the current block.
     ///
-    /// All blocks have a length of `NUM_DOCS_PER_BLOCK`,
+    /// All blocks have a length of `PostingsBlockSize::num_docs()`,
     /// except the last block that may have a length
-    /// of any number between 1 and `NUM_DOCS_PER_BLOCK - 1`
+    /// of any number between 1 and `PostingsBlockSize::num_docs() - 1`
     #[inline]
     fn block_len(&self) -> usize {
         self.doc_decoder.output_len
     }
 
+    /// Returns an upper bound of `term_freq / fieldnorm` over the documents
+    /// of the current block.
+    ///
+    /// It is meant to bound the score of the documents of the block,
+    /// in order to skip the blocks that cannot make it to the top-k.
+    ///
+    /// The impact of the last block is only known if it is full.
+    /// Otherwise, its max impact is infinite.
+    #[inline]
+    pub fn block_max_impact(&self) -> f32 {
+        self.block_max_impact
+    }
+
+    /// Returns an upper bound of `term_freq / fieldnorm` over the documents
+    /// of the current block and of the blocks that follow.
+    ///
+    /// Corrupted blocks do not bound the impacts.
+    pub fn max_impact(&self) -> f32 {
+        if self.num_vint_docs > 0 {
+            return f32::INFINITY;
+        }
+        self.try_max_impact().unwrap_or(f32::INFINITY)
+    }
+
+    fn try_max_impact(&self) -> io::Result<f32> {
+        let mut max_impact = self.block_max_impact;
+        let mut data: &[u8] = self.remaining_data.as_ref();
+        for _ in 0..self.num_bitpacked_blocks {
+            if data.len() < BLOCK_HEADER_LEN {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Truncated postings block header",
+                ));
+            }
+            let block_max_impact = f32::from_bits(LittleEndian::read_u32(&data[4..8]));
+            if block_max_impact > max_impact {
+                max_impact = block_max_impact;
+            }
+            data = &data[BLOCK_HEADER_LEN..];
+            data = &data[compressed_blocks_size(data, self.block_size)?..];
+            match self.freq_reading_option {
+                FreqReadingOption::NoFreq => {}
+                FreqReadingOption::SkipFreq | FreqReadingOption::ReadFreq => {
+                    data = &data[compressed_blocks_size(data, self.block_size)?..];
+                }
+            }
+        }
+        Ok(max_impact)
+    }
+
+    /// Marks the postings as exhausted, after failing to decode them.
+    ///
+    /// The blocks are checked when the postings are opened,
+    /// so that this should only happen if the postings file is
+    /// modified afterwards.
+    fn on_corrupted_block(&mut self, err: &io::Error) {
+        warn!("Failed to decode a postings block: {:?}", err);
+        self.num_bitpacked_blocks = 0;
+        self.num_vint_docs = 0;
+        self.doc_decoder.output_len = 0;
+    }
+
+    /// Skips the blocks whose documents are all lower than `target`,
+    /// without decoding them.
+    ///
+    /// The current block is left untouched, but its term frequencies
+    /// are overwritten if `sum_freqs` is true.
+    /// `.advance()` is expected to be called afterwards.
+    ///
+    /// Returns the sum of the term frequencies of the skipped documents
+    /// if `sum_freqs` is true, and 0 otherwise.
+    pub(crate) fn skip_blocks_before(&mut self, target: DocId, sum_freqs: bool) -> u32 {
+        match self.try_skip_blocks_before(target, sum_freqs) {
+            Ok(freqs_skipped) => freqs_skipped,
+            Err(err) => {
+                self.on_corrupted_block(&err);
+                0u32
+            }
+        }
+    }
+
+    fn try_skip_blocks_before(&mut self, target: DocId, sum_freqs: bool) -> io::Result<u32> {
+        let mut freqs_skipped = 0u32;
+        while self.num_bitpacked_blocks > 0 {
+            let last_doc = LittleEndian::read_u32(&self.remaining_data.as_ref()[..4]);
+            if last_doc >= target {
+                break;
+            }
+            self.remaining_data.advance(BLOCK_HEADER_LEN);
+            let num_bytes_to_skip =
+                compressed_blocks_size(self.remaining_data.as_ref(), self.block_size)?;
+            self.remaining_data.advance(num_bytes_to_skip);
+            match self.freq_reading_option {
+                FreqReadingOption::NoFreq => {
+                    // term frequencies that are not recorded are read as 1.
+                    if sum_freqs {
+                        freqs_skipped += self.block_size as u32;
+                    }
+                }
+                FreqReadingOption::ReadFreq if sum_freqs => {
+                    let num_consumed_bytes = self.freq_decoder
+                        .uncompress_blocks_unsorted(self.remaining_data.as_ref(), self.block_size)?;
+                    self.remaining_data.advance(num_consumed_bytes);
+                    freqs_skipped += self.freq_decoder.output_array().iter().sum::<u32>();
+                }
+                FreqReadingOption::SkipFreq | FreqReadingOption::ReadFreq => {
+                    let num_bytes_to_skip =
+                        compressed_blocks_size(self.remaining_data.as_ref(), self.block_size)?;
+                    self.remaining_data.advance(num_bytes_to_skip);
+                }
+            }
+            self.doc_offset = last_doc;
+            self.num_bitpacked_blocks -= 1;
+        }
+        Ok(freqs_skipped)
+    }
+
     /// Advance to the next block.
     ///
     /// Returns false iff there was no remaining blocks.
     pub fn advance(&mut self) -> bool {
+        match self.try_advance() {
+            Ok(advanced) => advanced,
+            Err(err) => {
+                self.on_corrupted_block(&err);
+                false
+            }
+        }
+    }
+
+    fn try_advance(&mut self) -> io::Result<bool> {
         if self.num_bitpacked_blocks > 0 {
+            let block_max_impact_bits = LittleEndian::read_u32(&self.remaining_data.as_ref()[4..8]);
+            self.block_max_impact = f32::from_bits(block_max_impact_bits);
+            self.remaining_data.advance(BLOCK_HEADER_LEN);
             let num_consumed_bytes = self.doc_decoder
-                .uncompress_block_sorted(self.remaining_data.as_ref(), self.doc_offset);
+                .uncompress_blocks_sorted(
+                    self.remaining_data.as_ref(),
+                    self.doc_offset,
+                    self.block_size,
+                )?;
             self.remaining_data.advance(num_consumed_bytes);
             match self.freq_reading_option {
                 FreqReadingOption::NoFreq => {}
                 FreqReadingOption::SkipFreq => {
-                    let num_bytes_to_skip = compressed_block_size(self.remaining_data.as_ref()[0]);
+                    let num_bytes_to_skip =
+                        compressed_blocks_size(self.remaining_data.as_ref(), self.block_size)?;
                     self.remaining_data.advance(num_bytes_to_skip);
                 }
                 FreqReadingOption::ReadFreq => {
                     let num_consumed_bytes = self.freq_decoder
-                        .uncompress_block_unsorted(self.remaining_data.as_ref());
+                        .uncompress_blocks_unsorted(self.remaining_data.as_ref(), self.block_size)?;
                     self.remaining_data.advance(num_consumed_bytes);
                 }
             }
             // it will be used as the next offset.
-            self.doc_offset = self.doc_decoder.output(COMPRESSION_BLOCK_SIZE - 1);
+            self.doc_offset = self.doc_decoder.output(self.block_size - 1);
             self.num_bitpacked_blocks -= 1;
-            true
+            Ok(true)
         } else if self.num_vint_docs > 0 {
             let num_compressed_bytes = self.doc_decoder.uncompress_vint_sorted(
                 self.remaining_data.as_ref(),
@@ -463,16 +794,18 @@ impl BlockSegmentPostings {
                         .uncompress_vint_unsorted(self.remaining_data.as_ref(), self.num_vint_docs);
                 }
             }
+            self.block_max_impact = f32::INFINITY;
             self.num_vint_docs = 0;
-            true
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     }
 
     /// Returns an empty segment postings object
     pub fn empty() -> BlockSegmentPostings {
         BlockSegmentPostings {
+            block_size: PostingsBlockSize::default().num_docs(),
             num_bitpacked_blocks: 0,
             num_vint_docs: 0,
 
@@ -482,6 +815,7 @@ impl BlockSegmentPostings {
 
             remaining_data: From::from(ReadOnlySource::empty()),
             doc_offset: 0,
+            block_max_impact: 0f32,
             doc_freq: 0,
         }
     }
@@ -512,6 +846,9 @@ mod tests {
     use schema::IndexRecordOption;
     use common::HasLen;
     use super::BlockSegmentPostings;
+    use directory::{ReadOnlySource, SourceRead};
+    use postings::serializer::PostingsSerializer;
+    use postings::{FreqReadingOption, PostingsBlockSize};
 
     #[test]
     fn test_empty_segment_postings() {
@@ -528,6 +865,39 @@ mod tests {
         assert_eq!(postings.doc_freq(), 0);
     }
 
+    #[test]
+    fn test_block_segment_postings_sparse_docs() {
+        // a bitpacked block of 256 docs, followed by 255 vint encoded docs
+        // of 4 bytes each.
+        let docs: Vec<u32> = (0u32..511).map(|i| i << 22).collect();
+        let mut buffer = Vec::new();
+        {
+            let mut postings_serializer =
+                PostingsSerializer::new(&mut buffer, true, PostingsBlockSize::Docs256);
+            for (i, &doc) in docs.iter().enumerate() {
+                postings_serializer
+                    .write_doc(doc, 1 + (i % 3) as u32, 1u64)
+                    .unwrap();
+            }
+            postings_serializer.close_term().unwrap();
+        }
+        let mut block_postings = BlockSegmentPostings::from_data(
+            docs.len(),
+            SourceRead::from(ReadOnlySource::from(buffer)),
+            FreqReadingOption::ReadFreq,
+            PostingsBlockSize::Docs256,
+        ).unwrap();
+        let mut decoded_docs = Vec::new();
+        let mut decoded_freqs = Vec::new();
+        while block_postings.advance() {
+            decoded_docs.extend_from_slice(block_postings.docs());
+            decoded_freqs.extend_from_slice(block_postings.freqs());
+        }
+        assert_eq!(decoded_docs, docs);
+        let freqs: Vec<u32> = (0..511).map(|i| 1 + (i % 3) as u32).collect();
+        assert_eq!(decoded_freqs, freqs);
+    }
+
     #[test]
     fn test_block_segment_postings() {
         let mut schema_builder = SchemaBuilder::default();
@@ -543,11 +913,12 @@ mod tests {
         index.load_searchers().unwrap();
         let searcher = index.searcher();
         let segment_reader = searcher.segment_reader(0);
-        let inverted_index = segment_reader.inverted_index(int_field);
+        let inverted_index = segment_reader.inverted_index(int_field).unwrap();
         let term = Term::from_field_u64(int_field, 0u64);
         let term_info = inverted_index.get_term_info(&term).unwrap();
-        let mut block_segments =
-            inverted_index.read_block_postings_from_terminfo(&term_info, IndexRecordOption::Basic);
+        let mut block_segments = inverted_index
+            .read_block_postings_from_terminfo(&term_info, IndexRecordOption::Basic)
+            .unwrap();
         let mut offset: u32 = 0u32;
         // checking that the block before calling advance is empty
         assert!(block_segments.docs().is_empty());
@@ -582,18 +953,21 @@ mod tests {
         let mut block_segments;
         {
             let term = Term::from_field_u64(int_field, 0u64);
-            let inverted_index = segment_reader.inverted_index(int_field);
+            let inverted_index = segment_reader.inverted_index(int_field).unwrap();
             let term_info = inverted_index.get_term_info(&term).unwrap();
             block_segments = inverted_index
-                .read_block_postings_from_terminfo(&term_info, IndexRecordOption::Basic);
+                .read_block_postings_from_terminfo(&term_info, IndexRecordOption::Basic)
+                .unwrap();
         }
         assert!(block_segments.advance());
         assert_eq!(block_segments.docs(), &[0, 2, 4]);
         {
             let term = Term::from_field_u64(int_field, 1u64);
-            let inverted_index = segment_reader.inverted_index(int_field);
+            let inverted_index = segment_reader.inverted_index(int_field).unwrap();
             let term_info = inverted_index.get_term_info(&term).unwrap();
-            inverted_index.reset_block_postings_from_terminfo(&term_info, &mut block_segments);
+            inverted_index
+                .reset_block_postings_from_terminfo(&term_info, &mut block_segments)
+                .unwrap();
         }
         assert!(block_segments.advance());
         assert_eq!(block_segments.docs(), &[1, 3, 5]);
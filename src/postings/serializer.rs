@@ -7,12 +7,16 @@ use schema::FieldType;
 use schema::Schema;
 use directory::WritePtr;
 use compression::{BlockEncoder, COMPRESSION_BLOCK_SIZE};
+use super::PostingsBlockSize;
 use DocId;
 use core::Segment;
 use std::io::{self, Write};
 use compression::VIntEncoder;
 use common::CountingWriter;
 use common::CompositeWrite;
+use common::BinarySerializable;
+use byteorder::{ByteOrder, LittleEndian};
+use std::f32;
 use termdict::TermDictionaryBuilder;
 
 /// `PostingsSerializer` is in charge of serializing
@@ -46,11 +50,16 @@ use termdict::TermDictionaryBuilder;
 ///
 /// A description of the serialization format is
 /// [available here](https://fulmicoton.gitbooks.io/tantivy-doc/content/inverted-index.html).
+///
+/// The number of documents of the postings blocks of each field
+/// is written as a `u32` header, in the postings composite file
+/// at index 1 of the field. It is 128 if the header is missing.
 pub struct InvertedIndexSerializer {
     terms_write: CompositeWrite<WritePtr>,
     postings_write: CompositeWrite<WritePtr>,
     positions_write: CompositeWrite<WritePtr>,
     schema: Schema,
+    postings_block_size: PostingsBlockSize,
 }
 
 impl InvertedIndexSerializer {
@@ -60,12 +69,14 @@ impl InvertedIndexSerializer {
         postings_write: CompositeWrite<WritePtr>,
         positions_write: CompositeWrite<WritePtr>,
         schema: Schema,
+        postings_block_size: PostingsBlockSize,
     ) -> Result<InvertedIndexSerializer> {
         Ok(InvertedIndexSerializer {
             terms_write,
             postings_write,
             positions_write,
             schema,
+            postings_block_size,
         })
     }
 
@@ -77,6 +88,7 @@ impl InvertedIndexSerializer {
             CompositeWrite::wrap(segment.open_write(POSTINGS)?),
             CompositeWrite::wrap(segment.open_write(POSITIONS)?),
             segment.schema(),
+            segment.index().settings().postings_block_size,
         )
     }
 
@@ -84,8 +96,19 @@ impl InvertedIndexSerializer {
     /// a given field.
     ///
     /// Loads the indexing options for the given field.
-    pub fn new_field(&mut self, field: Field) -> io::Result<FieldSerializer> {
+    ///
+    /// `fieldnorms` are the fieldnorms of the field, indexed by doc id.
+    /// They are used to compute the maximum impact of each block of postings.
+    /// The documents that are out of its bounds are given a fieldnorm of 1.
+    pub fn new_field<'a>(
+        &'a mut self,
+        field: Field,
+        fieldnorms: &'a [u64],
+    ) -> io::Result<FieldSerializer<'a>> {
         let field_entry: &FieldEntry = self.schema.get_field_entry(field);
+        let postings_block_size = self.postings_block_size;
+        (postings_block_size.num_docs() as u32)
+            .serialize(self.postings_write.for_field_with_idx(field, 1))?;
         let term_dictionary_write = self.terms_write.for_field(field);
         let postings_write = self.postings_write.for_field(field);
         let positions_write = self.positions_write.for_field(field);
@@ -94,6 +117,8 @@ impl InvertedIndexSerializer {
             term_dictionary_write,
             postings_write,
             positions_write,
+            fieldnorms,
+            postings_block_size,
         )
     }
 
@@ -112,8 +137,10 @@ pub struct FieldSerializer<'a> {
     term_dictionary_builder: TermDictionaryBuilderImpl<&'a mut CountingWriter<WritePtr>>,
     postings_serializer: PostingsSerializer<&'a mut CountingWriter<WritePtr>>,
     positions_serializer_opt: Option<PositionSerializer<&'a mut CountingWriter<WritePtr>>>,
+    fieldnorms: &'a [u64],
     current_term_info: TermInfo,
     term_open: bool,
+    pulsing_enabled: bool,
 }
 
 impl<'a> FieldSerializer<'a> {
@@ -122,6 +149,8 @@ impl<'a> FieldSerializer<'a> {
         term_dictionary_write: &'a mut CountingWriter<WritePtr>,
         postings_write: &'a mut CountingWriter<WritePtr>,
         positions_write: &'a mut CountingWriter<WritePtr>,
+        fieldnorms: &'a [u64],
+        postings_block_size: PostingsBlockSize,
     ) -> io::Result<FieldSerializer<'a>> {
         let (term_freq_enabled, position_enabled): (bool, bool) = match field_type {
             FieldType::Str(ref text_options) => {
@@ -139,7 +168,8 @@ impl<'a> FieldSerializer<'a> {
         };
         let term_dictionary_builder =
             TermDictionaryBuilderImpl::new(term_dictionary_write, field_type)?;
-        let postings_serializer = PostingsSerializer::new(postings_write, term_freq_enabled);
+        let postings_serializer =
+            PostingsSerializer::new(postings_write, term_freq_enabled, postings_block_size);
         let positions_serializer_opt = if position_enabled {
             Some(PositionSerializer::new(positions_write))
         } else {
@@ -150,8 +180,12 @@ impl<'a> FieldSerializer<'a> {
             term_dictionary_builder,
             postings_serializer,
             positions_serializer_opt,
+            fieldnorms,
             current_term_info: TermInfo::default(),
             term_open: false,
+            // A single posting without term frequency nor positions
+            // is entirely described by its doc id.
+            pulsing_enabled: !term_freq_enabled && !position_enabled,
         })
     }
 
@@ -165,6 +199,7 @@ impl<'a> FieldSerializer<'a> {
             postings_offset: self.postings_serializer.addr(),
             positions_offset: filepos,
             positions_inner_offset: offset,
+            inlined_doc: None,
         }
     }
 
@@ -190,6 +225,12 @@ impl<'a> FieldSerializer<'a> {
     /// For instance, if the positions are `2, 3, 17`,
     /// `position_deltas` is `2, 1, 14`
     ///
+    /// If the field records offsets, each position delta is followed by
+    /// the delta of the `offset_from` of the occurrence with respect to the one
+    /// of the previous occurrence (with wrapping arithmetic), and the length
+    /// `offset_to - offset_from` of the occurrence.
+    /// `position_deltas` then contains `3 * term_freq` values.
+    ///
     /// Term frequencies and positions may be ignored by the serializer depending
     /// on the configuration of the field in the `Schema`.
     pub fn write_doc(
@@ -199,7 +240,12 @@ impl<'a> FieldSerializer<'a> {
         position_deltas: &[u32],
     ) -> io::Result<()> {
         self.current_term_info.doc_freq += 1;
-        self.postings_serializer.write_doc(doc_id, term_freq)?;
+        let fieldnorm = self.fieldnorms
+            .get(doc_id as usize)
+            .cloned()
+            .unwrap_or(1u64);
+        self.postings_serializer
+            .write_doc(doc_id, term_freq, fieldnorm)?;
         if let Some(ref mut positions_serializer) = self.positions_serializer_opt.as_mut() {
             positions_serializer.write(position_deltas)?;
         }
@@ -210,11 +256,20 @@ impl<'a> FieldSerializer<'a> {
     ///
     /// If the current block is incomplete, it need to be encoded
     /// using `VInt` encoding.
+    ///
+    /// If the field does not record term frequencies nor positions,
+    /// the posting list of a term appearing in a single document is
+    /// inlined in its `TermInfo` instead.
     pub fn close_term(&mut self) -> io::Result<()> {
         if self.term_open {
+            if self.pulsing_enabled && self.current_term_info.doc_freq == 1 {
+                let doc = self.postings_serializer.pop_single_doc();
+                self.current_term_info.inlined_doc = Some(doc);
+            } else {
+                self.postings_serializer.close_term()?;
+            }
             self.term_dictionary_builder
                 .insert_value(&self.current_term_info)?;
-            self.postings_serializer.close_term()?;
             self.term_open = false;
         }
         Ok(())
@@ -232,51 +287,93 @@ impl<'a> FieldSerializer<'a> {
     }
 }
 
+/// Serializes the postings of a term.
+///
+/// The docs are encoded by blocks of `PostingsBlockSize::num_docs()` docs.
+/// Each bitpacked block is preceded by a header holding
+/// - the last doc of the block, as a little endian `u32`.
+/// - the maximum impact of the block, i.e. the maximum value of
+///   `term_freq / fieldnorm` over its documents, as the bits of a little endian `f32`.
+///
+/// This header makes it possible to skip a block without decoding it,
+/// and to bound the score of its documents.
+///
+/// The remaining documents are vint-encoded, without any header.
 pub struct PostingsSerializer<W: Write> {
     postings_write: CountingWriter<W>,
     last_doc_id_encoded: u32,
 
     block_encoder: BlockEncoder,
+    block_buffer: Vec<u8>,
+    block_size: usize,
     doc_ids: Vec<DocId>,
     term_freqs: Vec<u32>,
+    block_max_impact: f32,
 
     termfreq_enabled: bool,
 }
 
 impl<W: Write> PostingsSerializer<W> {
-    pub fn new(write: W, termfreq_enabled: bool) -> PostingsSerializer<W> {
+    pub fn new(
+        write: W,
+        termfreq_enabled: bool,
+        block_size: PostingsBlockSize,
+    ) -> PostingsSerializer<W> {
         PostingsSerializer {
             postings_write: CountingWriter::wrap(write),
 
             block_encoder: BlockEncoder::new(),
+            block_buffer: vec![],
+            block_size: block_size.num_docs(),
             doc_ids: vec![],
             term_freqs: vec![],
+            block_max_impact: 0f32,
 
             last_doc_id_encoded: 0u32,
             termfreq_enabled,
         }
     }
 
-    pub fn write_doc(&mut self, doc_id: DocId, term_freq: u32) -> io::Result<()> {
+    pub fn write_doc(&mut self, doc_id: DocId, term_freq: u32, fieldnorm: u64) -> io::Result<()> {
         self.doc_ids.push(doc_id);
-        if self.termfreq_enabled {
+        // when term frequencies are not recorded, they are read as 1.
+        let effective_term_freq = if self.termfreq_enabled {
             self.term_freqs.push(term_freq as u32);
+            term_freq
+        } else {
+            1u32
+        };
+        let impact = effective_term_freq as f32 / fieldnorm as f32;
+        // `NaN` impacts are recorded as infinite, so that they never prune anything.
+        if impact.is_nan() {
+            self.block_max_impact = f32::INFINITY;
+        } else if impact > self.block_max_impact {
+            self.block_max_impact = impact;
         }
-        if self.doc_ids.len() == COMPRESSION_BLOCK_SIZE {
+        if self.doc_ids.len() == self.block_size {
             {
-                // encode the doc ids
-                let block_encoded: &[u8] = self.block_encoder
-                    .compress_block_sorted(&self.doc_ids, self.last_doc_id_encoded);
-                self.last_doc_id_encoded = self.doc_ids[self.doc_ids.len() - 1];
-                self.postings_write.write_all(block_encoded)?;
+                // write the block header
+                let mut header = [0u8; 8];
+                LittleEndian::write_u32(&mut header[..4], doc_id);
+                LittleEndian::write_u32(&mut header[4..], self.block_max_impact.to_bits());
+                self.postings_write.write_all(&header)?;
+                self.block_max_impact = 0f32;
             }
+            self.block_buffer.clear();
+            // encode the doc ids
+            self.block_encoder.compress_blocks_sorted(
+                &self.doc_ids,
+                self.last_doc_id_encoded,
+                &mut self.block_buffer,
+            );
+            self.last_doc_id_encoded = self.doc_ids[self.doc_ids.len() - 1];
             if self.termfreq_enabled {
                 // encode the term_freqs
-                let block_encoded: &[u8] =
-                    self.block_encoder.compress_block_unsorted(&self.term_freqs);
-                self.postings_write.write_all(block_encoded)?;
+                self.block_encoder
+                    .compress_blocks_unsorted(&self.term_freqs, &mut self.block_buffer);
                 self.term_freqs.clear();
             }
+            self.postings_write.write_all(&self.block_buffer)?;
             self.doc_ids.clear();
         }
         Ok(())
@@ -307,6 +404,16 @@ impl<W: Write> PostingsSerializer<W> {
         Ok(())
     }
 
+    /// Removes and returns the only doc id of the current term.
+    ///
+    /// Nothing gets written for this term.
+    fn pop_single_doc(&mut self) -> DocId {
+        assert_eq!(self.doc_ids.len(), 1);
+        let doc = self.doc_ids[0];
+        self.clear();
+        doc
+    }
+
     fn close(mut self) -> io::Result<()> {
         self.postings_write.flush()
     }
@@ -318,10 +425,14 @@ impl<W: Write> PostingsSerializer<W> {
     fn clear(&mut self) {
         self.doc_ids.clear();
         self.term_freqs.clear();
+        self.block_max_impact = 0f32;
         self.last_doc_id_encoded = 0;
     }
 }
 
+/// Writes position deltas as bitpacked blocks.
+///
+/// See `PositionReader` for the format.
 struct PositionSerializer<W: Write> {
     buffer: Vec<u32>,
     write: CountingWriter<W>, // See if we can offset the original counting writer.
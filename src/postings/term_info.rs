@@ -1,5 +1,6 @@
 use common::{BinarySerializable, FixedSize};
 use std::io;
+use DocId;
 
 /// `TermInfo` contains all of the information
 /// associated to terms in the `.term` file.
@@ -11,6 +12,14 @@ use std::io;
 /// * `postings_offset` : an offset in the `.idx` file
 /// addressing the start of the posting list associated
 /// to this term.
+/// * `inlined_doc` : the only document containing the term,
+/// if its posting list was inlined in the term info.
+///
+/// The posting list of a term that appears in a single document is
+/// inlined in the term info ("pulsing") for the fields that do not record
+/// term frequencies nor positions. Nothing is written in the `.idx` file
+/// for such terms, saving a seek when reading their postings.
+/// This typically happens for fields storing unique ids.
 #[derive(Debug, Default, Ord, PartialOrd, Eq, PartialEq, Clone)]
 pub struct TermInfo {
     /// Number of documents in the segment containing the term
@@ -21,6 +30,25 @@ pub struct TermInfo {
     pub positions_offset: u64,
     /// Offset within the position block.
     pub positions_inner_offset: u8,
+    /// The only document containing the term, if its posting list
+    /// is inlined in the term info.
+    pub inlined_doc: Option<DocId>,
+}
+
+impl TermInfo {
+    /// Encodes `inlined_doc` as a `u32`.
+    ///
+    /// `0` means that no document is inlined, and `doc + 1`
+    /// encodes the inlined document `doc`.
+    pub(crate) fn inlined_doc_code(&self) -> u32 {
+        self.inlined_doc.map(|doc| doc + 1).unwrap_or(0u32)
+    }
+
+    /// Decodes `inlined_doc` from its `u32` code.
+    /// (See `.inlined_doc_code()`)
+    pub(crate) fn set_inlined_doc_code(&mut self, code: u32) {
+        self.inlined_doc = if code == 0 { None } else { Some(code - 1) };
+    }
 }
 
 impl FixedSize for TermInfo {
@@ -29,7 +57,8 @@ impl FixedSize for TermInfo {
     /// of the block are bitpacked.
     ///
     /// See `TermInfoStore`.
-    const SIZE_IN_BYTES: usize = u32::SIZE_IN_BYTES + 2 * u64::SIZE_IN_BYTES + u8::SIZE_IN_BYTES;
+    const SIZE_IN_BYTES: usize =
+        2 * u32::SIZE_IN_BYTES + 2 * u64::SIZE_IN_BYTES + u8::SIZE_IN_BYTES;
 }
 
 impl BinarySerializable for TermInfo {
@@ -37,7 +66,8 @@ impl BinarySerializable for TermInfo {
         self.doc_freq.serialize(writer)?;
         self.postings_offset.serialize(writer)?;
         self.positions_offset.serialize(writer)?;
-        self.positions_inner_offset.serialize(writer)
+        self.positions_inner_offset.serialize(writer)?;
+        self.inlined_doc_code().serialize(writer)
     }
 
     fn deserialize<R: io::Read>(reader: &mut R) -> io::Result<Self> {
@@ -45,12 +75,15 @@ impl BinarySerializable for TermInfo {
         let postings_offset = u64::deserialize(reader)?;
         let positions_offset = u64::deserialize(reader)?;
         let positions_inner_offset = u8::deserialize(reader)?;
-        Ok(TermInfo {
+        let mut term_info = TermInfo {
             doc_freq,
             postings_offset,
             positions_offset,
             positions_inner_offset,
-        })
+            inlined_doc: None,
+        };
+        term_info.set_inlined_doc_code(u32::deserialize(reader)?);
+        Ok(term_info)
     }
 }
 
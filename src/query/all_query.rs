@@ -7,10 +7,11 @@ use Result;
 use Score;
 use DocId;
 use core::Searcher;
+use fastfield::DocIdsAlive;
 
 /// Query that matches all of the documents.
 ///
-/// All of the document get the score 1f32.
+/// All of the document get the score 1.0.
 #[derive(Debug)]
 pub struct AllQuery;
 
@@ -26,28 +27,28 @@ pub struct AllWeight;
 impl Weight for AllWeight {
     fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
         Ok(box AllScorer {
-            started: false,
+            doc_ids_alive: reader.doc_ids_alive(),
             doc: 0u32,
-            max_doc: reader.max_doc(),
+            num_docs: reader.num_docs(),
         })
     }
 }
 
 /// Scorer associated to the `AllQuery` query.
 pub struct AllScorer {
-    started: bool,
+    doc_ids_alive: DocIdsAlive,
     doc: DocId,
-    max_doc: DocId,
+    num_docs: DocId,
 }
 
 impl DocSet for AllScorer {
     fn advance(&mut self) -> bool {
-        if self.started {
-            self.doc += 1u32;
+        if let Some(doc) = self.doc_ids_alive.next() {
+            self.doc = doc;
+            true
         } else {
-            self.started = true;
+            false
         }
-        self.doc < self.max_doc
     }
 
     fn doc(&self) -> DocId {
@@ -55,12 +56,44 @@ impl DocSet for AllScorer {
     }
 
     fn size_hint(&self) -> u32 {
-        self.max_doc
+        self.num_docs
     }
 }
 
 impl Scorer for AllScorer {
     fn score(&mut self) -> Score {
-        1f32
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::AllQuery;
+    use collector::tests::TestCollector;
+    use query::Query;
+    use schema::{SchemaBuilder, Term, STRING};
+    use Index;
+
+    #[test]
+    fn test_all_query_skips_deleted_docs() {
+        let mut schema_builder = SchemaBuilder::default();
+        let field = schema_builder.add_text_field("id", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for id in &["a", "b", "c", "d"] {
+                index_writer.add_document(doc!(field => *id));
+            }
+            index_writer.commit().unwrap();
+            index_writer.delete_term(Term::from_field_text(field, "b"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut test_collector = TestCollector::default();
+        searcher.search(&AllQuery, &mut test_collector).unwrap();
+        assert_eq!(test_collector.docs(), vec![0, 2, 3]);
+        assert_eq!(AllQuery.count(&*searcher).unwrap(), 3);
     }
 }
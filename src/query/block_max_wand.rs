@@ -0,0 +1,328 @@
+use docset::{DocSet, SkipResult};
+use collector::Collector;
+use query::Scorer;
+use query::term_query::TermScorer;
+use std::f32;
+use DocId;
+use Score;
+
+struct WandTerm {
+    // upper bound of the score of the remaining documents.
+    max_score: Score,
+    scorer: TermScorer,
+}
+
+impl WandTerm {
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+}
+
+/// Union of `TermScorer`s, summing their scores, that skips the
+/// documents that cannot make it to the top-k.
+///
+/// It implements the Block-Max WAND algorithm:
+/// each term comes with an upper bound of its score over
+/// the whole postings list, and over each block of postings.
+/// (See `BlockSegmentPostings::block_max_impact()`)
+///
+/// The documents whose upper bound is lower than the score of the worst
+/// document of the collector are skipped.
+/// (See `Collector::min_competitive_score()`)
+///
+/// When it is not driven by a collector, through `.collect(...)`,
+/// it behaves as a regular union.
+pub struct BlockMaxWand {
+    // sorted by doc id.
+    terms: Vec<WandTerm>,
+    threshold: Score,
+    // number of terms positioned on the current doc.
+    num_current_terms: usize,
+    doc: DocId,
+    score: Score,
+}
+
+impl From<Vec<TermScorer>> for BlockMaxWand {
+    fn from(scorers: Vec<TermScorer>) -> BlockMaxWand {
+        let terms: Vec<WandTerm> = scorers
+            .into_iter()
+            .flat_map(|mut scorer| {
+                if scorer.advance() {
+                    Some(WandTerm {
+                        max_score: scorer.max_score(),
+                        scorer,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let mut block_max_wand = BlockMaxWand {
+            terms,
+            threshold: f32::NEG_INFINITY as Score,
+            num_current_terms: 0,
+            doc: 0,
+            score: 0.0,
+        };
+        block_max_wand.sort_terms();
+        block_max_wand
+    }
+}
+
+impl BlockMaxWand {
+    fn sort_terms(&mut self) {
+        self.terms.sort_by_key(WandTerm::doc);
+    }
+
+    /// Skips the `num_terms` first terms to `target`,
+    /// removing the terms that are exhausted.
+    fn skip_terms(&mut self, num_terms: usize, target: DocId) {
+        let mut ord = 0;
+        for _ in 0..num_terms {
+            let remove = self.terms[ord].doc() < target
+                && self.terms[ord].scorer.skip_next(target) == SkipResult::End;
+            if remove {
+                self.terms.remove(ord);
+            } else {
+                ord += 1;
+            }
+        }
+        self.sort_terms();
+    }
+
+    fn set_threshold(&mut self, threshold: Score) {
+        if threshold > self.threshold {
+            self.threshold = threshold;
+        }
+    }
+}
+
+impl DocSet for BlockMaxWand {
+    fn advance(&mut self) -> bool {
+        if self.num_current_terms > 0 {
+            let next_doc = self.doc + 1;
+            let num_current_terms = self.num_current_terms;
+            self.num_current_terms = 0;
+            self.skip_terms(num_current_terms, next_doc);
+        }
+        loop {
+            // the pivot is the first term such that the documents lower
+            // than its doc can only reach a score lower than the threshold.
+            let mut upper_bound = 0.0;
+            let pivot_opt = self.terms.iter().position(|term| {
+                upper_bound += term.max_score;
+                upper_bound >= self.threshold
+            });
+            let pivot = match pivot_opt {
+                Some(pivot) => pivot,
+                None => {
+                    self.terms.clear();
+                    return false;
+                }
+            };
+            let pivot_doc = self.terms[pivot].doc();
+            if self.terms[0].doc() < pivot_doc {
+                self.skip_terms(pivot, pivot_doc);
+                continue;
+            }
+            let num_current_terms = self.terms
+                .iter()
+                .take_while(|term| term.doc() == pivot_doc)
+                .count();
+            let block_upper_bound: Score = self.terms[..num_current_terms]
+                .iter()
+                .map(|term| term.scorer.block_max_score())
+                .sum();
+            if block_upper_bound < self.threshold {
+                // none of the documents before the end of the current blocks
+                // may be competitive.
+                let mut next_doc = self.terms[..num_current_terms]
+                    .iter()
+                    .map(|term| term.scorer.postings().block_last_doc() + 1)
+                    .min()
+                    .unwrap_or(pivot_doc + 1);
+                if let Some(next_term) = self.terms.get(num_current_terms) {
+                    if next_term.doc() < next_doc {
+                        next_doc = next_term.doc();
+                    }
+                }
+                self.skip_terms(num_current_terms, next_doc);
+                continue;
+            }
+            self.doc = pivot_doc;
+            self.score = self.terms[..num_current_terms]
+                .iter_mut()
+                .map(|term| term.scorer.score())
+                .sum();
+            self.num_current_terms = num_current_terms;
+            return true;
+        }
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.terms
+            .iter()
+            .map(|term| term.scorer.size_hint())
+            .max()
+            .unwrap_or(0u32)
+    }
+}
+
+impl Scorer for BlockMaxWand {
+    fn score(&mut self) -> Score {
+        self.score
+    }
+
+    fn collect(&mut self, collector: &mut Collector) {
+        if let Some(min_score) = collector.min_competitive_score() {
+            self.set_threshold(min_score);
+        }
+        while self.advance() {
+            collector.collect(self.doc, self.score);
+            if let Some(min_score) = collector.min_competitive_score() {
+                self.set_threshold(min_score);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::BlockMaxWand;
+    use collector::{Collector, TopCollector};
+    use docset::DocSet;
+    use query::{Scorer, TermQuery};
+    use query::term_query::TermScorer;
+    use query::Union;
+    use query::score_combiner::SumCombiner;
+    use schema::{IndexRecordOption, SchemaBuilder, Term, TEXT};
+    use Index;
+    use Searcher;
+    use {DocId, Result, Score, SegmentLocalId, SegmentReader};
+
+    // Top collector counting the documents it receives.
+    struct CountingTopCollector {
+        top_collector: TopCollector,
+        count: usize,
+    }
+
+    impl Collector for CountingTopCollector {
+        fn set_segment(
+            &mut self,
+            segment_id: SegmentLocalId,
+            reader: &SegmentReader,
+        ) -> Result<()> {
+            self.top_collector.set_segment(segment_id, reader)
+        }
+
+        fn collect(&mut self, doc: DocId, score: Score) {
+            self.count += 1;
+            self.top_collector.collect(doc, score);
+        }
+
+        fn requires_scoring(&self) -> bool {
+            true
+        }
+
+        fn min_competitive_score(&self) -> Option<Score> {
+            self.top_collector.min_competitive_score()
+        }
+    }
+
+    fn term_scorers(searcher: &Searcher, terms: &[Term]) -> Vec<TermScorer> {
+        let segment_reader = searcher.segment_reader(0);
+        terms
+            .iter()
+            .map(|term| {
+                TermQuery::new(term.clone(), IndexRecordOption::WithFreqs)
+                    .specialized_weight(searcher, true)
+                    .unwrap()
+                    .specialized_scorer(segment_reader)
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    fn create_index() -> (Index, Vec<Term>) {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0u32..3_000 {
+                let mut text = String::new();
+                if i % 2 == 0 {
+                    text.push_str(" a");
+                }
+                if i % 3 == 0 {
+                    text.push_str(" b b");
+                }
+                if i % 7 == 0 {
+                    text.push_str(" c");
+                }
+                for _ in 0..(i * 13) % 11 {
+                    text.push_str(" d");
+                }
+                index_writer.add_document(doc!(text_field => text));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let terms = ["a", "b", "c"]
+            .iter()
+            .map(|text| Term::from_field_text(text_field, text))
+            .collect();
+        (index, terms)
+    }
+
+    #[test]
+    fn test_block_max_wand_without_threshold() {
+        let (index, terms) = create_index();
+        let searcher = index.searcher();
+        let mut union = Union::<TermScorer, SumCombiner>::from(term_scorers(&searcher, &terms));
+        let mut block_max_wand = BlockMaxWand::from(term_scorers(&searcher, &terms));
+        while union.advance() {
+            assert!(block_max_wand.advance());
+            assert_eq!(block_max_wand.doc(), union.doc());
+            assert!((block_max_wand.score() - union.score()).abs() < 1e-5);
+        }
+        assert!(!block_max_wand.advance());
+    }
+
+    #[test]
+    fn test_block_max_wand_top_k() {
+        let (index, terms) = create_index();
+        let searcher = index.searcher();
+        for &limit in &[1, 10, 100] {
+            let mut expected_collector = TopCollector::with_limit(limit);
+            {
+                let mut union =
+                    Union::<TermScorer, SumCombiner>::from(term_scorers(&searcher, &terms));
+                while union.advance() {
+                    let (doc, score) = (union.doc(), union.score());
+                    expected_collector.collect(doc, score);
+                }
+            }
+            let mut collector = CountingTopCollector {
+                top_collector: TopCollector::with_limit(limit),
+                count: 0,
+            };
+            BlockMaxWand::from(term_scorers(&searcher, &terms)).collect(&mut collector);
+            // the union matches 2_143 documents.
+            assert!(collector.count < 2_000);
+            // many documents share the same score, so only the scores are compared.
+            let expected_score_docs = expected_collector.score_docs();
+            let score_docs = collector.top_collector.score_docs();
+            assert_eq!(score_docs.len(), expected_score_docs.len());
+            for (score_doc, expected_score_doc) in score_docs.iter().zip(&expected_score_docs) {
+                assert!((score_doc.0 - expected_score_doc.0).abs() < 1e-5);
+            }
+        }
+    }
+}
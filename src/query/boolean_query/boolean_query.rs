@@ -7,41 +7,194 @@ use schema::Term;
 use query::TermQuery;
 use schema::IndexRecordOption;
 use query::Occur;
+use query::WeightCache;
+use query::{BoostWeight, EmptyQuery};
+use Score;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 
 /// The boolean query combines a set of queries
 ///
 /// The documents matched by the boolean query are
 /// those which
 /// * match all of the sub queries associated with the
-/// `Must` occurence
+/// `Must` or the `Filter` occurence
 /// * match none of the sub queries associated with the
 /// `MustNot` occurence.
 /// * match at least one of the subqueries that is not
 /// a `MustNot` occurence.
+///
+/// The subqueries associated with the `Filter` occurence do not
+/// contribute to the score. Their weight is created with scoring
+/// disabled.
+///
+/// If a minimum number of `Should` clauses to match is set,
+/// the documents also need to match at least that many of the
+/// subqueries associated with the `Should` occurence.
+///
+/// Identical subqueries appearing several times in the
+/// query tree share their scorer: it is only advanced once
+/// per document, and its score is only computed once per document.
+///
+/// Identical clauses, i.e. clauses with the same occurence and the same
+/// normalized subquery, are folded into a single clause, whose score is
+/// multiplied by its number of occurences. (The `Should` clauses
+/// are not folded when more than one of them need to match.)
+/// If a subquery is both required and excluded, the query
+/// is executed as an `EmptyQuery`.
 #[derive(Debug)]
 pub struct BooleanQuery {
     subqueries: Vec<(Occur, Box<Query>)>,
+    minimum_should_match: usize,
 }
 
 impl From<Vec<(Occur, Box<Query>)>> for BooleanQuery {
     fn from(subqueries: Vec<(Occur, Box<Query>)>) -> BooleanQuery {
-        BooleanQuery { subqueries }
+        BooleanQuery {
+            subqueries,
+            minimum_should_match: 0,
+        }
     }
 }
 
 impl Query for BooleanQuery {
     fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
-        let sub_weights = self.subqueries
-            .iter()
-            .map(|&(ref occur, ref subquery)| {
-                Ok((*occur, subquery.weight(searcher, scoring_enabled)?))
+        let mut weight_cache = WeightCache::default();
+        self.shared_weight(searcher, scoring_enabled, &mut weight_cache)
+    }
+
+    fn shared_weight(
+        &self,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+        weight_cache: &mut WeightCache,
+    ) -> Result<Box<Weight>> {
+        let clauses = match self.folded_clauses() {
+            Some(clauses) => clauses,
+            None => {
+                return EmptyQuery.weight(searcher, scoring_enabled);
+            }
+        };
+        let sub_weights = clauses
+            .into_iter()
+            .map(|(occur, subquery, num_occurences)| {
+                let sub_scoring_enabled = scoring_enabled && occur != Occur::Filter;
+                let sub_weight = weight_cache.weight(subquery, searcher, sub_scoring_enabled)?;
+                if sub_scoring_enabled && occur != Occur::MustNot && num_occurences > 1 {
+                    let boost_weight: Box<Weight> =
+                        box BoostWeight::new(sub_weight, num_occurences as Score);
+                    Ok((occur, boost_weight))
+                } else {
+                    Ok((occur, sub_weight))
+                }
             })
             .collect::<Result<_>>()?;
-        Ok(box BooleanWeight::new(sub_weights, scoring_enabled))
+        Ok(box BooleanWeight::new(
+            sub_weights,
+            self.minimum_should_match,
+            scoring_enabled,
+        ))
+    }
+
+    fn has_stable_representation(&self) -> bool {
+        self.subqueries
+            .iter()
+            .all(|&(_, ref subquery)| subquery.has_stable_representation())
+    }
+
+    fn normalized(&self) -> String {
+        let mut clauses: Vec<String> = self.subqueries
+            .iter()
+            .map(|&(occur, ref subquery)| format!("({:?}, {})", occur, subquery.normalized()))
+            .collect();
+        clauses.sort();
+        format!(
+            "BooleanQuery {{ subqueries: [{}], minimum_should_match: {} }}",
+            clauses.join(", "),
+            self.minimum_should_match
+        )
     }
 }
 
 impl BooleanQuery {
+    /// Folds the identical clauses into a single clause, together
+    /// with its number of occurences.
+    ///
+    /// Returns `None` if a subquery is both required and excluded,
+    /// as the query then matches no document.
+    fn folded_clauses(&self) -> Option<Vec<(Occur, &Query, usize)>> {
+        let mut clauses: Vec<(Occur, &Query, usize)> = Vec::with_capacity(self.subqueries.len());
+        let mut clause_ords: HashMap<(Occur, String), usize> = HashMap::new();
+        for &(occur, ref subquery) in &self.subqueries {
+            // the number of matching should clauses matters
+            // for a minimum number of should clauses above 1.
+            if occur == Occur::Should && self.minimum_should_match > 1 {
+                clauses.push((occur, &**subquery, 1));
+                continue;
+            }
+            match clause_ords.entry((occur, subquery.normalized())) {
+                Entry::Occupied(entry) => {
+                    clauses[*entry.get()].2 += 1;
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(clauses.len());
+                    clauses.push((occur, &**subquery, 1));
+                }
+            }
+        }
+        let has_contradiction = clause_ords.keys().any(|&(occur, ref normalized)| {
+            (occur == Occur::Must || occur == Occur::Filter)
+                && clause_ords.contains_key(&(Occur::MustNot, normalized.clone()))
+        });
+        if has_contradiction {
+            None
+        } else {
+            Some(clauses)
+        }
+    }
+
+    /// Returns a builder to create a `BooleanQuery`
+    /// clause by clause.
+    ///
+    /// ```rust
+    /// # extern crate tantivy;
+    /// use tantivy::query::{BooleanQuery, TermQuery};
+    /// use tantivy::schema::{SchemaBuilder, TEXT};
+    ///
+    /// # fn main() {
+    /// # let mut schema_builder = SchemaBuilder::default();
+    /// # let title = schema_builder.add_text_field("title", TEXT);
+    /// let query = BooleanQuery::builder()
+    ///     .must(TermQuery::new_text(title, "diary"))
+    ///     .should(TermQuery::new_text(title, "girl"))
+    ///     .must_not(TermQuery::new_text(title, "boy"))
+    ///     .filter(TermQuery::new_text(title, "secret"))
+    ///     .build();
+    /// # }
+    /// ```
+    pub fn builder() -> BooleanQueryBuilder {
+        BooleanQueryBuilder::default()
+    }
+
+    /// Sets the minimum number of `Should` clauses a document must match.
+    ///
+    /// By default, it is 0: documents need to match one of the `Should`
+    /// clauses if there is no `Must` or `Filter` clause, and otherwise the `Should`
+    /// clauses only contribute to the score.
+    pub fn set_minimum_should_match(&mut self, minimum_should_match: usize) {
+        self.minimum_should_match = minimum_should_match;
+    }
+
+    /// Returns the minimum number of `Should` clauses a document must match.
+    pub fn minimum_should_match(&self) -> usize {
+        self.minimum_should_match
+    }
+
+    /// Returns the clauses of the query, in order.
+    pub fn clauses(&self) -> &[(Occur, Box<Query>)] {
+        &self.subqueries
+    }
+
     /// Helper method to create a boolean query matching a given list of terms.
     /// The resulting query is a disjunction of the terms.
     pub fn new_multiterms_query(terms: Vec<Term>) -> BooleanQuery {
@@ -55,3 +208,57 @@ impl BooleanQuery {
         BooleanQuery::from(occur_term_queries)
     }
 }
+
+/// Builder for a `BooleanQuery`.
+///
+/// See [`BooleanQuery::builder()`](./struct.BooleanQuery.html#method.builder).
+#[derive(Debug, Default)]
+pub struct BooleanQueryBuilder {
+    subqueries: Vec<(Occur, Box<Query>)>,
+    minimum_should_match: usize,
+}
+
+impl BooleanQueryBuilder {
+    /// Adds a clause that documents must match.
+    pub fn must<TQuery: Query + 'static>(self, query: TQuery) -> BooleanQueryBuilder {
+        self.add(Occur::Must, box query)
+    }
+
+    /// Adds a clause that documents should match.
+    pub fn should<TQuery: Query + 'static>(self, query: TQuery) -> BooleanQueryBuilder {
+        self.add(Occur::Should, box query)
+    }
+
+    /// Adds a clause that documents must not match.
+    pub fn must_not<TQuery: Query + 'static>(self, query: TQuery) -> BooleanQueryBuilder {
+        self.add(Occur::MustNot, box query)
+    }
+
+    /// Adds a clause that documents must match, without
+    /// contributing to the score.
+    pub fn filter<TQuery: Query + 'static>(self, query: TQuery) -> BooleanQueryBuilder {
+        self.add(Occur::Filter, box query)
+    }
+
+    /// Adds an already boxed clause, with the given occurence.
+    pub fn add(mut self, occur: Occur, query: Box<Query>) -> BooleanQueryBuilder {
+        self.subqueries.push((occur, query));
+        self
+    }
+
+    /// Sets the minimum number of `Should` clauses a document must match.
+    ///
+    /// See [`BooleanQuery::set_minimum_should_match()`](
+    /// ./struct.BooleanQuery.html#method.set_minimum_should_match).
+    pub fn minimum_should_match(mut self, minimum_should_match: usize) -> BooleanQueryBuilder {
+        self.minimum_should_match = minimum_should_match;
+        self
+    }
+
+    /// Creates the `BooleanQuery`.
+    pub fn build(self) -> BooleanQuery {
+        let mut boolean_query = BooleanQuery::from(self.subqueries);
+        boolean_query.set_minimum_should_match(self.minimum_should_match);
+        boolean_query
+    }
+}
@@ -1,8 +1,8 @@
 use query::Weight;
 use core::SegmentReader;
-use query::{Intersection, Union};
+use query::{BlockMaxWand, Intersection, MinimumShouldMatchScorer, Union};
 use std::collections::HashMap;
-use query::EmptyScorer;
+use query::{ConstScorer, DocRangeDocSet, DocRangeIntersection, EmptyScorer};
 use query::Scorer;
 use downcast::Downcast;
 use query::term_query::TermScorer;
@@ -11,7 +11,63 @@ use query::Exclude;
 use query::Occur;
 use query::RequiredOptionalScorer;
 use query::score_combiner::{DoNothingCombiner, ScoreCombiner, SumWithCoordsCombiner};
+use DocId;
 use Result;
+use query::Explanation;
+use query::explanation::{does_not_match, seek_doc};
+
+fn is_all_term_scorers(scorers: &[Box<Scorer>]) -> bool {
+    scorers.iter().all(|scorer| {
+        let scorer_ref: &Scorer = scorer.borrow();
+        Downcast::<TermScorer>::is_type(scorer_ref)
+    })
+}
+
+fn is_empty_scorer(scorer: &Scorer) -> bool {
+    Downcast::<EmptyScorer>::is_type(scorer)
+}
+
+/// Wraps the scorer of a `Filter` clause, so that it
+/// does not contribute to the score.
+fn filter_scorer(scorer: Box<Scorer>) -> Box<Scorer> {
+    let match_cost = scorer.match_cost();
+    let mut const_scorer = ConstScorer::new(scorer);
+    const_scorer.set_score(0.0);
+    const_scorer.set_match_cost(match_cost);
+    box const_scorer
+}
+
+/// Removes the `DocRangeDocSet`s from the scorers of the required clauses,
+/// and intersects them into `doc_range_opt`.
+///
+/// The doc ranges of `Filter` clauses (`scored == false`)
+/// do not contribute to the score.
+fn extract_doc_ranges(
+    scorers: Vec<Box<Scorer>>,
+    doc_range_opt: &mut Option<DocRangeDocSet>,
+    scored: bool,
+) -> Vec<Box<Scorer>> {
+    let mut other_scorers = Vec::with_capacity(scorers.len());
+    for scorer in scorers {
+        let is_doc_range = {
+            let scorer_ref: &Scorer = scorer.borrow();
+            Downcast::<DocRangeDocSet>::is_type(scorer_ref)
+        };
+        if !is_doc_range {
+            other_scorers.push(scorer);
+            continue;
+        }
+        let mut doc_range = *Downcast::<DocRangeDocSet>::downcast(scorer).unwrap();
+        if !scored {
+            doc_range.set_score(0.0);
+        }
+        *doc_range_opt = Some(match doc_range_opt.take() {
+            Some(other_doc_range) => other_doc_range.intersect(doc_range),
+            None => doc_range,
+        });
+    }
+    other_scorers
+}
 
 fn scorer_union<TScoreCombiner>(scorers: Vec<Box<Scorer>>) -> Box<Scorer>
 where
@@ -21,11 +77,7 @@ where
     if scorers.len() == 1 {
         scorers.into_iter().next().unwrap() //< we checked the size beforehands
     } else {
-        let is_all_term_queries = scorers.iter().all(|scorer| {
-            let scorer_ref: &Scorer = scorer.borrow();
-            Downcast::<TermScorer>::is_type(scorer_ref)
-        });
-        if is_all_term_queries {
+        if is_all_term_scorers(&scorers) {
             let scorers: Vec<TermScorer> = scorers
                 .into_iter()
                 .map(|scorer| *Downcast::<TermScorer>::downcast(scorer).unwrap())
@@ -41,13 +93,19 @@ where
 
 pub struct BooleanWeight {
     weights: Vec<(Occur, Box<Weight>)>,
+    minimum_should_match: usize,
     scoring_enabled: bool,
 }
 
 impl BooleanWeight {
-    pub fn new(weights: Vec<(Occur, Box<Weight>)>, scoring_enabled: bool) -> BooleanWeight {
+    pub fn new(
+        weights: Vec<(Occur, Box<Weight>)>,
+        minimum_should_match: usize,
+        scoring_enabled: bool,
+    ) -> BooleanWeight {
         BooleanWeight {
             weights,
+            minimum_should_match,
             scoring_enabled,
         }
     }
@@ -59,46 +117,109 @@ impl BooleanWeight {
         let mut per_occur_scorers: HashMap<Occur, Vec<Box<Scorer>>> = HashMap::new();
         for &(ref occur, ref subweight) in &self.weights {
             let sub_scorer: Box<Scorer> = subweight.scorer(reader)?;
+            if is_empty_scorer(&*sub_scorer) {
+                // e.g. a term missing from the segment.
+                match *occur {
+                    Occur::Must | Occur::Filter => {
+                        return Ok(box EmptyScorer);
+                    }
+                    Occur::Should | Occur::MustNot => {
+                        continue;
+                    }
+                }
+            }
             per_occur_scorers
                 .entry(*occur)
                 .or_insert_with(Vec::new)
                 .push(sub_scorer);
         }
 
+        // Block-Max WAND may only skip documents when the score
+        // of the should clauses is the score of the document.
+        let should_block_max_wand = self.scoring_enabled
+            && !per_occur_scorers.contains_key(&Occur::Must)
+            && !per_occur_scorers.contains_key(&Occur::Filter);
+        let num_should_scorers = per_occur_scorers
+            .get(&Occur::Should)
+            .map(Vec::len)
+            .unwrap_or(0);
+        if num_should_scorers < self.minimum_should_match {
+            return Ok(box EmptyScorer);
+        }
+        // the should clauses are required when at least one of them has to match.
+        let should_required = self.minimum_should_match > 0;
         let should_scorer_opt: Option<Box<Scorer>> = per_occur_scorers
             .remove(&Occur::Should)
-            .map(scorer_union::<TScoreCombiner>);
+            .map(|scorers| {
+                if self.minimum_should_match > 1 {
+                    let scorer: Box<Scorer> =
+                        box MinimumShouldMatchScorer::new(scorers, self.minimum_should_match);
+                    scorer
+                } else if should_block_max_wand && scorers.len() > 1
+                    && is_all_term_scorers(&scorers)
+                {
+                    let scorers: Vec<TermScorer> = scorers
+                        .into_iter()
+                        .map(|scorer| *Downcast::<TermScorer>::downcast(scorer).unwrap())
+                        .collect();
+                    let scorer: Box<Scorer> = box BlockMaxWand::from(scorers);
+                    scorer
+                } else {
+                    scorer_union::<TScoreCombiner>(scorers)
+                }
+            });
 
         let exclude_scorer_opt: Option<Box<Scorer>> = per_occur_scorers
             .remove(&Occur::MustNot)
             .map(scorer_union::<TScoreCombiner>);
 
-        let must_scorer_opt: Option<Box<Scorer>> =
-            per_occur_scorers.remove(&Occur::Must).map(|scorers| {
-                if scorers.len() == 1 {
-                    scorers.into_iter().next().unwrap()
+        // The doc ranges of the required clauses are not part of the intersection:
+        // the other required clauses are restricted to their intersection.
+        let mut doc_range_opt: Option<DocRangeDocSet> = None;
+        let mut must_scorers_opt = match per_occur_scorers.remove(&Occur::Must) {
+            Some(must_scorers) => Some(extract_doc_ranges(must_scorers, &mut doc_range_opt, true)),
+            None => None,
+        };
+        if let Some(filter_scorers) = per_occur_scorers.remove(&Occur::Filter) {
+            let filter_scorers = extract_doc_ranges(filter_scorers, &mut doc_range_opt, false);
+            // filters are intersected with the must clauses.
+            must_scorers_opt
+                .get_or_insert_with(Vec::new)
+                .extend(filter_scorers.into_iter().map(filter_scorer));
+        }
+        let must_scorer_opt: Option<Box<Scorer>> = must_scorers_opt
+            .and_then(|scorers| {
+                if scorers.len() <= 1 {
+                    scorers.into_iter().next()
+                } else if is_all_term_scorers(&scorers) {
+                    let scorers: Vec<TermScorer> = scorers
+                        .into_iter()
+                        .map(|scorer| *Downcast::<TermScorer>::downcast(scorer).unwrap())
+                        .collect();
+                    let scorer: Box<Scorer> = box Intersection::from(scorers);
+                    Some(scorer)
                 } else {
-                    let is_all_term_queries = scorers.iter().all(|scorer| {
-                        let scorer_ref: &Scorer = scorer.borrow();
-                        Downcast::<TermScorer>::is_type(scorer_ref)
-                    });
-                    if is_all_term_queries {
-                        let scorers: Vec<TermScorer> = scorers
-                            .into_iter()
-                            .map(|scorer| *Downcast::<TermScorer>::downcast(scorer).unwrap())
-                            .collect();
-                        let scorer: Box<Scorer> = box Intersection::from(scorers);
-                        scorer
-                    } else {
-                        let scorer: Box<Scorer> = box Intersection::from(scorers);
-                        scorer
-                    }
+                    let scorer: Box<Scorer> = box Intersection::from_scorers(scorers);
+                    Some(scorer)
                 }
             });
+        let must_scorer_opt: Option<Box<Scorer>> = match (doc_range_opt, must_scorer_opt) {
+            (Some(doc_range), Some(must_scorer)) => {
+                let scorer: Box<Scorer> = box DocRangeIntersection::new(must_scorer, doc_range);
+                Some(scorer)
+            }
+            (Some(doc_range), None) => {
+                let scorer: Box<Scorer> = box doc_range;
+                Some(scorer)
+            }
+            (None, must_scorer_opt) => must_scorer_opt,
+        };
 
         let positive_scorer: Box<Scorer> = match (should_scorer_opt, must_scorer_opt) {
             (Some(should_scorer), Some(must_scorer)) => {
-                if self.scoring_enabled {
+                if should_required {
+                    box Intersection::from_scorers(vec![must_scorer, should_scorer])
+                } else if self.scoring_enabled {
                     box RequiredOptionalScorer::<_, _, TScoreCombiner>::new(
                         must_scorer,
                         should_scorer,
@@ -123,15 +244,39 @@ impl BooleanWeight {
 }
 
 impl Weight for BooleanWeight {
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        if self.weights.len() == 1 && self.minimum_should_match == 0 {
+            let &(occur, ref weight) = &self.weights[0];
+            if occur == Occur::Must || occur == Occur::Should {
+                return weight.explain(reader, doc);
+            }
+        }
+        let mut scorer = self.scorer(reader)?;
+        if !seek_doc(&mut scorer, doc) {
+            return Err(does_not_match(doc));
+        }
+        let mut explanation = Explanation::new("BooleanQuery, sum of", scorer.score());
+        for &(ref occur, ref subweight) in &self.weights {
+            if *occur == Occur::MustNot || *occur == Occur::Filter {
+                continue;
+            }
+            let mut sub_scorer = subweight.scorer(reader)?;
+            if seek_doc(&mut sub_scorer, doc) {
+                explanation.add_detail(subweight.explain(reader, doc)?);
+            }
+        }
+        Ok(explanation)
+    }
+
     fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
         if self.weights.is_empty() {
             Ok(box EmptyScorer)
-        } else if self.weights.len() == 1 {
+        } else if self.weights.len() == 1 && self.minimum_should_match == 0 {
             let &(occur, ref weight) = &self.weights[0];
-            if occur == Occur::MustNot {
-                Ok(box EmptyScorer)
-            } else {
-                weight.scorer(reader)
+            match occur {
+                Occur::MustNot => Ok(box EmptyScorer),
+                Occur::Filter => Ok(filter_scorer(weight.scorer(reader)?)),
+                Occur::Must | Occur::Should => weight.scorer(reader),
             }
         } else if self.scoring_enabled {
             self.complex_scorer::<SumWithCoordsCombiner>(reader)
@@ -1,7 +1,7 @@
 mod boolean_query;
 mod boolean_weight;
 
-pub use self::boolean_query::BooleanQuery;
+pub use self::boolean_query::{BooleanQuery, BooleanQueryBuilder};
 
 #[cfg(test)]
 mod tests {
@@ -20,6 +20,9 @@ mod tests {
     use query::QueryParser;
     use query::RequiredOptionalScorer;
     use query::score_combiner::SumWithCoordsCombiner;
+    use collector::TopCollector;
+    use DocAddress;
+    use Score;
 
 
 
@@ -65,6 +68,60 @@ mod tests {
         assert_eq!(query.count(&*index.searcher()).unwrap(), 3);
     }
 
+    #[test]
+    pub fn test_boolean_query_builder() {
+        let (index, text_field) = aux_test_helper();
+        let searcher = index.searcher();
+        let query = BooleanQuery::builder()
+            .must(TermQuery::new_text(text_field, "a"))
+            .should(TermQuery::new_text(text_field, "d"))
+            .must_not(TermQuery::new_text(text_field, "b"))
+            .build();
+        let mut test_collector = TestCollector::default();
+        searcher.search(&query, &mut test_collector).unwrap();
+        assert_eq!(test_collector.docs(), vec![1]);
+        let nested_query = BooleanQuery::builder()
+            .should(query)
+            .add(Occur::Should, box TermQuery::new_text(text_field, "d"))
+            .build();
+        assert_eq!(nested_query.count(&*searcher).unwrap(), 3);
+    }
+
+    #[test]
+    pub fn test_boolean_query_fingerprint() {
+        let field = Field(0);
+        let query = BooleanQuery::builder()
+            .must(TermQuery::new_text(field, "a"))
+            .should(TermQuery::new_text(field, "b"))
+            .should(TermQuery::new_text(field, "c"))
+            .build();
+        let reordered_query = BooleanQuery::builder()
+            .should(TermQuery::new_text(field, "c"))
+            .must(TermQuery::new_text(field, "a"))
+            .should(TermQuery::new_text(field, "b"))
+            .build();
+        assert_eq!(query.normalized(), reordered_query.normalized());
+        assert_eq!(query.fingerprint(), reordered_query.fingerprint());
+        let other_query = BooleanQuery::builder()
+            .should(TermQuery::new_text(field, "a"))
+            .should(TermQuery::new_text(field, "b"))
+            .should(TermQuery::new_text(field, "c"))
+            .build();
+        assert_ne!(query.fingerprint(), other_query.fingerprint());
+        let nested_query = BooleanQuery::builder()
+            .should(query)
+            .should(TermQuery::new_text(field, "d"))
+            .build();
+        let reordered_nested_query = BooleanQuery::builder()
+            .should(TermQuery::new_text(field, "d"))
+            .should(reordered_query)
+            .build();
+        assert_eq!(
+            nested_query.fingerprint(),
+            reordered_nested_query.fingerprint()
+        );
+    }
+
     #[test]
     pub fn test_boolean_single_must_clause() {
         let (index, text_field) = aux_test_helper();
@@ -95,6 +152,31 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn test_boolean_intersection_match_cost() {
+        let (index, text_field) = aux_test_helper();
+        let query_parser = QueryParser::for_index(&index, vec![text_field]);
+        let searcher = index.searcher();
+        let query = query_parser.parse_query("+\"a b\" +d").unwrap();
+        assert_eq!(query.count(&*searcher).unwrap(), 1);
+        let weight = query.weight(&*searcher, true).unwrap();
+        let scorer = weight.scorer(searcher.segment_reader(0u32)).unwrap();
+        assert_eq!(scorer.match_cost(), 20f32);
+        let intersection = Downcast::<Intersection<Box<Scorer>>>::downcast(scorer).unwrap();
+        // the term `d` is checked before the positions of the phrase.
+        assert_eq!(intersection.docsets()[0].match_cost(), 0f32);
+        assert_eq!(intersection.docsets()[1].match_cost(), 20f32);
+
+        // the phrase has fewer documents than the term `c`, so it drives the iteration.
+        let query = query_parser.parse_query("+c +\"a b\"").unwrap();
+        assert_eq!(query.count(&*searcher).unwrap(), 2);
+        let weight = query.weight(&*searcher, true).unwrap();
+        let scorer = weight.scorer(searcher.segment_reader(0u32)).unwrap();
+        let intersection = Downcast::<Intersection<Box<Scorer>>>::downcast(scorer).unwrap();
+        assert_eq!(intersection.docsets()[0].match_cost(), 20f32);
+        assert_eq!(intersection.docsets()[1].match_cost(), 0f32);
+    }
+
     #[test]
     pub fn test_boolean_reqopt() {
         let (index, text_field) = aux_test_helper();
@@ -170,4 +252,171 @@ mod tests {
             assert_eq!(matching_docs(&boolean_query), Vec::<u32>::new());
         }
     }
+
+    #[test]
+    pub fn test_boolean_query_minimum_should_match() {
+        let (index, text_field) = aux_test_helper();
+        let term_query = |text: &str| {
+            TermQuery::new(
+                Term::from_field_text(text_field, text),
+                IndexRecordOption::Basic,
+            )
+        };
+        let matching_docs = |must_c: bool, minimum_should_match: usize| {
+            let mut builder = BooleanQuery::builder()
+                .should(term_query("a"))
+                .should(term_query("b"))
+                .should(term_query("d"))
+                .minimum_should_match(minimum_should_match);
+            if must_c {
+                builder = builder.must(term_query("c"));
+            }
+            let searcher = index.searcher();
+            let mut test_collector = TestCollector::default();
+            searcher.search(&builder.build(), &mut test_collector).unwrap();
+            test_collector.docs()
+        };
+        assert_eq!(matching_docs(false, 0), vec![0, 1, 2, 3, 4]);
+        assert_eq!(matching_docs(false, 2), vec![0, 3]);
+        assert_eq!(matching_docs(false, 3), vec![3]);
+        assert_eq!(matching_docs(false, 4), Vec::<u32>::new());
+        assert_eq!(matching_docs(true, 0), vec![0, 1, 2, 3]);
+        assert_eq!(matching_docs(true, 1), vec![0, 1, 2, 3]);
+        assert_eq!(matching_docs(true, 2), vec![0, 3]);
+    }
+
+    #[test]
+    pub fn test_boolean_query_filter() {
+        let (index, text_field) = aux_test_helper();
+        let searcher = index.searcher();
+        let score_docs = |query: &BooleanQuery| {
+            let mut top_collector = TopCollector::with_limit(10);
+            searcher.search(query, &mut top_collector).unwrap();
+            let mut score_docs: Vec<(u32, Score)> = top_collector
+                .score_docs()
+                .into_iter()
+                .map(|(score, doc_address)| (doc_address.doc(), score))
+                .collect();
+            score_docs.sort_by_key(|&(doc, _)| doc);
+            score_docs
+        };
+        let a_score_docs = score_docs(
+            &BooleanQuery::builder()
+                .must(TermQuery::new_text(text_field, "a"))
+                .build(),
+        );
+        let a_filter_b_score_docs = score_docs(
+            &BooleanQuery::builder()
+                .must(TermQuery::new_text(text_field, "a"))
+                .filter(TermQuery::new_text(text_field, "b"))
+                .build(),
+        );
+        assert_eq!(
+            a_filter_b_score_docs,
+            a_score_docs
+                .into_iter()
+                .filter(|&(doc, _)| doc == 0 || doc == 3)
+                .collect::<Vec<_>>()
+        );
+        let filter_d_score_docs = score_docs(
+            &BooleanQuery::builder()
+                .filter(TermQuery::new_text(text_field, "d"))
+                .build(),
+        );
+        assert_eq!(filter_d_score_docs, vec![(3, 0.0), (4, 0.0)]);
+        let should_filter_c_score_docs = score_docs(
+            &BooleanQuery::builder()
+                .should(TermQuery::new_text(text_field, "d"))
+                .filter(TermQuery::new_text(text_field, "c"))
+                .build(),
+        );
+        assert_eq!(should_filter_c_score_docs.len(), 4);
+        assert_eq!(should_filter_c_score_docs[0], (0, 0.0));
+        assert!(should_filter_c_score_docs[3].1 > 0.0);
+    }
+
+    #[test]
+    pub fn test_boolean_explain() {
+        let (index, text_field) = aux_test_helper();
+        let query_parser = QueryParser::for_index(&index, vec![text_field]);
+        let searcher = index.searcher();
+        let query = query_parser.parse_query("+a b").unwrap();
+        let mut top_collector = TopCollector::with_limit(10);
+        searcher.search(&*query, &mut top_collector).unwrap();
+        for (score, doc_address) in top_collector.score_docs() {
+            let explanation = query.explain(&*searcher, &doc_address).unwrap();
+            assert_eq!(explanation.value(), score);
+            let details_sum: Score = explanation
+                .details()
+                .iter()
+                .map(|detail| detail.value())
+                .sum();
+            assert!((details_sum - score).abs() < 0.0001);
+        }
+        let explanation = query.explain(&*searcher, &DocAddress(0, 0)).unwrap();
+        assert_eq!(explanation.details().len(), 2);
+        let explanation = query.explain(&*searcher, &DocAddress(0, 1)).unwrap();
+        assert_eq!(explanation.details().len(), 1);
+        assert!(query.explain(&*searcher, &DocAddress(0, 2)).is_err());
+    }
+
+    #[test]
+    pub fn test_boolean_query_folded_clauses() {
+        use query::{BoostQuery, EmptyScorer};
+        let (index, text_field) = aux_test_helper();
+        let searcher = index.searcher();
+        let term_query = |text: &str| TermQuery::new_text(text_field, text);
+        let score_docs = |query: &BooleanQuery| {
+            let mut top_collector = TopCollector::with_limit(10);
+            searcher.search(query, &mut top_collector).unwrap();
+            let mut score_docs: Vec<(u32, Score)> = top_collector
+                .score_docs()
+                .into_iter()
+                .map(|(score, doc_address)| (doc_address.doc(), score))
+                .collect();
+            score_docs.sort_by_key(|&(doc, _)| doc);
+            score_docs
+        };
+        let duplicated_query = BooleanQuery::builder()
+            .must(term_query("a"))
+            .should(term_query("b"))
+            .must(term_query("a"))
+            .filter(term_query("c"))
+            .filter(term_query("c"))
+            .build();
+        let boosted_query = BooleanQuery::builder()
+            .add(Occur::Must, box BoostQuery::new(box term_query("a"), 2.0))
+            .should(term_query("b"))
+            .filter(term_query("c"))
+            .build();
+        assert_eq!(score_docs(&duplicated_query), score_docs(&boosted_query));
+        {
+            // the number of matching should clauses counts duplicates.
+            let query = BooleanQuery::builder()
+                .should(term_query("a"))
+                .should(term_query("a"))
+                .should(term_query("d"))
+                .minimum_should_match(2)
+                .build();
+            let mut test_collector = TestCollector::default();
+            searcher.search(&query, &mut test_collector).unwrap();
+            assert_eq!(test_collector.docs(), vec![0, 1, 3]);
+        }
+        {
+            let contradiction = BooleanQuery::builder()
+                .should(term_query("b"))
+                .filter(term_query("a"))
+                .must_not(term_query("a"))
+                .build();
+            let weight = contradiction.weight(&*searcher, true).unwrap();
+            let scorer = weight.scorer(searcher.segment_reader(0u32)).unwrap();
+            assert!(Downcast::<EmptyScorer>::is_type(&*scorer));
+            let should_and_excluded = BooleanQuery::builder()
+                .should(term_query("b"))
+                .should(term_query("a"))
+                .must_not(term_query("a"))
+                .build();
+            assert_eq!(should_and_excluded.count(&*searcher).unwrap(), 1);
+        }
+    }
 }
@@ -0,0 +1,183 @@
+use Result;
+use Score;
+use DocId;
+use common::BitSet;
+use core::SegmentReader;
+use core::Searcher;
+use docset::{DocSet, SkipResult};
+use query::{Explanation, Query, Scorer, Weight, WeightCache};
+
+/// `BoostQuery` multiplies the score of the documents
+/// matched by its underlying query by a constant `boost` factor.
+///
+/// It matches exactly the same documents as the underlying query.
+///
+/// The query parser emits a `BoostQuery` for boosted
+/// subqueries, e.g. `title:diary^2.5`.
+#[derive(Debug)]
+pub struct BoostQuery {
+    query: Box<Query>,
+    boost: Score,
+}
+
+impl BoostQuery {
+    /// Creates a `BoostQuery` multiplying the scores
+    /// of `query` by `boost`.
+    pub fn new(query: Box<Query>, boost: Score) -> BoostQuery {
+        BoostQuery { query, boost }
+    }
+
+    /// Returns the boost factor.
+    pub fn boost(&self) -> Score {
+        self.boost
+    }
+}
+
+impl Query for BoostQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+        let weight = self.query.weight(searcher, scoring_enabled)?;
+        Ok(box BoostWeight::new(weight, self.boost))
+    }
+
+    fn shared_weight(
+        &self,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+        weight_cache: &mut WeightCache,
+    ) -> Result<Box<Weight>> {
+        let weight = self.query
+            .shared_weight(searcher, scoring_enabled, weight_cache)?;
+        Ok(box BoostWeight::new(weight, self.boost))
+    }
+
+    fn has_stable_representation(&self) -> bool {
+        self.query.has_stable_representation()
+    }
+
+    fn normalized(&self) -> String {
+        format!(
+            "BoostQuery {{ query: {}, boost: {:?} }}",
+            self.query.normalized(),
+            self.boost
+        )
+    }
+}
+
+/// Weight associated to the `BoostQuery` query.
+pub struct BoostWeight {
+    weight: Box<Weight>,
+    boost: Score,
+}
+
+impl BoostWeight {
+    pub(crate) fn new(weight: Box<Weight>, boost: Score) -> BoostWeight {
+        BoostWeight { weight, boost }
+    }
+}
+
+impl Weight for BoostWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let scorer = self.weight.scorer(reader)?;
+        Ok(box BoostScorer {
+            scorer,
+            boost: self.boost,
+        })
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        let sub_explanation = self.weight.explain(reader, doc)?;
+        let mut explanation =
+            Explanation::new("Boost, product of", sub_explanation.value() * self.boost);
+        explanation.add_const("boost", self.boost);
+        explanation.add_detail(sub_explanation);
+        Ok(explanation)
+    }
+
+    fn count(&self, reader: &SegmentReader) -> Result<u32> {
+        self.weight.count(reader)
+    }
+}
+
+/// Scorer associated to the `BoostQuery` query.
+pub struct BoostScorer {
+    scorer: Box<Scorer>,
+    boost: Score,
+}
+
+impl DocSet for BoostScorer {
+    fn advance(&mut self) -> bool {
+        self.scorer.advance()
+    }
+
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        self.scorer.skip_next(target)
+    }
+
+    fn fill_buffer(&mut self, buffer: &mut [DocId]) -> usize {
+        self.scorer.fill_buffer(buffer)
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+
+    fn estimate_live(&self) -> u32 {
+        self.scorer.estimate_live()
+    }
+
+    fn append_to_bitset(&mut self, bitset: &mut BitSet) {
+        self.scorer.append_to_bitset(bitset);
+    }
+}
+
+impl Scorer for BoostScorer {
+    fn score(&mut self) -> Score {
+        self.scorer.score() * self.boost
+    }
+
+    fn match_cost(&self) -> f32 {
+        self.scorer.match_cost()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::BoostQuery;
+    use collector::TopCollector;
+    use query::{Query, TermQuery};
+    use schema::{SchemaBuilder, TEXT};
+    use DocAddress;
+    use Index;
+
+    #[test]
+    fn test_boost_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a b"));
+            index_writer.add_document(doc!(text_field => "b"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query = TermQuery::new_text(text_field, "a");
+        let boosted_query = BoostQuery::new(box TermQuery::new_text(text_field, "a"), 2.5);
+        let doc_address = DocAddress(0u32, 0u32);
+        let score = query.explain(&*searcher, &doc_address).unwrap().value();
+        let explanation = boosted_query.explain(&*searcher, &doc_address).unwrap();
+        assert_eq!(explanation.value(), score * 2.5);
+        let mut top_collector = TopCollector::with_limit(2);
+        boosted_query.search(&*searcher, &mut top_collector).unwrap();
+        let score_docs = top_collector.score_docs();
+        assert_eq!(score_docs.len(), 1);
+        assert_eq!(score_docs[0].0, score * 2.5);
+        assert_eq!(boosted_query.count(&*searcher).unwrap(), 1);
+    }
+}
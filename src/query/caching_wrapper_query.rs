@@ -0,0 +1,252 @@
+use Result;
+use DocId;
+use common::BitSet;
+use docset::DocSet;
+use core::{MemoryBudget, Searcher, SegmentReader};
+use query::{BitSetDocSet, ConstScorer, Explanation, FilterCache, Query, Scorer, Weight};
+use query::explanation::{does_not_match, seek_doc};
+use std::sync::Arc;
+
+/// `CachingWrapperQuery` wraps a query, and caches the set of
+/// documents it matches, for each segment, in a `FilterCache`.
+///
+/// It is meant to be used as a filter, e.g. as a `Must` clause of a
+/// `BooleanQuery` executed repeatedly: the documents matched by the
+/// wrapped query are only computed once per segment, and the
+/// scores of the wrapped query are discarded. All of the
+/// matching documents get a constant score of one.
+///
+/// Two queries share their cache entries iff their fingerprint is
+/// the same. (See `Query::fingerprint`)
+/// The queries without a stable representation, such as the ones holding
+/// a user defined function, have no fingerprint, and always bypass
+/// the cache, as do the segments masked via `SegmentReader::with_mask`.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::collector::CountCollector;
+/// use tantivy::query::{CachingWrapperQuery, FilterCache, RangeQuery};
+/// use tantivy::schema::{SchemaBuilder, INT_INDEXED};
+/// use tantivy::{Index, Result};
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let year = schema_builder.add_u64_field("year", INT_INDEXED);
+///     let index = Index::create_in_ram(schema_builder.build());
+///     let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+///     for y in 1950u64..2018u64 {
+///         index_writer.add_document(doc!(year => y));
+///     }
+///     index_writer.commit()?;
+///     index.load_searchers()?;
+///     let searcher = index.searcher();
+///
+///     let filter_cache = FilterCache::new();
+///     let sixties = CachingWrapperQuery::new(
+///         Box::new(RangeQuery::new_u64(year, 1960..1970)),
+///         filter_cache.clone(),
+///     );
+///     for _ in 0..2 {
+///         let mut count_collector = CountCollector::default();
+///         searcher.search(&sixties, &mut count_collector)?;
+///         assert_eq!(count_collector.count(), 10);
+///     }
+///     assert_eq!(filter_cache.num_entries(), 1);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct CachingWrapperQuery {
+    query: Box<Query>,
+    filter_cache: FilterCache,
+}
+
+impl CachingWrapperQuery {
+    /// Wraps `query`, caching the documents it matches in `filter_cache`.
+    pub fn new(query: Box<Query>, filter_cache: FilterCache) -> CachingWrapperQuery {
+        CachingWrapperQuery {
+            query,
+            filter_cache,
+        }
+    }
+}
+
+impl Query for CachingWrapperQuery {
+    fn weight(&self, searcher: &Searcher, _scoring_enabled: bool) -> Result<Box<Weight>> {
+        Ok(box CachingWrapperWeight {
+            weight: self.query.weight(searcher, false)?,
+            fingerprint: self.query.fingerprint(),
+            filter_cache: self.filter_cache.clone(),
+            memory_budget: searcher.memory_budget().clone(),
+        })
+    }
+
+    fn has_stable_representation(&self) -> bool {
+        self.query.has_stable_representation()
+    }
+
+    fn normalized(&self) -> String {
+        format!("CachingWrapperQuery({})", self.query.normalized())
+    }
+}
+
+struct CachingWrapperWeight {
+    weight: Box<Weight>,
+    // `None` if the wrapped query cannot be cached.
+    fingerprint: Option<u64>,
+    filter_cache: FilterCache,
+    memory_budget: MemoryBudget,
+}
+
+impl CachingWrapperWeight {
+    fn matching_docs(&self, reader: &SegmentReader) -> Result<Arc<BitSet>> {
+        // the entries are only valid for the actual deletes of the segment.
+        let fingerprint_opt = if reader.is_masked() {
+            None
+        } else {
+            self.fingerprint
+        };
+        if let Some(fingerprint) = fingerprint_opt {
+            if let Some(docs) = self.filter_cache.get(reader, fingerprint) {
+                return Ok(docs);
+            }
+        }
+        let mut docs = BitSet::with_max_value(reader.max_doc());
+        self.weight.scorer(reader)?.append_to_bitset(&mut docs);
+        let docs = Arc::new(docs);
+        if let Some(fingerprint) = fingerprint_opt {
+            self.filter_cache
+                .insert(reader, fingerprint, Arc::clone(&docs));
+        }
+        Ok(docs)
+    }
+}
+
+impl Weight for CachingWrapperWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let docs = self.matching_docs(reader)?;
+        // The cached bitset is shared: each scorer iterates over its own copy.
+        let num_bitset_bytes = (reader.max_doc() as usize + 63) / 64 * 8;
+        self.memory_budget.allocate(num_bitset_bytes)?;
+        let doc_bitset = BitSetDocSet::from((*docs).clone());
+        Ok(box ConstScorer::new(doc_bitset))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        let mut scorer = self.scorer(reader)?;
+        if !seek_doc(&mut scorer, doc) {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("CachingWrapperQuery", scorer.score()))
+    }
+
+    fn count(&self, reader: &SegmentReader) -> Result<u32> {
+        Ok(self.matching_docs(reader)?.len() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::CachingWrapperQuery;
+    use collector::tests::TestCollector;
+    use query::{FilterCache, MoreLikeThisQuery, Query, RangeQuery, TermQuery};
+    use schema::{IndexRecordOption, SchemaBuilder, Term, INT_INDEXED, STORED, TEXT};
+    use DocAddress;
+    use Index;
+
+    #[test]
+    fn test_caching_wrapper_query() {
+        let mut schema_builder = SchemaBuilder::new();
+        let num_field = schema_builder.add_u64_field("num", INT_INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        for num in 0u64..20u64 {
+            index_writer.add_document(doc!(num_field => num));
+        }
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(num_field => 5u64));
+        index_writer.commit().unwrap();
+
+        let filter_cache = FilterCache::new();
+        let matching_docs = |query: &Query| {
+            index.load_searchers().unwrap();
+            let searcher = index.searcher();
+            let mut test_collector = TestCollector::default();
+            searcher.search(query, &mut test_collector).unwrap();
+            (test_collector.docs(), query.count(&*searcher).unwrap())
+        };
+        let range_query = CachingWrapperQuery::new(
+            box RangeQuery::new_u64(num_field, 3u64..7u64),
+            filter_cache.clone(),
+        );
+        let expected = matching_docs(&RangeQuery::new_u64(num_field, 3u64..7u64));
+        assert_eq!(expected.1, 5);
+        assert_eq!(matching_docs(&range_query), expected);
+        assert_eq!(filter_cache.num_entries(), 2);
+        assert_eq!(matching_docs(&range_query), expected);
+        assert_eq!(filter_cache.num_entries(), 2);
+
+        // same query, same fingerprint.
+        let other_range_query = CachingWrapperQuery::new(
+            box RangeQuery::new_u64(num_field, 3u64..7u64),
+            filter_cache.clone(),
+        );
+        assert_eq!(matching_docs(&other_range_query), expected);
+        assert_eq!(filter_cache.num_entries(), 2);
+
+        let term = Term::from_field_u64(num_field, 5u64);
+        let term_query = CachingWrapperQuery::new(
+            box TermQuery::new(term.clone(), IndexRecordOption::Basic),
+            filter_cache.clone(),
+        );
+        let expected_term_docs = matching_docs(&TermQuery::new(term, IndexRecordOption::Basic));
+        assert_eq!(expected_term_docs.1, 2);
+        assert_eq!(matching_docs(&term_query), expected_term_docs);
+        assert_eq!(filter_cache.num_entries(), 4);
+
+        // entries are not reused after deletes.
+        index_writer.delete_term(Term::from_field_u64(num_field, 4u64));
+        index_writer.commit().unwrap();
+        assert_eq!(matching_docs(&range_query).1, 4);
+        assert_eq!(filter_cache.num_entries(), 3);
+
+        for segment_id in index.searchable_segment_ids().unwrap() {
+            filter_cache.evict_segment(segment_id);
+        }
+        assert_eq!(filter_cache.num_entries(), 0);
+        assert_eq!(matching_docs(&range_query).1, 4);
+        filter_cache.clear();
+        assert_eq!(filter_cache.num_entries(), 0);
+    }
+
+    #[test]
+    fn test_caching_wrapper_query_without_fingerprint() {
+        let mut schema_builder = SchemaBuilder::new();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a b"));
+        index_writer.add_document(doc!(text_field => "b c"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+
+        // the `DocAddress` of the document is only meaningful within the searcher.
+        let mut more_like_this_query =
+            MoreLikeThisQuery::from_document(DocAddress(0, 0), vec![text_field]);
+        more_like_this_query.set_min_term_freq(1);
+        more_like_this_query.set_min_doc_freq(1);
+        assert_eq!(more_like_this_query.fingerprint(), None);
+        let filter_cache = FilterCache::new();
+        let query = CachingWrapperQuery::new(box more_like_this_query, filter_cache.clone());
+        for _ in 0..2 {
+            let mut test_collector = TestCollector::default();
+            searcher.search(&query, &mut test_collector).unwrap();
+            assert_eq!(test_collector.docs(), vec![0, 1]);
+        }
+        assert_eq!(filter_cache.num_entries(), 0);
+    }
+}
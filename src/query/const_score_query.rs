@@ -0,0 +1,134 @@
+use Result;
+use Score;
+use DocId;
+use core::SegmentReader;
+use core::Searcher;
+use query::{ConstScorer, Explanation, Query, Scorer, Weight, WeightCache};
+use query::explanation::{does_not_match, seek_doc};
+
+/// `ConstScoreQuery` matches the same documents as its
+/// underlying query, but assigns them a constant `score`.
+///
+/// The underlying query is executed with scoring disabled,
+/// which is typically cheaper.
+#[derive(Debug)]
+pub struct ConstScoreQuery {
+    query: Box<Query>,
+    score: Score,
+}
+
+impl ConstScoreQuery {
+    /// Creates a `ConstScoreQuery` assigning the score `score`
+    /// to all of the documents matching `query`.
+    pub fn new(query: Box<Query>, score: Score) -> ConstScoreQuery {
+        ConstScoreQuery { query, score }
+    }
+
+    /// Returns the constant score.
+    pub fn score(&self) -> Score {
+        self.score
+    }
+}
+
+impl Query for ConstScoreQuery {
+    fn weight(&self, searcher: &Searcher, _scoring_enabled: bool) -> Result<Box<Weight>> {
+        let weight = self.query.weight(searcher, false)?;
+        Ok(box ConstScoreWeight::new(weight, self.score))
+    }
+
+    fn shared_weight(
+        &self,
+        searcher: &Searcher,
+        _scoring_enabled: bool,
+        weight_cache: &mut WeightCache,
+    ) -> Result<Box<Weight>> {
+        let weight = self.query.shared_weight(searcher, false, weight_cache)?;
+        Ok(box ConstScoreWeight::new(weight, self.score))
+    }
+
+    fn has_stable_representation(&self) -> bool {
+        self.query.has_stable_representation()
+    }
+
+    fn normalized(&self) -> String {
+        format!(
+            "ConstScoreQuery {{ query: {}, score: {:?} }}",
+            self.query.normalized(),
+            self.score
+        )
+    }
+}
+
+/// Weight associated to the `ConstScoreQuery` query.
+pub struct ConstScoreWeight {
+    weight: Box<Weight>,
+    score: Score,
+}
+
+impl ConstScoreWeight {
+    fn new(weight: Box<Weight>, score: Score) -> ConstScoreWeight {
+        ConstScoreWeight { weight, score }
+    }
+}
+
+impl Weight for ConstScoreWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let scorer = self.weight.scorer(reader)?;
+        let match_cost = scorer.match_cost();
+        let mut const_scorer = ConstScorer::new(scorer);
+        const_scorer.set_score(self.score);
+        const_scorer.set_match_cost(match_cost);
+        Ok(box const_scorer)
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        let mut scorer = self.weight.scorer(reader)?;
+        if !seek_doc(&mut scorer, doc) {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("ConstScoreQuery", self.score))
+    }
+
+    fn count(&self, reader: &SegmentReader) -> Result<u32> {
+        self.weight.count(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::ConstScoreQuery;
+    use collector::TopCollector;
+    use query::{Query, TermQuery};
+    use schema::{SchemaBuilder, TEXT};
+    use DocAddress;
+    use Index;
+
+    #[test]
+    fn test_const_score_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a b"));
+            index_writer.add_document(doc!(text_field => "a a a"));
+            index_writer.add_document(doc!(text_field => "b"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query = ConstScoreQuery::new(box TermQuery::new_text(text_field, "a"), 3.0);
+        let mut top_collector = TopCollector::with_limit(3);
+        query.search(&*searcher, &mut top_collector).unwrap();
+        let score_docs = top_collector.score_docs();
+        assert_eq!(score_docs.len(), 2);
+        for &(score, _) in &score_docs {
+            assert_eq!(score, 3.0);
+        }
+        assert_eq!(query.count(&*searcher).unwrap(), 2);
+        let explanation = query.explain(&*searcher, &DocAddress(0, 1)).unwrap();
+        assert_eq!(explanation.value(), 3.0);
+        assert!(query.explain(&*searcher, &DocAddress(0, 2)).is_err());
+    }
+}
@@ -0,0 +1,465 @@
+use Result;
+use Score;
+use DocId;
+use core::SegmentReader;
+use core::Searcher;
+use docset::{DocSet, SkipResult};
+use query::{EmptyScorer, Explanation, Query, Scorer, Union, Weight, WeightCache};
+use query::explanation::{does_not_match, seek_doc};
+use query::score_combiner::DoNothingCombiner;
+
+/// `DisjunctionMaxQuery` matches the documents matching any of its
+/// subqueries, and scores them with the maximum score of the subqueries
+/// they match, plus `tie_breaker` times the scores of the other matching
+/// subqueries.
+///
+/// It is typically used to search a term in several fields: a document
+/// matching the term in its `title` is not expected to be more relevant
+/// because it also happens to match it in its `body`. With a `tie_breaker`
+/// of `0`, only the best field counts. With a `tie_breaker` of `1`, the
+/// scores are summed, as in a `BooleanQuery` of `Should` clauses.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::collector::TopCollector;
+/// use tantivy::query::{DisjunctionMaxQuery, Query, TermQuery};
+/// use tantivy::schema::{SchemaBuilder, TEXT};
+/// use tantivy::{Index, Result};
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let title = schema_builder.add_text_field("title", TEXT);
+///     let body = schema_builder.add_text_field("body", TEXT);
+///     let index = Index::create_in_ram(schema_builder.build());
+///     let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+///     index_writer.add_document(doc!(title => "The Diary of Muadib", body => "A diary"));
+///     index_writer.add_document(doc!(title => "A Dairy Cow", body => "Not a diary"));
+///     index_writer.commit()?;
+///     index.load_searchers()?;
+///     let searcher = index.searcher();
+///
+///     let subqueries: Vec<Box<Query>> = vec![
+///         Box::new(TermQuery::new_text(title, "diary")),
+///         Box::new(TermQuery::new_text(body, "diary")),
+///     ];
+///     let query = DisjunctionMaxQuery::new(subqueries, 0.1);
+///     let mut top_collector = TopCollector::with_limit(2);
+///     searcher.search(&query, &mut top_collector)?;
+///     assert_eq!(top_collector.docs().len(), 2);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DisjunctionMaxQuery {
+    subqueries: Vec<Box<Query>>,
+    tie_breaker: Score,
+}
+
+impl DisjunctionMaxQuery {
+    /// Creates a `DisjunctionMaxQuery` over the given subqueries.
+    ///
+    /// `tie_breaker` is the factor applied to the scores of the
+    /// matching subqueries that do not have the maximum score.
+    /// It is typically within `[0, 1]`.
+    pub fn new(subqueries: Vec<Box<Query>>, tie_breaker: Score) -> DisjunctionMaxQuery {
+        DisjunctionMaxQuery {
+            subqueries,
+            tie_breaker,
+        }
+    }
+
+    /// Returns the subqueries, in order.
+    pub fn subqueries(&self) -> &[Box<Query>] {
+        &self.subqueries
+    }
+
+    /// Returns the tie breaker.
+    pub fn tie_breaker(&self) -> Score {
+        self.tie_breaker
+    }
+}
+
+impl Query for DisjunctionMaxQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+        let mut weight_cache = WeightCache::default();
+        self.shared_weight(searcher, scoring_enabled, &mut weight_cache)
+    }
+
+    fn shared_weight(
+        &self,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+        weight_cache: &mut WeightCache,
+    ) -> Result<Box<Weight>> {
+        let weights = self.subqueries
+            .iter()
+            .map(|subquery| weight_cache.weight(&**subquery, searcher, scoring_enabled))
+            .collect::<Result<_>>()?;
+        Ok(box DisjunctionMaxWeight {
+            weights,
+            tie_breaker: self.tie_breaker,
+            scoring_enabled,
+        })
+    }
+
+    fn has_stable_representation(&self) -> bool {
+        self.subqueries
+            .iter()
+            .all(|subquery| subquery.has_stable_representation())
+    }
+
+    fn normalized(&self) -> String {
+        let mut subqueries: Vec<String> = self.subqueries
+            .iter()
+            .map(|subquery| subquery.normalized())
+            .collect();
+        subqueries.sort();
+        format!(
+            "DisjunctionMaxQuery {{ subqueries: [{}], tie_breaker: {:?} }}",
+            subqueries.join(", "),
+            self.tie_breaker
+        )
+    }
+}
+
+/// Weight associated to the `DisjunctionMaxQuery` query.
+struct DisjunctionMaxWeight {
+    weights: Vec<Box<Weight>>,
+    tie_breaker: Score,
+    scoring_enabled: bool,
+}
+
+impl Weight for DisjunctionMaxWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let mut scorers: Vec<Box<Scorer>> = self.weights
+            .iter()
+            .map(|weight| weight.scorer(reader))
+            .collect::<Result<_>>()?;
+        if scorers.is_empty() {
+            Ok(box EmptyScorer)
+        } else if scorers.len() == 1 {
+            Ok(scorers.pop().unwrap())
+        } else if self.scoring_enabled {
+            Ok(box DisjunctionMaxScorer::new(scorers, self.tie_breaker))
+        } else {
+            Ok(box Union::<_, DoNothingCombiner>::from(scorers))
+        }
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        let mut scorer = self.scorer(reader)?;
+        if !seek_doc(&mut scorer, doc) {
+            return Err(does_not_match(doc));
+        }
+        let mut explanation = Explanation::new(
+            "DisjunctionMaxQuery, max plus tie_breaker times the others of",
+            scorer.score(),
+        );
+        explanation.add_const("tie_breaker", self.tie_breaker);
+        for weight in &self.weights {
+            let mut sub_scorer = weight.scorer(reader)?;
+            if seek_doc(&mut sub_scorer, doc) {
+                explanation.add_detail(weight.explain(reader, doc)?);
+            }
+        }
+        Ok(explanation)
+    }
+}
+
+/// Scorer associated to the `DisjunctionMaxQuery` query.
+///
+/// It matches the union of its scorers, and the score of a document
+/// is the maximum of the scores of the scorers matching it,
+/// plus `tie_breaker` times the sum of the other scores.
+pub struct DisjunctionMaxScorer<TScorer> {
+    // sorted by doc id.
+    scorers: Vec<TScorer>,
+    tie_breaker: Score,
+    // number of scorers positioned on the current doc.
+    num_current_scorers: usize,
+    doc: DocId,
+    score: Score,
+}
+
+impl<TScorer: Scorer> DisjunctionMaxScorer<TScorer> {
+    /// Creates the union of the `scorers`, scored
+    /// as a disjunction max.
+    pub fn new(scorers: Vec<TScorer>, tie_breaker: Score) -> DisjunctionMaxScorer<TScorer> {
+        let mut scorers: Vec<TScorer> = scorers
+            .into_iter()
+            .flat_map(|mut scorer| {
+                if scorer.advance() {
+                    Some(scorer)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        scorers.sort_by_key(|scorer| scorer.doc());
+        DisjunctionMaxScorer {
+            scorers,
+            tie_breaker,
+            num_current_scorers: 0,
+            doc: 0,
+            score: 0.0,
+        }
+    }
+
+    /// Advances the scorers positioned on the current doc,
+    /// removing the scorers that are exhausted.
+    ///
+    /// The scorers are not sorted anymore afterwards.
+    fn advance_current_scorers(&mut self) {
+        let mut ord = 0;
+        for _ in 0..self.num_current_scorers {
+            if self.scorers[ord].advance() {
+                ord += 1;
+            } else {
+                self.scorers.remove(ord);
+            }
+        }
+        self.num_current_scorers = 0;
+    }
+
+    /// Skips all of the scorers to `target`, after advancing
+    /// the scorers positioned on the current doc,
+    /// removing the scorers that are exhausted.
+    fn skip_scorers(&mut self, target: DocId) {
+        self.advance_current_scorers();
+        let mut ord = 0;
+        while ord < self.scorers.len() {
+            if self.scorers[ord].doc() >= target
+                || self.scorers[ord].skip_next(target) != SkipResult::End
+            {
+                ord += 1;
+            } else {
+                self.scorers.remove(ord);
+            }
+        }
+        self.scorers.sort_by_key(|scorer| scorer.doc());
+    }
+
+    /// Positions the scorer on the doc of the first scorer,
+    /// and computes its score.
+    ///
+    /// Returns false if all of the scorers are exhausted.
+    fn update_current_doc(&mut self) -> bool {
+        if self.scorers.is_empty() {
+            self.num_current_scorers = 0;
+            return false;
+        }
+        self.doc = self.scorers[0].doc();
+        let doc = self.doc;
+        self.num_current_scorers = self.scorers
+            .iter()
+            .take_while(|scorer| scorer.doc() == doc)
+            .count();
+        let mut max_score = self.scorers[0].score();
+        let mut sum_score = max_score;
+        for scorer in &mut self.scorers[1..self.num_current_scorers] {
+            let score = scorer.score();
+            if score > max_score {
+                max_score = score;
+            }
+            sum_score += score;
+        }
+        self.score = max_score + self.tie_breaker * (sum_score - max_score);
+        true
+    }
+}
+
+impl<TScorer: Scorer> DocSet for DisjunctionMaxScorer<TScorer> {
+    fn advance(&mut self) -> bool {
+        if self.num_current_scorers > 0 {
+            let next_doc = self.doc + 1;
+            self.advance_current_scorers();
+            debug_assert!(self.scorers.iter().all(|scorer| scorer.doc() >= next_doc));
+            self.scorers.sort_by_key(|scorer| scorer.doc());
+        }
+        self.update_current_doc()
+    }
+
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        self.skip_scorers(target);
+        if !self.update_current_doc() {
+            SkipResult::End
+        } else if self.doc == target {
+            SkipResult::Reached
+        } else {
+            SkipResult::OverStep
+        }
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorers
+            .iter()
+            .map(|scorer| scorer.size_hint())
+            .max()
+            .unwrap_or(0u32)
+    }
+}
+
+impl<TScorer: Scorer> Scorer for DisjunctionMaxScorer<TScorer> {
+    fn score(&mut self) -> Score {
+        self.score
+    }
+
+    fn match_cost(&self) -> f32 {
+        self.scorers.iter().map(Scorer::match_cost).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{DisjunctionMaxQuery, DisjunctionMaxScorer};
+    use collector::TopCollector;
+    use docset::{DocSet, SkipResult};
+    use query::{ConstScorer, Query, Scorer, TermQuery, VecDocSet};
+    use schema::{SchemaBuilder, TEXT};
+    use DocAddress;
+    use Index;
+    use Score;
+
+    fn const_scorer(docs: Vec<u32>, score: Score) -> ConstScorer<VecDocSet> {
+        let mut scorer = ConstScorer::new(VecDocSet::from(docs));
+        scorer.set_score(score);
+        scorer
+    }
+
+    fn make_scorer() -> DisjunctionMaxScorer<ConstScorer<VecDocSet>> {
+        DisjunctionMaxScorer::new(
+            vec![
+                const_scorer(vec![1, 3, 7, 50], 1.0),
+                const_scorer(vec![3, 4, 50, 51], 2.0),
+                const_scorer(vec![3, 8, 51], 4.0),
+                const_scorer(vec![], 8.0),
+            ],
+            0.5,
+        )
+    }
+
+    #[test]
+    fn test_disjunction_max_scorer() {
+        let mut scorer = make_scorer();
+        let expected: Vec<(u32, Score)> = vec![
+            (1, 1.0),
+            (3, 4.0 + 0.5 * 3.0),
+            (4, 2.0),
+            (7, 1.0),
+            (8, 4.0),
+            (50, 2.0 + 0.5 * 1.0),
+            (51, 4.0 + 0.5 * 2.0),
+        ];
+        for (doc, score) in expected {
+            assert!(scorer.advance());
+            assert_eq!(scorer.doc(), doc);
+            assert_eq!(scorer.score(), score);
+        }
+        assert!(!scorer.advance());
+    }
+
+    #[test]
+    fn test_disjunction_max_scorer_skip() {
+        let mut scorer = make_scorer();
+        assert_eq!(scorer.skip_next(3), SkipResult::Reached);
+        assert_eq!(scorer.score(), 4.0 + 0.5 * 3.0);
+        assert_eq!(scorer.skip_next(5), SkipResult::OverStep);
+        assert_eq!(scorer.doc(), 7);
+        assert_eq!(scorer.score(), 1.0);
+        assert!(scorer.advance());
+        assert_eq!(scorer.doc(), 8);
+        assert_eq!(scorer.skip_next(51), SkipResult::Reached);
+        assert_eq!(scorer.score(), 4.0 + 0.5 * 2.0);
+        assert_eq!(scorer.skip_next(52), SkipResult::End);
+    }
+
+    #[test]
+    fn test_disjunction_max_scorer_skip_advances_current_doc() {
+        let mut scorer = make_scorer();
+        assert!(scorer.advance());
+        assert_eq!(scorer.doc(), 1);
+        // skipping always advances, even when positioned on the target.
+        assert_eq!(scorer.skip_next(1), SkipResult::OverStep);
+        assert_eq!(scorer.doc(), 3);
+        assert_eq!(scorer.score(), 4.0 + 0.5 * 3.0);
+        assert_eq!(scorer.skip_next(3), SkipResult::OverStep);
+        assert_eq!(scorer.doc(), 4);
+        assert_eq!(scorer.score(), 2.0);
+        assert_eq!(scorer.skip_next(2), SkipResult::OverStep);
+        assert_eq!(scorer.doc(), 7);
+        assert_eq!(scorer.skip_next(50), SkipResult::Reached);
+        assert_eq!(scorer.score(), 2.0 + 0.5 * 1.0);
+        assert_eq!(scorer.skip_next(51), SkipResult::Reached);
+        assert_eq!(scorer.score(), 4.0 + 0.5 * 2.0);
+        assert!(!scorer.advance());
+    }
+
+    #[test]
+    fn test_disjunction_max_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(title => "a", body => "b"));
+            index_writer.add_document(doc!(title => "a", body => "a"));
+            index_writer.add_document(doc!(title => "b", body => "a"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let title_query = TermQuery::new_text(title, "a");
+        let body_query = TermQuery::new_text(body, "a");
+        let score = |query: &Query, doc: u32| {
+            query
+                .explain(&*searcher, &DocAddress(0u32, doc))
+                .unwrap()
+                .value()
+        };
+        let make_query = |tie_breaker: Score| {
+            DisjunctionMaxQuery::new(
+                vec![
+                    box TermQuery::new_text(title, "a"),
+                    box TermQuery::new_text(body, "a"),
+                ],
+                tie_breaker,
+            )
+        };
+        let query = make_query(0.1);
+        assert_eq!(query.count(&*searcher).unwrap(), 3);
+        let (title_score, body_score) = (score(&title_query, 1), score(&body_query, 1));
+        let max_score = title_score.max(body_score);
+        let expected_score = max_score + 0.1 * (title_score + body_score - max_score);
+        assert_eq!(score(&query, 0), score(&title_query, 0));
+        assert_eq!(score(&query, 1), expected_score);
+        assert_eq!(score(&query, 2), score(&body_query, 2));
+
+        let mut top_collector = TopCollector::with_limit(3);
+        query.search(&*searcher, &mut top_collector).unwrap();
+        let score_docs = top_collector.score_docs();
+        assert_eq!(score_docs.len(), 3);
+        assert_eq!(score_docs[0].1.doc(), 1);
+        assert_eq!(score_docs[0].0, expected_score);
+
+        assert_eq!(score(&make_query(0.0), 1), max_score);
+        assert_eq!(
+            make_query(0.1).fingerprint(),
+            DisjunctionMaxQuery::new(
+                vec![
+                    box TermQuery::new_text(body, "a"),
+                    box TermQuery::new_text(title, "a"),
+                ],
+                0.1,
+            ).fingerprint()
+        );
+        assert_ne!(make_query(0.1).fingerprint(), make_query(0.2).fingerprint());
+    }
+}
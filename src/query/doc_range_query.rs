@@ -0,0 +1,379 @@
+use Result;
+use Score;
+use DocId;
+use core::{Searcher, SegmentReader};
+use docset::{DocSet, SkipResult};
+use fastfield::DeleteBitSet;
+use query::{Explanation, Query, Scorer, Weight};
+use query::explanation::{does_not_match, seek_doc};
+use std::cmp;
+use std::ops::Range;
+
+/// `DocRangeQuery` matches the non-deleted documents whose doc id,
+/// within their segment, belongs to a given range.
+///
+/// The range is applied to each segment independently, and is truncated
+/// to the `max_doc` of the segment. All of the matching documents get a
+/// constant score of one.
+///
+/// It is typically used to sample the first documents of each segment,
+/// or with segments sorted by a field (See `SegmentReader::is_sorted_by`),
+/// in which case the documents of a range of values are contiguous.
+///
+/// Within a `BooleanQuery`, the doc ranges of the `Must` and `Filter` clauses
+/// are not intersected document by document: the other required clauses
+/// are simply restricted to the range.
+#[derive(Clone, Debug)]
+pub struct DocRangeQuery {
+    range: Range<DocId>,
+}
+
+impl DocRangeQuery {
+    /// Creates a `DocRangeQuery` matching the doc ids within `range`.
+    pub fn new(range: Range<DocId>) -> DocRangeQuery {
+        DocRangeQuery { range }
+    }
+}
+
+impl Query for DocRangeQuery {
+    fn weight(&self, _: &Searcher, _scoring_enabled: bool) -> Result<Box<Weight>> {
+        Ok(box DocRangeWeight {
+            range: self.range.clone(),
+        })
+    }
+
+    fn normalized(&self) -> String {
+        format!("DocRangeQuery({:?})", self.range)
+    }
+}
+
+struct DocRangeWeight {
+    range: Range<DocId>,
+}
+
+impl Weight for DocRangeWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let max_doc = reader.max_doc();
+        Ok(box DocRangeDocSet::new(
+            cmp::min(self.range.start, max_doc),
+            cmp::min(self.range.end, max_doc),
+            reader.delete_bitset().clone(),
+        ))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        let mut scorer = self.scorer(reader)?;
+        if !seek_doc(&mut scorer, doc) {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("DocRangeQuery", scorer.score()))
+    }
+
+    fn count(&self, reader: &SegmentReader) -> Result<u32> {
+        Ok(self.scorer(reader)?.count())
+    }
+}
+
+/// `DocSet` over the non-deleted documents of a range of doc ids
+/// `[start_doc, end_doc)`.
+///
+/// Skipping to a document does not require to go through
+/// the documents before it.
+///
+/// As a `Scorer`, all of its documents get a constant score,
+/// one by default.
+pub struct DocRangeDocSet {
+    doc: DocId,
+    next_doc: DocId,
+    end_doc: DocId,
+    delete_bitset: DeleteBitSet,
+    score: Score,
+}
+
+impl DocRangeDocSet {
+    /// Creates a `DocRangeDocSet` over the documents of `[start_doc, end_doc)`
+    /// that are not marked as deleted in `delete_bitset`.
+    pub fn new(start_doc: DocId, end_doc: DocId, delete_bitset: DeleteBitSet) -> DocRangeDocSet {
+        DocRangeDocSet {
+            doc: start_doc,
+            next_doc: start_doc,
+            end_doc: cmp::max(start_doc, end_doc),
+            delete_bitset,
+            score: 1.0,
+        }
+    }
+
+    /// Sets the constant score to a different value.
+    pub fn set_score(&mut self, score: Score) {
+        self.score = score;
+    }
+
+    /// Returns the doc ids `[start_doc, end_doc)` of the documents
+    /// that remain to be visited.
+    pub fn range(&self) -> Range<DocId> {
+        self.next_doc..self.end_doc
+    }
+
+    /// Returns the intersection of two doc ranges of the same segment.
+    ///
+    /// The score of the resulting doc range is the sum of their scores.
+    pub fn intersect(self, other: DocRangeDocSet) -> DocRangeDocSet {
+        let mut doc_range = DocRangeDocSet::new(
+            cmp::max(self.next_doc, other.next_doc),
+            cmp::min(self.end_doc, other.end_doc),
+            self.delete_bitset,
+        );
+        doc_range.set_score(self.score + other.score);
+        doc_range
+    }
+}
+
+impl DocSet for DocRangeDocSet {
+    fn advance(&mut self) -> bool {
+        while self.next_doc < self.end_doc {
+            let doc = self.next_doc;
+            self.next_doc += 1;
+            if !self.delete_bitset.is_deleted(doc) {
+                self.doc = doc;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        if target > self.next_doc {
+            self.next_doc = target;
+        }
+        if !self.advance() {
+            return SkipResult::End;
+        }
+        if self.doc == target {
+            SkipResult::Reached
+        } else {
+            SkipResult::OverStep
+        }
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.end_doc - self.next_doc
+    }
+
+    fn estimate_live(&self) -> u32 {
+        self.delete_bitset.estimate_alive(self.size_hint())
+    }
+
+    fn count(&mut self) -> u32 {
+        if self.delete_bitset.has_deletes() {
+            let mut count = 0u32;
+            while self.advance() {
+                count += 1u32;
+            }
+            count
+        } else {
+            let count = self.size_hint();
+            self.next_doc = self.end_doc;
+            count
+        }
+    }
+}
+
+impl Scorer for DocRangeDocSet {
+    fn score(&mut self) -> Score {
+        self.score
+    }
+}
+
+/// Restricts a `Scorer` to the documents of a `DocRangeDocSet`.
+///
+/// The scorer skips directly to the beginning of the range, and stops
+/// at its end. The score of the range is added to the score of the documents.
+pub struct DocRangeIntersection<TScorer: Scorer> {
+    scorer: TScorer,
+    start_doc: DocId,
+    end_doc: DocId,
+    score: Score,
+    started: bool,
+}
+
+impl<TScorer: Scorer> DocRangeIntersection<TScorer> {
+    /// Creates a `DocRangeIntersection`.
+    ///
+    /// The deleted documents of the range are expected to be
+    /// filtered out by `scorer`.
+    pub fn new(scorer: TScorer, doc_range: DocRangeDocSet) -> DocRangeIntersection<TScorer> {
+        let range = doc_range.range();
+        DocRangeIntersection {
+            scorer,
+            start_doc: range.start,
+            end_doc: range.end,
+            score: doc_range.score,
+            started: false,
+        }
+    }
+
+    fn in_range(&self) -> bool {
+        self.scorer.doc() < self.end_doc
+    }
+}
+
+impl<TScorer: Scorer> DocSet for DocRangeIntersection<TScorer> {
+    fn advance(&mut self) -> bool {
+        if self.started {
+            return self.scorer.advance() && self.in_range();
+        }
+        self.started = true;
+        if self.start_doc == self.end_doc || !self.scorer.advance() {
+            return false;
+        }
+        if self.scorer.doc() < self.start_doc
+            && self.scorer.skip_next(self.start_doc) == SkipResult::End
+        {
+            return false;
+        }
+        self.in_range()
+    }
+
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        if !self.started {
+            if !self.advance() {
+                return SkipResult::End;
+            }
+            if self.scorer.doc() >= target {
+                return if self.scorer.doc() == target {
+                    SkipResult::Reached
+                } else {
+                    SkipResult::OverStep
+                };
+            }
+        }
+        let skip_result = self.scorer.skip_next(target);
+        if skip_result == SkipResult::End || !self.in_range() {
+            return SkipResult::End;
+        }
+        skip_result
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        cmp::min(self.scorer.size_hint(), self.end_doc - self.start_doc)
+    }
+
+    fn estimate_live(&self) -> u32 {
+        cmp::min(self.scorer.estimate_live(), self.end_doc - self.start_doc)
+    }
+}
+
+impl<TScorer: Scorer> Scorer for DocRangeIntersection<TScorer> {
+    fn score(&mut self) -> Score {
+        self.scorer.score() + self.score
+    }
+
+    fn match_cost(&self) -> f32 {
+        self.scorer.match_cost()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{DocRangeDocSet, DocRangeIntersection, DocRangeQuery};
+    use collector::tests::TestCollector;
+    use docset::DocSet;
+    use fastfield::DeleteBitSet;
+    use postings::tests::test_skip_against_unoptimized;
+    use query::{BooleanQuery, ConstScorer, Occur, Query, Scorer, TermQuery, VecDocSet};
+    use schema::{SchemaBuilder, STRING};
+    use Index;
+
+    fn docs(docset: &mut DocSet) -> Vec<u32> {
+        let mut docs = vec![];
+        while docset.advance() {
+            docs.push(docset.doc());
+        }
+        docs
+    }
+
+    #[test]
+    fn test_doc_range_docset() {
+        let mut doc_range = DocRangeDocSet::new(3, 7, DeleteBitSet::empty());
+        assert_eq!(doc_range.size_hint(), 4);
+        assert_eq!(docs(&mut doc_range), vec![3, 4, 5, 6]);
+        assert_eq!(docs(&mut DocRangeDocSet::new(3, 3, DeleteBitSet::empty())), vec![]);
+        assert_eq!(DocRangeDocSet::new(3, 7, DeleteBitSet::empty()).count(), 4);
+        let intersection = DocRangeDocSet::new(3, 7, DeleteBitSet::empty())
+            .intersect(DocRangeDocSet::new(5, 10, DeleteBitSet::empty()));
+        assert_eq!(intersection.range(), 5..7);
+        test_skip_against_unoptimized(
+            || box DocRangeDocSet::new(3, 50, DeleteBitSet::empty()),
+            vec![0, 2, 3, 4, 10, 49, 50, 100],
+        );
+    }
+
+    #[test]
+    fn test_doc_range_intersection() {
+        let scorer = || ConstScorer::new(VecDocSet::from(vec![1, 4, 5, 8, 12, 20]));
+        let doc_range = || DocRangeDocSet::new(4, 13, DeleteBitSet::empty());
+        let mut intersection = DocRangeIntersection::new(scorer(), doc_range());
+        assert_eq!(docs(&mut intersection), vec![4, 5, 8, 12]);
+        let mut intersection = DocRangeIntersection::new(scorer(), doc_range());
+        assert!(intersection.advance());
+        assert_eq!(intersection.score(), 2.0);
+        test_skip_against_unoptimized(
+            || box DocRangeIntersection::new(scorer(), doc_range()),
+            vec![0, 4, 6, 12, 13, 30],
+        );
+    }
+
+    #[test]
+    fn test_doc_range_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0..10 {
+                let text = if i % 2 == 0 { "even" } else { "odd" };
+                index_writer.add_document(doc!(text_field => text));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let matching_docs = |query: &Query| {
+            let mut test_collector = TestCollector::default();
+            searcher.search(query, &mut test_collector).unwrap();
+            test_collector.docs()
+        };
+        assert_eq!(matching_docs(&DocRangeQuery::new(2..5)), vec![2, 3, 4]);
+        assert_eq!(matching_docs(&DocRangeQuery::new(8..100)), vec![8, 9]);
+        assert_eq!(DocRangeQuery::new(8..100).count(&*searcher).unwrap(), 2);
+
+        let even_query = || -> Box<Query> { box TermQuery::new_text(text_field, "even") };
+        let boolean_query = BooleanQuery::from(vec![
+            (Occur::Must, even_query()),
+            (Occur::Must, box DocRangeQuery::new(1..9) as Box<Query>),
+            (Occur::Filter, box DocRangeQuery::new(3..20) as Box<Query>),
+        ]);
+        assert_eq!(matching_docs(&boolean_query), vec![4, 6, 8]);
+        let ranges_only = BooleanQuery::from(vec![
+            (Occur::Must, box DocRangeQuery::new(1..9) as Box<Query>),
+            (Occur::Filter, box DocRangeQuery::new(3..20) as Box<Query>),
+            (Occur::MustNot, box TermQuery::new_text(text_field, "odd") as Box<Query>),
+        ]);
+        assert_eq!(matching_docs(&ranges_only), vec![4, 6, 8]);
+        let disjoint_ranges = BooleanQuery::from(vec![
+            (Occur::Must, even_query()),
+            (Occur::Filter, box DocRangeQuery::new(0..3) as Box<Query>),
+            (Occur::Filter, box DocRangeQuery::new(5..20) as Box<Query>),
+        ]);
+        assert!(matching_docs(&disjoint_ranges).is_empty());
+    }
+}
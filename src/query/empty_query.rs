@@ -0,0 +1,65 @@
+use query::{EmptyScorer, Query, Scorer, Weight};
+use core::SegmentReader;
+use core::Searcher;
+use Result;
+
+/// Query that matches none of the documents.
+///
+/// It is a typed no-op query: for instance, a `BooleanQuery`
+/// whose clauses contradict each other is executed as an `EmptyQuery`,
+/// and a `MatchQuery` whose text has no token is an `EmptyQuery`.
+#[derive(Debug)]
+pub struct EmptyQuery;
+
+impl Query for EmptyQuery {
+    fn weight(&self, _: &Searcher, _: bool) -> Result<Box<Weight>> {
+        Ok(box EmptyWeight)
+    }
+}
+
+/// Weight associated to the `EmptyQuery` query.
+///
+/// Its scorer is an `EmptyScorer`.
+pub struct EmptyWeight;
+
+impl Weight for EmptyWeight {
+    fn scorer(&self, _: &SegmentReader) -> Result<Box<Scorer>> {
+        Ok(box EmptyScorer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::EmptyQuery;
+    use downcast::Downcast;
+    use query::{EmptyScorer, Query, TermQuery};
+    use schema::{SchemaBuilder, TEXT};
+    use DocAddress;
+    use Index;
+
+    #[test]
+    fn test_empty_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(EmptyQuery.count(&*searcher).unwrap(), 0);
+        assert!(EmptyQuery.explain(&*searcher, &DocAddress(0, 0)).is_err());
+        // a term missing from a segment yields an `EmptyScorer`.
+        let segment_reader = searcher.segment_reader(0u32);
+        let missing_term_query = TermQuery::new_text(text_field, "b");
+        let weight = missing_term_query.weight(&*searcher, true).unwrap();
+        assert!(Downcast::<EmptyScorer>::is_type(&*weight.scorer(segment_reader).unwrap()));
+        assert!(missing_term_query.explain(&*searcher, &DocAddress(0, 0)).is_err());
+        let term_query = TermQuery::new_text(text_field, "a");
+        let weight = term_query.weight(&*searcher, true).unwrap();
+        assert!(!Downcast::<EmptyScorer>::is_type(&*weight.scorer(segment_reader).unwrap()));
+    }
+}
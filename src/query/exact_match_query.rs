@@ -0,0 +1,293 @@
+use Result;
+use Score;
+use DocId;
+use core::SegmentReader;
+use core::Searcher;
+use docset::{DocSet, SkipResult};
+use fastfield::FastFieldReader;
+use query::{EmptyQuery, EmptyScorer, Explanation, MatchQuery, PhraseQuery, Query,
+            QueryParserError, Scorer, TermQuery, Weight, WeightCache};
+use query::explanation::{does_not_match, seek_doc};
+use schema::{Field, FieldType, IndexRecordOption, Term};
+
+/// Match cost of the lookup of the fieldnorm of a candidate document.
+const FIELDNORM_MATCH_COST: f32 = 1f32;
+
+/// `ExactMatchQuery` matches the documents whose field value
+/// is exactly a given value, rather than containing it.
+///
+/// - For the fields indexed with the `raw` tokenizer, the value is searched
+/// as a single term, equal to the entire field value.
+/// In a multivalued field, a document matches if any of its values is equal to the value.
+/// - For the tokenized fields, the value is analyzed with the tokenizer of the field,
+/// and searched as a phrase. The documents only match if the field has as many tokens
+/// as the value (its fieldnorm), so that the phrase is anchored at
+/// the beginning and at the end of the field.
+/// As the phrase cannot span several values of a multivalued field while
+/// the tokens of all of its values are counted, a document with several
+/// non-empty values never matches.
+/// A value with more than one token requires the positions to be indexed.
+/// - For the other fields, the value is parsed as for a `MatchQuery`.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::collector::CountCollector;
+/// use tantivy::query::ExactMatchQuery;
+/// use tantivy::schema::{SchemaBuilder, STRING, TEXT};
+/// use tantivy::{Index, Result};
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let title = schema_builder.add_text_field("title", TEXT);
+///     let isbn = schema_builder.add_text_field("isbn", STRING);
+///     let index = Index::create_in_ram(schema_builder.build());
+///     let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+///     index_writer.add_document(doc!(title => "The Name of the Wind", isbn => "0-7564-0407-9"));
+///     index_writer.add_document(doc!(title => "The Wind", isbn => "0-7564-0407"));
+///     index_writer.commit()?;
+///     index.load_searchers()?;
+///     let searcher = index.searcher();
+///
+///     let count = |query: &ExactMatchQuery| -> Result<usize> {
+///         let mut count_collector = CountCollector::default();
+///         searcher.search(query, &mut count_collector)?;
+///         Ok(count_collector.count())
+///     };
+///
+///     assert_eq!(count(&ExactMatchQuery::new(title, "the wind"))?, 1);
+///     assert_eq!(count(&ExactMatchQuery::new(title, "name of the wind"))?, 0);
+///     assert_eq!(count(&ExactMatchQuery::new(isbn, "0-7564-0407"))?, 1);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ExactMatchQuery {
+    field: Field,
+    text: String,
+}
+
+impl ExactMatchQuery {
+    /// Creates an `ExactMatchQuery` matching the documents
+    /// whose value for `field` is `text`.
+    pub fn new(field: Field, text: &str) -> ExactMatchQuery {
+        ExactMatchQuery {
+            field,
+            text: text.to_string(),
+        }
+    }
+
+    /// Returns the query matching the documents containing the value,
+    /// together with the number of tokens the field needs to have, if any.
+    fn build_query(&self, searcher: &Searcher) -> Result<(Box<Query>, Option<u64>)> {
+        let field_entry = searcher.schema().get_field_entry(self.field);
+        let text_options = match *field_entry.field_type() {
+            FieldType::Str(ref text_options) => text_options,
+            _ => {
+                return Ok((box MatchQuery::new(self.field, &self.text), None));
+            }
+        };
+        let indexing_options = text_options.get_indexing_options().ok_or_else(|| {
+            QueryParserError::FieldNotIndexed(field_entry.name().to_string())
+        })?;
+        let tokenizer_name = indexing_options.tokenizer();
+        if tokenizer_name == "raw" {
+            let term = Term::from_field_text(self.field, &self.text);
+            return Ok((box TermQuery::new(term, IndexRecordOption::WithFreqs), None));
+        }
+        let tokenizer = searcher.tokenizers().get(tokenizer_name).ok_or_else(|| {
+            QueryParserError::UnknownTokenizer(
+                field_entry.name().to_string(),
+                tokenizer_name.to_string(),
+            )
+        })?;
+        // only the first term of each position is part of the phrase.
+        let mut phrase_terms: Vec<Term> = vec![];
+        let mut last_position_opt = None;
+        let field = self.field;
+        let num_tokens = tokenizer.token_stream(&self.text).process(&mut |token| {
+            if last_position_opt != Some(token.position) {
+                phrase_terms.push(Term::from_field_text(field, &token.text));
+                last_position_opt = Some(token.position);
+            }
+        });
+        let query: Box<Query> = match phrase_terms.len() {
+            0 => box EmptyQuery,
+            1 => box TermQuery::new(phrase_terms.pop().unwrap(), IndexRecordOption::WithFreqs),
+            _ => box PhraseQuery::from(phrase_terms),
+        };
+        Ok((query, Some(u64::from(num_tokens))))
+    }
+
+    fn wrap_weight(&self, weight: Box<Weight>, num_tokens_opt: Option<u64>) -> Box<Weight> {
+        match num_tokens_opt {
+            Some(num_tokens) => box ExactMatchWeight {
+                weight,
+                field: self.field,
+                num_tokens,
+            },
+            None => weight,
+        }
+    }
+}
+
+impl Query for ExactMatchQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+        let (query, num_tokens_opt) = self.build_query(searcher)?;
+        let weight = query.weight(searcher, scoring_enabled)?;
+        Ok(self.wrap_weight(weight, num_tokens_opt))
+    }
+
+    fn shared_weight(
+        &self,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+        weight_cache: &mut WeightCache,
+    ) -> Result<Box<Weight>> {
+        let (query, num_tokens_opt) = self.build_query(searcher)?;
+        let weight = query.shared_weight(searcher, scoring_enabled, weight_cache)?;
+        Ok(self.wrap_weight(weight, num_tokens_opt))
+    }
+}
+
+/// Weight restricting the documents matched by its underlying
+/// weight to the ones whose field has a given number of tokens.
+struct ExactMatchWeight {
+    weight: Box<Weight>,
+    field: Field,
+    num_tokens: u64,
+}
+
+impl Weight for ExactMatchWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let fieldnorm_reader = match reader.get_fieldnorms_reader(self.field) {
+            Some(fieldnorm_reader) => fieldnorm_reader,
+            None => {
+                return Ok(box EmptyScorer);
+            }
+        };
+        Ok(box ExactMatchScorer {
+            scorer: self.weight.scorer(reader)?,
+            fieldnorm_reader,
+            num_tokens: self.num_tokens,
+        })
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        let mut scorer = self.scorer(reader)?;
+        if !seek_doc(&mut scorer, doc) {
+            return Err(does_not_match(doc));
+        }
+        let mut explanation = Explanation::new("ExactMatchQuery", scorer.score());
+        explanation.add_detail(self.weight.explain(reader, doc)?);
+        Ok(explanation)
+    }
+}
+
+struct ExactMatchScorer {
+    scorer: Box<Scorer>,
+    fieldnorm_reader: FastFieldReader<u64>,
+    num_tokens: u64,
+}
+
+impl ExactMatchScorer {
+    fn is_exact_match(&self) -> bool {
+        self.fieldnorm_reader.get(self.scorer.doc()) == self.num_tokens
+    }
+}
+
+impl DocSet for ExactMatchScorer {
+    fn advance(&mut self) -> bool {
+        while self.scorer.advance() {
+            if self.is_exact_match() {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        match self.scorer.skip_next(target) {
+            SkipResult::End => SkipResult::End,
+            SkipResult::Reached if self.is_exact_match() => SkipResult::Reached,
+            SkipResult::OverStep if self.is_exact_match() => SkipResult::OverStep,
+            _ => {
+                if self.advance() {
+                    SkipResult::OverStep
+                } else {
+                    SkipResult::End
+                }
+            }
+        }
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+}
+
+impl Scorer for ExactMatchScorer {
+    fn score(&mut self) -> Score {
+        self.scorer.score()
+    }
+
+    fn match_cost(&self) -> f32 {
+        self.scorer.match_cost() + FIELDNORM_MATCH_COST
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::ExactMatchQuery;
+    use collector::tests::TestCollector;
+    use query::Query;
+    use schema::{SchemaBuilder, INT_INDEXED, STRING, TEXT};
+    use DocAddress;
+    use Index;
+
+    #[test]
+    fn test_exact_match_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let raw_field = schema_builder.add_text_field("raw", STRING);
+        let int_field = schema_builder.add_u64_field("int", INT_INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "New York", raw_field => "New York"));
+            index_writer.add_document(doc!(text_field => "New York City", raw_field => "New"));
+            index_writer.add_document(
+                doc!(text_field => "york", raw_field => "York", int_field => 3u64),
+            );
+            index_writer.add_document(doc!(text_field => "new", text_field => "york"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let test_query = |query: ExactMatchQuery| {
+            let mut test_collector = TestCollector::default();
+            searcher.search(&query, &mut test_collector).unwrap();
+            test_collector.docs()
+        };
+        assert_eq!(test_query(ExactMatchQuery::new(text_field, "new york")), vec![0]);
+        assert_eq!(test_query(ExactMatchQuery::new(text_field, "NEW YORK, city")), vec![1]);
+        assert_eq!(test_query(ExactMatchQuery::new(text_field, "York")), vec![2]);
+        assert_eq!(test_query(ExactMatchQuery::new(text_field, "new")), Vec::<u32>::new());
+        assert_eq!(test_query(ExactMatchQuery::new(text_field, "")), Vec::<u32>::new());
+        assert_eq!(test_query(ExactMatchQuery::new(raw_field, "New York")), vec![0]);
+        assert_eq!(test_query(ExactMatchQuery::new(raw_field, "New")), vec![1]);
+        assert_eq!(test_query(ExactMatchQuery::new(raw_field, "new york")), Vec::<u32>::new());
+        assert_eq!(test_query(ExactMatchQuery::new(int_field, "3")), vec![2]);
+
+        let query = ExactMatchQuery::new(text_field, "york");
+        assert_eq!(query.count(&*searcher).unwrap(), 1);
+        assert!(query.explain(&*searcher, &DocAddress(0, 2)).is_ok());
+        assert!(query.explain(&*searcher, &DocAddress(0, 0)).is_err());
+    }
+}
@@ -114,6 +114,10 @@ where
     fn size_hint(&self) -> u32 {
         self.underlying_docset.size_hint()
     }
+
+    fn estimate_live(&self) -> u32 {
+        self.underlying_docset.estimate_live()
+    }
 }
 
 impl<TScorer, TDocSetExclude> Scorer for Exclude<TScorer, TDocSetExclude>
@@ -124,6 +128,10 @@ where
     fn score(&mut self) -> Score {
         self.underlying_docset.score()
     }
+
+    fn match_cost(&self) -> f32 {
+        self.underlying_docset.match_cost()
+    }
 }
 
 #[cfg(test)]
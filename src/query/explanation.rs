@@ -0,0 +1,105 @@
+use DocId;
+use Score;
+use docset::DocSet;
+use error::{Error, ErrorKind};
+use serde_json;
+
+/// Returns the error emitted when trying to explain
+/// the score of a document that does not match the query.
+pub(crate) fn does_not_match(doc: DocId) -> Error {
+    ErrorKind::InvalidArgument(format!("Document #({}) does not match", doc)).into()
+}
+
+/// Positions the `DocSet` on `doc`.
+///
+/// Returns false if `doc` does not belong to the `DocSet`.
+pub(crate) fn seek_doc<TDocSet: DocSet + ?Sized>(docset: &mut TDocSet, doc: DocId) -> bool {
+    if !docset.advance() {
+        return false;
+    }
+    if docset.doc() < doc {
+        docset.skip_next(doc) == ::SkipResult::Reached
+    } else {
+        docset.doc() == doc
+    }
+}
+
+/// Object describing the score of a given document.
+///
+/// It is organized as a tree: the score of a node is computed
+/// from the scores of its details.
+///
+/// `Explanation`s are obtained by calling `.explain(...)` over a `Query`
+/// or a `Weight`, and are designed to help debugging relevance.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Explanation {
+    value: Score,
+    description: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default)]
+    details: Vec<Explanation>,
+}
+
+impl Explanation {
+    /// Creates a new explanation object.
+    pub fn new<T: ToString>(description: T, value: Score) -> Explanation {
+        Explanation {
+            value,
+            description: description.to_string(),
+            details: vec![],
+        }
+    }
+
+    /// Returns the value associated to the current node.
+    pub fn value(&self) -> Score {
+        self.value
+    }
+
+    /// Returns the description of the current node.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns the details explaining the value of the current node.
+    pub fn details(&self) -> &[Explanation] {
+        &self.details
+    }
+
+    /// Add some detail, explaining some part of the current node formula.
+    ///
+    /// Details are treated as child of the current node.
+    pub fn add_detail(&mut self, child_explanation: Explanation) {
+        self.details.push(child_explanation);
+    }
+
+    /// Shortcut for `self.add_detail(Explanation::new(description, value))`.
+    pub fn add_const<T: ToString>(&mut self, description: T, value: Score) {
+        self.add_detail(Explanation::new(description, value));
+    }
+
+    /// Returns an indented json representation of the explanation tree.
+    pub fn to_pretty_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Serializing an explanation should never fail")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Explanation;
+    use serde_json;
+
+    #[test]
+    fn test_explanation_serialization() {
+        let mut explanation = Explanation::new("product of", 6.0);
+        explanation.add_const("a", 2.0);
+        explanation.add_const("b", 3.0);
+        let json = serde_json::to_string(&explanation).unwrap();
+        assert_eq!(
+            json,
+            r#"{"value":6.0,"description":"product of","details":[{"value":2.0,"description":"a"},{"value":3.0,"description":"b"}]}"#
+        );
+        let deserialized: Explanation = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, explanation);
+    }
+}
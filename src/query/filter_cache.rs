@@ -0,0 +1,107 @@
+use Result;
+use common::BitSet;
+use core::{SegmentId, SegmentReader, Warmer};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+// (number of deleted docs, fingerprint of the query)
+type FilterKey = (u32, u64);
+
+/// Cache of the documents matching filter queries, for each segment.
+/// (See `CachingWrapperQuery`)
+///
+/// The entries of a segment are keyed by the fingerprint of the
+/// cached query, as well as by the number of deleted documents
+/// of the segment, so that they are not reused after new deletes.
+///
+/// The cache is cheap to clone: clones share their entries.
+/// Registering the cache as a `Warmer` of an `IndexReader` evicts
+/// the entries of the segments that are not searched anymore,
+/// typically after a merge.
+#[derive(Clone, Default)]
+pub struct FilterCache {
+    segments: Arc<RwLock<HashMap<SegmentId, HashMap<FilterKey, Arc<BitSet>>>>>,
+}
+
+impl FilterCache {
+    /// Creates an empty `FilterCache`.
+    pub fn new() -> FilterCache {
+        FilterCache::default()
+    }
+
+    pub(crate) fn get(
+        &self,
+        segment_reader: &SegmentReader,
+        fingerprint: u64,
+    ) -> Option<Arc<BitSet>> {
+        let key = (segment_reader.num_deleted_docs(), fingerprint);
+        self.segments
+            .read()
+            .expect("Filter cache lock poisoned")
+            .get(&segment_reader.segment_id())
+            .and_then(|segment_entries| segment_entries.get(&key))
+            .cloned()
+    }
+
+    pub(crate) fn insert(
+        &self,
+        segment_reader: &SegmentReader,
+        fingerprint: u64,
+        docs: Arc<BitSet>,
+    ) {
+        let num_deleted_docs = segment_reader.num_deleted_docs();
+        let mut segments = self.segments.write().expect("Filter cache lock poisoned");
+        let segment_entries = segments
+            .entry(segment_reader.segment_id())
+            .or_insert_with(HashMap::new);
+        // Deletes only ever grow: the entries computed before
+        // the last deletes will never be used again.
+        segment_entries.retain(|&(entry_num_deleted_docs, _), _| {
+            entry_num_deleted_docs == num_deleted_docs
+        });
+        segment_entries.insert((num_deleted_docs, fingerprint), docs);
+    }
+
+    /// Returns the number of cached filters, over all segments.
+    pub fn num_entries(&self) -> usize {
+        self.segments
+            .read()
+            .expect("Filter cache lock poisoned")
+            .values()
+            .map(|segment_entries| segment_entries.len())
+            .sum()
+    }
+
+    /// Removes the cached filters of the given segment.
+    pub fn evict_segment(&self, segment_id: SegmentId) {
+        self.segments
+            .write()
+            .expect("Filter cache lock poisoned")
+            .remove(&segment_id);
+    }
+
+    /// Removes all of the cached filters.
+    pub fn clear(&self) {
+        self.segments
+            .write()
+            .expect("Filter cache lock poisoned")
+            .clear();
+    }
+}
+
+impl Warmer for FilterCache {
+    fn warm(&self, _segment_reader: &SegmentReader) -> Result<()> {
+        Ok(())
+    }
+
+    fn evict(&self, segment_id: SegmentId) {
+        self.evict_segment(segment_id);
+    }
+}
+
+impl fmt::Debug for FilterCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FilterCache({} entries)", self.num_entries())
+    }
+}
@@ -0,0 +1,293 @@
+use Result;
+use Score;
+use DocId;
+use common::BitSet;
+use core::SegmentReader;
+use core::Searcher;
+use docset::{DocSet, SkipResult};
+use fastfield::{FastFieldReader, FastValue};
+use query::{Explanation, Query, Scorer, Weight, WeightCache};
+use schema::Field;
+use std::fmt;
+use std::sync::Arc;
+
+type ScoreFn<Item> = Fn(Score, Item) -> Score + Send + Sync;
+
+/// `FunctionScoreQuery` tweaks the score of the documents
+/// matched by its underlying query, using the value of a fast field.
+///
+/// It matches exactly the same documents as the underlying query.
+/// The score of each document is computed by a user defined function,
+/// given the score of the underlying query and the value
+/// of the fast field for the document.
+///
+/// It makes it possible, for instance, to boost the recent or popular
+/// documents.
+///
+/// The field needs to be a single valued fast field.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::schema::{SchemaBuilder, TEXT, FAST};
+/// use tantivy::query::{FunctionScoreQuery, TermQuery};
+/// use tantivy::collector::TopCollector;
+/// use tantivy::Index;
+///
+/// # fn main() {
+/// let mut schema_builder = SchemaBuilder::default();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let popularity = schema_builder.add_u64_field("popularity", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// {
+///     let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+///     index_writer.add_document(doc!(title => "diary", popularity => 1u64));
+///     index_writer.add_document(doc!(title => "diary", popularity => 10u64));
+///     index_writer.commit().unwrap();
+/// }
+/// index.load_searchers().unwrap();
+/// let searcher = index.searcher();
+/// let query = FunctionScoreQuery::new(
+///     Box::new(TermQuery::new_text(title, "diary")),
+///     popularity,
+///     |score, popularity: u64| score * (1.0 + (popularity as f32).ln_1p()),
+/// );
+/// let mut top_collector = TopCollector::with_limit(2);
+/// searcher.search(&query, &mut top_collector).unwrap();
+/// assert_eq!(top_collector.docs()[0].doc(), 1u32);
+/// # }
+/// ```
+pub struct FunctionScoreQuery<Item: FastValue> {
+    query: Box<Query>,
+    field: Field,
+    score_fn: Arc<ScoreFn<Item>>,
+}
+
+impl<Item: FastValue + Send + Sync + 'static> FunctionScoreQuery<Item> {
+    /// Creates a `FunctionScoreQuery` computing the score of the
+    /// documents matching `query` as `score_fn(score, value)`,
+    /// where `value` is the value of the fast field `field`.
+    pub fn new<F>(query: Box<Query>, field: Field, score_fn: F) -> FunctionScoreQuery<Item>
+    where
+        F: Fn(Score, Item) -> Score + Send + Sync + 'static,
+    {
+        FunctionScoreQuery {
+            query,
+            field,
+            score_fn: Arc::new(score_fn),
+        }
+    }
+
+    fn function_weight(&self, weight: Box<Weight>) -> Box<Weight> {
+        box FunctionScoreWeight {
+            weight,
+            field: self.field,
+            score_fn: Arc::clone(&self.score_fn),
+        }
+    }
+}
+
+impl<Item: FastValue> fmt::Debug for FunctionScoreQuery<Item> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The address of the function is part of the representation,
+        // so that queries with different functions do not share their weight.
+        write!(
+            f,
+            "FunctionScoreQuery {{ query: {:?}, field: {:?}, score_fn: {:p} }}",
+            self.query, self.field, &*self.score_fn
+        )
+    }
+}
+
+impl<Item: FastValue + Send + Sync + 'static> Query for FunctionScoreQuery<Item> {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+        let weight = self.query.weight(searcher, scoring_enabled)?;
+        Ok(self.function_weight(weight))
+    }
+
+    fn shared_weight(
+        &self,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+        weight_cache: &mut WeightCache,
+    ) -> Result<Box<Weight>> {
+        let weight = self.query
+            .shared_weight(searcher, scoring_enabled, weight_cache)?;
+        Ok(self.function_weight(weight))
+    }
+
+    /// The address of the function is part of the representation.
+    fn has_stable_representation(&self) -> bool {
+        false
+    }
+
+    /// As the address of the function is part of the representation,
+    /// it is only relevant within the current process.
+    fn normalized(&self) -> String {
+        format!(
+            "FunctionScoreQuery {{ query: {}, field: {:?}, score_fn: {:p} }}",
+            self.query.normalized(),
+            self.field,
+            &*self.score_fn
+        )
+    }
+}
+
+/// Weight associated to the `FunctionScoreQuery` query.
+pub struct FunctionScoreWeight<Item: FastValue> {
+    weight: Box<Weight>,
+    field: Field,
+    score_fn: Arc<ScoreFn<Item>>,
+}
+
+impl<Item: FastValue + 'static> Weight for FunctionScoreWeight<Item> {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let fast_field_reader = reader.fast_field_reader_or_default(self.field)?;
+        let scorer = self.weight.scorer(reader)?;
+        Ok(box FunctionScorer {
+            scorer,
+            fast_field_reader,
+            score_fn: Arc::clone(&self.score_fn),
+        })
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        let fast_field_reader: FastFieldReader<Item> =
+            reader.fast_field_reader_or_default(self.field)?;
+        let sub_explanation = self.weight.explain(reader, doc)?;
+        let score = (self.score_fn)(sub_explanation.value(), fast_field_reader.get(doc));
+        let mut explanation = Explanation::new("Function of the score and the fast field", score);
+        explanation.add_detail(sub_explanation);
+        Ok(explanation)
+    }
+
+    fn count(&self, reader: &SegmentReader) -> Result<u32> {
+        self.weight.count(reader)
+    }
+}
+
+/// Scorer associated to the `FunctionScoreQuery` query.
+pub struct FunctionScorer<Item: FastValue> {
+    scorer: Box<Scorer>,
+    fast_field_reader: FastFieldReader<Item>,
+    score_fn: Arc<ScoreFn<Item>>,
+}
+
+impl<Item: FastValue> DocSet for FunctionScorer<Item> {
+    fn advance(&mut self) -> bool {
+        self.scorer.advance()
+    }
+
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        self.scorer.skip_next(target)
+    }
+
+    fn fill_buffer(&mut self, buffer: &mut [DocId]) -> usize {
+        self.scorer.fill_buffer(buffer)
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+
+    fn estimate_live(&self) -> u32 {
+        self.scorer.estimate_live()
+    }
+
+    fn append_to_bitset(&mut self, bitset: &mut BitSet) {
+        self.scorer.append_to_bitset(bitset);
+    }
+}
+
+impl<Item: FastValue + 'static> Scorer for FunctionScorer<Item> {
+    fn score(&mut self) -> Score {
+        let val = self.fast_field_reader.get(self.scorer.doc());
+        (self.score_fn)(self.scorer.score(), val)
+    }
+
+    fn match_cost(&self) -> f32 {
+        self.scorer.match_cost()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::FunctionScoreQuery;
+    use collector::TopCollector;
+    use query::{BooleanQuery, Query, TermQuery};
+    use schema::{SchemaBuilder, FAST, TEXT};
+    use DocAddress;
+    use Index;
+
+    #[test]
+    fn test_function_score_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let timestamp_field = schema_builder.add_i64_field("timestamp", FAST);
+        let rank_field = schema_builder.add_u64_field("rank", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(
+                text_field => "a b",
+                timestamp_field => -10i64,
+                rank_field => 0u64
+            ));
+            index_writer.add_document(doc!(
+                text_field => "a",
+                timestamp_field => 5i64,
+                rank_field => 1u64
+            ));
+            index_writer.add_document(doc!(
+                text_field => "b",
+                timestamp_field => 8i64,
+                rank_field => 2u64
+            ));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query = FunctionScoreQuery::new(
+            box TermQuery::new_text(text_field, "a"),
+            timestamp_field,
+            |_score, timestamp: i64| timestamp as f32,
+        );
+        let mut top_collector = TopCollector::with_limit(2);
+        query.search(&*searcher, &mut top_collector).unwrap();
+        assert_eq!(
+            top_collector.score_docs(),
+            vec![(5f32, DocAddress(0, 1)), (-10f32, DocAddress(0, 0))]
+        );
+        let explanation = query.explain(&*searcher, &DocAddress(0, 0)).unwrap();
+        assert_eq!(explanation.value(), -10f32);
+        assert_eq!(query.count(&*searcher).unwrap(), 2);
+
+        // The same subqueries with different functions do not share their scores.
+        let text_query = || box TermQuery::new_text(text_field, "b");
+        let boolean_query = BooleanQuery::builder()
+            .should(FunctionScoreQuery::new(text_query(), rank_field, |_, rank: u64| {
+                rank as f32
+            }))
+            .should(FunctionScoreQuery::new(text_query(), rank_field, |_, rank: u64| {
+                10f32 * rank as f32
+            }))
+            .build();
+        let mut top_collector = TopCollector::with_limit(2);
+        boolean_query.search(&*searcher, &mut top_collector).unwrap();
+        let score_docs = top_collector.score_docs();
+        assert_eq!(score_docs[0].1, DocAddress(0, 2));
+        assert_eq!(score_docs[0].0, 22f32);
+
+        // The address of the functions only makes sense within the process.
+        assert!(!boolean_query.has_stable_representation());
+        assert_eq!(boolean_query.fingerprint(), None);
+        let text_query = TermQuery::new_text(text_field, "b");
+        assert!(text_query.has_stable_representation());
+        assert!(text_query.fingerprint().is_some());
+    }
+}
@@ -1,5 +1,6 @@
 use docset::{DocSet, SkipResult};
 use query::Scorer;
+use std::cmp::Ordering;
 use DocId;
 use Score;
 
@@ -13,7 +14,7 @@ pub struct Intersection<TDocSet: DocSet> {
 impl<TDocSet: DocSet> From<Vec<TDocSet>> for Intersection<TDocSet> {
     fn from(mut docsets: Vec<TDocSet>) -> Intersection<TDocSet> {
         assert!(docsets.len() >= 2);
-        docsets.sort_by_key(|docset| docset.size_hint());
+        docsets.sort_by_key(|docset| docset.estimate_live());
         Intersection {
             docsets,
             finished: false,
@@ -22,6 +23,32 @@ impl<TDocSet: DocSet> From<Vec<TDocSet>> for Intersection<TDocSet> {
     }
 }
 
+impl<TScorer: Scorer> Intersection<TScorer> {
+    /// Creates the intersection of scorers, checking the candidate
+    /// documents against the scorers by increasing match cost.
+    ///
+    /// As in `Intersection::from`, the scorer with the fewest documents
+    /// drives the iteration. The other scorers are sorted by increasing
+    /// match cost, then by increasing number of documents, so that an
+    /// expensive scorer, such as a phrase scorer, is only skipped to the
+    /// candidate documents matching all of the cheaper ones.
+    pub fn from_scorers(mut scorers: Vec<TScorer>) -> Intersection<TScorer> {
+        assert!(scorers.len() >= 2);
+        scorers.sort_by_key(|scorer| scorer.estimate_live());
+        scorers[1..].sort_by(|left, right| {
+            left.match_cost()
+                .partial_cmp(&right.match_cost())
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| left.estimate_live().cmp(&right.estimate_live()))
+        });
+        Intersection {
+            docsets: scorers,
+            finished: false,
+            doc: 0u32,
+        }
+    }
+}
+
 impl<TDocSet: DocSet> Intersection<TDocSet> {
     /// Returns an array to the underlying `DocSet`s of the intersection.
     /// These `DocSet` are in the same position as the `IntersectionDocSet`,
@@ -118,6 +145,14 @@ impl<TDocSet: DocSet> DocSet for Intersection<TDocSet> {
             .min()
             .unwrap_or(0u32)
     }
+
+    fn estimate_live(&self) -> u32 {
+        self.docsets
+            .iter()
+            .map(|docset| docset.estimate_live())
+            .min()
+            .unwrap_or(0u32)
+    }
 }
 
 impl<TScorer> Scorer for Intersection<TScorer>
@@ -127,14 +162,58 @@ where
     fn score(&mut self) -> Score {
         self.docsets.iter_mut().map(Scorer::score).sum()
     }
+
+    fn match_cost(&self) -> f32 {
+        self.docsets.iter().map(Scorer::match_cost).sum()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use docset::{DocSet, SkipResult};
     use super::Intersection;
-    use query::VecDocSet;
+    use query::{ConstScorer, Scorer, VecDocSet};
     use postings::tests::test_skip_against_unoptimized;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use DocId;
+    use Score;
+
+    /// Scorer recording the targets it is skipped to.
+    struct RecordingScorer {
+        docset: VecDocSet,
+        match_cost: f32,
+        skip_targets: Rc<RefCell<Vec<DocId>>>,
+    }
+
+    impl DocSet for RecordingScorer {
+        fn advance(&mut self) -> bool {
+            self.docset.advance()
+        }
+
+        fn skip_next(&mut self, target: DocId) -> SkipResult {
+            self.skip_targets.borrow_mut().push(target);
+            self.docset.skip_next(target)
+        }
+
+        fn doc(&self) -> DocId {
+            self.docset.doc()
+        }
+
+        fn size_hint(&self) -> u32 {
+            self.docset.size_hint()
+        }
+    }
+
+    impl Scorer for RecordingScorer {
+        fn score(&mut self) -> Score {
+            1.0
+        }
+
+        fn match_cost(&self) -> f32 {
+            self.match_cost
+        }
+    }
 
     #[test]
     fn test_intersection() {
@@ -159,6 +238,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_intersection_from_scorers() {
+        // the smallest scorer drives the iteration, even if it is expensive.
+        let mut expensive = ConstScorer::new(VecDocSet::from(vec![3, 9]));
+        expensive.set_match_cost(10f32);
+        let cheap = ConstScorer::new(VecDocSet::from(vec![1, 3, 4, 9, 18]));
+        let mut intersection = Intersection::from_scorers(vec![cheap, expensive]);
+        assert_eq!(intersection.docsets()[0].match_cost(), 10f32);
+        assert_eq!(intersection.docsets()[0].size_hint(), 2);
+        assert_eq!(intersection.match_cost(), 10f32);
+        assert!(intersection.advance());
+        assert_eq!(intersection.doc(), 3);
+        assert!(intersection.advance());
+        assert_eq!(intersection.doc(), 9);
+        assert!(!intersection.advance());
+    }
+
+    #[test]
+    fn test_intersection_from_scorers_skips_expensive_scorers_last() {
+        let recording_scorer = |docs: Vec<DocId>, match_cost: f32| RecordingScorer {
+            docset: VecDocSet::from(docs),
+            match_cost,
+            skip_targets: Rc::new(RefCell::new(Vec::new())),
+        };
+        let expensive = recording_scorer((1..13).collect(), 10f32);
+        let expensive_skip_targets = Rc::clone(&expensive.skip_targets);
+        let leader = recording_scorer(vec![3, 5, 9, 12], 0f32);
+        let cheap = recording_scorer(vec![3, 9, 12, 20, 30], 0f32);
+        let mut intersection = Intersection::from_scorers(vec![expensive, cheap, leader]);
+        let match_costs: Vec<f32> = intersection
+            .docsets()
+            .iter()
+            .map(|scorer| scorer.match_cost())
+            .collect();
+        assert_eq!(match_costs, vec![0f32, 0f32, 10f32]);
+        let mut docs = vec![];
+        while intersection.advance() {
+            docs.push(intersection.doc());
+        }
+        assert_eq!(docs, vec![3, 9, 12]);
+        // the expensive scorer is only skipped to the documents matching the others.
+        assert_eq!(*expensive_skip_targets.borrow(), vec![3, 9, 12]);
+    }
+
     #[test]
     fn test_intersection_zero() {
         let left = VecDocSet::from(vec![0]);
@@ -0,0 +1,329 @@
+use Result;
+use core::Searcher;
+use query::{BooleanQuery, EmptyQuery, Occur, PhraseQuery, Query, QueryParserError, TermQuery,
+            Weight};
+use query::WeightCache;
+use schema::{Field, FieldType, IndexRecordOption, Term};
+use std::str::FromStr;
+use common::base64;
+
+/// Defines how the clauses of a `MatchQuery` are combined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchOperator {
+    /// Documents need to match at least one of the clauses.
+    /// (or `minimum_should_match` of them, if it is set)
+    Or,
+    /// Documents need to match all of the clauses.
+    And,
+}
+
+/// Minimum number of clauses of a `MatchQuery` documents need to match,
+/// as a function of its number of clauses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MinimumShouldMatch {
+    /// A fixed number of clauses.
+    Count(usize),
+    /// All of the clauses but this number.
+    AllBut(usize),
+    /// A ratio of the clauses, between 0 and 1, rounded down.
+    Ratio(f32),
+}
+
+impl MinimumShouldMatch {
+    /// Returns the number of clauses to match, out of `num_clauses`.
+    ///
+    /// As long as there is a clause, at least one of them needs to match.
+    pub fn num_clauses_to_match(&self, num_clauses: usize) -> usize {
+        let num_clauses_to_match = match *self {
+            MinimumShouldMatch::Count(count) => count,
+            MinimumShouldMatch::AllBut(count) => num_clauses.saturating_sub(count),
+            MinimumShouldMatch::Ratio(ratio) => (ratio * num_clauses as f32) as usize,
+        };
+        if num_clauses == 0 {
+            0
+        } else if num_clauses_to_match < 1 {
+            1
+        } else if num_clauses_to_match > num_clauses {
+            num_clauses
+        } else {
+            num_clauses_to_match
+        }
+    }
+}
+
+/// `MatchQuery` matches the documents containing the terms of a
+/// user text, analyzed with the tokenizer of the field.
+///
+/// Each token of the text becomes a clause of the query.
+/// Tokens sharing the same position (e.g. synonyms) are regrouped in a single
+/// clause, matching any of them. The clauses are combined according
+/// to the `MatchOperator` (`Or` by default) and the `MinimumShouldMatch`.
+///
+/// `MatchQuery::new_phrase(...)` matches the sequence of tokens instead.
+///
+/// Unlike the `QueryParser`, the text is not interpreted: it may contain
+/// any character.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::collector::CountCollector;
+/// use tantivy::query::{MatchOperator, MatchQuery, MinimumShouldMatch};
+/// use tantivy::schema::{SchemaBuilder, TEXT};
+/// use tantivy::{Index, Result};
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let title = schema_builder.add_text_field("title", TEXT);
+///     let index = Index::create_in_ram(schema_builder.build());
+///     let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+///     index_writer.add_document(doc!(title => "The Name of the Wind"));
+///     index_writer.add_document(doc!(title => "The Diary of Muadib"));
+///     index_writer.add_document(doc!(title => "A Dairy Cow"));
+///     index_writer.commit()?;
+///     index.load_searchers()?;
+///     let searcher = index.searcher();
+///
+///     let count = |query: &MatchQuery| -> Result<usize> {
+///         let mut count_collector = CountCollector::default();
+///         searcher.search(query, &mut count_collector)?;
+///         Ok(count_collector.count())
+///     };
+///
+///     let mut query = MatchQuery::new(title, "The diary, of a cow?");
+///     assert_eq!(count(&query)?, 3);
+///     query.set_minimum_should_match(MinimumShouldMatch::Count(3));
+///     assert_eq!(count(&query)?, 1);
+///     query.set_operator(MatchOperator::And);
+///     assert_eq!(count(&query)?, 0);
+///     assert_eq!(count(&MatchQuery::new_phrase(title, "name of THE wind"))?, 1);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MatchQuery {
+    field: Field,
+    text: String,
+    phrase: bool,
+    operator: MatchOperator,
+    minimum_should_match: Option<MinimumShouldMatch>,
+}
+
+impl MatchQuery {
+    /// Creates a `MatchQuery` matching the documents containing
+    /// the tokens of `text` in `field`.
+    pub fn new(field: Field, text: &str) -> MatchQuery {
+        MatchQuery {
+            field,
+            text: text.to_string(),
+            phrase: false,
+            operator: MatchOperator::Or,
+            minimum_should_match: None,
+        }
+    }
+
+    /// Creates a `MatchQuery` matching the documents containing
+    /// the sequence of the tokens of `text` in `field`.
+    ///
+    /// The field needs to be indexed with positions.
+    pub fn new_phrase(field: Field, text: &str) -> MatchQuery {
+        MatchQuery {
+            phrase: true,
+            ..MatchQuery::new(field, text)
+        }
+    }
+
+    /// Sets the operator combining the clauses.
+    pub fn set_operator(&mut self, operator: MatchOperator) {
+        self.operator = operator;
+    }
+
+    /// Sets the minimum number of clauses documents need to match,
+    /// when the operator is `Or`.
+    pub fn set_minimum_should_match(&mut self, minimum_should_match: MinimumShouldMatch) {
+        self.minimum_should_match = Some(minimum_should_match);
+    }
+
+    /// Analyzes the text, and returns the terms of each of its positions.
+    fn terms_per_position(&self, searcher: &Searcher) -> Result<Vec<Vec<Term>>> {
+        let field_entry = searcher.schema().get_field_entry(self.field);
+        let field_type = field_entry.field_type();
+        if !field_type.is_indexed() {
+            let field_name = field_entry.name().to_string();
+            return Err(QueryParserError::FieldNotIndexed(field_name).into());
+        }
+        let text = self.text.trim();
+        match *field_type {
+            FieldType::I64(_) => {
+                let val = i64::from_str(text).map_err(QueryParserError::from)?;
+                Ok(vec![vec![Term::from_field_i64(self.field, val)]])
+            }
+            FieldType::U64(_) => {
+                let val = u64::from_str(text).map_err(QueryParserError::from)?;
+                Ok(vec![vec![Term::from_field_u64(self.field, val)]])
+            }
+            FieldType::HierarchicalFacet => Ok(vec![vec![Term::from_field_text(self.field, text)]]),
+            FieldType::Str(ref text_options) => {
+                let tokenizer_name = text_options
+                    .get_indexing_options()
+                    .map(|indexing_options| indexing_options.tokenizer())
+                    .unwrap_or_default();
+                let tokenizer = searcher.tokenizers().get(tokenizer_name).ok_or_else(|| {
+                    QueryParserError::UnknownTokenizer(
+                        field_entry.name().to_string(),
+                        tokenizer_name.to_string(),
+                    )
+                })?;
+                let mut terms_per_position: Vec<(usize, Vec<Term>)> = vec![];
+                let mut token_stream = tokenizer.token_stream(&self.text);
+                let field = self.field;
+                token_stream.process(&mut |token| {
+                    let term = Term::from_field_text(field, &token.text);
+                    if let Some(&mut (position, ref mut terms)) = terms_per_position.last_mut() {
+                        if position == token.position {
+                            terms.push(term);
+                            return;
+                        }
+                    }
+                    terms_per_position.push((token.position, vec![term]));
+                });
+                Ok(terms_per_position
+                    .into_iter()
+                    .map(|(_, terms)| terms)
+                    .collect())
+            }
+            FieldType::Bytes(_) => {
+                let bytes = base64::decode(text)
+                    .ok_or_else(|| QueryParserError::ExpectedBase64(text.to_string()))?;
+                Ok(vec![vec![Term::from_field_bytes(self.field, &bytes)]])
+            }
+            FieldType::Vector(_) => {
+                let field_name = field_entry.name().to_string();
+                Err(QueryParserError::FieldNotIndexed(field_name).into())
+            }
+        }
+    }
+
+    /// Builds the query matching the analyzed text.
+    fn build_query(&self, searcher: &Searcher) -> Result<Box<Query>> {
+        let mut terms_per_position = self.terms_per_position(searcher)?;
+        if self.phrase {
+            // only the first term of each position is part of the phrase.
+            let mut phrase_terms: Vec<Term> = terms_per_position
+                .into_iter()
+                .map(|terms| terms.into_iter().next().unwrap())
+                .collect();
+            let query: Box<Query> = match phrase_terms.len() {
+                0 => box EmptyQuery,
+                1 => box TermQuery::new(phrase_terms.pop().unwrap(), IndexRecordOption::WithFreqs),
+                _ => box PhraseQuery::from(phrase_terms),
+            };
+            return Ok(query);
+        }
+        let occur = match self.operator {
+            MatchOperator::Or => Occur::Should,
+            MatchOperator::And => Occur::Must,
+        };
+        let num_clauses = terms_per_position.len();
+        let clauses: Vec<(Occur, Box<Query>)> = terms_per_position
+            .drain(..)
+            .map(|mut terms| {
+                let clause: Box<Query> = if terms.len() == 1 {
+                    box TermQuery::new(terms.pop().unwrap(), IndexRecordOption::WithFreqs)
+                } else {
+                    box BooleanQuery::new_multiterms_query(terms)
+                };
+                (occur, clause)
+            })
+            .collect();
+        let mut boolean_query = BooleanQuery::from(clauses);
+        if let (MatchOperator::Or, Some(minimum_should_match)) =
+            (self.operator, self.minimum_should_match)
+        {
+            boolean_query
+                .set_minimum_should_match(minimum_should_match.num_clauses_to_match(num_clauses));
+        }
+        Ok(box boolean_query)
+    }
+}
+
+impl Query for MatchQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+        self.build_query(searcher)?.weight(searcher, scoring_enabled)
+    }
+
+    fn shared_weight(
+        &self,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+        weight_cache: &mut WeightCache,
+    ) -> Result<Box<Weight>> {
+        self.build_query(searcher)?
+            .shared_weight(searcher, scoring_enabled, weight_cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{MatchOperator, MatchQuery, MinimumShouldMatch};
+    use collector::tests::TestCollector;
+    use schema::{SchemaBuilder, INT_INDEXED, STRING, TEXT};
+    use Index;
+
+    #[test]
+    fn test_minimum_should_match_num_clauses() {
+        assert_eq!(MinimumShouldMatch::Count(2).num_clauses_to_match(3), 2);
+        assert_eq!(MinimumShouldMatch::Count(5).num_clauses_to_match(3), 3);
+        assert_eq!(MinimumShouldMatch::Count(0).num_clauses_to_match(3), 1);
+        assert_eq!(MinimumShouldMatch::AllBut(1).num_clauses_to_match(4), 3);
+        assert_eq!(MinimumShouldMatch::AllBut(6).num_clauses_to_match(4), 1);
+        assert_eq!(MinimumShouldMatch::Ratio(0.75).num_clauses_to_match(3), 2);
+        assert_eq!(MinimumShouldMatch::Ratio(0.75).num_clauses_to_match(0), 0);
+    }
+
+    #[test]
+    fn test_match_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let tag_field = schema_builder.add_text_field("tag", STRING);
+        let int_field = schema_builder.add_u64_field("num", INT_INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(
+                text_field => "a b c",
+                tag_field => "Hello World",
+                int_field => 3u64
+            ));
+            index_writer.add_document(doc!(text_field => "c b", int_field => 4u64));
+            index_writer.add_document(doc!(text_field => "d"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let matching_docs = |query: &MatchQuery| {
+            let mut test_collector = TestCollector::default();
+            searcher.search(query, &mut test_collector).unwrap();
+            test_collector.docs()
+        };
+        let mut query = MatchQuery::new(text_field, "A, b! (d)");
+        assert_eq!(matching_docs(&query), vec![0, 1, 2]);
+        query.set_minimum_should_match(MinimumShouldMatch::Count(2));
+        assert_eq!(matching_docs(&query), vec![0]);
+        query.set_operator(MatchOperator::And);
+        assert_eq!(matching_docs(&query), Vec::<u32>::new());
+        assert_eq!(matching_docs(&MatchQuery::new(text_field, "")), Vec::<u32>::new());
+        assert_eq!(matching_docs(&MatchQuery::new_phrase(text_field, "b c")), vec![0]);
+        assert_eq!(matching_docs(&MatchQuery::new_phrase(text_field, "c")), vec![0, 1]);
+        assert_eq!(matching_docs(&MatchQuery::new(tag_field, "Hello World")), vec![0]);
+        assert_eq!(matching_docs(&MatchQuery::new(int_field, " 4")), vec![1]);
+        let mut count_collector = TestCollector::default();
+        assert!(
+            searcher
+                .search(&MatchQuery::new(int_field, "four"), &mut count_collector)
+                .is_err()
+        );
+    }
+}
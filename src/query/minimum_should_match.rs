@@ -0,0 +1,182 @@
+use docset::{DocSet, SkipResult};
+use query::Scorer;
+use DocId;
+use Score;
+
+/// Union of scorers, restricted to the documents matching
+/// at least `minimum_should_match` of them.
+///
+/// The score of a document is the sum of the scores of the
+/// scorers it matches.
+pub struct MinimumShouldMatchScorer<TScorer> {
+    // sorted by doc id.
+    scorers: Vec<TScorer>,
+    minimum_should_match: usize,
+    // number of scorers positioned on the current doc.
+    num_current_scorers: usize,
+    doc: DocId,
+    score: Score,
+}
+
+impl<TScorer: Scorer> MinimumShouldMatchScorer<TScorer> {
+    /// Creates the union of the `scorers`, restricted to the documents
+    /// matching at least `minimum_should_match` of them.
+    ///
+    /// # Panics
+    /// Panics if `minimum_should_match` is 0.
+    pub fn new(
+        scorers: Vec<TScorer>,
+        minimum_should_match: usize,
+    ) -> MinimumShouldMatchScorer<TScorer> {
+        assert!(minimum_should_match > 0);
+        let mut scorers: Vec<TScorer> = scorers
+            .into_iter()
+            .flat_map(|mut scorer| {
+                if scorer.advance() {
+                    Some(scorer)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        scorers.sort_by_key(|scorer| scorer.doc());
+        MinimumShouldMatchScorer {
+            scorers,
+            minimum_should_match,
+            num_current_scorers: 0,
+            doc: 0,
+            score: 0.0,
+        }
+    }
+
+    /// Skips the `num_scorers` first scorers to `target`,
+    /// removing the scorers that are exhausted.
+    ///
+    /// Returns true iff all of them reached `target`.
+    fn skip_scorers(&mut self, num_scorers: usize, target: DocId) -> bool {
+        let mut all_reached = true;
+        let mut ord = 0;
+        for _ in 0..num_scorers {
+            let skip_result = if self.scorers[ord].doc() < target {
+                self.scorers[ord].skip_next(target)
+            } else {
+                SkipResult::Reached
+            };
+            match skip_result {
+                SkipResult::End => {
+                    self.scorers.remove(ord);
+                    all_reached = false;
+                }
+                SkipResult::OverStep => {
+                    ord += 1;
+                    all_reached = false;
+                }
+                SkipResult::Reached => {
+                    ord += 1;
+                }
+            }
+        }
+        if !all_reached {
+            self.scorers.sort_by_key(|scorer| scorer.doc());
+        }
+        all_reached
+    }
+}
+
+impl<TScorer: Scorer> DocSet for MinimumShouldMatchScorer<TScorer> {
+    fn advance(&mut self) -> bool {
+        if self.num_current_scorers > 0 {
+            let next_doc = self.doc + 1;
+            let num_current_scorers = self.num_current_scorers;
+            self.num_current_scorers = 0;
+            self.skip_scorers(num_current_scorers, next_doc);
+        }
+        loop {
+            if self.scorers.len() < self.minimum_should_match {
+                self.scorers.clear();
+                return false;
+            }
+            // the documents before the doc of this scorer match
+            // less than `minimum_should_match` scorers.
+            let candidate = self.scorers[self.minimum_should_match - 1].doc();
+            if self.skip_scorers(self.minimum_should_match, candidate) {
+                break;
+            }
+        }
+        self.doc = self.scorers[0].doc();
+        let doc = self.doc;
+        self.num_current_scorers = self.scorers
+            .iter()
+            .take_while(|scorer| scorer.doc() == doc)
+            .count();
+        self.score = self.scorers[..self.num_current_scorers]
+            .iter_mut()
+            .map(Scorer::score)
+            .sum();
+        true
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorers
+            .iter()
+            .map(|scorer| scorer.size_hint())
+            .max()
+            .unwrap_or(0u32)
+    }
+}
+
+impl<TScorer: Scorer> Scorer for MinimumShouldMatchScorer<TScorer> {
+    fn score(&mut self) -> Score {
+        self.score
+    }
+
+    fn match_cost(&self) -> f32 {
+        self.scorers.iter().map(Scorer::match_cost).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::MinimumShouldMatchScorer;
+    use docset::DocSet;
+    use query::{ConstScorer, Scorer, VecDocSet};
+
+    fn aux_test_minimum_should_match(vals: Vec<Vec<u32>>, minimum_should_match: usize) {
+        let mut expected: Vec<(u32, usize)> = vec![];
+        for doc in 0..100u32 {
+            let num_matches = vals.iter().filter(|docs| docs.contains(&doc)).count();
+            if num_matches >= minimum_should_match {
+                expected.push((doc, num_matches));
+            }
+        }
+        let scorers: Vec<ConstScorer<VecDocSet>> = vals.into_iter()
+            .map(VecDocSet::from)
+            .map(ConstScorer::new)
+            .collect();
+        let mut scorer = MinimumShouldMatchScorer::new(scorers, minimum_should_match);
+        for (doc, num_matches) in expected {
+            assert!(scorer.advance());
+            assert_eq!(scorer.doc(), doc);
+            assert_eq!(scorer.score(), num_matches as f32);
+        }
+        assert!(!scorer.advance());
+    }
+
+    #[test]
+    fn test_minimum_should_match() {
+        let vals = vec![
+            vec![1, 2, 3, 7, 50, 51],
+            vec![2, 3, 4, 50],
+            vec![3, 7, 8, 9, 50, 99],
+            vec![0, 50, 51, 99],
+        ];
+        for minimum_should_match in 1..6 {
+            aux_test_minimum_should_match(vals.clone(), minimum_should_match);
+        }
+    }
+}
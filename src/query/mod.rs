@@ -4,19 +4,38 @@ Query
 
 mod query;
 mod boolean_query;
+mod boost_query;
+mod const_score_query;
+mod function_score_query;
 mod scorer;
 mod occur;
 mod weight;
 mod term_query;
 mod query_parser;
 mod phrase_query;
+mod near_query;
 mod all_query;
 mod bitset;
 mod range_query;
 mod exclude;
 mod union;
+mod block_max_wand;
+mod minimum_should_match;
 mod intersection;
 mod reqopt_scorer;
+mod shared_weight;
+mod explanation;
+mod vector_similarity_query;
+mod match_query;
+mod more_like_this_query;
+mod filter_cache;
+mod caching_wrapper_query;
+mod disjunction_max_query;
+mod doc_range_query;
+mod exact_match_query;
+mod empty_query;
+mod recency_boost_query;
+mod regex_query;
 
 #[cfg(test)]
 mod vec_docset;
@@ -25,6 +44,8 @@ pub(crate) mod score_combiner;
 
 pub use self::intersection::Intersection;
 pub use self::union::Union;
+pub use self::block_max_wand::BlockMaxWand;
+pub use self::minimum_should_match::MinimumShouldMatchScorer;
 
 #[cfg(test)]
 pub use self::vec_docset::VecDocSet;
@@ -32,9 +53,13 @@ pub use self::vec_docset::VecDocSet;
 pub use self::reqopt_scorer::RequiredOptionalScorer;
 pub use self::exclude::Exclude;
 pub use self::bitset::BitSetDocSet;
-pub use self::boolean_query::BooleanQuery;
+pub use self::boolean_query::{BooleanQuery, BooleanQueryBuilder};
+pub use self::boost_query::BoostQuery;
+pub use self::const_score_query::ConstScoreQuery;
+pub use self::function_score_query::FunctionScoreQuery;
 pub use self::occur::Occur;
 pub use self::phrase_query::PhraseQuery;
+pub use self::near_query::NearQuery;
 pub use self::query_parser::QueryParserError;
 pub use self::query_parser::QueryParser;
 pub use self::query::Query;
@@ -42,6 +67,20 @@ pub use self::scorer::EmptyScorer;
 pub use self::scorer::Scorer;
 pub use self::term_query::TermQuery;
 pub use self::weight::Weight;
+pub use self::explanation::Explanation;
+pub use self::shared_weight::WeightCache;
 pub use self::all_query::{AllQuery, AllScorer, AllWeight};
 pub use self::range_query::RangeQuery;
+pub use self::vector_similarity_query::{Similarity, VectorSimilarityQuery};
+pub use self::match_query::{MatchOperator, MatchQuery, MinimumShouldMatch};
+pub use self::more_like_this_query::MoreLikeThisQuery;
+pub use self::filter_cache::FilterCache;
+pub use self::caching_wrapper_query::CachingWrapperQuery;
+pub use self::disjunction_max_query::{DisjunctionMaxQuery, DisjunctionMaxScorer};
+pub use self::doc_range_query::{DocRangeDocSet, DocRangeIntersection, DocRangeQuery};
+pub use self::exact_match_query::ExactMatchQuery;
+pub use self::empty_query::{EmptyQuery, EmptyWeight};
+pub use self::recency_boost_query::RecencyBoostQuery;
+pub use self::regex_query::{ExpansionStrategy, RegexQuery, RegexWeight, DEFAULT_MAX_EXPANSIONS};
+pub(crate) use self::boost_query::BoostWeight;
 pub use self::scorer::ConstScorer;
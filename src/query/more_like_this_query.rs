@@ -0,0 +1,306 @@
+use DocAddress;
+use ErrorKind;
+use Result;
+use Score;
+use core::Searcher;
+use query::{BooleanQuery, Occur, Query, QueryParserError, TermQuery, Weight};
+use query::WeightCache;
+use schema::{Field, FieldType, IndexRecordOption, Term, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+const DEFAULT_MIN_TERM_FREQ: u32 = 2;
+const DEFAULT_MIN_DOC_FREQ: u32 = 5;
+const DEFAULT_MAX_QUERY_TERMS: usize = 25;
+
+#[derive(Clone, Debug)]
+enum MoreLikeThisSource {
+    Document(DocAddress),
+    Text(String),
+}
+
+/// `MoreLikeThisQuery` matches the documents similar to a given
+/// document, or to a given text.
+///
+/// The text of the given fields is analyzed with the tokenizer of each field.
+/// For a document, the text is read from its stored fields: the fields
+/// need to be stored.
+///
+/// Each of the resulting terms is scored by TF-IDF, its term frequency
+/// in the analyzed text times its inverse document frequency in the index.
+/// The terms that are too rare in the text (`min_term_freq`)
+/// or in the index (`min_doc_freq`) are ignored, and the `max_query_terms`
+/// best terms are combined in a disjunction.
+///
+/// The document given as a source is not excluded from the results.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::collector::CountCollector;
+/// use tantivy::query::MoreLikeThisQuery;
+/// use tantivy::schema::{SchemaBuilder, STORED, TEXT};
+/// use tantivy::{DocAddress, Index, Result};
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let title = schema_builder.add_text_field("title", TEXT | STORED);
+///     let index = Index::create_in_ram(schema_builder.build());
+///     let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+///     index_writer.add_document(doc!(title => "The Diary of Muadib"));
+///     index_writer.add_document(doc!(title => "The Diary of a Young Girl"));
+///     index_writer.add_document(doc!(title => "A Dairy Cow"));
+///     index_writer.commit()?;
+///     index.load_searchers()?;
+///     let searcher = index.searcher();
+///
+///     let mut query = MoreLikeThisQuery::from_document(DocAddress(0, 0), vec![title]);
+///     query.set_min_term_freq(1);
+///     query.set_min_doc_freq(1);
+///     let mut count_collector = CountCollector::default();
+///     searcher.search(&query, &mut count_collector)?;
+///     assert_eq!(count_collector.count(), 2);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MoreLikeThisQuery {
+    source: MoreLikeThisSource,
+    fields: Vec<Field>,
+    min_term_freq: u32,
+    min_doc_freq: u32,
+    max_query_terms: usize,
+}
+
+impl MoreLikeThisQuery {
+    fn new(source: MoreLikeThisSource, fields: Vec<Field>) -> MoreLikeThisQuery {
+        MoreLikeThisQuery {
+            source,
+            fields,
+            min_term_freq: DEFAULT_MIN_TERM_FREQ,
+            min_doc_freq: DEFAULT_MIN_DOC_FREQ,
+            max_query_terms: DEFAULT_MAX_QUERY_TERMS,
+        }
+    }
+
+    /// Creates a `MoreLikeThisQuery` matching the documents similar
+    /// to the stored values of `fields` in the given document.
+    pub fn from_document(doc_address: DocAddress, fields: Vec<Field>) -> MoreLikeThisQuery {
+        MoreLikeThisQuery::new(MoreLikeThisSource::Document(doc_address), fields)
+    }
+
+    /// Creates a `MoreLikeThisQuery` matching the documents whose `fields`
+    /// are similar to the given text.
+    pub fn from_text(text: &str, fields: Vec<Field>) -> MoreLikeThisQuery {
+        MoreLikeThisQuery::new(MoreLikeThisSource::Text(text.to_string()), fields)
+    }
+
+    /// Sets the minimum number of occurrences of a term in the
+    /// analyzed text for it to be part of the query. Defaults to 2.
+    pub fn set_min_term_freq(&mut self, min_term_freq: u32) {
+        self.min_term_freq = min_term_freq;
+    }
+
+    /// Sets the minimum number of documents of the index a term
+    /// needs to appear in to be part of the query. Defaults to 5.
+    pub fn set_min_doc_freq(&mut self, min_doc_freq: u32) {
+        self.min_doc_freq = min_doc_freq;
+    }
+
+    /// Sets the maximum number of terms of the query. Defaults to 25.
+    pub fn set_max_query_terms(&mut self, max_query_terms: usize) {
+        self.max_query_terms = max_query_terms;
+    }
+
+    /// Returns the texts to analyze for each field.
+    fn texts_per_field(&self, searcher: &Searcher) -> Result<Vec<(Field, Vec<String>)>> {
+        match self.source {
+            MoreLikeThisSource::Document(ref doc_address) => {
+                let doc = searcher.doc(doc_address)?;
+                Ok(self.fields
+                    .iter()
+                    .map(|&field| {
+                        let texts = doc.get_all(field)
+                            .into_iter()
+                            .flat_map(|value| match *value {
+                                Value::Str(ref text) => Some(text.clone()),
+                                _ => None,
+                            })
+                            .collect();
+                        (field, texts)
+                    })
+                    .collect())
+            }
+            MoreLikeThisSource::Text(ref text) => Ok(self.fields
+                .iter()
+                .map(|&field| (field, vec![text.clone()]))
+                .collect()),
+        }
+    }
+
+    /// Returns the terms of the query, with their TF-IDF score,
+    /// sorted by decreasing score.
+    pub fn interesting_terms(&self, searcher: &Searcher) -> Result<Vec<(Term, Score)>> {
+        let mut term_freqs: HashMap<Term, u32> = HashMap::new();
+        for (field, texts) in self.texts_per_field(searcher)? {
+            let field_entry = searcher.schema().get_field_entry(field);
+            let indexing_options = match *field_entry.field_type() {
+                FieldType::Str(ref text_options) => text_options.get_indexing_options(),
+                _ => {
+                    let msg = format!(
+                        "MoreLikeThisQuery requires text fields. {:?} is not a text field.",
+                        field_entry.name()
+                    );
+                    bail!(ErrorKind::InvalidArgument(msg));
+                }
+            };
+            let tokenizer_name = match indexing_options {
+                Some(indexing_options) => indexing_options.tokenizer(),
+                None => {
+                    let field_name = field_entry.name().to_string();
+                    return Err(QueryParserError::FieldNotIndexed(field_name).into());
+                }
+            };
+            let tokenizer = searcher.tokenizers().get(tokenizer_name).ok_or_else(|| {
+                QueryParserError::UnknownTokenizer(
+                    field_entry.name().to_string(),
+                    tokenizer_name.to_string(),
+                )
+            })?;
+            for text in &texts {
+                let mut token_stream = tokenizer.token_stream(text);
+                token_stream.process(&mut |token| {
+                    let term = Term::from_field_text(field, &token.text);
+                    *term_freqs.entry(term).or_insert(0) += 1;
+                });
+            }
+        }
+        let num_docs = searcher.num_docs() as Score;
+        let mut scored_terms: Vec<(Term, Score)> = Vec::new();
+        for (term, term_freq) in term_freqs {
+            if term_freq < self.min_term_freq {
+                continue;
+            }
+            let doc_freq = searcher.doc_freq(&term)?;
+            if doc_freq == 0 || doc_freq < self.min_doc_freq {
+                continue;
+            }
+            let idf = 1.0 + (num_docs / (doc_freq as Score + 1.0)).ln();
+            scored_terms.push((term, term_freq as Score * idf));
+        }
+        scored_terms.sort_by(|&(ref left_term, left_score), &(ref right_term, right_score)| {
+            right_score
+                .partial_cmp(&left_score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| left_term.cmp(right_term))
+        });
+        scored_terms.truncate(self.max_query_terms);
+        Ok(scored_terms)
+    }
+
+    /// Builds the disjunction of the interesting terms.
+    fn build_query(&self, searcher: &Searcher) -> Result<BooleanQuery> {
+        let clauses: Vec<(Occur, Box<Query>)> = self.interesting_terms(searcher)?
+            .into_iter()
+            .map(|(term, _)| {
+                let term_query: Box<Query> =
+                    box TermQuery::new(term, IndexRecordOption::WithFreqs);
+                (Occur::Should, term_query)
+            })
+            .collect();
+        Ok(BooleanQuery::from(clauses))
+    }
+}
+
+impl Query for MoreLikeThisQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+        self.build_query(searcher)?.weight(searcher, scoring_enabled)
+    }
+
+    fn shared_weight(
+        &self,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+        weight_cache: &mut WeightCache,
+    ) -> Result<Box<Weight>> {
+        self.build_query(searcher)?
+            .shared_weight(searcher, scoring_enabled, weight_cache)
+    }
+    /// The terms of the query depend on the searcher: the source document
+    /// is identified by its `DocAddress`, and the terms are selected
+    /// given their document frequency.
+    fn has_stable_representation(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::MoreLikeThisQuery;
+    use collector::tests::TestCollector;
+    use schema::{SchemaBuilder, Term, INT_INDEXED, STORED, TEXT};
+    use DocAddress;
+    use Index;
+
+    #[test]
+    fn test_more_like_this_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT | STORED);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let int_field = schema_builder.add_u64_field("num", INT_INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(title => "rust rust search engine", body => "x"));
+            index_writer.add_document(doc!(title => "rust compiler"));
+            index_writer.add_document(doc!(title => "search engine"));
+            index_writer.add_document(doc!(title => "rust search"));
+            index_writer.add_document(doc!(title => "cooking"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let matching_docs = |query: &MoreLikeThisQuery| {
+            let mut test_collector = TestCollector::default();
+            searcher.search(query, &mut test_collector).unwrap();
+            test_collector.docs()
+        };
+
+        let mut query = MoreLikeThisQuery::from_document(DocAddress(0, 0), vec![title]);
+        query.set_min_doc_freq(1);
+        // only `rust` appears twice in the document.
+        assert_eq!(
+            query
+                .interesting_terms(&searcher)
+                .unwrap()
+                .into_iter()
+                .map(|(term, _)| term)
+                .collect::<Vec<Term>>(),
+            vec![Term::from_field_text(title, "rust")]
+        );
+        assert_eq!(matching_docs(&query), vec![0, 1, 3]);
+
+        query.set_min_term_freq(1);
+        assert_eq!(matching_docs(&query), vec![0, 1, 2, 3]);
+        let interesting_terms = query.interesting_terms(&searcher).unwrap();
+        assert_eq!(interesting_terms.len(), 3);
+        assert_eq!(interesting_terms[0].0, Term::from_field_text(title, "rust"));
+        query.set_max_query_terms(1);
+        assert_eq!(matching_docs(&query), vec![0, 1, 3]);
+
+        query.set_max_query_terms(10);
+        query.set_min_doc_freq(3);
+        assert_eq!(query.interesting_terms(&searcher).unwrap().len(), 2);
+
+        let mut text_query = MoreLikeThisQuery::from_text("Cooking engines", vec![title, body]);
+        text_query.set_min_term_freq(1);
+        text_query.set_min_doc_freq(1);
+        assert_eq!(matching_docs(&text_query), vec![4]);
+
+        let int_query = MoreLikeThisQuery::from_text("3", vec![int_field]);
+        let mut test_collector = TestCollector::default();
+        assert!(searcher.search(&int_query, &mut test_collector).is_err());
+    }
+}
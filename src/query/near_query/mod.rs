@@ -0,0 +1,142 @@
+mod near_query;
+mod near_weight;
+mod near_scorer;
+
+pub use self::near_query::NearQuery;
+pub use self::near_weight::NearWeight;
+pub use self::near_scorer::NearScorer;
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use core::Index;
+    use schema::{SchemaBuilder, Term, STRING, TEXT};
+    use collector::TopCollector;
+    use collector::tests::TestCollector;
+    use query::QueryParser;
+
+    #[test]
+    pub fn test_near_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            // 0
+            index_writer.add_document(doc!(text_field=>"a b c"));
+            // 1
+            index_writer.add_document(doc!(text_field=>"a d b"));
+            // 2
+            index_writer.add_document(doc!(text_field=>"b a"));
+            // 3
+            index_writer.add_document(doc!(text_field=>"b d d a"));
+            // 4
+            index_writer.add_document(doc!(text_field=>"a d d d b"));
+            assert!(index_writer.commit().is_ok());
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let test_query = |texts: Vec<&str>, slop: u32, ordered: bool| {
+            let mut test_collector = TestCollector::default();
+            let terms: Vec<Term> = texts
+                .iter()
+                .map(|text| Term::from_field_text(text_field, text))
+                .collect();
+            let near_query = if ordered {
+                NearQuery::ordered(terms, slop)
+            } else {
+                NearQuery::unordered(terms, slop)
+            };
+            searcher
+                .search(&near_query, &mut test_collector)
+                .expect("search should succeed");
+            test_collector.docs()
+        };
+        assert_eq!(test_query(vec!["a", "b"], 0, true), vec![0]);
+        assert_eq!(test_query(vec!["a", "b"], 1, true), vec![0, 1]);
+        assert_eq!(test_query(vec!["a", "b"], 3, true), vec![0, 1, 4]);
+        assert_eq!(test_query(vec!["a", "b"], 0, false), vec![0, 2]);
+        assert_eq!(test_query(vec!["a", "b"], 2, false), vec![0, 1, 2, 3]);
+        assert_eq!(test_query(vec!["b", "a", "c"], 0, false), vec![0]);
+        assert_eq!(test_query(vec!["a", "c"], 0, false), Vec::<u32>::new());
+        assert_eq!(test_query(vec!["a", "a"], 5, false), Vec::<u32>::new());
+        assert_eq!(test_query(vec!["d", "d"], 0, false), vec![3, 4]);
+        assert_eq!(test_query(vec!["d", "a", "d"], 0, false), vec![3, 4]);
+        assert_eq!(test_query(vec!["d", "d", "d"], 0, false), vec![4]);
+
+        let query_parser = QueryParser::for_index(&index, vec![text_field]);
+        let query = query_parser.parse_query("\"a b\"~1!").unwrap();
+        assert_eq!(query.count(&*searcher).unwrap(), 2);
+        let query = query_parser.parse_query("\"b a\"~1").unwrap();
+        assert_eq!(query.count(&*searcher).unwrap(), 3);
+    }
+
+    #[test]
+    pub fn test_near_query_without_positions() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field=>"a"));
+            assert!(index_writer.commit().is_ok());
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let terms = vec![
+            Term::from_field_text(text_field, "a"),
+            Term::from_field_text(text_field, "b"),
+        ];
+        let mut test_collector = TestCollector::default();
+        assert!(searcher
+            .search(&NearQuery::unordered(terms, 1), &mut test_collector)
+            .is_err());
+    }
+
+    #[test]
+    pub fn test_near_query_proximity_boost() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            // 0
+            index_writer.add_document(doc!(text_field=>"a d d b"));
+            // 1
+            index_writer.add_document(doc!(text_field=>"a d b d d a b"));
+            // 2
+            index_writer.add_document(doc!(text_field=>"b d a"));
+            assert!(index_writer.commit().is_ok());
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let terms = vec![
+            Term::from_field_text(text_field, "a"),
+            Term::from_field_text(text_field, "b"),
+        ];
+        let score_docs = |near_query: &NearQuery| {
+            let mut top_collector = TopCollector::with_limit(10);
+            searcher
+                .search(near_query, &mut top_collector)
+                .expect("search should succeed");
+            top_collector
+                .score_docs()
+                .into_iter()
+                .map(|(score, doc_address)| (score, doc_address.doc()))
+                .collect::<Vec<_>>()
+        };
+        let mut near_query = NearQuery::unordered(terms.clone(), 2);
+        assert_eq!(score_docs(&near_query), vec![(1.0, 0), (1.0, 1), (1.0, 2)]);
+        near_query.set_proximity_boost(true);
+        assert_eq!(
+            score_docs(&near_query),
+            vec![(1.0, 1), (0.5, 2), (1.0 / 3.0, 0)]
+        );
+        let mut near_query = NearQuery::ordered(terms, 2);
+        near_query.set_proximity_boost(true);
+        assert_eq!(score_docs(&near_query), vec![(1.0, 1), (1.0 / 3.0, 0)]);
+    }
+}
@@ -0,0 +1,140 @@
+use schema::Term;
+use query::Query;
+use core::searcher::Searcher;
+use super::NearWeight;
+use query::Weight;
+use ErrorKind;
+use Result;
+
+/// `NearQuery` matches documents in which some words
+/// appear close to each other.
+///
+/// The words must appear within a window of positions,
+/// where at most `slop` positions are not occupied by one of the words.
+///
+/// An *ordered* near query also requires the words to appear
+/// in the same order as in the query, while an *unordered* near query
+/// accepts any order.
+///
+/// For instance, the ordered query for `"part job"` with a slop of 1 will match
+/// the sentence
+///
+/// **Alan just got a part time job.**
+///
+/// but not the sentence
+///
+/// **Alan just got a job, part time.**
+///
+/// which is matched by the unordered query for `"part job"` with a slop of 1.
+///
+/// An ordered near query with a slop of 0 is equivalent to a `PhraseQuery`.
+///
+/// By default, all of the matching documents have the same score.
+/// With the proximity boost enabled (See `.set_proximity_boost(...)`),
+/// a document scores `1 / (1 + slop_used)`, where `slop_used` is the number of
+/// positions not occupied by the words within its narrowest match,
+/// so that the tighter matches rank above the looser ones.
+///
+/// Using a `NearQuery` on a field requires positions
+/// to be indexed for this field. Otherwise, the search fails
+/// with an `InvalidArgument` error.
+///
+/// In any order, a term repeated in the query must appear
+/// at as many distinct positions within the window.
+///
+/// The query parser emits a `NearQuery` for phrases followed by a slop,
+/// e.g. `"part job"~1` for an unordered query and `"part job"~1!` for an ordered one.
+#[derive(Debug)]
+pub struct NearQuery {
+    terms: Vec<Term>,
+    slop: u32,
+    ordered: bool,
+    proximity_boost: bool,
+}
+
+impl NearQuery {
+    /// Creates a `NearQuery` matching the `terms` in order,
+    /// with at most `slop` other positions between them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are less than two terms, or if they
+    /// do not belong to the same field.
+    pub fn ordered(terms: Vec<Term>, slop: u32) -> NearQuery {
+        NearQuery::new(terms, slop, true)
+    }
+
+    /// Creates a `NearQuery` matching the `terms` in any order,
+    /// with at most `slop` other positions between them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are less than two terms, or if they
+    /// do not belong to the same field.
+    pub fn unordered(terms: Vec<Term>, slop: u32) -> NearQuery {
+        NearQuery::new(terms, slop, false)
+    }
+
+    fn new(terms: Vec<Term>, slop: u32, ordered: bool) -> NearQuery {
+        assert!(terms.len() > 1, "A near query requires at least two terms.");
+        let field = terms[0].field();
+        assert!(
+            terms.iter().all(|term| term.field() == field),
+            "All of the terms of a near query should belong to the same field."
+        );
+        NearQuery {
+            terms,
+            slop,
+            ordered,
+            proximity_boost: false,
+        }
+    }
+
+    /// Returns the maximum number of positions not occupied
+    /// by the terms within a match.
+    pub fn slop(&self) -> u32 {
+        self.slop
+    }
+
+    /// Returns true iff the terms must appear in order.
+    pub fn is_ordered(&self) -> bool {
+        self.ordered
+    }
+
+    /// Makes the score of a document decrease with the slop of its narrowest
+    /// match, rather than being the same for all of the matching documents.
+    ///
+    /// Scoring a document then requires finding its narrowest match,
+    /// instead of stopping at its first match.
+    pub fn set_proximity_boost(&mut self, proximity_boost: bool) {
+        self.proximity_boost = proximity_boost;
+    }
+
+    /// Returns true iff the narrower matches get a higher score.
+    pub fn proximity_boost(&self) -> bool {
+        self.proximity_boost
+    }
+}
+
+impl Query for NearQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+        let field_entry = searcher.schema().get_field_entry(self.terms[0].field());
+        let has_positions = field_entry
+            .field_type()
+            .get_index_record_option()
+            .map(|index_record_option| index_record_option.has_positions())
+            .unwrap_or(false);
+        if !has_positions {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "The field {:?} of the near query is not indexed with positions.",
+                field_entry.name()
+            )));
+        }
+        Ok(box NearWeight::new(
+            self.terms.clone(),
+            self.slop,
+            self.ordered,
+            self.proximity_boost && scoring_enabled,
+        ))
+    }
+}
@@ -0,0 +1,291 @@
+use DocId;
+use Score;
+use docset::{DocSet, SkipResult};
+use postings::{Postings, SegmentPostings};
+use query::phrase_query::PostingsWithOffset;
+use query::{Intersection, Scorer};
+
+/// Match cost of the decoding and the merging of the positions
+/// of one of the terms of the query, in a candidate document.
+const TERM_POSITIONS_MATCH_COST: f32 = 20f32;
+
+/// Returns the smallest number of positions, between the first and
+/// the last term, that are not occupied by one of the terms,
+/// over the matches in which the terms appear in order.
+///
+/// Returns `None` if there is no such match with at most `slop` of them.
+/// Unless `narrowest` is true, the first match found is returned
+/// rather than the narrowest one.
+///
+/// `positions_arr[i]` are the sorted positions of the `i`-th term.
+fn ordered_match(positions_arr: &[&[u32]], slop: u32, narrowest: bool) -> Option<u32> {
+    let num_gaps = positions_arr.len() as u32 - 1;
+    let mut min_slop_used: Option<u32> = None;
+    'starts: for &start in positions_arr[0] {
+        // Matching each term with its first position after the
+        // previous term yields the narrowest match beginning at `start`.
+        let mut end = start;
+        for positions in &positions_arr[1..] {
+            let next_ord = match positions.binary_search(&(end + 1)) {
+                Ok(ord) | Err(ord) => ord,
+            };
+            if next_ord == positions.len() {
+                // Later starts cannot match either.
+                break 'starts;
+            }
+            end = positions[next_ord];
+        }
+        let slop_used = end - start - num_gaps;
+        if slop_used <= slop && min_slop_used.map_or(true, |min| slop_used < min) {
+            if slop_used == 0 || !narrowest {
+                return Some(slop_used);
+            }
+            min_slop_used = Some(slop_used);
+        }
+    }
+    min_slop_used
+}
+
+/// Returns the smallest number of positions that are not occupied by one
+/// of the terms, over the windows containing all of the terms in any order.
+///
+/// Returns `None` if there is no such window with at most `slop` of them.
+/// Unless `narrowest` is true, the first window found is returned
+/// rather than the narrowest one.
+///
+/// `positions_arr[i]` are the sorted positions of the `i`-th term.
+/// `multiplicities[i]` is the number of occurrences of the `i`-th term in the query,
+/// which must match as many distinct positions, or 0 for the later occurrences
+/// of a repeated term.
+/// `merged_positions` and `counts` are buffers, reused from one document to the other.
+fn unordered_match(
+    positions_arr: &[&[u32]],
+    multiplicities: &[u32],
+    slop: u32,
+    narrowest: bool,
+    merged_positions: &mut Vec<(u32, usize)>,
+    counts: &mut Vec<u32>,
+) -> Option<u32> {
+    let num_distinct_terms = multiplicities
+        .iter()
+        .filter(|&&multiplicity| multiplicity > 0)
+        .count();
+    merged_positions.clear();
+    for (ord, positions) in positions_arr.iter().enumerate() {
+        if multiplicities[ord] > 0 {
+            merged_positions.extend(positions.iter().map(|&pos| (pos, ord)));
+        }
+    }
+    merged_positions.sort();
+    counts.clear();
+    counts.resize(positions_arr.len(), 0);
+    let num_gaps = positions_arr.len() as u32 - 1;
+    let mut min_slop_used: Option<u32> = None;
+    let mut num_covered_terms = 0;
+    let mut window_start = 0;
+    for &(pos, ord) in merged_positions.iter() {
+        counts[ord] += 1;
+        if counts[ord] == multiplicities[ord] {
+            num_covered_terms += 1;
+        }
+        // Shrink the window, as long as it contains all of the terms.
+        while num_covered_terms == num_distinct_terms {
+            let (start_pos, start_ord) = merged_positions[window_start];
+            // Terms sharing a position may make the window narrower than the number of terms.
+            let slop_used = (pos - start_pos).saturating_sub(num_gaps);
+            if slop_used <= slop && min_slop_used.map_or(true, |min| slop_used < min) {
+                if slop_used == 0 || !narrowest {
+                    return Some(slop_used);
+                }
+                min_slop_used = Some(slop_used);
+            }
+            if counts[start_ord] == multiplicities[start_ord] {
+                num_covered_terms -= 1;
+            }
+            counts[start_ord] -= 1;
+            window_start += 1;
+        }
+    }
+    min_slop_used
+}
+
+/// Scorer associated to the `NearQuery` query.
+///
+/// As for the `PhraseScorer`, all of the matching documents
+/// have the same score, unless the proximity boost is enabled.
+/// The score of a document is then `1 / (1 + slop_used)`, where `slop_used`
+/// is the slop of its narrowest match.
+pub struct NearScorer {
+    intersection_docset: Intersection<PostingsWithOffset>,
+    // number of occurrences of each term in the query. (See `unordered_match`)
+    multiplicities: Vec<u32>,
+    slop: u32,
+    ordered: bool,
+    proximity_boost: bool,
+    // slop of the narrowest match of the current document.
+    slop_used: u32,
+    merged_positions: Vec<(u32, usize)>,
+    counts: Vec<u32>,
+}
+
+impl NearScorer {
+    pub fn new(
+        term_postings: Vec<SegmentPostings>,
+        multiplicities: Vec<u32>,
+        slop: u32,
+        ordered: bool,
+        proximity_boost: bool,
+    ) -> NearScorer {
+        let postings_with_offsets: Vec<_> = term_postings
+            .into_iter()
+            .enumerate()
+            .map(|(offset, postings)| PostingsWithOffset::new(postings, offset as u32))
+            .collect();
+        NearScorer {
+            intersection_docset: Intersection::from(postings_with_offsets),
+            multiplicities,
+            slop,
+            ordered,
+            proximity_boost,
+            slop_used: 0,
+            merged_positions: Vec::new(),
+            counts: Vec::new(),
+        }
+    }
+
+    fn near_match(&mut self) -> bool {
+        let docsets = self.intersection_docset.docsets();
+        let mut positions_arr: Vec<&[u32]> = vec![&[]; docsets.len()];
+        for docset in docsets {
+            positions_arr[docset.offset as usize] = docset.positions();
+        }
+        let slop_used_opt = if self.ordered {
+            ordered_match(&positions_arr, self.slop, self.proximity_boost)
+        } else {
+            unordered_match(
+                &positions_arr,
+                &self.multiplicities,
+                self.slop,
+                self.proximity_boost,
+                &mut self.merged_positions,
+                &mut self.counts,
+            )
+        };
+        if let Some(slop_used) = slop_used_opt {
+            self.slop_used = slop_used;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl DocSet for NearScorer {
+    fn advance(&mut self) -> bool {
+        while self.intersection_docset.advance() {
+            if self.near_match() {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        if self.intersection_docset.skip_next(target) == SkipResult::End {
+            return SkipResult::End;
+        }
+        if self.near_match() {
+            if self.doc() == target {
+                return SkipResult::Reached;
+            } else {
+                return SkipResult::OverStep;
+            }
+        }
+        if self.advance() {
+            SkipResult::OverStep
+        } else {
+            SkipResult::End
+        }
+    }
+
+    fn doc(&self) -> DocId {
+        self.intersection_docset.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.intersection_docset.size_hint()
+    }
+
+    fn estimate_live(&self) -> u32 {
+        self.intersection_docset.estimate_live()
+    }
+}
+
+impl Scorer for NearScorer {
+    fn score(&mut self) -> Score {
+        if self.proximity_boost {
+            1.0 / (1.0 + self.slop_used as Score)
+        } else {
+            1.0
+        }
+    }
+
+    fn match_cost(&self) -> f32 {
+        let num_terms = self.intersection_docset.docsets().len();
+        num_terms as f32 * TERM_POSITIONS_MATCH_COST
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{ordered_match, unordered_match};
+
+    #[test]
+    fn test_ordered_match() {
+        assert_eq!(ordered_match(&[&[1], &[2]], 0, true), Some(0));
+        assert_eq!(ordered_match(&[&[2], &[1]], 0, true), None);
+        assert_eq!(ordered_match(&[&[1], &[3]], 0, true), None);
+        assert_eq!(ordered_match(&[&[1], &[3]], 1, true), Some(1));
+        assert_eq!(ordered_match(&[&[0, 7], &[3, 8], &[9]], 0, true), Some(0));
+        assert_eq!(ordered_match(&[&[0, 7], &[3, 9], &[10]], 0, true), None);
+        assert_eq!(ordered_match(&[&[0, 7], &[3, 9], &[10]], 1, true), Some(1));
+        assert_eq!(ordered_match(&[&[0, 7], &[3, 9], &[10]], 5, true), Some(1));
+        assert_eq!(ordered_match(&[&[0, 5], &[3, 8]], 5, true), Some(2));
+        assert_eq!(ordered_match(&[&[0, 7], &[3, 9], &[10]], 8, false), Some(7));
+    }
+
+    #[test]
+    fn test_unordered_match() {
+        let test_match = |positions_arr: &[&[u32]], slop: u32| {
+            let multiplicities = vec![1; positions_arr.len()];
+            unordered_match(positions_arr, &multiplicities, slop, true, &mut vec![], &mut vec![])
+        };
+        assert_eq!(test_match(&[&[1], &[2]], 0), Some(0));
+        assert_eq!(test_match(&[&[2], &[1]], 0), Some(0));
+        assert_eq!(test_match(&[&[3], &[1]], 0), None);
+        assert_eq!(test_match(&[&[3], &[1]], 1), Some(1));
+        assert_eq!(test_match(&[&[0, 9], &[3, 10], &[8]], 0), Some(0));
+        assert_eq!(test_match(&[&[0, 9], &[3, 11], &[7]], 1), None);
+        assert_eq!(test_match(&[&[0, 9], &[3, 11], &[7]], 2), Some(2));
+        assert_eq!(test_match(&[&[0, 9], &[4, 12]], 5), Some(2));
+        assert_eq!(test_match(&[&[1], &[1]], 0), Some(0));
+        assert_eq!(
+            unordered_match(&[&[0, 9], &[4, 12]], &[1, 1], 5, false, &mut vec![], &mut vec![]),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_unordered_match_repeated_term() {
+        let test_match = |positions_arr: &[&[u32]], multiplicities: &[u32], slop: u32| {
+            unordered_match(positions_arr, multiplicities, slop, true, &mut vec![], &mut vec![])
+        };
+        // a repeated term must match distinct positions.
+        assert_eq!(test_match(&[&[1], &[1]], &[2, 0], 5), None);
+        assert_eq!(test_match(&[&[1, 3], &[1, 3]], &[2, 0], 0), None);
+        assert_eq!(test_match(&[&[1, 3], &[1, 3]], &[2, 0], 1), Some(1));
+        assert_eq!(test_match(&[&[1, 3], &[2], &[1, 3]], &[2, 1, 0], 0), Some(0));
+        assert_eq!(test_match(&[&[1, 4], &[2], &[1, 4]], &[2, 1, 0], 0), None);
+    }
+}
@@ -0,0 +1,70 @@
+use query::Weight;
+use query::Scorer;
+use schema::Term;
+use schema::IndexRecordOption;
+use core::SegmentReader;
+use super::NearScorer;
+use query::EmptyScorer;
+use Result;
+
+pub struct NearWeight {
+    terms: Vec<Term>,
+    multiplicities: Vec<u32>,
+    slop: u32,
+    ordered: bool,
+    proximity_boost: bool,
+}
+
+impl NearWeight {
+    /// Creates a new near weight.
+    ///
+    /// If `proximity_boost` is true, the narrower matches get a higher score.
+    /// (See `NearQuery::set_proximity_boost`)
+    pub fn new(terms: Vec<Term>, slop: u32, ordered: bool, proximity_boost: bool) -> NearWeight {
+        // In any order, the occurrences of a repeated term
+        // are all accounted for by its first occurrence.
+        let multiplicities = terms
+            .iter()
+            .enumerate()
+            .map(|(ord, term)| {
+                if ordered {
+                    1
+                } else if terms[..ord].contains(term) {
+                    0
+                } else {
+                    terms[ord..].iter().filter(|other| *other == term).count() as u32
+                }
+            })
+            .collect();
+        NearWeight {
+            terms,
+            multiplicities,
+            slop,
+            ordered,
+            proximity_boost,
+        }
+    }
+}
+
+impl Weight for NearWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let mut term_postings_list = Vec::new();
+        for term in &self.terms {
+            if let Some(postings) = reader
+                .inverted_index(term.field())?
+                .read_postings(term, IndexRecordOption::WithFreqsAndPositions)?
+            {
+                term_postings_list.push(postings);
+            } else {
+                return Ok(box EmptyScorer);
+            }
+        }
+        Ok(box NearScorer::new(
+            term_postings_list,
+            self.multiplicities.clone(),
+            self.slop,
+            self.ordered,
+            self.proximity_boost,
+        ))
+    }
+}
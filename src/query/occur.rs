@@ -1,5 +1,6 @@
 /// Defines whether a term in a query must be present,
-/// should be present or must not be present.
+/// should be present or must not be present, and whether
+/// it contributes to the score.
 #[derive(Debug, Clone, Hash, Copy, Eq, PartialEq)]
 pub enum Occur {
     /// For a given document to be considered for scoring,
@@ -11,4 +12,10 @@ pub enum Occur {
     /// Document that contain the term are excluded from the
     /// search.
     MustNot,
+    /// Like `Must`, document without the term are excluded from the search,
+    /// but the term does not contribute to the score.
+    ///
+    /// The clause is evaluated with scoring disabled, so that for
+    /// instance its term frequencies are not decoded.
+    Filter,
 }
@@ -5,6 +5,7 @@ mod phrase_scorer;
 pub use self::phrase_query::PhraseQuery;
 pub use self::phrase_weight::PhraseWeight;
 pub use self::phrase_scorer::PhraseScorer;
+pub(crate) use self::phrase_scorer::PostingsWithOffset;
 
 #[cfg(test)]
 mod tests {
@@ -1,10 +1,17 @@
 use DocId;
+use Score;
 use docset::{DocSet, SkipResult};
 use postings::{Postings, SegmentPostings};
 use query::{Intersection, Scorer};
 
-struct PostingsWithOffset {
-    offset: u32,
+/// Match cost of the decoding of the positions of
+/// one of the terms of the phrase, in a candidate document.
+const TERM_POSITIONS_MATCH_COST: f32 = 10f32;
+
+/// Postings of one of the terms of a query, with the
+/// offset of the term within the query.
+pub(crate) struct PostingsWithOffset {
+    pub(crate) offset: u32,
     segment_postings: SegmentPostings,
 }
 
@@ -25,6 +32,10 @@ impl Postings for PostingsWithOffset {
     fn positions(&self) -> &[u32] {
         self.segment_postings.positions()
     }
+
+    fn offsets(&self) -> &[(u32, u32)] {
+        self.segment_postings.offsets()
+    }
 }
 
 impl DocSet for PostingsWithOffset {
@@ -43,6 +54,10 @@ impl DocSet for PostingsWithOffset {
     fn size_hint(&self) -> u32 {
         self.segment_postings.size_hint()
     }
+
+    fn estimate_live(&self) -> u32 {
+        self.segment_postings.estimate_live()
+    }
 }
 
 pub struct PhraseScorer {
@@ -144,10 +159,19 @@ impl DocSet for PhraseScorer {
     fn size_hint(&self) -> u32 {
         self.intersection_docset.size_hint()
     }
+
+    fn estimate_live(&self) -> u32 {
+        self.intersection_docset.estimate_live()
+    }
 }
 
 impl Scorer for PhraseScorer {
-    fn score(&mut self) -> f32 {
-        1f32
+    fn score(&mut self) -> Score {
+        1.0
+    }
+
+    fn match_cost(&self) -> f32 {
+        let num_terms = self.intersection_docset.docsets().len();
+        num_terms as f32 * TERM_POSITIONS_MATCH_COST
     }
 }
@@ -29,8 +29,8 @@ impl Weight for PhraseWeight {
         let mut term_postings_list = Vec::new();
         for term in &self.phrase_terms {
             if let Some(postings) = reader
-                .inverted_index(term.field())
-                .read_postings(term, IndexRecordOption::WithFreqsAndPositions)
+                .inverted_index(term.field())?
+                .read_postings(term, IndexRecordOption::WithFreqsAndPositions)?
             {
                 term_postings_list.push(postings);
             } else {
@@ -2,8 +2,16 @@ use Result;
 use collector::Collector;
 use core::searcher::Searcher;
 use common::TimerTree;
+use common::Span;
+use std::time::Instant;
 use SegmentLocalId;
+use DocAddress;
+use super::Explanation;
 use super::Weight;
+use super::WeightCache;
+use super::Scorer;
+#[cfg(feature = "instrumentation")]
+use {DocId, Score, SegmentReader};
 use std::fmt;
 
 /// The `Query` trait defines a set of documents and a scoring method
@@ -39,7 +47,11 @@ use std::fmt;
 ///
 /// When implementing a new type of `Query`, it is normal to implement a
 /// dedicated `Query`, `Weight` and `Scorer`.
-pub trait Query: fmt::Debug {
+///
+/// Queries are required to be `Send + Sync`, so that a single
+/// query can be run concurrently over different parts of an index.
+/// (See [`Searcher::search_parallel`](../struct.Searcher.html#method.search_parallel))
+pub trait Query: fmt::Debug + Send + Sync {
     /// Create the weight associated to a query.
     ///
     /// If scoring is not required, setting `scoring_enabled` to `false`
@@ -48,6 +60,77 @@ pub trait Query: fmt::Debug {
     /// See [`Weight`](./trait.Weight.html).
     fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>>;
 
+    /// Create the weight associated to a query, within a larger query tree.
+    ///
+    /// The `weight_cache` makes it possible for identical subqueries to share
+    /// their weight, and ultimately, their scorer. Queries with subqueries
+    /// should create the weight of their subqueries via
+    /// `weight_cache.weight(...)`.
+    ///
+    /// Queries without any subqueries do not need to override this method.
+    fn shared_weight(
+        &self,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+        _weight_cache: &mut WeightCache,
+    ) -> Result<Box<Weight>> {
+        self.weight(searcher, scoring_enabled)
+    }
+
+    /// Returns a normalized representation of the query.
+    ///
+    /// Two queries with the same normalized representation match the same
+    /// documents, with the same scores. The normalization rewrites the
+    /// query in a canonical form: e.g. the clauses of a `BooleanQuery`
+    /// are sorted, and the bounds of a `RangeQuery` are expressed as an
+    /// inclusive lower bound and an exclusive upper bound.
+    ///
+    /// Equivalent queries are not guaranteed to have the same
+    /// normalized representation.
+    ///
+    /// By default, this is the `Debug` representation of the query.
+    /// Queries with subqueries should override this method to rely
+    /// on the normalized representation of their subqueries.
+    fn normalized(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    /// Returns true iff the normalized representation of the query only
+    /// depends on the query itself, and not on the process or the searcher.
+    ///
+    /// This is not the case of the queries holding a user defined function,
+    /// represented by its address, or referring to a document by its
+    /// `DocAddress`. Queries with subqueries should override this method,
+    /// so that it returns false if one of their subqueries does.
+    fn has_stable_representation(&self) -> bool {
+        true
+    }
+
+    /// Returns a 64-bit hash of the normalized representation of the query,
+    /// or `None` if the representation is not stable.
+    /// (See `.has_stable_representation()`)
+    ///
+    /// The hash does not depend on the process or the platform,
+    /// so that it can be used as a key by external caches.
+    /// (See `.normalized()`)
+    fn fingerprint(&self) -> Option<u64> {
+        if self.has_stable_representation() {
+            Some(fnv1a(self.normalized().as_bytes()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an `Explanation` for the score of the document
+    /// at the given `DocAddress`.
+    ///
+    /// Returns an error if the document does not match the query.
+    fn explain(&self, searcher: &Searcher, doc_address: &DocAddress) -> Result<Explanation> {
+        let weight = self.weight(searcher, true)?;
+        let segment_reader = searcher.segment_reader(doc_address.segment_ord());
+        weight.explain(segment_reader, doc_address.doc())
+    }
+
     /// Returns the number of documents matching the query.
     fn count(&self, searcher: &Searcher) -> Result<usize> {
         let weight = self.weight(searcher, false)?;
@@ -68,13 +151,21 @@ pub trait Query: fmt::Debug {
     /// - iterate throw the matched documents and push them to the collector.
     ///
     fn search(&self, searcher: &Searcher, collector: &mut Collector) -> Result<TimerTree> {
+        let start = Instant::now();
         let mut timer_tree = TimerTree::default();
         let scoring_enabled = collector.requires_scoring();
-        let weight = self.weight(searcher, scoring_enabled)?;
+        let weight = {
+            let _span = Span::enter("weight", || format!("query={:?}", self));
+            self.weight(searcher, scoring_enabled)?
+        };
+        collector.set_memory_budget(searcher.memory_budget())?;
         {
             let mut search_timer = timer_tree.open("search");
             for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
                 let mut segment_search_timer = search_timer.open("segment_search");
+                let mut span = Span::enter("collect_segment", || {
+                    format!("segment={}", segment_reader.segment_id().short_uuid_string())
+                });
                 {
                     let _ = segment_search_timer.open("set_segment");
                     collector.set_segment(segment_ord as SegmentLocalId, segment_reader)?;
@@ -82,10 +173,82 @@ pub trait Query: fmt::Debug {
                 let mut scorer = weight.scorer(segment_reader)?;
                 {
                     let _collection_timer = segment_search_timer.open("collection");
-                    scorer.collect(collector);
+                    collect_scorer(&mut scorer, collector, &mut span);
                 }
             }
         }
+        searcher.metrics().record_search(start.elapsed());
         Ok(timer_tree)
     }
 }
+
+/// 64-bit FNV-1a hash.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// Pushes the documents of `scorer` to the collector,
+/// and records their number in `span`.
+#[cfg(feature = "instrumentation")]
+fn collect_scorer(scorer: &mut Scorer, collector: &mut Collector, span: &mut Span) {
+    let mut counting_collector = CountingCollector {
+        collector,
+        count: 0,
+    };
+    scorer.collect(&mut counting_collector);
+    span.record("num_docs", counting_collector.count);
+}
+
+#[cfg(not(feature = "instrumentation"))]
+#[inline(always)]
+fn collect_scorer(scorer: &mut Scorer, collector: &mut Collector, _span: &mut Span) {
+    scorer.collect(collector);
+}
+
+/// Counts the documents pushed to the underlying collector.
+#[cfg(feature = "instrumentation")]
+struct CountingCollector<'a> {
+    collector: &'a mut Collector,
+    count: u64,
+}
+
+#[cfg(feature = "instrumentation")]
+impl<'a> Collector for CountingCollector<'a> {
+    fn set_segment(
+        &mut self,
+        segment_local_id: SegmentLocalId,
+        segment: &SegmentReader,
+    ) -> Result<()> {
+        self.collector.set_segment(segment_local_id, segment)
+    }
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        self.count += 1;
+        self.collector.collect(doc, score);
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.collector.requires_scoring()
+    }
+
+    fn min_competitive_score(&self) -> Option<Score> {
+        self.collector.min_competitive_score()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::fnv1a;
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}
@@ -1,16 +1,29 @@
 use std::fmt;
-use schema::Term;
+use std::collections::Bound;
+use schema::{Field, Term};
 use query::Occur;
+use Score;
 
 #[derive(Clone)]
 pub enum LogicalLiteral {
     Term(Term),
     Phrase(Vec<Term>),
+    Near {
+        terms: Vec<Term>,
+        slop: u32,
+        ordered: bool,
+    },
+    Range {
+        field: Field,
+        lower: Bound<Term>,
+        upper: Bound<Term>,
+    },
 }
 
 #[derive(Clone)]
 pub enum LogicalAST {
     Clause(Vec<(Occur, LogicalAST)>),
+    Boost(Box<LogicalAST>, Score),
     Leaf(Box<LogicalLiteral>),
 }
 
@@ -19,6 +32,7 @@ fn occur_letter(occur: Occur) -> &'static str {
         Occur::Must => "+",
         Occur::MustNot => "-",
         Occur::Should => "",
+        Occur::Filter => "#",
     }
 }
 
@@ -38,6 +52,9 @@ impl fmt::Debug for LogicalAST {
                 }
                 Ok(())
             }
+            LogicalAST::Boost(ref subquery, boost) => {
+                write!(formatter, "({:?})^{}", subquery, boost)
+            }
             LogicalAST::Leaf(ref literal) => write!(formatter, "{:?}", literal),
         }
     }
@@ -54,6 +71,34 @@ impl fmt::Debug for LogicalLiteral {
         match *self {
             LogicalLiteral::Term(ref term) => write!(formatter, "{:?}", term),
             LogicalLiteral::Phrase(ref terms) => write!(formatter, "\"{:?}\"", terms),
+            LogicalLiteral::Near {
+                ref terms,
+                slop,
+                ordered,
+            } => {
+                write!(formatter, "\"{:?}\"~{}", terms, slop)?;
+                if ordered {
+                    write!(formatter, "!")?;
+                }
+                Ok(())
+            }
+            LogicalLiteral::Range {
+                ref lower,
+                ref upper,
+                ..
+            } => {
+                match *lower {
+                    Bound::Included(ref term) => write!(formatter, "[{:?}", term)?,
+                    Bound::Excluded(ref term) => write!(formatter, "{{{:?}", term)?,
+                    Bound::Unbounded => write!(formatter, "{{*")?,
+                }
+                write!(formatter, " TO ")?;
+                match *upper {
+                    Bound::Included(ref term) => write!(formatter, "{:?}]", term),
+                    Bound::Excluded(ref term) => write!(formatter, "{:?}}}", term),
+                    Bound::Unbounded => write!(formatter, "*}}"),
+                }
+            }
         }
     }
 }
@@ -1,35 +1,95 @@
 use combine::*;
 use combine::char::*;
 use super::user_input_ast::*;
+use Score;
 
-fn literal<I>(input: I) -> ParseResult<UserInputAST, I>
+fn field<I>(input: I) -> ParseResult<String, I>
 where
     I: Stream<Item = char>,
 {
-    let term_val = || {
-        let word = many1(satisfy(|c: char| c.is_alphanumeric()));
-        let phrase = (char('"'), many1(satisfy(|c| c != '"')), char('"')).map(|(_, s, _)| s);
-        phrase.or(word)
-    };
-
-    let negative_numbers = (char('-'), many1(satisfy(|c: char| c.is_numeric())))
-        .map(|(s1, s2): (char, String)| format!("{}{}", s1, s2));
-
-    let field = (
+    (
         letter(),
         many(satisfy(|c: char| c.is_alphanumeric() || c == '_')),
-    ).map(|(s1, s2): (char, String)| format!("{}{}", s1, s2));
+    ).map(|(s1, s2): (char, String)| format!("{}{}", s1, s2))
+        .parse_stream(input)
+}
 
-    let term_val_with_field = negative_numbers.or(term_val());
+fn word<I>(input: I) -> ParseResult<String, I>
+where
+    I: Stream<Item = char>,
+{
+    many1(satisfy(|c: char| c.is_alphanumeric())).parse_stream(input)
+}
+
+fn phrase<I>(input: I) -> ParseResult<String, I>
+where
+    I: Stream<Item = char>,
+{
+    (char('"'), many1(satisfy(|c| c != '"')), char('"'))
+        .map(|(_, s, _)| s)
+        .parse_stream(input)
+}
+
+fn term_val<I>(input: I) -> ParseResult<String, I>
+where
+    I: Stream<Item = char>,
+{
+    parser(phrase).or(parser(word)).parse_stream(input)
+}
+
+/// Parses the slop of a phrase, e.g. `~2`, or `~2!` if the words are required
+/// to appear in order.
+fn slop<I>(input: I) -> ParseResult<UserInputSlop, I>
+where
+    I: Stream<Item = char>,
+{
+    (char('~'), many1(digit()), optional(char('!')))
+        .map(|(_, slop, exclamation_mark): (_, String, _)| UserInputSlop {
+            // a string of digits is a valid integer unless it overflows.
+            slop: slop.parse::<u32>().unwrap_or(u32::max_value()),
+            ordered: exclamation_mark.is_some(),
+        })
+        .parse_stream(input)
+}
+
+/// Parses the value of a literal, and the optional slop of phrases.
+fn literal_val<I>(input: I) -> ParseResult<(String, Option<UserInputSlop>), I>
+where
+    I: Stream<Item = char>,
+{
+    (parser(phrase), optional(parser(slop)))
+        .or(parser(word).map(|word| (word, None)))
+        .parse_stream(input)
+}
+
+fn negative_number<I>(input: I) -> ParseResult<String, I>
+where
+    I: Stream<Item = char>,
+{
+    (char('-'), many1(satisfy(|c: char| c.is_numeric())))
+        .map(|(s1, s2): (char, String)| format!("{}{}", s1, s2))
+        .parse_stream(input)
+}
+
+fn literal<I>(input: I) -> ParseResult<UserInputAST, I>
+where
+    I: Stream<Item = char>,
+{
+    let term_val_with_field = parser(negative_number)
+        .map(|number| (number, None))
+        .or(parser(literal_val));
 
-    let term_query =
-        (field, char(':'), term_val_with_field).map(|(field_name, _, phrase)| UserInputLiteral {
+    let term_query = (parser(field), char(':'), term_val_with_field).map(
+        |(field_name, _, (phrase, slop))| UserInputLiteral {
             field_name: Some(field_name),
             phrase,
-        });
-    let term_default_field = term_val().map(|phrase| UserInputLiteral {
+            slop,
+        },
+    );
+    let term_default_field = parser(literal_val).map(|(phrase, slop)| UserInputLiteral {
         field_name: None,
         phrase,
+        slop,
     });
     try(term_query)
         .or(term_default_field)
@@ -37,14 +97,134 @@ where
         .parse_stream(input)
 }
 
+/// Parses a range, e.g. `year:[1960 TO 1970}`.
+///
+/// `[` and `]` mark inclusive bounds, `{` and `}` exclusive bounds,
+/// and `*` stands for an open bound.
+fn range<I>(input: I) -> ParseResult<UserInputAST, I>
+where
+    I: Stream<Item = char>,
+{
+    let bound_val = || {
+        char('*')
+            .map(|_| None)
+            .or(parser(negative_number).or(parser(term_val)).map(Some))
+    };
+    let lower_bound = (
+        char('[').map(|_| true).or(char('{').map(|_| false)),
+        spaces(),
+        bound_val(),
+    ).map(|(inclusive, _, val)| UserInputBound::new(val, inclusive));
+    let upper_bound = (
+        bound_val(),
+        spaces(),
+        char(']').map(|_| true).or(char('}').map(|_| false)),
+    ).map(|(val, _, inclusive)| UserInputBound::new(val, inclusive));
+    (
+        optional(try((parser(field), char(':')).map(|(field_name, _)| field_name))),
+        lower_bound,
+        skip_many1(space()),
+        string("TO"),
+        skip_many1(space()),
+        upper_bound,
+    ).map(|(field_name, lower, _, _, _, upper)| UserInputAST::Range {
+        field_name,
+        lower,
+        upper,
+    })
+        .parse_stream(input)
+}
+
+/// Parses a boost factor, e.g. `2` or `2.5`.
+fn boost<I>(input: I) -> ParseResult<Score, I>
+where
+    I: Stream<Item = char>,
+{
+    (
+        many1(digit()),
+        optional((char('.'), many1(digit())).map(|(_, decimals): (char, String)| decimals)),
+    ).map(|(integer, decimals): (String, Option<String>)| {
+        let boost_str = match decimals {
+            Some(decimals) => format!("{}.{}", integer, decimals),
+            None => integer,
+        };
+        // a string of digits is always a valid float.
+        boost_str.parse::<Score>().unwrap()
+    })
+        .parse_stream(input)
+}
+
 fn leaf<I>(input: I) -> ParseResult<UserInputAST, I>
 where
     I: Stream<Item = char>,
 {
+    let boosted_leaf = (
+        (char('('), parser(parse_to_ast), char(')'))
+            .map(|(_, expr, _)| expr)
+            .or(try(parser(range)))
+            .or(parser(literal)),
+        optional((char('^'), parser(boost)).map(|(_, boost)| boost)),
+    ).map(|(expr, boost)| match boost {
+        Some(boost) => UserInputAST::Boost(box expr, boost),
+        None => expr,
+    });
     (char('-'), parser(leaf)).map(|(_, expr)| UserInputAST::Not(box expr))
         .or((char('+'), parser(leaf)).map(|(_, expr)| UserInputAST::Must(box expr)))
-        .or((char('('), parser(parse_to_ast), char(')')).map(|(_, expr, _)| expr))
-        .or(parser(literal))
+        .or(boosted_leaf)
+        .parse_stream(input)
+}
+
+/// Parses a sequence of leaves separated by `AND`.
+///
+/// All of the leaves are then required.
+fn conjunction<I>(input: I) -> ParseResult<UserInputAST, I>
+where
+    I: Stream<Item = char>,
+{
+    let and_leaf = try((
+        skip_many1(space()),
+        string("AND"),
+        skip_many1(space()),
+        parser(leaf),
+    )).map(|(_, _, _, expr)| expr);
+    (parser(leaf), many(and_leaf))
+        .map(|(first, others): (UserInputAST, Vec<UserInputAST>)| {
+            if others.is_empty() {
+                first
+            } else {
+                let subqueries = Some(first).into_iter().chain(others.into_iter());
+                UserInputAST::Clause(
+                    subqueries
+                        .map(|subquery| box UserInputAST::Must(box subquery))
+                        .collect(),
+                )
+            }
+        })
+        .parse_stream(input)
+}
+
+/// Parses a sequence of conjunctions separated by `OR`.
+///
+/// `AND` therefore takes precedence over `OR`.
+fn disjunction<I>(input: I) -> ParseResult<UserInputAST, I>
+where
+    I: Stream<Item = char>,
+{
+    let or_conjunction = try((
+        skip_many1(space()),
+        string("OR"),
+        skip_many1(space()),
+        parser(conjunction),
+    )).map(|(_, _, _, expr)| expr);
+    (parser(conjunction), many(or_conjunction))
+        .map(|(first, others): (UserInputAST, Vec<UserInputAST>)| {
+            if others.is_empty() {
+                first
+            } else {
+                let subqueries = Some(first).into_iter().chain(others.into_iter());
+                UserInputAST::Disjunction(subqueries.map(Box::new).collect())
+            }
+        })
         .parse_stream(input)
 }
 
@@ -52,7 +232,7 @@ pub fn parse_to_ast<I>(input: I) -> ParseResult<UserInputAST, I>
 where
     I: Stream<Item = char>,
 {
-    sep_by(parser(leaf), spaces())
+    sep_by(parser(disjunction), spaces())
         .map(|subqueries: Vec<UserInputAST>| {
             if subqueries.len() == 1 {
                 subqueries.into_iter().next().unwrap()
@@ -92,4 +272,50 @@ mod test {
         test_parse_query_to_ast_helper("abc:\"a b\"", "abc:\"a b\"");
         test_is_parse_err("abc +    ");
     }
+
+    #[test]
+    fn test_parse_query_to_ast_boolean_operators() {
+        test_parse_query_to_ast_helper("a AND b", "(+(\"a\") +(\"b\"))");
+        test_parse_query_to_ast_helper("a OR b", "(\"a\" OR \"b\")");
+        test_parse_query_to_ast_helper(
+            "a AND b OR c",
+            "((+(\"a\") +(\"b\")) OR \"c\")",
+        );
+        test_parse_query_to_ast_helper(
+            "a AND (b OR c) d",
+            "((+(\"a\") +((\"b\" OR \"c\"))) \"d\")",
+        );
+        test_parse_query_to_ast_helper("a AND -b", "(+(\"a\") +(-(\"b\")))");
+        test_parse_query_to_ast_helper("a ANDROID", "(\"a\" \"ANDROID\")");
+        test_parse_query_to_ast_helper("a ORANGE", "(\"a\" \"ORANGE\")");
+    }
+
+    #[test]
+    fn test_parse_query_to_ast_range() {
+        test_parse_query_to_ast_helper("year:[1960 TO 1970}", "year:[\"1960\" TO \"1970\"}");
+        test_parse_query_to_ast_helper("year:{-5 TO *]", "year:{\"-5\" TO *}");
+        test_parse_query_to_ast_helper("[a TO \"z z\"]", "[\"a\" TO \"z z\"]");
+        test_parse_query_to_ast_helper(
+            "+title:toto year:[ 1960 TO 1970 ]",
+            "(+(title:\"toto\") year:[\"1960\" TO \"1970\"])",
+        );
+    }
+
+    #[test]
+    fn test_parse_query_to_ast_boost() {
+        test_parse_query_to_ast_helper("a^2", "(\"a\")^2");
+        test_parse_query_to_ast_helper("title:a^2.5 b", "((title:\"a\")^2.5 \"b\")");
+        test_parse_query_to_ast_helper("(a b)^0.5", "((\"a\" \"b\"))^0.5");
+        test_parse_query_to_ast_helper("-\"a b\"^3", "-((\"a b\")^3)");
+        test_parse_query_to_ast_helper("year:[1 TO 2]^2", "(year:[\"1\" TO \"2\"])^2");
+        test_is_parse_err("a^");
+    }
+
+    #[test]
+    fn test_parse_query_to_ast_slop() {
+        test_parse_query_to_ast_helper("\"a b\"~2", "\"a b\"~2");
+        test_parse_query_to_ast_helper("title:\"a b\"~0! c", "(title:\"a b\"~0! \"c\")");
+        test_parse_query_to_ast_helper("\"a b\"~3^2", "(\"a b\"~3)^2");
+        test_is_parse_err("\"a b\"~");
+    }
 }
@@ -8,11 +8,19 @@ use query::Occur;
 use query::TermQuery;
 use schema::IndexRecordOption;
 use query::PhraseQuery;
+use query::NearQuery;
+use query::RangeQuery;
+use query::BoostQuery;
 use schema::{FieldType, Term};
 use std::str::FromStr;
 use tokenizer::TokenizerManager;
 use std::num::ParseIntError;
 use core::Index;
+use common::Span;
+use common::base64;
+use std::collections::Bound;
+use std::collections::{BTreeMap, HashMap};
+use Score;
 
 /// Possible error that may happen when parsing a query.
 #[derive(Debug, PartialEq, Eq)]
@@ -25,6 +33,10 @@ pub enum QueryParserError {
     /// The query contains a term for a `u64`-field, but the value
     /// is not a u64.
     ExpectedInt(ParseIntError),
+    /// `ExpectedBase64(value: String)`
+    /// The query contains a term for a bytes field, but the value
+    /// is not valid base64.
+    ExpectedBase64(String),
     /// It is forbidden queries that are only "excluding". (e.g. -title:pop)
     AllButQueryForbidden,
     /// If no default field is declared, running a query without any
@@ -36,6 +48,9 @@ pub enum QueryParserError {
     /// The tokenizer for the given field is unknown
     /// The two argument strings are the name of the field, the name of the tokenizer
     UnknownTokenizer(String, String),
+    /// One of the bounds of a range query is not a single term,
+    /// (e.g. `title:[a TO "b c"]`).
+    RangeMustNotHavePhrase,
 }
 
 impl From<ParseIntError> for QueryParserError {
@@ -66,17 +81,55 @@ impl From<ParseIntError> for QueryParserError {
 ///   Making it possible to make this behavior customizable is tracked in
 ///   [issue #27](https://github.com/fulmicoton/tantivy/issues/27).
 ///
+///   Default fields can be given a boost via `.set_field_boost(...)`.
+///   e.g. if the boost of `title` is `2`, `barack` is searched as
+///   `(title:barack^2 OR body:barack)`.
+///
 /// * negative terms: By prepending a term by a `-`, a term can be excluded
 ///   from the search. This is useful for disambiguating a query.
 ///   e.g. `apple -fruit`
 ///
 /// * must terms: By prepending a term by a `+`, a term can be made required for the search.
 ///
+/// * boolean operators: `diary AND girl` requires both terms,
+///   while `diary OR girl` requires at least one of them, even if
+///   `.set_conjunction_by_default()` was called. `AND` takes precedence over `OR`,
+///   and subqueries can be grouped using parentheses, e.g. `(diary OR journal) AND girl`.
+///
+/// * range queries: `year:[1960 TO 1970}` matches the documents whose `year` is within
+///   the range. `[` and `]` denote inclusive bounds, `{` and `}` exclusive bounds,
+///   and `*` an unbounded side (e.g. `year:[1960 TO *]`).
+///
+/// * numeric fields: the values of `u64` and `i64` fields are parsed as integers,
+///   e.g. `year:1960` or `delta:[-5 TO 5]`. Non-numeric values yield
+///   a `QueryParserError::ExpectedInt` error, except for terms searched in the default
+///   fields: numeric default fields are then simply ignored.
+///
+/// * bytes fields: the values of bytes fields are decoded from base64,
+///   e.g. `hash:Zm9vYg==`.
+///
+/// * boosts: `diary^2.5` multiplies the score of the matching documents by `2.5`.
+///   Boosts apply to terms, phrases, ranges and parenthesized subqueries.
+///
+/// * proximity: `"part job"~2` matches documents in which the words of the phrase
+///   appear in any order, within a window where at most 2 positions are not occupied
+///   by the words. `"part job"~2!` also requires the words to appear in order.
+///   (See `NearQuery`)
+///
+/// * phrases: `"new york"` is tokenized by the tokenizer of each field it is
+///   searched in. The tokens emitted at distinct positions are searched as a phrase,
+///   while the tokens sharing a position are alternatives. Hence, `tag:"new york"`
+///   is a single term if `tag` uses the `raw` tokenizer.
+///   The tokenizer used at query time can differ from the one used at indexing time.
+///   (See `QueryParser::set_field_analyzer`)
+///
 pub struct QueryParser {
     schema: Schema,
     default_fields: Vec<Field>,
     conjunction_by_default: bool,
     tokenizer_manager: TokenizerManager,
+    field_boosts: HashMap<Field, Score>,
+    field_analyzers: HashMap<Field, String>,
 }
 
 impl QueryParser {
@@ -94,6 +147,8 @@ impl QueryParser {
             default_fields,
             tokenizer_manager,
             conjunction_by_default: false,
+            field_boosts: HashMap::new(),
+            field_analyzers: HashMap::new(),
         }
     }
 
@@ -114,6 +169,35 @@ impl QueryParser {
         self.conjunction_by_default = true;
     }
 
+    /// Sets the boost of a default field.
+    ///
+    /// The score of the terms, phrases and ranges searched in this field,
+    /// as part of the default fields, gets multiplied by `boost`.
+    /// e.g. If `title` and `body` are the default fields and the boost of
+    /// `title` is `2`, the query `diary` is equivalent to
+    /// `title:diary^2 OR body:diary`.
+    ///
+    /// The boost does not apply to the literals explicitly targeting
+    /// the field, such as `title:diary`.
+    pub fn set_field_boost(&mut self, field: Field, boost: Score) {
+        self.field_boosts.insert(field, boost);
+    }
+
+    /// Sets the tokenizer used to analyze the text searched in a field,
+    /// in place of the tokenizer the field is indexed with.
+    ///
+    /// `analyzer_name` is the name of a tokenizer registered
+    /// in the `TokenizerManager` of the query parser.
+    ///
+    /// For instance, a field indexed with an edge ngram tokenizer, for
+    /// autocompletion, is typically searched without producing the ngrams
+    /// of the query: `ap` then only matches the documents having a word
+    /// starting with `ap`, rather than the documents having a word starting
+    /// with `a`.
+    pub fn set_field_analyzer(&mut self, field: Field, analyzer_name: &str) {
+        self.field_analyzers.insert(field, analyzer_name.to_string());
+    }
+
     /// Parse a query
     ///
     /// Note that `parse_query` returns an error if the input
@@ -125,6 +209,7 @@ impl QueryParser {
     /// Implementing a lenient mode for this query parser is tracked
     /// in [Issue 5](https://github.com/fulmicoton/tantivy/issues/5)
     pub fn parse_query(&self, query: &str) -> Result<Box<Query>, QueryParserError> {
+        let _span = Span::enter("parse_query", || format!("query={:?}", query));
         let logical_ast = self.parse_query_to_logical_ast(query)?;
         Ok(convert_to_query(logical_ast))
     }
@@ -152,11 +237,13 @@ impl QueryParser {
         }
         Ok(ast)
     }
-    fn compute_logical_ast_for_leaf(
+    /// Returns the terms of `phrase` for the given field,
+    /// along with the position of their token.
+    fn compute_positioned_terms_for_string(
         &self,
         field: Field,
-        phrase: &str
-    ) -> Result<Option<LogicalLiteral>, QueryParserError> {
+        phrase: &str,
+    ) -> Result<Vec<(usize, Term)>, QueryParserError> {
         let field_entry = self.schema.get_field_entry(field);
         let field_type = field_entry.field_type();
         if !field_type.is_indexed() {
@@ -167,38 +254,34 @@ impl QueryParser {
             FieldType::I64(_) => {
                 let val: i64 = i64::from_str(phrase)?;
                 let term = Term::from_field_i64(field, val);
-                Ok(Some(LogicalLiteral::Term(term)))
+                Ok(vec![(0, term)])
             }
             FieldType::U64(_) => {
                 let val: u64 = u64::from_str(phrase)?;
                 let term = Term::from_field_u64(field, val);
-                Ok(Some(LogicalLiteral::Term(term)))
+                Ok(vec![(0, term)])
             }
             FieldType::Str(ref str_options) => {
                 if let Some(option) = str_options.get_indexing_options() {
+                    let tokenizer_name = self.field_analyzers
+                        .get(&field)
+                        .map(|analyzer_name| analyzer_name.as_str())
+                        .unwrap_or_else(|| option.tokenizer());
                     let mut tokenizer = self.tokenizer_manager
-                        .get(option.tokenizer())
+                        .get(tokenizer_name)
                         .ok_or_else(|| {
                             QueryParserError::UnknownTokenizer(
                                 field_entry.name().to_string(),
-                                option.tokenizer().to_string(),
+                                tokenizer_name.to_string(),
                             )
                         })?;
-                    let mut terms: Vec<Term> = Vec::new();
+                    let mut terms: Vec<(usize, Term)> = Vec::new();
                     let mut token_stream = tokenizer.token_stream(phrase);
                     token_stream.process(&mut |token| {
                         let term = Term::from_field_text(field, &token.text);
-                        terms.push(term);
+                        terms.push((token.position, term));
                     });
-                    if terms.is_empty() {
-                        Ok(None)
-                    } else if terms.len() == 1 {
-                        Ok(Some(LogicalLiteral::Term(
-                            terms.into_iter().next().unwrap(),
-                        )))
-                    } else {
-                        Ok(Some(LogicalLiteral::Phrase(terms)))
-                    }
+                    Ok(terms)
                 } else {
                     // This should have been seen earlier really.
                     Err(QueryParserError::FieldNotIndexed(
@@ -208,9 +291,165 @@ impl QueryParser {
             }
             FieldType::HierarchicalFacet => {
                 let term = Term::from_field_text(field, phrase);
-                Ok(Some(LogicalLiteral::Term(term)))
+                Ok(vec![(0, term)])
+            }
+            FieldType::Vector(_) => Err(QueryParserError::FieldNotIndexed(
+                field_entry.name().to_string(),
+            )),
+            FieldType::Bytes(_) => {
+                let bytes = base64::decode(phrase)
+                    .ok_or_else(|| QueryParserError::ExpectedBase64(phrase.to_string()))?;
+                Ok(vec![(0, Term::from_field_bytes(field, &bytes))])
+            }
+        }
+    }
+
+    fn compute_terms_for_string(
+        &self,
+        field: Field,
+        phrase: &str,
+    ) -> Result<Vec<Term>, QueryParserError> {
+        let positioned_terms = self.compute_positioned_terms_for_string(field, phrase)?;
+        Ok(positioned_terms.into_iter().map(|(_, term)| term).collect())
+    }
+
+    /// Computes the logical AST of a literal for a given field,
+    /// depending on the positions of the tokens emitted by its tokenizer.
+    ///
+    /// - If all of the tokens share the same position, as for the `raw`
+    /// tokenizer, or for a single word and its synonyms, the literal matches
+    /// any of them: `tag:"new york"` is a single term for a `raw` field.
+    /// - If each position has a single token, the literal is a phrase
+    /// (or a near query if it has a slop).
+    /// - Otherwise, e.g. with n-grams, the literal requires one of the
+    /// tokens of each position, regardless of their adjacency.
+    fn compute_logical_ast_for_leaf(
+        &self,
+        field: Field,
+        phrase: &str,
+        slop: &Option<UserInputSlop>,
+    ) -> Result<Option<LogicalAST>, QueryParserError> {
+        let positioned_terms = self.compute_positioned_terms_for_string(field, phrase)?;
+        let mut terms_per_position: BTreeMap<usize, Vec<Term>> = BTreeMap::new();
+        for (position, term) in positioned_terms {
+            let terms = terms_per_position.entry(position).or_insert_with(Vec::new);
+            if !terms.contains(&term) {
+                terms.push(term);
+            }
+        }
+        let term_asts = |terms: Vec<Term>| -> Vec<LogicalAST> {
+            terms
+                .into_iter()
+                .map(|term| LogicalAST::from(LogicalLiteral::Term(term)))
+                .collect()
+        };
+        if terms_per_position.is_empty() {
+            Ok(None)
+        } else if terms_per_position.len() == 1 {
+            let (_, terms) = terms_per_position.into_iter().next().unwrap();
+            Ok(Some(compose_should(term_asts(terms))))
+        } else if terms_per_position.values().all(|terms| terms.len() == 1) {
+            let terms: Vec<Term> = terms_per_position
+                .into_iter()
+                .flat_map(|(_, terms)| terms)
+                .collect();
+            if let Some(ref slop) = *slop {
+                Ok(Some(LogicalAST::from(LogicalLiteral::Near {
+                    terms,
+                    slop: slop.slop,
+                    ordered: slop.ordered,
+                })))
+            } else {
+                Ok(Some(LogicalAST::from(LogicalLiteral::Phrase(terms))))
+            }
+        } else {
+            let position_asts: Vec<(Occur, LogicalAST)> = terms_per_position
+                .into_iter()
+                .map(|(_, terms)| (Occur::Must, compose_should(term_asts(terms))))
+                .collect();
+            Ok(Some(LogicalAST::Clause(position_asts)))
+        }
+    }
+
+    fn resolve_bound(
+        &self,
+        field: Field,
+        bound: &UserInputBound,
+    ) -> Result<Bound<Term>, QueryParserError> {
+        let compute_term = |phrase: &str| {
+            let mut terms = self.compute_terms_for_string(field, phrase)?;
+            if terms.len() != 1 {
+                return Err(QueryParserError::RangeMustNotHavePhrase);
+            }
+            Ok(terms.pop().unwrap())
+        };
+        match *bound {
+            UserInputBound::Inclusive(ref phrase) => Ok(Bound::Included(compute_term(phrase)?)),
+            UserInputBound::Exclusive(ref phrase) => Ok(Bound::Excluded(compute_term(phrase)?)),
+            UserInputBound::Unbounded => Ok(Bound::Unbounded),
+        }
+    }
+
+    /// Returns the fields targeted by a literal:
+    /// the field given in the query if any, or the default fields.
+    fn resolve_fields(&self, field_name: &Option<String>) -> Result<Vec<Field>, QueryParserError> {
+        match *field_name {
+            Some(ref field_name) => {
+                let field = self.resolve_field_name(field_name)?;
+                Ok(vec![field])
+            }
+            None => {
+                if self.default_fields.is_empty() {
+                    Err(QueryParserError::NoDefaultFieldDeclared)
+                } else {
+                    Ok(self.default_fields.clone())
+                }
+            }
+        }
+    }
+
+    /// Computes the logical AST of a literal for each of the fields it targets.
+    ///
+    /// When the literal does not specify any field, the numeric default fields
+    /// are ignored if the literal is not a number, so that `diary` can be searched
+    /// in the default fields `title` and `year`, and the logical AST of each
+    /// field is boosted by the boost of the field, if any.
+    fn compute_logical_ast_for_fields<F>(
+        &self,
+        field_name: &Option<String>,
+        compute_ast: F,
+    ) -> Result<Vec<LogicalAST>, QueryParserError>
+    where
+        F: Fn(Field) -> Result<Option<LogicalAST>, QueryParserError>,
+    {
+        let mut asts: Vec<LogicalAST> = Vec::new();
+        let mut ignored_error = None;
+        for field in self.resolve_fields(field_name)? {
+            match compute_ast(field) {
+                Ok(Some(ast)) => {
+                    let boost_opt = if field_name.is_none() {
+                        self.field_boosts.get(&field).cloned()
+                    } else {
+                        None
+                    };
+                    match boost_opt {
+                        Some(boost) => asts.push(LogicalAST::Boost(box ast, boost)),
+                        None => asts.push(ast),
+                    }
+                }
+                Ok(None) => {}
+                Err(QueryParserError::ExpectedInt(err)) if field_name.is_none() => {
+                    ignored_error = ignored_error.or(Some(QueryParserError::ExpectedInt(err)));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        if asts.is_empty() {
+            if let Some(err) = ignored_error {
+                return Err(err);
             }
         }
+        Ok(asts)
     }
 
     fn default_occur(&self) -> Occur {
@@ -236,6 +475,14 @@ impl QueryParser {
                 }
                 Ok((Occur::Should, LogicalAST::Clause(logical_sub_queries)))
             }
+            UserInputAST::Disjunction(sub_queries) => {
+                let mut logical_sub_queries: Vec<(Occur, LogicalAST)> = Vec::new();
+                for sub_query in sub_queries {
+                    let (occur, sub_ast) = self.compute_logical_ast_with_occur(*sub_query)?;
+                    logical_sub_queries.push((compose_occur(Occur::Should, occur), sub_ast));
+                }
+                Ok((Occur::Should, LogicalAST::Clause(logical_sub_queries)))
+            }
             UserInputAST::Not(subquery) => {
                 let (occur, logical_sub_queries) = self.compute_logical_ast_with_occur(*subquery)?;
                 Ok((compose_occur(Occur::MustNot, occur), logical_sub_queries))
@@ -244,43 +491,48 @@ impl QueryParser {
                 let (occur, logical_sub_queries) = self.compute_logical_ast_with_occur(*subquery)?;
                 Ok((compose_occur(Occur::Must, occur), logical_sub_queries))
             }
+            UserInputAST::Boost(subquery, boost) => {
+                let (occur, logical_sub_queries) = self.compute_logical_ast_with_occur(*subquery)?;
+                Ok((occur, LogicalAST::Boost(box logical_sub_queries, boost)))
+            }
+            UserInputAST::Range {
+                field_name,
+                lower,
+                upper,
+            } => {
+                let asts = self.compute_logical_ast_for_fields(&field_name, |field| {
+                    let range = LogicalLiteral::Range {
+                        field,
+                        lower: self.resolve_bound(field, &lower)?,
+                        upper: self.resolve_bound(field, &upper)?,
+                    };
+                    Ok(Some(LogicalAST::from(range)))
+                })?;
+                Ok((Occur::Should, compose_should(asts)))
+            }
             UserInputAST::Leaf(literal) => {
-                let term_phrases: Vec<(Field, String)> = match literal.field_name {
-                    Some(ref field_name) => {
-                        let field = self.resolve_field_name(field_name)?;
-                        vec![(field, literal.phrase.clone())]
-                    }
-                    None => {
-                        if self.default_fields.is_empty() {
-                            return Err(QueryParserError::NoDefaultFieldDeclared);
-                        } else {
-                            self.default_fields
-                                .iter()
-                                .map(|default_field| (*default_field, literal.phrase.clone()))
-                                .collect::<Vec<(Field, String)>>()
-                        }
-                    }
-                };
-                let mut asts: Vec<LogicalAST> = Vec::new();
-                for (field, phrase) in term_phrases {
-                    if let Some(ast) = self.compute_logical_ast_for_leaf(field, &phrase)? {
-                        asts.push(LogicalAST::Leaf(box ast));
-                    }
-                }
-                let result_ast = if asts.is_empty() {
+                let asts = self.compute_logical_ast_for_fields(&literal.field_name, |field| {
+                    self.compute_logical_ast_for_leaf(field, &literal.phrase, &literal.slop)
+                })?;
+                if asts.is_empty() {
                     // this should never happen
                     return Err(QueryParserError::SyntaxError);
-                } else if asts.len() == 1 {
-                    asts[0].clone()
-                } else {
-                    LogicalAST::Clause(asts.into_iter().map(|ast| (Occur::Should, ast)).collect())
-                };
-                Ok((Occur::Should, result_ast))
+                }
+                Ok((Occur::Should, compose_should(asts)))
             }
         }
     }
 }
 
+/// Returns a logical AST matching any of the given `asts`.
+fn compose_should(mut asts: Vec<LogicalAST>) -> LogicalAST {
+    if asts.len() == 1 {
+        asts.pop().unwrap()
+    } else {
+        LogicalAST::Clause(asts.into_iter().map(|ast| (Occur::Should, ast)).collect())
+    }
+}
+
 /// Compose two occur values.
 fn compose_occur(left: Occur, right: Occur) -> Occur {
     match left {
@@ -299,6 +551,13 @@ fn compose_occur(left: Occur, right: Occur) -> Occur {
                 Occur::MustNot
             }
         }
+        Occur::Filter => {
+            if right == Occur::MustNot {
+                Occur::MustNot
+            } else {
+                Occur::Filter
+            }
+        }
     }
 }
 
@@ -306,6 +565,22 @@ fn convert_literal_to_query(logical_literal: LogicalLiteral) -> Box<Query> {
     match logical_literal {
         LogicalLiteral::Term(term) => box TermQuery::new(term, IndexRecordOption::WithFreqs),
         LogicalLiteral::Phrase(terms) => box PhraseQuery::from(terms),
+        LogicalLiteral::Near {
+            terms,
+            slop,
+            ordered,
+        } => {
+            if ordered {
+                box NearQuery::ordered(terms, slop)
+            } else {
+                box NearQuery::unordered(terms, slop)
+            }
+        }
+        LogicalLiteral::Range {
+            field,
+            lower,
+            upper,
+        } => box RangeQuery::new_term_bounds(field, lower, upper),
     }
 }
 
@@ -318,6 +593,9 @@ fn convert_to_query(logical_ast: LogicalAST) -> Box<Query> {
                 .collect::<Vec<_>>();
             box BooleanQuery::from(occur_subqueries)
         }
+        LogicalAST::Boost(subquery, boost) => {
+            box BoostQuery::new(convert_to_query(*subquery), boost)
+        }
         LogicalAST::Leaf(logical_literal) => convert_literal_to_query(*logical_literal),
     }
 }
@@ -334,6 +612,7 @@ mod test {
     use Index;
     use tokenizer::SimpleTokenizer;
     use super::super::logical_ast::*;
+    use collector::TopCollector;
 
     fn make_query_parser() -> QueryParser {
         let mut schema_builder = SchemaBuilder::default();
@@ -543,6 +822,140 @@ mod test {
         assert!(query_parser.parse_query("title:\"happy tax\"").is_ok());
     }
 
+    #[test]
+    pub fn test_query_parser_per_field_tokenizer() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT.set_tokenizer("en_stem"));
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(
+                title => "happy tax payers",
+                body => "happy tax payers"
+            ));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![title, body]);
+        let count = |query: &str| {
+            query_parser
+                .parse_query(query)
+                .unwrap()
+                .count(&*searcher)
+                .unwrap()
+        };
+        assert_eq!(count("title:payer"), 1);
+        assert_eq!(count("body:payer"), 0);
+        assert_eq!(count("body:payers"), 1);
+    }
+
+    #[test]
+    pub fn test_query_parser_field_analyzer() {
+        use tokenizer::{LowerCaser, NgramTokenizer, Tokenizer};
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT.set_tokenizer("edge_ngram"));
+        let index = Index::create_in_ram(schema_builder.build());
+        index.tokenizers().register(
+            "edge_ngram",
+            NgramTokenizer::new(1, 10, true).filter(LowerCaser),
+        );
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(title => "Apple"));
+            index_writer.add_document(doc!(title => "Ananas"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let count = |query_parser: &QueryParser, query: &str| {
+            query_parser
+                .parse_query(query)
+                .unwrap()
+                .count(&*searcher)
+                .unwrap()
+        };
+        let mut query_parser = QueryParser::for_index(&index, vec![title]);
+        // the ngrams of the query: `a` OR `ap`.
+        assert_eq!(count(&query_parser, "ap"), 2);
+        query_parser.set_field_analyzer(title, "raw");
+        assert_eq!(count(&query_parser, "ap"), 1);
+        assert_eq!(count(&query_parser, "Ap"), 0);
+        assert_eq!(count(&query_parser, "ana"), 1);
+        query_parser.set_field_analyzer(title, "nonexistingtokenizer");
+        assert_matches!(
+            query_parser.parse_query("ap"),
+            Err(QueryParserError::UnknownTokenizer(_, _))
+        );
+    }
+
+    #[test]
+    pub fn test_query_parser_token_positions() {
+        use std::collections::HashMap;
+        use tokenizer::{LowerCaser, NgramTokenizer, SynonymFilter, Tokenizer};
+        let mut schema_builder = SchemaBuilder::default();
+        let tag = schema_builder.add_text_field("tag", STRING);
+        let text = schema_builder.add_text_field("text", TEXT);
+        let stemmed = schema_builder.add_text_field("stemmed", TEXT.set_tokenizer("en_stem"));
+        let ngram = schema_builder.add_text_field("ngram", TEXT.set_tokenizer("ngram"));
+        let synonym = schema_builder.add_text_field("synonym", TEXT.set_tokenizer("synonym"));
+        let index = Index::create_in_ram(schema_builder.build());
+        index
+            .tokenizers()
+            .register("ngram", NgramTokenizer::new(2, 3, false));
+        let mut synonyms = HashMap::new();
+        synonyms.insert("car".to_string(), vec!["automobile".to_string()]);
+        index.tokenizers().register(
+            "synonym",
+            SimpleTokenizer
+                .filter(LowerCaser)
+                .filter(SynonymFilter::new(synonyms)),
+        );
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(
+                tag => "new york",
+                text => "new york city",
+                stemmed => "running shoes",
+                ngram => "new york",
+                synonym => "automobile"
+            ));
+            index_writer.add_document(doc!(
+                tag => "new",
+                text => "york new",
+                stemmed => "run shoe",
+                ngram => "york",
+                synonym => "car"
+            ));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![text]);
+        let count = |query: &str| {
+            query_parser
+                .parse_query(query)
+                .unwrap()
+                .count(&*searcher)
+                .unwrap()
+        };
+        // raw: a single term, on the exact string.
+        assert_eq!(count("tag:\"new york\""), 1);
+        assert_eq!(count("tag:\"new\""), 1);
+        assert_eq!(count("tag:\"york\""), 0);
+        // one token per position: a phrase.
+        assert_eq!(count("text:\"new york\""), 1);
+        assert_eq!(count("stemmed:\"running shoes\""), 2);
+        // tokens stacked on several positions: one of the tokens of each position.
+        assert_eq!(count("ngram:york"), 2);
+        assert_eq!(count("ngram:\"w yo\""), 1);
+        assert_eq!(count("ngram:wyo"), 0);
+        // tokens on a single position: any of them.
+        assert_eq!(count("synonym:car"), 2);
+        assert_eq!(count("synonym:automobile"), 2);
+    }
+
     #[test]
     pub fn test_query_parser_expected_int() {
         let query_parser = make_query_parser();
@@ -557,6 +970,252 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn test_parse_query_boolean_operators() {
+        test_parse_query_to_logical_ast_helper(
+            "title:a AND title:b",
+            "(+Term([0, 0, 0, 0, 97]) +Term([0, 0, 0, 0, 98]))",
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "title:a OR title:b",
+            "(Term([0, 0, 0, 0, 97]) Term([0, 0, 0, 0, 98]))",
+            true,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "title:a OR title:b AND -title:c",
+            "(Term([0, 0, 0, 0, 97]) \
+             (+Term([0, 0, 0, 0, 98]) -Term([0, 0, 0, 0, 99])))",
+            true,
+        );
+    }
+
+    #[test]
+    pub fn test_parse_query_range() {
+        test_parse_query_to_logical_ast_helper(
+            "unsigned:[2 TO 5}",
+            "[Term([0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 2]) \
+             TO Term([0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 5])}",
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "title:{A TO *]",
+            "{Term([0, 0, 0, 0, 97]) TO *}",
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "[a TO b]",
+            "([Term([0, 0, 0, 0, 97]) TO Term([0, 0, 0, 0, 98])] \
+             [Term([0, 0, 0, 1, 97]) TO Term([0, 0, 0, 1, 98])])",
+            false,
+        );
+        let query_parser = make_query_parser();
+        assert_matches!(
+            query_parser.parse_query("title:[a TO \"b c\"]"),
+            Err(QueryParserError::RangeMustNotHavePhrase)
+        );
+        assert_matches!(
+            query_parser.parse_query("unsigned:[a TO 3]"),
+            Err(QueryParserError::ExpectedInt(_))
+        );
+    }
+
+    #[test]
+    pub fn test_parse_query_slop() {
+        test_parse_query_to_logical_ast_helper(
+            "title:\"a b\"~2",
+            "\"[Term([0, 0, 0, 0, 97]), Term([0, 0, 0, 0, 98])]\"~2",
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "title:\"a b\"~0!",
+            "\"[Term([0, 0, 0, 0, 97]), Term([0, 0, 0, 0, 98])]\"~0!",
+            false,
+        );
+        test_parse_query_to_logical_ast_helper("title:\"a\"~2", "Term([0, 0, 0, 0, 97])", false);
+    }
+
+    #[test]
+    pub fn test_parse_query_boost() {
+        test_parse_query_to_logical_ast_helper(
+            "title:a^2.5",
+            "(Term([0, 0, 0, 0, 97]))^2.5",
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "+title:a^2 -title:b^3",
+            "(+(Term([0, 0, 0, 0, 97]))^2 -(Term([0, 0, 0, 0, 98]))^3)",
+            false,
+        );
+    }
+
+    #[test]
+    pub fn test_parse_query_field_boost() {
+        let mut query_parser = make_query_parser();
+        let title = query_parser.schema.get_field("title").unwrap();
+        query_parser.set_field_boost(title, 2.0);
+        let logical_ast = |query: &str| {
+            format!(
+                "{:?}",
+                query_parser.parse_query_to_logical_ast(query).unwrap()
+            )
+        };
+        assert_eq!(
+            logical_ast("a"),
+            "((Term([0, 0, 0, 0, 97]))^2 Term([0, 0, 0, 1, 97]))"
+        );
+        assert_eq!(
+            logical_ast("a^3"),
+            "(((Term([0, 0, 0, 0, 97]))^2 Term([0, 0, 0, 1, 97])))^3"
+        );
+        assert_eq!(logical_ast("title:a"), "Term([0, 0, 0, 0, 97])");
+        assert_eq!(logical_ast("text:a"), "Term([0, 0, 0, 1, 97])");
+    }
+
+    #[test]
+    pub fn test_query_parser_field_boost_search() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(title => "girl", body => "diary"));
+            index_writer.add_document(doc!(title => "diary", body => "girl"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let top_doc = |query_parser: &QueryParser| {
+            let mut top_collector = TopCollector::with_limit(1);
+            query_parser
+                .parse_query("diary")
+                .unwrap()
+                .search(&*searcher, &mut top_collector)
+                .unwrap();
+            top_collector.docs()[0].doc()
+        };
+        let mut query_parser = QueryParser::for_index(&index, vec![title, body]);
+        query_parser.set_field_boost(body, 10.0);
+        assert_eq!(top_doc(&query_parser), 0);
+        query_parser.set_field_boost(title, 100.0);
+        assert_eq!(top_doc(&query_parser), 1);
+    }
+
+    #[test]
+    pub fn test_query_parser_range_and_boost_search() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let year = schema_builder.add_u64_field("year", INT_INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(title => "diary", year => 1958u64));
+            index_writer.add_document(doc!(title => "girl", year => 1960u64));
+            index_writer.add_document(doc!(title => "diary girl", year => 1965u64));
+            index_writer.add_document(doc!(title => "girl", year => 1970u64));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![title]);
+        let count = |query: &str| {
+            query_parser
+                .parse_query(query)
+                .unwrap()
+                .count(&*searcher)
+                .unwrap()
+        };
+        assert_eq!(count("year:[1960 TO 1970]"), 3);
+        assert_eq!(count("year:{1960 TO 1970}"), 1);
+        assert_eq!(count("year:[* TO 1965}"), 2);
+        assert_eq!(count("girl AND year:[1960 TO *]"), 3);
+        assert_eq!(count("diary AND (girl OR year:[* TO 1960])"), 2);
+
+        let top_doc = |query: &str| {
+            let mut top_collector = TopCollector::with_limit(1);
+            query_parser
+                .parse_query(query)
+                .unwrap()
+                .search(&*searcher, &mut top_collector)
+                .unwrap();
+            top_collector.docs()[0].doc()
+        };
+        assert_eq!(top_doc("diary^10 year:[1970 TO 1970]"), 0);
+        assert_eq!(top_doc("diary year:[1970 TO 1970]^10"), 3);
+    }
+
+    #[test]
+    pub fn test_parse_query_numeric_default_fields() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let year = schema_builder.add_u64_field("year", INT_INDEXED);
+        let schema = schema_builder.build();
+        let query_parser = QueryParser::new(
+            schema.clone(),
+            vec![title, year],
+            TokenizerManager::default(),
+        );
+        let logical_ast = |query: &str| {
+            format!(
+                "{:?}",
+                query_parser.parse_query_to_logical_ast(query).unwrap()
+            )
+        };
+        assert_eq!(logical_ast("a"), "Term([0, 0, 0, 0, 97])");
+        assert_eq!(
+            logical_ast("1"),
+            "(Term([0, 0, 0, 0, 49]) Term([0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1]))"
+        );
+        assert_eq!(
+            logical_ast("[a TO b]"),
+            "[Term([0, 0, 0, 0, 97]) TO Term([0, 0, 0, 0, 98])]"
+        );
+        assert_matches!(
+            query_parser.parse_query("year:a"),
+            Err(QueryParserError::ExpectedInt(_))
+        );
+        let numeric_query_parser =
+            QueryParser::new(schema, vec![year], TokenizerManager::default());
+        assert_matches!(
+            numeric_query_parser.parse_query("a"),
+            Err(QueryParserError::ExpectedInt(_))
+        );
+        assert!(numeric_query_parser.parse_query("1960").is_ok());
+    }
+
+    #[test]
+    pub fn test_query_parser_numeric_fields_search() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let delta = schema_builder.add_i64_field("delta", INT_INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for val in -10i64..10i64 {
+                index_writer.add_document(doc!(title => "diary", delta => val));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![title, delta]);
+        let count = |query: &str| {
+            query_parser
+                .parse_query(query)
+                .unwrap()
+                .count(&*searcher)
+                .unwrap()
+        };
+        assert_eq!(count("delta:-3"), 1);
+        assert_eq!(count("delta:[-5 TO 5]"), 11);
+        assert_eq!(count("delta:{-5 TO 5}"), 9);
+        assert_eq!(count("delta:[* TO -8]"), 3);
+        assert_eq!(count("diary AND delta:[0 TO *]"), 10);
+        assert_eq!(count("3"), 1);
+        assert_eq!(count("diary"), 20);
+    }
+
     #[test]
     pub fn test_parse_query_to_ast_conjunction() {
         test_parse_query_to_logical_ast_helper(
@@ -1,23 +1,87 @@
 use std::fmt;
+use Score;
+
+/// Slop of a phrase, e.g. `"a b"~2`.
+///
+/// The words of the phrase must then be near each other,
+/// and in order if the slop is followed by `!`.
+pub struct UserInputSlop {
+    pub slop: u32,
+    pub ordered: bool,
+}
 
 pub struct UserInputLiteral {
     pub field_name: Option<String>,
     pub phrase: String,
+    pub slop: Option<UserInputSlop>,
 }
 
 impl fmt::Debug for UserInputLiteral {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match self.field_name {
-            Some(ref field_name) => write!(formatter, "{}:\"{}\"", field_name, self.phrase),
-            None => write!(formatter, "\"{}\"", self.phrase),
+            Some(ref field_name) => write!(formatter, "{}:\"{}\"", field_name, self.phrase)?,
+            None => write!(formatter, "\"{}\"", self.phrase)?,
+        }
+        if let Some(ref slop) = self.slop {
+            write!(formatter, "~{}", slop.slop)?;
+            if slop.ordered {
+                write!(formatter, "!")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub enum UserInputBound {
+    Inclusive(String),
+    Exclusive(String),
+    Unbounded,
+}
+
+impl UserInputBound {
+    pub fn new(val: Option<String>, inclusive: bool) -> UserInputBound {
+        match val {
+            Some(val) => {
+                if inclusive {
+                    UserInputBound::Inclusive(val)
+                } else {
+                    UserInputBound::Exclusive(val)
+                }
+            }
+            None => UserInputBound::Unbounded,
+        }
+    }
+
+    fn display_lower(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            UserInputBound::Inclusive(ref val) => write!(formatter, "[\"{}\"", val),
+            UserInputBound::Exclusive(ref val) => write!(formatter, "{{\"{}\"", val),
+            UserInputBound::Unbounded => write!(formatter, "{{*"),
+        }
+    }
+
+    fn display_upper(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            UserInputBound::Inclusive(ref val) => write!(formatter, "\"{}\"]", val),
+            UserInputBound::Exclusive(ref val) => write!(formatter, "\"{}\"}}", val),
+            UserInputBound::Unbounded => write!(formatter, "*}}"),
         }
     }
 }
 
 pub enum UserInputAST {
     Clause(Vec<Box<UserInputAST>>),
+    /// Subqueries separated by `OR`.
+    /// They are optional, whatever the default occur of the query parser.
+    Disjunction(Vec<Box<UserInputAST>>),
     Not(Box<UserInputAST>),
     Must(Box<UserInputAST>),
+    Range {
+        field_name: Option<String>,
+        lower: UserInputBound,
+        upper: UserInputBound,
+    },
+    Boost(Box<UserInputAST>, Score),
     Leaf(Box<UserInputLiteral>),
 }
 
@@ -44,7 +108,32 @@ impl fmt::Debug for UserInputAST {
                 }
                 Ok(())
             }
+            UserInputAST::Disjunction(ref subqueries) => {
+                write!(formatter, "(")?;
+                for (i, subquery) in subqueries.iter().enumerate() {
+                    if i > 0 {
+                        write!(formatter, " OR ")?;
+                    }
+                    write!(formatter, "{:?}", subquery)?;
+                }
+                write!(formatter, ")")
+            }
             UserInputAST::Not(ref subquery) => write!(formatter, "-({:?})", subquery),
+            UserInputAST::Range {
+                ref field_name,
+                ref lower,
+                ref upper,
+            } => {
+                if let Some(ref field_name) = *field_name {
+                    write!(formatter, "{}:", field_name)?;
+                }
+                lower.display_lower(formatter)?;
+                write!(formatter, " TO ")?;
+                upper.display_upper(formatter)
+            }
+            UserInputAST::Boost(ref subquery, boost) => {
+                write!(formatter, "({:?})^{}", subquery, boost)
+            }
             UserInputAST::Leaf(ref subquery) => write!(formatter, "{:?}", subquery),
         }
     }
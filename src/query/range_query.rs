@@ -1,14 +1,22 @@
-use schema::{Field, IndexRecordOption, Term};
+use schema::{Cardinality, Field, FieldType, IndexRecordOption, Schema, Term};
 use query::{Query, Scorer, Weight};
 use termdict::{TermDictionary, TermStreamer, TermStreamerBuilder};
 use core::SegmentReader;
 use common::BitSet;
+use DocId;
 use Result;
-use core::Searcher;
+use query::Explanation;
+use query::explanation::{does_not_match, seek_doc};
+use core::{MemoryBudget, Searcher};
 use query::BitSetDocSet;
 use query::ConstScorer;
+use query::EmptyScorer;
+use std::cmp;
 use std::collections::Bound;
 use std::collections::range::RangeArgument;
+use byteorder::{BigEndian, ByteOrder};
+use fastfield::FastFieldReader;
+use query::DocRangeDocSet;
 
 fn map_bound<TFrom, Transform: Fn(TFrom) -> Vec<u8>>(
     bound: Bound<TFrom>,
@@ -31,6 +39,14 @@ fn map_bound<TFrom, Transform: Fn(TFrom) -> Vec<u8>>(
 /// The current implement will iterate over the terms within the range
 /// and append all of the document cross into a `BitSet`.
 ///
+/// The memory of the `BitSet` is reserved against the memory budget
+/// of the searcher. (See `Searcher::with_memory_budget`)
+///
+/// If the documents of a segment are sorted by the values of an integer
+/// fast field (See `SegmentReader::is_sorted_by`), the documents matching
+/// a range over this field are contiguous. Their doc id boundaries are then
+/// binary searched in the fast field, and no `BitSet` is required.
+///
 /// # Example
 ///
 /// ```rust
@@ -125,22 +141,62 @@ impl RangeQuery {
             right_bound: map_bound(range.end(), &make_term_val),
         }
     }
+
+    /// Create a new `RangeQuery` given the bounds of the range
+    /// expressed as `Term`s.
+    ///
+    /// The bound terms are expected to belong to `field`.
+    pub fn new_term_bounds(
+        field: Field,
+        left_bound: Bound<Term>,
+        right_bound: Bound<Term>,
+    ) -> RangeQuery {
+        let make_term_val = |term: Term| term.value_bytes().to_owned();
+        RangeQuery {
+            field,
+            left_bound: map_bound(left_bound, &make_term_val),
+            right_bound: map_bound(right_bound, &make_term_val),
+        }
+    }
 }
 
 impl Query for RangeQuery {
-    fn weight(&self, _searcher: &Searcher, _scoring_enabled: bool) -> Result<Box<Weight>> {
+    fn weight(&self, searcher: &Searcher, _scoring_enabled: bool) -> Result<Box<Weight>> {
         Ok(box RangeWeight {
             field: self.field,
             left_bound: self.left_bound.clone(),
             right_bound: self.right_bound.clone(),
+            memory_budget: searcher.memory_budget().clone(),
         })
     }
+
+    fn normalized(&self) -> String {
+        // The smallest term strictly greater than `val` is `val` followed by a null byte.
+        let successor = |val: &Vec<u8>| {
+            let mut successor = val.clone();
+            successor.push(0u8);
+            successor
+        };
+        let left_bound = match self.left_bound {
+            Bound::Excluded(ref val) => Bound::Included(successor(val)),
+            ref left_bound => left_bound.clone(),
+        };
+        let right_bound = match self.right_bound {
+            Bound::Included(ref val) => Bound::Excluded(successor(val)),
+            ref right_bound => right_bound.clone(),
+        };
+        format!(
+            "RangeQuery {{ field: {:?}, left_bound: {:?}, right_bound: {:?} }}",
+            self.field, left_bound, right_bound
+        )
+    }
 }
 
 pub struct RangeWeight {
     field: Field,
     left_bound: Bound<Vec<u8>>,
     right_bound: Bound<Vec<u8>>,
+    memory_budget: MemoryBudget,
 }
 
 impl RangeWeight {
@@ -162,41 +218,180 @@ impl RangeWeight {
         };
         term_stream_builder.into_stream()
     }
+
+    /// Returns the range of doc ids `[start_doc, end_doc)` matching
+    /// the query, if the segment is sorted by the field.
+    fn sorted_doc_range(&self, reader: &SegmentReader) -> Option<(DocId, DocId)> {
+        if !is_single_valued(reader.schema(), self.field)
+            || !reader.segment_meta().has_single_term_per_doc(self.field)
+            || !reader.is_sorted_by(self.field)
+        {
+            return None;
+        }
+        let fast_field_reader = reader.u64_fast_field_reader(self.field)?;
+        let max_doc = reader.max_doc();
+        // values of the range are within `[lower_val, upper_val)`,
+        // `upper_val` being `None` if the range is unbounded.
+        let lower_val: u64 = match self.left_bound {
+            Bound::Included(ref val) => u64_term_val(val)?,
+            Bound::Excluded(ref val) => match u64_term_val(val)?.checked_add(1) {
+                Some(lower_val) => lower_val,
+                None => return Some((max_doc, max_doc)),
+            },
+            Bound::Unbounded => 0u64,
+        };
+        let upper_val: Option<u64> = match self.right_bound {
+            Bound::Included(ref val) => u64_term_val(val)?.checked_add(1),
+            Bound::Excluded(ref val) => Some(u64_term_val(val)?),
+            Bound::Unbounded => None,
+        };
+        let start_doc = first_doc_with_val(&fast_field_reader, max_doc, lower_val);
+        let end_doc = match upper_val {
+            Some(upper_val) => first_doc_with_val(&fast_field_reader, max_doc, upper_val),
+            None => max_doc,
+        };
+        Some((start_doc, cmp::max(start_doc, end_doc)))
+    }
+
+    /// Returns true iff the range does not intersect the bounds of the
+    /// values of the field in the segment, so that no document can match.
+    fn is_out_of_segment_bounds(&self, reader: &SegmentReader) -> bool {
+        let (min_value, max_value) = match reader.u64_fast_field_bounds(self.field) {
+            Some(bounds) => bounds,
+            None => return false,
+        };
+        let is_below = match self.right_bound {
+            Bound::Included(ref val) => u64_term_val(val).map(|val| val < min_value),
+            Bound::Excluded(ref val) => u64_term_val(val).map(|val| val <= min_value),
+            Bound::Unbounded => None,
+        };
+        let is_above = match self.left_bound {
+            Bound::Included(ref val) => u64_term_val(val).map(|val| val > max_value),
+            Bound::Excluded(ref val) => u64_term_val(val).map(|val| val >= max_value),
+            Bound::Unbounded => None,
+        };
+        is_below.unwrap_or(false) || is_above.unwrap_or(false)
+    }
+}
+
+/// Returns the `u64` representation of the value of an integer term.
+fn u64_term_val(term_val: &[u8]) -> Option<u64> {
+    if term_val.len() == 8 {
+        Some(BigEndian::read_u64(term_val))
+    } else {
+        None
+    }
+}
+
+/// Returns the first document whose value is greater or equal to `val`,
+/// or `max_doc` if there are none, given that the values are sorted.
+fn first_doc_with_val(
+    fast_field_reader: &FastFieldReader<u64>,
+    max_doc: DocId,
+    val: u64,
+) -> DocId {
+    let mut start = 0u32;
+    let mut end = max_doc;
+    while start < end {
+        let mid = start + (end - start) / 2;
+        if fast_field_reader.get(mid) < val {
+            start = mid + 1;
+        } else {
+            end = mid;
+        }
+    }
+    start
 }
 
 impl Weight for RangeWeight {
     fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        if self.is_out_of_segment_bounds(reader) || !reader.has_inverted_index(self.field) {
+            return Ok(box EmptyScorer);
+        }
+        if let Some((start_doc, end_doc)) = self.sorted_doc_range(reader) {
+            let delete_bitset = reader.delete_bitset().clone();
+            return Ok(box DocRangeDocSet::new(start_doc, end_doc, delete_bitset));
+        }
         let max_doc = reader.max_doc();
+        let num_bitset_bytes = (max_doc as usize + 63) / 64 * 8;
+        self.memory_budget.allocate(num_bitset_bytes)?;
         let mut doc_bitset = BitSet::with_max_value(max_doc);
 
-        let inverted_index = reader.inverted_index(self.field);
+        let delete_bitset = reader.delete_bitset();
+        let inverted_index = reader.inverted_index(self.field)?;
         let term_dict = inverted_index.terms();
         let mut term_range = self.term_range(term_dict);
         while term_range.advance() {
             let term_info = term_range.value();
             let mut block_segment_postings = inverted_index
-                .read_block_postings_from_terminfo(term_info, IndexRecordOption::Basic);
+                .read_block_postings_from_terminfo(term_info, IndexRecordOption::Basic)?;
+            // the block postings do not skip the deleted (or masked) documents.
             while block_segment_postings.advance() {
                 for &doc in block_segment_postings.docs() {
-                    doc_bitset.insert(doc);
+                    if !delete_bitset.is_deleted(doc) {
+                        doc_bitset.insert(doc);
+                    }
                 }
             }
         }
         let doc_bitset = BitSetDocSet::from(doc_bitset);
         Ok(box ConstScorer::new(doc_bitset))
     }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        let mut scorer = self.scorer(reader)?;
+        if !seek_doc(&mut scorer, doc) {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("RangeQuery", scorer.score()))
+    }
+
+    fn count(&self, reader: &SegmentReader) -> Result<u32> {
+        if self.is_out_of_segment_bounds(reader) || !reader.has_inverted_index(self.field) {
+            return Ok(0);
+        }
+        // The doc freqs of the terms of the range can only be summed
+        // without decoding their postings if no document of the segment
+        // holds more than one term of the field.
+        if reader.has_deletes() || !reader.segment_meta().has_single_term_per_doc(self.field) {
+            return Ok(self.scorer(reader)?.count());
+        }
+        if let Some((start_doc, end_doc)) = self.sorted_doc_range(reader) {
+            return Ok(end_doc - start_doc);
+        }
+        let inverted_index = reader.inverted_index(self.field)?;
+        let term_dict = inverted_index.terms();
+        let mut term_range = self.term_range(term_dict);
+        let mut count = 0u32;
+        while term_range.advance() {
+            count += term_range.value().doc_freq;
+        }
+        Ok(count)
+    }
+}
+
+/// Returns true iff the field is an integer field declared
+/// as a single-valued fast field.
+fn is_single_valued(schema: &Schema, field: Field) -> bool {
+    match *schema.get_field_entry(field).field_type() {
+        FieldType::U64(ref int_options) | FieldType::I64(ref int_options) => {
+            int_options.get_fastfield_cardinality() == Some(Cardinality::SingleValue)
+        }
+        _ => false,
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use Index;
-    use schema::{Document, Field, SchemaBuilder, INT_INDEXED};
+    use schema::{Document, Field, SchemaBuilder, Term, FAST, INT_INDEXED};
     use collector::CountCollector;
     use std::collections::Bound;
     use query::Query;
     use Result;
     use super::RangeQuery;
+    use {ErrorKind, MemoryBudget};
 
     #[test]
     fn test_range_query_simple() {
@@ -284,4 +479,178 @@ mod tests {
         assert_eq!(count_multiples(RangeQuery::new_i64(int_field, 9..)), 91);
     }
 
+    #[test]
+    fn test_range_query_count() {
+        let mut schema_builder = SchemaBuilder::new();
+        let year_field = schema_builder.add_u64_field("year", INT_INDEXED | FAST);
+        let tag_field = schema_builder.add_u64_field("tag", INT_INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 6_000_000).unwrap();
+        for year in 1950u64..2017u64 {
+            index_writer.add_document(doc!(
+                year_field => year,
+                tag_field => 1u64,
+                tag_field => 2u64
+            ));
+        }
+        index_writer.commit().unwrap();
+        let count = |query: &RangeQuery| {
+            index.load_searchers().unwrap();
+            let searcher = index.searcher();
+            let mut count_collector = CountCollector::default();
+            query.search(&*searcher, &mut count_collector).unwrap();
+            let count = query.count(&*searcher).unwrap();
+            assert_eq!(count, count_collector.count());
+            count
+        };
+        let sixties = RangeQuery::new_u64(year_field, 1960u64..1970u64);
+        let tags = RangeQuery::new_u64(tag_field, 0u64..10u64);
+        assert_eq!(count(&sixties), 10);
+        // documents with several terms within the range are only counted once.
+        assert_eq!(count(&tags), 67);
+        index_writer.delete_term(Term::from_field_u64(year_field, 1965u64));
+        index_writer.commit().unwrap();
+        assert_eq!(count(&sixties), 9);
+        assert_eq!(count(&tags), 66);
+    }
+
+    #[test]
+    fn test_range_query_count_single_valued_field_with_several_values() {
+        let mut schema_builder = SchemaBuilder::new();
+        let rating_field = schema_builder.add_u64_field("rating", INT_INDEXED | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 6_000_000).unwrap();
+        index_writer.add_document(doc!(rating_field => 1u64));
+        index_writer.add_document(doc!(rating_field => 1u64, rating_field => 2u64));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(rating_field => 2u64));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let num_single_term_segments = searcher
+            .segment_readers()
+            .iter()
+            .filter(|reader| reader.segment_meta().has_single_term_per_doc(rating_field))
+            .count();
+        assert_eq!(num_single_term_segments, 1);
+        let query = RangeQuery::new_u64(rating_field, 0u64..10u64);
+        let mut count_collector = CountCollector::default();
+        query.search(&*searcher, &mut count_collector).unwrap();
+        assert_eq!(count_collector.count(), 3);
+        assert_eq!(query.count(&*searcher).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_range_query_sorted_segment() {
+        let mut schema_builder = SchemaBuilder::new();
+        let timestamp_field = schema_builder.add_i64_field("timestamp", INT_INDEXED | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 6_000_000).unwrap();
+        // sorted segment, with several documents per timestamp.
+        for i in 0i64..1_000i64 {
+            index_writer.add_document(doc!(timestamp_field => i / 2 - 100));
+        }
+        index_writer.commit().unwrap();
+        // unsorted segment.
+        for i in 0i64..100i64 {
+            index_writer.add_document(doc!(timestamp_field => 50 - i));
+        }
+        index_writer.commit().unwrap();
+        // segment with a document without a timestamp.
+        index_writer.add_document(doc!(timestamp_field => 0i64));
+        index_writer.add_document(Document::default());
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut sorted_segments: Vec<bool> = searcher
+            .segment_readers()
+            .iter()
+            .map(|segment_reader| segment_reader.is_sorted_by(timestamp_field))
+            .collect();
+        sorted_segments.sort();
+        assert_eq!(sorted_segments, vec![false, false, true]);
+
+        let count = |query: &RangeQuery| {
+            let mut count_collector = CountCollector::default();
+            query.search(&*searcher, &mut count_collector).unwrap();
+            let count = query.count(&*searcher).unwrap();
+            assert_eq!(count, count_collector.count());
+            count
+        };
+        assert_eq!(count(&RangeQuery::new_i64(timestamp_field, -10i64..10i64)), 40 + 20 + 1);
+        assert_eq!(count(&RangeQuery::new_i64(timestamp_field, 0i64..)), 800 + 51 + 1);
+        assert_eq!(count(&RangeQuery::new_i64(timestamp_field, ..-99i64)), 2);
+        assert_eq!(count(&RangeQuery::new_i64(timestamp_field, 1_000i64..)), 0);
+        assert_eq!(
+            count(&RangeQuery::new_term_bounds(
+                timestamp_field,
+                Bound::Excluded(Term::from_field_i64(timestamp_field, 398)),
+                Bound::Included(Term::from_field_i64(timestamp_field, 399)),
+            )),
+            2
+        );
+    }
+
+    #[test]
+    fn test_range_query_memory_budget() {
+        let mut schema_builder = SchemaBuilder::new();
+        let year_field = schema_builder.add_u64_field("year", INT_INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 6_000_000).unwrap();
+            for year in 0u64..1_000u64 {
+                index_writer.add_document(doc!(year_field => year));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let range_query = RangeQuery::new_u64(year_field, 10u64..20u64);
+
+        let memory_budget = MemoryBudget::unlimited();
+        let mut count_collector = CountCollector::default();
+        searcher
+            .with_memory_budget(memory_budget.clone())
+            .search(&range_query, &mut count_collector)
+            .unwrap();
+        assert_eq!(count_collector.count(), 10);
+        // 1_000 docs -> 16 u64 buckets
+        assert_eq!(memory_budget.allocated_bytes(), 128);
+
+        let mut count_collector = CountCollector::default();
+        let err = searcher
+            .with_memory_budget(MemoryBudget::with_limit(100))
+            .search(&range_query, &mut count_collector)
+            .unwrap_err();
+        match *err.kind() {
+            ErrorKind::BudgetExceeded(100) => {}
+            _ => panic!("Expected a BudgetExceeded error"),
+        }
+    }
+
+    #[test]
+    fn test_range_query_fingerprint() {
+        let field = Field(0);
+        let term = |val: &str| Term::from_field_text(field, val);
+        let included_excluded =
+            RangeQuery::new_term_bounds(field, Bound::Included(term("a")), Bound::Excluded(term("c")));
+        assert_eq!(
+            RangeQuery::new_str(field, "a".."c").fingerprint(),
+            included_excluded.fingerprint()
+        );
+        let excluded_included =
+            RangeQuery::new_term_bounds(field, Bound::Excluded(term("a")), Bound::Included(term("c")));
+        assert_eq!(
+            excluded_included.normalized(),
+            RangeQuery::new_term_bounds(
+                field,
+                Bound::Included(term("a\u{0}")),
+                Bound::Excluded(term("c\u{0}"))
+            ).normalized()
+        );
+        assert_ne!(
+            RangeQuery::new_str(field, "a".."c").fingerprint(),
+            RangeQuery::new_str(field, "a".."d").fingerprint()
+        );
+    }
 }
@@ -0,0 +1,313 @@
+use Result;
+use Score;
+use DocId;
+use common::BitSet;
+use core::SegmentReader;
+use core::Searcher;
+use docset::{DocSet, SkipResult};
+use fastfield::FastFieldReader;
+use query::{Explanation, Query, Scorer, Weight, WeightCache};
+use schema::Field;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// `RecencyBoostQuery` multiplies the score of the documents matched
+/// by its underlying query by a decay factor, depending on their age.
+///
+/// The date of the documents is read from an `i64` fast field,
+/// holding a timestamp in seconds since the Unix epoch.
+/// The decay is exponential: the factor is `0.5 ^ (age / half_life)`,
+/// where `age` is the number of seconds between the date of the document
+/// and the origin, i.e. the current time by default.
+/// The documents dated after the origin are not decayed.
+///
+/// It matches exactly the same documents as the underlying query.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use std::time::Duration;
+/// use tantivy::schema::{SchemaBuilder, TEXT, FAST};
+/// use tantivy::query::{RecencyBoostQuery, TermQuery};
+/// use tantivy::collector::TopCollector;
+/// use tantivy::Index;
+///
+/// # fn main() {
+/// let mut schema_builder = SchemaBuilder::default();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let published = schema_builder.add_i64_field("published", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// {
+///     let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+///     index_writer.add_document(doc!(title => "diary", published => 1_000_000i64));
+///     index_writer.add_document(doc!(title => "diary", published => 2_000_000i64));
+///     index_writer.commit().unwrap();
+/// }
+/// index.load_searchers().unwrap();
+/// let searcher = index.searcher();
+/// let one_week = Duration::from_secs(7 * 24 * 3_600);
+/// let mut query = RecencyBoostQuery::new(
+///     Box::new(TermQuery::new_text(title, "diary")),
+///     published,
+///     one_week,
+/// );
+/// query.set_origin(2_000_000i64);
+/// let mut top_collector = TopCollector::with_limit(2);
+/// searcher.search(&query, &mut top_collector).unwrap();
+/// assert_eq!(top_collector.docs()[0].doc(), 1u32);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RecencyBoostQuery {
+    query: Box<Query>,
+    date_field: Field,
+    half_life: Duration,
+    origin: i64,
+}
+
+impl RecencyBoostQuery {
+    /// Creates a `RecencyBoostQuery` decaying the score of the documents
+    /// matching `query` with their age, as given by `date_field`.
+    ///
+    /// The score of a document is halved every `half_life`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `half_life` is zero.
+    pub fn new(query: Box<Query>, date_field: Field, half_life: Duration) -> RecencyBoostQuery {
+        assert!(
+            half_life != Duration::from_secs(0),
+            "The half life of a RecencyBoostQuery must be positive."
+        );
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_else(|_| Duration::from_secs(0));
+        RecencyBoostQuery {
+            query,
+            date_field,
+            half_life,
+            origin: since_epoch.as_secs() as i64,
+        }
+    }
+
+    /// Sets the timestamp, in seconds since the Unix epoch,
+    /// from which the age of the documents is computed.
+    ///
+    /// By default, it is the time at which the query was created.
+    pub fn set_origin(&mut self, origin: i64) {
+        self.origin = origin;
+    }
+
+    /// Returns the timestamp from which the age of the documents is computed.
+    pub fn origin(&self) -> i64 {
+        self.origin
+    }
+
+    fn recency_weight(&self, weight: Box<Weight>) -> Box<Weight> {
+        let half_life = self.half_life;
+        box RecencyBoostWeight {
+            weight,
+            date_field: self.date_field,
+            decay: Decay {
+                origin: self.origin,
+                half_life_secs: half_life.as_secs() as f64
+                    + f64::from(half_life.subsec_nanos()) / 1_000_000_000f64,
+            },
+        }
+    }
+}
+
+impl Query for RecencyBoostQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+        let weight = self.query.weight(searcher, scoring_enabled)?;
+        Ok(self.recency_weight(weight))
+    }
+
+    fn shared_weight(
+        &self,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+        weight_cache: &mut WeightCache,
+    ) -> Result<Box<Weight>> {
+        let weight = self.query
+            .shared_weight(searcher, scoring_enabled, weight_cache)?;
+        Ok(self.recency_weight(weight))
+    }
+
+    fn has_stable_representation(&self) -> bool {
+        self.query.has_stable_representation()
+    }
+
+    fn normalized(&self) -> String {
+        format!(
+            "RecencyBoostQuery {{ query: {}, date_field: {:?}, half_life: {:?}, origin: {} }}",
+            self.query.normalized(),
+            self.date_field,
+            self.half_life,
+            self.origin
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Decay {
+    origin: i64,
+    half_life_secs: f64,
+}
+
+impl Decay {
+    fn age(&self, date: i64) -> i64 {
+        self.origin.saturating_sub(date).max(0)
+    }
+
+    fn factor(&self, date: i64) -> Score {
+        0.5f64.powf(self.age(date) as f64 / self.half_life_secs) as Score
+    }
+}
+
+/// Weight associated to the `RecencyBoostQuery` query.
+pub struct RecencyBoostWeight {
+    weight: Box<Weight>,
+    date_field: Field,
+    decay: Decay,
+}
+
+impl Weight for RecencyBoostWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let date_reader = reader.fast_field_reader_or_default(self.date_field)?;
+        let scorer = self.weight.scorer(reader)?;
+        Ok(box RecencyBoostScorer {
+            scorer,
+            date_reader,
+            decay: self.decay,
+        })
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        let date_reader: FastFieldReader<i64> =
+            reader.fast_field_reader_or_default(self.date_field)?;
+        let sub_explanation = self.weight.explain(reader, doc)?;
+        let date = date_reader.get(doc);
+        let factor = self.decay.factor(date);
+        let mut explanation =
+            Explanation::new("Recency decay, product of", sub_explanation.value() * factor);
+        let mut factor_explanation =
+            Explanation::new("decay, computed as 0.5 ^ (age / half_life)", factor);
+        factor_explanation.add_const("age", self.decay.age(date) as Score);
+        factor_explanation.add_const("half_life", self.decay.half_life_secs as Score);
+        explanation.add_detail(factor_explanation);
+        explanation.add_detail(sub_explanation);
+        Ok(explanation)
+    }
+
+    fn count(&self, reader: &SegmentReader) -> Result<u32> {
+        self.weight.count(reader)
+    }
+}
+
+/// Scorer associated to the `RecencyBoostQuery` query.
+pub struct RecencyBoostScorer {
+    scorer: Box<Scorer>,
+    date_reader: FastFieldReader<i64>,
+    decay: Decay,
+}
+
+impl DocSet for RecencyBoostScorer {
+    fn advance(&mut self) -> bool {
+        self.scorer.advance()
+    }
+
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        self.scorer.skip_next(target)
+    }
+
+    fn fill_buffer(&mut self, buffer: &mut [DocId]) -> usize {
+        self.scorer.fill_buffer(buffer)
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+
+    fn estimate_live(&self) -> u32 {
+        self.scorer.estimate_live()
+    }
+
+    fn append_to_bitset(&mut self, bitset: &mut BitSet) {
+        self.scorer.append_to_bitset(bitset);
+    }
+}
+
+impl Scorer for RecencyBoostScorer {
+    fn score(&mut self) -> Score {
+        let date = self.date_reader.get(self.scorer.doc());
+        self.scorer.score() * self.decay.factor(date)
+    }
+
+    fn match_cost(&self) -> f32 {
+        self.scorer.match_cost()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::RecencyBoostQuery;
+    use collector::TopCollector;
+    use query::{Query, TermQuery};
+    use schema::{SchemaBuilder, FAST, TEXT};
+    use std::time::Duration;
+    use DocAddress;
+    use Index;
+
+    #[test]
+    fn test_recency_boost_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let date_field = schema_builder.add_i64_field("date", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a", date_field => 800i64));
+            index_writer.add_document(doc!(text_field => "a", date_field => 900i64));
+            index_writer.add_document(doc!(text_field => "a", date_field => 1_100i64));
+            index_writer.add_document(doc!(text_field => "b", date_field => 1_000i64));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let term_query = || box TermQuery::new_text(text_field, "a");
+        let mut top_collector = TopCollector::with_limit(1);
+        term_query().search(&*searcher, &mut top_collector).unwrap();
+        let score = top_collector.score_docs()[0].0;
+
+        let mut query = RecencyBoostQuery::new(term_query(), date_field, Duration::from_secs(100));
+        query.set_origin(1_000);
+        let mut top_collector = TopCollector::with_limit(3);
+        query.search(&*searcher, &mut top_collector).unwrap();
+        let score_docs = top_collector.score_docs();
+        assert_eq!(
+            score_docs
+                .iter()
+                .map(|&(_, doc_address)| doc_address)
+                .collect::<Vec<_>>(),
+            vec![DocAddress(0, 2), DocAddress(0, 1), DocAddress(0, 0)]
+        );
+        assert!((score_docs[0].0 - score).abs() < 0.0001);
+        assert!((score_docs[1].0 - score / 2.0).abs() < 0.0001);
+        assert!((score_docs[2].0 - score / 4.0).abs() < 0.0001);
+        let explanation = query.explain(&*searcher, &DocAddress(0, 0)).unwrap();
+        assert!((explanation.value() - score / 4.0).abs() < 0.0001);
+        assert_eq!(query.count(&*searcher).unwrap(), 3);
+
+        let mut other_query =
+            RecencyBoostQuery::new(term_query(), date_field, Duration::from_secs(100));
+        other_query.set_origin(1_000);
+        assert_eq!(query.fingerprint(), other_query.fingerprint());
+        other_query.set_origin(2_000);
+        assert_ne!(query.fingerprint(), other_query.fingerprint());
+    }
+}
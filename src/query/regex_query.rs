@@ -0,0 +1,426 @@
+use Result;
+use DocId;
+use common::BitSet;
+use core::{InvertedIndexReader, MemoryBudget, SegmentReader, Searcher};
+use error::ErrorKind;
+use postings::TermInfo;
+use query::{BitSetDocSet, BooleanQuery, ConstScorer, EmptyScorer, Explanation, Occur, Query,
+            Scorer, TermQuery, Weight};
+use query::explanation::{does_not_match, seek_doc};
+use regex::{self, Regex};
+use regex_syntax::hir::literal::Literals;
+use regex_syntax::Parser;
+use schema::{Field, IndexRecordOption};
+use std::collections::BTreeSet;
+use std::str;
+use termdict::{TermDictionary, TermStreamer, TermStreamerBuilder};
+
+/// Default maximum number of terms a `RegexQuery` expands to,
+/// before switching to the `ExpansionStrategy::BitSet` strategy.
+pub const DEFAULT_MAX_EXPANSIONS: usize = 64;
+
+/// How a `RegexQuery` searches the documents of the matching terms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpansionStrategy {
+    /// The query is rewritten into the disjunction of the matching terms,
+    /// scored as a `BooleanQuery` of `TermQuery`s.
+    TermDisjunction,
+    /// There are more matching terms than `max_expansions`: the documents
+    /// of the matching terms are streamed into a bitset, as for the
+    /// `RangeQuery`, and are given a constant score of 1.
+    BitSet,
+}
+
+/// Matches the terms of the term dictionary against a regular expression.
+#[derive(Clone, Debug)]
+struct TermMatcher {
+    regex: Regex,
+    // literal prefix of all of the matching terms,
+    // so that the other terms are not scanned.
+    prefix: Vec<u8>,
+}
+
+impl TermMatcher {
+    /// Calls `f` on the matching terms, in sorted order,
+    /// until it returns false.
+    fn for_each_match<F>(&self, inverted_index: &InvertedIndexReader, mut f: F)
+    where
+        F: FnMut(&[u8], &TermInfo) -> bool,
+    {
+        let mut term_stream = inverted_index.terms().range().ge(&self.prefix).into_stream();
+        while term_stream.advance() {
+            let key = term_stream.key();
+            if !key.starts_with(&self.prefix) {
+                break;
+            }
+            let is_match = str::from_utf8(key)
+                .map(|text| self.regex.is_match(text))
+                .unwrap_or(false);
+            if is_match && !f(key, term_stream.value()) {
+                break;
+            }
+        }
+    }
+}
+
+/// Returns the longest common prefix of the terms matching the regular
+/// expression `pattern`, e.g. `abc` for `abc.*`, or `ab` for `ab(c|d)`.
+fn literal_prefix(pattern: &str) -> Vec<u8> {
+    match Parser::new().parse(pattern) {
+        Ok(hir) => Literals::prefixes(&hir).longest_common_prefix().to_vec(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Translates a wildcard pattern into a regular expression,
+/// and returns it together with its literal prefix.
+fn wildcard_to_regex(pattern: &str) -> (String, String) {
+    let prefix: String = pattern
+        .chars()
+        .take_while(|&c| c != '*' && c != '?')
+        .collect();
+    let mut regex_pattern = String::with_capacity(pattern.len() * 2);
+    for c in pattern.chars() {
+        match c {
+            '*' => regex_pattern.push_str(".*"),
+            '?' => regex_pattern.push('.'),
+            _ => regex_pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    (regex_pattern, prefix)
+}
+
+/// `RegexQuery` matches the documents containing a term of a text field
+/// that matches a regular expression, or a wildcard pattern.
+///
+/// The regular expression has to match the entire term, as emitted
+/// by the tokenizer of the field. Only the terms starting with the literal
+/// prefix of the pattern are scanned, e.g. the terms starting with `ab`
+/// for `ab(c|d).*`. If it has none, all of the terms of the field are.
+///
+/// The pattern is not normalized by the tokenizer of the field. In particular,
+/// for a field whose tokenizer lowercases the terms, as the default one does,
+/// the pattern has to be lowercased, e.g. `RegexQuery::wildcard(title, "harr*")`
+/// rather than `"Harr*"`.
+///
+/// If there are at most `max_expansions` matching terms in the index,
+/// the query is scored as the disjunction of these terms.
+/// Otherwise, the documents of the matching terms are collected in a bitset
+/// and are given a constant score, so that the query does not require
+/// a scorer per term. (See `ExpansionStrategy`)
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::schema::{SchemaBuilder, TEXT};
+/// use tantivy::query::{ExpansionStrategy, Query, RegexQuery};
+/// use tantivy::Index;
+///
+/// # fn main() {
+/// let mut schema_builder = SchemaBuilder::default();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// {
+///     let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+///     index_writer.add_document(doc!(title => "The Name of the Wind"));
+///     index_writer.add_document(doc!(title => "The Wise Man's Fear"));
+///     index_writer.commit().unwrap();
+/// }
+/// index.load_searchers().unwrap();
+/// let searcher = index.searcher();
+/// let query = RegexQuery::wildcard(title, "w*");
+/// assert_eq!(query.count(&*searcher).unwrap(), 2);
+/// let mut query = RegexQuery::new(title, "n[a-z]+").unwrap();
+/// assert_eq!(query.count(&*searcher).unwrap(), 1);
+/// query.set_max_expansions(0);
+/// assert_eq!(query.expansion_strategy(&*searcher).unwrap(), ExpansionStrategy::BitSet);
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct RegexQuery {
+    field: Field,
+    term_matcher: TermMatcher,
+    max_expansions: usize,
+}
+
+impl RegexQuery {
+    /// Creates a `RegexQuery` matching the terms of the field
+    /// matching the regular expression `pattern` entirely.
+    ///
+    /// Returns an error if the pattern is not a valid regular expression.
+    pub fn new(field: Field, pattern: &str) -> Result<RegexQuery> {
+        RegexQuery::with_prefix(field, pattern, &literal_prefix(pattern))
+    }
+
+    /// Creates a `RegexQuery` matching the terms of the field matching
+    /// the wildcard pattern `pattern`, where `*` stands for any sequence
+    /// of characters and `?` for any character.
+    pub fn wildcard(field: Field, pattern: &str) -> RegexQuery {
+        let (regex_pattern, prefix) = wildcard_to_regex(pattern);
+        RegexQuery::with_prefix(field, &regex_pattern, prefix.as_bytes())
+            .expect("The regular expression of a wildcard pattern is valid")
+    }
+
+    fn with_prefix(field: Field, pattern: &str, prefix: &[u8]) -> Result<RegexQuery> {
+        let regex = Regex::new(&format!("^(?:{})$", pattern)).map_err(|err| {
+            let msg = format!("Invalid regular expression {:?}: {}", pattern, err);
+            ErrorKind::InvalidArgument(msg)
+        })?;
+        Ok(RegexQuery {
+            field,
+            term_matcher: TermMatcher {
+                regex,
+                prefix: prefix.to_vec(),
+            },
+            max_expansions: DEFAULT_MAX_EXPANSIONS,
+        })
+    }
+
+    /// Sets the maximum number of terms the query is expanded to.
+    ///
+    /// Above it, the documents of the matching terms are collected
+    /// in a bitset. It is `DEFAULT_MAX_EXPANSIONS` by default.
+    pub fn set_max_expansions(&mut self, max_expansions: usize) {
+        self.max_expansions = max_expansions;
+    }
+
+    /// Returns the maximum number of terms the query is expanded to.
+    pub fn max_expansions(&self) -> usize {
+        self.max_expansions
+    }
+
+    /// Returns the distinct matching terms of the segments of the searcher,
+    /// stopping as soon as there are more than `max_expansions` of them.
+    fn expanded_terms(&self, searcher: &Searcher) -> Result<BTreeSet<String>> {
+        let mut terms = BTreeSet::new();
+        for segment_reader in searcher.segment_readers() {
+            if !segment_reader.has_inverted_index(self.field) {
+                continue;
+            }
+            let inverted_index = segment_reader.inverted_index(self.field)?;
+            self.term_matcher.for_each_match(&inverted_index, |key, _| {
+                if let Ok(text) = str::from_utf8(key) {
+                    terms.insert(text.to_string());
+                }
+                terms.len() <= self.max_expansions
+            });
+            if terms.len() > self.max_expansions {
+                break;
+            }
+        }
+        Ok(terms)
+    }
+
+    /// Returns the strategy used to search the index of the searcher,
+    /// depending on its number of matching terms.
+    pub fn expansion_strategy(&self, searcher: &Searcher) -> Result<ExpansionStrategy> {
+        if self.expanded_terms(searcher)?.len() > self.max_expansions {
+            Ok(ExpansionStrategy::BitSet)
+        } else {
+            Ok(ExpansionStrategy::TermDisjunction)
+        }
+    }
+}
+
+impl Query for RegexQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+        let terms = self.expanded_terms(searcher)?;
+        let expansion = if terms.len() > self.max_expansions {
+            Expansion::BitSet {
+                field: self.field,
+                term_matcher: self.term_matcher.clone(),
+                memory_budget: searcher.memory_budget().clone(),
+            }
+        } else {
+            let subqueries: Vec<(Occur, Box<Query>)> = terms
+                .iter()
+                .map(|text| {
+                    let term_query: Box<Query> = box TermQuery::new_text(self.field, text);
+                    (Occur::Should, term_query)
+                })
+                .collect();
+            let weight = BooleanQuery::from(subqueries).weight(searcher, scoring_enabled)?;
+            Expansion::TermDisjunction(weight)
+        };
+        Ok(box RegexWeight { expansion })
+    }
+}
+
+enum Expansion {
+    TermDisjunction(Box<Weight>),
+    BitSet {
+        field: Field,
+        term_matcher: TermMatcher,
+        memory_budget: MemoryBudget,
+    },
+}
+
+/// Weight associated to the `RegexQuery` query.
+pub struct RegexWeight {
+    expansion: Expansion,
+}
+
+impl RegexWeight {
+    /// Returns the strategy chosen for the query.
+    pub fn expansion_strategy(&self) -> ExpansionStrategy {
+        match self.expansion {
+            Expansion::TermDisjunction(_) => ExpansionStrategy::TermDisjunction,
+            Expansion::BitSet { .. } => ExpansionStrategy::BitSet,
+        }
+    }
+}
+
+impl Weight for RegexWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let (field, term_matcher, memory_budget) = match self.expansion {
+            Expansion::TermDisjunction(ref weight) => {
+                return weight.scorer(reader);
+            }
+            Expansion::BitSet {
+                ref field,
+                ref term_matcher,
+                ref memory_budget,
+            } => (*field, term_matcher, memory_budget),
+        };
+        if !reader.has_inverted_index(field) {
+            return Ok(box EmptyScorer);
+        }
+        let max_doc = reader.max_doc();
+        let num_bitset_bytes = (max_doc as usize + 63) / 64 * 8;
+        memory_budget.allocate(num_bitset_bytes)?;
+        let mut doc_bitset = BitSet::with_max_value(max_doc);
+        let delete_bitset = reader.delete_bitset();
+        let inverted_index = reader.inverted_index(field)?;
+        let mut postings_result = Ok(());
+        term_matcher.for_each_match(&inverted_index, |_, term_info| {
+            let mut block_segment_postings = match inverted_index
+                .read_block_postings_from_terminfo(term_info, IndexRecordOption::Basic)
+            {
+                Ok(block_segment_postings) => block_segment_postings,
+                Err(err) => {
+                    postings_result = Err(err);
+                    return false;
+                }
+            };
+            // the block postings do not skip the deleted (or masked) documents.
+            while block_segment_postings.advance() {
+                for &doc in block_segment_postings.docs() {
+                    if !delete_bitset.is_deleted(doc) {
+                        doc_bitset.insert(doc);
+                    }
+                }
+            }
+            true
+        });
+        postings_result?;
+        let doc_bitset = BitSetDocSet::from(doc_bitset);
+        Ok(box ConstScorer::new(doc_bitset))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        match self.expansion {
+            Expansion::TermDisjunction(ref weight) => {
+                let sub_explanation = weight.explain(reader, doc)?;
+                let mut explanation = Explanation::new(
+                    "RegexQuery, expanded to a disjunction of terms",
+                    sub_explanation.value(),
+                );
+                explanation.add_detail(sub_explanation);
+                Ok(explanation)
+            }
+            Expansion::BitSet { .. } => {
+                let mut scorer = self.scorer(reader)?;
+                if !seek_doc(&mut scorer, doc) {
+                    return Err(does_not_match(doc));
+                }
+                Ok(Explanation::new(
+                    "RegexQuery, too many terms: bitset of their documents",
+                    scorer.score(),
+                ))
+            }
+        }
+    }
+
+    fn count(&self, reader: &SegmentReader) -> Result<u32> {
+        match self.expansion {
+            Expansion::TermDisjunction(ref weight) => weight.count(reader),
+            Expansion::BitSet { .. } => Ok(self.scorer(reader)?.count()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{literal_prefix, wildcard_to_regex, ExpansionStrategy, RegexQuery};
+    use query::Query;
+    use schema::{SchemaBuilder, TEXT};
+    use DocAddress;
+    use Index;
+
+    #[test]
+    fn test_wildcard_to_regex() {
+        assert_eq!(
+            wildcard_to_regex("ab?c*"),
+            ("ab.c.*".to_string(), "ab".to_string())
+        );
+        assert_eq!(
+            wildcard_to_regex("*a.b"),
+            (".*a\\.b".to_string(), "".to_string())
+        );
+    }
+
+    #[test]
+    fn test_literal_prefix() {
+        assert_eq!(literal_prefix("abc.*"), b"abc".to_vec());
+        assert_eq!(literal_prefix("ab(c|d)[0-9]"), b"ab".to_vec());
+        assert_eq!(literal_prefix("abc?"), b"ab".to_vec());
+        assert_eq!(literal_prefix("été.*"), "été".as_bytes().to_vec());
+        assert_eq!(literal_prefix("ab|cd"), Vec::<u8>::new());
+        assert_eq!(literal_prefix(".*abc"), Vec::<u8>::new());
+        assert_eq!(literal_prefix("(?i)abc"), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_regex_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0..10 {
+                index_writer.add_document(doc!(text_field => format!("abc{} xyz", i)));
+            }
+            index_writer.commit().unwrap();
+            index_writer.add_document(doc!(text_field => "abd abc1"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+
+        let mut query = RegexQuery::new(text_field, "ab.[0-9]").unwrap();
+        assert_eq!(query.count(&*searcher).unwrap(), 11);
+        assert_eq!(
+            query.expansion_strategy(&*searcher).unwrap(),
+            ExpansionStrategy::TermDisjunction
+        );
+        let explanation = query.explain(&*searcher, &DocAddress(0, 0)).unwrap();
+        assert!(explanation.description().contains("disjunction"));
+
+        query.set_max_expansions(5);
+        assert_eq!(
+            query.expansion_strategy(&*searcher).unwrap(),
+            ExpansionStrategy::BitSet
+        );
+        assert_eq!(query.count(&*searcher).unwrap(), 11);
+        let explanation = query.explain(&*searcher, &DocAddress(0, 0)).unwrap();
+        assert!(explanation.description().contains("bitset"));
+        assert_eq!(explanation.value(), 1.0);
+
+        assert_eq!(RegexQuery::wildcard(text_field, "ab?").count(&*searcher).unwrap(), 1);
+        assert_eq!(RegexQuery::wildcard(text_field, "*z").count(&*searcher).unwrap(), 10);
+        assert_eq!(RegexQuery::wildcard(text_field, "abc1*").count(&*searcher).unwrap(), 2);
+        assert_eq!(RegexQuery::wildcard(text_field, "q*").count(&*searcher).unwrap(), 0);
+        assert!(RegexQuery::new(text_field, "ab(").is_err());
+    }
+}
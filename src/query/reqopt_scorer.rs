@@ -60,6 +60,10 @@ where
     fn size_hint(&self) -> u32 {
         self.req_scorer.size_hint()
     }
+
+    fn estimate_live(&self) -> u32 {
+        self.req_scorer.estimate_live()
+    }
 }
 
 impl<TReqScorer, TOptScorer, TScoreCombiner> Scorer
@@ -97,6 +101,10 @@ where
         self.score_cache = Some(score);
         score
     }
+
+    fn match_cost(&self) -> f32 {
+        self.req_scorer.match_cost()
+    }
 }
 
 #[cfg(test)]
@@ -135,42 +143,42 @@ mod tests {
         {
             assert!(reqoptscorer.advance());
             assert_eq!(reqoptscorer.doc(), 1);
-            assert_eq!(reqoptscorer.score(), 2f32);
+            assert_eq!(reqoptscorer.score(), 2.0);
         }
         {
             assert!(reqoptscorer.advance());
             assert_eq!(reqoptscorer.doc(), 3);
-            assert_eq!(reqoptscorer.score(), 1f32);
+            assert_eq!(reqoptscorer.score(), 1.0);
         }
         {
             assert!(reqoptscorer.advance());
             assert_eq!(reqoptscorer.doc(), 7);
-            assert_eq!(reqoptscorer.score(), 2f32);
+            assert_eq!(reqoptscorer.score(), 2.0);
         }
         {
             assert!(reqoptscorer.advance());
             assert_eq!(reqoptscorer.doc(), 8);
-            assert_eq!(reqoptscorer.score(), 1f32);
+            assert_eq!(reqoptscorer.score(), 1.0);
         }
         {
             assert!(reqoptscorer.advance());
             assert_eq!(reqoptscorer.doc(), 9);
-            assert_eq!(reqoptscorer.score(), 1f32);
+            assert_eq!(reqoptscorer.score(), 1.0);
         }
         {
             assert!(reqoptscorer.advance());
             assert_eq!(reqoptscorer.doc(), 10);
-            assert_eq!(reqoptscorer.score(), 1f32);
+            assert_eq!(reqoptscorer.score(), 1.0);
         }
         {
             assert!(reqoptscorer.advance());
             assert_eq!(reqoptscorer.doc(), 13);
-            assert_eq!(reqoptscorer.score(), 1f32);
+            assert_eq!(reqoptscorer.score(), 1.0);
         }
         {
             assert!(reqoptscorer.advance());
             assert_eq!(reqoptscorer.doc(), 15);
-            assert_eq!(reqoptscorer.score(), 2f32);
+            assert_eq!(reqoptscorer.score(), 2.0);
         }
         assert!(!reqoptscorer.advance());
     }
@@ -3,7 +3,7 @@ use Score;
 use collector::Collector;
 use docset::{DocSet, SkipResult};
 use common::BitSet;
-use std::ops::DerefMut;
+use std::ops::{Deref, DerefMut};
 use downcast;
 
 /// Scored set of documents matching a query within a specific segment.
@@ -15,6 +15,22 @@ pub trait Scorer: downcast::Any + DocSet + 'static {
     /// This method will perform a bit of computation and is not cached.
     fn score(&mut self) -> Score;
 
+    /// Returns the estimated cost of confirming that a document
+    /// matches, once all of its terms were found.
+    ///
+    /// It is zero for the scorers that match as soon as their
+    /// postings do, e.g. the `TermScorer`, and positive for the ones
+    /// verifying each candidate document, e.g. against the positions
+    /// of its terms. Intersections skip their cheapest scorers to a
+    /// candidate document first. (See `Intersection::from_scorers`)
+    ///
+    /// This only orders the scorers: there is no separate verification
+    /// step, so a scorer such as the `PhraseScorer` still checks the
+    /// positions of the documents it goes through while it is skipped.
+    fn match_cost(&self) -> f32 {
+        0f32
+    }
+
     /// Consumes the complete `DocSet` and
     /// push the scored documents to the collector.
     fn collect(&mut self, collector: &mut Collector) {
@@ -34,6 +50,10 @@ impl Scorer for Box<Scorer> {
         self.deref_mut().score()
     }
 
+    fn match_cost(&self) -> f32 {
+        self.deref().match_cost()
+    }
+
     fn collect(&mut self, collector: &mut Collector) {
         let scorer = self.deref_mut();
         scorer.collect(collector);
@@ -62,7 +82,7 @@ impl DocSet for EmptyScorer {
 
 impl Scorer for EmptyScorer {
     fn score(&mut self) -> Score {
-        0f32
+        0.0
     }
 }
 
@@ -75,6 +95,7 @@ impl Scorer for EmptyScorer {
 pub struct ConstScorer<TDocSet: DocSet> {
     docset: TDocSet,
     score: Score,
+    match_cost: f32,
 }
 
 impl<TDocSet: DocSet> ConstScorer<TDocSet> {
@@ -82,7 +103,8 @@ impl<TDocSet: DocSet> ConstScorer<TDocSet> {
     pub fn new(docset: TDocSet) -> ConstScorer<TDocSet> {
         ConstScorer {
             docset,
-            score: 1f32,
+            score: 1.0,
+            match_cost: 0f32,
         }
     }
 
@@ -90,6 +112,12 @@ impl<TDocSet: DocSet> ConstScorer<TDocSet> {
     pub fn set_score(&mut self, score: Score) {
         self.score = score;
     }
+
+    /// Sets the match cost of the scorer, e.g. to the match cost
+    /// of the wrapped scorer. (See `Scorer::match_cost`)
+    pub fn set_match_cost(&mut self, match_cost: f32) {
+        self.match_cost = match_cost;
+    }
 }
 
 impl<TDocSet: DocSet> DocSet for ConstScorer<TDocSet> {
@@ -113,6 +141,10 @@ impl<TDocSet: DocSet> DocSet for ConstScorer<TDocSet> {
         self.docset.size_hint()
     }
 
+    fn estimate_live(&self) -> u32 {
+        self.docset.estimate_live()
+    }
+
     fn append_to_bitset(&mut self, bitset: &mut BitSet) {
         self.docset.append_to_bitset(bitset);
     }
@@ -120,13 +152,18 @@ impl<TDocSet: DocSet> DocSet for ConstScorer<TDocSet> {
 
 impl<TDocSet: DocSet + 'static> Scorer for ConstScorer<TDocSet> {
     fn score(&mut self) -> Score {
-        1f32
+        self.score
+    }
+
+    fn match_cost(&self) -> f32 {
+        self.match_cost
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::EmptyScorer;
+    use super::{ConstScorer, EmptyScorer, Scorer};
+    use query::VecDocSet;
     use DocSet;
 
     #[test]
@@ -135,6 +172,15 @@ mod tests {
         assert!(!empty_scorer.advance());
     }
 
+    #[test]
+    fn test_const_scorer_set_score() {
+        let mut const_scorer = ConstScorer::new(VecDocSet::from(vec![1, 3]));
+        assert!(const_scorer.advance());
+        assert_eq!(const_scorer.score(), 1.0);
+        const_scorer.set_score(2.5);
+        assert_eq!(const_scorer.score(), 2.5);
+    }
+
     #[test]
     #[should_panic]
     fn test_empty_scorer_panic_on_doc_call() {
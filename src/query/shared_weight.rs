@@ -0,0 +1,577 @@
+use query::{Query, Scorer, Weight};
+use core::{SegmentId, SegmentReader};
+use core::Searcher;
+use docset::{DocSet, SkipResult};
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::rc::{Rc, Weak};
+use std::usize;
+use DocId;
+use query::Explanation;
+use Result;
+use Score;
+
+/// Maximum number of documents buffered for the handles
+/// lagging behind the others.
+const MAX_BUFFERED_DOCS: usize = 1_024;
+
+/// Keeps track of the weights created for a query tree,
+/// so that identical subqueries share the same `Weight`.
+///
+/// Two subqueries are considered identical if they have the
+/// same normalized representation (See `Query::normalized`),
+/// and their weight are created with scoring enabled (or disabled)
+/// in both cases.
+///
+/// The weight cache is populated while the weight of
+/// a query is being created, and it is dropped right after.
+#[derive(Default)]
+pub struct WeightCache {
+    weights: HashMap<(String, bool), Rc<SharedWeightInner>>,
+}
+
+impl WeightCache {
+    /// Returns the weight associated to `query`.
+    ///
+    /// If an identical query was already encountered,
+    /// the resulting weight is shared with the previous occurence.
+    pub fn weight(
+        &mut self,
+        query: &Query,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+    ) -> Result<Box<Weight>> {
+        let fingerprint = (query.normalized(), scoring_enabled);
+        if let Some(shared_weight_inner) = self.weights.get(&fingerprint) {
+            shared_weight_inner
+                .num_handles
+                .set(shared_weight_inner.num_handles.get() + 1);
+            return Ok(box SharedWeight {
+                inner: Rc::clone(shared_weight_inner),
+            });
+        }
+        let weight = query.shared_weight(searcher, scoring_enabled, self)?;
+        let shared_weight_inner = Rc::new(SharedWeightInner {
+            weight,
+            scoring_enabled,
+            num_handles: Cell::new(1),
+            scorers: RefCell::new(HashMap::new()),
+        });
+        self.weights
+            .insert(fingerprint, Rc::clone(&shared_weight_inner));
+        Ok(box SharedWeight {
+            inner: shared_weight_inner,
+        })
+    }
+}
+
+struct SharedWeightInner {
+    weight: Box<Weight>,
+    scoring_enabled: bool,
+    // number of references to this weight within the query tree.
+    num_handles: Cell<usize>,
+    scorers: RefCell<HashMap<SegmentId, Weak<RefCell<SharedScorerState>>>>,
+}
+
+/// Handle over a `Weight` that may appear several times
+/// within the same query tree.
+///
+/// If the weight is referenced only once, the scorer of the
+/// underlying weight is returned as is.
+///
+/// Otherwise, all of the scorers built by the different handles
+/// for a given segment are backed by a single underlying scorer.
+/// The underlying scorer is advanced once per document and its score
+/// is computed once per document.
+///
+/// The documents are buffered until all of the handles have consumed them,
+/// so that handles may advance at different paces.
+/// At most `MAX_BUFFERED_DOCS` documents are buffered: a handle reading
+/// ahead of this limit detaches from the shared scorer, and continues
+/// on a scorer of its own.
+struct SharedWeight {
+    inner: Rc<SharedWeightInner>,
+}
+
+impl SharedWeight {
+    fn shared_state(&self, reader: &SegmentReader) -> Result<Rc<RefCell<SharedScorerState>>> {
+        let segment_id = reader.segment_id();
+        let mut scorers = self.inner.scorers.borrow_mut();
+        if let Some(state) = scorers.get(&segment_id).and_then(Weak::upgrade) {
+            if state.borrow().next_handle_id < state.borrow().cursors.len() {
+                return Ok(state);
+            }
+        }
+        // Every single reference to this weight is expected to
+        // build one scorer for the segment.
+        let num_handles = self.inner.num_handles.get();
+        let scorer = self.inner.weight.scorer(reader)?;
+        let state = Rc::new(RefCell::new(SharedScorerState {
+            size_hint: scorer.size_hint(),
+            estimate_live: scorer.estimate_live(),
+            scorer,
+            scoring_enabled: self.inner.scoring_enabled,
+            buffer: VecDeque::new(),
+            offset: 0,
+            cursors: vec![0; num_handles],
+            next_handle_id: 0,
+            finished: false,
+        }));
+        scorers.insert(segment_id, Rc::downgrade(&state));
+        Ok(state)
+    }
+}
+
+impl Weight for SharedWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        if self.inner.num_handles.get() == 1 {
+            return self.inner.weight.scorer(reader);
+        }
+        let state = self.shared_state(reader)?;
+        let handle_id = {
+            let mut state_mut = state.borrow_mut();
+            state_mut.next_handle_id += 1;
+            state_mut.next_handle_id - 1
+        };
+        Ok(box SharedScorer {
+            inner: Rc::clone(&self.inner),
+            reader: reader.clone(),
+            state,
+            handle_id,
+            own_scorer: None,
+            doc: 0,
+            score: 0.0,
+        })
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        self.inner.weight.explain(reader, doc)
+    }
+
+    fn count(&self, reader: &SegmentReader) -> Result<u32> {
+        self.inner.weight.count(reader)
+    }
+}
+
+struct SharedScorerState {
+    scorer: Box<Scorer>,
+    scoring_enabled: bool,
+    size_hint: u32,
+    estimate_live: u32,
+    // (doc, score) emitted by the underlying scorer that have
+    // not been consumed by all of the handles yet.
+    buffer: VecDeque<(DocId, Score)>,
+    // number of elements that were removed from the front of the buffer.
+    offset: usize,
+    // for each handle, the ordinal of the next element it will read.
+    //
+    // Handles that have not been created yet are at position 0, so that
+    // they do not miss any document. Detached handles are at `usize::MAX`.
+    cursors: Vec<usize>,
+    next_handle_id: usize,
+    finished: bool,
+}
+
+impl SharedScorerState {
+    /// Returns true iff reading the next document of the handle
+    /// requires to buffer more than `MAX_BUFFERED_DOCS` documents.
+    fn is_buffer_full(&self, handle_id: usize) -> bool {
+        self.buffer.len() >= MAX_BUFFERED_DOCS
+            && self.cursors[handle_id] - self.offset == self.buffer.len()
+    }
+
+    fn next(&mut self, handle_id: usize) -> Option<(DocId, Score)> {
+        let buffer_pos = self.cursors[handle_id] - self.offset;
+        if buffer_pos == self.buffer.len() {
+            if self.finished || !self.scorer.advance() {
+                self.finished = true;
+                return None;
+            }
+            let doc = self.scorer.doc();
+            let score = if self.scoring_enabled {
+                self.scorer.score()
+            } else {
+                1.0
+            };
+            self.buffer.push_back((doc, score));
+        }
+        let doc_score = self.buffer[buffer_pos];
+        self.cursors[handle_id] += 1;
+        self.release_consumed_docs();
+        Some(doc_score)
+    }
+
+    /// Moves the cursor of the handle to the first buffered document
+    /// greater or equal to `target`, or to the end of the buffer.
+    fn seek(&mut self, handle_id: usize, target: DocId) {
+        let mut start = self.cursors[handle_id] - self.offset;
+        let mut end = self.buffer.len();
+        while start < end {
+            let mid = start + (end - start) / 2;
+            if self.buffer[mid].0 < target {
+                start = mid + 1;
+            } else {
+                end = mid;
+            }
+        }
+        self.cursors[handle_id] = self.offset + start;
+        self.release_consumed_docs();
+    }
+
+    fn detach(&mut self, handle_id: usize) {
+        self.cursors[handle_id] = usize::MAX;
+        self.release_consumed_docs();
+    }
+
+    fn release_consumed_docs(&mut self) {
+        let min_cursor = self.cursors.iter().cloned().min().unwrap_or(usize::MAX);
+        while self.offset < min_cursor && self.buffer.pop_front().is_some() {
+            self.offset += 1;
+        }
+    }
+}
+
+/// Scorer reading the documents of a `SharedScorerState`,
+/// at its own pace.
+///
+/// Skipping within the buffered documents is done by binary search.
+/// Past the buffer, the documents are read one by one,
+/// as other handles may still require the skipped documents.
+struct SharedScorer {
+    inner: Rc<SharedWeightInner>,
+    reader: SegmentReader,
+    state: Rc<RefCell<SharedScorerState>>,
+    handle_id: usize,
+    // scorer of the handle, once it detached from the shared scorer.
+    own_scorer: Option<Box<Scorer>>,
+    doc: DocId,
+    score: Score,
+}
+
+impl SharedScorer {
+    fn advance_shared(&mut self) -> bool {
+        if let Some((doc, score)) = self.state.borrow_mut().next(self.handle_id) {
+            self.doc = doc;
+            self.score = score;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Detaches the handle from the shared scorer, and positions
+    /// its own scorer on the document following the current one.
+    ///
+    /// Returns `None`, and leaves the handle attached, if the scorer
+    /// could not be created.
+    fn detach(&mut self) -> Option<bool> {
+        let mut scorer = match self.inner.weight.scorer(&self.reader) {
+            Ok(scorer) => scorer,
+            Err(e) => {
+                warn!("Failed to detach from the shared scorer {:?}", e);
+                return None;
+            }
+        };
+        self.state.borrow_mut().detach(self.handle_id);
+        let has_next = scorer.skip_next(self.doc + 1) != SkipResult::End;
+        self.own_scorer = Some(scorer);
+        Some(has_next)
+    }
+
+    fn read_own_scorer(&mut self) {
+        if let Some(ref mut scorer) = self.own_scorer {
+            self.doc = scorer.doc();
+            self.score = if self.inner.scoring_enabled {
+                scorer.score()
+            } else {
+                1.0
+            };
+        }
+    }
+}
+
+impl DocSet for SharedScorer {
+    fn advance(&mut self) -> bool {
+        let own_has_next = self.own_scorer.as_mut().map(|scorer| scorer.advance());
+        let has_next = match own_has_next {
+            Some(has_next) => has_next,
+            None => {
+                if !self.state.borrow().is_buffer_full(self.handle_id) {
+                    return self.advance_shared();
+                }
+                match self.detach() {
+                    Some(has_next) => has_next,
+                    None => return self.advance_shared(),
+                }
+            }
+        };
+        if has_next {
+            self.read_own_scorer();
+        }
+        has_next
+    }
+
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        let own_skip_result = self.own_scorer
+            .as_mut()
+            .map(|scorer| scorer.skip_next(target));
+        if let Some(skip_result) = own_skip_result {
+            if skip_result != SkipResult::End {
+                self.read_own_scorer();
+            }
+            return skip_result;
+        }
+        self.state.borrow_mut().seek(self.handle_id, target);
+        if !self.advance() {
+            return SkipResult::End;
+        }
+        while self.doc < target {
+            if !self.advance() {
+                return SkipResult::End;
+            }
+        }
+        if self.doc == target {
+            SkipResult::Reached
+        } else {
+            SkipResult::OverStep
+        }
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.state.borrow().size_hint
+    }
+
+    fn estimate_live(&self) -> u32 {
+        self.state.borrow().estimate_live
+    }
+}
+
+impl Scorer for SharedScorer {
+    fn score(&mut self) -> Score {
+        self.score
+    }
+
+    fn match_cost(&self) -> f32 {
+        self.state.borrow().scorer.match_cost()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use collector::tests::TestCollector;
+    use collector::TopCollector;
+    use docset::SkipResult;
+    use query::{BooleanQuery, Occur, TermQuery};
+    use schema::{IndexRecordOption, SchemaBuilder, Term, TEXT};
+    use std::fmt;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use Index;
+
+    /// Wraps a `TermQuery` and counts the number of calls to `.advance()`
+    /// of its scorers.
+    struct CountingQuery {
+        term_query: TermQuery,
+        num_advance: Arc<AtomicUsize>,
+    }
+
+    impl fmt::Debug for CountingQuery {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "Counting({:?})", self.term_query)
+        }
+    }
+
+    impl Query for CountingQuery {
+        fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+            Ok(box CountingWeight {
+                weight: self.term_query.weight(searcher, scoring_enabled)?,
+                num_advance: Arc::clone(&self.num_advance),
+            })
+        }
+    }
+
+    struct CountingWeight {
+        weight: Box<Weight>,
+        num_advance: Arc<AtomicUsize>,
+    }
+
+    impl Weight for CountingWeight {
+        fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+            Ok(box CountingScorer {
+                scorer: self.weight.scorer(reader)?,
+                num_advance: Arc::clone(&self.num_advance),
+            })
+        }
+    }
+
+    struct CountingScorer {
+        scorer: Box<Scorer>,
+        num_advance: Arc<AtomicUsize>,
+    }
+
+    impl DocSet for CountingScorer {
+        fn advance(&mut self) -> bool {
+            self.num_advance.fetch_add(1, Ordering::SeqCst);
+            self.scorer.advance()
+        }
+
+        fn doc(&self) -> DocId {
+            self.scorer.doc()
+        }
+
+        fn size_hint(&self) -> u32 {
+            self.scorer.size_hint()
+        }
+    }
+
+    impl Scorer for CountingScorer {
+        fn score(&mut self) -> Score {
+            self.scorer.score()
+        }
+    }
+
+    fn create_index() -> (Index, ::schema::Field) {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a b"));
+            index_writer.add_document(doc!(text_field => "a c"));
+            index_writer.add_document(doc!(text_field => "b c"));
+            index_writer.add_document(doc!(text_field => "a b c"));
+            index_writer.add_document(doc!(text_field => "a"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        (index, text_field)
+    }
+
+    #[test]
+    fn test_shared_subquery_advances_once() {
+        let (index, text_field) = create_index();
+        let num_advance = Arc::new(AtomicUsize::new(0));
+        let counting_query = || -> Box<Query> {
+            box CountingQuery {
+                term_query: TermQuery::new(
+                    Term::from_field_text(text_field, "a"),
+                    IndexRecordOption::WithFreqs,
+                ),
+                num_advance: Arc::clone(&num_advance),
+            }
+        };
+        let term_query = |text: &str| -> Box<Query> {
+            box TermQuery::new(
+                Term::from_field_text(text_field, text),
+                IndexRecordOption::WithFreqs,
+            )
+        };
+        let left: Box<Query> = box BooleanQuery::from(vec![
+            (Occur::Should, counting_query()),
+            (Occur::Should, term_query("b")),
+        ]);
+        let right: Box<Query> = box BooleanQuery::from(vec![
+            (Occur::Should, counting_query()),
+            (Occur::Should, term_query("c")),
+        ]);
+        let query = BooleanQuery::from(vec![(Occur::Should, left), (Occur::Should, right)]);
+        let searcher = index.searcher();
+        let mut test_collector = TestCollector::default();
+        searcher.search(&query, &mut test_collector).unwrap();
+        assert_eq!(test_collector.docs(), vec![0, 1, 2, 3, 4]);
+        // 4 docs contain "a", plus the final call returning false.
+        assert_eq!(num_advance.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn test_shared_subquery_same_scores() {
+        let (index, text_field) = create_index();
+        let searcher = index.searcher();
+        let term_query = |text: &str, index_record_option: IndexRecordOption| -> Box<Query> {
+            box TermQuery::new(Term::from_field_text(text_field, text), index_record_option)
+        };
+        // The two "a" term queries only differ by their `IndexRecordOption`,
+        // so they are not shared, but yield the same scores.
+        let query_with_option = |right_a_option: IndexRecordOption| {
+            let left: Box<Query> = box BooleanQuery::from(vec![
+                (Occur::Must, term_query("a", IndexRecordOption::WithFreqs)),
+                (Occur::Must, term_query("b", IndexRecordOption::WithFreqs)),
+            ]);
+            let right: Box<Query> = box BooleanQuery::from(vec![
+                (Occur::Must, term_query("a", right_a_option)),
+                (Occur::Must, term_query("c", IndexRecordOption::WithFreqs)),
+            ]);
+            BooleanQuery::from(vec![(Occur::Should, left), (Occur::Should, right)])
+        };
+        let score_docs = |query: &Query| {
+            let mut top_collector = TopCollector::with_limit(10);
+            searcher.search(query, &mut top_collector).unwrap();
+            top_collector.score_docs()
+        };
+        let shared = score_docs(&query_with_option(IndexRecordOption::WithFreqs));
+        let not_shared = score_docs(&query_with_option(IndexRecordOption::WithFreqsAndPositions));
+        assert_eq!(shared.len(), 3);
+        assert_eq!(shared, not_shared);
+    }
+
+    #[test]
+    fn test_shared_subquery_bounded_buffer() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0..2_500 {
+                if i % 2 == 0 {
+                    index_writer.add_document(doc!(text_field => "a b"));
+                } else {
+                    index_writer.add_document(doc!(text_field => "a"));
+                }
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let term_query = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let mut weight_cache = WeightCache::default();
+        let leading_weight = weight_cache.weight(&term_query, &*searcher, true).unwrap();
+        let lagging_weight = weight_cache.weight(&term_query, &*searcher, true).unwrap();
+        let segment_reader = searcher.segment_reader(0);
+        let mut leading_scorer = leading_weight.scorer(segment_reader).unwrap();
+        let mut lagging_scorer = lagging_weight.scorer(segment_reader).unwrap();
+
+        // the leading handle detaches once `MAX_BUFFERED_DOCS` documents
+        // are buffered for the lagging handle.
+        let mut score_docs = vec![];
+        while leading_scorer.advance() {
+            score_docs.push((leading_scorer.doc(), leading_scorer.score()));
+        }
+        assert_eq!(score_docs.len(), 2_500);
+        assert!(score_docs.iter().enumerate().all(|(i, &(doc, _))| doc == i as DocId));
+
+        assert!(lagging_scorer.advance());
+        assert_eq!(lagging_scorer.doc(), 0);
+        assert_eq!(lagging_scorer.score(), score_docs[0].1);
+        // within the buffer
+        assert_eq!(lagging_scorer.skip_next(1_001), SkipResult::Reached);
+        assert_eq!(lagging_scorer.score(), score_docs[1_001].1);
+        // past the buffer
+        assert_eq!(lagging_scorer.skip_next(2_000), SkipResult::Reached);
+        assert_eq!(lagging_scorer.score(), score_docs[2_000].1);
+        let mut num_docs = 1;
+        while lagging_scorer.advance() {
+            assert_eq!(lagging_scorer.score(), score_docs[lagging_scorer.doc() as usize].1);
+            num_docs += 1;
+        }
+        assert_eq!(num_docs, 500);
+    }
+}
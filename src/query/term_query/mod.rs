@@ -18,8 +18,10 @@ mod tests {
     use schema::*;
     use schema::IndexRecordOption;
     use fastfield::FastFieldReader;
+    use Score;
+    use DocAddress;
 
-    fn abs_diff(left: f32, right: f32) -> f32 {
+    fn abs_diff(left: Score, right: Score) -> Score {
         (right - left).abs()
     }
 
@@ -50,7 +52,7 @@ mod tests {
         let mut term_scorer = term_weight.scorer(segment_reader).unwrap();
         assert!(term_scorer.advance());
         assert_eq!(term_scorer.doc(), 0);
-        assert_eq!(term_scorer.score(), 0.30685282);
+        assert_eq!(term_scorer.score() as f32, 0.30685282);
     }
 
     #[test]
@@ -65,7 +67,42 @@ mod tests {
             postings: left,
         };
         left_scorer.advance();
-        assert!(abs_diff(left_scorer.score(), 0.15342641) < 0.001f32);
+        assert!(abs_diff(left_scorer.score(), 0.15342641) < 0.001);
     }
 
+    #[test]
+    pub fn test_term_query_explain() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a b a"));
+            index_writer.add_document(doc!(text_field => "b"));
+            assert!(index_writer.commit().is_ok());
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let term_query = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let explanation = term_query.explain(&searcher, &DocAddress(0, 0)).unwrap();
+        assert_eq!(
+            explanation.description(),
+            "TermQuery(text:\"a\"), product of"
+        );
+        let idf_explanation = &explanation.details()[0];
+        let tf_explanation = &explanation.details()[1];
+        assert_eq!(tf_explanation.details()[0].value(), 2.0);
+        assert_eq!(tf_explanation.details()[1].value(), 3.0);
+        assert!(
+            abs_diff(
+                explanation.value(),
+                idf_explanation.value() * tf_explanation.value()
+            ) < 0.0001
+        );
+        assert!(term_query.explain(&searcher, &DocAddress(0, 1)).is_err());
+    }
 }
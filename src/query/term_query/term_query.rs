@@ -3,7 +3,7 @@ use Result;
 use super::term_weight::TermWeight;
 use query::Query;
 use query::Weight;
-use schema::IndexRecordOption;
+use schema::{Field, IndexRecordOption};
 use Searcher;
 
 /// A Term query matches all of the documents
@@ -30,28 +30,45 @@ impl TermQuery {
         }
     }
 
+    /// Creates a term query matching the documents containing
+    /// `text` in the given text `field`.
+    ///
+    /// The text is not tokenized: it is expected to be a single token,
+    /// as emitted by the tokenizer of the field.
+    /// Term frequencies are used for scoring.
+    pub fn new_text(field: Field, text: &str) -> TermQuery {
+        TermQuery::new(
+            Term::from_field_text(field, text),
+            IndexRecordOption::WithFreqs,
+        )
+    }
+
     /// Returns a weight object.
     ///
     /// While `.weight(...)` returns a boxed trait object,
     /// this method return a specific implementation.
     /// This is useful for optimization purpose.
-    pub fn specialized_weight(&self, searcher: &Searcher, scoring_enabled: bool) -> TermWeight {
+    pub fn specialized_weight(
+        &self,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+    ) -> Result<TermWeight> {
         let index_record_option = if scoring_enabled {
             self.index_record_option
         } else {
             IndexRecordOption::Basic
         };
-        TermWeight {
+        Ok(TermWeight {
             num_docs: searcher.num_docs(),
-            doc_freq: searcher.doc_freq(&self.term),
+            doc_freq: searcher.doc_freq(&self.term)?,
             term: self.term.clone(),
             index_record_option,
-        }
+        })
     }
 }
 
 impl Query for TermQuery {
     fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
-        Ok(box self.specialized_weight(searcher, scoring_enabled))
+        Ok(box self.specialized_weight(searcher, scoring_enabled)?)
     }
 }
@@ -5,6 +5,8 @@ use postings::SegmentPostings;
 use query::Scorer;
 use postings::Postings;
 use fastfield::FastFieldReader;
+use query::Explanation;
+use std::f32;
 
 pub struct TermScorer {
     pub idf: Score,
@@ -16,6 +18,51 @@ impl TermScorer {
     pub fn postings(&self) -> &SegmentPostings {
         &self.postings
     }
+
+    /// Returns an upper bound of the score of the documents
+    /// of the current block of postings.
+    pub fn block_max_score(&self) -> Score {
+        self.impact_to_score(self.postings.block_max_impact())
+    }
+
+    /// Returns an upper bound of the score of the current document
+    /// and of the documents that follow.
+    pub fn max_score(&self) -> Score {
+        self.impact_to_score(self.postings.max_impact())
+    }
+
+    // The impacts are only meaningful when the score is normalized by the fieldnorms.
+    fn impact_to_score(&self, impact: f32) -> Score {
+        if self.fieldnorm_reader_opt.is_some() && self.idf >= 0.0 {
+            self.idf * (impact as Score).sqrt()
+        } else {
+            f32::INFINITY as Score
+        }
+    }
+
+    /// Explains the term frequency part of the score
+    /// of the current document.
+    pub fn explain_tf(&self) -> Explanation {
+        let term_freq = self.postings.term_freq() as Score;
+        match self.fieldnorm_reader_opt {
+            Some(ref fieldnorm_reader) => {
+                let field_norm = fieldnorm_reader.get(self.postings.doc()) as Score;
+                let mut explanation = Explanation::new(
+                    "tf, computed as sqrt(term_freq / field_norm)",
+                    (term_freq / field_norm).sqrt(),
+                );
+                explanation.add_const("term_freq", term_freq);
+                explanation.add_const("field_norm", field_norm);
+                explanation
+            }
+            None => {
+                let mut explanation =
+                    Explanation::new("tf, computed as sqrt(term_freq)", term_freq.sqrt());
+                explanation.add_const("term_freq", term_freq);
+                explanation
+            }
+        }
+    }
 }
 
 impl DocSet for TermScorer {
@@ -31,6 +78,10 @@ impl DocSet for TermScorer {
         self.postings.size_hint()
     }
 
+    fn estimate_live(&self) -> u32 {
+        self.postings.estimate_live()
+    }
+
     fn skip_next(&mut self, target: DocId) -> SkipResult {
         self.postings.skip_next(target)
     }
@@ -42,9 +93,9 @@ impl Scorer for TermScorer {
         let tf = match self.fieldnorm_reader_opt {
             Some(ref fieldnorm_reader) => {
                 let field_norm = fieldnorm_reader.get(doc);
-                (self.postings.term_freq() as f32 / field_norm as f32)
+                (self.postings.term_freq() as Score / field_norm as Score)
             }
-            None => self.postings.term_freq() as f32,
+            None => self.postings.term_freq() as Score,
         };
         self.idf * tf.sqrt()
     }
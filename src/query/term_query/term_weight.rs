@@ -1,12 +1,16 @@
 use Term;
 use query::Weight;
 use core::SegmentReader;
-use query::Scorer;
+use query::{EmptyScorer, Scorer};
 use docset::DocSet;
 use postings::SegmentPostings;
 use schema::IndexRecordOption;
 use super::term_scorer::TermScorer;
+use DocId;
 use Result;
+use query::Explanation;
+use query::explanation::{does_not_match, seek_doc};
+use Score;
 
 pub struct TermWeight {
     pub(crate) num_docs: u32,
@@ -17,48 +21,80 @@ pub struct TermWeight {
 
 impl Weight for TermWeight {
     fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
-        let specialized_scorer = self.specialized_scorer(reader)?;
-        Ok(box specialized_scorer)
+        match self.specialized_scorer(reader)? {
+            Some(specialized_scorer) => Ok(box specialized_scorer),
+            None => Ok(box EmptyScorer),
+        }
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        let mut scorer = match self.specialized_scorer(reader)? {
+            Some(scorer) => scorer,
+            None => {
+                return Err(does_not_match(doc));
+            }
+        };
+        if !seek_doc(&mut scorer, doc) {
+            return Err(does_not_match(doc));
+        }
+        let mut explanation = Explanation::new(
+            format!(
+                "TermQuery({:?}), product of",
+                self.term.debug(reader.schema())
+            ),
+            scorer.score(),
+        );
+        let mut idf_explanation = Explanation::new(
+            "idf, computed as 1 + ln(num_docs / (doc_freq + 1))",
+            self.idf(),
+        );
+        idf_explanation.add_const("num_docs", self.num_docs as Score);
+        idf_explanation.add_const("doc_freq", self.doc_freq as Score);
+        explanation.add_detail(idf_explanation);
+        explanation.add_detail(scorer.explain_tf());
+        Ok(explanation)
     }
 
     fn count(&self, reader: &SegmentReader) -> Result<u32> {
-        if reader.num_deleted_docs() == 0 {
-            let field = self.term.field();
+        // Without deletes, the doc freq of the term is the number
+        // of matching documents: postings do not need to be decoded.
+        let field = self.term.field();
+        if !reader.has_inverted_index(field) {
+            return Ok(0);
+        }
+        if !reader.has_deletes() {
             Ok(reader
-                .inverted_index(field)
+                .inverted_index(field)?
                 .get_term_info(&self.term)
                 .map(|term_info| term_info.doc_freq)
                 .unwrap_or(0))
         } else {
-            Ok(self.specialized_scorer(reader)?.count())
+            Ok(self.specialized_scorer(reader)?
+                .map(|mut scorer| scorer.count())
+                .unwrap_or(0))
         }
     }
 }
 
 impl TermWeight {
-    fn idf(&self) -> f32 {
-        1.0 + (self.num_docs as f32 / (self.doc_freq as f32 + 1.0)).ln()
+    fn idf(&self) -> Score {
+        1.0 + (self.num_docs as Score / (self.doc_freq as Score + 1.0)).ln()
     }
 
-    /// If the field is not found, returns an empty `DocSet`.
-    pub fn specialized_scorer(&self, reader: &SegmentReader) -> Result<TermScorer> {
+    /// Returns `None` if the term is not found in the segment.
+    pub fn specialized_scorer(&self, reader: &SegmentReader) -> Result<Option<TermScorer>> {
         let field = self.term.field();
-        let inverted_index = reader.inverted_index(field);
+        if !reader.has_inverted_index(field) {
+            return Ok(None);
+        }
+        let inverted_index = reader.inverted_index(field)?;
         let fieldnorm_reader_opt = reader.get_fieldnorms_reader(field);
         let postings_opt: Option<SegmentPostings> =
-            inverted_index.read_postings(&self.term, self.index_record_option);
-        if let Some(segment_postings) = postings_opt {
-            Ok(TermScorer {
-                idf: self.idf(),
-                fieldnorm_reader_opt,
-                postings: segment_postings,
-            })
-        } else {
-            Ok(TermScorer {
-                idf: 1f32,
-                fieldnorm_reader_opt: None,
-                postings: SegmentPostings::empty(),
-            })
-        }
+            inverted_index.read_postings(&self.term, self.index_record_option)?;
+        Ok(postings_opt.map(|segment_postings| TermScorer {
+            idf: self.idf(),
+            fieldnorm_reader_opt,
+            postings: segment_postings,
+        }))
     }
 }
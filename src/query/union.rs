@@ -45,7 +45,7 @@ where
             cursor: HORIZON_NUM_TINYBITSETS,
             offset: 0,
             doc: 0,
-            score: 0f32,
+            score: 0.0,
         }
     }
 }
@@ -223,8 +223,22 @@ impl<TScorer, TScoreCombiner> DocSet for Union<TScorer, TScoreCombiner>
         self.doc
     }
 
+    /// Returns the largest `size_hint` of the underlying docsets,
+    /// which is a lower bound of the size of the union.
     fn size_hint(&self) -> u32 {
-        0u32
+        self.docsets
+            .iter()
+            .map(|docset| docset.size_hint())
+            .max()
+            .unwrap_or(0u32)
+    }
+
+    fn estimate_live(&self) -> u32 {
+        self.docsets
+            .iter()
+            .map(|docset| docset.estimate_live())
+            .max()
+            .unwrap_or(0u32)
     }
 }
 
@@ -236,6 +250,10 @@ where
     fn score(&mut self) -> Score {
         self.score
     }
+
+    fn match_cost(&self) -> f32 {
+        self.docsets.iter().map(Scorer::match_cost).sum()
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,452 @@
+use Result;
+use Score;
+use DocId;
+use common::BitSet;
+use core::SegmentReader;
+use core::Searcher;
+use docset::{DocSet, SkipResult};
+use error::ErrorKind;
+use fastfield::VectorFastFieldReader;
+use query::{Explanation, Query, Scorer, Weight, WeightCache};
+use schema::Field;
+
+/// Similarity measure between the query vector
+/// and the vectors of the documents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Similarity {
+    /// Dot product of the two vectors.
+    DotProduct,
+    /// Cosine of the angle between the two vectors.
+    ///
+    /// The similarity of a vector of zeros is 0.
+    Cosine,
+}
+
+/// `VectorSimilarityQuery` scores the documents matching a
+/// candidate query by the similarity between their vector,
+/// within a vector field, and a query vector.
+///
+/// The similarity is computed by brute force, over all of the
+/// candidate documents: the candidate query (e.g. a filter, or a
+/// keyword query) is in charge of keeping their number reasonable.
+///
+/// By default, the score of a document is the similarity of its vector.
+/// Hybrid rankings can be obtained by adding the score of the candidate
+/// query, multiplied by a keyword weight.
+/// (See `.set_keyword_weight(...)`)
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate tantivy;
+/// use tantivy::schema::{SchemaBuilder, VectorOptions, STORED, TEXT};
+/// use tantivy::{Index, Result};
+/// use tantivy::collector::TopCollector;
+/// use tantivy::query::{Similarity, TermQuery, VectorSimilarityQuery};
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<()> {
+///     let mut schema_builder = SchemaBuilder::new();
+///     let title = schema_builder.add_text_field("title", TEXT | STORED);
+///     let embedding = schema_builder.add_vector_field("embedding", VectorOptions::with_dims(2));
+///     let index = Index::create_in_ram(schema_builder.build());
+///     {
+///         let mut index_writer = index.writer_with_num_threads(1, 3_000_000)?;
+///         index_writer.add_document(doc!(
+///             title => "The Diary of Muadib",
+///             embedding => vec![0.9f32, 0.1f32]
+///         ));
+///         index_writer.add_document(doc!(
+///             title => "The Diary of a Young Girl",
+///             embedding => vec![0.1f32, 0.9f32]
+///         ));
+///         index_writer.commit()?;
+///     }
+///     index.load_searchers()?;
+///     let searcher = index.searcher();
+///
+///     let candidates = TermQuery::new_text(title, "diary");
+///     let query = VectorSimilarityQuery::new(
+///         embedding,
+///         vec![0f32, 1f32],
+///         Similarity::Cosine,
+///         Box::new(candidates),
+///     );
+///     let mut top_collector = TopCollector::with_limit(1);
+///     searcher.search(&query, &mut top_collector)?;
+///     let doc = searcher.doc(&top_collector.docs()[0])?;
+///     assert_eq!(doc.get_first(title).unwrap().text(), "The Diary of a Young Girl");
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct VectorSimilarityQuery {
+    field: Field,
+    vector: Vec<f32>,
+    similarity: Similarity,
+    candidates: Box<Query>,
+    keyword_weight: Score,
+}
+
+impl VectorSimilarityQuery {
+    /// Creates a `VectorSimilarityQuery` scoring the documents matching
+    /// `candidates` by the similarity of their vector in `field`
+    /// and `vector`.
+    pub fn new(
+        field: Field,
+        vector: Vec<f32>,
+        similarity: Similarity,
+        candidates: Box<Query>,
+    ) -> VectorSimilarityQuery {
+        VectorSimilarityQuery {
+            field,
+            vector,
+            similarity,
+            candidates,
+            keyword_weight: 0.0,
+        }
+    }
+
+    /// Sets the weight of the score of the candidate query
+    /// in the score of the documents.
+    ///
+    /// The score of a document is then
+    /// `similarity + keyword_weight * candidate_score`.
+    pub fn set_keyword_weight(&mut self, keyword_weight: Score) {
+        self.keyword_weight = keyword_weight;
+    }
+
+    /// Returns the weight of the score of the candidate query.
+    pub fn keyword_weight(&self) -> Score {
+        self.keyword_weight
+    }
+
+    fn create_weight(&self, candidates_weight: Box<Weight>) -> VectorSimilarityWeight {
+        let query_vector = match self.similarity {
+            Similarity::DotProduct => self.vector.clone(),
+            Similarity::Cosine => {
+                let norm = norm(&self.vector);
+                self.vector
+                    .iter()
+                    .map(|&val| if norm > 0f32 { val / norm } else { 0f32 })
+                    .collect()
+            }
+        };
+        VectorSimilarityWeight {
+            field: self.field,
+            query_vector,
+            similarity: self.similarity,
+            candidates_weight,
+            keyword_weight: self.keyword_weight,
+        }
+    }
+}
+
+impl Query for VectorSimilarityQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+        let keyword_scoring_enabled = scoring_enabled && self.keyword_weight != 0.0;
+        let candidates_weight = self.candidates
+            .weight(searcher, keyword_scoring_enabled)?;
+        Ok(box self.create_weight(candidates_weight))
+    }
+
+    fn shared_weight(
+        &self,
+        searcher: &Searcher,
+        scoring_enabled: bool,
+        weight_cache: &mut WeightCache,
+    ) -> Result<Box<Weight>> {
+        let keyword_scoring_enabled = scoring_enabled && self.keyword_weight != 0.0;
+        let candidates_weight =
+            self.candidates
+                .shared_weight(searcher, keyword_scoring_enabled, weight_cache)?;
+        Ok(box self.create_weight(candidates_weight))
+    }
+    fn has_stable_representation(&self) -> bool {
+        self.candidates.has_stable_representation()
+    }
+}
+
+fn dot(left: &[f32], right: &[f32]) -> f32 {
+    left.iter().zip(right).map(|(&l, &r)| l * r).sum()
+}
+
+fn norm(vals: &[f32]) -> f32 {
+    dot(vals, vals).sqrt()
+}
+
+/// Weight associated to the `VectorSimilarityQuery` query.
+pub struct VectorSimilarityWeight {
+    field: Field,
+    // normalized, for the cosine similarity.
+    query_vector: Vec<f32>,
+    similarity: Similarity,
+    candidates_weight: Box<Weight>,
+    keyword_weight: Score,
+}
+
+impl VectorSimilarityWeight {
+    /// Returns the reader of the vectors of the segment, or `None` if the
+    /// segment is missing the vector field: the vectors are then empty.
+    fn vector_reader(&self, reader: &SegmentReader) -> Result<Option<VectorFastFieldReader>> {
+        if reader.is_missing_fast_field(self.field) {
+            return Ok(None);
+        }
+        let vector_reader = reader.vector_reader(self.field)?;
+        if vector_reader.dims() != self.query_vector.len() {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "The query vector has {} dimensions, while the field {:?} has {}.",
+                self.query_vector.len(),
+                self.field,
+                vector_reader.dims()
+            )));
+        }
+        Ok(Some(vector_reader))
+    }
+}
+
+/// Sets `doc_vector` to the vector of the document `doc`,
+/// or to an empty vector if the segment has no vectors.
+fn read_doc_vector(
+    vector_reader: &Option<VectorFastFieldReader>,
+    doc: DocId,
+    doc_vector: &mut Vec<f32>,
+) {
+    match *vector_reader {
+        Some(ref vector_reader) => vector_reader.get_vals(doc, doc_vector),
+        None => doc_vector.clear(),
+    }
+}
+
+impl Weight for VectorSimilarityWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let vector_reader = self.vector_reader(reader)?;
+        let candidates = self.candidates_weight.scorer(reader)?;
+        Ok(box VectorSimilarityScorer {
+            candidates,
+            vector_reader,
+            query_vector: self.query_vector.clone(),
+            similarity: self.similarity,
+            keyword_weight: self.keyword_weight,
+            doc_vector: Vec::with_capacity(self.query_vector.len()),
+        })
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        let candidate_explanation = self.candidates_weight.explain(reader, doc)?;
+        let vector_reader = self.vector_reader(reader)?;
+        let mut doc_vector = Vec::new();
+        read_doc_vector(&vector_reader, doc, &mut doc_vector);
+        let similarity = similarity(self.similarity, &self.query_vector, &doc_vector);
+        if self.keyword_weight == 0.0 {
+            return Ok(Explanation::new(
+                format!("{:?} similarity", self.similarity),
+                similarity,
+            ));
+        }
+        let keyword_score = candidate_explanation.value() * self.keyword_weight;
+        let mut explanation = Explanation::new(
+            "Vector similarity and weighted keyword score, sum of",
+            similarity + keyword_score,
+        );
+        explanation.add_const(format!("{:?} similarity", self.similarity), similarity);
+        let mut keyword_explanation =
+            Explanation::new("Weighted keyword score, product of", keyword_score);
+        keyword_explanation.add_const("keyword weight", self.keyword_weight);
+        keyword_explanation.add_detail(candidate_explanation);
+        explanation.add_detail(keyword_explanation);
+        Ok(explanation)
+    }
+
+    fn count(&self, reader: &SegmentReader) -> Result<u32> {
+        self.candidates_weight.count(reader)
+    }
+}
+
+/// Computes the similarity of the vector of a document,
+/// given the query vector, normalized in the case of the cosine.
+fn similarity(similarity: Similarity, query_vector: &[f32], doc_vector: &[f32]) -> Score {
+    let dot_product = dot(query_vector, doc_vector);
+    let similarity = match similarity {
+        Similarity::DotProduct => dot_product,
+        Similarity::Cosine => {
+            let doc_norm = norm(doc_vector);
+            if doc_norm > 0f32 {
+                dot_product / doc_norm
+            } else {
+                0f32
+            }
+        }
+    };
+    similarity as Score
+}
+
+/// Scorer associated to the `VectorSimilarityQuery` query.
+pub struct VectorSimilarityScorer {
+    candidates: Box<Scorer>,
+    vector_reader: Option<VectorFastFieldReader>,
+    query_vector: Vec<f32>,
+    similarity: Similarity,
+    keyword_weight: Score,
+    doc_vector: Vec<f32>,
+}
+
+impl DocSet for VectorSimilarityScorer {
+    fn advance(&mut self) -> bool {
+        self.candidates.advance()
+    }
+
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        self.candidates.skip_next(target)
+    }
+
+    fn fill_buffer(&mut self, buffer: &mut [DocId]) -> usize {
+        self.candidates.fill_buffer(buffer)
+    }
+
+    fn doc(&self) -> DocId {
+        self.candidates.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.candidates.size_hint()
+    }
+
+    fn estimate_live(&self) -> u32 {
+        self.candidates.estimate_live()
+    }
+
+    fn append_to_bitset(&mut self, bitset: &mut BitSet) {
+        self.candidates.append_to_bitset(bitset);
+    }
+}
+
+impl Scorer for VectorSimilarityScorer {
+    fn score(&mut self) -> Score {
+        let doc = self.candidates.doc();
+        read_doc_vector(&self.vector_reader, doc, &mut self.doc_vector);
+        let similarity = similarity(self.similarity, &self.query_vector, &self.doc_vector);
+        if self.keyword_weight == 0.0 {
+            similarity
+        } else {
+            similarity + self.keyword_weight * self.candidates.score()
+        }
+    }
+
+    fn match_cost(&self) -> f32 {
+        self.candidates.match_cost()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Similarity, VectorSimilarityQuery};
+    use collector::TopCollector;
+    use query::{AllQuery, Query, TermQuery};
+    use schema::{SchemaBuilder, VectorOptions, TEXT};
+    use DocAddress;
+    use Index;
+    use Score;
+
+    #[test]
+    fn test_vector_similarity_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let vector_field = schema_builder.add_vector_field("vector", VectorOptions::with_dims(2));
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a", vector_field => vec![3f32, 0f32]));
+            index_writer.add_document(doc!(text_field => "a b", vector_field => vec![1f32, 1f32]));
+            index_writer.add_document(doc!(text_field => "b", vector_field => vec![0f32, 1f32]));
+            index_writer.add_document(doc!(text_field => "a"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let top_docs = |query: &Query| {
+            let mut top_collector = TopCollector::with_limit(10);
+            query.search(&*searcher, &mut top_collector).unwrap();
+            top_collector
+                .score_docs()
+                .into_iter()
+                .map(|(score, doc_address)| (doc_address.doc(), score))
+                .collect::<Vec<_>>()
+        };
+        let dot_product_query = VectorSimilarityQuery::new(
+            vector_field,
+            vec![1f32, 2.5f32],
+            Similarity::DotProduct,
+            box AllQuery,
+        );
+        assert_eq!(
+            top_docs(&dot_product_query),
+            vec![(1, 3.5), (0, 3.0), (2, 2.5), (3, 0.0)]
+        );
+        let cosine_query = VectorSimilarityQuery::new(
+            vector_field,
+            vec![2f32, 0f32],
+            Similarity::Cosine,
+            box TermQuery::new_text(text_field, "a"),
+        );
+        let cosine_top_docs = top_docs(&cosine_query);
+        assert_eq!(cosine_top_docs.len(), 3);
+        assert_eq!(cosine_top_docs[0], (0, 1.0));
+        assert_eq!(cosine_top_docs[1].0, 1);
+        assert!((cosine_top_docs[1].1 - (0.5 as Score).sqrt()).abs() < 1e-6);
+        assert_eq!(cosine_top_docs[2], (3, 0.0));
+        assert_eq!(cosine_query.count(&*searcher).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_vector_similarity_query_hybrid() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let vector_field = schema_builder.add_vector_field("vector", VectorOptions::with_dims(1));
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a", vector_field => vec![1f32]));
+            index_writer.add_document(doc!(text_field => "a b c", vector_field => vec![2f32]));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let keyword_query = TermQuery::new_text(text_field, "a");
+        let mut query = VectorSimilarityQuery::new(
+            vector_field,
+            vec![1f32],
+            Similarity::DotProduct,
+            box TermQuery::new_text(text_field, "a"),
+        );
+        query.set_keyword_weight(0.5);
+        for doc in 0..2 {
+            let doc_address = DocAddress(0, doc);
+            let keyword_score = keyword_query
+                .explain(&*searcher, &doc_address)
+                .unwrap()
+                .value();
+            let explanation = query.explain(&*searcher, &doc_address).unwrap();
+            let expected = (doc + 1) as Score + 0.5 * keyword_score;
+            assert_eq!(explanation.value(), expected);
+        }
+    }
+
+    #[test]
+    fn test_vector_similarity_query_wrong_dims() {
+        let mut schema_builder = SchemaBuilder::default();
+        let vector_field = schema_builder.add_vector_field("vector", VectorOptions::with_dims(2));
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(vector_field => vec![1f32, 0f32]));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query =
+            VectorSimilarityQuery::new(vector_field, vec![1f32], Similarity::Cosine, box AllQuery);
+        let mut top_collector = TopCollector::with_limit(1);
+        assert!(query.search(&*searcher, &mut top_collector).is_err());
+    }
+}
@@ -1,4 +1,7 @@
 use super::Scorer;
+use super::Explanation;
+use super::explanation::{does_not_match, seek_doc};
+use DocId;
 use Result;
 use core::SegmentReader;
 
@@ -11,6 +14,21 @@ pub trait Weight {
     /// See [`Query`](./trait.Query.html).
     fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>>;
 
+    /// Returns an `Explanation` for the score of the document `doc`
+    /// within the given `SegmentReader`.
+    ///
+    /// Returns an error if the document does not match.
+    ///
+    /// The default implementation only reports the score
+    /// of the document as computed by the scorer.
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> Result<Explanation> {
+        let mut scorer = self.scorer(reader)?;
+        if !seek_doc(&mut scorer, doc) {
+            return Err(does_not_match(doc));
+        }
+        Ok(Explanation::new("<no explanation available>", scorer.score()))
+    }
+
     /// Returns the number documents within the given `SegmentReader`.
     fn count(&self, reader: &SegmentReader) -> Result<u32> {
         Ok(self.scorer(reader)?.count())
@@ -0,0 +1,55 @@
+/// Define how a bytes field should be handled by tantivy.
+///
+/// Bytes fields hold opaque binary values. They can be indexed
+/// as a single term, for exact matches (See `Term::from_field_bytes`),
+/// stored, and stored as a fast field for random access at collection time.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BytesOptions {
+    indexed: bool,
+    fast: bool,
+    stored: bool,
+}
+
+impl BytesOptions {
+    /// Returns true iff the value is indexed.
+    pub fn is_indexed(&self) -> bool {
+        self.indexed
+    }
+
+    /// Returns true iff the value is a fast field.
+    pub fn is_fast(&self) -> bool {
+        self.fast
+    }
+
+    /// Returns true iff the value is stored.
+    pub fn is_stored(&self) -> bool {
+        self.stored
+    }
+
+    /// Set the bytes options as indexed.
+    ///
+    /// Each value is indexed as a single term.
+    pub fn set_indexed(mut self) -> BytesOptions {
+        self.indexed = true;
+        self
+    }
+
+    /// Set the bytes options as a fast field.
+    ///
+    /// Fast fields are designed for random access.
+    /// If more than one value is associated to the document,
+    /// only the first one is kept.
+    pub fn set_fast(mut self) -> BytesOptions {
+        self.fast = true;
+        self
+    }
+
+    /// Set the bytes options as stored.
+    ///
+    /// Only the fields that are set as *stored* are
+    /// persisted into the Tantivy's store.
+    pub fn set_stored(mut self) -> BytesOptions {
+        self.stored = true;
+        self
+    }
+}
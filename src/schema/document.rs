@@ -3,6 +3,7 @@ use itertools::Itertools;
 use common::VInt;
 use std::io::{self, Read, Write};
 use common::BinarySerializable;
+use tokenizer::PreTokenizedString;
 
 /// Tantivy's Document is the object that can
 /// be indexed and then searched for.
@@ -77,6 +78,14 @@ impl Document {
         self.add(FieldValue::new(field, value));
     }
 
+    /// Add a pre-tokenized text field.
+    ///
+    /// At indexing time, the tokens are used as is, instead of the
+    /// tokens produced by the tokenizer of the field.
+    pub fn add_pre_tokenized_text(&mut self, field: Field, pre_tokenized: PreTokenizedString) {
+        self.add(FieldValue::new(field, Value::PreTokStr(pre_tokenized)));
+    }
+
     /// Add a u64 field
     pub fn add_u64(&mut self, field: Field, value: u64) {
         self.add(FieldValue::new(field, Value::U64(value)));
@@ -87,6 +96,20 @@ impl Document {
         self.add(FieldValue::new(field, Value::I64(value)));
     }
 
+    /// Add a vector field
+    ///
+    /// The vector must have the number of dimensions declared in the
+    /// `VectorOptions` of the field. Otherwise, it is ignored at indexing time.
+    pub fn add_vector(&mut self, field: Field, vals: &[f32]) {
+        let value = Value::Vector(Vector::from(vals));
+        self.add(FieldValue::new(field, value));
+    }
+
+    /// Add a bytes field
+    pub fn add_bytes(&mut self, field: Field, bytes: Vec<u8>) {
+        self.add(FieldValue::new(field, Value::Bytes(bytes)));
+    }
+
     /// Add a field value
     pub fn add(&mut self, field_value: FieldValue) {
         self.field_values.push(field_value);
@@ -121,6 +144,14 @@ impl Document {
             .collect()
     }
 
+    /// Returns the JSON representation of the document,
+    /// with the field names of the `schema`.
+    ///
+    /// See `Schema::to_json`.
+    pub fn to_named_json(&self, schema: &Schema) -> String {
+        schema.to_json(self)
+    }
+
     /// Returns the first `FieldValue` associated the given field
     pub fn get_first(&self, field: Field) -> Option<&Value> {
         self.field_values
@@ -149,6 +180,26 @@ impl BinarySerializable for Document {
     }
 }
 
+impl Document {
+    /// Deserializes a document, only keeping the values of the given fields.
+    ///
+    /// The values of the other fields are skipped without being decoded.
+    pub(crate) fn deserialize_fields(reader: &mut &[u8], fields: &[Field]) -> io::Result<Document> {
+        let num_field_values = VInt::deserialize(reader)?.val() as usize;
+        let mut field_values = Vec::new();
+        for _ in 0..num_field_values {
+            let field = Field::deserialize(reader)?;
+            if fields.contains(&field) {
+                let value = Value::deserialize(reader)?;
+                field_values.push(FieldValue::new(field, value));
+            } else {
+                Value::skip_serialized(reader)?;
+            }
+        }
+        Ok(Document::from(field_values))
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -163,4 +214,40 @@ mod tests {
         assert_eq!(doc.field_values().len(), 1);
     }
 
+    #[test]
+    fn test_deserialize_fields() {
+        use common::BinarySerializable;
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let id = schema_builder.add_u64_field("id", INT_STORED);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let tags = schema_builder.add_facet_field("tags");
+        let blob = schema_builder.add_bytes_field("blob", BytesOptions::default());
+        let embedding = schema_builder.add_vector_field("embedding", VectorOptions::with_dims(2));
+        let mut doc = Document::default();
+        doc.add_text(title, "My title");
+        doc.add_text(body, "A very long body");
+        doc.add_u64(id, 12u64);
+        doc.add_facet(tags, "/a/b");
+        doc.add_bytes(blob, vec![1u8, 2u8, 3u8]);
+        doc.add_vector(embedding, &[0.5f32, 1.5f32]);
+        doc.add_text(title, "My subtitle");
+        let mut buffer = Vec::new();
+        doc.serialize(&mut buffer).unwrap();
+
+        let mut cursor = &buffer[..];
+        let title_doc = Document::deserialize_fields(&mut cursor, &[title, id]).unwrap();
+        assert!(cursor.is_empty());
+        let mut expected = Document::default();
+        expected.add_text(title, "My title");
+        expected.add_u64(id, 12u64);
+        expected.add_text(title, "My subtitle");
+        assert_eq!(title_doc, expected);
+
+        let mut cursor = &buffer[..];
+        let empty_doc = Document::deserialize_fields(&mut cursor, &[]).unwrap();
+        assert!(cursor.is_empty());
+        assert!(empty_doc.is_empty());
+    }
+
 }
@@ -1,5 +1,7 @@
 use schema::TextOptions;
 use schema::IntOptions;
+use schema::VectorOptions;
+use schema::BytesOptions;
 
 use std::fmt;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -56,6 +58,22 @@ impl FieldEntry {
         }
     }
 
+    /// Creates a field entry for a vector field.
+    pub fn new_vector(field_name: String, vector_options: VectorOptions) -> FieldEntry {
+        FieldEntry {
+            name: field_name,
+            field_type: FieldType::Vector(vector_options),
+        }
+    }
+
+    /// Creates a field entry for a bytes field.
+    pub fn new_bytes(field_name: String, bytes_options: BytesOptions) -> FieldEntry {
+        FieldEntry {
+            name: field_name,
+            field_type: FieldType::Bytes(bytes_options),
+        }
+    }
+
     /// Returns the name of the field
     pub fn name(&self) -> &str {
         &self.name
@@ -72,6 +90,8 @@ impl FieldEntry {
             FieldType::Str(ref options) => options.get_indexing_options().is_some(),
             FieldType::U64(ref options) | FieldType::I64(ref options) => options.is_indexed(),
             FieldType::HierarchicalFacet => true,
+            FieldType::Vector(_) => false,
+            FieldType::Bytes(ref options) => options.is_indexed(),
         }
     }
 
@@ -83,6 +103,28 @@ impl FieldEntry {
         }
     }
 
+    /// Returns true iff the field has a fast field,
+    /// i.e. is an int fast field, a facet field,
+    /// a bytes fast field or a vector field.
+    pub fn is_fast(&self) -> bool {
+        match self.field_type {
+            FieldType::Str(_) => false,
+            FieldType::U64(ref options) | FieldType::I64(ref options) => options.is_fast(),
+            FieldType::HierarchicalFacet | FieldType::Vector(_) => true,
+            FieldType::Bytes(ref options) => options.is_fast(),
+        }
+    }
+
+    /// Returns true iff the field is a unique key.
+    /// (See `TextOptions::set_unique` and `IntOptions::set_unique`)
+    pub fn is_unique(&self) -> bool {
+        match self.field_type {
+            FieldType::Str(ref options) => options.is_unique(),
+            FieldType::U64(ref options) | FieldType::I64(ref options) => options.is_unique(),
+            _ => false,
+        }
+    }
+
     /// Returns true iff the field is stored
     pub fn is_stored(&self) -> bool {
         match self.field_type {
@@ -90,6 +132,8 @@ impl FieldEntry {
             FieldType::Str(ref options) => options.is_stored(),
             FieldType::HierarchicalFacet => true,
             // TODO make stored hierachical facet optional
+            FieldType::Vector(ref options) => options.is_stored(),
+            FieldType::Bytes(ref options) => options.is_stored(),
         }
     }
 }
@@ -118,6 +162,14 @@ impl Serialize for FieldEntry {
             FieldType::HierarchicalFacet => {
                 s.serialize_field("type", "hierarchical_facet")?;
             }
+            FieldType::Vector(ref options) => {
+                s.serialize_field("type", "vector")?;
+                s.serialize_field("options", options)?;
+            }
+            FieldType::Bytes(ref options) => {
+                s.serialize_field("type", "bytes")?;
+                s.serialize_field("options", options)?;
+            }
         }
 
         s.end()
@@ -182,6 +234,10 @@ impl<'de> Deserialize<'de> for FieldEntry {
                                 "text" => field_type = Some(FieldType::Str(map.next_value()?)),
                                 "u64" => field_type = Some(FieldType::U64(map.next_value()?)),
                                 "i64" => field_type = Some(FieldType::I64(map.next_value()?)),
+                                "vector" => {
+                                    field_type = Some(FieldType::Vector(map.next_value()?))
+                                }
+                                "bytes" => field_type = Some(FieldType::Bytes(map.next_value()?)),
                                 _ => {
                                     let msg = format!("Unrecognised type {}", ty);
                                     return Err(de::Error::custom(msg));
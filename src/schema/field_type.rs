@@ -1,9 +1,12 @@
-use schema::{IntOptions, TextOptions};
+use schema::{BytesOptions, IntOptions, TextOptions, VectorOptions};
 
-use serde_json::Value as JsonValue;
+use serde_json::{self, Value as JsonValue};
 use schema::Value;
 use schema::IndexRecordOption;
 use schema::Facet;
+use schema::Vector;
+use common::base64;
+use tokenizer::PreTokenizedString;
 
 /// Possible error that may occur while parsing a field value
 /// At this point the JSON is known to be valid.
@@ -29,6 +32,10 @@ pub enum FieldType {
     I64(IntOptions),
     /// Hierachical Facet
     HierarchicalFacet,
+    /// Dense vector of `f32` field type configuration
+    Vector(VectorOptions),
+    /// Raw binary values field type configuration
+    Bytes(BytesOptions),
 }
 
 impl FieldType {
@@ -40,6 +47,8 @@ impl FieldType {
                 int_options.is_indexed()
             }
             FieldType::HierarchicalFacet => true,
+            FieldType::Vector(_) => false,
+            FieldType::Bytes(ref bytes_options) => bytes_options.is_indexed(),
         }
     }
 
@@ -60,6 +69,14 @@ impl FieldType {
                 }
             }
             FieldType::HierarchicalFacet => Some(IndexRecordOption::Basic),
+            FieldType::Vector(_) => None,
+            FieldType::Bytes(ref bytes_options) => {
+                if bytes_options.is_indexed() {
+                    Some(IndexRecordOption::Basic)
+                } else {
+                    None
+                }
+            }
         }
     }
 
@@ -68,6 +85,10 @@ impl FieldType {
     /// Tantivy will not try to cast values.
     /// For instance, If the json value is the integer `3` and the
     /// target field is a `Str`, this method will return an Error.
+    ///
+    /// The values of bytes fields are expected to be encoded in base64.
+    /// Pre-tokenized text values are expected as an object with a `text`
+    /// and a `tokens` property. (See `PreTokenizedString`)
     pub fn value_from_json(&self, json: &JsonValue) -> Result<Value, ValueParsingError> {
         match *json {
             JsonValue::String(ref field_text) => match *self {
@@ -76,6 +97,14 @@ impl FieldType {
                     format!("Expected an integer, got {:?}", json),
                 )),
                 FieldType::HierarchicalFacet => Ok(Value::Facet(Facet::from(field_text))),
+                FieldType::Vector(_) => Err(ValueParsingError::TypeError(format!(
+                    "Expected an array of numbers, got {:?}",
+                    json
+                ))),
+                FieldType::Bytes(_) => base64::decode(field_text).map(Value::Bytes).ok_or_else(|| {
+                    let msg = format!("Expected a base64 string, got {:?}", json);
+                    ValueParsingError::TypeError(msg)
+                }),
             },
             JsonValue::Number(ref field_val_num) => match *self {
                 FieldType::I64(_) => {
@@ -94,10 +123,52 @@ impl FieldType {
                         Err(ValueParsingError::OverflowError(msg))
                     }
                 }
-                FieldType::Str(_) | FieldType::HierarchicalFacet => {
+                FieldType::Str(_) | FieldType::HierarchicalFacet | FieldType::Bytes(_) => {
                     let msg = format!("Expected a string, got {:?}", json);
                     Err(ValueParsingError::TypeError(msg))
                 }
+                FieldType::Vector(_) => {
+                    let msg = format!("Expected an array of numbers, got {:?}", json);
+                    Err(ValueParsingError::TypeError(msg))
+                }
+            },
+            JsonValue::Array(ref json_vals) => match *self {
+                FieldType::Vector(ref vector_options) => {
+                    if json_vals.len() != vector_options.dims() {
+                        let msg = format!(
+                            "Expected a vector of {} dimensions, got {:?}",
+                            vector_options.dims(),
+                            json
+                        );
+                        return Err(ValueParsingError::TypeError(msg));
+                    }
+                    json_vals
+                        .iter()
+                        .map(|json_val| {
+                            json_val.as_f64().map(|val| val as f32).ok_or_else(|| {
+                                let msg = format!("Expected a number, got {:?}", json_val);
+                                ValueParsingError::TypeError(msg)
+                            })
+                        })
+                        .collect::<Result<Vec<f32>, _>>()
+                        .map(|vals| Value::Vector(Vector::from(vals)))
+                }
+                _ => {
+                    let msg = format!("Expected a single value, got {:?}", json);
+                    Err(ValueParsingError::TypeError(msg))
+                }
+            },
+            JsonValue::Object(_) => match *self {
+                FieldType::Str(_) => serde_json::from_value::<PreTokenizedString>(json.clone())
+                    .map(Value::PreTokStr)
+                    .map_err(|err| {
+                        let msg = format!("Expected a pre-tokenized text, got {:?}: {}", json, err);
+                        ValueParsingError::TypeError(msg)
+                    }),
+                _ => {
+                    let msg = format!("Expected a single value, got {:?}", json);
+                    Err(ValueParsingError::TypeError(msg))
+                }
             },
             _ => {
                 let msg = format!(
@@ -12,7 +12,10 @@
 /// (See [`InvertedIndexReader.read_postings`](
 ///     ../struct.InvertedIndexReader.html#method.read_postings))
 ///
-#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Ord, Eq, Hash, Serialize, Deserialize)]
+/// The options are not totally ordered: `WithPositions` records the positions
+/// but not the term frequencies, unlike `WithFreqs`. Use `.covers(...)` to
+/// compare the information recorded by two options.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum IndexRecordOption {
     /// records only the `DocId`s
     #[serde(rename = "basic")]
@@ -26,13 +29,31 @@ pub enum IndexRecordOption {
     /// Positions are required to run [PhraseQueries](../query/struct.PhraseQuery.html).
     #[serde(rename = "position")]
     WithFreqsAndPositions,
+    /// records the document id, the term frequency, the positions of
+    /// the occurences in the document, as well as the byte offsets
+    /// of each of these occurences in the original text.
+    /// Offsets make it possible to highlight the occurences of a term
+    /// without having to tokenize the stored text again.
+    #[serde(rename = "offset")]
+    WithFreqsPositionsAndOffsets,
+    /// records the document id and the position of the first occurence
+    /// of the term in the document. The term frequency is not recorded,
+    /// and is considered to be 1.
+    ///
+    /// This is meant for fields where terms are not expected to be repeated
+    /// within a document, but positions are required: e.g. autocomplete fields
+    /// tokenized with the [`EdgeNgramTokenizer`](../tokenizer/struct.EdgeNgramTokenizer.html).
+    #[serde(rename = "position_only")]
+    WithPositions,
 }
 
 impl IndexRecordOption {
     /// Returns true iff the term frequency will be encoded.
     pub fn is_termfreq_enabled(&self) -> bool {
         match *self {
-            IndexRecordOption::WithFreqsAndPositions | IndexRecordOption::WithFreqs => true,
+            IndexRecordOption::WithFreqsPositionsAndOffsets
+            | IndexRecordOption::WithFreqsAndPositions
+            | IndexRecordOption::WithFreqs => true,
             _ => false,
         }
     }
@@ -40,7 +61,9 @@ impl IndexRecordOption {
     /// Returns true iff the term positions within the document are stored as well.
     pub fn is_position_enabled(&self) -> bool {
         match *self {
-            IndexRecordOption::WithFreqsAndPositions => true,
+            IndexRecordOption::WithFreqsPositionsAndOffsets
+            | IndexRecordOption::WithFreqsAndPositions
+            | IndexRecordOption::WithPositions => true,
             _ => false,
         }
     }
@@ -49,8 +72,10 @@ impl IndexRecordOption {
     /// term frequencies.
     pub fn has_freq(&self) -> bool {
         match *self {
-            IndexRecordOption::Basic => false,
-            IndexRecordOption::WithFreqs | IndexRecordOption::WithFreqsAndPositions => true,
+            IndexRecordOption::Basic | IndexRecordOption::WithPositions => false,
+            IndexRecordOption::WithFreqs
+            | IndexRecordOption::WithFreqsAndPositions
+            | IndexRecordOption::WithFreqsPositionsAndOffsets => true,
         }
     }
 
@@ -59,7 +84,26 @@ impl IndexRecordOption {
     pub fn has_positions(&self) -> bool {
         match *self {
             IndexRecordOption::Basic | IndexRecordOption::WithFreqs => false,
-            IndexRecordOption::WithFreqsAndPositions => true,
+            IndexRecordOption::WithFreqsAndPositions
+            | IndexRecordOption::WithFreqsPositionsAndOffsets
+            | IndexRecordOption::WithPositions => true,
         }
     }
+
+    /// Returns true iff this option include encoding
+    /// the byte offsets of the terms.
+    pub fn has_offsets(&self) -> bool {
+        match *self {
+            IndexRecordOption::WithFreqsPositionsAndOffsets => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true iff this option records at least all
+    /// of the information recorded by `other`.
+    pub fn covers(&self, other: IndexRecordOption) -> bool {
+        (self.has_freq() || !other.has_freq())
+            && (self.has_positions() || !other.has_positions())
+            && (self.has_offsets() || !other.has_offsets())
+    }
 }
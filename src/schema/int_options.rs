@@ -19,6 +19,8 @@ pub struct IntOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     fast: Option<Cardinality>,
     stored: bool,
+    #[serde(default, skip_serializing_if = "super::is_false")]
+    unique: bool,
 }
 
 impl IntOptions {
@@ -37,6 +39,11 @@ impl IntOptions {
         self.fast.is_some()
     }
 
+    /// Returns true iff the field is a unique key.
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
     /// Set the u64 options as stored.
     ///
     /// Only the fields that are set as *stored* are
@@ -55,6 +62,19 @@ impl IntOptions {
         self
     }
 
+    /// Set the u64 options as a unique key.
+    ///
+    /// Adding a document then deletes the documents added before it
+    /// with the same value for this field, be they committed or not.
+    /// (See `IndexWriter::add_document`)
+    ///
+    /// The field also needs to be indexed.
+    /// Otherwise, building the schema fails. (See `SchemaBuilder::try_build`)
+    pub fn set_unique(mut self) -> IntOptions {
+        self.unique = true;
+        self
+    }
+
     /// Set the u64 options as a single-valued fast field.
     ///
     /// Fast fields are designed for random access.
@@ -81,6 +101,7 @@ impl Default for IntOptions {
             indexed: false,
             stored: false,
             fast: None,
+            unique: false,
         }
     }
 }
@@ -92,6 +113,7 @@ pub const FAST: IntOptions = IntOptions {
     indexed: false,
     stored: false,
     fast: Some(Cardinality::SingleValue),
+    unique: false,
 };
 
 /// Shortcut for a u64 indexed field.
@@ -101,6 +123,7 @@ pub const INT_INDEXED: IntOptions = IntOptions {
     indexed: true,
     stored: false,
     fast: None,
+    unique: false,
 };
 
 /// Shortcut for a u64 stored field.
@@ -110,6 +133,7 @@ pub const INT_STORED: IntOptions = IntOptions {
     indexed: false,
     stored: true,
     fast: None,
+    unique: false,
 };
 
 impl BitOr for IntOptions {
@@ -120,6 +144,7 @@ impl BitOr for IntOptions {
         res.indexed = self.indexed | other.indexed;
         res.stored = self.stored | other.stored;
         res.fast = self.fast.or(other.fast);
+        res.unique = self.unique | other.unique;
         res
     }
 }
@@ -111,6 +111,10 @@ mod field_value;
 
 mod text_options;
 mod int_options;
+mod vector_options;
+mod vector;
+mod bytes_options;
+mod source_options;
 mod field;
 mod value;
 mod named_field_document;
@@ -124,9 +128,11 @@ pub use self::schema::DocParsingError;
 pub use self::facet::Facet;
 pub use self::facet::FACET_SEP_BYTE;
 
+pub use self::vector::Vector;
+
 pub use self::document::Document;
 pub use self::field::Field;
-pub use self::term::Term;
+pub use self::term::{Term, TermDebug};
 
 pub use self::field_type::FieldType;
 pub use self::field_entry::FieldEntry;
@@ -145,6 +151,12 @@ pub use self::int_options::INT_INDEXED;
 pub use self::int_options::INT_STORED;
 pub use self::int_options::Cardinality;
 
+pub use self::vector_options::VectorOptions;
+
+pub use self::bytes_options::BytesOptions;
+
+pub use self::source_options::SourceOptions;
+
 use regex::Regex;
 
 /// Validator for a potential `field_name`.
@@ -159,6 +171,11 @@ pub fn is_valid_field_name(field_name: &str) -> bool {
     FIELD_NAME_PTN.is_match(field_name)
 }
 
+/// Used to skip the serialization of the flags that are not set.
+fn is_false(val: &bool) -> bool {
+    !*val
+}
+
 #[cfg(test)]
 mod tests {
 
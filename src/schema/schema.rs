@@ -6,9 +6,10 @@ use std::sync::Arc;
 use serde_json::{self, Map as JsonObject, Value as JsonValue};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::ser::SerializeSeq;
-use serde::de::{SeqAccess, Visitor};
+use serde::de::{self, SeqAccess, Visitor};
 use super::*;
 use std::fmt;
+use ErrorKind;
 
 /// Tantivy has a very strict schema.
 /// You need to specify in advance whether a field is indexed or not,
@@ -95,6 +96,42 @@ impl SchemaBuilder {
         self.add_field(field_entry)
     }
 
+    /// Adds a new vector field.
+    /// Returns the associated field handle
+    ///
+    /// # Caution
+    ///
+    /// Appending two fields with the same name
+    /// will result in the shadowing of the first
+    /// by the second one.
+    /// The first field will get a field id
+    /// but only the second one will be indexed
+    pub fn add_vector_field(
+        &mut self,
+        field_name_str: &str,
+        field_options: VectorOptions,
+    ) -> Field {
+        let field_name = String::from(field_name_str);
+        let field_entry = FieldEntry::new_vector(field_name, field_options);
+        self.add_field(field_entry)
+    }
+
+    /// Adds a new bytes field.
+    /// Returns the associated field handle
+    ///
+    /// # Caution
+    ///
+    /// Appending two fields with the same name
+    /// will result in the shadowing of the first
+    /// by the second one.
+    /// The first field will get a field id
+    /// but only the second one will be indexed
+    pub fn add_bytes_field(&mut self, field_name_str: &str, field_options: BytesOptions) -> Field {
+        let field_name = String::from(field_name_str);
+        let field_entry = FieldEntry::new_bytes(field_name, field_options);
+        self.add_field(field_entry)
+    }
+
     /// Adds a field entry to the schema in build.
     fn add_field(&mut self, field_entry: FieldEntry) -> Field {
         let field = Field(self.fields.len() as u32);
@@ -104,14 +141,65 @@ impl SchemaBuilder {
         field
     }
 
+    /// Returns the name of a field added to the `SchemaBuilder`.
+    pub(crate) fn get_field_name(&self, field: Field) -> &str {
+        self.fields[field.0 as usize].name()
+    }
+
     /// Finalize the creation of a `Schema`
     /// This will consume your `SchemaBuilder`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the schema is invalid. (See `.try_build()`)
     pub fn build(self) -> Schema {
-        Schema(Arc::new(InnerSchema {
+        self.try_build().expect("Invalid schema")
+    }
+
+    /// Finalize the creation of a `Schema`
+    /// This will consume your `SchemaBuilder`
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgument` error if a unique key field is not indexed,
+    /// or is a text field that is not indexed with the `raw` tokenizer.
+    /// The documents sharing its key could not be found by their key
+    /// otherwise. (See `TextOptions::set_unique`)
+    pub fn try_build(self) -> ::Result<Schema> {
+        for field_entry in &self.fields {
+            check_unique_field(field_entry)?;
+        }
+        Ok(Schema(Arc::new(InnerSchema {
             fields: self.fields,
             fields_map: self.fields_map,
-        }))
+        })))
+    }
+}
+
+/// Checks that a document can be looked up by the term of its unique key,
+/// if the field is a unique key.
+fn check_unique_field(field_entry: &FieldEntry) -> ::Result<()> {
+    if !field_entry.is_unique() {
+        return Ok(());
     }
+    if !field_entry.is_indexed() {
+        bail!(ErrorKind::InvalidArgument(format!(
+            "The unique key field {:?} is not indexed",
+            field_entry.name()
+        )));
+    }
+    if let FieldType::Str(ref text_options) = *field_entry.field_type() {
+        let tokenizer = text_options
+            .get_indexing_options()
+            .map(|indexing_options| indexing_options.tokenizer());
+        if tokenizer != Some("raw") {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "The unique key field {:?} is not indexed with the raw tokenizer",
+                field_entry.name()
+            )));
+        }
+    }
+    Ok(())
 }
 
 impl Default for SchemaBuilder {
@@ -181,6 +269,34 @@ impl Schema {
         self.0.fields_map.get(field_name).cloned()
     }
 
+    /// Returns a copy of the schema, in which the `u64` or `i64`
+    /// field `field` is a single-valued fast field.
+    ///
+    /// Returns None if `field` is neither a `u64` nor an `i64` field.
+    pub(crate) fn with_fast_field(&self, field: Field) -> Option<Schema> {
+        let field_entry = self.get_field_entry(field);
+        let field_name = field_entry.name().to_string();
+        let fast_field_entry = match *field_entry.field_type() {
+            FieldType::U64(ref int_options) => FieldEntry::new_u64(
+                field_name,
+                int_options.clone().set_fast(Cardinality::SingleValue),
+            ),
+            FieldType::I64(ref int_options) => FieldEntry::new_i64(
+                field_name,
+                int_options.clone().set_fast(Cardinality::SingleValue),
+            ),
+            _ => {
+                return None;
+            }
+        };
+        let mut fields = self.0.fields.clone();
+        fields[field.0 as usize] = fast_field_entry;
+        Some(Schema(Arc::new(InnerSchema {
+            fields,
+            fields_map: self.0.fields_map.clone(),
+        })))
+    }
+
     /// Create a named document off the doc.
     pub fn to_named_doc(&self, doc: &Document) -> NamedFieldDocument {
         let mut field_map = BTreeMap::new();
@@ -221,13 +337,23 @@ impl Schema {
                 Some(field) => {
                     let field_entry = self.get_field_entry(field);
                     let field_type = field_entry.field_type();
-                    match *json_value {
-                        JsonValue::Array(ref json_items) => for json_item in json_items {
-                            let value = field_type
-                                .value_from_json(json_item)
-                                .map_err(|e| DocParsingError::ValueError(field_name.clone(), e))?;
-                            doc.add(FieldValue::new(field, value));
+                    let is_multivalued = match *json_value {
+                        JsonValue::Array(ref json_items) => match *field_type {
+                            // a vector is itself an array of numbers.
+                            FieldType::Vector(_) => json_items.iter().all(JsonValue::is_array),
+                            _ => true,
                         },
+                        _ => false,
+                    };
+                    match *json_value {
+                        JsonValue::Array(ref json_items) if is_multivalued => {
+                            for json_item in json_items {
+                                let value = field_type.value_from_json(json_item).map_err(|e| {
+                                    DocParsingError::ValueError(field_name.clone(), e)
+                                })?;
+                                doc.add(FieldValue::new(field, value));
+                            }
+                        }
                         _ => {
                             let value = field_type
                                 .value_from_json(json_value)
@@ -284,7 +410,9 @@ impl<'de> Deserialize<'de> for Schema {
                     schema.add_field(value);
                 }
 
-                Ok(schema.build())
+                schema
+                    .try_build()
+                    .map_err(|err| de::Error::custom(err.to_string()))
             }
         }
 
@@ -513,4 +641,65 @@ mod tests {
             assert_matches!(json_err, Err(NotJSON(_)));
         }
     }
+    #[test]
+    pub fn test_parse_document_vector() {
+        let mut schema_builder = SchemaBuilder::default();
+        let vector_field =
+            schema_builder.add_vector_field("embedding", VectorOptions::with_dims(2).set_stored());
+        let schema = schema_builder.build();
+        {
+            let doc = schema
+                .parse_document(r#"{"embedding": [0.5, -1]}"#)
+                .unwrap();
+            let vector = doc.get_first(vector_field).unwrap().vector();
+            assert_eq!(vector.as_slice(), &[0.5f32, -1f32]);
+            assert_eq!(schema.to_json(&doc), r#"{"embedding":[[0.5,-1.0]]}"#);
+        }
+        {
+            let doc = schema
+                .parse_document(r#"{"embedding": [[0.5, -1], [1, 2]]}"#)
+                .unwrap();
+            assert_eq!(doc.get_all(vector_field).len(), 2);
+        }
+        {
+            let json_err = schema.parse_document(r#"{"embedding": [0.5, -1, 3]}"#);
+            assert_matches!(
+                json_err,
+                Err(DocParsingError::ValueError(_, ValueParsingError::TypeError(_)))
+            );
+        }
+        {
+            let json_err = schema.parse_document(r#"{"embedding": 0.5}"#);
+            assert_matches!(
+                json_err,
+                Err(DocParsingError::ValueError(_, ValueParsingError::TypeError(_)))
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_parse_document_bytes() {
+        let mut schema_builder = SchemaBuilder::default();
+        let bytes_field =
+            schema_builder.add_bytes_field("payload", BytesOptions::default().set_stored());
+        let schema = schema_builder.build();
+        let doc = schema.parse_document(r#"{"payload": "Zm9vYg=="}"#).unwrap();
+        assert_eq!(doc.get_first(bytes_field).unwrap().bytes_value(), b"foob");
+        assert_eq!(schema.to_json(&doc), r#"{"payload":["Zm9vYg=="]}"#);
+        let json_err = schema.parse_document(r#"{"payload": "Zm9vY"}"#);
+        assert_matches!(
+            json_err,
+            Err(DocParsingError::ValueError(_, ValueParsingError::TypeError(_)))
+        );
+    }
+
+    #[test]
+    pub fn test_deserialize_schema_invalid_unique_field() {
+        let mut schema_builder = SchemaBuilder::default();
+        schema_builder.add_text_field("id", STRING.set_unique());
+        let schema_json = serde_json::to_string(&schema_builder.build()).unwrap();
+        assert!(serde_json::from_str::<Schema>(&schema_json).is_ok());
+        let tokenized_schema_json = schema_json.replace("\"raw\"", "\"default\"");
+        assert!(serde_json::from_str::<Schema>(&tokenized_schema_json).is_err());
+    }
 }
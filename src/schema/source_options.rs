@@ -0,0 +1,94 @@
+use schema::{Field, FieldType, Schema};
+
+/// Define how the original document is kept in a `_source`-like field.
+///
+/// When the index is configured with some `SourceOptions`
+/// (See `IndexSettings`), the `IndexWriter` automatically
+/// writes the JSON representation of each document
+/// (See `Document::to_named_json`) in the given stored text field.
+///
+/// The original document can then be retrieved with `Searcher::source`,
+/// even if its fields are not stored, for instance to reindex it.
+///
+/// If `includes` is not empty, only the fields listed in it are
+/// kept in the source. The fields listed in `excludes` are never kept.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceOptions {
+    field: String,
+    #[serde(default)]
+    includes: Vec<String>,
+    #[serde(default)]
+    excludes: Vec<String>,
+}
+
+impl SourceOptions {
+    /// Creates a new `SourceOptions`, writing the source of the documents
+    /// in the field named `field_name`.
+    ///
+    /// This field needs to be a stored text field.
+    pub fn new(field_name: &str) -> SourceOptions {
+        SourceOptions {
+            field: field_name.to_string(),
+            includes: vec![],
+            excludes: vec![],
+        }
+    }
+
+    /// Returns the name of the field the source is written in.
+    pub fn field_name(&self) -> &str {
+        &self.field
+    }
+
+    /// Only keep the field named `field_name` (and the other included fields)
+    /// in the source.
+    pub fn include(mut self, field_name: &str) -> SourceOptions {
+        self.includes.push(field_name.to_string());
+        self
+    }
+
+    /// Remove the field named `field_name` from the source.
+    pub fn exclude(mut self, field_name: &str) -> SourceOptions {
+        self.excludes.push(field_name.to_string());
+        self
+    }
+
+    /// Returns true iff the field named `field_name` should be kept in the source.
+    ///
+    /// The source field itself is never kept.
+    pub fn is_included(&self, field_name: &str) -> bool {
+        if field_name == self.field || self.excludes.iter().any(|name| name == field_name) {
+            return false;
+        }
+        self.includes.is_empty() || self.includes.iter().any(|name| name == field_name)
+    }
+
+    /// Returns the source field, if it is a stored text field of the `schema`.
+    pub(crate) fn resolve_field(&self, schema: &Schema) -> Option<Field> {
+        let field = schema.get_field(&self.field)?;
+        let field_entry = schema.get_field_entry(field);
+        match *field_entry.field_type() {
+            FieldType::Str(ref text_options) if text_options.is_stored() => Some(field),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::SourceOptions;
+
+    #[test]
+    fn test_source_options_is_included() {
+        let all_fields = SourceOptions::new("source");
+        assert!(all_fields.is_included("title"));
+        assert!(!all_fields.is_included("source"));
+        let source_options = SourceOptions::new("source")
+            .include("title")
+            .include("body")
+            .exclude("body");
+        assert!(source_options.is_included("title"));
+        assert!(!source_options.is_included("body"));
+        assert!(!source_options.is_included("timestamp"));
+    }
+}
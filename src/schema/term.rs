@@ -2,7 +2,8 @@ use std::fmt;
 
 use common;
 use byteorder::{BigEndian, ByteOrder};
-use super::Field;
+use super::{Facet, Field, FieldEntry, FieldType, Schema};
+use std::cmp::Ordering;
 use std::str;
 
 /// Size (in bytes) of the buffer of a int field.
@@ -11,6 +12,9 @@ const INT_TERM_LEN: usize = 4 + 8;
 /// Term represents the value that the token can take.
 ///
 /// It actually wraps a `Vec<u8>`.
+///
+/// Terms are ordered by field, and then by value.
+/// The encoding of `u64` and `i64` values preserves their natural order.
 #[derive(Clone, PartialEq, PartialOrd, Ord, Eq, Hash)]
 pub struct Term<B = Vec<u8>>(B)
 where
@@ -57,6 +61,33 @@ impl Term {
         term
     }
 
+    /// Builds a term given a field, and a facet.
+    ///
+    /// As the ancestors of a facet are indexed too, the term
+    /// of `/category` matches the documents of `/category/fiction`.
+    pub fn from_facet(field: Field, facet: &Facet) -> Term {
+        let facet_bytes = facet.encoded_bytes();
+        let mut buffer = Vec::with_capacity(4 + facet_bytes.len());
+        buffer.extend_from_slice(&[0u8; 4]);
+        buffer.extend_from_slice(facet_bytes);
+        let mut term = Term(buffer);
+        term.set_field(field);
+        term
+    }
+
+    /// Builds a term given a field, and a bytes value.
+    ///
+    /// The value of the term is the raw bytes, so that
+    /// it matches the exact same value of a bytes field.
+    pub fn from_field_bytes(field: Field, bytes: &[u8]) -> Term {
+        let mut buffer = Vec::with_capacity(4 + bytes.len());
+        buffer.extend_from_slice(&[0u8; 4]);
+        buffer.extend_from_slice(bytes);
+        let mut term = Term(buffer);
+        term.set_field(field);
+        term
+    }
+
     /// Creates a new Term with an empty buffer,
     /// but with a given capacity.
     ///
@@ -154,6 +185,110 @@ where
     pub fn as_slice(&self) -> &[u8] {
         self.0.as_ref()
     }
+
+    /// Returns the type of the term's field within the given schema,
+    /// or `None` if the field does not belong to the schema.
+    fn field_type<'a>(&self, schema: &'a Schema) -> Option<&'a FieldType> {
+        schema
+            .fields()
+            .get(self.field().0 as usize)
+            .map(FieldEntry::field_type)
+    }
+
+    /// Returns the `u64` value of the term.
+    ///
+    /// Returns `None` if the field of the term is not
+    /// a `u64` field of the schema.
+    pub fn as_u64(&self, schema: &Schema) -> Option<u64> {
+        match self.field_type(schema) {
+            Some(&FieldType::U64(_)) if self.value_bytes().len() == 8 => Some(self.get_u64()),
+            _ => None,
+        }
+    }
+
+    /// Returns the `i64` value of the term.
+    ///
+    /// Returns `None` if the field of the term is not
+    /// a `i64` field of the schema.
+    pub fn as_i64(&self, schema: &Schema) -> Option<i64> {
+        match self.field_type(schema) {
+            Some(&FieldType::I64(_)) if self.value_bytes().len() == 8 => Some(self.get_i64()),
+            _ => None,
+        }
+    }
+
+    /// Returns the text of the term.
+    ///
+    /// Returns `None` if the field of the term is not
+    /// a text field of the schema, or if the term is not valid utf-8.
+    pub fn as_text(&self, schema: &Schema) -> Option<&str> {
+        match self.field_type(schema) {
+            Some(&FieldType::Str(_)) => str::from_utf8(self.value_bytes()).ok(),
+            _ => None,
+        }
+    }
+
+    /// Compares the values of two terms.
+    ///
+    /// Returns `None` if the terms do not belong to the same field.
+    pub fn value_cmp<TOtherBytes: AsRef<[u8]>>(
+        &self,
+        other: &Term<TOtherBytes>,
+    ) -> Option<Ordering> {
+        if self.field() == other.field() {
+            Some(self.value_bytes().cmp(other.value_bytes()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an object implementing `Debug`, that displays the term
+    /// using the name and the type of its field in the schema.
+    /// (e.g. `title:"diary"`, `year:1960`, `category:/a/b`)
+    pub fn debug<'a>(&'a self, schema: &'a Schema) -> TermDebug<'a, B> {
+        TermDebug { term: self, schema }
+    }
+}
+
+/// Displays a `Term` using a `Schema`.
+///
+/// See [`Term::debug`](./struct.Term.html#method.debug).
+pub struct TermDebug<'a, B>
+where
+    B: AsRef<[u8]> + 'a,
+{
+    term: &'a Term<B>,
+    schema: &'a Schema,
+}
+
+impl<'a, B> fmt::Debug for TermDebug<'a, B>
+where
+    B: AsRef<[u8]>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let term = self.term;
+        let field_type = match term.field_type(self.schema) {
+            Some(field_type) => field_type,
+            None => return write!(f, "Term({:?})", term.as_slice()),
+        };
+        let field_name = self.schema.get_field_name(term.field());
+        if let Some(val) = term.as_u64(self.schema) {
+            return write!(f, "{}:{}", field_name, val);
+        }
+        if let Some(val) = term.as_i64(self.schema) {
+            return write!(f, "{}:{}", field_name, val);
+        }
+        if let Some(text) = term.as_text(self.schema) {
+            return write!(f, "{}:{:?}", field_name, text);
+        }
+        if let FieldType::HierarchicalFacet = *field_type {
+            if str::from_utf8(term.value_bytes()).is_ok() {
+                let facet = Facet::from_encoded(term.value_bytes().to_owned());
+                return write!(f, "{}:{}", field_name, facet);
+            }
+        }
+        write!(f, "{}:{:?}", field_name, term.value_bytes())
+    }
 }
 
 impl<B> AsRef<[u8]> for Term<B>
@@ -203,4 +338,63 @@ mod tests {
             assert_eq!(term.as_slice()[11], (983u64 % 256u64) as u8);
         }
     }
+
+    #[test]
+    pub fn test_term_typed_accessors() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title_field = schema_builder.add_text_field("title", STRING);
+        let year_field = schema_builder.add_u64_field("year", INT_INDEXED);
+        let delta_field = schema_builder.add_i64_field("delta", INT_INDEXED);
+        let category_field = schema_builder.add_facet_field("category");
+        let schema = schema_builder.build();
+
+        let text_term = Term::from_field_text(title_field, "diary");
+        assert_eq!(text_term.as_text(&schema), Some("diary"));
+        assert_eq!(text_term.as_u64(&schema), None);
+        assert_eq!(text_term.as_i64(&schema), None);
+
+        let u64_term = Term::from_field_u64(year_field, 1960u64);
+        assert_eq!(u64_term.as_u64(&schema), Some(1960u64));
+        assert_eq!(u64_term.as_i64(&schema), None);
+        assert_eq!(u64_term.as_text(&schema), None);
+
+        let i64_term = Term::from_field_i64(delta_field, -3i64);
+        assert_eq!(i64_term.as_i64(&schema), Some(-3i64));
+        assert_eq!(i64_term.as_u64(&schema), None);
+
+        // u64 encoded into a text field
+        let invalid_term = Term::from_field_u64(title_field, 3u64);
+        assert_eq!(invalid_term.as_u64(&schema), None);
+
+        assert_eq!(format!("{:?}", text_term.debug(&schema)), "title:\"diary\"");
+        assert_eq!(format!("{:?}", u64_term.debug(&schema)), "year:1960");
+        assert_eq!(format!("{:?}", i64_term.debug(&schema)), "delta:-3");
+        let facet = Facet::from("/a/b");
+        let facet_term = Term::from_facet(category_field, &facet);
+        assert_eq!(format!("{:?}", facet_term.debug(&schema)), "category:/a/b");
+        let unknown_term = Term::from_field_text(Field(10u32), "a");
+        assert_eq!(
+            format!("{:?}", unknown_term.debug(&schema)),
+            "Term([0, 0, 0, 10, 97])"
+        );
+    }
+
+    #[test]
+    pub fn test_term_value_cmp() {
+        use std::cmp::Ordering;
+        let field = Field(1u32);
+        let other_field = Field(2u32);
+        let term = |val: i64| Term::from_field_i64(field, val);
+        assert_eq!(term(-3).value_cmp(&term(2)), Some(Ordering::Less));
+        assert_eq!(term(2).value_cmp(&term(2)), Some(Ordering::Equal));
+        assert_eq!(term(3).value_cmp(&term(-2)), Some(Ordering::Greater));
+        assert_eq!(
+            term(-3).value_cmp(&Term::from_field_i64(other_field, 2)),
+            None
+        );
+        let wrapped_term = Term::wrap(term(2).as_slice().to_owned());
+        assert_eq!(term(2).value_cmp(&wrapped_term), Some(Ordering::Equal));
+        assert!(term(-3) < term(2));
+        assert!(term(3) < Term::from_field_i64(other_field, -2));
+    }
 }
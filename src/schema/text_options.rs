@@ -7,6 +7,8 @@ use schema::IndexRecordOption;
 pub struct TextOptions {
     indexing: Option<TextFieldIndexing>,
     stored: bool,
+    #[serde(default, skip_serializing_if = "super::is_false")]
+    unique: bool,
 }
 
 impl TextOptions {
@@ -20,17 +22,53 @@ impl TextOptions {
         self.stored
     }
 
+    /// Returns true iff the field is a unique key.
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
     /// Sets the field as stored
     pub fn set_stored(mut self) -> TextOptions {
         self.stored = true;
         self
     }
 
+    /// Sets the field as a unique key.
+    ///
+    /// Adding a document then deletes the documents added before it
+    /// with the same value for this field, be they committed or not.
+    /// (See `IndexWriter::add_document`)
+    ///
+    /// The field needs to be indexed with the `raw` tokenizer, as in `STRING`,
+    /// for the key to be indexed as a single term.
+    /// Otherwise, building the schema fails. (See `SchemaBuilder::try_build`)
+    pub fn set_unique(mut self) -> TextOptions {
+        self.unique = true;
+        self
+    }
+
     /// Sets the field as indexed, with the specific indexing options.
     pub fn set_indexing_options(mut self, indexing: TextFieldIndexing) -> TextOptions {
         self.indexing = Some(indexing);
         self
     }
+
+    /// Sets the tokenizer used for this field.
+    ///
+    /// The tokenizer is looked up by name in the `TokenizerManager`
+    /// of the index, both when indexing documents and when
+    /// parsing queries targeting this field.
+    ///
+    /// If the field was not indexed, it gets indexed with the default
+    /// [`TextFieldIndexing`](./struct.TextFieldIndexing.html) options.
+    pub fn set_tokenizer(mut self, tokenizer_name: &str) -> TextOptions {
+        let indexing = self.indexing
+            .take()
+            .unwrap_or_default()
+            .set_tokenizer(tokenizer_name);
+        self.indexing = Some(indexing);
+        self
+    }
 }
 
 impl Default for TextOptions {
@@ -38,6 +76,7 @@ impl Default for TextOptions {
         TextOptions {
             indexing: None,
             stored: false,
+            unique: false,
         }
     }
 }
@@ -97,6 +136,7 @@ pub const STRING: TextOptions = TextOptions {
         record: IndexRecordOption::Basic,
     }),
     stored: false,
+    unique: false,
 };
 
 /// The field will be tokenized and indexed
@@ -106,6 +146,7 @@ pub const TEXT: TextOptions = TextOptions {
         record: IndexRecordOption::WithFreqsAndPositions,
     }),
     stored: false,
+    unique: false,
 };
 
 /// A stored fields of a document can be retrieved given its `DocId`.
@@ -115,6 +156,7 @@ pub const TEXT: TextOptions = TextOptions {
 pub const STORED: TextOptions = TextOptions {
     indexing: None,
     stored: true,
+    unique: false,
 };
 
 impl BitOr for TextOptions {
@@ -124,6 +166,7 @@ impl BitOr for TextOptions {
         let mut res = TextOptions::default();
         res.indexing = self.indexing.or(other.indexing);
         res.stored = self.stored | other.stored;
+        res.unique = self.unique | other.unique;
         res
     }
 }
@@ -160,9 +203,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_set_tokenizer() {
+        let text_options = TEXT.set_tokenizer("en_stem");
+        let indexing_options = text_options.get_indexing_options().unwrap();
+        assert_eq!(indexing_options.tokenizer(), "en_stem");
+        assert_eq!(
+            indexing_options.index_option(),
+            IndexRecordOption::WithFreqsAndPositions
+        );
+        let text_options = STORED.set_tokenizer("raw");
+        assert!(text_options.is_stored());
+        assert_eq!(
+            text_options.get_indexing_options().unwrap().tokenizer(),
+            "raw"
+        );
+    }
+
     #[test]
     fn test_cmp_index_record_option() {
-        assert!(IndexRecordOption::WithFreqsAndPositions > IndexRecordOption::WithFreqs);
-        assert!(IndexRecordOption::WithFreqs > IndexRecordOption::Basic);
+        assert!(
+            IndexRecordOption::WithFreqsPositionsAndOffsets
+                .covers(IndexRecordOption::WithFreqsAndPositions)
+        );
+        assert!(IndexRecordOption::WithFreqsAndPositions.covers(IndexRecordOption::WithFreqs));
+        assert!(IndexRecordOption::WithFreqs.covers(IndexRecordOption::Basic));
+        assert!(IndexRecordOption::WithFreqsAndPositions.covers(IndexRecordOption::WithPositions));
+        assert!(IndexRecordOption::WithPositions.covers(IndexRecordOption::Basic));
+        assert!(!IndexRecordOption::WithPositions.covers(IndexRecordOption::WithFreqs));
+        assert!(!IndexRecordOption::WithFreqs.covers(IndexRecordOption::WithPositions));
+        assert!(
+            !IndexRecordOption::WithPositions
+                .covers(IndexRecordOption::WithFreqsPositionsAndOffsets)
+        );
     }
 }
@@ -1,7 +1,11 @@
 use std::fmt;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde::de::Visitor;
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::de::value::MapAccessDeserializer;
 use schema::Facet;
+use schema::Vector;
+use common::base64;
+use tokenizer::PreTokenizedString;
 
 /// Value represents the value of a any field.
 /// It is an enum over all over all of the possible field type.
@@ -15,6 +19,12 @@ pub enum Value {
     I64(i64),
     /// Hierarchical Facet
     Facet(Facet),
+    /// Dense vector of `f32`
+    Vector(Vector),
+    /// Raw binary value
+    Bytes(Vec<u8>),
+    /// Text, with the tokens computed by an external tokenizer.
+    PreTokStr(PreTokenizedString),
 }
 
 impl Serialize for Value {
@@ -27,6 +37,9 @@ impl Serialize for Value {
             Value::U64(u) => serializer.serialize_u64(u),
             Value::I64(u) => serializer.serialize_i64(u),
             Value::Facet(ref facet) => facet.serialize(serializer),
+            Value::Vector(ref vector) => vector.serialize(serializer),
+            Value::Bytes(ref bytes) => serializer.serialize_str(&base64::encode(bytes)),
+            Value::PreTokStr(ref pre_tokenized) => pre_tokenized.serialize(serializer),
         }
     }
 }
@@ -60,6 +73,26 @@ impl<'de> Deserialize<'de> for Value {
             fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
                 Ok(Value::Str(v))
             }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut vals: Vec<f32> = Vec::new();
+                while let Some(val) = seq.next_element()? {
+                    vals.push(val);
+                }
+                Ok(Value::Vector(Vector::from(vals)))
+            }
+
+            fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let map_deserializer = MapAccessDeserializer::new(map);
+                let pre_tokenized = PreTokenizedString::deserialize(map_deserializer)?;
+                Ok(Value::PreTokStr(pre_tokenized))
+            }
         }
 
         deserializer.deserialize_any(ValueVisitor)
@@ -67,13 +100,15 @@ impl<'de> Deserialize<'de> for Value {
 }
 
 impl Value {
-    /// Returns the text value, provided the value is of the `Str` type.
+    /// Returns the text value, provided the value is of the `Str`
+    /// or of the `PreTokStr` type.
     ///
     /// # Panics
-    /// If the value is not of type `Str`
+    /// If the value is not of type `Str` or `PreTokStr`
     pub fn text(&self) -> &str {
         match *self {
             Value::Str(ref text) => text,
+            Value::PreTokStr(ref pre_tokenized) => &pre_tokenized.text,
             _ => panic!("This is not a text field."),
         }
     }
@@ -99,6 +134,28 @@ impl Value {
             _ => panic!("This is not a text field."),
         }
     }
+
+    /// Returns the vector, provided the value is of the `Vector` type.
+    ///
+    /// # Panics
+    /// If the value is not of type `Vector`
+    pub fn vector(&self) -> &Vector {
+        match *self {
+            Value::Vector(ref vector) => vector,
+            _ => panic!("This is not a vector field."),
+        }
+    }
+
+    /// Returns the bytes, provided the value is of the `Bytes` type.
+    ///
+    /// # Panics
+    /// If the value is not of type `Bytes`
+    pub fn bytes_value(&self) -> &[u8] {
+        match *self {
+            Value::Bytes(ref bytes) => bytes,
+            _ => panic!("This is not a bytes field."),
+        }
+    }
 }
 
 impl From<String> for Value {
@@ -131,16 +188,44 @@ impl<'a> From<Facet> for Value {
     }
 }
 
+impl From<PreTokenizedString> for Value {
+    fn from(pre_tokenized: PreTokenizedString) -> Value {
+        Value::PreTokStr(pre_tokenized)
+    }
+}
+
+impl From<Vector> for Value {
+    fn from(vector: Vector) -> Value {
+        Value::Vector(vector)
+    }
+}
+
+impl From<Vec<f32>> for Value {
+    fn from(vals: Vec<f32>) -> Value {
+        Value::Vector(Vector::from(vals))
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Value {
+        Value::Bytes(bytes)
+    }
+}
+
 mod binary_serialize {
-    use common::BinarySerializable;
+    use common::{BinarySerializable, VInt};
     use std::io::{self, Read, Write};
     use super::Value;
-    use schema::Facet;
+    use schema::{Facet, Vector};
+    use tokenizer::PreTokenizedString;
 
     const TEXT_CODE: u8 = 0;
     const U64_CODE: u8 = 1;
     const I64_CODE: u8 = 2;
     const HIERARCHICAL_FACET_CODE: u8 = 3;
+    const VECTOR_CODE: u8 = 4;
+    const BYTES_CODE: u8 = 5;
+    const PRE_TOKENIZED_TEXT_CODE: u8 = 6;
 
     impl BinarySerializable for Value {
         fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
@@ -161,6 +246,18 @@ mod binary_serialize {
                     HIERARCHICAL_FACET_CODE.serialize(writer)?;
                     facet.serialize(writer)
                 }
+                Value::Vector(ref vector) => {
+                    VECTOR_CODE.serialize(writer)?;
+                    vector.serialize(writer)
+                }
+                Value::Bytes(ref bytes) => {
+                    BYTES_CODE.serialize(writer)?;
+                    bytes.serialize(writer)
+                }
+                Value::PreTokStr(ref pre_tokenized) => {
+                    PRE_TOKENIZED_TEXT_CODE.serialize(writer)?;
+                    pre_tokenized.serialize(writer)
+                }
             }
         }
         fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
@@ -179,6 +276,11 @@ mod binary_serialize {
                     Ok(Value::I64(value))
                 }
                 HIERARCHICAL_FACET_CODE => Ok(Value::Facet(Facet::deserialize(reader)?)),
+                VECTOR_CODE => Ok(Value::Vector(Vector::deserialize(reader)?)),
+                BYTES_CODE => Ok(Value::Bytes(Vec::<u8>::deserialize(reader)?)),
+                PRE_TOKENIZED_TEXT_CODE => Ok(Value::PreTokStr(PreTokenizedString::deserialize(
+                    reader,
+                )?)),
                 _ => Err(io::Error::new(
                     io::ErrorKind::InvalidData,
                     format!("No field type is associated with code {:?}", type_code),
@@ -186,4 +288,36 @@ mod binary_serialize {
             }
         }
     }
+
+    impl Value {
+        /// Advances the reader past a serialized value, without decoding it.
+        pub(crate) fn skip_serialized(reader: &mut &[u8]) -> io::Result<()> {
+            let type_code = u8::deserialize(reader)?;
+            let num_bytes = match type_code {
+                TEXT_CODE | HIERARCHICAL_FACET_CODE | BYTES_CODE => {
+                    VInt::deserialize(reader)?.val() as usize
+                }
+                U64_CODE | I64_CODE => 8,
+                VECTOR_CODE => <u32 as BinarySerializable>::deserialize(reader)? as usize * 4,
+                PRE_TOKENIZED_TEXT_CODE => {
+                    PreTokenizedString::deserialize(reader)?;
+                    0
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("No field type is associated with code {:?}", type_code),
+                    ))
+                }
+            };
+            if num_bytes > reader.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Truncated serialized value",
+                ));
+            }
+            *reader = &reader[num_bytes..];
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,129 @@
+use common::BinarySerializable;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::io::{self, Read, Write};
+
+/// A dense vector of `f32`, the value of a vector field.
+///
+/// So that `Value` can be totally ordered, vectors are compared
+/// over the bit representation of their components: a `NaN`
+/// component is equal to itself, and `-0.0` differs from `0.0`.
+#[derive(Clone, Debug, Default)]
+pub struct Vector(Vec<f32>);
+
+/// Maps a float to an integer preserving the order
+/// of the floats, for all of the values but `NaN`.
+fn total_order_key(val: f32) -> i32 {
+    let bits = val.to_bits() as i32;
+    bits ^ ((((bits >> 31) as u32) >> 1) as i32)
+}
+
+impl Vector {
+    /// Returns the number of dimensions of the vector.
+    pub fn dims(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns the components of the vector.
+    pub fn as_slice(&self) -> &[f32] {
+        &self.0[..]
+    }
+}
+
+impl From<Vec<f32>> for Vector {
+    fn from(vals: Vec<f32>) -> Vector {
+        Vector(vals)
+    }
+}
+
+impl<'a> From<&'a [f32]> for Vector {
+    fn from(vals: &'a [f32]) -> Vector {
+        Vector(vals.to_vec())
+    }
+}
+
+impl PartialEq for Vector {
+    fn eq(&self, other: &Vector) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Vector {}
+
+impl PartialOrd for Vector {
+    fn partial_cmp(&self, other: &Vector) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Vector {
+    fn cmp(&self, other: &Vector) -> Ordering {
+        let keys = self.0.iter().cloned().map(total_order_key);
+        let other_keys = other.0.iter().cloned().map(total_order_key);
+        keys.cmp(other_keys)
+    }
+}
+
+impl BinarySerializable for Vector {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        BinarySerializable::serialize(&(self.0.len() as u32), writer)?;
+        for &val in &self.0 {
+            BinarySerializable::serialize(&val.to_bits(), writer)?;
+        }
+        Ok(())
+    }
+
+    fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let dims = <u32 as BinarySerializable>::deserialize(reader)? as usize;
+        let mut vals = Vec::with_capacity(dims);
+        for _ in 0..dims {
+            let bits = <u32 as BinarySerializable>::deserialize(reader)?;
+            vals.push(f32::from_bits(bits));
+        }
+        Ok(Vector(vals))
+    }
+}
+
+impl Serialize for Vector {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Vector {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        <Vec<f32> as Deserialize<'de>>::deserialize(deserializer).map(Vector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Vector;
+    use common::BinarySerializable;
+
+    #[test]
+    fn test_vector_order() {
+        let vector = |vals: &[f32]| Vector::from(vals);
+        assert!(vector(&[-1.5, 2.0]) < vector(&[-0.5, 0.0]));
+        assert!(vector(&[-0.0]) < vector(&[0.0]));
+        assert!(vector(&[0.5]) < vector(&[0.5, -3.0]));
+        assert_eq!(vector(&[::std::f32::NAN]), vector(&[::std::f32::NAN]));
+    }
+
+    #[test]
+    fn test_vector_binary_serialization() {
+        let vector = Vector::from(vec![0.25f32, -1.0, 3.5]);
+        let mut buffer = Vec::new();
+        BinarySerializable::serialize(&vector, &mut buffer).unwrap();
+        assert_eq!(buffer.len(), 4 + 3 * 4);
+        let deserialized = <Vector as BinarySerializable>::deserialize(&mut &buffer[..]).unwrap();
+        assert_eq!(deserialized.as_slice(), &[0.25f32, -1.0, 3.5]);
+    }
+}
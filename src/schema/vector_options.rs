@@ -0,0 +1,44 @@
+/// Define how a vector field should be handled by tantivy.
+///
+/// Vector fields hold, for each document, a dense vector of `f32`
+/// with a fixed number of dimensions.
+/// They are stored in a column-oriented fashion, like fast fields,
+/// and are typically consumed by a `VectorSimilarityQuery`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorOptions {
+    dims: usize,
+    stored: bool,
+}
+
+impl VectorOptions {
+    /// Creates the options of a vector field of `dims` dimensions.
+    ///
+    /// # Panics
+    /// Panics if `dims` is 0.
+    pub fn with_dims(dims: usize) -> VectorOptions {
+        assert!(dims > 0, "A vector field must have at least one dimension.");
+        VectorOptions {
+            dims,
+            stored: false,
+        }
+    }
+
+    /// Returns the number of dimensions of the vectors.
+    pub fn dims(&self) -> usize {
+        self.dims
+    }
+
+    /// Returns true iff the value is stored.
+    pub fn is_stored(&self) -> bool {
+        self.stored
+    }
+
+    /// Set the vector options as stored.
+    ///
+    /// Only the fields that are set as *stored* are
+    /// persisted into the Tantivy's store.
+    pub fn set_stored(mut self) -> VectorOptions {
+        self.stored = true;
+        self
+    }
+}
@@ -0,0 +1,122 @@
+use lz4;
+use std::io::{self, Read, Write};
+
+/// Compression level used by `Compressor::Lz4HighCompression`.
+const LZ4_HIGH_COMPRESSION_LEVEL: u32 = 9;
+
+/// Codec used to compress the blocks of the doc store.
+///
+/// It is defined at the creation of the index, in its `IndexSettings`.
+/// Each block records the codec it was compressed with, so that
+/// a store may contain blocks compressed with different codecs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compressor {
+    /// The blocks are not compressed.
+    #[serde(rename = "none")]
+    None,
+    /// The blocks are compressed with `LZ4`.
+    ///
+    /// This is the default, favoring speed.
+    #[serde(rename = "lz4")]
+    Lz4,
+    /// The blocks are compressed with `LZ4HC`.
+    ///
+    /// Its compression is slower, but has a higher ratio,
+    /// and decompresses as fast as `LZ4`.
+    /// It suits indexes that are mostly archived.
+    #[serde(rename = "lz4hc")]
+    Lz4HighCompression,
+}
+
+impl Default for Compressor {
+    fn default() -> Compressor {
+        Compressor::Lz4
+    }
+}
+
+impl Compressor {
+    /// Identifier of the codec within the store.
+    pub(crate) fn id(&self) -> u8 {
+        match *self {
+            Compressor::None => 0,
+            Compressor::Lz4 => 1,
+            Compressor::Lz4HighCompression => 2,
+        }
+    }
+
+    pub(crate) fn from_id(id: u8) -> Option<Compressor> {
+        match id {
+            0 => Some(Compressor::None),
+            1 => Some(Compressor::Lz4),
+            2 => Some(Compressor::Lz4HighCompression),
+            _ => None,
+        }
+    }
+
+    /// Appends the compressed bytes of `uncompressed` to `compressed`.
+    pub(crate) fn compress(&self, uncompressed: &[u8], compressed: &mut Vec<u8>) -> io::Result<()> {
+        let level = match *self {
+            Compressor::None => {
+                compressed.extend_from_slice(uncompressed);
+                return Ok(());
+            }
+            Compressor::Lz4 => 0,
+            Compressor::Lz4HighCompression => LZ4_HIGH_COMPRESSION_LEVEL,
+        };
+        let mut encoder = lz4::EncoderBuilder::new().level(level).build(compressed)?;
+        encoder.write_all(uncompressed)?;
+        let (_, encoder_result) = encoder.finish();
+        encoder_result
+    }
+
+    /// Appends the decompressed bytes of `compressed` to `uncompressed`.
+    pub(crate) fn decompress(
+        &self,
+        compressed: &[u8],
+        uncompressed: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        match *self {
+            Compressor::None => {
+                uncompressed.extend_from_slice(compressed);
+            }
+            Compressor::Lz4 | Compressor::Lz4HighCompression => {
+                let mut lz4_decoder = lz4::Decoder::new(compressed)?;
+                lz4_decoder.read_to_end(uncompressed)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::Compressor;
+    use serde_json;
+
+    #[test]
+    fn test_compressor_roundtrip() {
+        let text = "lorem ipsum dolor sit amet ".repeat(100);
+        for &compressor in &[
+            Compressor::None,
+            Compressor::Lz4,
+            Compressor::Lz4HighCompression,
+        ] {
+            assert_eq!(Compressor::from_id(compressor.id()), Some(compressor));
+            let mut compressed = vec![];
+            compressor
+                .compress(text.as_bytes(), &mut compressed)
+                .unwrap();
+            let mut decompressed = vec![];
+            compressor
+                .decompress(&compressed, &mut decompressed)
+                .unwrap();
+            assert_eq!(decompressed, text.as_bytes());
+        }
+        assert_eq!(Compressor::from_id(3), None);
+        assert_eq!(
+            serde_json::to_string(&Compressor::Lz4HighCompression).unwrap(),
+            r#""lz4hc""#
+        );
+    }
+}
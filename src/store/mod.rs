@@ -5,9 +5,14 @@ A field needs to be marked as stored in the schema in
 order to be handled in the `Store`.
 
 Internally, documents (or rather their stored fields) are serialized to a buffer.
-When the buffer exceeds 16K, the buffer is compressed using `LZ4`
+When the buffer exceeds 16K, the buffer is compressed
 and the resulting block is written to disk.
 
+The codec is defined by the `Compressor` of the `IndexSettings`:
+`LZ4` by default, `LZ4HC` for a higher compression ratio,
+or no compression at all.
+Each block records the codec it was compressed with.
+
 One can then request for a specific `DocId`.
 A skip list helps navigating to the right block,
 decompresses it entirely and returns the document within it.
@@ -31,13 +36,21 @@ and should rely on either
 - at the index level, the
 [`Searcher`'s `doc` method](../struct.Searcher.html#method.doc)
 
+Their `doc_fields` counterparts only decode the values of
+the requested fields, skipping over the other ones.
+
 !*/
 
+mod compressor;
 mod reader;
 mod writer;
+pub use self::compressor::Compressor;
 pub use self::reader::StoreReader;
 pub use self::writer::StoreWriter;
 
+/// Version of the store format, written in the footer of the store.
+const STORE_FORMAT_VERSION: u32 = 1;
+
 #[cfg(test)]
 mod tests {
 
@@ -48,9 +61,17 @@ mod tests {
     use schema::TextOptions;
     use schema::FieldValue;
     use schema::Document;
-    use directory::{Directory, MmapDirectory, RAMDirectory, WritePtr};
+    use directory::{Directory, MmapDirectory, RAMDirectory, ReadOnlySource, WritePtr};
+    use std::io::Write;
+    use directory::ManagedDirectory;
+    use schema::STORED;
+    use {DocAddress, Index, IndexSettings};
 
-    fn write_lorem_ipsum_store(writer: WritePtr, num_docs: usize) -> Schema {
+    fn write_lorem_ipsum_store(
+        writer: WritePtr,
+        num_docs: usize,
+        compressor: Compressor,
+    ) -> Schema {
         let mut schema_builder = SchemaBuilder::default();
         let field_body = schema_builder.add_text_field("body", TextOptions::default().set_stored());
         let field_title =
@@ -67,7 +88,7 @@ mod tests {
              laborum.",
         );
         {
-            let mut store_writer = StoreWriter::new(writer);
+            let mut store_writer = StoreWriter::new(writer, compressor);
             for i in 0..num_docs {
                 let mut fields: Vec<FieldValue> = Vec::new();
                 {
@@ -90,27 +111,143 @@ mod tests {
 
     #[test]
     fn test_store() {
+        for &compressor in &[
+            Compressor::None,
+            Compressor::Lz4,
+            Compressor::Lz4HighCompression,
+        ] {
+            let path = Path::new("store");
+            let mut directory = RAMDirectory::create();
+            let store_file = directory.open_write(path).unwrap();
+            let schema = write_lorem_ipsum_store(store_file, 1_000, compressor);
+            let field_title = schema.get_field("title").unwrap();
+            let store_source = directory.open_read(path).unwrap();
+            let store = StoreReader::from_source(store_source).unwrap();
+            for i in 0..1_000 {
+                assert_eq!(
+                    *store.get(i).unwrap().get_first(field_title).unwrap().text(),
+                    format!("Doc {}", i)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_store_get_fields() {
         let path = Path::new("store");
         let mut directory = RAMDirectory::create();
         let store_file = directory.open_write(path).unwrap();
-        let schema = write_lorem_ipsum_store(store_file, 1_000);
+        let schema = write_lorem_ipsum_store(store_file, 1_000, Compressor::Lz4);
         let field_title = schema.get_field("title").unwrap();
+        let field_body = schema.get_field("body").unwrap();
         let store_source = directory.open_read(path).unwrap();
-        let store = StoreReader::from_source(store_source);
+        let store = StoreReader::from_source(store_source).unwrap();
         for i in 0..1_000 {
+            let doc = store.get_fields(i, &[field_title]).unwrap();
+            assert_eq!(doc.len(), 1);
+            assert_eq!(*doc.get_first(field_title).unwrap().text(), format!("Doc {}", i));
+        }
+        assert_eq!(store.get_fields(7, &[field_body, field_title]).unwrap(), store.get(7).unwrap());
+        assert!(store.get_fields(7, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_store_stack_compressors() {
+        let mut directory = RAMDirectory::create();
+        let lz4_path = Path::new("lz4_store");
+        write_lorem_ipsum_store(directory.open_write(lz4_path).unwrap(), 100, Compressor::Lz4);
+        let path = Path::new("store");
+        let store_file = directory.open_write(path).unwrap();
+        let schema = write_lorem_ipsum_store(store_file, 100, Compressor::None);
+        let field_title = schema.get_field("title").unwrap();
+        let stacked_path = Path::new("stacked_store");
+        {
+            let lz4_store =
+                StoreReader::from_source(directory.open_read(lz4_path).unwrap()).unwrap();
+            let store = StoreReader::from_source(directory.open_read(path).unwrap()).unwrap();
+            let stacked_file = directory.open_write(stacked_path).unwrap();
+            let mut store_writer = StoreWriter::new(stacked_file, Compressor::None);
+            store_writer.stack(&lz4_store).unwrap();
+            store_writer.stack(&store).unwrap();
+            store_writer.close().unwrap();
+        }
+        let stacked_store =
+            StoreReader::from_source(directory.open_read(stacked_path).unwrap()).unwrap();
+        for i in 0..200 {
             assert_eq!(
-                *store.get(i).unwrap().get_first(field_title).unwrap().text(),
-                format!("Doc {}", i)
+                *stacked_store.get(i).unwrap().get_first(field_title).unwrap().text(),
+                format!("Doc {}", i % 100)
             );
         }
     }
 
+    #[test]
+    fn test_store_unsupported_version() {
+        let path = Path::new("store");
+        let mut directory = RAMDirectory::create();
+        {
+            let mut store_file = directory.open_write(path).unwrap();
+            store_file.write_all(&[0u8; 20]).unwrap();
+            store_file.flush().unwrap();
+        }
+        assert!(StoreReader::from_source(directory.open_read(path).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_store_corrupted() {
+        let path = Path::new("store");
+        let mut directory = RAMDirectory::create();
+        let store_file = directory.open_write(path).unwrap();
+        write_lorem_ipsum_store(store_file, 100, Compressor::None);
+        let data = directory.open_read(path).unwrap().as_slice().to_vec();
+
+        // the offset index address is out of bounds.
+        let mut invalid_offset = data.clone();
+        let footer_offset = invalid_offset.len() - 16;
+        for b in &mut invalid_offset[footer_offset..footer_offset + 8] {
+            *b = 255u8;
+        }
+        assert!(StoreReader::from_source(ReadOnlySource::from(invalid_offset)).is_err());
+
+        // the length of the first block is out of bounds.
+        let mut invalid_block_len = data.clone();
+        for b in &mut invalid_block_len[..4] {
+            *b = 255u8;
+        }
+        let store = StoreReader::from_source(ReadOnlySource::from(invalid_block_len)).unwrap();
+        assert!(store.get(0).is_err());
+    }
+
+    #[test]
+    fn test_index_store_compressor() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", STORED);
+        let schema = schema_builder.build();
+        let directory = ManagedDirectory::new(RAMDirectory::create()).unwrap();
+        let settings = IndexSettings {
+            store_compressor: Compressor::Lz4HighCompression,
+            ..IndexSettings::default()
+        };
+        let index = Index::from_directory_with_settings(directory, schema, settings).unwrap();
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "hello"));
+            index_writer.commit().unwrap();
+        }
+        let metas = index.load_metas().unwrap();
+        assert_eq!(metas.settings.store_compressor, Compressor::Lz4HighCompression);
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let doc = searcher.doc(&DocAddress(0, 0)).unwrap();
+        assert_eq!(doc.get_first(text_field).unwrap().text(), "hello");
+    }
+
     #[bench]
     fn bench_store_encode(b: &mut Bencher) {
         let mut directory = MmapDirectory::create_from_tempdir().unwrap();
         let path = Path::new("store");
         b.iter(|| {
-            write_lorem_ipsum_store(directory.open_write(path).unwrap(), 1_000);
+            write_lorem_ipsum_store(directory.open_write(path).unwrap(), 1_000, Compressor::Lz4);
             directory.delete(path).unwrap();
         });
     }
@@ -119,9 +256,9 @@ mod tests {
     fn bench_store_decode(b: &mut Bencher) {
         let mut directory = MmapDirectory::create_from_tempdir().unwrap();
         let path = Path::new("store");
-        write_lorem_ipsum_store(directory.open_write(path).unwrap(), 1_000);
+        write_lorem_ipsum_store(directory.open_write(path).unwrap(), 1_000, Compressor::Lz4);
         let store_source = directory.open_read(path).unwrap();
-        let store = StoreReader::from_source(store_source);
+        let store = StoreReader::from_source(store_source).unwrap();
         b.iter(|| {
             store.get(12).unwrap();
         });
@@ -3,13 +3,13 @@ use Result;
 use directory::ReadOnlySource;
 use std::cell::RefCell;
 use DocId;
-use schema::Document;
+use schema::{Document, Field};
 use common::BinarySerializable;
 use std::mem::size_of;
-use std::io::{self, Read};
+use std::io;
 use common::VInt;
 use datastruct::SkipList;
-use lz4;
+use super::{Compressor, STORE_FORMAT_VERSION};
 
 /// Reads document off tantivy's [`Store`](./index.html)
 #[derive(Clone)]
@@ -23,15 +23,19 @@ pub struct StoreReader {
 
 impl StoreReader {
     /// Opens a store reader
-    pub fn from_source(data: ReadOnlySource) -> StoreReader {
-        let (data_source, offset_index_source, max_doc) = split_source(data);
-        StoreReader {
+    ///
+    /// # Errors
+    /// Returns an `InvalidData` error if the store was written
+    /// in another version of the store format.
+    pub fn from_source(data: ReadOnlySource) -> io::Result<StoreReader> {
+        let (data_source, offset_index_source, max_doc) = split_source(data)?;
+        Ok(StoreReader {
             data: data_source,
             offset_index_source,
             current_block_offset: RefCell::new(usize::max_value()),
             current_block: RefCell::new(Vec::new()),
             max_doc,
-        }
+        })
     }
 
     pub(crate) fn block_index(&self) -> SkipList<u64> {
@@ -49,60 +53,120 @@ impl StoreReader {
         self.data.as_slice()
     }
 
-    fn compressed_block(&self, addr: usize) -> &[u8] {
+    fn compressed_block(&self, addr: usize) -> io::Result<(Compressor, &[u8])> {
         let total_buffer = self.data.as_slice();
+        if addr > total_buffer.len() {
+            return Err(corrupted_store("block address out of bounds"));
+        }
         let mut buffer = &total_buffer[addr..];
-        let block_len = u32::deserialize(&mut buffer).expect("") as usize;
-        &buffer[..block_len]
+        let block_len = u32::deserialize(&mut buffer)? as usize;
+        let compressor_id = u8::deserialize(&mut buffer)?;
+        let compressor = Compressor::from_id(compressor_id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown store compressor {}", compressor_id),
+            )
+        })?;
+        if block_len > buffer.len() {
+            return Err(corrupted_store("truncated block"));
+        }
+        Ok((compressor, &buffer[..block_len]))
     }
 
     fn read_block(&self, block_offset: usize) -> io::Result<()> {
         if block_offset != *self.current_block_offset.borrow() {
             let mut current_block_mut = self.current_block.borrow_mut();
             current_block_mut.clear();
-            let compressed_block = self.compressed_block(block_offset);
-            let mut lz4_decoder = lz4::Decoder::new(compressed_block)?;
+            let (compressor, compressed_block) = self.compressed_block(block_offset)?;
             *self.current_block_offset.borrow_mut() = usize::max_value();
-            lz4_decoder.read_to_end(&mut current_block_mut).map(|_| ())?;
+            compressor.decompress(compressed_block, &mut current_block_mut)?;
             *self.current_block_offset.borrow_mut() = block_offset;
         }
         Ok(())
     }
 
-    /// Reads a given document.
-    ///
-    /// Calling `.get(doc)` is relatively costly as it requires
-    /// decompressing a LZ4-compressed block.
-    ///
-    /// It should not be called to score documents
-    /// for instance.
-    pub fn get(&self, doc_id: DocId) -> Result<Document> {
+    fn read_doc<T, F>(&self, doc_id: DocId, deserialize: F) -> Result<T>
+    where
+        F: FnOnce(&mut &[u8]) -> io::Result<T>,
+    {
         let (first_doc_id, block_offset) = self.block_offset(doc_id);
         self.read_block(block_offset as usize)?;
         let current_block_mut = self.current_block.borrow_mut();
         let mut cursor = &current_block_mut[..];
         for _ in first_doc_id..doc_id {
             let doc_length = VInt::deserialize(&mut cursor)?.val() as usize;
+            if doc_length > cursor.len() {
+                return Err(corrupted_store("truncated document").into());
+            }
             cursor = &cursor[doc_length..];
         }
         let doc_length = VInt::deserialize(&mut cursor)?.val() as usize;
+        if doc_length > cursor.len() {
+            return Err(corrupted_store("truncated document").into());
+        }
         cursor = &cursor[..doc_length];
-        Ok(Document::deserialize(&mut cursor)?)
+        Ok(deserialize(&mut cursor)?)
+    }
+
+    /// Reads a given document.
+    ///
+    /// Calling `.get(doc)` is relatively costly as it requires
+    /// decompressing a compressed block.
+    ///
+    /// It should not be called to score documents
+    /// for instance.
+    pub fn get(&self, doc_id: DocId) -> Result<Document> {
+        self.read_doc(doc_id, |cursor| Document::deserialize(cursor))
+    }
+
+    /// Reads the values of the given fields of a document.
+    ///
+    /// The block still needs to be decompressed, but the values
+    /// of the other fields are skipped rather than decoded.
+    pub fn get_fields(&self, doc_id: DocId, fields: &[Field]) -> Result<Document> {
+        self.read_doc(doc_id, |cursor| Document::deserialize_fields(cursor, fields))
     }
 }
 
 #[allow(needless_pass_by_value)]
-fn split_source(data: ReadOnlySource) -> (ReadOnlySource, ReadOnlySource, DocId) {
+fn split_source(data: ReadOnlySource) -> io::Result<(ReadOnlySource, ReadOnlySource, DocId)> {
     let data_len = data.len();
-    let footer_offset = data_len - size_of::<u64>() - size_of::<u32>();
+    let footer_len = size_of::<u64>() + 2 * size_of::<u32>();
+    if data_len < footer_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "The store is too short",
+        ));
+    }
+    let footer_offset = data_len - footer_len;
     let serialized_offset: ReadOnlySource = data.slice(footer_offset, data_len);
     let mut serialized_offset_buf = serialized_offset.as_slice();
-    let offset = u64::deserialize(&mut serialized_offset_buf).unwrap();
+    let offset = u64::deserialize(&mut serialized_offset_buf)?;
     let offset = offset as usize;
-    let max_doc = u32::deserialize(&mut serialized_offset_buf).unwrap();
-    (
+    let max_doc = u32::deserialize(&mut serialized_offset_buf)?;
+    let format_version = u32::deserialize(&mut serialized_offset_buf)?;
+    if format_version != STORE_FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Unsupported store format version {} (expected {})",
+                format_version, STORE_FORMAT_VERSION
+            ),
+        ));
+    }
+    if offset > footer_offset {
+        return Err(corrupted_store("offset index address out of bounds"));
+    }
+    Ok((
         data.slice(0, offset),
         data.slice(offset, footer_offset),
         max_doc,
+    ))
+}
+
+fn corrupted_store(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Corrupted store: {}", msg),
     )
 }
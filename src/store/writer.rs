@@ -2,8 +2,7 @@ use directory::WritePtr;
 use DocId;
 use common::{BinarySerializable, VInt};
 use std::io::{self, Write};
-use super::StoreReader;
-use lz4;
+use super::{Compressor, StoreReader, STORE_FORMAT_VERSION};
 use datastruct::SkipListBuilder;
 use common::CountingWriter;
 use schema::Document;
@@ -24,20 +23,22 @@ pub struct StoreWriter {
     writer: CountingWriter<WritePtr>,
     intermediary_buffer: Vec<u8>,
     current_block: Vec<u8>,
+    compressor: Compressor,
 }
 
 impl StoreWriter {
     /// Create a store writer.
     ///
     /// The store writer will writes blocks on disc as
-    /// document are added.
-    pub fn new(writer: WritePtr) -> StoreWriter {
+    /// document are added, compressed with `compressor`.
+    pub fn new(writer: WritePtr, compressor: Compressor) -> StoreWriter {
         StoreWriter {
             doc: 0,
             offset_index_writer: SkipListBuilder::new(4),
             writer: CountingWriter::wrap(writer),
             intermediary_buffer: Vec::new(),
             current_block: Vec::new(),
+            compressor,
         }
     }
 
@@ -87,13 +88,10 @@ impl StoreWriter {
 
     fn write_and_compress_block(&mut self) -> io::Result<()> {
         self.intermediary_buffer.clear();
-        {
-            let mut encoder = lz4::EncoderBuilder::new().build(&mut self.intermediary_buffer)?;
-            encoder.write_all(&self.current_block)?;
-            let (_, encoder_result) = encoder.finish();
-            encoder_result?;
-        }
+        self.compressor
+            .compress(&self.current_block, &mut self.intermediary_buffer)?;
         (self.intermediary_buffer.len() as u32).serialize(&mut self.writer)?;
+        self.compressor.id().serialize(&mut self.writer)?;
         self.writer.write_all(&self.intermediary_buffer)?;
         self.offset_index_writer
             .insert(u64::from(self.doc), &(self.writer.written_bytes() as u64))?;
@@ -113,6 +111,7 @@ impl StoreWriter {
         self.offset_index_writer.write(&mut self.writer)?;
         header_offset.serialize(&mut self.writer)?;
         self.doc.serialize(&mut self.writer)?;
+        STORE_FORMAT_VERSION.serialize(&mut self.writer)?;
         self.writer.flush()
     }
 }
@@ -20,6 +20,7 @@ struct TermInfoBlockMeta {
     doc_freq_nbits: u8,
     postings_offset_nbits: u8,
     positions_offset_nbits: u8,
+    inlined_doc_nbits: u8,
 }
 
 impl BinarySerializable for TermInfoBlockMeta {
@@ -30,6 +31,7 @@ impl BinarySerializable for TermInfoBlockMeta {
             self.doc_freq_nbits,
             self.postings_offset_nbits,
             self.positions_offset_nbits,
+            self.inlined_doc_nbits,
         ])?;
         Ok(())
     }
@@ -37,7 +39,7 @@ impl BinarySerializable for TermInfoBlockMeta {
     fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
         let offset = u64::deserialize(reader)?;
         let ref_term_info = TermInfo::deserialize(reader)?;
-        let mut buffer = [0u8; 3];
+        let mut buffer = [0u8; 4];
         reader.read_exact(&mut buffer)?;
         Ok(TermInfoBlockMeta {
             offset,
@@ -45,18 +47,53 @@ impl BinarySerializable for TermInfoBlockMeta {
             doc_freq_nbits: buffer[0],
             postings_offset_nbits: buffer[1],
             positions_offset_nbits: buffer[2],
+            inlined_doc_nbits: buffer[3],
         })
     }
 }
 
 impl FixedSize for TermInfoBlockMeta {
     const SIZE_IN_BYTES: usize =
-        u64::SIZE_IN_BYTES + TermInfo::SIZE_IN_BYTES + 3 * u8::SIZE_IN_BYTES;
+        u64::SIZE_IN_BYTES + TermInfo::SIZE_IN_BYTES + 4 * u8::SIZE_IN_BYTES;
 }
 
 impl TermInfoBlockMeta {
     fn num_bits(&self) -> u8 {
         self.doc_freq_nbits + self.postings_offset_nbits + self.positions_offset_nbits + 7
+            + self.inlined_doc_nbits
+    }
+
+    /// Checks that the `num_term_infos` bitpacked term infos of the block
+    /// can be read from `term_info_data`, and that their offsets do not overflow.
+    fn check(&self, term_info_data: &[u8], num_term_infos: usize) -> io::Result<()> {
+        if self.doc_freq_nbits > 32 || self.inlined_doc_nbits > 32
+            || self.postings_offset_nbits > 56
+            || self.positions_offset_nbits > 56
+        {
+            return Err(corrupted_term_info_store("invalid number of bits"));
+        }
+        let max_offset = |nbits: u8| (1u64 << nbits) - 1;
+        if self.ref_term_info
+            .postings_offset
+            .checked_add(max_offset(self.postings_offset_nbits))
+            .is_none()
+            || self.ref_term_info
+                .positions_offset
+                .checked_add(max_offset(self.positions_offset_nbits))
+                .is_none()
+        {
+            return Err(corrupted_term_info_store("the offsets overflow"));
+        }
+        // `extract_bits` reads 8 bytes at a time, hence the 7 bytes of padding.
+        let num_bytes = (self.num_bits() as usize * num_term_infos + 7) / 8 + 7;
+        let is_truncated = (self.offset as usize)
+            .checked_add(num_bytes)
+            .map(|end| end > term_info_data.len())
+            .unwrap_or(true);
+        if is_truncated {
+            return Err(corrupted_term_info_store("the term infos are truncated"));
+        }
+        Ok(())
     }
 
     fn deserialize_term_info(&self, data: &[u8], inner_offset: usize) -> TermInfo {
@@ -73,24 +110,34 @@ impl TermInfoBlockMeta {
         cursor += self.positions_offset_nbits as usize;
 
         let positions_inner_offset = extract_bits(data, cursor, 7) as u8;
+        cursor += 7;
+
+        let inlined_doc_code = extract_bits(data, cursor, self.inlined_doc_nbits) as u32;
 
-        TermInfo {
+        let mut term_info = TermInfo {
             doc_freq,
             postings_offset: postings_offset + self.ref_term_info.postings_offset,
             positions_offset: positions_offset + self.ref_term_info.positions_offset,
             positions_inner_offset,
-        }
+            inlined_doc: None,
+        };
+        term_info.set_inlined_doc_code(inlined_doc_code);
+        term_info
     }
 }
 
 pub struct TermInfoStore {
     num_terms: usize,
-    block_meta_source: ReadOnlySource,
+    block_metas: Vec<TermInfoBlockMeta>,
     term_info_source: ReadOnlySource,
 }
 
 fn extract_bits(data: &[u8], addr_bits: usize, num_bits: u8) -> u64 {
     assert!(num_bits <= 56);
+    if num_bits == 0 {
+        // `addr_bits` may point at the end of the data.
+        return 0u64;
+    }
     let addr_byte = addr_bits / 8;
     let bit_shift = (addr_bits % 8) as u64;
     let val_unshifted_unmasked: u64 = unsafe { *(data[addr_byte..].as_ptr() as *const u64) };
@@ -99,29 +146,63 @@ fn extract_bits(data: &[u8], addr_bits: usize, num_bits: u8) -> u64 {
     val_shifted_unmasked & mask
 }
 
+fn corrupted_term_info_store(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Corrupted term info store: {}", msg),
+    )
+}
+
 impl TermInfoStore {
-    pub fn open(data: &ReadOnlySource) -> TermInfoStore {
+    /// Opens a term info store.
+    ///
+    /// The block metas are deserialized and checked against the term infos,
+    /// so that `.get(...)` can then read them without panicking.
+    pub fn open(data: &ReadOnlySource) -> io::Result<TermInfoStore> {
         let buffer = data.as_slice();
+        if buffer.len() < 16 {
+            return Err(corrupted_term_info_store("the header is truncated"));
+        }
         let len = Endianness::read_u64(&buffer[0..8]) as usize;
         let num_terms = Endianness::read_u64(&buffer[8..16]) as usize;
-        let block_meta_source = data.slice(16, 16 + len);
+        if len > buffer.len() - 16 {
+            return Err(corrupted_term_info_store("the block metas are truncated"));
+        }
+        let num_blocks = num_terms.saturating_add(BLOCK_LEN - 1) / BLOCK_LEN;
+        if num_blocks.checked_mul(TermInfoBlockMeta::SIZE_IN_BYTES) != Some(len) {
+            return Err(corrupted_term_info_store(
+                "the number of terms does not match the block metas",
+            ));
+        }
         let term_info_source = data.slice_from(16 + len);
-        TermInfoStore {
+        let mut block_meta_data: &[u8] = &buffer[16..16 + len];
+        let mut block_metas = Vec::with_capacity(num_blocks);
+        for block_id in 0..num_blocks {
+            let block_meta = TermInfoBlockMeta::deserialize(&mut block_meta_data)?;
+            let num_term_infos = cmp::min(num_terms - block_id * BLOCK_LEN, BLOCK_LEN) - 1;
+            block_meta.check(term_info_source.as_slice(), num_term_infos)?;
+            block_metas.push(block_meta);
+        }
+        Ok(TermInfoStore {
             num_terms,
-            block_meta_source,
+            block_metas,
             term_info_source,
-        }
+        })
     }
 
+    /// Returns the term info of the given term ordinal.
+    ///
+    /// A corrupted term dictionary may yield ordinals beyond the number
+    /// of terms. They are associated to an empty `TermInfo`.
     pub fn get(&self, term_ord: TermOrdinal) -> TermInfo {
+        if term_ord >= self.num_terms as u64 {
+            return TermInfo::default();
+        }
         let block_id = (term_ord as usize) / BLOCK_LEN;
-        let buffer = self.block_meta_source.as_slice();
-        let mut block_data: &[u8] = &buffer[block_id * TermInfoBlockMeta::SIZE_IN_BYTES..];
-        let term_info_block_data = TermInfoBlockMeta::deserialize(&mut block_data)
-            .expect("Failed to deserialize terminfoblockmeta");
+        let term_info_block_data = &self.block_metas[block_id];
         let inner_offset = (term_ord as usize) % BLOCK_LEN;
         if inner_offset == 0 {
-            term_info_block_data.ref_term_info
+            term_info_block_data.ref_term_info.clone()
         } else {
             let term_info_data = self.term_info_source.as_slice();
             term_info_block_data.deserialize_term_info(
@@ -165,6 +246,11 @@ fn bitpack_serialize<W: Write>(
         write,
     )?;
     bit_packer.write(u64::from(term_info.positions_inner_offset), 7, write)?;
+    bit_packer.write(
+        u64::from(term_info.inlined_doc_code()),
+        term_info_block_meta.inlined_doc_nbits,
+        write,
+    )?;
     Ok(())
 }
 
@@ -192,15 +278,18 @@ impl TermInfoStoreWriter {
         let mut max_doc_freq: u32 = 0u32;
         let mut max_postings_offset: u64 = 0u64;
         let mut max_positions_offset: u64 = 0u64;
+        let mut max_inlined_doc_code: u32 = 0u32;
         for term_info in &self.term_infos[1..] {
             max_doc_freq = cmp::max(max_doc_freq, term_info.doc_freq);
             max_postings_offset = cmp::max(max_postings_offset, term_info.postings_offset);
             max_positions_offset = cmp::max(max_positions_offset, term_info.positions_offset);
+            max_inlined_doc_code = cmp::max(max_inlined_doc_code, term_info.inlined_doc_code());
         }
 
         let max_doc_freq_nbits: u8 = compute_num_bits(u64::from(max_doc_freq));
         let max_postings_offset_nbits = compute_num_bits(max_postings_offset);
         let max_positions_offset_nbits = compute_num_bits(max_positions_offset);
+        let max_inlined_doc_nbits = compute_num_bits(u64::from(max_inlined_doc_code));
 
         let term_info_block_meta = TermInfoBlockMeta {
             offset: self.buffer_term_infos.len() as u64,
@@ -208,6 +297,7 @@ impl TermInfoStoreWriter {
             doc_freq_nbits: max_doc_freq_nbits,
             postings_offset_nbits: max_postings_offset_nbits,
             positions_offset_nbits: max_positions_offset_nbits,
+            inlined_doc_nbits: max_inlined_doc_nbits,
         };
 
         term_info_block_meta.serialize(&mut self.buffer_block_metas)?;
@@ -255,7 +345,7 @@ mod tests {
 
     use super::extract_bits;
     use common::bitpacker::BitPacker;
-    use common::BinarySerializable;
+    use common::{BinarySerializable, FixedSize};
     use super::TermInfoBlockMeta;
     use super::{TermInfoStore, TermInfoStoreWriter};
     use directory::ReadOnlySource;
@@ -294,10 +384,12 @@ mod tests {
                 postings_offset: 51,
                 positions_offset: 3584,
                 positions_inner_offset: 0,
+                inlined_doc: None,
             },
             doc_freq_nbits: 10,
             postings_offset_nbits: 5,
             positions_offset_nbits: 11,
+            inlined_doc_nbits: 3,
         };
         let mut buffer: Vec<u8> = Vec::new();
         term_info_block_meta.serialize(&mut buffer).unwrap();
@@ -316,16 +408,46 @@ mod tests {
                 postings_offset: (i / 10) as u64,
                 positions_offset: (i * 7) as u64,
                 positions_inner_offset: (i % 128) as u8,
+                inlined_doc: if i % 3 == 1 { Some(i as u32 * 11) } else { None },
             };
             store_writer.write_term_info(&term_info).unwrap();
             term_infos.push(term_info);
         }
         let mut buffer = Vec::new();
         store_writer.serialize(&mut buffer).unwrap();
-        let term_info_store = TermInfoStore::open(&ReadOnlySource::from(buffer));
+        let term_info_store = TermInfoStore::open(&ReadOnlySource::from(buffer)).unwrap();
         for i in 0..1000 {
             assert_eq!(term_info_store.get(i as u64), term_infos[i]);
         }
     }
 
+    #[test]
+    fn test_open_corrupted() {
+        let mut store_writer = TermInfoStoreWriter::new();
+        for i in 0..1000u64 {
+            let term_info = TermInfo {
+                doc_freq: i as u32,
+                postings_offset: i * 1_000,
+                positions_offset: i,
+                positions_inner_offset: 0,
+                inlined_doc: None,
+            };
+            store_writer.write_term_info(&term_info).unwrap();
+        }
+        let mut buffer = Vec::new();
+        store_writer.serialize(&mut buffer).unwrap();
+        {
+            let term_info_store = TermInfoStore::open(&ReadOnlySource::from(buffer.clone()))
+                .unwrap();
+            // out of range ordinals are associated to an empty term info.
+            assert_eq!(term_info_store.get(1_000u64), TermInfo::default());
+        }
+        let truncated = buffer[..buffer.len() - 8].to_vec();
+        assert!(TermInfoStore::open(&ReadOnlySource::from(truncated)).is_err());
+        // the postings offset of the first block is bitpacked over 64 bits.
+        let mut invalid_num_bits = buffer.clone();
+        invalid_num_bits[16 + u64::SIZE_IN_BYTES + TermInfo::SIZE_IN_BYTES + 1] = 64u8;
+        assert!(TermInfoStore::open(&ReadOnlySource::from(invalid_num_bits)).is_err());
+    }
+
 }
@@ -13,6 +13,13 @@ fn convert_fst_error(e: fst::Error) -> io::Error {
     io::Error::new(io::ErrorKind::Other, e)
 }
 
+fn corrupted_term_dictionary(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Corrupted term dictionary: {}", msg),
+    )
+}
+
 /// See [`TermDictionaryBuilder`](./trait.TermDictionaryBuilder.html)
 pub struct TermDictionaryBuilderImpl<W> {
     fst_builder: fst::MapBuilder<W>,
@@ -81,16 +88,16 @@ where
     }
 }
 
-fn open_fst_index(source: ReadOnlySource) -> fst::Map {
+fn open_fst_index(source: ReadOnlySource) -> io::Result<fst::Map> {
     let fst = match source {
         ReadOnlySource::Anonymous(data) => {
-            Fst::from_shared_bytes(data.data, data.start, data.len).expect("FST data is corrupted")
+            Fst::from_shared_bytes(data.data, data.start, data.len).map_err(convert_fst_error)?
         }
         ReadOnlySource::Mmap(mmap_readonly) => {
-            Fst::from_mmap(mmap_readonly).expect("FST data is corrupted")
+            Fst::from_mmap(mmap_readonly).map_err(convert_fst_error)?
         }
     };
-    fst::Map::from(fst)
+    Ok(fst::Map::from(fst))
 }
 
 /// See [`TermDictionary`](./trait.TermDictionary.html)
@@ -104,20 +111,36 @@ impl<'a> TermDictionary<'a> for TermDictionaryImpl {
 
     type StreamBuilder = TermStreamerBuilderImpl<'a>;
 
-    fn from_source(source: ReadOnlySource) -> Self {
+    fn open(source: ReadOnlySource) -> io::Result<Self> {
         let total_len = source.len();
+        if total_len < 8 {
+            return Err(corrupted_term_dictionary("the footer is truncated"));
+        }
         let length_offset = total_len - 8;
         let mut split_len_buffer: &[u8] = &source.as_slice()[length_offset..];
-        let footer_size = u64::deserialize(&mut split_len_buffer)
-            .expect("Deserializing 8 bytes should always work") as usize;
+        let footer_size = u64::deserialize(&mut split_len_buffer)? as usize;
+        if footer_size > length_offset {
+            return Err(corrupted_term_dictionary("the footer size is invalid"));
+        }
         let split_len = length_offset - footer_size;
         let fst_source = source.slice(0, split_len);
         let values_source = source.slice(split_len, length_offset);
-        let fst_index = open_fst_index(fst_source);
-        TermDictionaryImpl {
+        let fst_index = open_fst_index(fst_source)?;
+        let term_info_store = TermInfoStore::open(&values_source)?;
+        Ok(TermDictionaryImpl {
             fst_index,
-            term_info_store: TermInfoStore::open(&values_source),
-        }
+            term_info_store,
+        })
+    }
+
+    fn empty(field_type: FieldType) -> Self {
+        let term_dictionary_data: Vec<u8> =
+            TermDictionaryBuilderImpl::new(Vec::<u8>::new(), field_type)
+                .expect("Creating a TermDictionaryBuilder in a Vec<u8> should never fail")
+                .finish()
+                .expect("Writing in a Vec<u8> should never fail");
+        Self::open(ReadOnlySource::from(term_dictionary_data))
+            .expect("An empty term dictionary should never be corrupted")
     }
 
     fn num_terms(&self) -> usize {
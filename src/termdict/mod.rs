@@ -29,8 +29,11 @@ performance, to the detriment of `lookup` performance.
 
 `streamdict` can be enabled by adding the `streamdict`
 feature when compiling `tantivy`.
+The `bench_termdict_*` benchmarks measure the lookup and
+range seek performance of the term dictionary.
 
-`streamdict` encodes each term relatively to the precedent
+`streamdict` splits the sorted terms into blocks of `128` terms.
+Within a block, each term is encoded relatively to the precedent
 as follows.
 
 - number of bytes that needs to be popped.
@@ -38,13 +41,16 @@ as follows.
 - sequence of bytes that is to be added
 - value.
 
+The first term of each block is written in full, so that
+a block can be decoded without decoding the previous ones.
+
 Because such a structure does not allow for lookups,
-it comes with a `fst` that indexes 1 out of `1024`
-terms in this structure.
+it comes with a small in-memory index of the first key of each
+block.
 
-A `lookup` therefore consists in a lookup in the `fst`
-followed by a streaming through at most `1024` elements in the
-term `stream`.
+A `lookup`, or the seek at the beginning of a range, therefore
+consists in a binary search in this index followed by
+a streaming through at most `128` elements in the term `stream`.
 */
 
 use schema::{Field, FieldType, Term};
@@ -83,7 +89,13 @@ where
     type StreamBuilder: TermStreamerBuilder<Streamer = Self::Streamer> + 'a;
 
     /// Opens a `TermDictionary` given a data source.
-    fn from_source(source: ReadOnlySource) -> Self;
+    ///
+    /// Returns an error if the data is corrupted.
+    fn open(source: ReadOnlySource) -> io::Result<Self>;
+
+    /// Returns a `TermDictionary` without any term, e.g. for
+    /// a field having no term in a segment.
+    fn empty(field_type: FieldType) -> Self;
 
     /// Returns the number of terms in the dictionary.
     /// Term ordinals range from 0 to `num_terms() - 1`.
@@ -194,6 +206,33 @@ pub trait TermStreamer: Sized {
             None
         }
     }
+
+    /// Turns the stream into an `Iterator` over the `(key, value)` pairs,
+    /// so that it can be used with the standard iterator adapters.
+    ///
+    /// Each key is copied into a new `Vec<u8>`.
+    fn into_iter_owned(self) -> OwnedTermIter<Self> {
+        OwnedTermIter { streamer: self }
+    }
+}
+
+/// `Iterator` over the owned `(key, value)` pairs of a `TermStreamer`.
+///
+/// (See `TermStreamer::into_iter_owned`)
+pub struct OwnedTermIter<S: TermStreamer> {
+    streamer: S,
+}
+
+impl<S: TermStreamer> Iterator for OwnedTermIter<S> {
+    type Item = (Vec<u8>, TermInfo);
+
+    fn next(&mut self) -> Option<(Vec<u8>, TermInfo)> {
+        if self.streamer.advance() {
+            Some((self.streamer.key().to_owned(), self.streamer.value().clone()))
+        } else {
+            None
+        }
+    }
 }
 
 /// `TermStreamerBuilder` is an helper object used to define
@@ -232,6 +271,7 @@ mod tests {
     use termdict::TermDictionary;
     use termdict::TermDictionaryBuilder;
     use postings::TermInfo;
+    use test::Bencher;
 
     const BLOCK_SIZE: usize = 1_500;
 
@@ -241,6 +281,7 @@ mod tests {
             positions_offset: val * 2u64,
             postings_offset: val * 3u64,
             positions_inner_offset: 5u8,
+            inlined_doc: None,
         }
     }
 
@@ -270,13 +311,29 @@ mod tests {
             term_dictionary_builder.finish().unwrap();
         }
         let source = directory.open_read(&path).unwrap();
-        let term_dict: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dict: TermDictionaryImpl = TermDictionaryImpl::open(source).unwrap();
         for (term_ord, term) in COUNTRIES.iter().enumerate() {
             assert_eq!(term_dict.term_ord(term).unwrap(), term_ord as u64);
             let mut bytes = vec![];
             assert!(term_dict.ord_to_term(term_ord as u64, &mut bytes));
             assert_eq!(bytes, term.as_bytes());
         }
+        let countries_with_e: Vec<String> = term_dict
+            .stream()
+            .into_iter_owned()
+            .map(|(key, _)| String::from_utf8(key).unwrap())
+            .filter(|country| country.contains('e'))
+            .take(3)
+            .collect();
+        assert_eq!(countries_with_e, vec!["Serbia", "Slovenia", "Sweden"]);
+        let term_ords: Vec<u64> = term_dict
+            .range()
+            .ge("Slovenia")
+            .into_stream()
+            .into_iter_owned()
+            .map(|(key, _)| term_dict.term_ord(key).unwrap())
+            .collect();
+        assert_eq!(term_ords, vec![3, 4, 5, 6]);
     }
 
     #[test]
@@ -297,7 +354,7 @@ mod tests {
             term_dictionary_builder.finish().unwrap();
         }
         let source = directory.open_read(&path).unwrap();
-        let term_dict: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dict: TermDictionaryImpl = TermDictionaryImpl::open(source).unwrap();
         assert_eq!(term_dict.get("abc").unwrap().doc_freq, 34u32);
         assert_eq!(term_dict.get("abcd").unwrap().doc_freq, 346u32);
         let mut stream = term_dict.stream();
@@ -357,7 +414,7 @@ mod tests {
         index.load_searchers().unwrap();
         let searcher = index.searcher();
 
-        let field_searcher = searcher.field(text_field);
+        let field_searcher = searcher.field(text_field).unwrap();
         let mut term_it = field_searcher.terms();
         let mut term_string = String::new();
         while term_it.advance() {
@@ -384,7 +441,7 @@ mod tests {
             term_dictionary_builder.finish().unwrap()
         };
         let source = ReadOnlySource::from(buffer);
-        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::open(source).unwrap();
         {
             let mut streamer = term_dictionary.stream();
             let mut i = 0;
@@ -419,7 +476,7 @@ mod tests {
             term_dictionary_builder.finish().unwrap()
         };
         let source = ReadOnlySource::from(buffer);
-        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::open(source).unwrap();
         let mut kv_stream = term_dictionary.stream();
         assert!(kv_stream.advance());
         assert_eq!(kv_stream.key(), "abcdefghijklmnopqrstuvwxy".as_bytes());
@@ -451,7 +508,7 @@ mod tests {
 
         let source = ReadOnlySource::from(buffer);
 
-        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::open(source).unwrap();
         {
             for i in (0..20).chain(6000..8_000) {
                 let &(ref target_key, _) = &ids[i];
@@ -519,7 +576,7 @@ mod tests {
             term_dictionary_builder.finish().unwrap()
         };
         let source = ReadOnlySource::from(buffer);
-        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::open(source).unwrap();
         let mut stream = term_dictionary.stream();
         assert!(stream.advance());
         assert!(stream.key().is_empty());
@@ -543,7 +600,7 @@ mod tests {
             term_dictionary_builder.finish().unwrap()
         };
         let source = ReadOnlySource::from(buffer);
-        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::open(source).unwrap();
 
         let value_list = |mut streamer: TermStreamerImpl| {
             let mut res: Vec<u32> = vec![];
@@ -583,4 +640,47 @@ mod tests {
         }
     }
 
+    fn build_lookup_bench_dictionary() -> (Vec<String>, TermDictionaryImpl) {
+        let keys: Vec<String> = (0u32..100_000u32)
+            .map(|i| format!("doc{:0>6}", i))
+            .collect();
+        let field_type = FieldType::Str(TEXT);
+        let mut term_dictionary_builder =
+            TermDictionaryBuilderImpl::new(vec![], field_type).unwrap();
+        for (i, key) in keys.iter().enumerate() {
+            term_dictionary_builder
+                .insert(key.as_bytes(), &make_term_info(i as u64))
+                .unwrap();
+        }
+        let buffer = term_dictionary_builder.finish().unwrap();
+        let term_dictionary = TermDictionaryImpl::open(ReadOnlySource::from(buffer)).unwrap();
+        (keys, term_dictionary)
+    }
+
+    #[bench]
+    fn bench_termdict_lookup(b: &mut Bencher) {
+        let (keys, term_dictionary) = build_lookup_bench_dictionary();
+        b.iter(|| {
+            let mut doc_freq_sum = 0u32;
+            for key in Iterator::step_by(keys.iter(), 997) {
+                doc_freq_sum += term_dictionary.get(key.as_bytes()).unwrap().doc_freq;
+            }
+            doc_freq_sum
+        });
+    }
+
+    #[bench]
+    fn bench_termdict_range_seek(b: &mut Bencher) {
+        let (keys, term_dictionary) = build_lookup_bench_dictionary();
+        b.iter(|| {
+            let mut doc_freq_sum = 0u32;
+            for key in Iterator::step_by(keys.iter(), 997) {
+                let mut streamer = term_dictionary.range().gt(key.as_bytes()).into_stream();
+                if let Some((_, term_info)) = streamer.next() {
+                    doc_freq_sum += term_info.doc_freq;
+                }
+            }
+            doc_freq_sum
+        });
+    }
 }
@@ -0,0 +1,136 @@
+use std::io::{self, Read, Write};
+use common::{BinarySerializable, VInt};
+use super::CheckPoint;
+
+/// In-memory index of the blocks of the term stream.
+///
+/// For each block, it keeps the first key of the block,
+/// as well as the `CheckPoint` required to start decoding it.
+///
+/// Keys are sorted, so that the block that may
+/// contain a given key can be found by binary search.
+#[derive(Default)]
+pub struct BlockIndex {
+    keys: Vec<u8>,
+    key_offsets: Vec<usize>,
+    checkpoints: Vec<CheckPoint>,
+}
+
+impl BlockIndex {
+    /// Appends a block.
+    ///
+    /// Blocks have to be pushed in the order of their first key.
+    pub fn push(&mut self, first_key: &[u8], checkpoint: CheckPoint) {
+        self.key_offsets.push(self.keys.len());
+        self.keys.extend_from_slice(first_key);
+        self.checkpoints.push(checkpoint);
+    }
+
+    /// Returns the number of blocks.
+    pub fn num_blocks(&self) -> usize {
+        self.checkpoints.len()
+    }
+
+    /// Returns the first key of the block `block_ord`.
+    pub fn first_key(&self, block_ord: usize) -> &[u8] {
+        let start = self.key_offsets[block_ord];
+        let stop = self.key_offsets
+            .get(block_ord + 1)
+            .cloned()
+            .unwrap_or_else(|| self.keys.len());
+        &self.keys[start..stop]
+    }
+
+    /// Returns the checkpoint of the block `block_ord`.
+    pub fn checkpoint(&self, block_ord: usize) -> &CheckPoint {
+        &self.checkpoints[block_ord]
+    }
+
+    /// Returns the ordinal of the last block whose first key
+    /// is lower or equal to `key`.
+    ///
+    /// If `key` is lower than all of the keys, or if there are no blocks,
+    /// returns `0`.
+    pub fn block_containing(&self, key: &[u8]) -> usize {
+        // Invariant: the first key of all of the blocks before `start`
+        // is lower or equal to `key`, and the first key of all of
+        // the blocks after `stop` is greater.
+        let mut start = 0;
+        let mut stop = self.num_blocks();
+        while start < stop {
+            let mid = start + (stop - start) / 2;
+            if self.first_key(mid) <= key {
+                start = mid + 1;
+            } else {
+                stop = mid;
+            }
+        }
+        if start > 0 {
+            start - 1
+        } else {
+            0
+        }
+    }
+
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for block_ord in 0..self.num_blocks() {
+            let first_key = self.first_key(block_ord);
+            VInt(first_key.len() as u64).serialize(writer)?;
+            writer.write_all(first_key)?;
+            self.checkpoints[block_ord].serialize(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn deserialize<R: Read>(reader: &mut R, num_blocks: usize) -> io::Result<BlockIndex> {
+        let mut block_index = BlockIndex::default();
+        let mut key = vec![];
+        for _ in 0..num_blocks {
+            let key_len = VInt::deserialize_u64(reader)?;
+            // the key is not preallocated, as its length may be corrupted.
+            key.clear();
+            (&mut *reader).take(key_len).read_to_end(&mut key)?;
+            if key.len() as u64 != key_len {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "The block index is truncated.",
+                ));
+            }
+            let checkpoint = CheckPoint::deserialize(reader)?;
+            block_index.push(&key, checkpoint);
+        }
+        Ok(block_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::BlockIndex;
+    use super::super::CheckPoint;
+
+    #[test]
+    fn test_block_index() {
+        let mut block_index = BlockIndex::default();
+        assert_eq!(block_index.block_containing(b"abc"), 0);
+        for (stream_offset, key) in [&b"b"[..], b"bcd", b"d"].iter().enumerate() {
+            let checkpoint = CheckPoint {
+                stream_offset: stream_offset as u64,
+                ..CheckPoint::default()
+            };
+            block_index.push(key, checkpoint);
+        }
+        let mut buffer = vec![];
+        block_index.serialize(&mut buffer).unwrap();
+        let block_index = BlockIndex::deserialize(&mut &buffer[..], 3).unwrap();
+        assert_eq!(block_index.num_blocks(), 3);
+        assert_eq!(block_index.first_key(1), b"bcd");
+        assert_eq!(block_index.checkpoint(2).stream_offset, 2);
+        assert_eq!(block_index.block_containing(b"a"), 0);
+        assert_eq!(block_index.block_containing(b"b"), 0);
+        assert_eq!(block_index.block_containing(b"bc"), 0);
+        assert_eq!(block_index.block_containing(b"bcd"), 1);
+        assert_eq!(block_index.block_containing(b"c"), 1);
+        assert_eq!(block_index.block_containing(b"zzz"), 2);
+    }
+}
@@ -1,7 +1,7 @@
 use postings::TermInfo;
 use super::CheckPoint;
-use std::mem;
-use common::BinarySerializable;
+use std::io::{self, Write};
+use common::{BinarySerializable, VInt};
 
 /// Returns the len of the longest
 /// common prefix of `s1` and `s2`.
@@ -28,8 +28,13 @@ impl TermDeltaEncoder {
         self.last_term.extend_from_slice(&term[self.prefix_len..]);
     }
 
-    pub fn term(&self) -> &[u8] {
-        &self.last_term[..]
+    /// Forgets about the previous term, so that the next
+    /// term is encoded in full.
+    ///
+    /// This is called at the beginning of each block, so that
+    /// blocks can be decoded independently.
+    pub fn reset(&mut self) {
+        self.last_term.clear();
     }
 
     pub fn prefix_suffix(&mut self) -> (usize, &[u8]) {
@@ -44,9 +49,7 @@ pub struct TermDeltaDecoder {
 
 impl TermDeltaDecoder {
     pub fn with_previous_term(term: Vec<u8>) -> TermDeltaDecoder {
-        TermDeltaDecoder {
-            term: Vec::from(term),
-        }
+        TermDeltaDecoder { term }
     }
 
     // code
@@ -67,7 +70,7 @@ impl TermDeltaDecoder {
             let suffix_len = u32::deserialize(&mut cursor).unwrap();
             (prefix_len as usize, suffix_len as usize)
         };
-        unsafe { self.term.set_len(prefix_len) };
+        self.term.truncate(prefix_len);
         self.term.extend_from_slice(&(*cursor)[..suffix_len]);
         &cursor[suffix_len..]
     }
@@ -83,6 +86,26 @@ pub struct DeltaTermInfo {
     pub delta_postings_offset: u64,
     pub delta_positions_offset: u64,
     pub positions_inner_offset: u8,
+    pub inlined_doc_code: u32,
+}
+
+impl DeltaTermInfo {
+    /// Writes the delta term info as a sequence of `VInt`s.
+    ///
+    /// The positions are only written if `has_positions` is true,
+    /// and the inlined doc is only written if `doc_freq` is 1.
+    pub fn serialize<W: Write>(&self, has_positions: bool, write: &mut W) -> io::Result<()> {
+        VInt(u64::from(self.doc_freq)).serialize(write)?;
+        VInt(self.delta_postings_offset).serialize(write)?;
+        if self.doc_freq == 1 {
+            VInt(u64::from(self.inlined_doc_code)).serialize(write)?;
+        }
+        if has_positions {
+            VInt(self.delta_positions_offset).serialize(write)?;
+            write.write_all(&[self.positions_inner_offset])?;
+        }
+        Ok(())
+    }
 }
 
 pub struct TermInfoDeltaEncoder {
@@ -108,13 +131,14 @@ impl TermInfoDeltaEncoder {
             delta_postings_offset: term_info.postings_offset - self.term_info.postings_offset,
             delta_positions_offset: 0u64,
             positions_inner_offset: 0,
+            inlined_doc_code: term_info.inlined_doc_code(),
         };
         if self.has_positions {
             delta_term_info.delta_positions_offset =
                 term_info.positions_offset - self.term_info.positions_offset;
             delta_term_info.positions_inner_offset = term_info.positions_inner_offset;
         }
-        mem::replace(&mut self.term_info, term_info);
+        self.term_info = term_info;
         delta_term_info
     }
 }
@@ -124,12 +148,6 @@ pub struct TermInfoDeltaDecoder {
     has_positions: bool,
 }
 
-#[inline(always)]
-pub fn make_mask(num_bytes: usize) -> u32 {
-    const MASK: [u32; 4] = [0xffu32, 0xffffu32, 0xffffffu32, 0xffffffffu32];
-    *unsafe { MASK.get_unchecked(num_bytes.wrapping_sub(1) as usize) }
-}
-
 impl TermInfoDeltaDecoder {
     pub fn from_term_info(term_info: TermInfo, has_positions: bool) -> TermInfoDeltaDecoder {
         TermInfoDeltaDecoder {
@@ -145,29 +163,31 @@ impl TermInfoDeltaDecoder {
                 postings_offset: checkpoint.postings_offset,
                 positions_offset: checkpoint.positions_offset,
                 positions_inner_offset: 0u8,
+                inlined_doc: None,
             },
             has_positions,
         }
     }
 
     #[inline(always)]
-    pub fn decode<'a>(&mut self, code: u8, mut cursor: &'a [u8]) -> &'a [u8] {
-        let num_bytes_docfreq: usize = ((code >> 1) & 3) as usize + 1;
-        let num_bytes_postings_offset: usize = ((code >> 3) & 3) as usize + 1;
-        let mut v: u64 = unsafe { *(cursor.as_ptr() as *const u64) };
-        let doc_freq: u32 = (v as u32) & make_mask(num_bytes_docfreq);
-        v >>= (num_bytes_docfreq as u64) * 8u64;
-        let delta_postings_offset: u64 = v & make_mask(num_bytes_postings_offset);
-        cursor = &cursor[num_bytes_docfreq + num_bytes_postings_offset..];
-        self.term_info.doc_freq = doc_freq;
+    pub fn decode<'a>(&mut self, mut cursor: &'a [u8]) -> &'a [u8] {
+        let doc_freq = VInt::deserialize_u64(&mut cursor).expect("Term info data is corrupted");
+        let delta_postings_offset =
+            VInt::deserialize_u64(&mut cursor).expect("Term info data is corrupted");
+        self.term_info.doc_freq = doc_freq as u32;
         self.term_info.postings_offset += delta_postings_offset;
+        let inlined_doc_code = if doc_freq == 1 {
+            VInt::deserialize_u64(&mut cursor).expect("Term info data is corrupted") as u32
+        } else {
+            0u32
+        };
+        self.term_info.set_inlined_doc_code(inlined_doc_code);
         if self.has_positions {
-            let num_bytes_positions_offset = ((code >> 5) & 3) as usize + 1;
-            let delta_positions_offset: u32 =
-                unsafe { *(cursor.as_ptr() as *const u32) } & make_mask(num_bytes_positions_offset);
+            let delta_positions_offset =
+                VInt::deserialize_u64(&mut cursor).expect("Term info data is corrupted");
             self.term_info.positions_offset += delta_positions_offset;
-            self.term_info.positions_inner_offset = cursor[num_bytes_positions_offset];
-            &cursor[num_bytes_positions_offset + 1..]
+            self.term_info.positions_inner_offset = cursor[0];
+            &cursor[1..]
         } else {
             cursor
         }
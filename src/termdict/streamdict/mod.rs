@@ -4,20 +4,29 @@ use common::BinarySerializable;
 mod termdict;
 mod streamer;
 mod delta_encoder;
+mod block_index;
 
-pub use self::delta_encoder::{TermDeltaDecoder, TermDeltaEncoder};
-pub use self::delta_encoder::{DeltaTermInfo, TermInfoDeltaDecoder, TermInfoDeltaEncoder};
+pub use self::delta_encoder::TermDeltaEncoder;
+pub use self::delta_encoder::{DeltaTermInfo, TermInfoDeltaEncoder};
+pub use self::block_index::BlockIndex;
 
 pub use self::termdict::TermDictionaryImpl;
 pub use self::termdict::TermDictionaryBuilderImpl;
 pub use self::streamer::TermStreamerImpl;
 pub use self::streamer::TermStreamerBuilderImpl;
 
-#[derive(Debug)]
+/// Number of terms in each block of the term stream.
+///
+/// The first term of a block is not prefix-compressed,
+/// so that the block can be decoded without decoding the previous ones.
+pub const BLOCK_LEN: usize = 128;
+
+/// State of the decoder at the beginning of a block.
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct CheckPoint {
-    pub stream_offset: u32,
-    pub postings_offset: u32,
-    pub positions_offset: u32,
+    pub stream_offset: u64,
+    pub postings_offset: u64,
+    pub positions_offset: u64,
 }
 
 impl BinarySerializable for CheckPoint {
@@ -29,9 +38,9 @@ impl BinarySerializable for CheckPoint {
     }
 
     fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
-        let stream_offset = u32::deserialize(reader)?;
-        let postings_offset = u32::deserialize(reader)?;
-        let positions_offset = u32::deserialize(reader)?;
+        let stream_offset = u64::deserialize(reader)?;
+        let postings_offset = u64::deserialize(reader)?;
+        let positions_offset = u64::deserialize(reader)?;
         Ok(CheckPoint {
             stream_offset,
             postings_offset,
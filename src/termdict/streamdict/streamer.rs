@@ -1,34 +1,46 @@
 #![allow(should_implement_trait)]
 
 use std::cmp::max;
-use super::TermDictionaryImpl;
-use termdict::{TermStreamer, TermStreamerBuilder};
+use super::{TermDictionaryImpl, BLOCK_LEN};
+use termdict::{TermDictionary, TermOrdinal, TermStreamer, TermStreamerBuilder};
 use postings::TermInfo;
 use super::delta_encoder::{TermDeltaDecoder, TermInfoDeltaDecoder};
 
+/// State of a streamer, right before a given term.
+struct StreamState {
+    offset: usize,
+    previous_key: Vec<u8>,
+    term_info: TermInfo,
+    term_ord: TermOrdinal,
+}
+
+impl Default for StreamState {
+    fn default() -> StreamState {
+        StreamState {
+            offset: 0,
+            previous_key: Vec::with_capacity(300),
+            term_info: TermInfo::default(),
+            term_ord: 0,
+        }
+    }
+}
+
+/// Returns a streamer starting at the beginning of the block
+/// that may contain `target_key`, so that it reaches `target_key`
+/// within at most one block.
 fn stream_before<'a>(
     term_dictionary: &'a TermDictionaryImpl,
     target_key: &[u8],
-    has_positions: bool,
 ) -> TermStreamerImpl<'a> {
-    let (prev_key, checkpoint) = term_dictionary.strictly_previous_key(target_key.as_ref());
-    let stream_data: &'a [u8] = &term_dictionary.stream_data()[checkpoint.stream_offset as usize..];
-    TermStreamerImpl {
-        cursor: stream_data,
-        term_delta_decoder: TermDeltaDecoder::with_previous_term(prev_key),
-        term_info_decoder: TermInfoDeltaDecoder::from_checkpoint(&checkpoint, has_positions),
-    }
+    let block_ord = term_dictionary.block_index().block_containing(target_key);
+    TermStreamerImpl::from_block(term_dictionary, block_ord)
 }
 
 /// See [`TermStreamerBuilder`](./trait.TermStreamerBuilder.html)
 pub struct TermStreamerBuilderImpl<'a> {
     term_dictionary: &'a TermDictionaryImpl,
-    origin: usize,
-    offset_from: usize,
+    start: StreamState,
     offset_to: usize,
-    current_key: Vec<u8>,
-    term_info: TermInfo,
-    has_positions: bool,
 }
 
 impl<'a> TermStreamerBuilder for TermStreamerBuilderImpl<'a> {
@@ -37,128 +49,137 @@ impl<'a> TermStreamerBuilder for TermStreamerBuilderImpl<'a> {
     /// Limit the range to terms greater or equal to the bound
     fn ge<T: AsRef<[u8]>>(mut self, bound: T) -> Self {
         let target_key = bound.as_ref();
-        let streamer = stream_before(
-            self.term_dictionary,
-            target_key.as_ref(),
-            self.has_positions,
-        );
+        let streamer = stream_before(self.term_dictionary, target_key);
         let smaller_than = |k: &[u8]| k.lt(target_key);
-        let (offset_before, current_key, term_info) = get_offset(smaller_than, streamer);
-        self.current_key = current_key;
-        self.term_info = term_info;
-        self.offset_from = offset_before - self.origin;
+        self.start = get_offset(smaller_than, streamer);
         self
     }
 
     /// Limit the range to terms strictly greater than the bound
     fn gt<T: AsRef<[u8]>>(mut self, bound: T) -> Self {
         let target_key = bound.as_ref();
-        let streamer = stream_before(
-            self.term_dictionary,
-            target_key.as_ref(),
-            self.has_positions,
-        );
+        let streamer = stream_before(self.term_dictionary, target_key);
         let smaller_than = |k: &[u8]| k.le(target_key);
-        let (offset_before, current_key, term_info) = get_offset(smaller_than, streamer);
-        self.current_key = current_key;
-        self.term_info = term_info;
-        self.offset_from = offset_before - self.origin;
+        self.start = get_offset(smaller_than, streamer);
         self
     }
 
     /// Limit the range to terms lesser or equal to the bound
     fn lt<T: AsRef<[u8]>>(mut self, bound: T) -> Self {
         let target_key = bound.as_ref();
-        let streamer = stream_before(
-            self.term_dictionary,
-            target_key.as_ref(),
-            self.has_positions,
-        );
+        let streamer = stream_before(self.term_dictionary, target_key);
         let smaller_than = |k: &[u8]| k.lt(target_key);
-        let (offset_before, _, _) = get_offset(smaller_than, streamer);
-        self.offset_to = offset_before - self.origin;
+        self.offset_to = get_offset(smaller_than, streamer).offset;
         self
     }
 
     /// Limit the range to terms lesser or equal to the bound
     fn le<T: AsRef<[u8]>>(mut self, bound: T) -> Self {
         let target_key = bound.as_ref();
-        let streamer = stream_before(
-            self.term_dictionary,
-            target_key.as_ref(),
-            self.has_positions,
-        );
+        let streamer = stream_before(self.term_dictionary, target_key);
         let smaller_than = |k: &[u8]| k.le(target_key);
-        let (offset_before, _, _) = get_offset(smaller_than, streamer);
-        self.offset_to = offset_before - self.origin;
+        self.offset_to = get_offset(smaller_than, streamer).offset;
         self
     }
 
     /// Build the streamer.
     fn into_stream(self) -> Self::Streamer {
         let data: &[u8] = self.term_dictionary.stream_data();
-        let start = self.offset_from;
+        let start = self.start.offset;
         let stop = max(self.offset_to, start);
-        let term_delta_decoder = TermDeltaDecoder::with_previous_term(self.current_key);
-        let term_info_decoder =
-            TermInfoDeltaDecoder::from_term_info(self.term_info, self.has_positions);
+        let term_delta_decoder = TermDeltaDecoder::with_previous_term(self.start.previous_key);
+        let term_info_decoder = TermInfoDeltaDecoder::from_term_info(
+            self.start.term_info,
+            self.term_dictionary.has_positions(),
+        );
         TermStreamerImpl {
+            term_dictionary: self.term_dictionary,
             cursor: &data[start..stop],
             term_delta_decoder,
             term_info_decoder,
+            next_term_ord: self.start.term_ord,
         }
     }
 }
 
-/// Returns offset information for the first
-/// key in the stream matching a given predicate.
+/// Returns the state of the streamer right before the first
+/// key in the stream that does not match the predicate.
 ///
-/// returns
-///     - the block start
-///     - the index within this block
-///     - the term_buffer state to initialize the block)
+/// The streamer is expected to span until the end of the term stream.
 fn get_offset<'a, P: Fn(&[u8]) -> bool>(
     predicate: P,
     mut streamer: TermStreamerImpl<'a>,
-) -> (usize, Vec<u8>, TermInfo) {
-    let mut prev: &[u8] = streamer.cursor;
-
-    let mut term_info = streamer.value().clone();
-    let mut prev_data: Vec<u8> = Vec::from(streamer.term_delta_decoder.term());
-
-    while let Some((iter_key, iter_term_info)) = streamer.next() {
-        if !predicate(iter_key.as_ref()) {
-            return (prev.as_ptr() as usize, prev_data, term_info);
+) -> StreamState {
+    let stream_len = streamer.term_dictionary.stream_data().len();
+    let mut state = StreamState {
+        offset: stream_len - streamer.cursor.len(),
+        previous_key: Vec::from(streamer.key()),
+        term_info: streamer.value().clone(),
+        term_ord: streamer.next_term_ord,
+    };
+    while streamer.advance() {
+        if !predicate(streamer.key()) {
+            return state;
         }
-        prev = streamer.cursor;
-        prev_data.clear();
-        prev_data.extend_from_slice(iter_key.as_ref());
-        term_info = iter_term_info.clone();
+        state.offset = stream_len - streamer.cursor.len();
+        state.previous_key.clear();
+        state.previous_key.extend_from_slice(streamer.key());
+        state.term_info = streamer.value().clone();
+        state.term_ord = streamer.next_term_ord;
     }
-    (prev.as_ptr() as usize, prev_data, term_info)
+    state
 }
 
 impl<'a> TermStreamerBuilderImpl<'a> {
-    pub(crate) fn new(term_dictionary: &'a TermDictionaryImpl, has_positions: bool) -> Self {
-        let data = term_dictionary.stream_data();
-        let origin = data.as_ptr() as usize;
+    pub(crate) fn new(term_dictionary: &'a TermDictionaryImpl) -> Self {
         TermStreamerBuilderImpl {
             term_dictionary,
-            term_info: TermInfo::default(),
-            origin,
-            offset_from: 0,
-            offset_to: data.len(),
-            current_key: Vec::with_capacity(300),
-            has_positions,
+            start: StreamState::default(),
+            offset_to: term_dictionary.stream_data().len(),
         }
     }
 }
 
 /// See [`TermStreamer`](./trait.TermStreamer.html)
 pub struct TermStreamerImpl<'a> {
+    term_dictionary: &'a TermDictionaryImpl,
     cursor: &'a [u8],
     term_delta_decoder: TermDeltaDecoder,
     term_info_decoder: TermInfoDeltaDecoder,
+    next_term_ord: TermOrdinal,
+}
+
+impl<'a> TermStreamerImpl<'a> {
+    /// Returns a streamer over all of the terms, starting
+    /// from the beginning of the block `block_ord`.
+    pub(crate) fn from_block(
+        term_dictionary: &'a TermDictionaryImpl,
+        block_ord: usize,
+    ) -> TermStreamerImpl<'a> {
+        let data = term_dictionary.stream_data();
+        let has_positions = term_dictionary.has_positions();
+        let block_index = term_dictionary.block_index();
+        if block_ord >= block_index.num_blocks() {
+            return TermStreamerImpl {
+                term_dictionary,
+                cursor: &data[data.len()..],
+                term_delta_decoder: TermDeltaDecoder::default(),
+                term_info_decoder: TermInfoDeltaDecoder::from_term_info(
+                    TermInfo::default(),
+                    has_positions,
+                ),
+                next_term_ord: term_dictionary.num_terms() as TermOrdinal,
+            };
+        }
+        let checkpoint = block_index.checkpoint(block_ord);
+        TermStreamerImpl {
+            term_dictionary,
+            cursor: &data[checkpoint.stream_offset as usize..],
+            term_delta_decoder: TermDeltaDecoder::default(),
+            term_info_decoder: TermInfoDeltaDecoder::from_checkpoint(checkpoint, has_positions),
+            next_term_ord: (block_ord * BLOCK_LEN) as TermOrdinal,
+        }
+    }
 }
 
 impl<'a> TermStreamer for TermStreamerImpl<'a> {
@@ -169,16 +190,21 @@ impl<'a> TermStreamer for TermStreamerImpl<'a> {
         let mut cursor: &[u8] = &self.cursor;
         let code: u8 = cursor[0];
         cursor = self.term_delta_decoder.decode(code, &cursor[1..]);
-        cursor = self.term_info_decoder.decode(code, cursor);
+        cursor = self.term_info_decoder.decode(cursor);
         self.cursor = cursor;
+        self.next_term_ord += 1;
         true
     }
 
+    fn term_ord(&self) -> TermOrdinal {
+        self.next_term_ord - 1
+    }
+
     fn key(&self) -> &[u8] {
         self.term_delta_decoder.term()
     }
 
     fn value(&self) -> &TermInfo {
-        &self.term_info_decoder.term_info()
+        self.term_info_decoder.term_info()
     }
 }
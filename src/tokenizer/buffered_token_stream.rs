@@ -0,0 +1,77 @@
+use super::{Token, TokenStream};
+
+/// Expands each of the tokens of a token stream into additional tokens,
+/// e.g. its synonyms or its subwords. (See `BufferedTokenStream`)
+pub trait TokenExpander {
+    /// Pushes the tokens to emit right after `token`, in order.
+    ///
+    /// The token itself may be modified in place.
+    fn expand(&self, token: &mut Token, expanded_tokens: &mut Vec<Token>);
+}
+
+/// `BufferedTokenStream` emits each of the tokens of its tail,
+/// followed by the tokens its `TokenExpander` expands it into.
+pub struct BufferedTokenStream<TailTokenStream, Expander>
+where
+    TailTokenStream: TokenStream,
+    Expander: TokenExpander,
+{
+    tail: TailTokenStream,
+    expander: Expander,
+    // expanded tokens that remain to be emitted, in reverse order.
+    pending_tokens: Vec<Token>,
+    // current token, if it is an expanded token.
+    expanded_token: Option<Token>,
+}
+
+impl<TailTokenStream, Expander> BufferedTokenStream<TailTokenStream, Expander>
+where
+    TailTokenStream: TokenStream,
+    Expander: TokenExpander,
+{
+    pub(crate) fn wrap(
+        tail: TailTokenStream,
+        expander: Expander,
+    ) -> BufferedTokenStream<TailTokenStream, Expander> {
+        BufferedTokenStream {
+            tail,
+            expander,
+            pending_tokens: vec![],
+            expanded_token: None,
+        }
+    }
+}
+
+impl<TailTokenStream, Expander> TokenStream for BufferedTokenStream<TailTokenStream, Expander>
+where
+    TailTokenStream: TokenStream,
+    Expander: TokenExpander,
+{
+    fn token(&self) -> &Token {
+        match self.expanded_token {
+            Some(ref token) => token,
+            None => self.tail.token(),
+        }
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        match self.expanded_token {
+            Some(ref mut token) => token,
+            None => self.tail.token_mut(),
+        }
+    }
+
+    fn advance(&mut self) -> bool {
+        self.expanded_token = self.pending_tokens.pop();
+        if self.expanded_token.is_some() {
+            return true;
+        }
+        if self.tail.advance() {
+            self.expander.expand(self.tail.token_mut(), &mut self.pending_tokens);
+            self.pending_tokens.reverse();
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use super::buffered_token_stream::{BufferedTokenStream, TokenExpander};
+use super::{Token, TokenFilter, TokenStream};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+#[derive(Clone)]
+struct DecompounderOptions {
+    words: Arc<HashSet<String>>,
+    // bounds of the length of the subwords, in chars.
+    min_subword_len: usize,
+    max_subword_len: usize,
+    only_longest_match: bool,
+}
+
+impl DecompounderOptions {
+    /// Returns the subwords of the dictionary contained in `text`,
+    /// in the order of their start.
+    fn subwords(&self, text: &str) -> Vec<String> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut subwords = vec![];
+        for start in 0..chars.len() {
+            let max_end = chars.len().min(start + self.max_subword_len);
+            let mut longest_match: Option<String> = None;
+            for end in (start + self.min_subword_len)..(max_end + 1) {
+                if end - start == chars.len() {
+                    // the token itself is not one of its subwords.
+                    break;
+                }
+                let candidate: String = chars[start..end].iter().collect();
+                if self.words.contains(&candidate) {
+                    if self.only_longest_match {
+                        longest_match = Some(candidate);
+                    } else {
+                        subwords.push(candidate);
+                    }
+                }
+            }
+            subwords.extend(longest_match);
+        }
+        subwords
+    }
+}
+
+/// `DecompounderFilter` splits the compound words into the words of a
+/// dictionary they contain, as is common in German or Dutch.
+///
+/// The subwords are emitted right after the compound word, with
+/// its position and its offsets, so that a query for a subword
+/// matches the compound words containing it.
+/// The subwords may overlap, and the compound word is kept.
+///
+/// Tokens are compared to the dictionary as is, so this filter
+/// should be placed after the `LowerCaser` in a tokenizer pipeline.
+///
+/// ```rust
+/// # extern crate tantivy;
+/// use tantivy::tokenizer::*;
+///
+/// # fn main() {
+/// let words = vec!["dampf".to_string(), "schiff".to_string(), "fahrt".to_string()];
+/// let tokenizer = SimpleTokenizer
+///     .filter(LowerCaser)
+///     .filter(DecompounderFilter::new(words));
+/// let mut tokens = vec![];
+/// tokenizer
+///     .token_stream("Dampfschifffahrt")
+///     .process(&mut |token: &Token| tokens.push(token.text.clone()));
+/// assert_eq!(tokens, vec!["dampfschifffahrt", "dampf", "schiff", "fahrt"]);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct DecompounderFilter {
+    options: DecompounderOptions,
+}
+
+impl DecompounderFilter {
+    /// Creates a `DecompounderFilter` splitting the compound words
+    /// into the given words.
+    ///
+    /// By default, the subwords have between 2 and 15 characters.
+    pub fn new(words: Vec<String>) -> DecompounderFilter {
+        DecompounderFilter {
+            options: DecompounderOptions {
+                words: Arc::new(words.into_iter().collect()),
+                min_subword_len: 2,
+                max_subword_len: 15,
+                only_longest_match: false,
+            },
+        }
+    }
+
+    /// Sets the minimum number of characters of the subwords.
+    ///
+    /// The shorter words of the dictionary are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_subword_len` is zero.
+    pub fn min_subword_len(mut self, min_subword_len: usize) -> DecompounderFilter {
+        assert!(min_subword_len > 0, "The subwords need to have at least one character.");
+        self.options.min_subword_len = min_subword_len;
+        self
+    }
+
+    /// Sets the maximum number of characters of the subwords.
+    ///
+    /// The longer words of the dictionary are ignored.
+    pub fn max_subword_len(mut self, max_subword_len: usize) -> DecompounderFilter {
+        self.options.max_subword_len = max_subword_len;
+        self
+    }
+
+    /// Only emits the longest subword starting at each character,
+    /// e.g. `fahrt` but not `fahr` in `schifffahrt`.
+    pub fn only_longest_match(mut self) -> DecompounderFilter {
+        self.options.only_longest_match = true;
+        self
+    }
+}
+
+impl TokenExpander for DecompounderFilter {
+    fn expand(&self, token: &mut Token, subword_tokens: &mut Vec<Token>) {
+        for subword in self.options.subwords(&token.text) {
+            subword_tokens.push(Token {
+                offset_from: token.offset_from,
+                offset_to: token.offset_to,
+                position: token.position,
+                text: subword,
+            });
+        }
+    }
+}
+
+impl<TailTokenStream> TokenFilter<TailTokenStream> for DecompounderFilter
+where
+    TailTokenStream: TokenStream,
+{
+    type ResultTokenStream = BufferedTokenStream<TailTokenStream, DecompounderFilter>;
+
+    fn transform(&self, token_stream: TailTokenStream) -> Self::ResultTokenStream {
+        BufferedTokenStream::wrap(token_stream, self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::DecompounderFilter;
+    use collector::CountCollector;
+    use query::TermQuery;
+    use schema::{IndexRecordOption, SchemaBuilder, TextFieldIndexing, TextOptions, Term};
+    use tokenizer::{LowerCaser, SimpleTokenizer, Token, TokenStream, Tokenizer};
+    use Index;
+
+    fn decompounder() -> DecompounderFilter {
+        let words = vec!["dampf", "schiff", "fahr", "fahrt", "see", "über"];
+        DecompounderFilter::new(words.into_iter().map(String::from).collect())
+    }
+
+    fn tokens(decompounder: DecompounderFilter, text: &str) -> Vec<(String, usize)> {
+        let mut tokens = vec![];
+        SimpleTokenizer
+            .filter(LowerCaser)
+            .filter(decompounder)
+            .token_stream(text)
+            .process(&mut |token: &Token| tokens.push((token.text.clone(), token.position)));
+        tokens
+    }
+
+    #[test]
+    fn test_decompounder_filter() {
+        assert_eq!(
+            tokens(decompounder(), "Dampfschifffahrt überseeschiff"),
+            vec![
+                ("dampfschifffahrt".to_string(), 0),
+                ("dampf".to_string(), 0),
+                ("schiff".to_string(), 0),
+                ("fahr".to_string(), 0),
+                ("fahrt".to_string(), 0),
+                ("überseeschiff".to_string(), 1),
+                ("über".to_string(), 1),
+                ("see".to_string(), 1),
+                ("schiff".to_string(), 1),
+            ]
+        );
+        assert_eq!(
+            tokens(decompounder().only_longest_match(), "schifffahrt"),
+            vec![
+                ("schifffahrt".to_string(), 0),
+                ("schiff".to_string(), 0),
+                ("fahrt".to_string(), 0),
+            ]
+        );
+        assert_eq!(
+            tokens(decompounder().min_subword_len(5), "schifffahrt"),
+            vec![
+                ("schifffahrt".to_string(), 0),
+                ("schiff".to_string(), 0),
+                ("fahrt".to_string(), 0),
+            ]
+        );
+        assert_eq!(
+            tokens(decompounder(), "schiff"),
+            vec![("schiff".to_string(), 0)]
+        );
+    }
+
+    #[test]
+    fn test_decompounder_filter_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field_indexing = TextFieldIndexing::default()
+            .set_tokenizer("decompounder")
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions);
+        let text_options = TextOptions::default().set_indexing_options(text_field_indexing);
+        let text_field = schema_builder.add_text_field("text", text_options);
+        let index = Index::create_in_ram(schema_builder.build());
+        index.tokenizers().register(
+            "decompounder",
+            SimpleTokenizer.filter(LowerCaser).filter(decompounder()),
+        );
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "Dampfschifffahrt"));
+            index_writer.add_document(doc!(text_field => "Schiff"));
+            index_writer.add_document(doc!(text_field => "Fahrrad"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let count = |word: &str| {
+            let term = Term::from_field_text(text_field, word);
+            let mut count_collector = CountCollector::default();
+            searcher
+                .search(
+                    &TermQuery::new(term, IndexRecordOption::Basic),
+                    &mut count_collector,
+                )
+                .unwrap();
+            count_collector.count()
+        };
+        assert_eq!(count("schiff"), 2);
+        assert_eq!(count("fahr"), 2);
+        assert_eq!(count("dampfschifffahrt"), 1);
+    }
+}
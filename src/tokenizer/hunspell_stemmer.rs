@@ -0,0 +1,514 @@
+use super::buffered_token_stream::{BufferedTokenStream, TokenExpander};
+use super::{Token, TokenFilter, TokenStream};
+use error::ErrorKind;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use Result;
+
+/// How the flags of the affix rules and of the words are encoded,
+/// as defined by the `FLAG` directive of the `.aff` file.
+#[derive(Clone, Copy)]
+enum FlagType {
+    /// One character per flag (default).
+    Char,
+    /// Two characters per flag (`FLAG long`).
+    Long,
+    /// Comma separated numbers (`FLAG num`).
+    Num,
+}
+
+impl FlagType {
+    fn parse_flags(&self, flags: &str) -> Vec<String> {
+        match *self {
+            FlagType::Char => flags.chars().map(|c| c.to_string()).collect(),
+            FlagType::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+                chars
+                    .chunks(2)
+                    .map(|chunk| chunk.iter().collect::<String>())
+                    .collect()
+            }
+            FlagType::Num => flags
+                .split(',')
+                .map(|flag| flag.trim().to_string())
+                .filter(|flag| !flag.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// Element of the condition of an affix rule.
+#[derive(Debug)]
+enum CharClass {
+    Any,
+    Chars { chars: Vec<char>, negated: bool },
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            CharClass::Any => true,
+            CharClass::Chars {
+                ref chars,
+                ref negated,
+            } => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+fn parse_condition(condition: &str) -> Result<Vec<CharClass>> {
+    let mut char_classes = vec![];
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => char_classes.push(CharClass::Any),
+            '[' => {
+                let mut class_chars = vec![];
+                let mut negated = false;
+                let mut closed = false;
+                for class_char in chars.by_ref() {
+                    match class_char {
+                        ']' => {
+                            closed = true;
+                            break;
+                        }
+                        '^' if class_chars.is_empty() && !negated => negated = true,
+                        _ => class_chars.push(class_char),
+                    }
+                }
+                if !closed {
+                    bail!(ErrorKind::InvalidArgument(format!(
+                        "Invalid affix condition '{}'",
+                        condition
+                    )));
+                }
+                char_classes.push(CharClass::Chars {
+                    chars: class_chars,
+                    negated,
+                });
+            }
+            _ => char_classes.push(CharClass::Chars {
+                chars: vec![c],
+                negated: false,
+            }),
+        }
+    }
+    Ok(char_classes)
+}
+
+/// A prefix or suffix rule of the `.aff` file.
+#[derive(Debug)]
+struct Affix {
+    flag: String,
+    cross_product: bool,
+    strip: String,
+    append: String,
+    condition: Vec<CharClass>,
+}
+
+impl Affix {
+    /// Returns the candidate stem of `word`, if `word` can be
+    /// obtained by applying the suffix rule to it.
+    fn remove_suffix(&self, word: &str) -> Option<String> {
+        if !word.ends_with(&self.append[..]) {
+            return None;
+        }
+        let mut stem = word[..word.len() - self.append.len()].to_string();
+        stem.push_str(&self.strip);
+        if stem.is_empty() || !matches_condition(self.condition.iter().rev(), stem.chars().rev()) {
+            return None;
+        }
+        Some(stem)
+    }
+
+    /// Returns the candidate stem of `word`, if `word` can be
+    /// obtained by applying the prefix rule to it.
+    fn remove_prefix(&self, word: &str) -> Option<String> {
+        if !word.starts_with(&self.append[..]) {
+            return None;
+        }
+        let mut stem = self.strip.clone();
+        stem.push_str(&word[self.append.len()..]);
+        if stem.is_empty() || !matches_condition(self.condition.iter(), stem.chars()) {
+            return None;
+        }
+        Some(stem)
+    }
+}
+
+/// Matches the condition of an affix rule against the end of the stem
+/// for the suffixes (both reversed), and against its beginning for the prefixes.
+fn matches_condition<'a, C, I>(mut condition: C, mut chars: I) -> bool
+where
+    C: Iterator<Item = &'a CharClass>,
+    I: Iterator<Item = char>,
+{
+    condition.all(|char_class| {
+        chars
+            .next()
+            .map(|c| char_class.matches(c))
+            .unwrap_or(false)
+    })
+}
+
+/// A Hunspell dictionary, made of an affix file (`.aff`)
+/// and of a dictionary file (`.dic`).
+///
+/// Only the directives used for stemming are supported:
+/// the `PFX` and `SFX` rules and their cross product, and the
+/// `FLAG` directive. In particular, the flag aliases (`AF`),
+/// the continuation classes of the affixes (twofold suffixes),
+/// and the compounding rules are ignored.
+/// The files are expected to be encoded in UTF-8.
+pub struct HunspellDictionary {
+    words: HashMap<String, HashSet<String>>,
+    prefixes: Vec<Affix>,
+    suffixes: Vec<Affix>,
+}
+
+impl HunspellDictionary {
+    /// Loads a dictionary from its `.aff` and `.dic` files.
+    pub fn open<P: AsRef<Path>>(aff_path: P, dic_path: P) -> Result<HunspellDictionary> {
+        let read_file = |path: &Path| -> Result<String> {
+            let mut content = String::new();
+            File::open(path)?.read_to_string(&mut content)?;
+            Ok(content)
+        };
+        let aff = read_file(aff_path.as_ref())?;
+        let dic = read_file(dic_path.as_ref())?;
+        HunspellDictionary::parse(&aff, &dic)
+    }
+
+    /// Parses a dictionary, given the content of its `.aff` and `.dic` files.
+    pub fn parse(aff: &str, dic: &str) -> Result<HunspellDictionary> {
+        let mut flag_type = FlagType::Char;
+        let mut prefixes = vec![];
+        let mut suffixes = vec![];
+        // cross product of the affix rules being parsed, by flag.
+        let mut cross_products: HashMap<(String, String), bool> = HashMap::new();
+        for line in aff.lines() {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.is_empty() {
+                continue;
+            }
+            match parts[0] {
+                "FLAG" if parts.len() > 1 => {
+                    flag_type = match parts[1] {
+                        "long" => FlagType::Long,
+                        "num" => FlagType::Num,
+                        _ => FlagType::Char,
+                    };
+                }
+                "PFX" | "SFX" => {
+                    let kind = parts[0].to_string();
+                    let invalid_rule = || {
+                        ErrorKind::InvalidArgument(format!("Invalid affix rule '{}'", line))
+                    };
+                    if parts.len() < 4 {
+                        bail!(invalid_rule());
+                    }
+                    let flag = parts[1].to_string();
+                    let is_header = (parts[2] == "Y" || parts[2] == "N")
+                        && parts[3].parse::<usize>().is_ok()
+                        && !cross_products.contains_key(&(kind.clone(), flag.clone()));
+                    if is_header {
+                        cross_products.insert((kind, flag), parts[2] == "Y");
+                        continue;
+                    }
+                    let cross_product = *cross_products
+                        .get(&(kind, flag.clone()))
+                        .ok_or_else(invalid_rule)?;
+                    let affix_text = |text: &str| -> String {
+                        // the continuation classes after the `/` are ignored.
+                        let text = text.split('/').next().unwrap_or("");
+                        if text == "0" {
+                            String::new()
+                        } else {
+                            text.to_string()
+                        }
+                    };
+                    let condition = parse_condition(parts.get(4).cloned().unwrap_or("."))?;
+                    let affix = Affix {
+                        flag,
+                        cross_product,
+                        strip: affix_text(parts[2]),
+                        append: affix_text(parts[3]),
+                        condition,
+                    };
+                    if parts[0] == "PFX" {
+                        prefixes.push(affix);
+                    } else {
+                        suffixes.push(affix);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut words: HashMap<String, HashSet<String>> = HashMap::new();
+        for (line_ord, line) in dic.lines().enumerate() {
+            let entry = match line.split_whitespace().next() {
+                Some(entry) => entry,
+                None => {
+                    continue;
+                }
+            };
+            // the first line is the approximate number of words.
+            if line_ord == 0 && entry.parse::<usize>().is_ok() {
+                continue;
+            }
+            let mut entry_parts = entry.splitn(2, '/');
+            let word = entry_parts.next().unwrap_or("").to_string();
+            let flags = entry_parts
+                .next()
+                .map(|flags| flag_type.parse_flags(flags))
+                .unwrap_or_else(Vec::new);
+            words
+                .entry(word)
+                .or_insert_with(HashSet::new)
+                .extend(flags);
+        }
+        Ok(HunspellDictionary {
+            words,
+            prefixes,
+            suffixes,
+        })
+    }
+
+    fn has_flag(&self, word: &str, flag: &str) -> bool {
+        self.words
+            .get(word)
+            .map(|flags| flags.contains(flag))
+            .unwrap_or(false)
+    }
+
+    /// Returns the stems of a word, in the order of the affix rules.
+    ///
+    /// A word of the dictionary is its own stem. The returned list is
+    /// empty if the word is neither in the dictionary, nor derived
+    /// from one of its words.
+    pub fn stem(&self, word: &str) -> Vec<String> {
+        let mut stems: Vec<String> = vec![];
+        {
+            let mut push_stem = |stem: String| {
+                if !stems.contains(&stem) {
+                    stems.push(stem);
+                }
+            };
+            if self.words.contains_key(word) {
+                push_stem(word.to_string());
+            }
+            for suffix in &self.suffixes {
+                let candidate = match suffix.remove_suffix(word) {
+                    Some(candidate) => candidate,
+                    None => {
+                        continue;
+                    }
+                };
+                if suffix.cross_product {
+                    for prefix in self.prefixes.iter().filter(|prefix| prefix.cross_product) {
+                        if let Some(stem) = prefix.remove_prefix(&candidate) {
+                            if self.has_flag(&stem, &prefix.flag)
+                                && self.has_flag(&stem, &suffix.flag)
+                            {
+                                push_stem(stem);
+                            }
+                        }
+                    }
+                }
+                if self.has_flag(&candidate, &suffix.flag) {
+                    push_stem(candidate);
+                }
+            }
+            for prefix in &self.prefixes {
+                if let Some(stem) = prefix.remove_prefix(word) {
+                    if self.has_flag(&stem, &prefix.flag) {
+                        push_stem(stem);
+                    }
+                }
+            }
+        }
+        stems
+    }
+}
+
+/// `HunspellStemFilter` replaces the tokens by their stems,
+/// as given by a `HunspellDictionary`.
+///
+/// A token with several stems is replaced by all of them, emitted
+/// at its position. The tokens that the dictionary does not know are
+/// left unchanged. The original tokens can also be kept, at the same
+/// position as their stems. (See `.keep_original()`)
+///
+/// The dictionary is case sensitive, so this filter should be placed
+/// after the `LowerCaser` in a tokenizer pipeline, with a dictionary
+/// in lowercase.
+///
+/// ```rust
+/// # extern crate tantivy;
+/// use tantivy::tokenizer::*;
+///
+/// # fn main() {
+/// let aff = "SFX S Y 1\nSFX S 0 s .\n";
+/// let dic = "2\nbook/S\nshelf\n";
+/// let dictionary = HunspellDictionary::parse(aff, dic).unwrap();
+/// assert_eq!(dictionary.stem("books"), vec!["book".to_string()]);
+/// let tokenizer = SimpleTokenizer
+///     .filter(LowerCaser)
+///     .filter(HunspellStemFilter::new(dictionary));
+/// let mut stems = vec![];
+/// tokenizer
+///     .token_stream("Books")
+///     .process(&mut |token: &Token| stems.push(token.text.clone()));
+/// assert_eq!(stems, vec!["book".to_string()]);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct HunspellStemFilter {
+    dictionary: Arc<HunspellDictionary>,
+    keep_original: bool,
+}
+
+impl HunspellStemFilter {
+    /// Creates a `HunspellStemFilter` stemming the tokens with the given dictionary.
+    pub fn new(dictionary: HunspellDictionary) -> HunspellStemFilter {
+        HunspellStemFilter {
+            dictionary: Arc::new(dictionary),
+            keep_original: false,
+        }
+    }
+
+    /// Keeps the original tokens, emitted before their stems.
+    pub fn keep_original(mut self) -> HunspellStemFilter {
+        self.keep_original = true;
+        self
+    }
+}
+
+impl TokenExpander for HunspellStemFilter {
+    fn expand(&self, token: &mut Token, stem_tokens: &mut Vec<Token>) {
+        let mut stems = self.dictionary.stem(&token.text);
+        if self.keep_original {
+            stems.retain(|stem| *stem != token.text);
+        } else if !stems.is_empty() {
+            let first_stem = stems.remove(0);
+            token.text.clear();
+            token.text.push_str(&first_stem);
+        }
+        for stem in stems {
+            stem_tokens.push(Token {
+                offset_from: token.offset_from,
+                offset_to: token.offset_to,
+                position: token.position,
+                text: stem,
+            });
+        }
+    }
+}
+
+impl<TailTokenStream> TokenFilter<TailTokenStream> for HunspellStemFilter
+where
+    TailTokenStream: TokenStream,
+{
+    type ResultTokenStream = BufferedTokenStream<TailTokenStream, HunspellStemFilter>;
+
+    fn transform(&self, token_stream: TailTokenStream) -> Self::ResultTokenStream {
+        BufferedTokenStream::wrap(token_stream, self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{HunspellDictionary, HunspellStemFilter};
+    use tokenizer::{LowerCaser, SimpleTokenizer, Token, TokenStream, Tokenizer};
+
+    const AFF: &str = "SET UTF-8
+FLAG long
+
+PFX Re Y 1
+PFX Re 0 re .
+
+SFX Pl Y 3
+SFX Pl 0 s [^sy]
+SFX Pl y ies [^aeiou]y
+SFX Pl 0 es s
+
+SFX Pa N 2
+SFX Pa 0 d e
+SFX Pa 0 ed [^e]
+";
+
+    const DIC: &str = "5
+pony/Pl
+read/RePa
+bus/Pl
+use/RePa
+bu
+";
+
+    fn dictionary() -> HunspellDictionary {
+        HunspellDictionary::parse(AFF, DIC).unwrap()
+    }
+
+    #[test]
+    fn test_hunspell_stem() {
+        let dictionary = dictionary();
+        let stem = |word: &str| dictionary.stem(word);
+        assert_eq!(stem("ponies"), vec!["pony".to_string()]);
+        assert_eq!(stem("pony"), vec!["pony".to_string()]);
+        assert_eq!(stem("ponys"), Vec::<String>::new());
+        assert_eq!(stem("buses"), vec!["bus".to_string()]);
+        assert_eq!(stem("reread"), vec!["read".to_string()]);
+        assert_eq!(stem("reused"), Vec::<String>::new());
+        assert_eq!(stem("used"), vec!["use".to_string()]);
+        assert_eq!(stem("reuse"), vec!["use".to_string()]);
+        assert_eq!(stem("unknown"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_hunspell_cross_product() {
+        let aff = "PFX U Y 1\nPFX U 0 un .\nSFX S Y 1\nSFX S 0 s .\n";
+        let dic = "1\ndo/US\n";
+        let dictionary = HunspellDictionary::parse(aff, dic).unwrap();
+        assert_eq!(dictionary.stem("undos"), vec!["do".to_string()]);
+        assert!(HunspellDictionary::parse("SFX S Y 1\nSFX S 0 s [a\n", dic).is_err());
+    }
+
+    #[test]
+    fn test_hunspell_stem_filter() {
+        let tokens = |stem_filter: HunspellStemFilter| {
+            let mut tokens: Vec<(String, usize)> = vec![];
+            SimpleTokenizer
+                .filter(LowerCaser)
+                .filter(stem_filter)
+                .token_stream("Ponies reread buses daily")
+                .process(&mut |token: &Token| tokens.push((token.text.clone(), token.position)));
+            tokens
+        };
+        assert_eq!(
+            tokens(HunspellStemFilter::new(dictionary())),
+            vec![
+                ("pony".to_string(), 0),
+                ("read".to_string(), 1),
+                ("bus".to_string(), 2),
+                ("daily".to_string(), 3),
+            ]
+        );
+        assert_eq!(
+            tokens(HunspellStemFilter::new(dictionary()).keep_original()),
+            vec![
+                ("ponies".to_string(), 0),
+                ("pony".to_string(), 0),
+                ("reread".to_string(), 1),
+                ("read".to_string(), 1),
+                ("buses".to_string(), 2),
+                ("bus".to_string(), 2),
+                ("daily".to_string(), 3),
+            ]
+        );
+    }
+}
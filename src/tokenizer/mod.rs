@@ -55,6 +55,11 @@
 //! remove their inflection. This tokenizer is slower than the default one,
 //! but is recommended to improve recall.
 //!
+//! ## `en_stem_with_stopwords`
+//!
+//! Like `en_stem`, but also removes common English words (`the`, `a`, ...)
+//! before stemming. (See [`StopWordFilter`](./struct.StopWordFilter.html))
+//!
 //!
 //! # Custom tokenizers
 //!
@@ -139,6 +144,13 @@ mod japanese_tokenizer;
 mod token_stream_chain;
 mod raw_tokenizer;
 mod alphanum_only;
+mod stop_word_filter;
+mod ngram_tokenizer;
+mod synonym_filter;
+mod pre_tokenized_stream;
+mod hunspell_stemmer;
+mod decompounder;
+mod buffered_token_stream;
 
 pub use self::alphanum_only::AlphaNumOnlyFilter;
 pub use self::tokenizer::{Token, TokenFilter, TokenStream, Tokenizer};
@@ -146,17 +158,24 @@ pub use self::tokenizer::BoxedTokenizer;
 pub use self::tokenizer_manager::TokenizerManager;
 pub use self::simple_tokenizer::SimpleTokenizer;
 pub use self::raw_tokenizer::RawTokenizer;
+pub use self::ngram_tokenizer::{EdgeNgramTokenizer, NgramTokenizer};
 pub(crate) use self::token_stream_chain::TokenStreamChain;
 pub use self::japanese_tokenizer::JapaneseTokenizer;
 pub use self::remove_long::RemoveLongFilter;
+pub use self::stop_word_filter::StopWordFilter;
+pub use self::synonym_filter::SynonymFilter;
+pub use self::decompounder::DecompounderFilter;
 pub use self::lower_caser::LowerCaser;
 pub use self::stemmer::Stemmer;
+pub use self::hunspell_stemmer::{HunspellDictionary, HunspellStemFilter};
 pub use self::facet_tokenizer::FacetTokenizer;
+pub use self::pre_tokenized_stream::{PreTokenizedStream, PreTokenizedString};
 
 #[cfg(test)]
 mod test {
     use super::Token;
     use super::TokenizerManager;
+    use super::{LowerCaser, SimpleTokenizer, StopWordFilter, TokenStream, Tokenizer};
 
     #[test]
     fn test_raw_tokenizer() {
@@ -196,6 +215,47 @@ mod test {
         assert_eq!(&tokens[3], "payer");
     }
 
+    #[test]
+    fn test_en_tokenizer_with_stopwords() {
+        let tokenizer_manager = TokenizerManager::default();
+        let en_tokenizer = tokenizer_manager.get("en_stem_with_stopwords").unwrap();
+        let mut tokens: Vec<(String, usize)> = vec![];
+        {
+            let mut add_token = |token: &Token| {
+                tokens.push((token.text.clone(), token.position));
+            };
+            en_tokenizer
+                .token_stream("The happy tax payer is the one")
+                .process(&mut add_token);
+        }
+        assert_eq!(
+            tokens,
+            vec![
+                ("happi".to_string(), 1),
+                ("tax".to_string(), 2),
+                ("payer".to_string(), 3),
+                ("one".to_string(), 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_stopwords() {
+        let tokenizer = SimpleTokenizer
+            .filter(LowerCaser)
+            .filter(StopWordFilter::remove(vec!["tax".to_string()]));
+        let mut tokens: Vec<String> = vec![];
+        {
+            let mut add_token = |token: &Token| {
+                tokens.push(token.text.clone());
+            };
+            tokenizer
+                .token_stream("Hello happy TAX payer")
+                .process(&mut add_token);
+        }
+        assert_eq!(tokens, vec!["hello", "happy", "payer"]);
+    }
+
     #[test]
     fn test_jp_tokenizer() {
         let tokenizer_manager = TokenizerManager::default();
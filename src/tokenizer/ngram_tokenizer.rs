@@ -0,0 +1,226 @@
+use super::{Token, TokenStream, Tokenizer};
+
+/// Tokenize the text by emitting all of its n-grams,
+/// i.e. the substrings of `min_gram` to `max_gram` characters.
+///
+/// If `prefix_only` is set, only the n-grams starting at the beginning
+/// of the text are emitted. (See also
+/// [`EdgeNgramTokenizer`](./struct.EdgeNgramTokenizer.html))
+///
+/// The position of a n-gram is the index of its first character,
+/// and its offsets are expressed in bytes, as for any other token.
+///
+/// For instance, `NgramTokenizer::new(2, 3, false)` chops
+/// `hello` into `he`, `hel`, `el`, `ell`, `ll`, `llo`, `lo`,
+/// while `NgramTokenizer::new(2, 3, true)` only emits `he`, `hel`.
+#[derive(Clone, Debug)]
+pub struct NgramTokenizer {
+    min_gram: usize,
+    max_gram: usize,
+    prefix_only: bool,
+}
+
+impl NgramTokenizer {
+    /// Creates a `NgramTokenizer` emitting n-grams
+    /// of `min_gram` to `max_gram` characters, both inclusive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_gram` is 0 or if `min_gram > max_gram`.
+    pub fn new(min_gram: usize, max_gram: usize, prefix_only: bool) -> NgramTokenizer {
+        assert!(min_gram > 0, "min_gram must be strictly greater than 0");
+        assert!(
+            min_gram <= max_gram,
+            "min_gram must not be greater than max_gram"
+        );
+        NgramTokenizer {
+            min_gram,
+            max_gram,
+            prefix_only,
+        }
+    }
+}
+
+/// Tokenize the text by emitting its leading n-grams,
+/// i.e. its prefixes of `min_gram` to `max_gram` characters.
+///
+/// This is typically used to index text for prefix autocompletion.
+///
+/// For instance, `EdgeNgramTokenizer::new(1, 3)` chops
+/// `hello` into `h`, `he`, `hel`.
+#[derive(Clone, Debug)]
+pub struct EdgeNgramTokenizer {
+    ngram_tokenizer: NgramTokenizer,
+}
+
+impl EdgeNgramTokenizer {
+    /// Creates a `EdgeNgramTokenizer` emitting the prefixes
+    /// of `min_gram` to `max_gram` characters, both inclusive.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_gram` is 0 or if `min_gram > max_gram`.
+    pub fn new(min_gram: usize, max_gram: usize) -> EdgeNgramTokenizer {
+        EdgeNgramTokenizer {
+            ngram_tokenizer: NgramTokenizer::new(min_gram, max_gram, true),
+        }
+    }
+}
+
+pub struct NgramTokenStream<'a> {
+    text: &'a str,
+    // byte offsets of the characters of the text,
+    // followed by the length of the text.
+    char_offsets: Vec<usize>,
+    min_gram: usize,
+    max_gram: usize,
+    prefix_only: bool,
+    // index of the first character of the next n-gram.
+    start_char: usize,
+    // number of characters of the next n-gram.
+    gram_len: usize,
+    token: Token,
+}
+
+impl<'a> Tokenizer<'a> for NgramTokenizer {
+    type TokenStreamImpl = NgramTokenStream<'a>;
+
+    fn token_stream(&self, text: &'a str) -> Self::TokenStreamImpl {
+        let mut char_offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+        char_offsets.push(text.len());
+        NgramTokenStream {
+            text,
+            char_offsets,
+            min_gram: self.min_gram,
+            max_gram: self.max_gram,
+            prefix_only: self.prefix_only,
+            start_char: 0,
+            gram_len: self.min_gram,
+            token: Token::default(),
+        }
+    }
+}
+
+impl<'a> Tokenizer<'a> for EdgeNgramTokenizer {
+    type TokenStreamImpl = NgramTokenStream<'a>;
+
+    fn token_stream(&self, text: &'a str) -> Self::TokenStreamImpl {
+        self.ngram_tokenizer.token_stream(text)
+    }
+}
+
+impl<'a> NgramTokenStream<'a> {
+    fn num_chars(&self) -> usize {
+        self.char_offsets.len() - 1
+    }
+}
+
+impl<'a> TokenStream for NgramTokenStream<'a> {
+    fn advance(&mut self) -> bool {
+        loop {
+            if self.gram_len > self.max_gram || self.start_char + self.gram_len > self.num_chars() {
+                if self.prefix_only {
+                    return false;
+                }
+                // moving on to the n-grams starting at the next character.
+                self.start_char += 1;
+                self.gram_len = self.min_gram;
+                if self.start_char + self.gram_len > self.num_chars() {
+                    return false;
+                }
+                continue;
+            }
+            let offset_from = self.char_offsets[self.start_char];
+            let offset_to = self.char_offsets[self.start_char + self.gram_len];
+            self.token.offset_from = offset_from;
+            self.token.offset_to = offset_to;
+            self.token.position = self.start_char;
+            self.token.text.clear();
+            self.token.text.push_str(&self.text[offset_from..offset_to]);
+            self.gram_len += 1;
+            return true;
+        }
+    }
+
+    fn token(&self) -> &Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{EdgeNgramTokenizer, NgramTokenizer};
+    use tokenizer::{Token, TokenStream, Tokenizer};
+
+    fn tokens<'a, T: Tokenizer<'a>>(
+        tokenizer: &T,
+        text: &'a str,
+    ) -> Vec<(String, usize, usize, usize)> {
+        let mut tokens = vec![];
+        {
+            let mut add_token = |token: &Token| {
+                tokens.push((
+                    token.text.clone(),
+                    token.position,
+                    token.offset_from,
+                    token.offset_to,
+                ));
+            };
+            tokenizer.token_stream(text).process(&mut add_token);
+        }
+        tokens
+    }
+
+    fn texts(tokens: Vec<(String, usize, usize, usize)>) -> Vec<String> {
+        tokens.into_iter().map(|(text, _, _, _)| text).collect()
+    }
+
+    #[test]
+    fn test_ngram_tokenizer() {
+        let tokenizer = NgramTokenizer::new(2, 3, false);
+        assert_eq!(
+            texts(tokens(&tokenizer, "hello")),
+            vec!["he", "hel", "el", "ell", "ll", "llo", "lo"]
+        );
+        assert!(tokens(&tokenizer, "h").is_empty());
+        assert!(tokens(&tokenizer, "").is_empty());
+    }
+
+    #[test]
+    fn test_ngram_tokenizer_offsets() {
+        let tokenizer = NgramTokenizer::new(1, 2, false);
+        assert_eq!(
+            tokens(&tokenizer, "ébé"),
+            vec![
+                ("é".to_string(), 0, 0, 2),
+                ("éb".to_string(), 0, 0, 3),
+                ("b".to_string(), 1, 2, 3),
+                ("bé".to_string(), 1, 2, 5),
+                ("é".to_string(), 2, 3, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edge_ngram_tokenizer() {
+        let tokenizer = EdgeNgramTokenizer::new(1, 3);
+        assert_eq!(texts(tokens(&tokenizer, "hello")), vec!["h", "he", "hel"]);
+        assert_eq!(texts(tokens(&tokenizer, "hé")), vec!["h", "hé"]);
+        let tokenizer = NgramTokenizer::new(2, 10, true);
+        assert_eq!(
+            texts(tokens(&tokenizer, "hello")),
+            vec!["he", "hel", "hell", "hello"]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ngram_tokenizer_min_gram_zero() {
+        NgramTokenizer::new(0, 2, false);
+    }
+}
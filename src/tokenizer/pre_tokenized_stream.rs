@@ -0,0 +1,139 @@
+use common::{BinarySerializable, VInt};
+use std::io::{self, Read, Write};
+use tokenizer::{Token, TokenStream};
+
+/// A text together with its tokens, as computed by a tokenizer
+/// external to tantivy (e.g. a NLP pipeline).
+///
+/// The tokens of a `PreTokenizedString` are indexed as is, bypassing
+/// the tokenizer of the field. Their offsets are expected to refer
+/// to `text`, and their positions to be increasing.
+/// (See `Document::add_pre_tokenized_text`)
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PreTokenizedString {
+    /// Original text.
+    pub text: String,
+    /// Tokens of the text.
+    pub tokens: Vec<Token>,
+}
+
+impl BinarySerializable for PreTokenizedString {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.text.serialize(writer)?;
+        VInt(self.tokens.len() as u64).serialize(writer)?;
+        for token in &self.tokens {
+            VInt(token.offset_from as u64).serialize(writer)?;
+            VInt(token.offset_to as u64).serialize(writer)?;
+            VInt(token.position as u64).serialize(writer)?;
+            token.text.serialize(writer)?;
+        }
+        Ok(())
+    }
+
+    fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let text = String::deserialize(reader)?;
+        let num_tokens = VInt::deserialize(reader)?.val() as usize;
+        let mut tokens = Vec::with_capacity(num_tokens);
+        for _ in 0..num_tokens {
+            let offset_from = VInt::deserialize(reader)?.val() as usize;
+            let offset_to = VInt::deserialize(reader)?.val() as usize;
+            let position = VInt::deserialize(reader)?.val() as usize;
+            let text = String::deserialize(reader)?;
+            tokens.push(Token {
+                offset_from,
+                offset_to,
+                position,
+                text,
+            });
+        }
+        Ok(PreTokenizedString { text, tokens })
+    }
+}
+
+/// `TokenStream` over the tokens of a `PreTokenizedString`.
+pub struct PreTokenizedStream<'a> {
+    tokens: &'a [Token],
+    token_idx: usize,
+    token: Token,
+}
+
+impl<'a> From<&'a PreTokenizedString> for PreTokenizedStream<'a> {
+    fn from(pre_tokenized: &'a PreTokenizedString) -> PreTokenizedStream<'a> {
+        PreTokenizedStream {
+            tokens: &pre_tokenized.tokens,
+            token_idx: 0,
+            token: Token::default(),
+        }
+    }
+}
+
+impl<'a> TokenStream for PreTokenizedStream<'a> {
+    fn advance(&mut self) -> bool {
+        if let Some(token) = self.tokens.get(self.token_idx) {
+            self.token.offset_from = token.offset_from;
+            self.token.offset_to = token.offset_to;
+            self.token.position = token.position;
+            self.token.text.clear();
+            self.token.text.push_str(&token.text);
+            self.token_idx += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn token(&self) -> &Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{PreTokenizedStream, PreTokenizedString};
+    use common::BinarySerializable;
+    use tokenizer::{Token, TokenStream};
+
+    fn pre_tokenized_string() -> PreTokenizedString {
+        PreTokenizedString {
+            text: String::from("New York"),
+            tokens: vec![
+                Token {
+                    offset_from: 0,
+                    offset_to: 8,
+                    position: 0,
+                    text: String::from("new york"),
+                },
+                Token {
+                    offset_from: 4,
+                    offset_to: 8,
+                    position: 1,
+                    text: String::from("york"),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_pre_tokenized_stream() {
+        let pre_tokenized = pre_tokenized_string();
+        let mut token_stream = PreTokenizedStream::from(&pre_tokenized);
+        let mut tokens = vec![];
+        token_stream.process(&mut |token: &Token| tokens.push(token.clone()));
+        assert_eq!(tokens, pre_tokenized.tokens);
+    }
+
+    #[test]
+    fn test_pre_tokenized_string_serialization() {
+        let pre_tokenized = pre_tokenized_string();
+        let mut buffer = Vec::new();
+        pre_tokenized.serialize(&mut buffer).unwrap();
+        let mut cursor = &buffer[..];
+        assert_eq!(PreTokenizedString::deserialize(&mut cursor).unwrap(), pre_tokenized);
+        assert!(cursor.is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+use super::{Token, TokenFilter, TokenStream};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Stop words used by `StopWordFilter::english()`.
+const ENGLISH_STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+    "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+    "they", "this", "to", "was", "will", "with",
+];
+
+/// Stop words used by `StopWordFilter::french()`.
+const FRENCH_STOP_WORDS: &[&str] = &[
+    "au", "aux", "avec", "ce", "ces", "dans", "de", "des", "du", "elle", "en", "et", "eux", "il",
+    "je", "la", "le", "les", "leur", "lui", "ma", "mais", "me", "même", "mes", "moi", "mon", "ne",
+    "nos", "notre", "nous", "on", "ou", "par", "pas", "pour", "qu", "que", "qui", "sa", "se",
+    "ses", "son", "sur", "ta", "te", "tes", "toi", "ton", "tu", "un", "une", "vos", "votre",
+    "vous",
+];
+
+/// Stop words used by `StopWordFilter::german()`.
+const GERMAN_STOP_WORDS: &[&str] = &[
+    "aber", "alle", "als", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "das", "dass",
+    "dem", "den", "der", "des", "die", "du", "ein", "eine", "einem", "einen", "einer", "eines",
+    "er", "es", "für", "hat", "ich", "ihr", "im", "in", "ist", "mit", "nach", "nicht", "noch",
+    "oder", "sie", "sind", "so", "über", "um", "und", "uns", "von", "vor", "war", "wie", "wir",
+    "zu",
+];
+
+/// Stop words used by `StopWordFilter::spanish()`.
+const SPANISH_STOP_WORDS: &[&str] = &[
+    "a", "al", "como", "con", "de", "del", "el", "en", "es", "la", "las", "lo", "los", "más",
+    "me", "mi", "no", "o", "para", "pero", "por", "que", "se", "si", "sin", "su", "sus", "un",
+    "una", "y", "ya",
+];
+
+/// `StopWordFilter` removes tokens that belong to a given set of words.
+///
+/// Tokens are compared to the stop words as is, so this filter
+/// should be placed after the `LowerCaser` in a tokenizer pipeline.
+///
+/// The position of the remaining tokens is left unchanged, so that the
+/// removed tokens leave a gap. Phrase queries therefore do not match
+/// over a removed stop word.
+#[derive(Clone)]
+pub struct StopWordFilter {
+    words: Arc<HashSet<String>>,
+}
+
+impl StopWordFilter {
+    /// Creates a `StopWordFilter` removing the given words.
+    pub fn remove(words: Vec<String>) -> StopWordFilter {
+        StopWordFilter {
+            words: Arc::new(words.into_iter().collect()),
+        }
+    }
+
+    fn from_list(words: &[&str]) -> StopWordFilter {
+        StopWordFilter::remove(words.iter().map(|word| word.to_string()).collect())
+    }
+
+    /// Creates a `StopWordFilter` removing common English words.
+    pub fn english() -> StopWordFilter {
+        StopWordFilter::from_list(ENGLISH_STOP_WORDS)
+    }
+
+    /// Creates a `StopWordFilter` removing common French words.
+    pub fn french() -> StopWordFilter {
+        StopWordFilter::from_list(FRENCH_STOP_WORDS)
+    }
+
+    /// Creates a `StopWordFilter` removing common German words.
+    pub fn german() -> StopWordFilter {
+        StopWordFilter::from_list(GERMAN_STOP_WORDS)
+    }
+
+    /// Creates a `StopWordFilter` removing common Spanish words.
+    pub fn spanish() -> StopWordFilter {
+        StopWordFilter::from_list(SPANISH_STOP_WORDS)
+    }
+}
+
+impl<TailTokenStream> TokenFilter<TailTokenStream> for StopWordFilter
+where
+    TailTokenStream: TokenStream,
+{
+    type ResultTokenStream = StopWordFilterStream<TailTokenStream>;
+
+    fn transform(&self, token_stream: TailTokenStream) -> Self::ResultTokenStream {
+        StopWordFilterStream::wrap(Arc::clone(&self.words), token_stream)
+    }
+}
+
+pub struct StopWordFilterStream<TailTokenStream>
+where
+    TailTokenStream: TokenStream,
+{
+    words: Arc<HashSet<String>>,
+    tail: TailTokenStream,
+}
+
+impl<TailTokenStream> StopWordFilterStream<TailTokenStream>
+where
+    TailTokenStream: TokenStream,
+{
+    fn predicate(&self, token: &Token) -> bool {
+        !self.words.contains(&token.text)
+    }
+
+    fn wrap(
+        words: Arc<HashSet<String>>,
+        tail: TailTokenStream,
+    ) -> StopWordFilterStream<TailTokenStream> {
+        StopWordFilterStream { words, tail }
+    }
+}
+
+impl<TailTokenStream> TokenStream for StopWordFilterStream<TailTokenStream>
+where
+    TailTokenStream: TokenStream,
+{
+    fn token(&self) -> &Token {
+        self.tail.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.tail.token_mut()
+    }
+
+    fn advance(&mut self) -> bool {
+        loop {
+            if self.tail.advance() {
+                if self.predicate(self.tail.token()) {
+                    return true;
+                }
+            } else {
+                return false;
+            }
+        }
+    }
+}
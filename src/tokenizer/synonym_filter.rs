@@ -0,0 +1,189 @@
+use super::buffered_token_stream::{BufferedTokenStream, TokenExpander};
+use super::{Token, TokenFilter, TokenStream};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// `SynonymFilter` emits the synonyms of a token right after it.
+///
+/// The synonyms are emitted with the same position and the same
+/// offsets as the original token.
+///
+/// A synonym may consist of several words separated by whitespaces,
+/// (e.g. `ny` -> `new york`). Its words are then emitted at consecutive
+/// positions, starting from the position of the original token, so that
+/// phrase queries over the synonym match.
+///
+/// Tokens are compared to the synonym map as is, so this filter
+/// should be placed after the `LowerCaser` in a tokenizer pipeline.
+///
+/// The synonyms are typically expanded at indexing time only.
+/// When they are also expanded at query time, the query parser matches
+/// any of the tokens sharing a position. (See `QueryParser`)
+///
+/// ```rust
+/// # extern crate tantivy;
+/// use std::collections::HashMap;
+/// use tantivy::tokenizer::*;
+///
+/// # fn main() {
+/// let mut synonyms = HashMap::new();
+/// synonyms.insert("ny".to_string(), vec!["new york".to_string()]);
+/// let tokenizer = SimpleTokenizer
+///     .filter(LowerCaser)
+///     .filter(SynonymFilter::new(synonyms));
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SynonymFilter {
+    synonyms: Arc<HashMap<String, Vec<Vec<String>>>>,
+}
+
+impl SynonymFilter {
+    /// Creates a `SynonymFilter`, given a map associating
+    /// a token to its list of synonyms.
+    pub fn new(synonyms: HashMap<String, Vec<String>>) -> SynonymFilter {
+        let synonyms = synonyms
+            .into_iter()
+            .map(|(text, synonyms)| {
+                let synonyms_words: Vec<Vec<String>> = synonyms
+                    .iter()
+                    .map(|synonym| synonym.split_whitespace().map(String::from).collect())
+                    .filter(|words: &Vec<String>| !words.is_empty())
+                    .filter(|words: &Vec<String>| words.len() > 1 || words[0] != text)
+                    .collect();
+                (text, synonyms_words)
+            })
+            .collect();
+        SynonymFilter {
+            synonyms: Arc::new(synonyms),
+        }
+    }
+}
+
+impl TokenExpander for SynonymFilter {
+    fn expand(&self, token: &mut Token, synonym_tokens: &mut Vec<Token>) {
+        if let Some(synonyms) = self.synonyms.get(&token.text) {
+            for words in synonyms {
+                for (word_ord, word) in words.iter().enumerate() {
+                    synonym_tokens.push(Token {
+                        offset_from: token.offset_from,
+                        offset_to: token.offset_to,
+                        position: token.position + word_ord,
+                        text: word.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl<TailTokenStream> TokenFilter<TailTokenStream> for SynonymFilter
+where
+    TailTokenStream: TokenStream,
+{
+    type ResultTokenStream = BufferedTokenStream<TailTokenStream, SynonymFilter>;
+
+    fn transform(&self, token_stream: TailTokenStream) -> Self::ResultTokenStream {
+        BufferedTokenStream::wrap(token_stream, self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::SynonymFilter;
+    use collector::CountCollector;
+    use query::PhraseQuery;
+    use schema::{IndexRecordOption, SchemaBuilder, TextFieldIndexing, TextOptions, Term};
+    use std::collections::HashMap;
+    use tokenizer::{LowerCaser, SimpleTokenizer, Token, TokenStream, Tokenizer};
+    use Index;
+
+    fn synonym_filter() -> SynonymFilter {
+        let mut synonyms = HashMap::new();
+        synonyms.insert(
+            "ny".to_string(),
+            vec!["nyc".to_string(), "new york".to_string()],
+        );
+        synonyms.insert("nyc".to_string(), vec!["new york city".to_string()]);
+        synonyms.insert(
+            "big".to_string(),
+            vec!["big".to_string(), "large".to_string()],
+        );
+        SynonymFilter::new(synonyms)
+    }
+
+    #[test]
+    fn test_synonym_filter() {
+        let tokenizer = SimpleTokenizer
+            .filter(LowerCaser)
+            .filter(synonym_filter());
+        let mut tokens: Vec<(String, usize, usize, usize)> = vec![];
+        {
+            let mut add_token = |token: &Token| {
+                tokens.push((
+                    token.text.clone(),
+                    token.position,
+                    token.offset_from,
+                    token.offset_to,
+                ));
+            };
+            tokenizer
+                .token_stream("Big NY city")
+                .process(&mut add_token);
+        }
+        assert_eq!(
+            tokens,
+            vec![
+                ("big".to_string(), 0, 0, 3),
+                ("large".to_string(), 0, 0, 3),
+                ("ny".to_string(), 1, 4, 6),
+                ("nyc".to_string(), 1, 4, 6),
+                ("new".to_string(), 1, 4, 6),
+                ("york".to_string(), 2, 4, 6),
+                ("city".to_string(), 2, 7, 11),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_synonym_filter_phrase_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field_indexing = TextFieldIndexing::default()
+            .set_tokenizer("synonyms")
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions);
+        let text_options = TextOptions::default().set_indexing_options(text_field_indexing);
+        let text_field = schema_builder.add_text_field("text", text_options);
+        let index = Index::create_in_ram(schema_builder.build());
+        index.tokenizers().register(
+            "synonyms",
+            SimpleTokenizer
+                .filter(LowerCaser)
+                .filter(synonym_filter()),
+        );
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "new ny city york new"));
+            // the positions of `city` are emitted out of order.
+            index_writer.add_document(doc!(text_field => "nyc city"));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let count_phrase = |words: &[&str]| {
+            let terms: Vec<Term> = words
+                .iter()
+                .map(|word| Term::from_field_text(text_field, word))
+                .collect();
+            let mut count_collector = CountCollector::default();
+            searcher
+                .search(&PhraseQuery::from(terms), &mut count_collector)
+                .unwrap();
+            count_collector.count()
+        };
+        assert_eq!(count_phrase(&["new", "york"]), 2);
+        assert_eq!(count_phrase(&["nyc", "city"]), 2);
+        assert_eq!(count_phrase(&["york", "city"]), 1);
+        assert_eq!(count_phrase(&["city", "new"]), 0);
+    }
+}
@@ -9,6 +9,7 @@ use tokenizer::JapaneseTokenizer;
 use tokenizer::RemoveLongFilter;
 use tokenizer::LowerCaser;
 use tokenizer::Stemmer;
+use tokenizer::StopWordFilter;
 
 /// The tokenizer manager serves as a store for
 /// all of the pre-configured tokenizer pipelines.
@@ -22,6 +23,8 @@ use tokenizer::Stemmer;
 ///  * `en_stem` : Like `default`, but also applies stemming on the
 ///  resulting tokens. Stemming can improve the recall of your
 ///  search engine.
+///  * `en_stem_with_stopwords` : Like `en_stem`, but also removes
+///  common English words before stemming.
 #[derive(Clone)]
 pub struct TokenizerManager {
     tokenizers: Arc<RwLock<HashMap<String, Box<BoxedTokenizer>>>>,
@@ -55,6 +58,7 @@ impl Default for TokenizerManager {
     /// the default pre-configured tokenizers of `tantivy`.
     /// - simple
     /// - en_stem
+    /// - en_stem_with_stopwords
     /// - ja
     fn default() -> TokenizerManager {
         let manager = TokenizerManager {
@@ -74,6 +78,14 @@ impl Default for TokenizerManager {
                 .filter(LowerCaser)
                 .filter(Stemmer::new()),
         );
+        manager.register(
+            "en_stem_with_stopwords",
+            SimpleTokenizer
+                .filter(RemoveLongFilter::limit(40))
+                .filter(LowerCaser)
+                .filter(StopWordFilter::english())
+                .filter(Stemmer::new()),
+        );
         manager.register("ja", JapaneseTokenizer.filter(RemoveLongFilter::limit(40)));
         manager
     }